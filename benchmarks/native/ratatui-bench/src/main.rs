@@ -394,6 +394,62 @@ fn memory_soak_lines(rows: u64, cols: u64, tick: u64) -> Vec<String> {
     out
 }
 
+/// Content rotated up by `step` rows per tick: row `r` shows source line
+/// `(r + tick * step) % rows`, so every frame is the previous one scrolled and
+/// only the `step` rows entering at the bottom are new.
+fn scroll_lines(rows: u64, cols: u64, step: u64, tick: u64) -> Vec<String> {
+    let cols = cols as usize;
+    let mut out = Vec::with_capacity(rows as usize);
+    for r in 0..rows {
+        let src = (r + tick.wrapping_mul(step)) % rows;
+        let v = (src.wrapping_mul(2_654_435_761) >> 7) as u32;
+        out.push(clip_pad(
+            format!(
+                "{src:>5} | scroll line {src} v={v:08x} {}",
+                "~".repeat((src % 13) as usize)
+            ),
+            cols,
+        ));
+    }
+    out
+}
+
+/// Mixed-width text: ASCII words, CJK and emoji (two columns each) whose
+/// positions shift per tick, so wide cells and their continuation columns
+/// move through the diff every frame. Lines are filled to exactly `cols`
+/// columns; `clip_pad` cannot be used since it counts bytes.
+fn wide_glyph_lines(rows: u64, cols: u64, tick: u64) -> Vec<String> {
+    const ASCII: [&str; 4] = ["tick", "diff", "cell", "row"];
+    const CJK: [char; 8] = ['漢', '字', '表', '示', '幅', '広', '文', '章'];
+    const EMOJI: [char; 6] = ['😀', '🚀', '🌍', '🎉', '🔥', '✅'];
+    let cols = cols as usize;
+    let mut out = Vec::with_capacity(rows as usize);
+    for r in 0..rows {
+        let mut line = String::new();
+        let mut width = 0usize;
+        let mut k = tick.wrapping_add(r.wrapping_mul(7));
+        while width < cols {
+            let (piece, w) = match k % 3 {
+                0 => {
+                    let word = ASCII[(k / 3 % 4) as usize];
+                    (format!("{word}{} ", k % 10), word.len() + 2)
+                }
+                1 => (CJK[(k % 8) as usize].to_string(), 2),
+                _ => (EMOJI[(k % 6) as usize].to_string(), 2),
+            };
+            if width + w > cols {
+                break;
+            }
+            line.push_str(&piece);
+            width += w;
+            k = k.wrapping_add(1);
+        }
+        line.push_str(&" ".repeat(cols - width));
+        out.push(line);
+    }
+    out
+}
+
 fn full_ui_pane_widths(cols: usize) -> (usize, usize, usize) {
     let left = 22usize.max(((cols as f64) * 0.24).floor() as usize);
     let right = 24usize.max(((cols as f64) * 0.28).floor() as usize);
@@ -1262,6 +1318,15 @@ enum ScenarioSpec {
         rows: u64,
         cols: u64,
     },
+    TerminalScroll {
+        rows: u64,
+        cols: u64,
+        step: u64,
+    },
+    TerminalWideGlyph {
+        rows: u64,
+        cols: u64,
+    },
     TerminalFullUi {
         rows: u64,
         cols: u64,
@@ -1322,6 +1387,15 @@ fn scenario_spec(
             rows: get_u64(params, "rows")?,
             cols: get_u64(params, "cols")?,
         }),
+        "terminal-scroll" => Ok(ScenarioSpec::TerminalScroll {
+            rows: get_u64(params, "rows")?,
+            cols: get_u64(params, "cols")?,
+            step: get_u64(params, "step")?,
+        }),
+        "terminal-wide-glyph" => Ok(ScenarioSpec::TerminalWideGlyph {
+            rows: get_u64(params, "rows")?,
+            cols: get_u64(params, "cols")?,
+        }),
         "terminal-full-ui" => Ok(ScenarioSpec::TerminalFullUi {
             rows: get_u64(params, "rows")?,
             cols: get_u64(params, "cols")?,
@@ -1348,6 +1422,56 @@ fn scenario_spec(
     }
 }
 
+/// Text a line-based scenario renders at `tick`; `None` for the strict
+/// layouts, which draw bordered sections instead.
+fn spec_lines(spec: &ScenarioSpec, tick: u64) -> Option<Vec<String>> {
+    let lines = match spec {
+        ScenarioSpec::TerminalRerender => {
+            vec!["terminal-rerender".to_string(), format!("tick={tick}")]
+        }
+        ScenarioSpec::TerminalFrameFill {
+            rows,
+            cols,
+            dirty_lines,
+        } => frame_fill_lines(*rows, *cols, *dirty_lines, tick),
+        ScenarioSpec::TerminalVirtualList { items, viewport } => {
+            virtual_list_lines(*items, *viewport, tick)
+        }
+        ScenarioSpec::TerminalTable { rows, cols } => table_lines(*rows, *cols, tick),
+        ScenarioSpec::TerminalScreenTransition { rows, cols } => {
+            screen_transition_lines(*rows, *cols, tick)
+        }
+        ScenarioSpec::TerminalFpsStream {
+            rows,
+            cols,
+            channels,
+        } => fps_stream_lines(*rows, *cols, *channels, tick),
+        ScenarioSpec::TerminalInputLatency { rows, cols } => {
+            input_latency_lines(*rows, *cols, tick)
+        }
+        ScenarioSpec::TerminalMemorySoak { rows, cols } => memory_soak_lines(*rows, *cols, tick),
+        ScenarioSpec::TerminalScroll { rows, cols, step } => {
+            scroll_lines(*rows, *cols, *step, tick)
+        }
+        ScenarioSpec::TerminalWideGlyph { rows, cols } => wide_glyph_lines(*rows, *cols, tick),
+        ScenarioSpec::TerminalFullUi {
+            rows,
+            cols,
+            services,
+        } => full_ui_lines(*rows, *cols, *services, tick),
+        ScenarioSpec::TerminalFullUiNavigation {
+            rows,
+            cols,
+            services,
+            dwell,
+        } => full_ui_navigation_lines(*rows, *cols, *services, *dwell, tick),
+        ScenarioSpec::TerminalStrictUi { .. } | ScenarioSpec::TerminalStrictUiNavigation { .. } => {
+            return None
+        }
+    };
+    Some(lines)
+}
+
 fn draw_spec_frame(f: &mut ratatui::Frame<'_>, spec: &ScenarioSpec, tick: u64) {
     let area = f.size();
     match spec {
@@ -1369,55 +1493,59 @@ fn draw_spec_frame(f: &mut ratatui::Frame<'_>, spec: &ScenarioSpec, tick: u64) {
             draw_strict_sections(f, &sections);
         }
         _ => {
-            let text = match spec {
-                ScenarioSpec::TerminalRerender => to_text(vec![
-                    "terminal-rerender".to_string(),
-                    format!("tick={tick}"),
-                ]),
-                ScenarioSpec::TerminalFrameFill {
-                    rows,
-                    cols,
-                    dirty_lines,
-                } => to_text(frame_fill_lines(*rows, *cols, *dirty_lines, tick)),
-                ScenarioSpec::TerminalVirtualList { items, viewport } => {
-                    to_text(virtual_list_lines(*items, *viewport, tick))
-                }
-                ScenarioSpec::TerminalTable { rows, cols } => {
-                    to_text(table_lines(*rows, *cols, tick))
-                }
-                ScenarioSpec::TerminalScreenTransition { rows, cols } => {
-                    to_text(screen_transition_lines(*rows, *cols, tick))
-                }
-                ScenarioSpec::TerminalFpsStream {
-                    rows,
-                    cols,
-                    channels,
-                } => to_text(fps_stream_lines(*rows, *cols, *channels, tick)),
-                ScenarioSpec::TerminalInputLatency { rows, cols } => {
-                    to_text(input_latency_lines(*rows, *cols, tick))
-                }
-                ScenarioSpec::TerminalMemorySoak { rows, cols } => {
-                    to_text(memory_soak_lines(*rows, *cols, tick))
-                }
-                ScenarioSpec::TerminalFullUi {
-                    rows,
-                    cols,
-                    services,
-                } => to_text(full_ui_lines(*rows, *cols, *services, tick)),
-                ScenarioSpec::TerminalFullUiNavigation {
-                    rows,
-                    cols,
-                    services,
-                    dwell,
-                } => to_text(full_ui_navigation_lines(
-                    *rows, *cols, *services, *dwell, tick,
-                )),
-                ScenarioSpec::TerminalStrictUi { .. }
-                | ScenarioSpec::TerminalStrictUiNavigation { .. } => unreachable!(),
-            };
-            f.render_widget(Paragraph::new(text), area);
+            let lines = spec_lines(spec, tick).unwrap_or_default();
+            f.render_widget(Paragraph::new(to_text(lines)), area);
+        }
+    }
+}
+
+/// `--frame <n>`: print the content of a single frame at tick `n` instead of
+/// benchmarking. Generators are pure functions of their parameters and the
+/// tick, so the output matches what that tick renders inside a run. The strict
+/// layouts are rendered once into an offscreen buffer (`--cols`x`--rows`) and
+/// its rows are printed.
+fn dump_frame(
+    scenario: &str,
+    tick: u64,
+    params: &std::collections::HashMap<String, String>,
+) -> Result<(), String> {
+    let spec = scenario_spec(scenario, params)?;
+    let lines = match spec_lines(&spec, tick) {
+        Some(lines) => lines,
+        None => {
+            let cols = get_u64(params, "cols")?.min(u64::from(u16::MAX)) as u16;
+            let rows = get_u64(params, "rows")?.min(u64::from(u16::MAX)) as u16;
+            let mut terminal =
+                Terminal::new(TestBackend::new(cols, rows)).map_err(|e| e.to_string())?;
+            let frame = terminal
+                .draw(|f| draw_spec_frame(f, &spec, tick))
+                .map_err(|e| e.to_string())?;
+            let buf = frame.buffer;
+            (0..buf.area.height)
+                .map(|y| {
+                    (0..buf.area.width)
+                        .map(|x| buf.get(x, y).symbol())
+                        .collect::<String>()
+                })
+                .collect()
         }
+    };
+    let mut out = io::stdout().lock();
+    for line in lines {
+        writeln!(out, "{line}").map_err(|e| e.to_string())?;
     }
+    out.flush().map_err(|e| e.to_string())
+}
+
+/// Offscreen terminal size for `--io stub`: `--cols` x `--rows` when given,
+/// otherwise 120x40.
+fn stub_size(params: &std::collections::HashMap<String, String>) -> (u16, u16) {
+    let dim = |key: &str, default: u16| {
+        get_u64(params, key)
+            .map(|v| v.clamp(1, u64::from(u16::MAX)) as u16)
+            .unwrap_or(default)
+    };
+    (dim("cols", 120), dim("rows", 40))
 }
 
 fn run_stub(
@@ -1427,7 +1555,8 @@ fn run_stub(
     params: &std::collections::HashMap<String, String>,
 ) -> Result<(Vec<f64>, f64), String> {
     let spec = scenario_spec(scenario, params)?;
-    let backend = TestBackend::new(120, 40);
+    let (cols, rows) = stub_size(params);
+    let backend = TestBackend::new(cols, rows);
     let mut terminal = Terminal::new(backend).map_err(|e| e.to_string())?;
 
     let mut render = |tick: u64| -> Result<(), String> {
@@ -1503,6 +1632,15 @@ fn main() {
             std::process::exit(2);
         }
     };
+    if args.contains_key("frame") {
+        let dumped = get_u64(&args, "frame").and_then(|tick| dump_frame(&scenario, tick, &args));
+        if let Err(e) = dumped {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+        return;
+    }
+
     let warmup = get_u64(&args, "warmup").unwrap_or(0);
     let iterations = get_u64(&args, "iterations").unwrap_or(0);
     let io_mode = get_str(&args, "io").unwrap_or("stub");
@@ -5,20 +5,78 @@
 //!
 //! Usage:
 //!   ratatui-bench --scenario <name> --iterations <n> --warmup <n> [--items <n>]
+//!                 [--format json|csv] [--no-header] [--cols <n>] [--rows <n>]
+//!                 [--io stub|pty] [--result-path <file>]
+//!
+//! Output is `{ "ok": true, "data": {...} }`, or `{ "ok": false, "error": "..." }`
+//! when the scenario cannot run (the process still exits 0). It goes to
+//! `--result-path` when given, otherwise to stdout. With `--format csv` a
+//! failure is printed to stderr and the exit code is 1.
+//!
+//! `--cols` / `--rows` set the terminal size every scenario renders into;
+//! each scenario keeps its own default size for whichever is not given.
+//!
+//! `--io stub` (default) renders into an offscreen `TestBackend`. `--io pty`
+//! writes the real ANSI output of every frame to stdout, which should be the
+//! terminal under test, and reports the bytes written as `bytes_written`.
+//!
+//! `--format csv` prints a header and one row with the timing stats flattened;
+//! `--no-header` drops the header so rows from many runs can be appended to
+//! one file.
 //!
 //! Scenarios: construction, rerender, content-update, memory-profile, startup
 
 use ratatui::{
-    backend::TestBackend,
-    layout::{Constraint, Direction, Layout},
+    backend::{Backend, CrosstermBackend, TestBackend},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
     widgets::{List, ListItem, Paragraph},
-    Terminal,
+    Terminal, TerminalOptions, Viewport,
 };
 use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
+#[derive(Clone)]
+struct ByteCounter(Arc<AtomicU64>);
+
+impl ByteCounter {
+    fn new() -> Self {
+        Self(Arc::new(AtomicU64::new(0)))
+    }
+    fn add(&self, n: usize) {
+        self.0.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+struct CountingWriter<W: Write> {
+    inner: W,
+    counter: ByteCounter,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W, counter: ByteCounter) -> Self {
+        Self { inner, counter }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.counter.add(n);
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 fn xorshift32_next(x: &mut u32) -> u32 {
     // xorshift32
     *x ^= *x << 13;
@@ -81,6 +139,13 @@ struct TimingStats {
     mean_ci95_high: f64,
 }
 
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ResultFile {
+    Ok { ok: bool, data: BenchOutput },
+    Err { ok: bool, error: String },
+}
+
 #[derive(Serialize)]
 struct BenchOutput {
     timing: TimingStats,
@@ -88,6 +153,30 @@ struct BenchOutput {
     total_wall_ms: f64,
     ops_per_sec: f64,
     peak_rss_kb: u64,
+    bytes_written: u64,
+}
+
+const CSV_HEADER: &str =
+    "scenario,mean,median,p95,p99,min,max,stddev,cv,iterations,ops_per_sec,peak_rss_kb,bytes_written";
+
+fn csv_row(scenario: &str, out: &BenchOutput) -> String {
+    let t = &out.timing;
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        scenario,
+        t.mean,
+        t.median,
+        t.p95,
+        t.p99,
+        t.min,
+        t.max,
+        t.stddev,
+        t.cv,
+        out.iterations,
+        out.ops_per_sec,
+        out.peak_rss_kb,
+        out.bytes_written,
+    )
 }
 
 fn compute_stats(samples: &mut Vec<f64>) -> TimingStats {
@@ -149,9 +238,68 @@ fn get_peak_rss_kb() -> u64 {
     0
 }
 
+/// Terminal size overrides from `--cols` / `--rows`.
+#[derive(Clone, Copy, Default)]
+struct TermSize {
+    cols: Option<u16>,
+    rows: Option<u16>,
+}
+
+impl TermSize {
+    /// The overridden size, falling back to the scenario's own `cols` x `rows`.
+    fn dims(self, cols: u16, rows: u16) -> (u16, u16) {
+        (self.cols.unwrap_or(cols), self.rows.unwrap_or(rows))
+    }
+}
+
+/// Where scenarios render, chosen by `--io`.
+trait BenchIo {
+    type Backend: Backend;
+    fn terminal(&self, cols: u16, rows: u16) -> Terminal<Self::Backend>;
+    /// Terminal output so far (0 when nothing is written out).
+    fn bytes_written(&self) -> u64;
+}
+
+/// `--io stub`: an offscreen buffer, no output.
+struct StubIo(TermSize);
+
+impl BenchIo for StubIo {
+    type Backend = TestBackend;
+    fn terminal(&self, cols: u16, rows: u16) -> Terminal<TestBackend> {
+        let (cols, rows) = self.0.dims(cols, rows);
+        Terminal::new(TestBackend::new(cols, rows)).unwrap()
+    }
+    fn bytes_written(&self) -> u64 {
+        0
+    }
+}
+
+/// `--io pty`: crossterm output to stdout, counted. The viewport is fixed to
+/// the scenario size so the output matches the stub geometry whatever the
+/// real terminal size is.
+struct PtyIo {
+    size: TermSize,
+    counter: ByteCounter,
+}
+
+impl BenchIo for PtyIo {
+    type Backend = CrosstermBackend<CountingWriter<io::Stdout>>;
+    fn terminal(&self, cols: u16, rows: u16) -> Terminal<Self::Backend> {
+        let (cols, rows) = self.size.dims(cols, rows);
+        let writer = CountingWriter::new(io::stdout(), self.counter.clone());
+        let options = TerminalOptions {
+            viewport: Viewport::Fixed(Rect::new(0, 0, cols, rows)),
+        };
+        Terminal::with_options(CrosstermBackend::new(writer), options).unwrap()
+    }
+    fn bytes_written(&self) -> u64 {
+        self.counter.get()
+    }
+}
+
 /// Build the same tree structure as the Node.js benchmarks:
 /// Title (bold), summary row, then N item rows with dim index + text + italic details
-fn render_list(terminal: &mut Terminal<TestBackend>, n: usize, seed: usize) {
+fn render_list<B: Backend>(terminal: &mut Terminal<B>, n: usize, seed: usize) {
     terminal.draw(|frame| {
         let area = frame.area();
 
@@ -197,7 +345,7 @@ fn render_list(terminal: &mut Terminal<TestBackend>, n: usize, seed: usize) {
 }
 
 /// Counter app (same as rerender scenario)
-fn render_counter(terminal: &mut Terminal<TestBackend>, count: usize) {
+fn render_counter<B: Backend>(terminal: &mut Terminal<B>, count: usize) {
     terminal.draw(|frame| {
         let area = frame.area();
         let chunks = Layout::default()
@@ -232,7 +380,7 @@ fn render_counter(terminal: &mut Terminal<TestBackend>, count: usize) {
 }
 
 /// 500-row list with movable selection (content-update scenario)
-fn render_content_update(terminal: &mut Terminal<TestBackend>, selected: usize) {
+fn render_content_update<B: Backend>(terminal: &mut Terminal<B>, selected: usize) {
     let list_size = 500;
     terminal.draw(|frame| {
         let area = frame.area();
@@ -284,7 +432,7 @@ fn render_content_update(terminal: &mut Terminal<TestBackend>, selected: usize)
 }
 
 /// Memory-profile tree: progress bar + 20 lines
-fn render_memory_tree(terminal: &mut Terminal<TestBackend>, iter: usize) {
+fn render_memory_tree<B: Backend>(terminal: &mut Terminal<B>, iter: usize) {
     terminal.draw(|frame| {
         let area = frame.area();
         let chunks = Layout::default()
@@ -327,19 +475,25 @@ fn render_memory_tree(terminal: &mut Terminal<TestBackend>, iter: usize) {
     }).unwrap();
 }
 
-fn run_scenario(scenario: &str, warmup: usize, iterations: usize, items: usize) -> BenchOutput {
+fn run_scenario(
+    scenario: &str,
+    warmup: usize,
+    iterations: usize,
+    items: usize,
+    io: &impl BenchIo,
+) -> Result<BenchOutput, String> {
     match scenario {
-        "construction" => run_construction(warmup, iterations, items),
-        "rerender" => run_rerender(warmup, iterations),
-        "content-update" => run_content_update(warmup, iterations),
-        "memory-profile" => run_memory_profile(warmup, iterations),
-        "startup" => run_startup(warmup, iterations),
-        _ => panic!("Unknown scenario: {}", scenario),
+        "construction" => Ok(run_construction(warmup, iterations, items, io)),
+        "rerender" => Ok(run_rerender(warmup, iterations, io)),
+        "content-update" => Ok(run_content_update(warmup, iterations, io)),
+        "memory-profile" => Ok(run_memory_profile(warmup, iterations, io)),
+        "startup" => Ok(run_startup(warmup, iterations, io)),
+        _ => Err(format!("unknown scenario: {}", scenario)),
     }
 }
 
-fn run_construction(warmup: usize, iterations: usize, n: usize) -> BenchOutput {
-    let mut terminal = Terminal::new(TestBackend::new(120, 540.max(n as u16 + 5))).unwrap();
+fn run_construction(warmup: usize, iterations: usize, n: usize, io: &impl BenchIo) -> BenchOutput {
+    let mut terminal = io.terminal(120, 540.max(n as u16 + 5));
 
     for i in 0..warmup {
         render_list(&mut terminal, n, i);
@@ -363,11 +517,12 @@ fn run_construction(warmup: usize, iterations: usize, n: usize) -> BenchOutput {
         total_wall_ms,
         ops_per_sec,
         peak_rss_kb: get_peak_rss_kb(),
+        bytes_written: io.bytes_written(),
     }
 }
 
-fn run_rerender(warmup: usize, iterations: usize) -> BenchOutput {
-    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+fn run_rerender(warmup: usize, iterations: usize, io: &impl BenchIo) -> BenchOutput {
+    let mut terminal = io.terminal(120, 40);
 
     for i in 0..warmup {
         render_counter(&mut terminal, i);
@@ -390,11 +545,12 @@ fn run_rerender(warmup: usize, iterations: usize) -> BenchOutput {
         total_wall_ms,
         ops_per_sec: iterations as f64 / (total_wall_ms / 1000.0),
         peak_rss_kb: get_peak_rss_kb(),
+        bytes_written: io.bytes_written(),
     }
 }
 
-fn run_content_update(warmup: usize, iterations: usize) -> BenchOutput {
-    let mut terminal = Terminal::new(TestBackend::new(120, 540)).unwrap();
+fn run_content_update(warmup: usize, iterations: usize, io: &impl BenchIo) -> BenchOutput {
+    let mut terminal = io.terminal(120, 540);
     let list_size = 500;
     let mut selected: usize = 0;
 
@@ -421,11 +577,12 @@ fn run_content_update(warmup: usize, iterations: usize) -> BenchOutput {
         total_wall_ms,
         ops_per_sec: iterations as f64 / (total_wall_ms / 1000.0),
         peak_rss_kb: get_peak_rss_kb(),
+        bytes_written: io.bytes_written(),
     }
 }
 
-fn run_memory_profile(warmup: usize, iterations: usize) -> BenchOutput {
-    let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+fn run_memory_profile(warmup: usize, iterations: usize, io: &impl BenchIo) -> BenchOutput {
+    let mut terminal = io.terminal(120, 40);
 
     for i in 0..warmup {
         render_memory_tree(&mut terminal, i);
@@ -448,14 +605,15 @@ fn run_memory_profile(warmup: usize, iterations: usize) -> BenchOutput {
         total_wall_ms,
         ops_per_sec: iterations as f64 / (total_wall_ms / 1000.0),
         peak_rss_kb: get_peak_rss_kb(),
+        bytes_written: io.bytes_written(),
     }
 }
 
-fn run_startup(warmup: usize, iterations: usize) -> BenchOutput {
+fn run_startup(warmup: usize, iterations: usize, io: &impl BenchIo) -> BenchOutput {
     let n = 50;
 
     for i in 0..warmup {
-        let mut terminal = Terminal::new(TestBackend::new(120, 60)).unwrap();
+        let mut terminal = io.terminal(120, 60);
         render_list(&mut terminal, n, i);
         drop(terminal);
     }
@@ -465,7 +623,7 @@ fn run_startup(warmup: usize, iterations: usize) -> BenchOutput {
 
     for i in 0..iterations {
         let ts = Instant::now();
-        let mut terminal = Terminal::new(TestBackend::new(120, 60)).unwrap();
+        let mut terminal = io.terminal(120, 60);
         render_list(&mut terminal, n, warmup + i);
         samples.push(ts.elapsed().as_secs_f64() * 1000.0);
         drop(terminal);
@@ -479,6 +637,7 @@ fn run_startup(warmup: usize, iterations: usize) -> BenchOutput {
         total_wall_ms,
         ops_per_sec: iterations as f64 / (total_wall_ms / 1000.0),
         peak_rss_kb: get_peak_rss_kb(),
+        bytes_written: io.bytes_written(),
     }
 }
 
@@ -488,6 +647,11 @@ fn main() {
     let mut warmup = 50;
     let mut iterations = 500;
     let mut items = 1000;
+    let mut format = "json";
+    let mut header = true;
+    let mut size = TermSize::default();
+    let mut io_mode = "stub";
+    let mut result_path: Option<&str> = None;
 
     let mut i = 1;
     while i < args.len() {
@@ -496,11 +660,73 @@ fn main() {
             "--warmup" => { i += 1; warmup = args.get(i).and_then(|s| s.parse().ok()).unwrap_or(warmup); }
             "--iterations" => { i += 1; iterations = args.get(i).and_then(|s| s.parse().ok()).unwrap_or(iterations); }
             "--items" => { i += 1; items = args.get(i).and_then(|s| s.parse().ok()).unwrap_or(items); }
+            "--format" => { i += 1; format = args.get(i).map(|s| s.as_str()).unwrap_or(format); }
+            "--no-header" => { header = false; }
+            "--cols" => { i += 1; size.cols = args.get(i).and_then(|s| s.parse().ok()).or(size.cols); }
+            "--rows" => { i += 1; size.rows = args.get(i).and_then(|s| s.parse().ok()).or(size.rows); }
+            "--io" => { i += 1; io_mode = args.get(i).map(|s| s.as_str()).unwrap_or(io_mode); }
+            "--result-path" => { i += 1; result_path = args.get(i).map(|s| s.as_str()).or(result_path); }
             _ => {}
         }
         i += 1;
     }
 
-    let output = run_scenario(scenario, warmup, iterations, items);
-    println!("{}", serde_json::to_string(&output).unwrap());
+    if format != "json" && format != "csv" {
+        eprintln!("Unknown format: {} (expected json or csv)", format);
+        std::process::exit(2);
+    }
+
+    let run = match io_mode {
+        "stub" => run_scenario(scenario, warmup, iterations, items, &StubIo(size)),
+        "pty" => {
+            let io = PtyIo {
+                size,
+                counter: ByteCounter::new(),
+            };
+            let run = run_scenario(scenario, warmup, iterations, items, &io);
+            if result_path.is_none() {
+                println!();
+            }
+            run
+        }
+        _ => {
+            eprintln!("Unknown io mode: {} (expected stub or pty)", io_mode);
+            std::process::exit(2);
+        }
+    };
+
+    let text = if format == "csv" {
+        match run {
+            Ok(output) => {
+                let row = csv_row(scenario, &output);
+                if header {
+                    format!("{}\n{}\n", CSV_HEADER, row)
+                } else {
+                    format!("{}\n", row)
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let payload = match run {
+            Ok(data) => ResultFile::Ok { ok: true, data },
+            Err(error) => ResultFile::Err { ok: false, error },
+        };
+        let json = serde_json::to_string(&payload)
+            .unwrap_or_else(|e| format!(r#"{{"ok":false,"error":"serde_json: {e}"}}"#));
+        format!("{}\n", json)
+    };
+
+    match result_path {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("write result failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", text),
+    }
 }
@@ -7,7 +7,8 @@
  */
 
 import { execFileSync, execSync } from "node:child_process";
-import { existsSync } from "node:fs";
+import { existsSync, readFileSync, unlinkSync } from "node:fs";
+import { tmpdir } from "node:os";
 import { dirname, resolve } from "node:path";
 import { fileURLToPath } from "node:url";
 import { computeStats, takeMemory } from "./measure.js";
@@ -84,6 +85,32 @@ interface RatatuiOutput {
   total_wall_ms: number;
   ops_per_sec: number;
   peak_rss_kb: number;
+  // Optional for backwards compatibility with previously built binaries.
+  bytes_written?: number;
+}
+
+type RatatuiResult =
+  | Readonly<{ ok: true; data: RatatuiOutput }>
+  | Readonly<{ ok: false; error: string }>;
+
+/** Read and remove the result file the binary wrote to `--result-path`. */
+function readResult(path: string): RatatuiOutput {
+  if (!existsSync(path)) {
+    throw new Error(
+      `ratatui bench wrote no result to ${path}; rebuild it with cargo build --release`,
+    );
+  }
+  try {
+    const result = JSON.parse(readFileSync(path, "utf-8")) as RatatuiResult;
+    if (!result.ok) throw new Error(`ratatui bench failed: ${result.error}`);
+    return result.data;
+  } finally {
+    try {
+      unlinkSync(path);
+    } catch {
+      // ignore
+    }
+  }
 }
 
 /**
@@ -109,13 +136,18 @@ export function runRatatui(
     args.push("--items", String(params.items));
   }
 
-  const stdout = execFileSync(BINARY, args, {
+  const resultPath = resolve(
+    tmpdir(),
+    `rezi-ratatui-${process.pid}-${Date.now()}-${Math.random().toString(16).slice(2)}.json`,
+  );
+  args.push("--result-path", resultPath);
+
+  execFileSync(BINARY, args, {
     timeout: 300_000,
-    encoding: "utf-8",
-    stdio: ["ignore", "pipe", "inherit"],
+    stdio: ["ignore", "ignore", "inherit"],
   });
 
-  const output: RatatuiOutput = JSON.parse(stdout.trim());
+  const output = readResult(resultPath);
   const timing = {
     n: output.timing.n ?? output.iterations,
     mean: output.timing.mean,
@@ -154,7 +186,7 @@ export function runRatatui(
     totalWallMs: output.total_wall_ms,
     opsPerSec: output.ops_per_sec,
     framesProduced: output.iterations,
-    bytesProduced: 0,
+    bytesProduced: output.bytes_written ?? 0,
     ptyBytesObserved: null,
   };
 }
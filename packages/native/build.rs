@@ -1,17 +1,338 @@
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 fn main() {
     napi_build::setup();
 
+    // `prebuilt-engine` links a `libzireael_core` built earlier (for example
+    // from this build's OUT_DIR) instead of compiling the vendored C sources,
+    // for CI caches and cross builds without a target C toolchain.
+    if env::var_os("CARGO_FEATURE_PREBUILT_ENGINE").is_some() {
+        link_prebuilt_engine();
+    } else {
+        compile_engine();
+    }
+
+    // Cross builds can point at a target sysroot's libraries.
+    for dir in target_paths("ZIREAEL_EXTRA_LINK_SEARCH") {
+        println!("cargo:rustc-link-search=native={}", dir.display());
+    }
+
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
+        println!("cargo:rustc-link-lib=dylib=advapi32");
+        println!("cargo:rustc-link-lib=dylib=kernel32");
+        println!("cargo:rustc-link-lib=dylib=user32");
+    }
+
+    // Surface the linked engine revision through `ziEngineVersion`.
+    let commit = fs::read_to_string("vendor/VENDOR_COMMIT.txt").unwrap_or_default();
+    let commit = match commit.trim() {
+        "" => "unknown",
+        commit => commit,
+    };
+    println!("cargo:rustc-env=ZIREAEL_VENDOR_COMMIT={commit}");
+    println!(
+        "cargo:rustc-env=ZIREAEL_VENDOR_PATCHES={}",
+        patch_series().join(",")
+    );
+
+    // Keep rebuilds deterministic when vendored sources change.
+    println!("cargo:rerun-if-changed=vendor/VENDOR_COMMIT.txt");
+    println!("cargo:rerun-if-changed=vendor/patches");
+    println!("cargo:rerun-if-changed=vendor/zireael/include");
+    println!("cargo:rerun-if-changed=vendor/zireael/src");
+}
+
+/// Patch files listed in `vendor/patches/series`, in the order they apply.
+fn patch_series() -> Vec<String> {
+    let series = fs::read_to_string("vendor/patches/series").unwrap_or_default();
+    series
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Copy the vendored snapshot into `OUT_DIR` and apply the patch series to
+/// the copy; `vendor/zireael` itself stays an unmodified mirror of
+/// `VENDOR_COMMIT.txt`.
+fn patched_engine_tree(vendor: &Path, patches: &Path) -> PathBuf {
+    let out_dir = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR"));
+    let tree = out_dir.join("zireael");
+    if tree.exists() {
+        fs::remove_dir_all(&tree).expect("remove stale patched engine tree");
+    }
+    copy_dir(&vendor.join("include"), &tree.join("include"));
+    copy_dir(&vendor.join("src"), &tree.join("src"));
+
+    for name in patch_series() {
+        let patch = fs::read_to_string(patches.join(&name))
+            .unwrap_or_else(|err| panic!("failed to read vendor patch {name}: {err}"));
+        if let Err(reason) = apply_patch(&tree, &patch) {
+            panic!(
+                "vendor patch {name} does not apply to vendor/zireael ({reason}); refresh vendor/patches for VENDOR_COMMIT.txt"
+            );
+        }
+    }
+    tree
+}
+
+/// One `@@` hunk. Lines keep their `\n`, except where the patch marks
+/// "\\ No newline at end of file".
+struct Hunk {
+    old_start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+/// The part of `git diff` output touching one file.
+struct FilePatch {
+    path: String,
+    created: bool,
+    deleted: bool,
+    hunks: Vec<Hunk>,
+}
+
+/// Apply a `git diff` patch to the files under `root`, stricter than
+/// `git apply`: context must match exactly and each hunk must sit where its
+/// header says once earlier hunks are accounted for. A patch that would
+/// need fuzz or an offset fails, so a stale series is refreshed instead of
+/// landing somewhere nobody reviewed. Done in Rust so the build does not
+/// need `git` or `patch` on `PATH`.
+fn apply_patch(root: &Path, patch: &str) -> Result<(), String> {
+    for file in parse_patch(patch)? {
+        let path = root.join(&file.path);
+        let text = if file.created {
+            String::new()
+        } else {
+            fs::read_to_string(&path).map_err(|err| format!("{}: {err}", file.path))?
+        };
+        let mut lines: Vec<String> = text.split_inclusive('\n').map(str::to_owned).collect();
+        // Offset of the lines so far against the hunk headers' numbering.
+        let mut shift = 0isize;
+        for hunk in &file.hunks {
+            let at = hunk
+                .old_start
+                .saturating_sub(usize::from(!hunk.old.is_empty()))
+                .saturating_add_signed(shift);
+            if lines.get(at..at + hunk.old.len()) != Some(&hunk.old[..]) {
+                let moved = (0..=lines.len().saturating_sub(hunk.old.len()))
+                    .find(|&i| lines[i..].starts_with(&hunk.old));
+                return Err(match moved {
+                    Some(i) => format!(
+                        "{}: hunk at line {} matches only at line {}",
+                        file.path,
+                        hunk.old_start,
+                        i + 1
+                    ),
+                    None => format!(
+                        "{}: hunk at line {} does not match",
+                        file.path, hunk.old_start
+                    ),
+                });
+            }
+            lines.splice(at..at + hunk.old.len(), hunk.new.iter().cloned());
+            shift += hunk.new.len() as isize - hunk.old.len() as isize;
+        }
+        if file.deleted {
+            fs::remove_file(&path).map_err(|err| format!("{}: {err}", file.path))?;
+        } else {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", file.path))?;
+            }
+            fs::write(&path, lines.concat()).map_err(|err| format!("{}: {err}", file.path))?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut lines = patch.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            // `a/<path> b/<path>`; the `+++`/`---` lines refine it below.
+            let path = paths
+                .trim_end()
+                .rsplit_once(" b/")
+                .map(|(_, path)| path.to_owned())
+                .ok_or_else(|| format!("malformed diff header: {}", line.trim_end()))?;
+            files.push(FilePatch {
+                path,
+                created: false,
+                deleted: false,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            // The description above the first diff.
+            continue;
+        };
+        if line.starts_with("new file mode") || line.starts_with("--- /dev/null") {
+            file.created = true;
+        } else if line.starts_with("deleted file mode") || line.starts_with("+++ /dev/null") {
+            file.deleted = true;
+        } else if let Some(path) = line.strip_prefix("+++ b/") {
+            file.path = path.trim_end().to_owned();
+        } else if line.starts_with("rename ") || line.starts_with("Binary files") {
+            return Err(format!("{}: only text edits are supported", file.path));
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            let (mut hunk, mut old_left, mut new_left) = parse_hunk_header(header)
+                .ok_or_else(|| format!("malformed hunk header: {}", line.trim_end()))?;
+            // Side of the last body line, for "\\ No newline at end of file".
+            let mut last = ' ';
+            while old_left > 0 || new_left > 0 {
+                let body = lines
+                    .next()
+                    .ok_or_else(|| format!("{}: hunk is cut short", file.path))?;
+                // Editors may strip the lone space of an empty context line.
+                let (kind, text) = if body == "\n" {
+                    (' ', "\n")
+                } else {
+                    let mut chars = body.chars();
+                    (chars.next().unwrap_or(' '), chars.as_str())
+                };
+                match kind {
+                    '\\' => {
+                        strip_last_newline(&mut hunk, last);
+                        continue;
+                    }
+                    ' ' if old_left > 0 && new_left > 0 => {
+                        hunk.old.push(text.to_owned());
+                        hunk.new.push(text.to_owned());
+                        old_left -= 1;
+                        new_left -= 1;
+                    }
+                    '-' if old_left > 0 => {
+                        hunk.old.push(text.to_owned());
+                        old_left -= 1;
+                    }
+                    '+' if new_left > 0 => {
+                        hunk.new.push(text.to_owned());
+                        new_left -= 1;
+                    }
+                    _ => return Err(format!("{}: unexpected hunk line", file.path)),
+                }
+                last = kind;
+            }
+            if lines.next_if(|next| next.starts_with('\\')).is_some() {
+                strip_last_newline(&mut hunk, last);
+            }
+            file.hunks.push(hunk);
+        }
+    }
+    if files.is_empty() {
+        return Err("no file changes found".to_owned());
+    }
+    Ok(files)
+}
+
+/// `a[,b] +c[,d] @@`: an empty hunk starting at old line `a`, and its
+/// old and new line counts `b` and `d`.
+fn parse_hunk_header(header: &str) -> Option<(Hunk, usize, usize)> {
+    let (ranges, _) = header.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = range(old)?;
+    let (_, new_len) = range(new)?;
+    let hunk = Hunk {
+        old_start,
+        old: Vec::with_capacity(old_len),
+        new: Vec::with_capacity(new_len),
+    };
+    Some((hunk, old_len, new_len))
+}
+
+/// Drop the `\n` of the last line on `side` of the hunk (both for context).
+fn strip_last_newline(hunk: &mut Hunk, side: char) {
+    let strip = |lines: &mut Vec<String>| {
+        if let Some(line) = lines.last_mut() {
+            if line.ends_with('\n') {
+                line.pop();
+            }
+        }
+    };
+    if side != '+' {
+        strip(&mut hunk.old);
+    }
+    if side != '-' {
+        strip(&mut hunk.new);
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).expect("create patched engine directory");
+    for entry in fs::read_dir(from).expect("read vendored engine directory") {
+        let entry = entry.expect("read vendored engine entry");
+        let dest = to.join(entry.file_name());
+        if entry.file_type().expect("vendored entry type").is_dir() {
+            copy_dir(&entry.path(), &dest);
+        } else {
+            fs::copy(entry.path(), &dest).expect("copy vendored engine file");
+        }
+    }
+}
+
+/// Paths listed (in the platform's `PATH` syntax) by `{name}_{TARGET}`, with
+/// the target's `-` also tried as `_`, falling back to plain `{name}` — the
+/// same lookup `cc` uses for `CC`/`CFLAGS`.
+fn target_paths(name: &str) -> Vec<PathBuf> {
+    let target = env::var("TARGET").expect("TARGET");
+    let keys = [
+        format!("{name}_{target}"),
+        format!("{name}_{}", target.replace('-', "_")),
+        name.to_owned(),
+    ];
+    for key in &keys {
+        println!("cargo:rerun-if-env-changed={key}");
+    }
+    keys.iter()
+        .find_map(env::var_os)
+        .map(|value| env::split_paths(&value).collect())
+        .unwrap_or_default()
+}
+
+/// Link `zireael_core` from `ZIREAEL_LIB_DIR`. The library must come from the
+/// same `VENDOR_COMMIT.txt` with `vendor/patches` applied and, with
+/// `assert-log`, be built without `zr_assert.c`, exactly as `compile_engine`
+/// would build it.
+fn link_prebuilt_engine() {
+    println!("cargo:rerun-if-env-changed=ZIREAEL_LIB_DIR");
+    let lib_dir = match env::var_os("ZIREAEL_LIB_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => panic!(
+            "the prebuilt-engine feature needs ZIREAEL_LIB_DIR set to the directory holding libzireael_core"
+        ),
+    };
+    if !lib_dir.is_dir() {
+        panic!("ZIREAEL_LIB_DIR is not a directory: {}", lib_dir.display());
+    }
+    println!("cargo:rustc-link-search=native={}", lib_dir.display());
+    println!("cargo:rustc-link-lib=static=zireael_core");
+}
+
+fn compile_engine() {
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR"));
     let vendor = manifest_dir.join("vendor").join("zireael");
-    let include_dir = vendor.join("include");
-    let src_dir = vendor.join("src");
+    let patches = manifest_dir.join("vendor").join("patches");
+    let tree = patched_engine_tree(&vendor, &patches);
+    let include_dir = tree.join("include");
+    let src_dir = tree.join("src");
 
     let mut build = cc::Build::new();
     build.include(&include_dir);
     build.include(&src_dir);
+    for dir in target_paths("ZIREAEL_EXTRA_INCLUDE") {
+        build.include(dir);
+    }
     build.warnings(false);
 
     // The engine assumes a C99-or-newer compiler; C11 is required for atomics on MSVC.
@@ -22,8 +343,30 @@ fn main() {
         build.flag_if_supported("-std=c11");
     }
 
+    // `sanitize` instruments the engine with ASan/UBSan for `cargo test`
+    // runs over the diff and framebuffer code, linking GCC's libasan and
+    // libubsan into the test binary. Clang objects need clang's own runtimes
+    // and `-fsanitize` on the link line, which the `cc` linker rustc runs
+    // does not give them, so any compiler but GCC is refused. MSVC builds
+    // ignore it.
+    let compiler = build.get_compiler();
+    if env::var_os("CARGO_FEATURE_SANITIZE").is_some() && !compiler.is_like_msvc() {
+        if !compiler.is_like_gnu() {
+            panic!(
+                "the sanitize feature needs GCC as the C compiler, found {}; set CC=gcc",
+                compiler.path().display()
+            );
+        }
+        build.flag("-fsanitize=address,undefined");
+        build.flag("-fno-omit-frame-pointer");
+        build.flag("-fno-sanitize-recover=undefined");
+        println!("cargo:rustc-link-lib=dylib=asan");
+        println!("cargo:rustc-link-lib=dylib=ubsan");
+    }
+
     // Core + unicode + util.
     build.file(src_dir.join("core").join("zr_engine.c"));
+    build.file(src_dir.join("core").join("zr_engine_hooks.c"));
     build.file(src_dir.join("core").join("zr_framebuffer.c"));
     build.file(src_dir.join("core").join("zr_drawlist.c"));
     build.file(src_dir.join("core").join("zr_event_pack.c"));
@@ -59,35 +402,32 @@ fn main() {
     build.file(src_dir.join("util").join("zr_caps.c"));
     build.file(src_dir.join("util").join("zr_ring.c"));
     build.file(src_dir.join("util").join("zr_log.c"));
-    build.file(src_dir.join("util").join("zr_assert.c"));
+    // The `assert-log` feature supplies zr_assert_* from Rust (src/asserts.rs)
+    // so invariant failures can be reported instead of aborting.
+    if env::var_os("CARGO_FEATURE_ASSERT_LOG").is_none() {
+        build.file(src_dir.join("util").join("zr_assert.c"));
+    }
     build.file(src_dir.join("util").join("zr_string_builder.c"));
     build.file(src_dir.join("util").join("zr_vec.c"));
 
     // Platform selection + backend.
     build.file(src_dir.join("platform").join("zr_platform_select.c"));
-    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("windows") {
-        build.file(
-            src_dir
-                .join("platform")
-                .join("win32")
-                .join("zr_plat_win32.c"),
-        );
-        println!("cargo:rustc-link-lib=dylib=advapi32");
-        println!("cargo:rustc-link-lib=dylib=kernel32");
-        println!("cargo:rustc-link-lib=dylib=user32");
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let backend = if target_os == "windows" {
+        src_dir
+            .join("platform")
+            .join("win32")
+            .join("zr_plat_win32.c")
+    } else if env::var_os("CARGO_CFG_UNIX").is_some() {
+        // Linux (glibc and musl), macOS and the BSDs share the posix backend.
+        src_dir
+            .join("platform")
+            .join("posix")
+            .join("zr_plat_posix.c")
     } else {
-        build.file(
-            src_dir
-                .join("platform")
-                .join("posix")
-                .join("zr_plat_posix.c"),
-        );
-    }
+        panic!("zireael has no platform backend for target_os {target_os:?}");
+    };
+    build.file(backend);
 
     build.compile("zireael_core");
-
-    // Keep rebuilds deterministic when vendored sources change.
-    println!("cargo:rerun-if-changed=vendor/VENDOR_COMMIT.txt");
-    println!("cargo:rerun-if-changed=vendor/zireael/include");
-    println!("cargo:rerun-if-changed=vendor/zireael/src");
 }
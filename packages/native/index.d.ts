@@ -3,6 +3,42 @@
 
 /* auto-generated by NAPI-RS */
 
+/** Result code names, as carried in a thrown `ZrError`'s `code`. */
+export type ZrErrorCode =
+  | "ZR_ERR_INVALID_ARGUMENT"
+  | "ZR_ERR_OOM"
+  | "ZR_ERR_LIMIT"
+  | "ZR_ERR_UNSUPPORTED"
+  | "ZR_ERR_FORMAT"
+  | "ZR_ERR_PLATFORM"
+  | "ZR_ERR_UNKNOWN"
+  | "POLL_CANCELED"
+  | "ASSERT_FAILED";
+/** Every error the addon throws carries its result code name in `code`. */
+export interface ZrError extends Error {
+  code: ZrErrorCode;
+}
+/** The `ZrErrorCode` (or `"ZR_OK"`) for a numeric result code. */
+export declare function ziErrorName(code: number): ZrErrorCode | "ZR_OK";
+/** Numeric result code for every `ZrErrorCode` except `"ZR_ERR_UNKNOWN"`. */
+export declare function ziResultCodes(): Record<Exclude<ZrErrorCode, "ZR_ERR_UNKNOWN">, number>;
+/** Whether a numeric result reports success (`ZR_OK` or a count). */
+export declare function ziErrorIsOk(code: number): boolean;
+export interface EngineVersion {
+  /** Vendored engine commit (`vendor/VENDOR_COMMIT.txt`), or `"unknown"`. */
+  commit: string;
+  /** Local patches applied on top of `commit` (`vendor/patches/series`), in order. */
+  patches: string[];
+  abiMajor: number;
+  abiMinor: number;
+  abiPatch: number;
+}
+/** The engine revision this addon links and its default ABI version. */
+export declare function ziEngineVersion(): EngineVersion;
+/** Drawlist versions the linked engine accepts, ascending. */
+export declare function ziSupportedDrawlistVersions(): number[];
+/** Event batch versions the linked engine accepts, ascending. */
+export declare function ziSupportedEventBatchVersions(): number[];
 export interface DebugStats {
   totalRecords: bigint;
   totalDropped: bigint;
@@ -18,24 +54,140 @@ export interface DebugQueryResult {
   newestRecordId: bigint;
   recordsDropped: number;
 }
+export interface DebugRecord {
+  recordId: bigint;
+  timestampUs: bigint;
+  frameId: bigint;
+  category: number;
+  /** Stable category name (`"frame"`, `"event"`, ...), or `"unknown"`. */
+  categoryName: string;
+  severity: number;
+  /** Stable severity name (`"trace"`, `"info"`, `"warn"`, `"error"`), or `"unknown"`. */
+  severityName: string;
+  code: number;
+  payloadSize: number;
+}
+/**
+ * Debug trace categories (`zr_debug_category_t`). `categoryMask` selects
+ * category `c` with bit `1 << c`.
+ */
+export declare enum DebugCategory {
+  None = 0,
+  Frame = 1,
+  Event = 2,
+  Drawlist = 3,
+  Error = 4,
+  State = 5,
+  Perf = 6
+}
+/** Debug trace severities (`zr_debug_severity_t`), for `minSeverity`. */
+export declare enum DebugSeverity {
+  Trace = 0,
+  Info = 1,
+  Warn = 2,
+  Error = 3
+}
+/** `categoryMask` selecting every category. */
+export const DEBUG_CATEGORY_MASK_ALL: number;
+export declare function debugCategoryName(category: number): string | null;
+export declare function debugSeverityName(severity: number): string | null;
 export declare function engineDebugEnable(
   engineId: number,
   config?: object | undefined | null,
-): number;
-export declare function engineDebugDisable(engineId: number): number;
+): void;
+export declare function engineDebugDisable(engineId: number): void;
 export declare function engineDebugQuery(
   engineId: number,
   query: object | undefined | null,
   outHeaders: Uint8Array,
 ): DebugQueryResult;
+export declare function engineDebugQueryRecords(
+  engineId: number,
+  query?: object | undefined | null,
+): Array<DebugRecord>;
+/**
+ * Copy a debug record's payload into `outPayload` and return the bytes
+ * written. Throws `ZR_ERR_LIMIT` when the buffer is too small or the record
+ * is not in the ring.
+ */
 export declare function engineDebugGetPayload(
   engineId: number,
   recordId: bigint,
   outPayload: Uint8Array,
 ): number;
+/**
+ * A debug record's payload in a new buffer of exactly its size. Throws
+ * `ZR_ERR_LIMIT` when the record is not in the ring (or tracing is off).
+ */
+export declare function engineDebugGetPayloadAlloc(engineId: number, recordId: bigint): Uint8Array;
 export declare function engineDebugGetStats(engineId: number): DebugStats;
+/** Record counts per debug category over the current ring contents. */
+export interface DebugCategoryCounts {
+  none: number;
+  frame: number;
+  event: number;
+  drawlist: number;
+  error: number;
+  state: number;
+  perf: number;
+  /** Records whose category this binding does not know. */
+  unknown: number;
+}
+/**
+ * Break the records currently held in the ring down by category, to help
+ * tune `categoryMask` before a capture.
+ */
+export declare function engineDebugCategoryCounts(engineId: number): DebugCategoryCounts;
+/**
+ * Copy the ring's records, oldest first, into `outBuf` as header (40 bytes)
+ * plus payload each, and return the bytes written. Only whole records are
+ * copied: a short buffer gets the oldest records that fit. With an empty
+ * `outBuf` nothing is written and the size a full export needs is returned
+ * instead, so callers can probe, allocate exactly, then export. Both return 0
+ * when tracing is off or the ring is empty.
+ */
 export declare function engineDebugExport(engineId: number, outBuf: Uint8Array): number;
-export declare function engineDebugReset(engineId: number): number;
+/**
+ * The debug ring as a JSON document, `{ version, records }`. Each record has
+ * its header fields, the category, severity and code names, and `payload`
+ * decoded into named fields. Payloads without a known layout come back as
+ * `payload: null` plus `payloadBase64`. `records` is empty while tracing is
+ * off.
+ */
+export declare function engineDebugExportJson(engineId: number): string;
+/**
+ * Submit and present each of `frames` in order, as recorded drawlists
+ * (`drawlist_cmd` trace payloads or saved `engineSubmitDrawlist` input), and
+ * return the `engineLastFrameReport` of every frame. All frames are validated
+ * against the engine's limits before the first is submitted; a malformed one
+ * throws naming its index and nothing is replayed. A frame the engine rejects
+ * at submit or present throws the same way, after the frames before it were
+ * shown.
+ */
+export declare function engineDebugReplay(engineId: number, frames: Array<Uint8Array>): Array<FrameReport>;
+export declare function engineDebugReset(engineId: number): void;
+/** One trace record as delivered to an `engineDebugSubscribe` callback. */
+export interface DebugTraceEvent {
+  header: DebugRecord;
+  payload: Uint8Array;
+  /**
+   * Records this subscription has dropped so far, because the callback fell
+   * behind or the ring overwrote them before delivery.
+   */
+  dropped: number;
+}
+/**
+ * Call `callback` for each trace record the engine produces from now on.
+ * Records are collected after each submit, present and poll and delivered
+ * asynchronously; a slow callback loses records (see `dropped`) instead of
+ * stalling the engine. Replaces any earlier subscription.
+ */
+export declare function engineDebugSubscribe(
+  engineId: number,
+  callback: (event: DebugTraceEvent) => void,
+): void;
+/** Stop `engineDebugSubscribe` delivery; destroying the engine does too. */
+export declare function engineDebugUnsubscribe(engineId: number): void;
 export interface EngineMetrics {
   structSize: number;
   negotiatedEngineAbiMajor: number;
@@ -60,6 +212,20 @@ export interface EngineMetrics {
   damageRectsLastFrame: number;
   damageCellsLastFrame: number;
   damageFullFrame: boolean;
+  /** Writes the last present was split into (0 unless `writeChunkBytes` is set). */
+  writeChunksLastFrame: number;
+  /** Chunks of the last present after which the terminal was not writable. */
+  writeStallsLastFrame: number;
+  writeChunksTotal: bigint;
+  writeStallsTotal: bigint;
+  /** Blank row tails the differ cleared with EL (`enableEraseOptimizations`). */
+  diffLineErasesTotal: bigint;
+  /** Blank bottom blocks the differ cleared with ED (`enableEraseOptimizations`). */
+  diffScreenErasesTotal: bigint;
+  /** Input bytes the parser could not decode (malformed CSI/SS3, invalid UTF-8). */
+  inputParseErrorsTotal: number;
+  /** Bracketed pastes dropped for exceeding the 64 KiB payload ring. */
+  pasteDroppedTotal: number;
 }
 export interface TerminalCaps {
   /** Color mode: 0=unknown, 1=16, 2=256, 3=rgb */
@@ -78,25 +244,1094 @@ export interface TerminalCaps {
   /** Bitmask of supported SGR attributes */
   sgrAttrsSupported: number;
 }
-export declare function engineCreate(config?: object | undefined | null): number;
+export interface WatchdogReport {
+  engineId: number;
+  functionName: string;
+  budgetMs: number;
+  /** Elapsed time when the overrun was detected (the call may still be running). */
+  elapsedMs: number;
+}
+/**
+ * Drain overrun reports for an engine. Callable from any thread so a
+ * supervisor can observe an owner thread that is stuck inside the engine.
+ * Throws `ZR_ERR_INVALID_ARGUMENT` for an unknown or destroyed engine id.
+ */
+export declare function engineTakeWatchdogReports(engineId: number): Array<WatchdogReport>;
+export interface IdleTransition {
+  /** `true` when presenting was paused, `false` when it resumed. */
+  idle: boolean;
+  /** Quiet period before pausing, or time spent paused before resuming. */
+  durationMs: number;
+}
+/**
+ * Whether presenting is currently paused by the idle policy. Throws
+ * `ZR_ERR_INVALID_ARGUMENT` for an unknown or destroyed engine id.
+ */
+export declare function engineIsIdle(engineId: number): boolean;
+/** Drain idle/active transitions recorded since the last call. */
+export declare function engineTakeIdleTransitions(engineId: number): Array<IdleTransition>;
+/**
+ * Create an engine and return its id. `config` may also be a JSON string
+ * holding the same object. Throws a `ZrError` on failure.
+ */
+export declare function engineCreate(config?: object | string | undefined | null): number;
 export declare function engineDestroy(engineId: number): void;
-export declare function engineSubmitDrawlist(engineId: number, drawlist: Uint8Array): number;
+export interface TryDestroyResult {
+  destroyed: boolean;
+}
+/**
+ * `engineDestroy` that never waits for calls in flight on other threads:
+ * reports `destroyed: false` and leaves the engine alive instead.
+ */
+export declare function engineTryDestroy(engineId: number): TryDestroyResult;
+/**
+ * Make the calling thread the engine's owner. Throws while another thread is
+ * inside a call on the engine, or when the engine does not exist.
+ */
+export declare function engineTransferOwnership(engineId: number): void;
+/** Whether `engineId` names a live engine. Callable from any thread. */
+export declare function engineIsAlive(engineId: number): boolean;
+/** Ids of all live engines, ascending. Callable from any thread. */
+export declare function engineListEngines(): Array<number>;
+/** Submit a drawlist for the next present. Throws a `ZrError` on failure. */
+export declare function engineSubmitDrawlist(engineId: number, drawlist: Uint8Array): void;
 export declare function engineCommitScrollback(
   engineId: number,
   drawlist: Uint8Array,
   rows: number,
-): number;
-export declare function enginePresent(engineId: number): number;
+): void;
+/**
+ * Replace layer `layerIndex` (0..7) and rebuild the frame from all cached
+ * layers back-to-front. An empty array removes the layer.
+ */
+export declare function engineSubmitLayer(
+  engineId: number,
+  layerIndex: number,
+  drawlist: Uint8Array,
+): void;
+/** Present the submitted frame. Throws a `ZrError` on failure. */
+export declare function enginePresent(engineId: number): void;
+/**
+ * Block until everything already presented has reached the terminal,
+ * regardless of `waitForOutputDrain`. Returns at once when nothing is
+ * pending; throws `ZR_ERR_LIMIT` if the terminal stays unwritable for 1s.
+ */
+export declare function engineFlush(engineId: number): void;
+/**
+ * Run a present and return the bytes it would write to the terminal instead
+ * of writing them. The next `enginePresent` repaints every cell. Throws on
+ * failure.
+ */
+export declare function enginePresentToString(engineId: number): Uint8Array;
+/** Screen rectangle in cells. */
+export interface ProtectedRect {
+  x: number;
+  y: number;
+  w: number;
+  h: number;
+}
+/**
+ * Keep the present diff out of `rects` (externally managed regions such as
+ * images or embedded PTYs). `[]` clears.
+ */
+export declare function engineSetProtectedRegions(engineId: number, rects: ProtectedRect[]): void;
+/**
+ * Poll input and write an event batch into `out`; returns the bytes written
+ * (`0` when nothing arrived). Throws a `ZrError` on failure, with code
+ * `ZR_ERR_LIMIT` when `out` is too small to hold a batch.
+ */
 export declare function enginePollEvents(
   engineId: number,
   timeoutMs: number,
   out: Uint8Array,
 ): number;
+/** `enginePollEventsCounted` outcome. */
+export interface PollResult {
+  /** Bytes of the batch written to `out` (0 when nothing arrived). */
+  bytesWritten: number;
+  /** Records in that batch. */
+  eventCount: number;
+  /** Events the engine dropped since the previous `enginePollEventsCounted`. */
+  dropped: number;
+}
+/**
+ * `enginePollEvents` that also reports the batch's record count and the
+ * engine's drops, without a separate metrics call.
+ */
+export declare function enginePollEventsCounted(
+  engineId: number,
+  timeoutMs: number,
+  out: Uint8Array,
+): PollResult;
+/**
+ * Numeric value of the `POLL_CANCELED` code `enginePollEventsCancelable`
+ * throws when the cancel token was tripped before any event arrived.
+ * Binding-owned; never produced by the engine.
+ */
+export const POLL_CANCELED: number;
+/**
+ * Like `enginePollEvents`, but throws `POLL_CANCELED` early once
+ * `cancelToken[0]` becomes non-zero. The token is an `Int32Array` (usually
+ * over a `SharedArrayBuffer`) tripped with `enginePollCancel`, which also
+ * wakes the poll; it is never reset by the binding.
+ */
+export declare function enginePollEventsCancelable(
+  engineId: number,
+  timeoutMs: number,
+  out: Uint8Array,
+  cancelToken: Int32Array,
+): number;
+/**
+ * Trip `cancelToken` and wake the engine's blocked poll so
+ * `enginePollEventsCancelable` returns at once. Callable from any thread.
+ */
+export declare function enginePollCancel(engineId: number, cancelToken: Int32Array): void;
+/**
+ * Allocate a `capacity`-byte poll buffer owned by the engine and return a view
+ * over it for `enginePollEventsInto`. Calling again replaces the buffer;
+ * earlier views keep their last batch but are no longer written.
+ */
+export declare function enginePollBuffer(engineId: number, capacity: number): Uint8Array;
+/**
+ * Poll into the `enginePollBuffer` buffer; returns the bytes written at its
+ * start. The batch is valid only until the next poll overwrites it.
+ */
+export declare function enginePollEventsInto(engineId: number, timeoutMs: number): number;
+/** Why events were lost or held back, per engine. */
+export interface DroppedEventReasons {
+  /**
+   * Events the engine evicted or refused because its queue was full
+   * (`eventsDroppedTotal`). Raise the poll rate or the queue limit.
+   */
+  queueFull: number;
+  /**
+   * `enginePostUserEvent` calls refused because the queue or the payload
+   * ring was full.
+   */
+  userQueueFull: number;
+  /**
+   * `enginePostUserEvent` calls refused because the payload exceeds the
+   * engine's 64 KiB payload ring.
+   */
+  userPayloadTooLarge: number;
+  /**
+   * Polls whose batch was truncated: the buffer filled up and the rest
+   * stayed queued. Not a loss by itself, but sustained truncation leads to
+   * `queueFull`. Use a bigger poll buffer.
+   */
+  pollTruncated: number;
+  /** Polls refused because the buffer cannot hold even the batch header. */
+  pollBufferTooSmall: number;
+  /**
+   * Input sequences the parser could not decode (`inputParseErrorsTotal`).
+   * Their bytes are delivered as plain keys or dropped, not as the
+   * intended event.
+   */
+  parseErrors: number;
+  /**
+   * Bracketed pastes dropped because they exceed the 64 KiB payload ring
+   * (`pasteDroppedTotal`).
+   */
+  pasteTooLarge: number;
+}
+/**
+ * Break event loss down by reason, so a too-small poll buffer can be told
+ * apart from an engine queue overflow.
+ */
+export declare function engineDroppedEventReasons(engineId: number): DroppedEventReasons;
+/** Oldest event batch version `decodeEventBatch` understands. */
+export const EVENT_BATCH_DECODER_VERSION_MIN: number;
+/** Newest event batch version `decodeEventBatch` understands. */
+export const EVENT_BATCH_DECODER_VERSION_MAX: number;
+/**
+ * One self-framed record from an event batch. The payload fields of its kind
+ * are decoded; fields of other kinds are absent.
+ */
+export interface EventRecord {
+  /**
+   * `"key"`, `"focus"`, `"text"`, `"paste"`, `"mouse"`, `"resize"`, `"tick"`,
+   * `"user"` or `"unknown"`.
+   */
+  kind: string;
+  timeMs: number;
+  flags: number;
+  /**
+   * Record payload after the 16-byte record header; for `"unknown"`, the raw
+   * bytes of the unrecognized sequence.
+   */
+  payload: Uint8Array;
+  /** Queue position: `opts.firstSeq` plus the record's index in the batch. */
+  seq: number;
+  /**
+   * Engine clock in microseconds when the record was queued, from
+   * `opts.timestampsUs`; never decreases in queue order.
+   */
+  timestampUs?: number;
+  /** `"key"`: `ZR_KEY_*` code. */
+  key?: number;
+  /** `"key"`, `"mouse"`: `ZR_MOD_*` bitmask. */
+  mods?: number;
+  /** `"key"`: 1=down, 2=up, 3=repeat. */
+  action?: number;
+  /** `"focus"`: whether the terminal gained focus. */
+  focused?: boolean;
+  /** `"text"`: Unicode scalar value. */
+  codepoint?: number;
+  /** `"text"`: the codepoint as a string; `"paste"`: the pasted text. */
+  text?: string;
+  /** `"mouse"`: cell column. */
+  x?: number;
+  /** `"mouse"`: cell row. */
+  y?: number;
+  /** `"mouse"`: 1=move, 2=drag, 3=down, 4=up, 5=wheel. */
+  mouseKind?: number;
+  /** `"mouse"`: pressed buttons bitmask. */
+  buttons?: number;
+  /** `"mouse"`: horizontal wheel delta. */
+  wheelX?: number;
+  /** `"mouse"`: vertical wheel delta. */
+  wheelY?: number;
+  /** `"resize"`: new size in cells. */
+  cols?: number;
+  rows?: number;
+  /** `"tick"`: milliseconds since the previous tick. */
+  dtMs?: number;
+  /** `"user"`: tag passed to `enginePostUserEvent`. */
+  tag?: number;
+  /** `"user"`: bytes passed to `enginePostUserEvent`. */
+  data?: Uint8Array;
+}
+/**
+ * Decode a batch filled by `enginePollEvents`. `version` is the negotiated
+ * `negotiatedEventBatchVersion`; batches in any other version are rejected
+ * with a precise error instead of being misparsed.
+ *
+ * `opts.coalesce` collapses runs of consecutive mouse moves, drags, wheel
+ * events (deltas summed) or resizes into the latest one, per event type;
+ * the merged record keeps the latest one's `seq`. `opts.firstSeq` (from
+ * `engineEventSeqBase`) numbers the records across polls, and
+ * `opts.timestampsUs` (from `engineEventTimestampsUs`) gives each record its
+ * `timestampUs`.
+ */
+export declare function decodeEventBatch(
+  bytes: Uint8Array,
+  version: number,
+  opts?:
+    | {
+        coalesce?: {
+          mouseMove?: boolean;
+          mouseDrag?: boolean;
+          wheel?: boolean;
+          resize?: boolean;
+        };
+        firstSeq?: number | bigint;
+        timestampsUs?: Float64Array;
+      }
+    | undefined
+    | null,
+): Array<EventRecord>;
+/**
+ * `seq` of the first record in the batch the last poll returned; pass it as
+ * `decodeEventBatch` `opts.firstSeq`. Every record an engine hands out gets
+ * the next number, so `seq` follows queue order across polls.
+ */
+export declare function engineEventSeqBase(engineId: number): number;
+/**
+ * Queue stamps of the records in the batch the last poll returned, in record
+ * order; pass them as `decodeEventBatch` `opts.timestampsUs`. Each is the
+ * engine's monotonic microsecond clock when the record was queued, and stamps
+ * never decrease in queue order, across polls included.
+ */
+export declare function engineEventTimestampsUs(engineId: number): Float64Array;
+/**
+ * User event tag reserved for unrecognized input sequences, which
+ * `decodeEventBatch` reports as `"unknown"` records. Only engines created
+ * with `unknownSequences: true` queue them.
+ */
+export const UNKNOWN_SEQUENCE_TAG: number;
+/** Queue a user event for the next poll. Throws a `ZrError` on failure. */
 export declare function enginePostUserEvent(
   engineId: number,
   tag: number,
   payload: Uint8Array,
-): number;
-export declare function engineSetConfig(engineId: number, cfg?: object | undefined | null): number;
+): void;
+/** One event for `enginePostUserEvents`. */
+export interface UserEvent {
+  tag: number;
+  payload: Uint8Array;
+}
+/**
+ * `enginePostUserEvent` for each of `events`, in order, under a single engine
+ * lookup. Stops at the first refused event and throws a `ZrError` whose
+ * message names its index; the events before it stay posted.
+ */
+export declare function enginePostUserEvents(engineId: number, events: Array<UserEvent>): void;
+/**
+ * Apply the given runtime config keys on top of the config in effect
+ * (`engineGetConfig`); omitted keys, including ones inside `limits` and
+ * `plat`, keep their current values.
+ */
+export declare function engineSetConfig(engineId: number, cfg: object): void;
+/** `plat.requestedColorMode` values (`plat_color_mode_t`). */
+export declare enum COLOR_MODE {
+  UNKNOWN = 0,
+  ANSI16 = 1,
+  ANSI256 = 2,
+  RGB = 3
+}
+/** `widthPolicy` values: how emoji graphemes are measured. */
+export declare enum WIDTH_POLICY {
+  EMOJI_NARROW = 0,
+  EMOJI_WIDE = 1
+}
+/** Engine limits under the `engineCreate` / `engineSetConfig` key names. */
+export interface EngineLimits {
+  arenaMaxTotalBytes: number;
+  arenaInitialBytes: number;
+  outMaxBytesPerFrame: number;
+  dlMaxTotalBytes: number;
+  dlMaxCmds: number;
+  dlMaxStrings: number;
+  dlMaxBlobs: number;
+  dlMaxClipDepth: number;
+  dlMaxTextRunSegments: number;
+  diffMaxDamageRects: number;
+}
+/** Platform settings of a runtime config. */
+export interface EnginePlatConfig {
+  requestedColorMode: number;
+  enableMouse: boolean;
+  enableBracketedPaste: boolean;
+  enableFocusEvents: boolean;
+  enableOsc52: boolean;
+  /** 0 alternate screen, 1 inline. */
+  screenMode: number;
+}
+/**
+ * Runtime config as `engineGetConfig` reports it; accepted back by
+ * `engineSetConfig` unchanged.
+ */
+export interface EngineRuntimeConfig {
+  limits: EngineLimits;
+  plat: EnginePlatConfig;
+  tabWidth: number;
+  widthPolicy: number;
+  targetFps: number;
+  enableScrollOptimizations: boolean;
+  enableDebugOverlay: boolean;
+  enableReplayRecording: boolean;
+  waitForOutputDrain: boolean;
+  capForceFlags: number;
+  capSuppressFlags: number;
+  inlineRows: number;
+  enableEraseOptimizations: boolean;
+}
+/**
+ * The runtime config the engine last accepted, from `engineCreate` or the
+ * latest successful `engineSetConfig`.
+ */
+export declare function engineGetConfig(engineId: number): EngineRuntimeConfig;
+/**
+ * Create-time config as `engineConfigPreset` builds it: the runtime keys plus
+ * the requested versions, all under the `engineCreate` key names.
+ */
+export interface EngineCreateConfig {
+  requestedEngineAbiMajor: number;
+  requestedEngineAbiMinor: number;
+  requestedEngineAbiPatch: number;
+  requestedDrawlistVersion: number;
+  requestedEventBatchVersion: number;
+  limits: EngineLimits;
+  plat: EnginePlatConfig;
+  tabWidth: number;
+  widthPolicy: number;
+  targetFps: number;
+  enableScrollOptimizations: boolean;
+  enableDebugOverlay: boolean;
+  enableReplayRecording: boolean;
+  waitForOutputDrain: boolean;
+  capForceFlags: number;
+  capSuppressFlags: number;
+  inlineRows: number;
+  enableEraseOptimizations: boolean;
+}
+/**
+ * A ready-to-pass `engineCreate` config: the engine defaults with the
+ * overrides of the named preset.
+ */
+export declare function engineConfigPreset(name: "low-memory" | "high-throughput" | "debug"): EngineCreateConfig;
+/** The engine's default limits, read without creating an engine. */
+export declare function ziLimitsDefault(): EngineLimits;
+/** The engine's default create config, read without creating an engine. */
+export declare function ziConfigDefault(): EngineCreateConfig;
+/** What `enginePrewarm` reserved. */
+export interface PrewarmReport {
+  /** Bytes a full `cols`x`rows` frame asks each arena for. */
+  arenaBytesRequested: number;
+  /** Initial bytes each engine arena now reserves up front. */
+  arenaBytesReserved: number;
+  /** `true` when `arenaMaxTotalBytes` capped the reservation. */
+  clamped: boolean;
+}
+/**
+ * Reserve the arenas a full-screen frame needs before the first present so
+ * early frames skip arena growth. Never exceeds `arenaMaxTotalBytes`.
+ */
+export declare function enginePrewarm(
+  engineId: number,
+  opts: { cols: number; rows: number },
+): PrewarmReport;
+/** Drawlist limits an engine enforces on `engineSubmitDrawlist`. */
+export interface DrawlistBudget {
+  /** Total drawlist size in bytes. */
+  maxTotalBytes: number;
+  maxCmds: number;
+  maxStrings: number;
+  maxBlobs: number;
+  /** Nesting depth of `PUSH_CLIP`. */
+  maxClipDepth: number;
+  /** Segments in one text-run blob. */
+  maxTextRunSegments: number;
+}
+/**
+ * The drawlist limits the engine runs with now (create-time or last accepted
+ * `engineSetConfig`). Exceeding any of them fails with `ZR_ERR_LIMIT`.
+ */
+export declare function engineDrawlistBudget(engineId: number): DrawlistBudget;
+/** What an accepted drawlist used of each `DrawlistBudget` limit. */
+export interface DrawlistUsage {
+  totalBytes: number;
+  cmds: number;
+  /** Strings this drawlist defined that are still live at its end. */
+  strings: number;
+  blobs: number;
+  /** Deepest `PUSH_CLIP` nesting reached. */
+  clipDepth: number;
+  /** Largest text run drawn from a blob defined in this drawlist. */
+  textRunSegments: number;
+}
+/**
+ * `engineSubmitDrawlist` that, on success, reports how close the drawlist
+ * came to each `engineDrawlistBudget` limit, so callers can scale back before
+ * a frame is rejected with `ZR_ERR_LIMIT`. `strings`/`blobs` count only this
+ * drawlist's definitions, not ones kept from earlier submits.
+ */
+export declare function engineSubmitDrawlistChecked(engineId: number, drawlist: Uint8Array): DrawlistUsage;
 export declare function engineGetMetrics(engineId: number): EngineMetrics;
+/** Versions the engine negotiated at create (possibly below the requested ones). */
+export interface EngineNegotiation {
+  abiMajor: number;
+  abiMinor: number;
+  abiPatch: number;
+  drawlistVersion: number;
+  eventBatchVersion: number;
+}
+/** The negotiated ABI, drawlist and event batch versions of an engine. */
+export declare function engineGetNegotiation(engineId: number): EngineNegotiation;
+/** Size in bytes of the raw metrics struct `engineGetMetricsInto` writes. */
+export const METRICS_STRUCT_SIZE: number;
+/**
+ * Write the raw little-endian `zr_metrics_t` into `out` (at least
+ * `METRICS_STRUCT_SIZE` bytes, 8-byte aligned) without allocating; returns the
+ * bytes the engine filled (120 for an engine from before ABI 1.5, whose
+ * missing fields are zeroed). Field offsets are listed in the native backend
+ * docs.
+ */
+export declare function engineGetMetricsInto(engineId: number, out: Uint8Array): number;
+/**
+ * Restart `bytesEmittedTotal`, `eventsDroppedTotal`, `writeChunksTotal`,
+ * `writeStallsTotal` and the `engineDroppedEventReasons` counts from zero.
+ * Per-frame fields, `frameIndex` and arena high-water marks are unaffected.
+ */
+export declare function engineResetMetrics(engineId: number): void;
+/** Rolling frame timing over the last `frameStatsWindow` presents. */
+export interface FrameStats {
+  /** Frame times summarized (0 until two frames have been presented). */
+  frames: number;
+  /** Frames per second over the window. */
+  fps: number;
+  /** Median frame time in milliseconds. */
+  p50: number;
+  p95: number;
+  p99: number;
+  max: number;
+}
+/**
+ * FPS and frame-time percentiles (milliseconds) over the engine's last
+ * `frameStatsWindow` frames, where a frame time is the interval between two
+ * successive `enginePresent` calls that reached the terminal.
+ */
+export declare function engineGetFrameStats(engineId: number): FrameStats;
+/**
+ * Switch mouse reporting right away. Disabling always writes the disable
+ * sequence, cleaning up after other programs too.
+ */
+export declare function engineSetMouse(engineId: number, enabled: boolean): void;
+/** Switch bracketed paste right away. */
+export declare function engineSetBracketedPaste(engineId: number, enabled: boolean): void;
+/** Switch focus in/out reporting right away. */
+export declare function engineSetFocusEvents(engineId: number, enabled: boolean): void;
+/**
+ * Last focus state reported by the terminal (`true` before the first focus
+ * event); `undefined` while focus reporting is off or unsupported.
+ */
+export declare function engineIsFocused(engineId: number): boolean | undefined;
+/** Cursor blink override; `"off"` defers to the SET_CURSOR blink flag. */
+export type CursorBlinkMode = "on" | "off" | "steady";
+/**
+ * Force blinking or steady DECSCUSR variants on every following present.
+ * Returns the effective mode (`"off"` without cursor-shape support).
+ */
+export declare function engineSetCursorBlink(
+  engineId: number,
+  options: { mode: CursorBlinkMode },
+): CursorBlinkMode;
+export type DebugOverlayCorner = "topLeft" | "topRight" | "bottomLeft" | "bottomRight";
+/**
+ * Show or hide the engine's debug overlay from the next present on, without
+ * touching the rest of the runtime config. An omitted `corner` keeps the
+ * current one (initially `"topLeft"`).
+ */
+export declare function engineSetDebugOverlay(
+  engineId: number,
+  options: { enabled: boolean; corner?: DebugOverlayCorner },
+): void;
+/**
+ * Ring the terminal bell. `{ visual: true }` flashes the screen in reverse
+ * video on the next present instead of emitting BEL.
+ */
+export declare function engineBell(
+  engineId: number,
+  opts?: { visual?: boolean } | undefined | null,
+): void;
+/**
+ * Set the terminal window title (OSC 2) on the next present. Control
+ * characters are stripped.
+ */
+export declare function engineSetTitle(engineId: number, title: string): void;
+/**
+ * Save (`"push"`) or restore (`"pop"`) the title via the xterm title stack on
+ * the next present.
+ */
+export declare function engineSetTitleStack(engineId: number, op: "push" | "pop"): void;
+/**
+ * Queue raw bytes to be written right after the next present's diff output.
+ * The bytes are wrapped in DECSC/DECRC so the cursor position and SGR state
+ * the differ tracks are restored afterwards, and the present after them
+ * repaints every cell.
+ */
+export declare function engineWriteRaw(engineId: number, bytes: Uint8Array): void;
+/**
+ * How the differ produced the last frame. Only available while debug tracing
+ * records the `perf` category.
+ */
+export interface FrameDiffPath {
+  /** Full line sweep was used. */
+  sweepUsed: boolean;
+  /** Damage-rect path was used. */
+  damageUsed: boolean;
+  scrollOptAttempted: boolean;
+  scrollOptHit: boolean;
+  /** Hash-collision guard hits in this frame. */
+  collisionGuardHits: number;
+  collisionGuardHitsTotal: bigint;
+}
+/** Everything known about the last presented frame, gathered in one call. */
+export interface FrameReport {
+  frameIndex: bigint;
+  usInput: number;
+  usDrawlist: number;
+  usDiff: number;
+  usWrite: number;
+  bytesEmitted: number;
+  dirtyLines: number;
+  dirtyCols: number;
+  damageRects: number;
+  damageCells: number;
+  damageFullFrame: boolean;
+  /** `bytesEmitted / damageCells`; `0` when no cell changed. */
+  bytesPerDamagedCell: number;
+  arenaFrameHighWaterBytes: bigint;
+  arenaPersistentHighWaterBytes: bigint;
+  /** `null` unless debug tracing captured the frame's diff telemetry. */
+  diffPath?: FrameDiffPath;
+}
+/**
+ * Snapshot of the last presented frame: timing, bytes, damage, arena
+ * high-water marks and, when traced, the diff path taken.
+ */
+export declare function engineLastFrameReport(engineId: number): FrameReport;
+/** A present whose damage covered the whole frame. */
+export interface FullFrame {
+  frameIndex: bigint;
+  reason: "initial" | "resize" | "restore" | "raw" | "invalidated" | "scroll" | "damage";
+}
+/**
+ * The last 32 full-frame presents, oldest first.
+ */
+export declare function engineRecentFullFrames(engineId: number): Array<FullFrame>;
+/** One byte range of a presented frame. */
+export interface FrameSpan {
+  byteOffset: number;
+  length: number;
+  kind: "cup" | "sgr" | "text" | "el" | "scroll" | "other";
+}
+/** Bytes the engine wrote for the last present, split into spans. */
+export interface FrameSpans {
+  bytes: Uint8Array;
+  spans: FrameSpan[];
+}
+/**
+ * Record each present's output for `engineLastFrameSpans`. Off by default.
+ */
+export declare function engineSetFrameSpans(engineId: number, enabled: boolean): void;
+/** Last recorded frame, or `null` before one was recorded. */
+export declare function engineLastFrameSpans(engineId: number): FrameSpans | null;
+/** One framebuffer cell; colors are `0x00RRGGBB`. */
+export interface ExportedCell {
+  /** UTF-8 grapheme; empty for the trailing half of a wide glyph. */
+  glyph: string;
+  fg: number;
+  bg: number;
+  /** `ATTR_*` bitmask. */
+  attrs: number;
+  /** Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail. */
+  width: number;
+}
+/**
+ * Rows of cells of the frame built by the drawlists submitted so far, with
+ * RGB colors independent of the terminal's color mode. Needs an engine
+ * created with `trackFrame: true`; throws until the initial resize event has
+ * been polled.
+ */
+export declare function engineExportFrame(engineId: number): Array<Array<ExportedCell>>;
+/**
+ * Opaque blob of the frame built so far and the requested cursor, tagged
+ * with the engine ABI. Needs an engine created with `trackFrame: true`;
+ * throws until the initial resize event has been polled.
+ */
+export declare function engineSaveState(engineId: number): Uint8Array;
+/**
+ * Put a blob from `engineSaveState` back into the back buffer; the next
+ * present repaints every cell. The screen size must match.
+ */
+export declare function engineRestoreState(engineId: number, blob: Uint8Array): void;
+/** Rows of a rect to shift: positive `dy` moves content down, negative up. */
+export interface ScrollRegion {
+  rect: CellRect;
+  dy: number;
+}
+/**
+ * Shift the rows of `region.rect` in the frame built so far by `region.dy`
+ * and fill the vacated rows with spaces in `style`. A full-width rect lets
+ * the next present use a hardware scroll.
+ */
+export declare function engineScrollRegion(
+  engineId: number,
+  region: ScrollRegion,
+  style?: Style | undefined | null,
+): void;
+/**
+ * Restore the terminal (modes, cursor, screen, input mode) when the process
+ * is killed by one of `signals`, then die with the default action. A handler
+ * that was already installed for a signal (Node's own, for instance) is
+ * called instead and the terminal is left alone; that handler restores it
+ * with `engineDestroy`. Each installing engine stays armed until it is
+ * destroyed. Throws `ZR_ERR_LIMIT` once 16 engines are armed, and
+ * `ZR_ERR_UNSUPPORTED` on Windows and for engines created with `inputFd` or
+ * `outputFd`.
+ */
+export declare function engineInstallSignalHandlers(
+  engineId: number,
+  signals: Array<"SIGTERM" | "SIGINT" | "SIGHUP">,
+): void;
+/**
+ * Destroy the engines the calling thread still owns when its JS environment
+ * shuts down, including on `process.exit()`. Never waits: engines another
+ * thread is inside a call on are skipped. Engines owned by other threads
+ * are not destroyed, but on `process.exit()` the process terminal they
+ * render to is restored (POSIX). Per thread; repeat calls are no-ops.
+ */
+export declare function engineInstallExitHook(): void;
+/**
+ * Numeric value of the `ASSERT_FAILED` code an engine call throws in assert
+ * mode `"error"` when an engine invariant failed during the call.
+ * Binding-owned; never produced by the engine.
+ */
+export const ASSERT_FAILED: number;
+/**
+ * What an engine assertion failure does: `"abort"` (default), `"log"` (record
+ * it, pass it to `onFailure` and continue) or `"error"` (log and fail the
+ * in-flight call with `ASSERT_FAILED`). `onFailure` also receives other
+ * engine log lines such as watchdog overruns. It replaces the previous
+ * callback; omitting it removes that. Non-abort modes need the `assert-log`
+ * build feature and throw `ZR_ERR_UNSUPPORTED` otherwise.
+ */
+export declare function setAssertMode(
+  mode: "abort" | "log" | "error",
+  onFailure?: (message: string) => void,
+): void;
+/** Drain reported assertion failures, oldest first. */
+export declare function takeAssertFailures(): Array<string>;
+/** Where the engine is rendering, for bug reports and diagnostics. */
+export interface PlatformInfo {
+  /** Platform backend compiled in: `"posix"` or `"win32"`. */
+  backend: "posix" | "win32";
+  /** Terminal identified by the engine's detection (`"unknown"` if none). */
+  terminal: string;
+  /** `$TERM` as seen by the platform layer; absent for `inputFd`/`outputFd` engines. */
+  term?: string;
+  /** `$TERM_PROGRAM` as seen by the platform layer; absent for bound engines. */
+  termProgram?: string;
+  /**
+   * XTVERSION reply when the terminal answered, else `$TERM_PROGRAM_VERSION`
+   * (never the environment for bound engines).
+   */
+  termVersion?: string;
+  /** `false` when rendering to redirected stdio (pipe mode) or a non-TTY `outputFd`. */
+  isTty: boolean;
+}
+/** Report the platform backend and the terminal the engine detected. */
+export declare function enginePlatformInfo(engineId: number): PlatformInfo;
 export declare function engineGetCaps(engineId: number): TerminalCaps;
+export interface TerminalSize {
+  cols: number;
+  rows: number;
+}
+/**
+ * Size from the last `resize` event polled from the engine: the full screen,
+ * or the viewport in inline mode. `{ cols: 0, rows: 0 }` until the first
+ * resize (queued at create) has been polled.
+ */
+export declare function engineGetSize(engineId: number): TerminalSize;
+/**
+ * Latest size from a `resize` event polled since the previous call, or `null`.
+ * Several resizes in between collapse into the last; the events themselves
+ * stay in their batches.
+ */
+export declare function engineTakeResize(engineId: number): TerminalSize | null;
+/**
+ * Treat the terminal as `cols` x `rows` (each 1-65535) from now on; the next
+ * poll resizes the framebuffers and reports a `resize` event. For hosts whose
+ * size changes never arrive as SIGWINCH.
+ */
+export declare function enginePostResize(engineId: number, cols: number, rows: number): void;
+export declare function engineSupports(
+  engineId: number,
+  feature:
+    | "osc52"
+    | "hyperlinks"
+    | "syncUpdate"
+    | "scrollRegion"
+    | "cursorShape"
+    | "mouse"
+    | "truecolor"
+    | "bracketedPaste"
+    | "focusEvents"
+    | "underlineStyles"
+    | "coloredUnderlines",
+): boolean;
+/** Cell rectangle of a framebuffer. */
+export interface CellRect {
+  x: number;
+  y: number;
+  w: number;
+  h: number;
+}
+/** One grapheme for `Framebuffer.putRow`; `style` defaults to all zeros. */
+export interface CellWrite {
+  glyph: string;
+  style?: Style | undefined | null;
+}
+/** One framebuffer cell as `Framebuffer.cell` returns it. */
+export interface FramebufferCell {
+  /** UTF-8 grapheme; empty for the trailing half of a wide glyph. */
+  glyph: string;
+  /** Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail. */
+  width: number;
+  style: Style;
+}
+/** One cell of `Framebuffer.snapshot`. */
+export interface SnapshotCell {
+  /** UTF-8 grapheme; empty for the trailing half of a wide glyph. */
+  g: string;
+  /** Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail. */
+  w: number;
+  /** `0x00RRGGBB`. */
+  fg: number;
+  /** `0x00RRGGBB`. */
+  bg: number;
+  /** `ZR_STYLE_ATTR_*` bitmask. */
+  attrs: number;
+}
+export interface FramebufferSnapshot {
+  cols: number;
+  rows: number;
+  /** Row-major, `cols * rows` entries. */
+  cells: Array<SnapshotCell>;
+}
+/** Offscreen framebuffer owned by JS (no engine or terminal required). */
+export declare class Framebuffer {
+  constructor(cols: number, rows: number);
+  get cols(): number;
+  get rows(): number;
+  /** Reset every cell to a space in `style` and drop the hyperlinks. */
+  clear(style?: Style | undefined | null): void;
+  /** The cell at `(x, y)`; out-of-range coordinates throw. */
+  cell(x: number, y: number): FramebufferCell;
+  /**
+   * Write one cell per grapheme along row `y` from column 0. Everything is
+   * checked before any cell changes; a misfit throws naming its index.
+   */
+  putRow(y: number, cells: CellWrite[]): void;
+  /**
+   * Write `graphemes` left to right from `(x, y)` in one style, each
+   * advancing by its width. Atomic, like `putRow`.
+   */
+  putSpan(x: number, y: number, graphemes: string[], style?: Style | undefined | null): void;
+  /**
+   * Fill `rect` with `glyph`. A wide glyph repeats every two columns and an
+   * odd last column gets a space. The rect must lie inside the framebuffer.
+   */
+  fillRect(rect: CellRect, glyph: string, style?: Style | undefined | null): void;
+  /**
+   * Shift the rows of `rect` by `dy` (positive moves content down) and fill
+   * the vacated rows with spaces in `style`.
+   */
+  scroll(rect: CellRect, dy: number, style?: Style | undefined | null): void;
+  /**
+   * Reallocate to `cols` x `rows`, keeping the overlapping top-left cells.
+   * Newly exposed cells are spaces in `style` (default all zeros).
+   */
+  resize(cols: number, rows: number, style?: Style | undefined | null): void;
+  /** Every cell as `{ g, w, fg, bg, attrs }`, row-major, for golden tests. */
+  snapshot(): FramebufferSnapshot;
+  /** The text of each row, trailing spaces trimmed. */
+  toLines(): Array<string>;
+  /** A clipping painter over this framebuffer nesting up to `clipCapacity` clips. */
+  painter(clipCapacity: number): Painter;
+  /** Free the cell storage now instead of waiting for GC. Idempotent. */
+  release(): void;
+}
+/** Clipped drawing on a `Framebuffer`; see `Framebuffer.painter`. */
+export declare class Painter {
+  /** Clips currently pushed. */
+  readonly clipDepth: number;
+  /**
+   * Narrow the clip to `rect` intersected with the current one. Throws
+   * `ZR_ERR_LIMIT` when `clipCapacity` clips are already pushed.
+   */
+  clipPush(rect: CellRect): void;
+  /** Undo the last `clipPush`. Throws `ZR_ERR_LIMIT` when none is pushed. */
+  clipPop(): void;
+  /** Write one grapheme `width` (1 or 2) columns wide, subject to the clip. */
+  putGrapheme(
+    x: number,
+    y: number,
+    glyph: string,
+    width: number,
+    style?: Style | undefined | null,
+  ): void;
+}
+/** `diffRender` options. */
+export interface DiffRenderOpts {
+  /** Clear blank row tails and bottom rows with EL/ED. */
+  eraseBlankTails?: boolean;
+  /** Let a vertical shift come out as a hardware scroll. */
+  enableScrollOptimizations?: boolean;
+  /** `prev` is already on screen, so the diff starts without a clear. */
+  assumeScreen?: boolean;
+  /** Return the differ's stats with the bytes. */
+  stats?: boolean;
+  /** Return the damage rects with the bytes. */
+  damage?: boolean;
+}
+/**
+ * Diff two caller-owned framebuffers and return the terminal bytes that turn
+ * `prev` into `next`, or, with `opts.stats` or `opts.damage`, the bytes with
+ * those. Unless `assumeScreen` is set the terminal state is unknown at the
+ * start, so output is deterministic for a given pair and caps.
+ */
+export declare function diffRender(
+  prev: Framebuffer,
+  next: Framebuffer,
+  caps: object | undefined | null,
+  opts: DiffRenderOpts & ({ stats: true } | { damage: true }),
+): FramebufferDiff;
+export declare function diffRender(
+  prev: Framebuffer,
+  next: Framebuffer,
+  caps?: object | undefined | null,
+  opts?: DiffRenderOpts | undefined | null,
+): Uint8Array;
+/** What the differ did for one `diffRender` call. */
+export interface DiffStats {
+  dirtyLines: number;
+  dirtyCells: number;
+  damageRects: number;
+  damageCells: number;
+  /** Damage covered the whole frame. */
+  damageFullFrame: boolean;
+  /** Full line sweep was used. */
+  sweepUsed: boolean;
+  /** Damage-rect path was used. */
+  damageUsed: boolean;
+  scrollOptAttempted: boolean;
+  scrollOptHit: boolean;
+  /** Hash-collision guard hits. */
+  collisionGuardHits: number;
+  /** Blank row tails cleared with EL. */
+  lineErases: number;
+  /** Blank bottom blocks cleared with ED. */
+  screenErases: number;
+  bytesEmitted: number;
+}
+/** Cells a diff considered dirty; `x1`/`y1` are inclusive. */
+export interface DamageRect {
+  x0: number;
+  y0: number;
+  x1: number;
+  y1: number;
+}
+/** `diffRender` result with `opts.stats` or `opts.damage`. */
+export interface FramebufferDiff {
+  bytes: Uint8Array;
+  /** Set with `opts.stats`. */
+  stats?: DiffStats;
+  /**
+   * Set with `opts.damage`: the damage rects behind `stats.damageRects`, one
+   * whole-frame rect when `damageFullFrame`, none when the row sweep ran
+   * (`sweepUsed`).
+   */
+  damage?: DamageRect[];
+}
+/**
+ * Parse a drawlist into a fresh framebuffer and return the bytes that paint
+ * it over a blank screen, without an engine or a TTY. Malformed drawlists
+ * throw with the index of the offending command. `initialStyle` declares the
+ * screen already blank in that SGR state, so the output carries only deltas
+ * instead of a clear and a full reset.
+ */
+export declare function renderDrawlistToBytes(
+  drawlist: Uint8Array,
+  opts: {
+    cols: number;
+    rows: number;
+    caps?: object;
+    limits?: object;
+    initialStyle?: Style;
+    eraseBlankTails?: boolean;
+  },
+): Uint8Array;
+/** Outcome of `validateDrawlist`. */
+export interface DrawlistValidation {
+  ok: boolean;
+  /**
+   * The `EngineLimits` field exceeded (`"dlMaxClipDepth"`, ...), or the
+   * result code name for any other rejection (`"ZR_ERR_FORMAT"`, ...).
+   */
+  violation?: string;
+  /** Index of the offending command, when one is to blame. */
+  commandIndex?: number;
+  /** What went wrong, for logs; `"ok"` when valid. */
+  detail: string;
+}
+/**
+ * Check a drawlist against `limits` (defaults when omitted) the way
+ * `engineSubmitDrawlist` would, without an engine, and say which limit or
+ * command is at fault instead of only `ZR_ERR_LIMIT`. Strings and blobs
+ * defined by earlier submits are not known here.
+ */
+export declare function validateDrawlist(drawlist: Uint8Array, limits?: object | undefined | null): DrawlistValidation;
+/**
+ * `ZR_STYLE_ATTR_*` bits, as used in `Style.attrs` and
+ * `TerminalCaps.sgrAttrsSupported`.
+ */
+export const ATTR_BOLD: number;
+export const ATTR_ITALIC: number;
+export const ATTR_UNDERLINE: number;
+export const ATTR_REVERSE: number;
+export const ATTR_DIM: number;
+export const ATTR_STRIKE: number;
+export const ATTR_OVERLINE: number;
+export const ATTR_BLINK: number;
+/** An attribute bitmask as one flag per attribute. */
+export interface SgrAttrs {
+  bold: boolean;
+  italic: boolean;
+  underline: boolean;
+  reverse: boolean;
+  dim: boolean;
+  strike: boolean;
+  overline: boolean;
+  blink: boolean;
+}
+/** Packed `zr_style_t` colors and attributes (`0x00RRGGBB` colors). */
+export interface Style {
+  fgRgb: number;
+  bgRgb: number;
+  /** `ZR_STYLE_ATTR_*` bitmask. */
+  attrs: number;
+  reserved: number;
+}
+/**
+ * Build a packed style from colors (palette index, hex string, or
+ * `{ r, g, b }`) and attribute flags. Omitted colors default to 0 (black).
+ */
+export declare function makeStyle(
+  opts?:
+    | {
+        fg?: number | string | { r: number; g: number; b: number };
+        bg?: number | string | { r: number; g: number; b: number };
+        bold?: boolean;
+        dim?: boolean;
+        italic?: boolean;
+        underline?: boolean;
+        reverse?: boolean;
+        strike?: boolean;
+      }
+    | undefined
+    | null,
+): Style;
+/**
+ * Split an attribute bitmask (`Style.attrs`, `TerminalCaps.sgrAttrsSupported`)
+ * into named flags. Bits outside the `ATTR_*` constants throw.
+ */
+export declare function decodeSgrAttrs(mask: number): SgrAttrs;
+/** Pack `SgrAttrs` flags back into a bitmask; omitted flags are off. */
+export declare function encodeSgrAttrs(attrs: Partial<SgrAttrs>): number;
+/**
+ * Columns `input` occupies under the engine's width rules (widest line,
+ * tabs expanded every `tabWidth` columns). `widthPolicy`: 0 emoji narrow,
+ * 1 emoji wide.
+ */
+export declare function measureText(input: string, widthPolicy: number, tabWidth: number): number;
+/** One grapheme cluster; offsets are UTF-8 bytes. */
+export interface GraphemeMeasure {
+  byteStart: number;
+  byteLen: number;
+  width: number;
+}
+/** Grapheme clusters of `input` as the engine splits them, with widths. */
+export declare function measureGraphemes(
+  input: string,
+  widthPolicy?: number | undefined | null,
+): Array<GraphemeMeasure>;
+/**
+ * Grapheme clusters of a string or of UTF-8 bytes, with widths under the
+ * engine's default policy. Invalid bytes come back as one-byte clusters.
+ */
+export declare function segmentGraphemes(input: string | Uint8Array): Array<GraphemeMeasure>;
+/**
+ * Lines of `input` wrapped to `maxWidth` columns by the engine's wrapper.
+ * Hard breaks are removed from the lines; a trailing newline yields a final
+ * empty line. `maxWidth` 0 throws `ZR_ERR_INVALID_ARGUMENT`.
+ */
+export declare function wrapText(
+  input: string,
+  maxWidth: number,
+  opts?:
+    | { tabWidth?: number; widthPolicy?: number; breakLongWords?: boolean }
+    | undefined
+    | null,
+): Array<string>;
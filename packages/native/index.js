@@ -1,22 +1,128 @@
 import native from "./loader.cjs";
 
 export const {
+  ziErrorName,
+  ziResultCodes,
+  ziErrorIsOk,
+  ziEngineVersion,
+  ziSupportedDrawlistVersions,
+  ziSupportedEventBatchVersions,
+  ziLimitsDefault,
+  ziConfigDefault,
   engineCreate,
   engineDestroy,
+  engineTryDestroy,
+  engineTransferOwnership,
+  engineIsAlive,
+  engineListEngines,
   engineSubmitDrawlist,
   engineCommitScrollback,
+  engineSubmitLayer,
   enginePresent,
+  engineFlush,
+  enginePresentToString,
+  engineSetProtectedRegions,
   enginePollEvents,
+  enginePollEventsCounted,
+  enginePollEventsCancelable,
+  enginePollCancel,
+  enginePollBuffer,
+  enginePollEventsInto,
+  engineDroppedEventReasons,
+  POLL_CANCELED,
+  ASSERT_FAILED,
+  setAssertMode,
+  takeAssertFailures,
+  decodeEventBatch,
+  engineEventSeqBase,
+  engineEventTimestampsUs,
+  EVENT_BATCH_DECODER_VERSION_MIN,
+  EVENT_BATCH_DECODER_VERSION_MAX,
   enginePostUserEvent,
+  enginePostUserEvents,
+  UNKNOWN_SEQUENCE_TAG,
+  engineBell,
+  engineSetMouse,
+  engineSetBracketedPaste,
+  engineSetFocusEvents,
+  engineIsFocused,
+  engineSetCursorBlink,
+  engineSetDebugOverlay,
+  engineSetTitle,
+  engineSetTitleStack,
+  engineWriteRaw,
   engineGetMetrics,
+  engineGetNegotiation,
+  engineGetMetricsInto,
+  METRICS_STRUCT_SIZE,
+  engineResetMetrics,
+  engineGetFrameStats,
+  engineLastFrameReport,
+  engineRecentFullFrames,
+  engineSetFrameSpans,
+  engineLastFrameSpans,
+  engineExportFrame,
+  engineSaveState,
+  engineRestoreState,
+  engineScrollRegion,
+  engineInstallSignalHandlers,
+  engineInstallExitHook,
   engineSetConfig,
+  engineGetConfig,
+  engineConfigPreset,
+  COLOR_MODE,
+  WIDTH_POLICY,
+  enginePrewarm,
+  engineDrawlistBudget,
+  engineSubmitDrawlistChecked,
   engineGetCaps,
+  engineGetSize,
+  engineTakeResize,
+  enginePostResize,
+  engineSupports,
+  enginePlatformInfo,
+  engineTakeWatchdogReports,
+  engineIsIdle,
+  engineTakeIdleTransitions,
   // Debug trace API
   engineDebugEnable,
   engineDebugDisable,
   engineDebugQuery,
+  engineDebugQueryRecords,
   engineDebugGetPayload,
+  engineDebugGetPayloadAlloc,
   engineDebugGetStats,
+  engineDebugCategoryCounts,
   engineDebugExport,
+  engineDebugExportJson,
+  engineDebugReplay,
   engineDebugReset,
+  engineDebugSubscribe,
+  engineDebugUnsubscribe,
+  debugCategoryName,
+  debugSeverityName,
+  DebugCategory,
+  DebugSeverity,
+  DEBUG_CATEGORY_MASK_ALL,
+  // Offscreen framebuffers
+  Framebuffer,
+  Painter,
+  diffRender,
+  renderDrawlistToBytes,
+  validateDrawlist,
+  makeStyle,
+  decodeSgrAttrs,
+  encodeSgrAttrs,
+  ATTR_BOLD,
+  ATTR_ITALIC,
+  ATTR_UNDERLINE,
+  ATTR_REVERSE,
+  ATTR_DIM,
+  ATTR_STRIKE,
+  ATTR_OVERLINE,
+  ATTR_BLINK,
+  measureText,
+  measureGraphemes,
+  segmentGraphemes,
+  wrapText,
 } = native;
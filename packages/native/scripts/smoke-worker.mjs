@@ -8,6 +8,16 @@ import {
 
 const { engineId } = workerData;
 
+/** Code a throwing call failed with, or "ok". */
+function thrownCode(fn) {
+  try {
+    fn();
+    return "ok";
+  } catch (err) {
+    return err?.code ?? String(err);
+  }
+}
+
 if (!parentPort) {
   throw new Error("smoke-worker: missing parentPort");
 }
@@ -16,10 +26,10 @@ if (workerData?.phase === "loadOnly") {
   parentPort.postMessage({ phase: "loadOnly" });
   parentPort.close();
 } else {
-  const res1 = enginePresent(engineId);
-  const res2 = enginePostUserEvent(engineId, 123, new Uint8Array([1, 2, 3]));
-  const res3 = engineSetConfig(engineId, { targetFps: 33 });
-  const res4 = engineDebugDisable(engineId);
+  const res1 = thrownCode(() => enginePresent(engineId));
+  const res2 = thrownCode(() => enginePostUserEvent(engineId, 123, new Uint8Array([1, 2, 3])));
+  const res3 = thrownCode(() => engineSetConfig(engineId, { targetFps: 33 }));
+  const res4 = thrownCode(() => engineDebugDisable(engineId));
   parentPort.postMessage({
     phase: "alive",
     present: res1,
@@ -30,7 +40,7 @@ if (workerData?.phase === "loadOnly") {
 
   parentPort.on("message", (msg) => {
     if (msg?.type !== "afterDestroy") return;
-    const res = enginePostUserEvent(engineId, 456, new Uint8Array([9]));
+    const res = thrownCode(() => enginePostUserEvent(engineId, 456, new Uint8Array([9])));
     parentPort.postMessage({ phase: "destroyed", postUserEvent: res });
   });
 }
@@ -15,24 +15,26 @@ import {
   engineSetConfig,
 } from "../index.js";
 
-const ZR_OK = 0;
-const ZR_ERR_INVALID_ARGUMENT = -1;
-const ZR_ERR_PLATFORM = -6;
-const INVALID_ARG_ERROR_RE = /INVALID_ARGUMENT|InvalidArg|ZR_ERR_INVALID_ARGUMENT/i;
+const INVALID_ARGUMENT = "ZR_ERR_INVALID_ARGUMENT";
 
 function assert(cond, msg) {
   if (!cond) throw new Error(msg);
 }
 
-function assertThrows(fn, pattern, msg) {
+// `expected` is an error code compared against `err.code`, or a RegExp the
+// message must match.
+function assertThrows(fn, expected, msg) {
   let threw = false;
   try {
     fn();
   } catch (err) {
     threw = true;
-    if (pattern) {
+    if (typeof expected === "string") {
+      const code = err?.code ?? String(err);
+      assert(code === expected, `${msg}: wrong error code: ${code}`);
+    } else if (expected) {
       const detail = err instanceof Error ? err.message : String(err);
-      assert(pattern.test(detail), `${msg}: wrong error detail: ${detail}`);
+      assert(expected.test(detail), `${msg}: wrong error detail: ${detail}`);
     }
   }
   assert(threw, `${msg}: expected throw`);
@@ -100,73 +102,106 @@ async function assertWorkerLoadExitCleanly() {
 await assertWorkerLoadExitCleanly();
 
 // Unknown / stale id behavior (result-returning functions).
-assert(
-  enginePresent(0) === ZR_ERR_INVALID_ARGUMENT,
-  "enginePresent(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => enginePresent(0),
+  INVALID_ARGUMENT,
+  "enginePresent(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  enginePresent(0x7fff_fffe) === ZR_ERR_INVALID_ARGUMENT,
-  "enginePresent(unknown) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => enginePresent(0x7fff_fffe),
+  INVALID_ARGUMENT,
+  "enginePresent(unknown) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  engineSetConfig(0, { targetFps: 30 }) === ZR_ERR_INVALID_ARGUMENT,
-  "engineSetConfig(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineSetConfig(0, { targetFps: 30 }),
+  INVALID_ARGUMENT,
+  "engineSetConfig(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  enginePostUserEvent(0, 1, new Uint8Array([1])) === ZR_ERR_INVALID_ARGUMENT,
-  "enginePostUserEvent(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => enginePostUserEvent(0, 1, new Uint8Array([1])),
+  INVALID_ARGUMENT,
+  "enginePostUserEvent(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  engineDebugEnable(0, { enabled: true }) === ZR_ERR_INVALID_ARGUMENT,
-  "engineDebugEnable(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineDebugEnable(0, { enabled: true }),
+  INVALID_ARGUMENT,
+  "engineDebugEnable(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  engineDebugDisable(0) === ZR_ERR_INVALID_ARGUMENT,
-  "engineDebugDisable(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineDebugDisable(0),
+  INVALID_ARGUMENT,
+  "engineDebugDisable(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  engineDebugExport(0, new Uint8Array(64)) === ZR_ERR_INVALID_ARGUMENT,
-  "engineDebugExport(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineDebugExport(0, new Uint8Array(64)),
+  INVALID_ARGUMENT,
+  "engineDebugExport(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
-assert(
-  engineDebugReset(0) === ZR_ERR_INVALID_ARGUMENT,
-  "engineDebugReset(0) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineDebugReset(0),
+  INVALID_ARGUMENT,
+  "engineDebugReset(0) must throw ZR_ERR_INVALID_ARGUMENT",
 );
 assertThrows(
   () => engineGetMetrics(0),
-  INVALID_ARG_ERROR_RE,
+  INVALID_ARGUMENT,
   "engineGetMetrics(0) must throw invalid-arg",
 );
 assertThrows(
   () => engineDebugQuery(0, null, new Uint8Array(40)),
-  INVALID_ARG_ERROR_RE,
+  INVALID_ARGUMENT,
   "engineDebugQuery(0) must throw invalid-arg",
 );
 assertThrows(
   () => engineDebugGetStats(0),
-  INVALID_ARG_ERROR_RE,
+  INVALID_ARGUMENT,
   "engineDebugGetStats(0) must throw invalid-arg",
 );
 assertThrows(
   () => engineDebugGetPayload(0, 0n, new Uint8Array(16)),
-  INVALID_ARG_ERROR_RE,
+  INVALID_ARGUMENT,
   "engineDebugGetPayload(0) must throw invalid-arg",
 );
 
-const engineId = engineCreate({});
+// JSON string configs: parse failures and non-objects are rejected before
+// any engine exists; a parsed object goes through the strict checks.
+assertThrows(
+  () => engineCreate("{targetFps: 30"),
+  "ZR_ERR_FORMAT",
+  "engineCreate(invalid JSON) must throw ZR_ERR_FORMAT",
+);
+for (const text of ["null", "[]", "[1, 2]", "42"]) {
+  assertThrows(
+    () => engineCreate(text),
+    /config JSON must be an object/,
+    `engineCreate(${text}) must reject non-object JSON`,
+  );
+}
+assertThrows(
+  () => engineCreate('{"unknownKey": 1}'),
+  /unknown key/i,
+  "engineCreate(JSON) must apply the strict key checks",
+);
 
-assert(typeof engineId === "number", "engineCreate must return a number");
-if (engineId === ZR_ERR_PLATFORM || engineId === ZR_ERR_INVALID_ARGUMENT) {
+let engineId;
+try {
+  engineId = engineCreate({});
+} catch (err) {
+  const code = err?.code ?? String(err);
+  if (code !== "ZR_ERR_PLATFORM" && code !== "ZR_ERR_INVALID_ARGUMENT") throw err;
   process.stdout.write(
-    `native-smoke: SKIP engineCreate() deep checks (engineCreate returned ${engineId}; stdout.isTTY=${String(process.stdout.isTTY)} stdin.isTTY=${String(process.stdin.isTTY)})\n`,
+    `native-smoke: SKIP engineCreate() deep checks (engineCreate threw ${code}; stdout.isTTY=${String(process.stdout.isTTY)} stdin.isTTY=${String(process.stdin.isTTY)})\n`,
   );
   process.exit(0);
 }
+
+assert(typeof engineId === "number", "engineCreate must return a number");
 assert(engineId > 0, `engineCreate must return a non-zero engineId, got: ${engineId}`);
 
-assert(
-  engineSetConfig(engineId, null) === ZR_ERR_INVALID_ARGUMENT,
-  "engineSetConfig(null) must return ZR_ERR_INVALID_ARGUMENT",
+assertThrows(
+  () => engineSetConfig(engineId, null),
+  INVALID_ARGUMENT,
+  "engineSetConfig(null) must throw ZR_ERR_INVALID_ARGUMENT",
 );
 assertThrows(
   () => engineSetConfig(engineId, { plat: 1 }),
@@ -184,15 +219,15 @@ assert(
     enableScrollOptimizations: true,
     waitForOutputDrain: false,
     plat: { enableMouse: true, enableBracketedPaste: true },
-  }) === ZR_OK,
-  "engineSetConfig(valid config) must return ZR_OK",
+  }) === undefined,
+  "engineSetConfig(valid config) must succeed",
 );
 
 const metricsBefore = engineGetMetrics(engineId);
 assertMetricsShape(metricsBefore);
 assert(
-  enginePresent(engineId) === ZR_OK,
-  "enginePresent(owner-thread) must return ZR_OK after successful create",
+  enginePresent(engineId) === undefined,
+  "enginePresent(owner-thread) must succeed after successful create",
 );
 const metricsAfter = engineGetMetrics(engineId);
 assertMetricsShape(metricsAfter);
@@ -204,8 +239,8 @@ assert(
     ringCapacity: 256,
     minSeverity: 0,
     categoryMask: 0xffff_ffff,
-  }) === ZR_OK,
-  "engineDebugEnable(valid config) must return ZR_OK",
+  }) === undefined,
+  "engineDebugEnable(valid config) must succeed",
 );
 assertThrows(
   () => engineDebugQuery(engineId, { bogus: true }, new Uint8Array(40)),
@@ -219,8 +254,8 @@ assertThrows(
 );
 
 assert(
-  enginePostUserEvent(engineId, 0xbeef, new Uint8Array([1, 2, 3, 4])) === ZR_OK,
-  "enginePostUserEvent(owner-thread) must return ZR_OK",
+  enginePostUserEvent(engineId, 0xbeef, new Uint8Array([1, 2, 3, 4])) === undefined,
+  "enginePostUserEvent(owner-thread) must succeed",
 );
 
 const headers = new Uint8Array(40 * 8);
@@ -250,14 +285,11 @@ assertThrows(
   /u64/i,
   "engineDebugGetPayload with out-of-range recordId must throw",
 );
-const payloadBytes = engineDebugGetPayload(engineId, firstRecordId, payloadOut);
-assert(
-  Number.isInteger(payloadBytes),
-  "engineDebugGetPayload must return an integer byte count/result code",
-);
-if (payloadBytes >= 0) {
+if (query.recordsReturned > 0) {
+  const payloadBytes = engineDebugGetPayload(engineId, firstRecordId, payloadOut);
+  assert(Number.isInteger(payloadBytes), "engineDebugGetPayload must return an integer byte count");
   assert(
-    payloadBytes <= payloadOut.byteLength,
+    payloadBytes >= 0 && payloadBytes <= payloadOut.byteLength,
     "engineDebugGetPayload bytes must fit output buffer",
   );
 }
@@ -266,13 +298,14 @@ const debugStats = engineDebugGetStats(engineId);
 assertDebugStatsShape(debugStats);
 
 const exportedBytes = engineDebugExport(engineId, new Uint8Array(64 * 1024));
-assert(Number.isInteger(exportedBytes), "engineDebugExport must return integer bytes/result code");
-if (exportedBytes >= 0) {
-  assert(exportedBytes <= 64 * 1024, "engineDebugExport bytes must fit output buffer");
-}
+assert(Number.isInteger(exportedBytes), "engineDebugExport must return integer bytes");
+assert(
+  exportedBytes >= 0 && exportedBytes <= 64 * 1024,
+  "engineDebugExport bytes must fit output buffer",
+);
 
-assert(engineDebugReset(engineId) === ZR_OK, "engineDebugReset must return ZR_OK");
-assert(engineDebugDisable(engineId) === ZR_OK, "engineDebugDisable must return ZR_OK");
+assert(engineDebugReset(engineId) === undefined, "engineDebugReset must succeed");
+assert(engineDebugDisable(engineId) === undefined, "engineDebugDisable must succeed");
 
 const worker = new Worker(new URL("./smoke-worker.mjs", import.meta.url), {
   workerData: { engineId },
@@ -294,22 +327,26 @@ const alive = await new Promise((resolve, reject) => {
 
 assert(alive.phase === "alive", "worker must send alive phase");
 assert(
-  alive.present === ZR_ERR_INVALID_ARGUMENT,
-  `wrong-thread enginePresent must return ZR_ERR_INVALID_ARGUMENT, got: ${alive.present}`,
+  alive.present === "ZR_ERR_INVALID_ARGUMENT",
+  `wrong-thread enginePresent must throw ZR_ERR_INVALID_ARGUMENT, got: ${alive.present}`,
 );
 assert(
-  alive.postUserEvent === ZR_ERR_INVALID_ARGUMENT,
-  `wrong-thread enginePostUserEvent must return ZR_ERR_INVALID_ARGUMENT, got: ${alive.postUserEvent}`,
+  alive.postUserEvent === "ZR_ERR_INVALID_ARGUMENT",
+  `wrong-thread enginePostUserEvent must throw ZR_ERR_INVALID_ARGUMENT, got: ${alive.postUserEvent}`,
 );
 assert(
-  alive.setConfig === ZR_ERR_INVALID_ARGUMENT,
-  `wrong-thread engineSetConfig must return ZR_ERR_INVALID_ARGUMENT, got: ${alive.setConfig}`,
+  alive.setConfig === "ZR_ERR_INVALID_ARGUMENT",
+  `wrong-thread engineSetConfig must throw ZR_ERR_INVALID_ARGUMENT, got: ${alive.setConfig}`,
 );
 assert(
-  alive.debugDisable === ZR_ERR_INVALID_ARGUMENT,
-  `wrong-thread engineDebugDisable must return ZR_ERR_INVALID_ARGUMENT, got: ${alive.debugDisable}`,
+  alive.debugDisable === "ZR_ERR_INVALID_ARGUMENT",
+  `wrong-thread engineDebugDisable must throw ZR_ERR_INVALID_ARGUMENT, got: ${alive.debugDisable}`,
 );
 
+const jsonEngineId = engineCreate('{"targetFps": 30, "limits": {"dlMaxCmds": 1000}}');
+assert(jsonEngineId > 0, `engineCreate(valid JSON) must return an engineId, got: ${jsonEngineId}`);
+engineDestroy(jsonEngineId);
+
 engineDestroy(engineId);
 engineDestroy(engineId); // idempotent
 
@@ -330,8 +367,8 @@ const destroyed = await new Promise((resolve, reject) => {
 
 assert(destroyed.phase === "destroyed", "worker must send destroyed phase");
 assert(
-  destroyed.postUserEvent === ZR_ERR_INVALID_ARGUMENT,
-  `postUserEvent after destroy must return ZR_ERR_INVALID_ARGUMENT, got: ${destroyed.postUserEvent}`,
+  destroyed.postUserEvent === "ZR_ERR_INVALID_ARGUMENT",
+  `postUserEvent after destroy must throw ZR_ERR_INVALID_ARGUMENT, got: ${destroyed.postUserEvent}`,
 );
 
 await worker.terminate();
@@ -0,0 +1,277 @@
+use crate::error::{napi_error, zr_result_to_napi, ZrCode};
+use crate::ffi;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction};
+use napi_derive::napi;
+
+/// Code an engine call throws in assert mode `"error"` when an engine
+/// invariant failed during that call. Binding-owned; never produced by the
+/// engine.
+#[napi]
+pub const ASSERT_FAILED: i32 = -101;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AssertMode {
+    /// Vendored behavior: restore the terminal and abort the process.
+    Abort,
+    /// Report to the listener and carry on.
+    Log,
+    /// Like `Log`, and fail the in-flight engine call with `ASSERT_FAILED`.
+    Error,
+}
+
+impl AssertMode {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "abort" => Some(Self::Abort),
+            "log" => Some(Self::Log),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Receives each engine log line (assertion failures included) in the
+/// `"log"` and `"error"` modes.
+pub(crate) type AssertListener = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Failures kept for `takeAssertFailures`; older ones are dropped. Also the
+/// queue size of the `setAssertMode` callback.
+pub(crate) const ASSERT_FAILURES_MAX: usize = 64;
+
+/// `zr_assert.c` replaced by a reporting implementation; only built with the
+/// `assert-log` feature, which also drops the vendored file from the build.
+#[cfg(feature = "assert-log")]
+mod imp {
+    use super::{AssertListener, AssertMode, ASSERT_FAILED, ASSERT_FAILURES_MAX};
+    use crate::ffi;
+    use std::cell::Cell;
+    use std::ffi::{c_char, c_int, c_void, CStr};
+    use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+    use std::sync::{Mutex, MutexGuard, Once};
+
+    static MODE: AtomicU8 = AtomicU8::new(0);
+    static CLEANUP_HOOK: AtomicUsize = AtomicUsize::new(0);
+    static FAIL_GUARD: AtomicBool = AtomicBool::new(false);
+    static SINK_INSTALLED: Once = Once::new();
+    static FAILURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static LISTENER: Mutex<Option<AssertListener>> = Mutex::new(None);
+
+    thread_local! {
+        /// Set when an assert fails on this thread during an engine call.
+        static IN_FLIGHT: Cell<bool> = const { Cell::new(false) };
+    }
+
+    type CleanupHook = extern "C" fn();
+
+    fn mode() -> AssertMode {
+        match MODE.load(Ordering::Acquire) {
+            1 => AssertMode::Log,
+            2 => AssertMode::Error,
+            _ => AssertMode::Abort,
+        }
+    }
+
+    fn lock<T>(m: &'static Mutex<T>) -> MutexGuard<'static, T> {
+        match m.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// `assertion failed: <expr> at <file>:<line>`, with the file's
+    /// directories stripped.
+    fn format_assert_failure(file: &str, line: i32, expr: &str) -> String {
+        let base = file.rsplit(['/', '\\']).next().unwrap_or(file);
+        format!("assertion failed: {expr} at {base}:{line}")
+    }
+
+    extern "C" fn log_sink(_user: *mut c_void, msg: ffi::zr_string_view_t) {
+        let text = if msg.ptr.is_null() {
+            String::new()
+        } else {
+            let bytes = unsafe { std::slice::from_raw_parts(msg.ptr.cast::<u8>(), msg.len) };
+            String::from_utf8_lossy(bytes).into_owned()
+        };
+        if let Some(listener) = lock(&LISTENER).as_ref() {
+            listener(&text);
+        }
+    }
+
+    pub(crate) fn set_mode(mode: AssertMode, listener: Option<AssertListener>) -> i32 {
+        *lock(&LISTENER) = listener;
+        if mode != AssertMode::Abort {
+            SINK_INSTALLED.call_once(|| unsafe {
+                ffi::zr_log_set_sink(Some(log_sink), std::ptr::null_mut());
+            });
+        }
+        let raw = match mode {
+            AssertMode::Abort => 0,
+            AssertMode::Log => 1,
+            AssertMode::Error => 2,
+        };
+        MODE.store(raw, Ordering::Release);
+        ffi::ZR_OK
+    }
+
+    pub(crate) fn take_failures() -> Vec<String> {
+        std::mem::take(&mut *lock(&FAILURES))
+    }
+
+    pub(crate) fn begin_call() {
+        IN_FLIGHT.with(|f| f.set(false));
+    }
+
+    pub(crate) fn finish_call(rc: i32) -> i32 {
+        let failed = IN_FLIGHT.with(|f| f.replace(false));
+        if failed && mode() == AssertMode::Error {
+            ASSERT_FAILED
+        } else {
+            rc
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn zr_assert_set_cleanup_hook(hook: Option<CleanupHook>) {
+        CLEANUP_HOOK.store(hook.map_or(0, |f| f as usize), Ordering::Release);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn zr_assert_clear_cleanup_hook(hook: Option<CleanupHook>) {
+        if let Some(f) = hook {
+            let _ =
+                CLEANUP_HOOK.compare_exchange(f as usize, 0, Ordering::AcqRel, Ordering::Acquire);
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn zr_assert_invoke_cleanup_hook_for_test() {
+        let raw = CLEANUP_HOOK.load(Ordering::Acquire);
+        if raw != 0 {
+            // SAFETY: only ever stored from a valid `CleanupHook` above.
+            let hook: CleanupHook = unsafe { std::mem::transmute::<usize, CleanupHook>(raw) };
+            hook();
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn zr_assert_fail(file: *const c_char, line: c_int, expr: *const c_char) {
+        if mode() == AssertMode::Abort {
+            if !FAIL_GUARD.swap(true, Ordering::AcqRel) {
+                zr_assert_invoke_cleanup_hook_for_test();
+            }
+            std::process::abort();
+        }
+        let text = |p: *const c_char| {
+            if p.is_null() {
+                String::from("?")
+            } else {
+                unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()
+            }
+        };
+        let msg = format_assert_failure(&text(file), line, &text(expr));
+        unsafe {
+            ffi::zr_log_write(ffi::zr_string_view_t {
+                ptr: msg.as_ptr().cast(),
+                len: msg.len(),
+            });
+        }
+        let mut failures = lock(&FAILURES);
+        if failures.len() == ASSERT_FAILURES_MAX {
+            failures.remove(0);
+        }
+        failures.push(msg);
+        IN_FLIGHT.with(|f| f.set(true));
+    }
+}
+
+#[cfg(not(feature = "assert-log"))]
+mod imp {
+    use super::{AssertListener, AssertMode};
+    use crate::ffi;
+
+    pub(crate) fn set_mode(mode: AssertMode, _listener: Option<AssertListener>) -> i32 {
+        match mode {
+            AssertMode::Abort => ffi::ZR_OK,
+            _ => ffi::ZR_ERR_UNSUPPORTED,
+        }
+    }
+
+    pub(crate) fn take_failures() -> Vec<String> {
+        Vec::new()
+    }
+
+    pub(crate) fn begin_call() {}
+
+    pub(crate) fn finish_call(rc: i32) -> i32 {
+        rc
+    }
+}
+
+/// Brackets one engine call so an assert that fails inside it can fail the
+/// call in `"error"` mode. Owner-thread only, like the call itself.
+pub(crate) struct AssertScope(());
+
+impl AssertScope {
+    pub(crate) fn begin() -> Self {
+        imp::begin_call();
+        Self(())
+    }
+
+    pub(crate) fn finish(self, rc: i32) -> i32 {
+        imp::finish_call(rc)
+    }
+}
+
+pub(crate) fn apply_assert_mode(
+    mode: &str,
+    listener: Option<AssertListener>,
+) -> napi::Result<(), ZrCode> {
+    let rc = match AssertMode::parse(mode) {
+        Some(mode) => imp::set_mode(mode, listener),
+        None => ffi::ZR_ERR_INVALID_ARGUMENT,
+    };
+    zr_result_to_napi(rc, "setAssertMode")
+}
+
+/// Choose what an engine invariant failure does: `"abort"` (default; restore
+/// the terminal and abort), `"log"` (record `assertion failed: <expr> at
+/// <file>:<line>`, pass it to `onFailure` and continue) or `"error"` (log,
+/// and make the in-flight engine call throw `ASSERT_FAILED`). `onFailure`
+/// runs on the JS thread after the failing call, and also receives other
+/// engine log lines such as watchdog overruns; it replaces the previous
+/// callback, and omitting it removes that. Only `"abort"` is available unless
+/// the addon was built with the `assert-log` feature; other modes then throw
+/// `ZR_ERR_UNSUPPORTED`.
+#[napi(js_name = "setAssertMode")]
+pub fn set_assert_mode(
+    env: Env,
+    mode: String,
+    on_failure: Option<JsFunction>,
+) -> napi::Result<(), ZrCode> {
+    let listener = match on_failure {
+        Some(callback) => {
+            let mut tsfn: ThreadsafeFunction<String, ErrorStrategy::Fatal> = callback
+                .create_threadsafe_function(
+                    ASSERT_FAILURES_MAX,
+                    |ctx: ThreadSafeCallContext<String>| Ok(vec![ctx.value]),
+                )
+                .map_err(napi_error)?;
+            tsfn.unref(&env).map_err(napi_error)?;
+            let listener: AssertListener = Box::new(move |msg: &str| {
+                tsfn.call(msg.to_owned(), ThreadsafeFunctionCallMode::NonBlocking);
+            });
+            Some(listener)
+        }
+        None => None,
+    };
+    apply_assert_mode(&mode, listener)
+}
+
+/// Drain the assertion failures reported since the last call, oldest first.
+#[napi(js_name = "takeAssertFailures")]
+pub fn take_assert_failures() -> Vec<String> {
+    imp::take_failures()
+}
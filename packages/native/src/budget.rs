@@ -0,0 +1,79 @@
+use crate::drawlist::drawlist_counts;
+use crate::error::{zr_result_to_napi, ZrCode};
+use crate::{ffi, owner_engine_guard, submit_slot};
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+
+/// Drawlist limits an engine enforces on `engineSubmitDrawlist`.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct DrawlistBudget {
+    /// Total drawlist size in bytes.
+    pub maxTotalBytes: u32,
+    pub maxCmds: u32,
+    pub maxStrings: u32,
+    pub maxBlobs: u32,
+    /// Nesting depth of `PUSH_CLIP`.
+    pub maxClipDepth: u32,
+    /// Segments in one text-run blob.
+    pub maxTextRunSegments: u32,
+}
+
+pub(crate) fn drawlist_budget(limits: &ffi::zr_limits_t) -> DrawlistBudget {
+    DrawlistBudget {
+        maxTotalBytes: limits.dl_max_total_bytes,
+        maxCmds: limits.dl_max_cmds,
+        maxStrings: limits.dl_max_strings,
+        maxBlobs: limits.dl_max_blobs,
+        maxClipDepth: limits.dl_max_clip_depth,
+        maxTextRunSegments: limits.dl_max_text_run_segments,
+    }
+}
+
+/// Report the drawlist limits the engine runs with now: the `engineCreate`
+/// limits, or those of the last accepted `engineSetConfig`. A drawlist whose
+/// header counts exceed any of them is rejected with `ZR_ERR_LIMIT`.
+#[napi(js_name = "engineDrawlistBudget")]
+pub fn engine_drawlist_budget(engine_id: u32) -> napi::Result<DrawlistBudget, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDrawlistBudget")?;
+    Ok(drawlist_budget(&guard.slot.runtime_cfg().limits))
+}
+
+/// What an accepted drawlist used of each `DrawlistBudget` limit.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct DrawlistUsage {
+    pub totalBytes: u32,
+    pub cmds: u32,
+    /// Strings this drawlist defined that are still live at its end.
+    pub strings: u32,
+    pub blobs: u32,
+    /// Deepest `PUSH_CLIP` nesting reached.
+    pub clipDepth: u32,
+    /// Largest text run drawn from a blob defined in this drawlist.
+    pub textRunSegments: u32,
+}
+
+/// `engineSubmitDrawlist` that, on success, reports how close the drawlist
+/// came to each `engineDrawlistBudget` limit, so callers can scale back
+/// before a frame is rejected with `ZR_ERR_LIMIT`. `strings`/`blobs` count
+/// only this drawlist's definitions, not ones kept from earlier submits.
+#[napi(js_name = "engineSubmitDrawlistChecked")]
+pub fn engine_submit_drawlist_checked(
+    engine_id: u32,
+    drawlist: Uint8Array,
+) -> napi::Result<DrawlistUsage, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSubmitDrawlistChecked")?;
+    let rc = submit_slot(&guard.slot, &drawlist, "engineSubmitDrawlistChecked");
+    zr_result_to_napi(rc, "engineSubmitDrawlistChecked")?;
+
+    let counts = drawlist_counts(&drawlist);
+    Ok(DrawlistUsage {
+        totalBytes: counts.total_bytes,
+        cmds: counts.cmds,
+        strings: counts.strings,
+        blobs: counts.blobs,
+        clipDepth: counts.max_clip_depth,
+        textRunSegments: counts.max_text_run_segments,
+    })
+}
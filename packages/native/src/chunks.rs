@@ -0,0 +1,160 @@
+use crate::{empty_terminal_caps, ffi};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// How long a chunk waits for the terminal to drain before writing anyway.
+pub(crate) const CHUNK_WAIT_MS: i32 = 50;
+
+/// How a present splits its output, set for the duration of the present.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ChunkPlan {
+    /// Largest single write, in bytes (non-zero).
+    pub(crate) chunk_bytes: u32,
+    /// Whether the platform can report output writability.
+    pub(crate) wait_writable: bool,
+}
+
+/// Chunked writes of one present.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ChunkCounts {
+    /// Writes issued.
+    pub(crate) chunks: u32,
+    /// Chunks after which the terminal was not yet writable.
+    pub(crate) stalls: u32,
+}
+
+thread_local! {
+    static ACTIVE: Cell<Option<ChunkPlan>> = const { Cell::new(None) };
+    static COUNTS: Cell<ChunkCounts> = const {
+        Cell::new(ChunkCounts { chunks: 0, stalls: 0 })
+    };
+}
+
+/// Write `bytes` in chunks of at most `plan.chunk_bytes`. Between chunks the
+/// terminal is probed with `wait(0)`; when it is not writable the stall is
+/// counted and `wait(CHUNK_WAIT_MS)` gives it time to drain. The next chunk
+/// is written either way, since dropping output would corrupt the screen.
+/// Without writability support the thread just yields between chunks.
+pub(crate) fn write_chunks(
+    bytes: &[u8],
+    plan: ChunkPlan,
+    counts: &mut ChunkCounts,
+    mut write: impl FnMut(&[u8]) -> i32,
+    mut wait: impl FnMut(i32) -> i32,
+) -> i32 {
+    for (i, chunk) in bytes.chunks(plan.chunk_bytes as usize).enumerate() {
+        if i > 0 {
+            if !plan.wait_writable {
+                std::thread::yield_now();
+            } else if wait(0) == ffi::ZR_ERR_LIMIT {
+                counts.stalls = counts.stalls.saturating_add(1);
+                wait(CHUNK_WAIT_MS);
+            }
+        }
+        counts.chunks = counts.chunks.saturating_add(1);
+        let rc = write(chunk);
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+    }
+    ffi::ZR_OK
+}
+
+/// Per-engine write chunking from the `writeChunkBytes` create option, with
+/// the counts `engineGetMetrics` reports.
+#[derive(Default)]
+pub(crate) struct WriteChunking {
+    chunk_bytes: u32,
+    chunks_last_frame: AtomicU32,
+    stalls_last_frame: AtomicU32,
+    chunks_total: AtomicU64,
+    stalls_total: AtomicU64,
+}
+
+impl WriteChunking {
+    pub(crate) fn new(chunk_bytes: u32) -> Self {
+        Self {
+            chunk_bytes,
+            ..Self::default()
+        }
+    }
+
+    /// Run `present` with its terminal writes split into chunks. Off when
+    /// `writeChunkBytes` is 0; the counts then stay at 0.
+    pub(crate) fn apply(
+        &self,
+        engine: *mut ffi::zr_engine_t,
+        present: impl FnOnce() -> i32,
+    ) -> i32 {
+        if self.chunk_bytes == 0 {
+            return present();
+        }
+        let mut caps = empty_terminal_caps();
+        let wait_writable = unsafe { ffi::engine_get_caps(engine, &mut caps as *mut _) }
+            == ffi::ZR_OK
+            && caps.supports_output_wait_writable != 0;
+        ACTIVE.with(|a| {
+            a.set(Some(ChunkPlan {
+                chunk_bytes: self.chunk_bytes,
+                wait_writable,
+            }))
+        });
+        COUNTS.with(|c| c.set(ChunkCounts::default()));
+        let rc = present();
+        ACTIVE.with(|a| a.set(None));
+        let counts = COUNTS.with(Cell::take);
+        self.chunks_last_frame
+            .store(counts.chunks, Ordering::Relaxed);
+        self.stalls_last_frame
+            .store(counts.stalls, Ordering::Relaxed);
+        self.chunks_total
+            .fetch_add(u64::from(counts.chunks), Ordering::Relaxed);
+        self.stalls_total
+            .fetch_add(u64::from(counts.stalls), Ordering::Relaxed);
+        rc
+    }
+
+    /// Counts of the last present.
+    pub(crate) fn last_frame(&self) -> ChunkCounts {
+        ChunkCounts {
+            chunks: self.chunks_last_frame.load(Ordering::Relaxed),
+            stalls: self.stalls_last_frame.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Chunks and stalls since the engine was created or its metrics were
+    /// last reset.
+    pub(crate) fn totals(&self) -> (u64, u64) {
+        (
+            self.chunks_total.load(Ordering::Relaxed),
+            self.stalls_total.load(Ordering::Relaxed),
+        )
+    }
+
+    pub(crate) fn reset_totals(&self) {
+        self.chunks_total.store(0, Ordering::Relaxed);
+        self.stalls_total.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Write one flush of terminal output, chunked when a present set a plan
+/// (see the `plat_write_output` hook in spans.rs).
+pub(crate) fn write_output(
+    plat: *mut std::ffi::c_void,
+    bytes: &[u8],
+    mut write: impl FnMut(&[u8]) -> i32,
+) -> i32 {
+    unsafe extern "C" {
+        fn plat_wait_output_writable(plat: *mut std::ffi::c_void, timeout_ms: i32) -> i32;
+    }
+
+    let Some(plan) = ACTIVE.with(Cell::get) else {
+        return write(bytes);
+    };
+    let mut counts = COUNTS.with(Cell::get);
+    let rc = write_chunks(bytes, plan, &mut counts, &mut write, |timeout_ms| unsafe {
+        plat_wait_output_writable(plat, timeout_ms)
+    });
+    COUNTS.with(|c| c.set(counts));
+    rc
+}
@@ -1,10 +1,16 @@
+use crate::drawlist::AttrFallback;
+use crate::error::{invalid_argument, napi_error, zr_error, ZrCode};
 use crate::ffi;
-use napi::bindgen_prelude::{Error, Status, ValueType};
-use napi::{JsObject, JsUnknown};
+use crate::metrics::FRAME_STATS_WINDOW_MAX;
+use crate::output::TerminalTarget;
+use crate::style::parse_packed_style;
+use napi::bindgen_prelude::ValueType;
+use napi::{Env, JsFunction, JsObject, JsUnknown};
+use napi_derive::napi;
 
 pub(crate) type ParseResult<T> = std::result::Result<T, ()>;
 
-const LIMITS_KEYS: &[(&str, &str)] = &[
+pub(crate) const LIMITS_KEYS: &[(&str, &str)] = &[
     ("arenaMaxTotalBytes", "arena_max_total_bytes"),
     ("arenaInitialBytes", "arena_initial_bytes"),
     ("outMaxBytesPerFrame", "out_max_bytes_per_frame"),
@@ -47,6 +53,17 @@ const CREATE_CFG_KEYS: &[(&str, &str)] = &[
     ("capForceFlags", "cap_force_flags"),
     ("capSuppressFlags", "cap_suppress_flags"),
     ("inlineRows", "inline_rows"),
+    ("enableEraseOptimizations", "enable_erase_optimizations"),
+    ("maxCallMs", "max_call_ms"),
+    ("unsupportedAttrFallback", "unsupported_attr_fallback"),
+    ("idleMs", "idle_ms"),
+    ("writeChunkBytes", "write_chunk_bytes"),
+    ("frameStatsWindow", "frame_stats_window"),
+    ("trackFrame", "track_frame"),
+    ("unknownSequences", "unknown_sequences"),
+    ("inputFd", "input_fd"),
+    ("outputFd", "output_fd"),
+    ("initialStyle", "initial_style"),
 ];
 
 const RUNTIME_CFG_KEYS: &[(&str, &str)] = &[
@@ -62,29 +79,277 @@ const RUNTIME_CFG_KEYS: &[(&str, &str)] = &[
     ("capForceFlags", "cap_force_flags"),
     ("capSuppressFlags", "cap_suppress_flags"),
     ("inlineRows", "inline_rows"),
+    ("enableEraseOptimizations", "enable_erase_optimizations"),
 ];
 
+/// `plat.requestedColorMode` values (`plat_color_mode_t`).
+#[napi(js_name = "COLOR_MODE")]
+#[allow(clippy::upper_case_acronyms)]
+pub enum ColorMode {
+    UNKNOWN = 0,
+    ANSI16 = 1,
+    ANSI256 = 2,
+    RGB = 3,
+}
+
+/// `widthPolicy` values: how emoji graphemes are measured.
+#[napi(js_name = "WIDTH_POLICY")]
+#[allow(non_camel_case_types)]
+pub enum WidthPolicy {
+    EMOJI_NARROW = 0,
+    EMOJI_WIDE = 1,
+}
+
+const _: () = {
+    assert!(ColorMode::UNKNOWN as u8 == ffi::PLAT_COLOR_MODE_UNKNOWN);
+    assert!(ColorMode::ANSI16 as u8 == ffi::PLAT_COLOR_MODE_16);
+    assert!(ColorMode::ANSI256 as u8 == ffi::PLAT_COLOR_MODE_256);
+    assert!(ColorMode::RGB as u8 == ffi::PLAT_COLOR_MODE_RGB);
+    assert!(WidthPolicy::EMOJI_NARROW as u32 == ffi::ZR_WIDTH_EMOJI_NARROW);
+    assert!(WidthPolicy::EMOJI_WIDE as u32 == ffi::ZR_WIDTH_EMOJI_WIDE);
+};
+
+/// Reject anything but `WIDTH_POLICY.EMOJI_NARROW` (0) and
+/// `WIDTH_POLICY.EMOJI_WIDE` (1).
+pub(crate) fn check_width_policy(policy: u32, ctx: &str) -> napi::Result<(), ZrCode> {
+    if policy != ffi::ZR_WIDTH_EMOJI_NARROW && policy != ffi::ZR_WIDTH_EMOJI_WIDE {
+        return Err(invalid_argument(format!(
+            "{ctx}: widthPolicy must be 0 (emoji narrow) or 1 (emoji wide), got {policy}"
+        )));
+    }
+    Ok(())
+}
+
+/// Reject a `requestedColorMode` outside `COLOR_MODE` (0..=3).
+pub(crate) fn check_color_mode(mode: u32, ctx: &str) -> napi::Result<(), ZrCode> {
+    if mode > u32::from(ffi::PLAT_COLOR_MODE_RGB) {
+        return Err(invalid_argument(format!(
+            "{ctx}: plat.requestedColorMode must be 0 (unknown), 1 (ansi16), 2 (ansi256) or 3 (rgb), got {mode}"
+        )));
+    }
+    Ok(())
+}
+
+/// Range-check the enum-valued keys before `apply_*` narrows them, so an
+/// out-of-range value is named instead of reported as a generic bad value.
+fn check_enum_keys(obj: &JsObject, plat: Option<&JsObject>, ctx: &str) -> napi::Result<(), ZrCode> {
+    if let Ok(Some(policy)) = js_u32(obj, "widthPolicy", "width_policy") {
+        check_width_policy(policy, ctx)?;
+    }
+    if let Some(Ok(Some(mode))) =
+        plat.map(|plat| js_u32(plat, "requestedColorMode", "requested_color_mode"))
+    {
+        check_color_mode(mode, ctx)?;
+    }
+    Ok(())
+}
+
+/// Create-time options consumed by the binding itself (never forwarded to C).
+#[derive(Default)]
+pub(crate) struct BindingCreateOptions {
+    /// Watchdog budget per engine call in milliseconds (0 = disabled).
+    pub(crate) max_call_ms: u32,
+    /// Substitute for SGR attributes the terminal cannot render.
+    pub(crate) attr_fallback: AttrFallback,
+    /// Undamaged time before presents pause (0 = never idle).
+    pub(crate) idle_ms: u32,
+    /// Largest single terminal write of a present (0 = unchunked).
+    pub(crate) write_chunk_bytes: u32,
+    /// Inline screen mode, taken from the engine config.
+    pub(crate) inline_screen: bool,
+    /// Frames in the `engineGetFrameStats` window (0 = default window).
+    pub(crate) frame_stats_window: u32,
+    /// Replay every drawlist into a frame copy for export and saved states.
+    pub(crate) track_frame: bool,
+    /// Split unrecognized input sequences out of the engine's parser.
+    pub(crate) unknown_sequences: bool,
+    /// SGR state the terminal is in before the first present.
+    pub(crate) initial_style: Option<ffi::zr_style_t>,
+    /// Descriptors the platform layer binds instead of stdin/stdout.
+    pub(crate) input_fd: Option<i32>,
+    pub(crate) output_fd: Option<i32>,
+}
+
+impl BindingCreateOptions {
+    /// `engine_create_io` binding: the chosen fds, -1 for the process stdio.
+    pub(crate) fn engine_io(&self) -> ffi::zr_engine_io_t {
+        ffi::zr_engine_io_t {
+            input_fd: self.input_fd.unwrap_or(-1),
+            output_fd: self.output_fd.unwrap_or(-1),
+        }
+    }
+
+    /// Stream the binding writes to: the bound output fd (stdout when only
+    /// `inputFd` is bound), else the process terminal.
+    pub(crate) fn terminal_target(&self) -> TerminalTarget {
+        if self.input_fd.is_some() || self.output_fd.is_some() {
+            TerminalTarget::bound(self.output_fd)
+        } else {
+            TerminalTarget::Process
+        }
+    }
+}
+
+pub(crate) fn parse_binding_create_opts(
+    obj: &JsObject,
+) -> napi::Result<BindingCreateOptions, ZrCode> {
+    let mut opts = BindingCreateOptions::default();
+    if let Some(v) = js_u32(obj, "maxCallMs", "max_call_ms")
+        .map_err(|_| invalid_argument("engineCreate: maxCallMs must be a non-negative integer"))?
+    {
+        opts.max_call_ms = v;
+    }
+    if let Some(v) = js_u32(obj, "idleMs", "idle_ms")
+        .map_err(|_| invalid_argument("engineCreate: idleMs must be a non-negative integer"))?
+    {
+        opts.idle_ms = v;
+    }
+    if let Some(v) = js_u32(obj, "writeChunkBytes", "write_chunk_bytes").map_err(|_| {
+        invalid_argument("engineCreate: writeChunkBytes must be a non-negative integer")
+    })? {
+        opts.write_chunk_bytes = v;
+    }
+    let window = js_u32(obj, "frameStatsWindow", "frame_stats_window")
+        .and_then(|v| match v {
+            Some(0) => Err(()),
+            Some(v) if v > FRAME_STATS_WINDOW_MAX => Err(()),
+            v => Ok(v),
+        })
+        .map_err(|_| {
+            invalid_argument(format!(
+                "engineCreate: frameStatsWindow must be an integer from 1 to {FRAME_STATS_WINDOW_MAX}"
+            ))
+        })?;
+    if let Some(v) = window {
+        opts.frame_stats_window = v;
+    }
+    if let Some(v) = js_u8_bool(obj, "trackFrame", "track_frame")
+        .map_err(|_| invalid_argument("engineCreate: trackFrame must be a boolean"))?
+    {
+        opts.track_frame = v != 0;
+    }
+    if let Some(v) = js_u8_bool(obj, "unknownSequences", "unknown_sequences")
+        .map_err(|_| invalid_argument("engineCreate: unknownSequences must be a boolean"))?
+    {
+        opts.unknown_sequences = v != 0;
+    }
+    let fallback = js_str(obj, "unsupportedAttrFallback", "unsupported_attr_fallback")
+        .and_then(|v| {
+            v.map(|name| AttrFallback::parse(&name).ok_or(()))
+                .transpose()
+        })
+        .map_err(|_| {
+            invalid_argument(
+                "engineCreate: unsupportedAttrFallback must be \"drop\", \"underline\" or \"dim\"",
+            )
+        })?;
+    if let Some(policy) = fallback {
+        opts.attr_fallback = policy;
+    }
+    for (primary, alias, access) in [
+        ("inputFd", "input_fd", FdAccess::Read),
+        ("outputFd", "output_fd", FdAccess::Write),
+    ] {
+        let fd = js_u32(obj, primary, alias).map_err(|_| {
+            invalid_argument(format!(
+                "engineCreate: {primary} must be a non-negative integer"
+            ))
+        })?;
+        let Some(fd) = fd else {
+            continue;
+        };
+        let fd = check_platform_fd(primary, fd, access)?;
+        match access {
+            FdAccess::Read => opts.input_fd = Some(fd),
+            FdAccess::Write => opts.output_fd = Some(fd),
+        }
+    }
+    if let Some(style) = js_obj(obj, "initialStyle", "initial_style")
+        .map_err(|_| invalid_argument("engineCreate: initialStyle must be an object"))?
+    {
+        opts.initial_style = Some(parse_packed_style(&style, "engineCreate initialStyle")?);
+    }
+    Ok(opts)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum FdAccess {
+    Read,
+    Write,
+}
+
+/// Whether `fd` is open (`None` when it is not) and allows `access`.
+#[cfg(unix)]
+pub(crate) fn fd_allows(fd: i32, access: FdAccess) -> Option<bool> {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 {
+        return None;
+    }
+    let mode = flags & libc::O_ACCMODE;
+    Some(match access {
+        FdAccess::Read => mode == libc::O_RDONLY || mode == libc::O_RDWR,
+        FdAccess::Write => mode == libc::O_WRONLY || mode == libc::O_RDWR,
+    })
+}
+
+/// Check an `inputFd`/`outputFd` before the platform layer binds it, so a
+/// bad descriptor fails with its key named rather than a bare engine code.
+#[cfg(unix)]
+fn check_platform_fd(key: &str, fd: u32, access: FdAccess) -> napi::Result<i32, ZrCode> {
+    let Ok(fd) = i32::try_from(fd) else {
+        return Err(invalid_argument(format!(
+            "engineCreate: {key} {fd} is not an open file descriptor"
+        )));
+    };
+    match fd_allows(fd, access) {
+        None => Err(invalid_argument(format!(
+            "engineCreate: {key} {fd} is not an open file descriptor"
+        ))),
+        Some(false) => {
+            let mode = if access == FdAccess::Read {
+                "reading"
+            } else {
+                "writing"
+            };
+            Err(invalid_argument(format!(
+                "engineCreate: {key} {fd} is not open for {mode}"
+            )))
+        }
+        Some(true) => Ok(fd),
+    }
+}
+
+#[cfg(not(unix))]
+fn check_platform_fd(key: &str, _fd: u32, _access: FdAccess) -> napi::Result<i32, ZrCode> {
+    Err(zr_error(
+        ffi::ZR_ERR_UNSUPPORTED,
+        format!("engineCreate: {key} needs a POSIX platform"),
+    ))
+}
+
 pub(crate) fn validate_known_keys(
     obj: &JsObject,
     allowed: &[(&str, &str)],
     ctx: &str,
-) -> napi::Result<()> {
-    let names = obj.get_property_names()?;
-    let len = names.get_array_length()?;
+) -> napi::Result<(), ZrCode> {
+    let names = obj.get_property_names().map_err(napi_error)?;
+    let len = names.get_array_length().map_err(napi_error)?;
 
     'outer: for i in 0..len {
-        let unk = names.get_element::<JsUnknown>(i)?;
-        let s = unk.coerce_to_string()?;
-        let k = s.into_utf8()?.as_str()?.to_owned();
+        let unk = names.get_element::<JsUnknown>(i).map_err(napi_error)?;
+        let s = unk.coerce_to_string().map_err(napi_error)?;
+        let k = s
+            .into_utf8()
+            .map_err(napi_error)?
+            .as_str()
+            .map_err(napi_error)?
+            .to_owned();
         for (primary, alias) in allowed {
             if k == *primary || k == *alias {
                 continue 'outer;
             }
         }
-        return Err(Error::new(
-            Status::InvalidArg,
-            format!("{ctx}: unknown key: {k}"),
-        ));
+        return Err(invalid_argument(format!("{ctx}: unknown key: {k}")));
     }
     Ok(())
 }
@@ -92,49 +357,78 @@ pub(crate) fn validate_known_keys(
 pub(crate) fn apply_create_cfg_strict(
     dst: &mut ffi::zr_engine_config_t,
     obj: &JsObject,
-) -> napi::Result<()> {
+) -> napi::Result<(), ZrCode> {
     validate_known_keys(obj, CREATE_CFG_KEYS, "engineCreate config")?;
     if let Some(lim) = js_obj(obj, "limits", "limits")
-        .map_err(|_| Error::new(Status::InvalidArg, "engineCreate: limits must be an object"))?
+        .map_err(|_| invalid_argument("engineCreate: limits must be an object"))?
     {
         validate_known_keys(&lim, LIMITS_KEYS, "engineCreate config.limits")?;
     }
-    if let Some(plat) = js_obj(obj, "plat", "plat")
-        .map_err(|_| Error::new(Status::InvalidArg, "engineCreate: plat must be an object"))?
-    {
-        validate_known_keys(&plat, PLAT_KEYS, "engineCreate config.plat")?;
+    let plat = js_obj(obj, "plat", "plat")
+        .map_err(|_| invalid_argument("engineCreate: plat must be an object"))?;
+    if let Some(plat) = &plat {
+        validate_known_keys(plat, PLAT_KEYS, "engineCreate config.plat")?;
     }
+    check_enum_keys(obj, plat.as_ref(), "engineCreate")?;
 
     apply_create_cfg(dst, obj)
-        .map_err(|_| Error::new(Status::InvalidArg, "engineCreate: invalid config value"))?;
-    Ok(())
+        .map_err(|_| invalid_argument("engineCreate: invalid config value"))?;
+    check_limits(&dst.limits, "engineCreate")
 }
 
 pub(crate) fn apply_runtime_cfg_strict(
     dst: &mut ffi::zr_engine_runtime_config_t,
     obj: &JsObject,
-) -> napi::Result<()> {
+) -> napi::Result<(), ZrCode> {
     validate_known_keys(obj, RUNTIME_CFG_KEYS, "engineSetConfig config")?;
-    if let Some(lim) = js_obj(obj, "limits", "limits").map_err(|_| {
-        Error::new(
-            Status::InvalidArg,
-            "engineSetConfig: limits must be an object",
-        )
-    })? {
+    if let Some(lim) = js_obj(obj, "limits", "limits")
+        .map_err(|_| invalid_argument("engineSetConfig: limits must be an object"))?
+    {
         validate_known_keys(&lim, LIMITS_KEYS, "engineSetConfig config.limits")?;
     }
-    if let Some(plat) = js_obj(obj, "plat", "plat").map_err(|_| {
-        Error::new(
-            Status::InvalidArg,
-            "engineSetConfig: plat must be an object",
-        )
-    })? {
-        validate_known_keys(&plat, PLAT_KEYS, "engineSetConfig config.plat")?;
+    let plat = js_obj(obj, "plat", "plat")
+        .map_err(|_| invalid_argument("engineSetConfig: plat must be an object"))?;
+    if let Some(plat) = &plat {
+        validate_known_keys(plat, PLAT_KEYS, "engineSetConfig config.plat")?;
     }
+    check_enum_keys(obj, plat.as_ref(), "engineSetConfig")?;
 
     apply_runtime_cfg(dst, obj)
-        .map_err(|_| Error::new(Status::InvalidArg, "engineSetConfig: invalid config value"))?;
-    Ok(())
+        .map_err(|_| invalid_argument("engineSetConfig: invalid config value"))?;
+    check_limits(&dst.limits, "engineSetConfig")
+}
+
+/// Parse `text` with `JSON.parse` into a config object, which then goes
+/// through the same strict checks as one passed in directly.
+pub(crate) fn parse_json_config(
+    env: &Env,
+    text: &str,
+    ctx: &str,
+) -> napi::Result<JsObject, ZrCode> {
+    let json: JsObject = env
+        .get_global()
+        .and_then(|global| global.get_named_property("JSON"))
+        .map_err(napi_error)?;
+    let parse: JsFunction = json.get_named_property("parse").map_err(napi_error)?;
+    let text = env.create_string(text).map_err(napi_error)?;
+    let value = parse.call(Some(&json), &[text]).map_err(|err| {
+        /* JSON.parse left its SyntaxError pending; napi-rs would throw that
+         * in place of the ZR_ERR_FORMAT error. */
+        let mut pending = std::ptr::null_mut();
+        unsafe { napi::sys::napi_get_and_clear_last_exception(env.raw(), &mut pending) };
+        zr_error(
+            ffi::ZR_ERR_FORMAT,
+            format!("{ctx}: config is not valid JSON: {}", err.reason),
+        )
+    })?;
+    let is_object = value.get_type().map_err(napi_error)? == ValueType::Object
+        && !value.is_array().map_err(napi_error)?;
+    if !is_object {
+        return Err(invalid_argument(format!(
+            "{ctx}: config JSON must be an object"
+        )));
+    }
+    Ok(unsafe { value.cast() })
 }
 
 pub(crate) fn js_u32(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<u32>> {
@@ -158,6 +452,24 @@ pub(crate) fn js_u32(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<
     Ok(None)
 }
 
+pub(crate) fn js_str(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<String>> {
+    for name in [primary, alias] {
+        let v = match obj.get_named_property::<JsUnknown>(name) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        match v.get_type().map_err(|_| ())? {
+            ValueType::Undefined => continue,
+            ValueType::String => {}
+            _ => return Err(()),
+        }
+        let s = v.coerce_to_string().map_err(|_| ())?;
+        let utf8 = s.into_utf8().map_err(|_| ())?;
+        return Ok(Some(utf8.as_str().map_err(|_| ())?.to_owned()));
+    }
+    Ok(None)
+}
+
 pub(crate) fn checked_u8(value: u32) -> ParseResult<u8> {
     if value > u8::MAX as u32 {
         return Err(());
@@ -194,7 +506,7 @@ pub(crate) fn js_u8_bool(obj: &JsObject, primary: &str, alias: &str) -> ParseRes
     Ok(None)
 }
 
-fn js_obj(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<JsObject>> {
+pub(crate) fn js_obj(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<JsObject>> {
     for name in [primary, alias] {
         let v = match obj.get_named_property::<JsUnknown>(name) {
             Ok(v) => v,
@@ -212,7 +524,36 @@ fn js_obj(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<JsOb
     Ok(None)
 }
 
-fn apply_limits(dst: &mut ffi::zr_limits_t, obj: &JsObject) -> ParseResult<()> {
+/// Reject limits the engine would only refuse later with a bare
+/// `ZR_ERR_INVALID_ARGUMENT` / `ZR_ERR_LIMIT`, naming the offending key.
+pub(crate) fn check_limits(lim: &ffi::zr_limits_t, ctx: &str) -> napi::Result<(), ZrCode> {
+    let fields = [
+        ("arenaMaxTotalBytes", lim.arena_max_total_bytes),
+        ("arenaInitialBytes", lim.arena_initial_bytes),
+        ("outMaxBytesPerFrame", lim.out_max_bytes_per_frame),
+        ("dlMaxTotalBytes", lim.dl_max_total_bytes),
+        ("dlMaxCmds", lim.dl_max_cmds),
+        ("dlMaxStrings", lim.dl_max_strings),
+        ("dlMaxBlobs", lim.dl_max_blobs),
+        ("dlMaxClipDepth", lim.dl_max_clip_depth),
+        ("dlMaxTextRunSegments", lim.dl_max_text_run_segments),
+        ("diffMaxDamageRects", lim.diff_max_damage_rects),
+    ];
+    if let Some((key, _)) = fields.iter().find(|(_, v)| *v == 0) {
+        return Err(invalid_argument(format!(
+            "{ctx}: limits.{key} must be greater than 0"
+        )));
+    }
+    if lim.arena_initial_bytes > lim.arena_max_total_bytes {
+        return Err(invalid_argument(format!(
+            "{ctx}: limits.arenaInitialBytes ({}) must not exceed limits.arenaMaxTotalBytes ({})",
+            lim.arena_initial_bytes, lim.arena_max_total_bytes
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn apply_limits(dst: &mut ffi::zr_limits_t, obj: &JsObject) -> ParseResult<()> {
     if let Some(v) = js_u32(obj, "arenaMaxTotalBytes", "arena_max_total_bytes")? {
         dst.arena_max_total_bytes = v;
     }
@@ -333,11 +674,21 @@ fn apply_create_cfg(dst: &mut ffi::zr_engine_config_t, obj: &JsObject) -> ParseR
     if let Some(v) = js_u32(obj, "inlineRows", "inline_rows")? {
         dst.inline_rows = v;
     }
+    if let Some(v) = js_u8_bool(
+        obj,
+        "enableEraseOptimizations",
+        "enable_erase_optimizations",
+    )? {
+        dst.enable_erase_optimizations = v;
+    }
     Ok(())
 }
 
-pub(crate) fn create_default_runtime_cfg() -> ffi::zr_engine_runtime_config_t {
-    let base = unsafe { ffi::zr_engine_config_default() };
+/// The runtime subset of a create-time config: what the engine runs with
+/// until the first `engineSetConfig`.
+pub(crate) fn runtime_cfg_from_create(
+    base: &ffi::zr_engine_config_t,
+) -> ffi::zr_engine_runtime_config_t {
     ffi::zr_engine_runtime_config_t {
         limits: base.limits,
         plat: base.plat,
@@ -351,6 +702,8 @@ pub(crate) fn create_default_runtime_cfg() -> ffi::zr_engine_runtime_config_t {
         cap_force_flags: base.cap_force_flags,
         cap_suppress_flags: base.cap_suppress_flags,
         inline_rows: base.inline_rows,
+        enable_erase_optimizations: base.enable_erase_optimizations,
+        _pad_erase0: [0; 3],
     }
 }
 
@@ -395,5 +748,186 @@ fn apply_runtime_cfg(dst: &mut ffi::zr_engine_runtime_config_t, obj: &JsObject)
     if let Some(v) = js_u32(obj, "inlineRows", "inline_rows")? {
         dst.inline_rows = v;
     }
+    if let Some(v) = js_u8_bool(
+        obj,
+        "enableEraseOptimizations",
+        "enable_erase_optimizations",
+    )? {
+        dst.enable_erase_optimizations = v;
+    }
     Ok(())
 }
+
+/// Engine limits under the `engineCreate` / `engineSetConfig` key names.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EngineLimits {
+    pub arenaMaxTotalBytes: u32,
+    pub arenaInitialBytes: u32,
+    pub outMaxBytesPerFrame: u32,
+    pub dlMaxTotalBytes: u32,
+    pub dlMaxCmds: u32,
+    pub dlMaxStrings: u32,
+    pub dlMaxBlobs: u32,
+    pub dlMaxClipDepth: u32,
+    pub dlMaxTextRunSegments: u32,
+    pub diffMaxDamageRects: u32,
+}
+
+/// Platform settings of a runtime config.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EnginePlatConfig {
+    pub requestedColorMode: u32,
+    pub enableMouse: bool,
+    pub enableBracketedPaste: bool,
+    pub enableFocusEvents: bool,
+    pub enableOsc52: bool,
+    /// 0 alternate screen, 1 inline.
+    pub screenMode: u32,
+}
+
+/// Runtime config as `engineGetConfig` reports it; accepted back by
+/// `engineSetConfig` unchanged.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EngineRuntimeConfig {
+    pub limits: EngineLimits,
+    pub plat: EnginePlatConfig,
+    pub tabWidth: u32,
+    pub widthPolicy: u32,
+    pub targetFps: u32,
+    pub enableScrollOptimizations: bool,
+    pub enableDebugOverlay: bool,
+    pub enableReplayRecording: bool,
+    pub waitForOutputDrain: bool,
+    pub capForceFlags: u32,
+    pub capSuppressFlags: u32,
+    pub inlineRows: u32,
+    pub enableEraseOptimizations: bool,
+}
+
+pub(crate) fn limits_to_js(limits: &ffi::zr_limits_t) -> EngineLimits {
+    EngineLimits {
+        arenaMaxTotalBytes: limits.arena_max_total_bytes,
+        arenaInitialBytes: limits.arena_initial_bytes,
+        outMaxBytesPerFrame: limits.out_max_bytes_per_frame,
+        dlMaxTotalBytes: limits.dl_max_total_bytes,
+        dlMaxCmds: limits.dl_max_cmds,
+        dlMaxStrings: limits.dl_max_strings,
+        dlMaxBlobs: limits.dl_max_blobs,
+        dlMaxClipDepth: limits.dl_max_clip_depth,
+        dlMaxTextRunSegments: limits.dl_max_text_run_segments,
+        diffMaxDamageRects: limits.diff_max_damage_rects,
+    }
+}
+
+pub(crate) fn runtime_cfg_to_js(cfg: &ffi::zr_engine_runtime_config_t) -> EngineRuntimeConfig {
+    let plat = &cfg.plat;
+    EngineRuntimeConfig {
+        limits: limits_to_js(&cfg.limits),
+        plat: EnginePlatConfig {
+            requestedColorMode: u32::from(plat.requested_color_mode),
+            enableMouse: plat.enable_mouse != 0,
+            enableBracketedPaste: plat.enable_bracketed_paste != 0,
+            enableFocusEvents: plat.enable_focus_events != 0,
+            enableOsc52: plat.enable_osc52 != 0,
+            screenMode: u32::from(plat.screen_mode),
+        },
+        tabWidth: cfg.tab_width,
+        widthPolicy: cfg.width_policy,
+        targetFps: cfg.target_fps,
+        enableScrollOptimizations: cfg.enable_scroll_optimizations != 0,
+        enableDebugOverlay: cfg.enable_debug_overlay != 0,
+        enableReplayRecording: cfg.enable_replay_recording != 0,
+        waitForOutputDrain: cfg.wait_for_output_drain != 0,
+        capForceFlags: cfg.cap_force_flags,
+        capSuppressFlags: cfg.cap_suppress_flags,
+        inlineRows: cfg.inline_rows,
+        enableEraseOptimizations: cfg.enable_erase_optimizations != 0,
+    }
+}
+
+/// Create-time config as `engineConfigPreset` builds it: the runtime keys plus
+/// the requested versions, all under the `engineCreate` key names.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EngineCreateConfig {
+    pub requestedEngineAbiMajor: u32,
+    pub requestedEngineAbiMinor: u32,
+    pub requestedEngineAbiPatch: u32,
+    pub requestedDrawlistVersion: u32,
+    pub requestedEventBatchVersion: u32,
+    pub limits: EngineLimits,
+    pub plat: EnginePlatConfig,
+    pub tabWidth: u32,
+    pub widthPolicy: u32,
+    pub targetFps: u32,
+    pub enableScrollOptimizations: bool,
+    pub enableDebugOverlay: bool,
+    pub enableReplayRecording: bool,
+    pub waitForOutputDrain: bool,
+    pub capForceFlags: u32,
+    pub capSuppressFlags: u32,
+    pub inlineRows: u32,
+    pub enableEraseOptimizations: bool,
+}
+
+pub(crate) fn create_cfg_to_js(cfg: &ffi::zr_engine_config_t) -> EngineCreateConfig {
+    let rt = runtime_cfg_to_js(&runtime_cfg_from_create(cfg));
+    EngineCreateConfig {
+        requestedEngineAbiMajor: cfg.requested_engine_abi_major,
+        requestedEngineAbiMinor: cfg.requested_engine_abi_minor,
+        requestedEngineAbiPatch: cfg.requested_engine_abi_patch,
+        requestedDrawlistVersion: cfg.requested_drawlist_version,
+        requestedEventBatchVersion: cfg.requested_event_batch_version,
+        limits: rt.limits,
+        plat: rt.plat,
+        tabWidth: rt.tabWidth,
+        widthPolicy: rt.widthPolicy,
+        targetFps: rt.targetFps,
+        enableScrollOptimizations: rt.enableScrollOptimizations,
+        enableDebugOverlay: rt.enableDebugOverlay,
+        enableReplayRecording: rt.enableReplayRecording,
+        waitForOutputDrain: rt.waitForOutputDrain,
+        capForceFlags: rt.capForceFlags,
+        capSuppressFlags: rt.capSuppressFlags,
+        inlineRows: rt.inlineRows,
+        enableEraseOptimizations: rt.enableEraseOptimizations,
+    }
+}
+
+pub(crate) const CONFIG_PRESET_NAMES: &[&str] = &["low-memory", "high-throughput", "debug"];
+
+/// `zr_engine_config_default()` with the overrides of the named preset, or
+/// `None` for a name outside `CONFIG_PRESET_NAMES`.
+pub(crate) fn config_preset(name: &str) -> Option<ffi::zr_engine_config_t> {
+    let mut cfg = unsafe { ffi::zr_engine_config_default() };
+    match name {
+        "low-memory" => {
+            cfg.limits.arena_max_total_bytes = 1024 * 1024;
+            cfg.limits.arena_initial_bytes = 16 * 1024;
+            cfg.limits.out_max_bytes_per_frame = 64 * 1024;
+            cfg.limits.dl_max_total_bytes = 64 * 1024;
+            cfg.limits.diff_max_damage_rects = 1024;
+        }
+        "high-throughput" => {
+            cfg.limits.arena_max_total_bytes = 16 * 1024 * 1024;
+            cfg.limits.arena_initial_bytes = 1024 * 1024;
+            cfg.limits.out_max_bytes_per_frame = 1024 * 1024;
+            cfg.limits.dl_max_total_bytes = 1024 * 1024;
+            cfg.limits.dl_max_cmds = 16384;
+            cfg.limits.dl_max_strings = 16384;
+            cfg.limits.dl_max_blobs = 16384;
+            cfg.limits.dl_max_text_run_segments = 16384;
+            cfg.limits.diff_max_damage_rects = 16384;
+            cfg.target_fps = 120;
+        }
+        "debug" => {
+            cfg.enable_debug_overlay = 1;
+            cfg.enable_replay_recording = 1;
+        }
+        _ => return None,
+    }
+    Some(cfg)
+}
@@ -0,0 +1,152 @@
+use crate::error::{invalid_argument, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::{empty_terminal_caps, owner_engine_guard};
+use napi_derive::napi;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Blink override applied to the cursor state at present time.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum CursorBlink {
+    /// No override; the SET_CURSOR blink flag decides (engine default).
+    #[default]
+    Off,
+    /// Always request the blinking DECSCUSR variant of the shape.
+    On,
+    /// Always request the steady DECSCUSR variant of the shape.
+    Steady,
+}
+
+impl CursorBlink {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(Self::Off),
+            "on" => Some(Self::On),
+            "steady" => Some(Self::Steady),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::On => "on",
+            Self::Steady => "steady",
+        }
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::On,
+            2 => Self::Steady,
+            _ => Self::Off,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::On => 1,
+            Self::Steady => 2,
+        }
+    }
+
+    /// Blink flag forced into the cursor state, `None` to leave it alone.
+    pub(crate) fn blink_flag(self) -> Option<u8> {
+        match self {
+            Self::Off => None,
+            Self::On => Some(1),
+            Self::Steady => Some(0),
+        }
+    }
+
+    /// Mode the terminal actually gets: without cursor-shape support the
+    /// engine sends no DECSCUSR at all, so the override has no effect.
+    pub(crate) fn effective(self, supports_cursor_shape: bool) -> Self {
+        if supports_cursor_shape {
+            self
+        } else {
+            Self::Off
+        }
+    }
+}
+
+#[napi(object)]
+pub struct CursorBlinkOptions {
+    /// `"on"`, `"off"` or `"steady"`.
+    pub mode: String,
+}
+
+thread_local! {
+    static ACTIVE: Cell<Option<u8>> = const { Cell::new(None) };
+}
+
+/// Blink mode an engine's presents apply, as last set.
+#[derive(Default)]
+pub(crate) struct CursorBlinkState {
+    mode: AtomicU8,
+}
+
+impl CursorBlinkState {
+    pub(crate) fn get(&self) -> CursorBlink {
+        CursorBlink::from_u8(self.mode.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, mode: CursorBlink) {
+        self.mode.store(mode.to_u8(), Ordering::Relaxed);
+    }
+
+    /// Run `present` with the blink override applied to its diff.
+    pub(crate) fn apply(&self, present: impl FnOnce() -> i32) -> i32 {
+        let Some(flag) = self.get().blink_flag() else {
+            return present();
+        };
+        ACTIVE.with(|a| a.set(Some(flag)));
+        let rc = present();
+        ACTIVE.with(|a| a.set(None));
+        rc
+    }
+}
+
+/// Copy of the desired cursor state with the active blink override applied,
+/// for the present diff hook (see protect.rs); `None` when nothing changes.
+pub(crate) fn override_desired_cursor(
+    desired: *const ffi::zr_cursor_state_t,
+) -> Option<ffi::zr_cursor_state_t> {
+    let flag = ACTIVE.with(Cell::get)?;
+    if desired.is_null() {
+        return None;
+    }
+    let mut cursor = unsafe { *desired };
+    cursor.blink = flag;
+    Some(cursor)
+}
+
+/// Force the cursor's blink for every following present: `"on"` and
+/// `"steady"` select the blinking or steady DECSCUSR variant of the current
+/// shape, `"off"` goes back to the SET_CURSOR blink flag. Returns the
+/// effective mode, which is `"off"` when the terminal lacks cursor-shape
+/// support.
+#[napi(js_name = "engineSetCursorBlink")]
+pub fn engine_set_cursor_blink(
+    engine_id: u32,
+    options: CursorBlinkOptions,
+) -> napi::Result<String, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSetCursorBlink")?;
+    let Some(mode) = CursorBlink::parse(&options.mode) else {
+        return Err(invalid_argument(format!(
+            "unknown cursor blink mode {:?} (expected \"on\", \"off\" or \"steady\")",
+            options.mode
+        )));
+    };
+    let mut caps = empty_terminal_caps();
+    let rc = unsafe { ffi::engine_get_caps(guard.slot.engine, &mut caps as *mut _) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineSetCursorBlink"));
+    }
+    guard.slot.cursor_blink.set(mode);
+    Ok(mode
+        .effective(caps.supports_cursor_shape != 0)
+        .name()
+        .to_string())
+}
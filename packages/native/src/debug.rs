@@ -1,8 +1,9 @@
 use crate::config::{js_u32, js_u8_bool, validate_known_keys, ParseResult};
+use crate::error::{invalid_argument, zr_result_error, zr_result_to_napi, ZrCode};
 use crate::ffi;
 use crate::registry::get_engine_guard;
-use crate::{bigint_from_u64, invalid_arg_error};
-use napi::bindgen_prelude::{BigInt, Error, Status, Uint8Array, ValueType};
+use crate::{bigint_from_u64, owner_engine_guard};
+use napi::bindgen_prelude::{BigInt, Uint8Array, ValueType};
 use napi::{Env, JsBigInt, JsObject, JsUnknown};
 use napi_derive::napi;
 
@@ -27,6 +28,128 @@ pub struct DebugQueryResult {
     pub recordsDropped: u32,
 }
 
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct DebugRecord {
+    pub recordId: BigInt,
+    pub timestampUs: BigInt,
+    pub frameId: BigInt,
+    pub category: u32,
+    /// Stable category name (`"frame"`, `"event"`, ...), or `"unknown"`.
+    pub categoryName: String,
+    pub severity: u32,
+    /// Stable severity name (`"trace"`, `"info"`, `"warn"`, `"error"`), or `"unknown"`.
+    pub severityName: String,
+    pub code: u32,
+    pub payloadSize: u32,
+}
+
+/// Record counts per debug category over the current ring contents.
+#[napi(object)]
+#[allow(non_snake_case)]
+#[derive(Default)]
+pub struct DebugCategoryCounts {
+    pub none: u32,
+    pub frame: u32,
+    pub event: u32,
+    pub drawlist: u32,
+    pub error: u32,
+    pub state: u32,
+    pub perf: u32,
+    /// Records whose category this binding does not know.
+    pub unknown: u32,
+}
+
+/// Debug trace categories (`zr_debug_category_t`). `categoryMask` selects
+/// category `c` with bit `1 << c`.
+#[napi]
+pub enum DebugCategory {
+    None = 0,
+    Frame = 1,
+    Event = 2,
+    Drawlist = 3,
+    Error = 4,
+    State = 5,
+    Perf = 6,
+}
+
+/// Debug trace severities (`zr_debug_severity_t`), for `minSeverity`.
+#[napi]
+pub enum DebugSeverity {
+    Trace = 0,
+    Info = 1,
+    Warn = 2,
+    Error = 3,
+}
+
+/// `categoryMask` selecting every category.
+#[napi]
+pub const DEBUG_CATEGORY_MASK_ALL: u32 = 0xFFFF_FFFF;
+
+const _: () = {
+    assert!(DebugCategory::None as u32 == ffi::ZR_DEBUG_CAT_NONE);
+    assert!(DebugCategory::Frame as u32 == ffi::ZR_DEBUG_CAT_FRAME);
+    assert!(DebugCategory::Event as u32 == ffi::ZR_DEBUG_CAT_EVENT);
+    assert!(DebugCategory::Drawlist as u32 == ffi::ZR_DEBUG_CAT_DRAWLIST);
+    assert!(DebugCategory::Error as u32 == ffi::ZR_DEBUG_CAT_ERROR);
+    assert!(DebugCategory::State as u32 == ffi::ZR_DEBUG_CAT_STATE);
+    assert!(DebugCategory::Perf as u32 == ffi::ZR_DEBUG_CAT_PERF);
+    assert!(DebugSeverity::Trace as u32 == ffi::ZR_DEBUG_SEV_TRACE);
+    assert!(DebugSeverity::Info as u32 == ffi::ZR_DEBUG_SEV_INFO);
+    assert!(DebugSeverity::Warn as u32 == ffi::ZR_DEBUG_SEV_WARN);
+    assert!(DebugSeverity::Error as u32 == ffi::ZR_DEBUG_SEV_ERROR);
+};
+
+// Kept next to the FFI constants so names cannot drift from zr_debug.h.
+const DEBUG_CATEGORY_NAMES: &[(u32, &str)] = &[
+    (ffi::ZR_DEBUG_CAT_NONE, "none"),
+    (ffi::ZR_DEBUG_CAT_FRAME, "frame"),
+    (ffi::ZR_DEBUG_CAT_EVENT, "event"),
+    (ffi::ZR_DEBUG_CAT_DRAWLIST, "drawlist"),
+    (ffi::ZR_DEBUG_CAT_ERROR, "error"),
+    (ffi::ZR_DEBUG_CAT_STATE, "state"),
+    (ffi::ZR_DEBUG_CAT_PERF, "perf"),
+];
+
+const DEBUG_SEVERITY_NAMES: &[(u32, &str)] = &[
+    (ffi::ZR_DEBUG_SEV_TRACE, "trace"),
+    (ffi::ZR_DEBUG_SEV_INFO, "info"),
+    (ffi::ZR_DEBUG_SEV_WARN, "warn"),
+    (ffi::ZR_DEBUG_SEV_ERROR, "error"),
+];
+
+pub(crate) fn debug_category_name(category: u32) -> Option<&'static str> {
+    DEBUG_CATEGORY_NAMES
+        .iter()
+        .find(|(value, _)| *value == category)
+        .map(|(_, name)| *name)
+}
+
+pub(crate) fn debug_severity_name(severity: u32) -> Option<&'static str> {
+    DEBUG_SEVERITY_NAMES
+        .iter()
+        .find(|(value, _)| *value == severity)
+        .map(|(_, name)| *name)
+}
+
+pub(crate) fn debug_record_to_js(header: &ffi::zr_debug_record_header_t) -> DebugRecord {
+    DebugRecord {
+        recordId: bigint_from_u64(header.record_id),
+        timestampUs: bigint_from_u64(header.timestamp_us),
+        frameId: bigint_from_u64(header.frame_id),
+        category: header.category,
+        categoryName: debug_category_name(header.category)
+            .unwrap_or("unknown")
+            .to_owned(),
+        severity: header.severity,
+        severityName: debug_severity_name(header.severity)
+            .unwrap_or("unknown")
+            .to_owned(),
+        code: header.code,
+        payloadSize: header.payload_size,
+    }
+}
+
 const DEBUG_CFG_KEYS: &[(&str, &str)] = &[
     ("enabled", "enabled"),
     ("ringCapacity", "ring_capacity"),
@@ -72,7 +195,7 @@ pub(crate) fn parse_debug_query_number_u64(float: f64) -> ParseResult<u64> {
     Ok(float as u64)
 }
 
-fn js_u64(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<u64>> {
+pub(crate) fn js_u64(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<u64>> {
     for name in [primary, alias] {
         let value = match obj.get_named_property::<JsUnknown>(name) {
             Ok(value) => value,
@@ -144,19 +267,58 @@ fn apply_debug_query(dst: &mut ffi::zr_debug_query_t, obj: &JsObject) -> ParseRe
     Ok(())
 }
 
+fn empty_debug_query_result() -> ffi::zr_debug_query_result_t {
+    ffi::zr_debug_query_result_t {
+        records_returned: 0,
+        records_available: 0,
+        oldest_record_id: 0,
+        newest_record_id: 0,
+        records_dropped: 0,
+        _pad0: 0,
+    }
+}
+
+fn build_debug_query(
+    query: Option<&JsObject>,
+    ctx: &str,
+) -> napi::Result<ffi::zr_debug_query_t, ZrCode> {
+    let mut debug_query = ffi::zr_debug_query_t {
+        min_record_id: 0,
+        max_record_id: 0,
+        min_frame_id: 0,
+        max_frame_id: 0,
+        category_mask: 0xFFFF_FFFF,
+        min_severity: 0,
+        max_records: 0,
+        _pad0: 0,
+    };
+
+    if let Some(obj) = query {
+        validate_known_keys(obj, DEBUG_QUERY_KEYS, &format!("{ctx} query"))?;
+        apply_debug_query(&mut debug_query, obj)
+            .map_err(|_| invalid_argument(format!("{ctx}: invalid query value")))?;
+    }
+
+    Ok(debug_query)
+}
+
+#[napi(js_name = "debugCategoryName")]
+pub fn debug_category_name_js(category: u32) -> Option<String> {
+    debug_category_name(category).map(str::to_owned)
+}
+
+#[napi(js_name = "debugSeverityName")]
+pub fn debug_severity_name_js(severity: u32) -> Option<String> {
+    debug_severity_name(severity).map(str::to_owned)
+}
+
 #[napi(js_name = "engineDebugEnable")]
 pub fn engine_debug_enable(
     _env: Env,
     engine_id: u32,
     config: Option<JsObject>,
-) -> napi::Result<i32> {
-    let guard = match get_engine_guard(engine_id) {
-        Ok(guard) => guard,
-        Err(rc) => return Ok(rc),
-    };
-    if !guard.slot.is_owner_thread() {
-        return Ok(ffi::ZR_ERR_INVALID_ARGUMENT);
-    }
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugEnable")?;
 
     let mut cfg = ffi::zr_debug_config_t {
         enabled: 1,
@@ -171,19 +333,22 @@ pub fn engine_debug_enable(
 
     if let Some(obj) = config {
         validate_known_keys(&obj, DEBUG_CFG_KEYS, "engineDebugEnable config")?;
-        apply_debug_cfg(&mut cfg, &obj).map_err(|_| {
-            Error::new(
-                Status::InvalidArg,
-                "engineDebugEnable: invalid config value",
-            )
-        })?;
+        apply_debug_cfg(&mut cfg, &obj)
+            .map_err(|_| invalid_argument("engineDebugEnable: invalid config value"))?;
     }
 
-    Ok(unsafe { ffi::engine_debug_enable(guard.slot.engine, &cfg as *const _) })
+    let rc = unsafe { ffi::engine_debug_enable(guard.slot.engine, &cfg as *const _) };
+    zr_result_to_napi(rc, "engineDebugEnable")?;
+    guard.slot.debug_sub.rewind();
+    Ok(())
 }
 
 #[napi(js_name = "engineDebugDisable")]
-pub fn engine_debug_disable(engine_id: u32) -> i32 {
+pub fn engine_debug_disable(engine_id: u32) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(debug_disable(engine_id), "engineDebugDisable")
+}
+
+fn debug_disable(engine_id: u32) -> i32 {
     let guard = match get_engine_guard(engine_id) {
         Ok(guard) => guard,
         Err(rc) => return rc,
@@ -196,58 +361,41 @@ pub fn engine_debug_disable(engine_id: u32) -> i32 {
     ffi::ZR_OK
 }
 
+/// Record headers an `engineDebugQuery` buffer of `len` bytes at `addr`
+/// holds, clamped to `u32::MAX`. Any non-empty buffer must be aligned for
+/// the headers, even one too small to hold a single header.
+pub(crate) fn debug_headers_cap(len: usize, addr: usize) -> napi::Result<u32, ZrCode> {
+    if len == 0 {
+        return Ok(0);
+    }
+    if !addr.is_multiple_of(std::mem::align_of::<ffi::zr_debug_record_header_t>()) {
+        return Err(invalid_argument(
+            "engineDebugQuery: outHeaders must be aligned for debug record headers",
+        ));
+    }
+    let cap = len / std::mem::size_of::<ffi::zr_debug_record_header_t>();
+    Ok(u32::try_from(cap).unwrap_or(u32::MAX))
+}
+
 #[napi(js_name = "engineDebugQuery")]
 pub fn engine_debug_query(
     _env: Env,
     engine_id: u32,
     query: Option<JsObject>,
     mut out_headers: Uint8Array,
-) -> napi::Result<DebugQueryResult> {
-    let guard = get_engine_guard(engine_id).map_err(|_| invalid_arg_error())?;
-    if !guard.slot.is_owner_thread() {
-        return Err(invalid_arg_error());
-    }
-
-    let mut debug_query = ffi::zr_debug_query_t {
-        min_record_id: 0,
-        max_record_id: 0,
-        min_frame_id: 0,
-        max_frame_id: 0,
-        category_mask: 0xFFFF_FFFF,
-        min_severity: 0,
-        max_records: 0,
-        _pad0: 0,
-    };
+) -> napi::Result<DebugQueryResult, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugQuery")?;
 
-    if let Some(obj) = query {
-        validate_known_keys(&obj, DEBUG_QUERY_KEYS, "engineDebugQuery query")?;
-        apply_debug_query(&mut debug_query, &obj)
-            .map_err(|_| Error::new(Status::InvalidArg, "engineDebugQuery: invalid query value"))?;
-    }
+    let debug_query = build_debug_query(query.as_ref(), "engineDebugQuery")?;
 
-    let mut result = ffi::zr_debug_query_result_t {
-        records_returned: 0,
-        records_available: 0,
-        oldest_record_id: 0,
-        newest_record_id: 0,
-        records_dropped: 0,
-        _pad0: 0,
-    };
+    let mut result = empty_debug_query_result();
 
     let out_headers_slice = out_headers.as_mut();
-    let header_size = std::mem::size_of::<ffi::zr_debug_record_header_t>();
-    let header_align = std::mem::align_of::<ffi::zr_debug_record_header_t>();
-    let headers_cap = (out_headers_slice.len() / header_size) as u32;
+    let raw = out_headers_slice.as_mut_ptr();
+    let headers_cap = debug_headers_cap(out_headers_slice.len(), raw as usize)?;
     let headers_ptr = if headers_cap == 0 {
         std::ptr::null_mut()
     } else {
-        let raw = out_headers_slice.as_mut_ptr();
-        if (raw as usize) % header_align != 0 {
-            return Err(Error::new(
-                Status::InvalidArg,
-                "engineDebugQuery: outHeaders must be aligned for debug record headers",
-            ));
-        }
         raw as *mut ffi::zr_debug_record_header_t
     };
 
@@ -261,10 +409,7 @@ pub fn engine_debug_query(
         )
     };
     if rc != ffi::ZR_OK {
-        return Err(Error::new(
-            Status::GenericFailure,
-            format!("engine_debug_query failed: {rc}"),
-        ));
+        return Err(zr_result_error(rc, "engineDebugQuery"));
     }
 
     Ok(DebugQueryResult {
@@ -276,23 +421,122 @@ pub fn engine_debug_query(
     })
 }
 
+/// Fetch every record header matching `debug_query`, sized by a count-only
+/// probe so the header buffer is exact.
+pub(crate) fn query_debug_headers(
+    engine: *mut ffi::zr_engine_t,
+    debug_query: &ffi::zr_debug_query_t,
+    ctx: &str,
+) -> napi::Result<Vec<ffi::zr_debug_record_header_t>, ZrCode> {
+    // Count-only probe first so the header buffer is sized exactly.
+    let mut probe = empty_debug_query_result();
+    let rc = unsafe {
+        ffi::engine_debug_query(
+            engine,
+            debug_query as *const _,
+            std::ptr::null_mut(),
+            0,
+            &mut probe as *mut _,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+
+    let mut cap = probe.records_available;
+    if debug_query.max_records != 0 {
+        cap = cap.min(debug_query.max_records);
+    }
+    if cap == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut headers = vec![
+        ffi::zr_debug_record_header_t {
+            record_id: 0,
+            timestamp_us: 0,
+            frame_id: 0,
+            category: 0,
+            severity: 0,
+            code: 0,
+            payload_size: 0,
+        };
+        cap as usize
+    ];
+    let mut result = empty_debug_query_result();
+    let rc = unsafe {
+        ffi::engine_debug_query(
+            engine,
+            debug_query as *const _,
+            headers.as_mut_ptr(),
+            cap,
+            &mut result as *mut _,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+
+    headers.truncate(result.records_returned.min(cap) as usize);
+    Ok(headers)
+}
+
+pub(crate) fn count_debug_categories(
+    headers: &[ffi::zr_debug_record_header_t],
+) -> DebugCategoryCounts {
+    let mut counts = DebugCategoryCounts::default();
+    for header in headers {
+        let slot = match header.category {
+            ffi::ZR_DEBUG_CAT_NONE => &mut counts.none,
+            ffi::ZR_DEBUG_CAT_FRAME => &mut counts.frame,
+            ffi::ZR_DEBUG_CAT_EVENT => &mut counts.event,
+            ffi::ZR_DEBUG_CAT_DRAWLIST => &mut counts.drawlist,
+            ffi::ZR_DEBUG_CAT_ERROR => &mut counts.error,
+            ffi::ZR_DEBUG_CAT_STATE => &mut counts.state,
+            ffi::ZR_DEBUG_CAT_PERF => &mut counts.perf,
+            _ => &mut counts.unknown,
+        };
+        *slot += 1;
+    }
+    counts
+}
+
+/// Break the records currently held in the ring down by category, to help
+/// tune `categoryMask` before a capture.
+#[napi(js_name = "engineDebugCategoryCounts")]
+pub fn engine_debug_category_counts(engine_id: u32) -> napi::Result<DebugCategoryCounts, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugCategoryCounts")?;
+
+    let debug_query = build_debug_query(None, "engineDebugCategoryCounts")?;
+    let headers =
+        query_debug_headers(guard.slot.engine, &debug_query, "engineDebugCategoryCounts")?;
+    Ok(count_debug_categories(&headers))
+}
+
+#[napi(js_name = "engineDebugQueryRecords")]
+pub fn engine_debug_query_records(
+    _env: Env,
+    engine_id: u32,
+    query: Option<JsObject>,
+) -> napi::Result<Vec<DebugRecord>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugQueryRecords")?;
+
+    let debug_query = build_debug_query(query.as_ref(), "engineDebugQueryRecords")?;
+    let headers = query_debug_headers(guard.slot.engine, &debug_query, "engineDebugQueryRecords")?;
+    Ok(headers.iter().map(debug_record_to_js).collect())
+}
+
 #[napi(js_name = "engineDebugGetPayload")]
 pub fn engine_debug_get_payload(
     engine_id: u32,
     record_id: BigInt,
     mut out_payload: Uint8Array,
-) -> napi::Result<i32> {
-    let guard = get_engine_guard(engine_id).map_err(|_| invalid_arg_error())?;
-    if !guard.slot.is_owner_thread() {
-        return Err(invalid_arg_error());
-    }
+) -> napi::Result<i32, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugGetPayload")?;
 
     let record_id =
         parse_debug_query_bigint_u64(record_id.sign_bit, &record_id.words).map_err(|_| {
-            Error::new(
-                Status::InvalidArg,
-                "engineDebugGetPayload: recordId must be a non-negative u64",
-            )
+            invalid_argument("engineDebugGetPayload: recordId must be a non-negative u64")
         })?;
 
     let mut out_size = 0u32;
@@ -307,19 +551,75 @@ pub fn engine_debug_get_payload(
             &mut out_size as *mut _,
         )
     };
+    zr_result_to_napi(rc, "engineDebugGetPayload")?;
+    Ok(out_size as i32)
+}
+
+/// Read a payload sized by a capacity-0 probe, which reports the record's
+/// `payload_size` (`ZR_ERR_LIMIT` with size 0 means no such record).
+/// `get(out)` runs `engine_debug_get_payload` into `out`.
+pub(crate) fn read_debug_payload(
+    mut get: impl FnMut(&mut [u8]) -> (i32, u32),
+) -> Result<Vec<u8>, i32> {
+    let (rc, size) = get(&mut []);
+    if rc == ffi::ZR_OK {
+        return Ok(Vec::new());
+    }
+    if rc != ffi::ZR_ERR_LIMIT || size == 0 {
+        return Err(rc);
+    }
+    let mut payload = vec![0u8; size as usize];
+    let (rc, size) = get(&mut payload);
     if rc != ffi::ZR_OK {
-        return Ok(rc);
+        return Err(rc);
     }
+    payload.truncate(size as usize);
+    Ok(payload)
+}
 
-    Ok(out_size as i32)
+/// `engineDebugGetPayload` into a buffer of exactly the payload's size.
+#[napi(js_name = "engineDebugGetPayloadAlloc")]
+pub fn engine_debug_get_payload_alloc(
+    engine_id: u32,
+    record_id: BigInt,
+) -> napi::Result<Uint8Array, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugGetPayloadAlloc")?;
+
+    let record_id =
+        parse_debug_query_bigint_u64(record_id.sign_bit, &record_id.words).map_err(|_| {
+            invalid_argument("engineDebugGetPayloadAlloc: recordId must be a non-negative u64")
+        })?;
+
+    let payload = read_debug_payload(|out| {
+        let mut size = 0u32;
+        let ptr = if out.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            out.as_mut_ptr()
+        };
+        let rc = unsafe {
+            ffi::engine_debug_get_payload(
+                guard.slot.engine,
+                record_id,
+                ptr,
+                out.len() as u32,
+                &mut size as *mut _,
+            )
+        };
+        (rc, size)
+    })
+    .map_err(|rc| {
+        zr_result_error(
+            rc,
+            &format!("engineDebugGetPayloadAlloc: record {record_id}"),
+        )
+    })?;
+    Ok(Uint8Array::new(payload))
 }
 
 #[napi(js_name = "engineDebugGetStats")]
-pub fn engine_debug_get_stats(engine_id: u32) -> napi::Result<DebugStats> {
-    let guard = get_engine_guard(engine_id).map_err(|_| invalid_arg_error())?;
-    if !guard.slot.is_owner_thread() {
-        return Err(invalid_arg_error());
-    }
+pub fn engine_debug_get_stats(engine_id: u32) -> napi::Result<DebugStats, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugGetStats")?;
 
     let mut stats = ffi::zr_debug_stats_t {
         total_records: 0,
@@ -331,10 +631,7 @@ pub fn engine_debug_get_stats(engine_id: u32) -> napi::Result<DebugStats> {
     };
     let rc = unsafe { ffi::engine_debug_get_stats(guard.slot.engine, &mut stats as *mut _) };
     if rc != ffi::ZR_OK {
-        return Err(Error::new(
-            Status::GenericFailure,
-            format!("engine_debug_get_stats failed: {rc}"),
-        ));
+        return Err(zr_result_error(rc, "engineDebugGetStats"));
     }
 
     Ok(DebugStats {
@@ -347,8 +644,23 @@ pub fn engine_debug_get_stats(engine_id: u32) -> napi::Result<DebugStats> {
     })
 }
 
+/// Bytes `engineDebugExport` writes for `headers`: each record is its
+/// header followed by its payload.
+pub(crate) fn debug_export_size(headers: &[ffi::zr_debug_record_header_t]) -> u64 {
+    headers
+        .iter()
+        .map(|h| size_of::<ffi::zr_debug_record_header_t>() as u64 + u64::from(h.payload_size))
+        .sum()
+}
+
 #[napi(js_name = "engineDebugExport")]
-pub fn engine_debug_export(engine_id: u32, mut out_buf: Uint8Array) -> i32 {
+pub fn engine_debug_export(engine_id: u32, out_buf: Uint8Array) -> napi::Result<i32, ZrCode> {
+    let rc = debug_export(engine_id, out_buf);
+    zr_result_to_napi(rc, "engineDebugExport")?;
+    Ok(rc)
+}
+
+fn debug_export(engine_id: u32, mut out_buf: Uint8Array) -> i32 {
     let guard = match get_engine_guard(engine_id) {
         Ok(guard) => guard,
         Err(rc) => return rc,
@@ -357,13 +669,35 @@ pub fn engine_debug_export(engine_id: u32, mut out_buf: Uint8Array) -> i32 {
         return ffi::ZR_ERR_INVALID_ARGUMENT;
     }
 
+    if out_buf.is_empty() {
+        // Size probe: what a full export would write, nothing copied.
+        let query = ffi::zr_debug_query_t {
+            min_record_id: 0,
+            max_record_id: 0,
+            min_frame_id: 0,
+            max_frame_id: 0,
+            category_mask: 0xFFFF_FFFF,
+            min_severity: 0,
+            max_records: 0,
+            _pad0: 0,
+        };
+        return match query_debug_headers(guard.slot.engine, &query, "engineDebugExport") {
+            Ok(headers) => i32::try_from(debug_export_size(&headers)).unwrap_or(ffi::ZR_ERR_LIMIT),
+            Err(err) => err.status.0,
+        };
+    }
+
     let out_cap = out_buf.len();
     let out_ptr = out_buf.as_mut().as_mut_ptr();
     unsafe { ffi::engine_debug_export(guard.slot.engine, out_ptr, out_cap) }
 }
 
 #[napi(js_name = "engineDebugReset")]
-pub fn engine_debug_reset(engine_id: u32) -> i32 {
+pub fn engine_debug_reset(engine_id: u32) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(debug_reset(engine_id), "engineDebugReset")
+}
+
+fn debug_reset(engine_id: u32) -> i32 {
     let guard = match get_engine_guard(engine_id) {
         Ok(guard) => guard,
         Err(rc) => return rc,
@@ -373,5 +707,6 @@ pub fn engine_debug_reset(engine_id: u32) -> i32 {
     }
 
     unsafe { ffi::engine_debug_reset(guard.slot.engine) };
+    guard.slot.debug_sub.rewind();
     ffi::ZR_OK
 }
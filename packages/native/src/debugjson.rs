@@ -0,0 +1,323 @@
+use crate::debug::{
+    debug_category_name, debug_export_size, debug_severity_name, query_debug_headers,
+};
+use crate::error::{zr_error, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::owner_engine_guard;
+use napi_derive::napi;
+use std::fmt::Write;
+
+/// Bumped whenever the document shape changes incompatibly.
+const DEBUG_JSON_VERSION: u32 = 1;
+
+const HEADER_BYTES: usize = size_of::<ffi::zr_debug_record_header_t>();
+
+// Record codes from zr_debug_trace.h (zr_debug_code_t).
+const DEBUG_CODE_NAMES: &[(u32, &str)] = &[
+    (0x0100, "frame_begin"),
+    (0x0101, "frame_submit"),
+    (0x0102, "frame_present"),
+    (0x0103, "frame_resize"),
+    (0x0200, "event_poll_begin"),
+    (0x0201, "event_poll_end"),
+    (0x0202, "event_parsed"),
+    (0x0203, "event_dropped"),
+    (0x0300, "drawlist_validate"),
+    (0x0301, "drawlist_execute"),
+    (0x0302, "drawlist_cmd"),
+    (0x0400, "error_generic"),
+    (0x0401, "error_drawlist"),
+    (0x0402, "error_event"),
+    (0x0403, "error_platform"),
+    (0x0500, "state_change"),
+    (0x0600, "perf_timing"),
+    (0x0601, "perf_diff_path"),
+];
+
+/// One field of a fixed payload struct: JSON key and byte offset.
+enum Field {
+    U32(&'static str, usize),
+    U64(&'static str, usize),
+    Flag(&'static str, usize),
+    /// NUL-padded `char[len]`.
+    Text(&'static str, usize, usize),
+}
+
+/// Layout of a payload struct from zr_debug.h: its size and the fields
+/// worth reporting (padding left out).
+struct PayloadLayout {
+    size: usize,
+    fields: &'static [Field],
+}
+
+const FRAME_RECORD: PayloadLayout = PayloadLayout {
+    size: 56,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U32("cols", 8),
+        Field::U32("rows", 12),
+        Field::U32("drawlistBytes", 16),
+        Field::U32("drawlistCmds", 20),
+        Field::U32("diffBytesEmitted", 24),
+        Field::U32("dirtyLines", 28),
+        Field::U32("dirtyCells", 32),
+        Field::U32("damageRects", 36),
+        Field::U32("usDrawlist", 40),
+        Field::U32("usDiff", 44),
+        Field::U32("usWrite", 48),
+    ],
+};
+
+const EVENT_RECORD: PayloadLayout = PayloadLayout {
+    size: 32,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U32("eventType", 8),
+        Field::U32("eventFlags", 12),
+        Field::U32("timeMs", 16),
+        Field::U32("rawBytesLen", 20),
+        Field::U32("parseResult", 24),
+        Field::U32("dropReason", 28),
+    ],
+};
+
+const ERROR_RECORD: PayloadLayout = PayloadLayout {
+    size: 120,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U32("errorCode", 8),
+        Field::U32("sourceLine", 12),
+        Field::U32("occurrenceCount", 16),
+        Field::Text("sourceFile", 24, 32),
+        Field::Text("message", 56, 64),
+    ],
+};
+
+const DRAWLIST_RECORD: PayloadLayout = PayloadLayout {
+    size: 48,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U32("totalBytes", 8),
+        Field::U32("cmdCount", 12),
+        Field::U32("version", 16),
+        Field::U32("validationResult", 20),
+        Field::U32("executionResult", 24),
+        Field::U32("clipStackMaxDepth", 28),
+        Field::U32("textRuns", 32),
+        Field::U32("fillRects", 36),
+    ],
+};
+
+const PERF_RECORD: PayloadLayout = PayloadLayout {
+    size: 24,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U32("phase", 8),
+        Field::U32("usElapsed", 12),
+        Field::U32("bytesProcessed", 16),
+    ],
+};
+
+/* zr_diff_telemetry_record_t (zr_engine_present.inc). */
+const DIFF_PATH_RECORD: PayloadLayout = PayloadLayout {
+    size: 64,
+    fields: &[
+        Field::U64("frameId", 0),
+        Field::U64("sweepFramesTotal", 8),
+        Field::U64("damageFramesTotal", 16),
+        Field::U64("scrollAttemptsTotal", 24),
+        Field::U64("scrollHitsTotal", 32),
+        Field::U64("collisionGuardHitsTotal", 40),
+        Field::Flag("sweepUsed", 48),
+        Field::Flag("damageUsed", 49),
+        Field::Flag("scrollOptAttempted", 50),
+        Field::Flag("scrollOptHit", 51),
+        Field::U32("collisionGuardHits", 52),
+    ],
+};
+
+fn debug_code_name(code: u32) -> Option<&'static str> {
+    DEBUG_CODE_NAMES
+        .iter()
+        .find(|(value, _)| *value == code)
+        .map(|(_, name)| *name)
+}
+
+/// The struct a record with `code` carries. Drawlist bytes and state
+/// changes have none.
+fn payload_layout(code: u32) -> Option<&'static PayloadLayout> {
+    match code {
+        0x0100..=0x0103 => Some(&FRAME_RECORD),
+        0x0200..=0x0203 => Some(&EVENT_RECORD),
+        0x0300 | 0x0301 => Some(&DRAWLIST_RECORD),
+        0x0400..=0x0403 => Some(&ERROR_RECORD),
+        0x0600 => Some(&PERF_RECORD),
+        ffi::ZR_DEBUG_CODE_PERF_DIFF_PATH => Some(&DIFF_PATH_RECORD),
+        _ => None,
+    }
+}
+
+fn read_u32(bytes: &[u8], off: usize) -> u32 {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&bytes[off..off + 4]);
+    u32::from_le_bytes(b)
+}
+
+fn read_u64(bytes: &[u8], off: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&bytes[off..off + 8]);
+    u64::from_le_bytes(b)
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_base64(out: &mut String, bytes: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+fn push_payload(out: &mut String, layout: &PayloadLayout, payload: &[u8]) {
+    out.push('{');
+    for (i, field) in layout.fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let (name, value) = match *field {
+            Field::U32(name, off) => (name, read_u32(payload, off).to_string()),
+            Field::U64(name, off) => (name, read_u64(payload, off).to_string()),
+            Field::Flag(name, off) => (name, (payload[off] != 0).to_string()),
+            Field::Text(name, off, len) => {
+                let raw = &payload[off..off + len];
+                let end = raw.iter().position(|&b| b == 0).unwrap_or(len);
+                let mut s = String::new();
+                push_json_string(&mut s, &String::from_utf8_lossy(&raw[..end]));
+                (name, s)
+            }
+        };
+        push_json_string(out, name);
+        out.push(':');
+        out.push_str(&value);
+    }
+    out.push('}');
+}
+
+/// Render an `engineDebugExport` blob as the `engineDebugExportJson`
+/// document. Fails on a record cut short.
+pub(crate) fn debug_export_to_json(blob: &[u8]) -> Result<String, String> {
+    let mut out = String::with_capacity(64 + blob.len() * 4);
+    let _ = write!(out, "{{\"version\":{DEBUG_JSON_VERSION},\"records\":[");
+    let mut off = 0usize;
+    let mut first = true;
+    while off < blob.len() {
+        if blob.len() - off < HEADER_BYTES {
+            return Err(format!(
+                "debug export: truncated record header at byte {off}"
+            ));
+        }
+        let h = &blob[off..off + HEADER_BYTES];
+        let category = read_u32(h, 24);
+        let severity = read_u32(h, 28);
+        let code = read_u32(h, 32);
+        let payload_size = read_u32(h, 36) as usize;
+        let start = off + HEADER_BYTES;
+        if blob.len() - start < payload_size {
+            return Err(format!("debug export: truncated payload at byte {start}"));
+        }
+        let payload = &blob[start..start + payload_size];
+        off = start + payload_size;
+
+        if !first {
+            out.push(',');
+        }
+        first = false;
+        let _ = write!(
+            out,
+            "{{\"recordId\":{},\"timestampUs\":{},\"frameId\":{},\"category\":{category},\"categoryName\":",
+            read_u64(h, 0),
+            read_u64(h, 8),
+            read_u64(h, 16),
+        );
+        push_json_string(&mut out, debug_category_name(category).unwrap_or("unknown"));
+        let _ = write!(out, ",\"severity\":{severity},\"severityName\":");
+        push_json_string(&mut out, debug_severity_name(severity).unwrap_or("unknown"));
+        let _ = write!(out, ",\"code\":{code},\"codeName\":");
+        push_json_string(&mut out, debug_code_name(code).unwrap_or("unknown"));
+        let _ = write!(out, ",\"payloadSize\":{payload_size},\"payload\":");
+        match payload_layout(code).filter(|layout| payload.len() >= layout.size) {
+            Some(layout) => push_payload(&mut out, layout, payload),
+            None => {
+                out.push_str("null,\"payloadBase64\":\"");
+                push_base64(&mut out, payload);
+                out.push('"');
+            }
+        }
+        out.push('}');
+    }
+    out.push_str("]}");
+    Ok(out)
+}
+
+/// The whole debug ring as a JSON document: `{ version, records }`, each
+/// record carrying its header fields with category, severity and code names,
+/// and `payload` decoded into named fields. Payloads without a known layout
+/// (captured drawlist bytes, state changes) come back as `payload: null`
+/// plus `payloadBase64`. Empty `records` when tracing is off.
+#[napi(js_name = "engineDebugExportJson")]
+pub fn engine_debug_export_json(engine_id: u32) -> napi::Result<String, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugExportJson")?;
+
+    let query = ffi::zr_debug_query_t {
+        min_record_id: 0,
+        max_record_id: 0,
+        min_frame_id: 0,
+        max_frame_id: 0,
+        category_mask: 0xFFFF_FFFF,
+        min_severity: 0,
+        max_records: 0,
+        _pad0: 0,
+    };
+    let headers = query_debug_headers(guard.slot.engine, &query, "engineDebugExportJson")?;
+    let size = usize::try_from(debug_export_size(&headers))
+        .ok()
+        .filter(|&size| size <= i32::MAX as usize)
+        .ok_or_else(|| zr_error(ffi::ZR_ERR_LIMIT, "engineDebugExportJson: trace too large"))?;
+    let mut blob = vec![0u8; size];
+    if size != 0 {
+        let rc = unsafe { ffi::engine_debug_export(guard.slot.engine, blob.as_mut_ptr(), size) };
+        if rc < 0 {
+            return Err(zr_result_error(rc, "engineDebugExportJson"));
+        }
+        blob.truncate(rc as usize);
+    }
+    debug_export_to_json(&blob).map_err(|msg| zr_error(ffi::ZR_ERR_FORMAT, msg))
+}
@@ -0,0 +1,242 @@
+use crate::debug::{debug_record_to_js, query_debug_headers, DebugRecord};
+use crate::error::{napi_error, ZrCode};
+use crate::{ffi, owner_engine_guard};
+use napi::bindgen_prelude::Uint8Array;
+use napi::threadsafe_function::{
+    ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction, ThreadsafeFunctionCallMode,
+};
+use napi::{Env, JsFunction, Status};
+use napi_derive::napi;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Records queued for a subscriber and not yet handed to its callback. Past
+/// this, new records are dropped instead of blocking the engine.
+const DEBUG_SUBSCRIBE_QUEUE: usize = 1024;
+
+/// One trace record as delivered to an `engineDebugSubscribe` callback.
+#[napi(object)]
+pub struct DebugTraceEvent {
+    pub header: DebugRecord,
+    pub payload: Uint8Array,
+    /// Records this subscription has dropped so far, because the callback
+    /// fell behind or the ring overwrote them before delivery.
+    pub dropped: u32,
+}
+
+struct TraceDelivery {
+    header: ffi::zr_debug_record_header_t,
+    payload: Vec<u8>,
+    dropped: u32,
+}
+
+/// Where delivery resumes in the engine's record id sequence.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TraceCursor {
+    pub(crate) next_id: u64,
+    pub(crate) dropped: u32,
+}
+
+impl TraceCursor {
+    pub(crate) fn new(next_id: u64) -> Self {
+        Self {
+            next_id,
+            dropped: 0,
+        }
+    }
+
+    /// Ids restart at 1 when tracing is re-enabled or reset.
+    pub(crate) fn rewind(&mut self) {
+        self.next_id = 1;
+    }
+
+    /// Account for the gap before `record_id` (records the ring overwrote)
+    /// and move past it.
+    pub(crate) fn advance(&mut self, record_id: u64) {
+        let skipped = record_id.saturating_sub(self.next_id);
+        self.note_dropped(skipped);
+        self.next_id = record_id.saturating_add(1);
+    }
+
+    pub(crate) fn note_dropped(&mut self, count: u64) {
+        self.dropped = self
+            .dropped
+            .saturating_add(u32::try_from(count).unwrap_or(u32::MAX));
+    }
+}
+
+/// Where records go. Boxed so only `engineDebugSubscribe` instantiates the
+/// threadsafe-function code, which needs the Node runtime to link.
+trait TraceSink: Send {
+    /// `false` when the record could not be queued.
+    fn send(&self, delivery: TraceDelivery) -> bool;
+}
+
+impl TraceSink for ThreadsafeFunction<TraceDelivery, ErrorStrategy::Fatal> {
+    fn send(&self, delivery: TraceDelivery) -> bool {
+        self.call(delivery, ThreadsafeFunctionCallMode::NonBlocking) == Status::Ok
+    }
+}
+
+struct Subscription {
+    callback: Box<dyn TraceSink>,
+    cursor: TraceCursor,
+}
+
+/// The `engineDebugSubscribe` callback of an engine, fed after every call
+/// that can produce trace records.
+#[derive(Default)]
+pub(crate) struct DebugSubscriber {
+    active: AtomicBool,
+    inner: Mutex<Option<Subscription>>,
+}
+
+impl DebugSubscriber {
+    fn lock(&self) -> MutexGuard<'_, Option<Subscription>> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    fn set(&self, sub: Option<Subscription>) {
+        let mut inner = self.lock();
+        self.active.store(sub.is_some(), Ordering::Relaxed);
+        *inner = sub;
+    }
+
+    /// Drop the subscription; the callback is released.
+    pub(crate) fn clear(&self) {
+        self.set(None);
+    }
+
+    /// Let go of the subscription without releasing the callback. At process
+    /// exit the JS environment is already torn down, and releasing would
+    /// call into it.
+    pub(crate) fn abandon(&self) {
+        let mut inner = self.lock();
+        self.active.store(false, Ordering::Relaxed);
+        std::mem::forget(inner.take());
+    }
+
+    /// Tracing was re-enabled or reset, so record ids start over.
+    pub(crate) fn rewind(&self) {
+        if let Some(sub) = self.lock().as_mut() {
+            sub.cursor.rewind();
+        }
+    }
+
+    /// Queue every record produced since the last delivery. Never blocks:
+    /// records the callback queue cannot take are counted as dropped.
+    pub(crate) fn deliver(&self, engine: *mut ffi::zr_engine_t) {
+        if !self.active.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut inner = self.lock();
+        let Some(sub) = inner.as_mut() else {
+            return;
+        };
+        let query = ffi::zr_debug_query_t {
+            min_record_id: sub.cursor.next_id,
+            max_record_id: 0,
+            min_frame_id: 0,
+            max_frame_id: 0,
+            category_mask: 0xFFFF_FFFF,
+            min_severity: 0,
+            max_records: 0,
+            _pad0: 0,
+        };
+        let Ok(mut headers) = query_debug_headers(engine, &query, "engineDebugSubscribe") else {
+            return;
+        };
+        /* The ring hands records back in slot order, not id order. */
+        headers.sort_unstable_by_key(|h| h.record_id);
+        for header in headers {
+            sub.cursor.advance(header.record_id);
+            let mut payload = vec![0u8; header.payload_size as usize];
+            let mut size = 0u32;
+            let rc = unsafe {
+                ffi::engine_debug_get_payload(
+                    engine,
+                    header.record_id,
+                    payload.as_mut_ptr(),
+                    payload.len() as u32,
+                    &mut size as *mut _,
+                )
+            };
+            if rc != ffi::ZR_OK {
+                sub.cursor.note_dropped(1);
+                continue;
+            }
+            payload.truncate(size as usize);
+            let delivery = TraceDelivery {
+                header,
+                payload,
+                dropped: sub.cursor.dropped,
+            };
+            if !sub.callback.send(delivery) {
+                sub.cursor.note_dropped(1);
+            }
+        }
+    }
+}
+
+/// Call `callback` with `{ header, payload, dropped }` for each trace record
+/// the engine produces from now on, as captured under the `engineDebugEnable`
+/// `categoryMask`/`minSeverity`. Records are collected after each submit,
+/// present and poll and delivered asynchronously on this thread's event
+/// loop; a callback that falls behind loses records (counted in `dropped`)
+/// rather than stalling the engine. Replaces any earlier subscription and
+/// does not keep the process alive.
+#[napi(js_name = "engineDebugSubscribe")]
+pub fn engine_debug_subscribe(
+    env: Env,
+    engine_id: u32,
+    callback: JsFunction,
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugSubscribe")?;
+
+    let mut stats = ffi::zr_debug_stats_t {
+        total_records: 0,
+        total_dropped: 0,
+        error_count: 0,
+        warn_count: 0,
+        current_ring_usage: 0,
+        ring_capacity: 0,
+    };
+    let rc = unsafe { ffi::engine_debug_get_stats(guard.slot.engine, &mut stats as *mut _) };
+    let next_id = if rc == ffi::ZR_OK {
+        stats.total_records.saturating_add(1)
+    } else {
+        1
+    };
+    let mut callback: ThreadsafeFunction<TraceDelivery, ErrorStrategy::Fatal> = callback
+        .create_threadsafe_function(
+            DEBUG_SUBSCRIBE_QUEUE,
+            |ctx: ThreadSafeCallContext<TraceDelivery>| {
+                let d = ctx.value;
+                Ok(vec![DebugTraceEvent {
+                    header: debug_record_to_js(&d.header),
+                    payload: Uint8Array::new(d.payload),
+                    dropped: d.dropped,
+                }])
+            },
+        )
+        .map_err(napi_error)?;
+    callback.unref(&env).map_err(napi_error)?;
+    guard.slot.debug_sub.set(Some(Subscription {
+        callback: Box::new(callback),
+        cursor: TraceCursor::new(next_id),
+    }));
+    Ok(())
+}
+
+/// Stop delivering trace records to the `engineDebugSubscribe` callback.
+/// Records already queued are still delivered. Destroying the engine
+/// unsubscribes too.
+#[napi(js_name = "engineDebugUnsubscribe")]
+pub fn engine_debug_unsubscribe(engine_id: u32) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugUnsubscribe")?;
+    guard.slot.debug_sub.clear();
+    Ok(())
+}
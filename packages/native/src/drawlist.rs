@@ -0,0 +1,451 @@
+use crate::ffi;
+
+/* Wire layout (little-endian, see zr_drawlist.h). */
+const DL_MAGIC: u32 = 0x4C44_525A;
+const DL_HEADER_BYTES: usize = 64;
+const DL_HEADER_VERSION: usize = 4;
+const DL_HEADER_SIZE: usize = 8;
+const DL_HEADER_TOTAL_SIZE: usize = 12;
+const DL_HEADER_CMD_OFFSET: usize = 16;
+const DL_HEADER_CMD_BYTES: usize = 20;
+const DL_HEADER_CMD_COUNT: usize = 24;
+const DL_CMD_HEADER_BYTES: usize = 8;
+
+/* Offset of `attrs` inside a command / text-run segment (style = fg, bg, attrs, ...). */
+const DL_FILL_RECT_ATTRS_OFFSET: usize = DL_CMD_HEADER_BYTES + 16 + 8;
+const DL_DRAW_TEXT_ATTRS_OFFSET: usize = DL_CMD_HEADER_BYTES + 20 + 8;
+const DL_TEXT_RUN_BLOB_ID_OFFSET: usize = DL_CMD_HEADER_BYTES + 8;
+const DL_DEF_RESOURCE_DATA_OFFSET: usize = DL_CMD_HEADER_BYTES + 8;
+const DL_TEXT_RUN_SEGMENT_BYTES: usize = 40;
+const DL_TEXT_RUN_SEGMENT_ATTRS_OFFSET: usize = 8;
+
+/// A command in the stream as (opcode, byte offset, size).
+pub(crate) type CmdSpan = (u16, usize, usize);
+
+/// How an attribute the terminal cannot render is presented.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum AttrFallback {
+    /// Drop the attribute silently (engine default).
+    #[default]
+    Drop,
+    /// Substitute underline so the styled span stays distinguishable.
+    Underline,
+    /// Substitute dim so the styled span stays distinguishable.
+    Dim,
+}
+
+impl AttrFallback {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "drop" => Some(Self::Drop),
+            "underline" => Some(Self::Underline),
+            "dim" => Some(Self::Dim),
+            _ => None,
+        }
+    }
+
+    fn substitute_attr(self) -> u32 {
+        match self {
+            Self::Drop => 0,
+            Self::Underline => ffi::ZR_STYLE_ATTR_UNDERLINE,
+            Self::Dim => ffi::ZR_STYLE_ATTR_DIM,
+        }
+    }
+}
+
+/// Apply `policy` to a style's attrs given the terminal's supported SGR mask.
+///
+/// Unsupported attributes are left in place (the diff masks them out); the
+/// substitute is only added when at least one requested attribute is missing
+/// and the substitute itself is supported.
+pub(crate) fn fallback_attrs(attrs: u32, supported: u32, policy: AttrFallback) -> u32 {
+    let missing = attrs & ffi::ZR_STYLE_ATTR_ALL_MASK & !supported;
+    if missing == 0 {
+        return attrs;
+    }
+    let substitute = policy.substitute_attr();
+    if substitute & supported == 0 {
+        return attrs;
+    }
+    attrs | substitute
+}
+
+fn read_u16(bytes: &[u8], off: usize) -> Option<u16> {
+    let b = bytes.get(off..off.checked_add(2)?)?;
+    Some(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], off: usize) -> Option<u32> {
+    let b = bytes.get(off..off.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn map_u32(bytes: &mut [u8], off: usize, f: &mut impl FnMut(u32) -> u32) -> Option<()> {
+    let v = read_u32(bytes, off)?;
+    bytes[off..off + 4].copy_from_slice(&f(v).to_le_bytes());
+    Some(())
+}
+
+/// Stable name of a drawlist opcode, or `None` for opcodes this binding does
+/// not know.
+pub(crate) fn dl_opcode_name(opcode: u16) -> Option<&'static str> {
+    Some(match opcode {
+        ffi::ZR_DL_OP_CLEAR => "CLEAR",
+        ffi::ZR_DL_OP_FILL_RECT => "FILL_RECT",
+        ffi::ZR_DL_OP_DRAW_TEXT => "DRAW_TEXT",
+        ffi::ZR_DL_OP_PUSH_CLIP => "PUSH_CLIP",
+        ffi::ZR_DL_OP_POP_CLIP => "POP_CLIP",
+        ffi::ZR_DL_OP_DRAW_TEXT_RUN => "DRAW_TEXT_RUN",
+        ffi::ZR_DL_OP_SET_CURSOR => "SET_CURSOR",
+        ffi::ZR_DL_OP_DRAW_CANVAS => "DRAW_CANVAS",
+        ffi::ZR_DL_OP_DRAW_IMAGE => "DRAW_IMAGE",
+        ffi::ZR_DL_OP_DEF_STRING => "DEF_STRING",
+        ffi::ZR_DL_OP_FREE_STRING => "FREE_STRING",
+        ffi::ZR_DL_OP_DEF_BLOB => "DEF_BLOB",
+        ffi::ZR_DL_OP_FREE_BLOB => "FREE_BLOB",
+        ffi::ZR_DL_OP_BLIT_RECT => "BLIT_RECT",
+        _ => return None,
+    })
+}
+
+/// Walk the command stream as (opcode, byte offset, size), stopping at the
+/// declared count or where the framing breaks. Returns the declared count and
+/// the well-framed prefix; `None` when the header itself cannot be read.
+pub(crate) fn walk_cmds(bytes: &[u8]) -> Option<(usize, Vec<CmdSpan>)> {
+    if bytes.len() < DL_HEADER_BYTES {
+        return None;
+    }
+    let cmd_offset = read_u32(bytes, DL_HEADER_CMD_OFFSET)? as usize;
+    let cmd_bytes = read_u32(bytes, DL_HEADER_CMD_BYTES)? as usize;
+    let cmd_count = read_u32(bytes, DL_HEADER_CMD_COUNT)? as usize;
+    let end = cmd_offset.checked_add(cmd_bytes)?.min(bytes.len());
+
+    let mut spans = Vec::with_capacity(cmd_count.min(cmd_bytes / DL_CMD_HEADER_BYTES));
+    let mut off = cmd_offset;
+    for _ in 0..cmd_count {
+        let (Some(opcode), Some(size)) = (read_u16(bytes, off), read_u32(bytes, off + 4)) else {
+            break;
+        };
+        let size = size as usize;
+        if size < DL_CMD_HEADER_BYTES || off.checked_add(size).is_none_or(|e| e > end) {
+            break;
+        }
+        spans.push((opcode, off, size));
+        off += size;
+    }
+    Some((cmd_count, spans))
+}
+
+/// Locate every command in the stream as (opcode, byte offset, size).
+fn cmd_spans(bytes: &[u8]) -> Option<Vec<CmdSpan>> {
+    let cmd_offset = read_u32(bytes, DL_HEADER_CMD_OFFSET)? as usize;
+    let cmd_bytes = read_u32(bytes, DL_HEADER_CMD_BYTES)? as usize;
+    if cmd_offset.checked_add(cmd_bytes)? > bytes.len() {
+        return None;
+    }
+    let (count, spans) = walk_cmds(bytes)?;
+    (spans.len() == count).then_some(spans)
+}
+
+/// Copy of `bytes` keeping only the first `count` commands of `spans`, with
+/// the header rewritten so the result is a self-consistent drawlist.
+pub(crate) fn drawlist_prefix(bytes: &[u8], spans: &[CmdSpan], count: usize) -> Vec<u8> {
+    let mut out = bytes[..DL_HEADER_BYTES].to_vec();
+    for &(_, off, size) in &spans[..count] {
+        out.extend_from_slice(&bytes[off..off + size]);
+    }
+    let cmd_bytes = (out.len() - DL_HEADER_BYTES) as u32;
+    let cmd_offset = if count == 0 {
+        0
+    } else {
+        DL_HEADER_BYTES as u32
+    };
+    let total = out.len() as u32;
+    out[DL_HEADER_TOTAL_SIZE..DL_HEADER_TOTAL_SIZE + 4].copy_from_slice(&total.to_le_bytes());
+    out[DL_HEADER_CMD_OFFSET..DL_HEADER_CMD_OFFSET + 4].copy_from_slice(&cmd_offset.to_le_bytes());
+    out[DL_HEADER_CMD_BYTES..DL_HEADER_CMD_BYTES + 4].copy_from_slice(&cmd_bytes.to_le_bytes());
+    out[DL_HEADER_CMD_COUNT..DL_HEADER_CMD_COUNT + 4]
+        .copy_from_slice(&(count as u32).to_le_bytes());
+    out
+}
+
+/// Drawlist of format `version` holding a single CLEAR command.
+pub(crate) fn clear_drawlist(version: u32) -> Vec<u8> {
+    let total = DL_HEADER_BYTES + DL_CMD_HEADER_BYTES;
+    let mut out = vec![0u8; total];
+    let mut put = |off: usize, v: u32| out[off..off + 4].copy_from_slice(&v.to_le_bytes());
+    put(0, DL_MAGIC);
+    put(DL_HEADER_VERSION, version);
+    put(DL_HEADER_SIZE, DL_HEADER_BYTES as u32);
+    put(DL_HEADER_TOTAL_SIZE, total as u32);
+    put(DL_HEADER_CMD_OFFSET, DL_HEADER_BYTES as u32);
+    put(DL_HEADER_CMD_BYTES, DL_CMD_HEADER_BYTES as u32);
+    put(DL_HEADER_CMD_COUNT, 1);
+    put(DL_HEADER_BYTES, u32::from(ffi::ZR_DL_OP_CLEAR));
+    put(DL_HEADER_BYTES + 4, DL_CMD_HEADER_BYTES as u32);
+    out
+}
+
+/// Rewrite every style `attrs` field in a drawlist in place.
+///
+/// Covers FILL_RECT, DRAW_TEXT and the segments of text-run blobs defined in
+/// the same drawlist and drawn by DRAW_TEXT_RUN. Blobs defined by an earlier
+/// submit are engine-owned and are not revisited. Returns `false` without
+/// guaranteeing which fields were touched when the framing is malformed; the
+/// caller is expected to submit the original bytes so the engine reports the
+/// precise validation error.
+pub(crate) fn rewrite_style_attrs(bytes: &mut [u8], mut f: impl FnMut(u32) -> u32) -> bool {
+    let Some(spans) = cmd_spans(bytes) else {
+        return false;
+    };
+
+    let mut text_run_blobs = Vec::new();
+    for &(opcode, off, size) in &spans {
+        if opcode == ffi::ZR_DL_OP_DRAW_TEXT_RUN {
+            match read_u32(&bytes[off..off + size], DL_TEXT_RUN_BLOB_ID_OFFSET) {
+                Some(id) => text_run_blobs.push(id),
+                None => return false,
+            }
+        }
+    }
+
+    for (opcode, off, size) in spans {
+        let cmd = &mut bytes[off..off + size];
+        let ok = match opcode {
+            ffi::ZR_DL_OP_FILL_RECT => map_u32(cmd, DL_FILL_RECT_ATTRS_OFFSET, &mut f).is_some(),
+            ffi::ZR_DL_OP_DRAW_TEXT => map_u32(cmd, DL_DRAW_TEXT_ATTRS_OFFSET, &mut f).is_some(),
+            ffi::ZR_DL_OP_DEF_BLOB => rewrite_text_run_blob(cmd, &text_run_blobs, &mut f),
+            _ => true,
+        };
+        if !ok {
+            return false;
+        }
+    }
+    true
+}
+
+fn rewrite_text_run_blob(
+    cmd: &mut [u8],
+    text_run_blobs: &[u32],
+    f: &mut impl FnMut(u32) -> u32,
+) -> bool {
+    let (Some(id), Some(len)) = (
+        read_u32(cmd, DL_CMD_HEADER_BYTES),
+        read_u32(cmd, DL_CMD_HEADER_BYTES + 4),
+    ) else {
+        return false;
+    };
+    if !text_run_blobs.contains(&id) {
+        return true;
+    }
+    let Some(blob) =
+        cmd.get_mut(DL_DEF_RESOURCE_DATA_OFFSET..DL_DEF_RESOURCE_DATA_OFFSET + len as usize)
+    else {
+        return false;
+    };
+    let Some(seg_count) = read_u32(blob, 0) else {
+        return false;
+    };
+    let expected = (seg_count as usize)
+        .checked_mul(DL_TEXT_RUN_SEGMENT_BYTES)
+        .and_then(|n| n.checked_add(4));
+    if expected != Some(blob.len()) {
+        return false;
+    }
+    for i in 0..seg_count as usize {
+        let off = 4 + i * DL_TEXT_RUN_SEGMENT_BYTES + DL_TEXT_RUN_SEGMENT_ATTRS_OFFSET;
+        if map_u32(blob, off, f).is_none() {
+            return false;
+        }
+    }
+    true
+}
+
+/// The first drawlist limit a drawlist exceeds.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct LimitViolation {
+    /// `EngineLimits` field name, e.g. `dlMaxClipDepth`.
+    pub(crate) limit: &'static str,
+    /// Command that crossed the limit; `None` for whole-drawlist limits.
+    pub(crate) command_index: Option<u32>,
+    pub(crate) detail: String,
+}
+
+/// How much of each drawlist limit a drawlist uses.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct DrawlistCounts {
+    pub(crate) total_bytes: u32,
+    pub(crate) cmds: u32,
+    /// Distinct string ids defined and still live at the end.
+    pub(crate) strings: u32,
+    pub(crate) blobs: u32,
+    pub(crate) max_clip_depth: u32,
+    /// Largest text run drawn from a blob defined in the same drawlist.
+    pub(crate) max_text_run_segments: u32,
+}
+
+/// Check `bytes` against each drawlist limit in the order the engine does,
+/// naming the limit and the command that crossed it. Only the well-framed
+/// command prefix is walked, and strings and blobs count only this
+/// drawlist's definitions: ones left over from earlier submits also count
+/// towards the engine's `dlMaxStrings`/`dlMaxBlobs`.
+pub(crate) fn find_limit_violation(
+    bytes: &[u8],
+    limits: &ffi::zr_limits_t,
+) -> Option<LimitViolation> {
+    scan_drawlist(bytes, limits).err()
+}
+
+/// Usage of a drawlist the engine accepted, counted by the same rules as
+/// [`find_limit_violation`].
+pub(crate) fn drawlist_counts(bytes: &[u8]) -> DrawlistCounts {
+    let unlimited = ffi::zr_limits_t {
+        arena_max_total_bytes: 0,
+        arena_initial_bytes: 0,
+        out_max_bytes_per_frame: 0,
+        dl_max_total_bytes: u32::MAX,
+        dl_max_cmds: u32::MAX,
+        dl_max_strings: u32::MAX,
+        dl_max_blobs: u32::MAX,
+        dl_max_clip_depth: u32::MAX,
+        dl_max_text_run_segments: u32::MAX,
+        diff_max_damage_rects: 0,
+    };
+    scan_drawlist(bytes, &unlimited).unwrap_or_default()
+}
+
+fn scan_drawlist(
+    bytes: &[u8],
+    limits: &ffi::zr_limits_t,
+) -> Result<DrawlistCounts, LimitViolation> {
+    let violation = |limit, command_index: Option<usize>, detail: String| LimitViolation {
+        limit,
+        command_index: command_index.map(|i| i as u32),
+        detail,
+    };
+    if bytes.len() > limits.dl_max_total_bytes as usize {
+        return Err(violation(
+            "dlMaxTotalBytes",
+            None,
+            format!(
+                "drawlist is {} bytes, limit is {}",
+                bytes.len(),
+                limits.dl_max_total_bytes
+            ),
+        ));
+    }
+    let mut usage = DrawlistCounts {
+        total_bytes: bytes.len() as u32,
+        ..DrawlistCounts::default()
+    };
+    let Some((count, spans)) = walk_cmds(bytes) else {
+        return Ok(usage);
+    };
+    if count > limits.dl_max_cmds as usize {
+        return Err(violation(
+            "dlMaxCmds",
+            Some(limits.dl_max_cmds as usize),
+            format!(
+                "drawlist declares {count} commands, limit is {}",
+                limits.dl_max_cmds
+            ),
+        ));
+    }
+    usage.cmds = count as u32;
+
+    let mut clip_depth = 0u32;
+    let mut strings: Vec<u32> = Vec::new();
+    let mut blobs: Vec<(u32, Option<u32>)> = Vec::new();
+    for (i, &(opcode, off, size)) in spans.iter().enumerate() {
+        let cmd = &bytes[off..off + size];
+        match opcode {
+            ffi::ZR_DL_OP_PUSH_CLIP => {
+                clip_depth += 1;
+                if clip_depth > limits.dl_max_clip_depth {
+                    return Err(violation(
+                        "dlMaxClipDepth",
+                        Some(i),
+                        format!(
+                            "PUSH_CLIP nests {clip_depth} deep, limit is {}",
+                            limits.dl_max_clip_depth
+                        ),
+                    ));
+                }
+                usage.max_clip_depth = usage.max_clip_depth.max(clip_depth);
+            }
+            ffi::ZR_DL_OP_POP_CLIP => clip_depth = clip_depth.saturating_sub(1),
+            ffi::ZR_DL_OP_DEF_STRING => {
+                let Some(id) = read_u32(cmd, DL_CMD_HEADER_BYTES) else {
+                    break;
+                };
+                if !strings.contains(&id) {
+                    strings.push(id);
+                }
+                if strings.len() > limits.dl_max_strings as usize {
+                    return Err(violation(
+                        "dlMaxStrings",
+                        Some(i),
+                        format!(
+                            "DEF_STRING defines string {} of at most {}",
+                            strings.len(),
+                            limits.dl_max_strings
+                        ),
+                    ));
+                }
+            }
+            ffi::ZR_DL_OP_FREE_STRING => {
+                let id = read_u32(cmd, DL_CMD_HEADER_BYTES);
+                strings.retain(|&s| Some(s) != id);
+            }
+            ffi::ZR_DL_OP_DEF_BLOB => {
+                let Some(id) = read_u32(cmd, DL_CMD_HEADER_BYTES) else {
+                    break;
+                };
+                let segments = read_u32(cmd, DL_DEF_RESOURCE_DATA_OFFSET);
+                match blobs.iter_mut().find(|(b, _)| *b == id) {
+                    Some(blob) => blob.1 = segments,
+                    None => blobs.push((id, segments)),
+                }
+                if blobs.len() > limits.dl_max_blobs as usize {
+                    return Err(violation(
+                        "dlMaxBlobs",
+                        Some(i),
+                        format!(
+                            "DEF_BLOB defines blob {} of at most {}",
+                            blobs.len(),
+                            limits.dl_max_blobs
+                        ),
+                    ));
+                }
+            }
+            ffi::ZR_DL_OP_FREE_BLOB => {
+                let id = read_u32(cmd, DL_CMD_HEADER_BYTES);
+                blobs.retain(|&(b, _)| Some(b) != id);
+            }
+            ffi::ZR_DL_OP_DRAW_TEXT_RUN => {
+                let id = read_u32(cmd, DL_TEXT_RUN_BLOB_ID_OFFSET);
+                let segments = blobs
+                    .iter()
+                    .find(|(b, _)| Some(*b) == id)
+                    .and_then(|&(_, segments)| segments);
+                if let Some(segments) = segments {
+                    if segments > limits.dl_max_text_run_segments {
+                        return Err(violation(
+                            "dlMaxTextRunSegments",
+                            Some(i),
+                            format!(
+                                "DRAW_TEXT_RUN draws {segments} segments, limit is {}",
+                                limits.dl_max_text_run_segments
+                            ),
+                        ));
+                    }
+                    usage.max_text_run_segments = usage.max_text_run_segments.max(segments);
+                }
+            }
+            _ => {}
+        }
+    }
+    usage.strings = strings.len() as u32;
+    usage.blobs = blobs.len() as u32;
+    Ok(usage)
+}
@@ -0,0 +1,136 @@
+use crate::error::{zr_result_error, ZrCode};
+use crate::{empty_metrics, ffi, owner_engine_guard};
+
+use napi_derive::napi;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/* zr_event.h: batch header flags live at byte 16. */
+const EV_BATCH_FLAGS_OFFSET: usize = 16;
+const EV_BATCH_TRUNCATED: u32 = 1 << 0;
+
+/* ZR_ENGINE_USER_BYTES_CAP in zr_engine.c: the user/paste payload ring size. */
+pub(crate) const ENGINE_USER_BYTES_CAP: usize = 64 * 1024;
+
+/// Why events were lost or held back, per engine.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct DroppedEventReasons {
+    /// Events the engine evicted or refused because its queue was full
+    /// (`eventsDroppedTotal`). Raise the poll rate or the queue limit.
+    pub queueFull: u32,
+    /// `enginePostUserEvent` calls refused because the queue or the payload
+    /// ring was full.
+    pub userQueueFull: u32,
+    /// `enginePostUserEvent` calls refused because the payload exceeds the
+    /// engine's 64 KiB payload ring.
+    pub userPayloadTooLarge: u32,
+    /// Polls whose batch was truncated: the buffer filled up and the rest
+    /// stayed queued. Not a loss by itself, but sustained truncation leads to
+    /// `queueFull`. Use a bigger poll buffer.
+    pub pollTruncated: u32,
+    /// Polls refused because the buffer cannot hold even the batch header.
+    pub pollBufferTooSmall: u32,
+    /// Input sequences the parser could not decode (`inputParseErrorsTotal`).
+    /// Their bytes are delivered as plain keys or dropped, not as the
+    /// intended event.
+    pub parseErrors: u32,
+    /// Bracketed pastes dropped because they exceed the 64 KiB payload ring
+    /// (`pasteDroppedTotal`).
+    pub pasteTooLarge: u32,
+}
+
+/// Binding-side classification of poll and post outcomes; the engine counts
+/// queue overflow, parse errors and paste drops itself.
+#[derive(Default)]
+pub(crate) struct DropCounters {
+    user_queue_full: AtomicU32,
+    user_payload_too_large: AtomicU32,
+    poll_truncated: AtomicU32,
+    poll_buffer_too_small: AtomicU32,
+    /// Raw `eventsDroppedTotal` as of the last `enginePollEventsCounted`.
+    engine_dropped_seen: AtomicU32,
+}
+
+fn bump(counter: &AtomicU32) {
+    let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+        Some(v.saturating_add(1))
+    });
+}
+
+impl DropCounters {
+    /// Classify the outcome of an `engine_poll_events` call.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8]) {
+        if rc == ffi::ZR_ERR_LIMIT {
+            bump(&self.poll_buffer_too_small);
+            return;
+        }
+        if rc <= 0 {
+            return;
+        }
+        let flags = out
+            .get(EV_BATCH_FLAGS_OFFSET..EV_BATCH_FLAGS_OFFSET + 4)
+            .map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        if flags & EV_BATCH_TRUNCATED != 0 {
+            bump(&self.poll_truncated);
+        }
+    }
+
+    /// Classify the outcome of an `engine_post_user_event` call.
+    pub(crate) fn note_user_post(&self, rc: i32, payload_len: usize) {
+        if rc != ffi::ZR_ERR_LIMIT {
+            return;
+        }
+        if payload_len > ENGINE_USER_BYTES_CAP {
+            bump(&self.user_payload_too_large);
+        } else {
+            bump(&self.user_queue_full);
+        }
+    }
+
+    /// Engine drops since the previous call, given the raw
+    /// `eventsDroppedTotal` (unaffected by `engineResetMetrics`).
+    pub(crate) fn take_engine_drops(&self, total: u32) -> u32 {
+        let seen = self.engine_dropped_seen.swap(total, Ordering::Relaxed);
+        total.saturating_sub(seen)
+    }
+
+    /// Zero every counter (`engineResetMetrics`).
+    pub(crate) fn reset(&self) {
+        for counter in [
+            &self.user_queue_full,
+            &self.user_payload_too_large,
+            &self.poll_truncated,
+            &self.poll_buffer_too_small,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Combine these counters with the engine's (rebased) loss metrics.
+    pub(crate) fn snapshot(&self, m: &ffi::zr_metrics_t) -> DroppedEventReasons {
+        DroppedEventReasons {
+            queueFull: m.events_dropped_total,
+            userQueueFull: self.user_queue_full.load(Ordering::Relaxed),
+            userPayloadTooLarge: self.user_payload_too_large.load(Ordering::Relaxed),
+            pollTruncated: self.poll_truncated.load(Ordering::Relaxed),
+            pollBufferTooSmall: self.poll_buffer_too_small.load(Ordering::Relaxed),
+            parseErrors: m.input_parse_errors_total,
+            pasteTooLarge: m.paste_dropped_total,
+        }
+    }
+}
+
+/// Break event loss down by reason, so a too-small poll buffer can be told
+/// apart from an engine queue overflow.
+#[napi(js_name = "engineDroppedEventReasons")]
+pub fn engine_dropped_event_reasons(engine_id: u32) -> napi::Result<DroppedEventReasons, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDroppedEventReasons")?;
+
+    let mut m = empty_metrics();
+    let rc = unsafe { ffi::engine_get_metrics(guard.slot.engine, &mut m as *mut _) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineDroppedEventReasons"));
+    }
+    guard.slot.metrics_baseline.apply(&mut m);
+    Ok(guard.slot.drops.snapshot(&m))
+}
@@ -0,0 +1,144 @@
+use crate::asserts::ASSERT_FAILED;
+use crate::{ffi, POLL_CANCELED};
+use napi::bindgen_prelude::{Error, Status};
+use napi_derive::napi;
+use std::collections::HashMap;
+
+/// Stand-in code for a JS exception already pending from a callback or
+/// getter; napi rethrows the original exception instead of a new error.
+const PENDING_EXCEPTION: i32 = i32::MIN;
+
+/// Codes a thrown error can carry (`PENDING_EXCEPTION` never reaches JS).
+const ERROR_CODES: [i32; 8] = [
+    ffi::ZR_ERR_INVALID_ARGUMENT,
+    ffi::ZR_ERR_OOM,
+    ffi::ZR_ERR_LIMIT,
+    ffi::ZR_ERR_UNSUPPORTED,
+    ffi::ZR_ERR_FORMAT,
+    ffi::ZR_ERR_PLATFORM,
+    POLL_CANCELED,
+    ASSERT_FAILED,
+];
+
+/// Result code behind an error the addon throws. It becomes the JS error's
+/// `code` property as its `ZR_*` name; every fallible addon function reports
+/// failure this way rather than returning the code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZrCode(pub(crate) i32);
+
+impl ZrCode {
+    pub(crate) fn name(self) -> &'static str {
+        match self.0 {
+            ffi::ZR_OK => "ZR_OK",
+            ffi::ZR_ERR_INVALID_ARGUMENT => "ZR_ERR_INVALID_ARGUMENT",
+            ffi::ZR_ERR_OOM => "ZR_ERR_OOM",
+            ffi::ZR_ERR_LIMIT => "ZR_ERR_LIMIT",
+            ffi::ZR_ERR_UNSUPPORTED => "ZR_ERR_UNSUPPORTED",
+            ffi::ZR_ERR_FORMAT => "ZR_ERR_FORMAT",
+            ffi::ZR_ERR_PLATFORM => "ZR_ERR_PLATFORM",
+            POLL_CANCELED => "POLL_CANCELED",
+            ASSERT_FAILED => "ASSERT_FAILED",
+            PENDING_EXCEPTION => Status::PendingException.as_ref(),
+            _ => "ZR_ERR_UNKNOWN",
+        }
+    }
+
+    /// What went wrong, for the message of a thrown error.
+    fn describe(self) -> &'static str {
+        match self.0 {
+            ffi::ZR_ERR_INVALID_ARGUMENT => "invalid argument, stale engine id or wrong thread",
+            ffi::ZR_ERR_OOM => "out of memory",
+            ffi::ZR_ERR_LIMIT => "a buffer or configured limit is too small",
+            ffi::ZR_ERR_UNSUPPORTED => "not supported by this engine or terminal",
+            ffi::ZR_ERR_FORMAT => "malformed input",
+            ffi::ZR_ERR_PLATFORM => "terminal I/O failed",
+            POLL_CANCELED => "poll canceled",
+            ASSERT_FAILED => "engine assertion failed",
+            _ => "unknown engine result code",
+        }
+    }
+}
+
+/// The `ZR_*` name of result code `code`, as thrown errors carry in `code`
+/// (`"ZR_ERR_UNKNOWN"` for codes the engine does not define).
+#[napi(js_name = "ziErrorName")]
+pub fn zi_error_name(code: i32) -> String {
+    match code {
+        PENDING_EXCEPTION => "ZR_ERR_UNKNOWN".to_owned(),
+        code => ZrCode(code).name().to_owned(),
+    }
+}
+
+/// Every failure code the addon throws, keyed by the name errors carry in
+/// `code`. JS callers that need the numeric code read it from here instead of
+/// keeping their own copy.
+#[napi(js_name = "ziResultCodes")]
+pub fn zi_result_codes() -> HashMap<&'static str, i32> {
+    ERROR_CODES
+        .iter()
+        .map(|&rc| (ZrCode(rc).name(), rc))
+        .collect()
+}
+
+/// Whether `code` reports success: `ZR_OK` or a non-negative count.
+#[napi(js_name = "ziErrorIsOk")]
+pub fn zi_error_is_ok(code: i32) -> bool {
+    code >= ffi::ZR_OK
+}
+
+impl AsRef<str> for ZrCode {
+    fn as_ref(&self) -> &str {
+        self.name()
+    }
+}
+
+/// Error thrown with `code` set to the name of `rc`.
+pub(crate) fn zr_error(rc: i32, reason: impl Into<String>) -> Error<ZrCode> {
+    Error::new(ZrCode(rc), reason.into())
+}
+
+/// `Ok` for a non-negative engine result (`ZR_OK` or a count), otherwise an
+/// error named after the code. Success does not allocate.
+pub(crate) fn zr_result_to_napi(rc: i32, ctx: &str) -> napi::Result<(), ZrCode> {
+    if rc >= ffi::ZR_OK {
+        return Ok(());
+    }
+    Err(zr_result_error(rc, ctx))
+}
+
+/// The error `zr_result_to_napi` throws for a failed result `rc`.
+pub(crate) fn zr_result_error(rc: i32, ctx: &str) -> Error<ZrCode> {
+    let code = ZrCode(rc);
+    zr_error(
+        rc,
+        format!("{ctx}: {} ({}, {rc})", code.describe(), code.name()),
+    )
+}
+
+/// `ZR_ERR_INVALID_ARGUMENT` error for bad input from JS.
+pub(crate) fn invalid_argument(reason: impl Into<String>) -> Error<ZrCode> {
+    zr_error(ffi::ZR_ERR_INVALID_ARGUMENT, reason)
+}
+
+/// Rethrow a napi failure (a JS value of the wrong type, a throwing getter)
+/// under the addon's codes: bad input is `ZR_ERR_INVALID_ARGUMENT`, a JS
+/// exception stays pending, anything else is `ZR_ERR_PLATFORM`.
+pub(crate) fn napi_error(err: Error) -> Error<ZrCode> {
+    let rc = match err.status {
+        Status::PendingException => PENDING_EXCEPTION,
+        Status::InvalidArg
+        | Status::ObjectExpected
+        | Status::StringExpected
+        | Status::NameExpected
+        | Status::FunctionExpected
+        | Status::NumberExpected
+        | Status::BooleanExpected
+        | Status::ArrayExpected
+        | Status::BigintExpected
+        | Status::DateExpected
+        | Status::ArrayBufferExpected
+        | Status::DetachableArraybufferExpected => ffi::ZR_ERR_INVALID_ARGUMENT,
+        _ => ffi::ZR_ERR_PLATFORM,
+    };
+    Error::new(ZrCode(rc), err.reason)
+}
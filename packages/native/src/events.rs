@@ -0,0 +1,638 @@
+use crate::config::{js_obj, js_u8_bool, validate_known_keys, ParseResult};
+use crate::debug::js_u64;
+use crate::error::{invalid_argument, zr_error, ZrCode};
+use crate::unknown::UNKNOWN_SEQUENCE_TAG;
+use crate::{ffi, owner_engine_guard};
+use napi::bindgen_prelude::{Float64Array, Uint8Array};
+use napi::{JsObject, JsUnknown, ValueType};
+use napi_derive::napi;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/* Wire layout (little-endian, see zr_event.h). */
+const EV_MAGIC: u32 = 0x5645_525A; /* 'ZREV' */
+const EV_HEADER_BYTES: usize = 24;
+const EV_RECORD_HEADER_BYTES: usize = 16;
+const EV_EVENT_COUNT: usize = 12;
+
+/* zr_ev_key_t payload offsets. */
+const KEY_KEY: usize = 0;
+const KEY_MODS: usize = 4;
+const KEY_ACTION: usize = 8;
+
+/* zr_ev_mouse_t payload offsets. */
+const MOUSE_X: usize = 0;
+const MOUSE_Y: usize = 4;
+const MOUSE_KIND: usize = 8;
+const MOUSE_MODS: usize = 12;
+const MOUSE_BUTTONS: usize = 16;
+const MOUSE_WHEEL_X: usize = 20;
+const MOUSE_WHEEL_Y: usize = 24;
+
+/* zr_ev_resize_t payload offsets. */
+const RESIZE_COLS: usize = 0;
+const RESIZE_ROWS: usize = 4;
+
+/* zr_ev_paste_t payload offsets; the UTF-8 bytes follow the fixed fields. */
+const PASTE_BYTE_LEN: usize = 0;
+const PASTE_PAYLOAD: usize = 8;
+
+/* zr_ev_user_t payload offsets; the user bytes follow the fixed fields. */
+const USER_TAG: usize = 0;
+const USER_BYTE_LEN: usize = 4;
+const USER_PAYLOAD: usize = 16;
+
+/* Focus changes arrive as key records with these pseudo-keys (zr_key_t). */
+const KEY_FOCUS_IN: u32 = 30;
+const KEY_FOCUS_OUT: u32 = 31;
+
+const MOUSE_KIND_MOVE: u32 = 1;
+const MOUSE_KIND_DRAG: u32 = 2;
+const MOUSE_KIND_WHEEL: u32 = 5;
+
+const DECODE_OPTS_KEYS: &[(&str, &str)] = &[
+    ("coalesce", "coalesce"),
+    ("firstSeq", "first_seq"),
+    ("timestampsUs", "timestamps_us"),
+];
+const COALESCE_KEYS: &[(&str, &str)] = &[
+    ("mouseMove", "mouse_move"),
+    ("mouseDrag", "mouse_drag"),
+    ("wheel", "wheel"),
+    ("resize", "resize"),
+];
+
+/// Oldest event batch version `decodeEventBatch` understands.
+#[napi]
+pub const EVENT_BATCH_DECODER_VERSION_MIN: u32 = 1;
+/// Newest event batch version `decodeEventBatch` understands.
+#[napi]
+pub const EVENT_BATCH_DECODER_VERSION_MAX: u32 = 1;
+
+/// One self-framed record from an event batch. The payload fields of its
+/// kind are decoded; fields of other kinds are absent.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EventRecord {
+    /// `"key"`, `"focus"`, `"text"`, `"paste"`, `"mouse"`, `"resize"`,
+    /// `"tick"`, `"user"` or `"unknown"`.
+    pub kind: String,
+    pub timeMs: u32,
+    pub flags: u32,
+    /// Record payload after the 16-byte record header; for `"unknown"`, the
+    /// raw bytes of the unrecognized sequence.
+    pub payload: Uint8Array,
+    /// Queue position: `opts.firstSeq` plus the record's index in the batch.
+    pub seq: f64,
+    /// Engine clock in microseconds when the record was queued, from
+    /// `opts.timestampsUs`; never decreases in queue order.
+    pub timestampUs: Option<f64>,
+    /// `"key"`: `ZR_KEY_*` code.
+    pub key: Option<u32>,
+    /// `"key"`, `"mouse"`: `ZR_MOD_*` bitmask.
+    pub mods: Option<u32>,
+    /// `"key"`: 1=down, 2=up, 3=repeat.
+    pub action: Option<u32>,
+    /// `"focus"`: whether the terminal gained focus.
+    pub focused: Option<bool>,
+    /// `"text"`: Unicode scalar value.
+    pub codepoint: Option<u32>,
+    /// `"text"`: the codepoint as a string; `"paste"`: the pasted text.
+    pub text: Option<String>,
+    /// `"mouse"`: cell column.
+    pub x: Option<i32>,
+    /// `"mouse"`: cell row.
+    pub y: Option<i32>,
+    /// `"mouse"`: 1=move, 2=drag, 3=down, 4=up, 5=wheel.
+    pub mouseKind: Option<u32>,
+    /// `"mouse"`: pressed buttons bitmask.
+    pub buttons: Option<u32>,
+    /// `"mouse"`: horizontal wheel delta.
+    pub wheelX: Option<i32>,
+    /// `"mouse"`: vertical wheel delta.
+    pub wheelY: Option<i32>,
+    /// `"resize"`: new size in cells.
+    pub cols: Option<u32>,
+    pub rows: Option<u32>,
+    /// `"tick"`: milliseconds since the previous tick.
+    pub dtMs: Option<u32>,
+    /// `"user"`: tag passed to `enginePostUserEvent`.
+    pub tag: Option<u32>,
+    /// `"user"`: bytes passed to `enginePostUserEvent`.
+    pub data: Option<Uint8Array>,
+}
+
+pub(crate) struct RawRecord {
+    pub(crate) kind: &'static str,
+    /// Position in the batch, counting records of unknown type.
+    pub(crate) index: u32,
+    pub(crate) time_ms: u32,
+    pub(crate) flags: u32,
+    pub(crate) payload: Vec<u8>,
+}
+
+fn read_u32(bytes: &[u8], off: usize) -> Option<u32> {
+    let b = bytes.get(off..off.checked_add(4)?)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_i32(bytes: &[u8], off: usize) -> Option<i32> {
+    read_u32(bytes, off).map(|v| v as i32)
+}
+
+/// Payload fields of one record, by kind; see `EventRecord`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct RecordFields {
+    /// Kind reported to JS: focus pseudo-keys become `"focus"`.
+    pub(crate) kind: &'static str,
+    pub(crate) key: Option<u32>,
+    pub(crate) mods: Option<u32>,
+    pub(crate) action: Option<u32>,
+    pub(crate) focused: Option<bool>,
+    pub(crate) codepoint: Option<u32>,
+    pub(crate) text: Option<String>,
+    pub(crate) x: Option<i32>,
+    pub(crate) y: Option<i32>,
+    pub(crate) mouse_kind: Option<u32>,
+    pub(crate) buttons: Option<u32>,
+    pub(crate) wheel_x: Option<i32>,
+    pub(crate) wheel_y: Option<i32>,
+    pub(crate) cols: Option<u32>,
+    pub(crate) rows: Option<u32>,
+    pub(crate) dt_ms: Option<u32>,
+    pub(crate) tag: Option<u32>,
+    pub(crate) data: Option<Vec<u8>>,
+}
+
+/// Decode the payload of `r` per its kind. A payload too short for its
+/// kind's fields, a length running past the payload or a codepoint that is
+/// not a Unicode scalar value is an error, never a read past the record.
+pub(crate) fn record_fields(r: &RawRecord) -> Result<RecordFields, String> {
+    let p = &r.payload;
+    let short = || {
+        format!(
+            "event record {} ({}) payload of {} bytes is too short",
+            r.index,
+            r.kind,
+            p.len()
+        )
+    };
+    let u32_at = |off: usize| read_u32(p, off).ok_or_else(short);
+    let i32_at = |off: usize| read_i32(p, off).ok_or_else(short);
+    let trailing = |len_off: usize, start: usize| -> Result<&[u8], String> {
+        let len = u32_at(len_off)? as usize;
+        p.get(start..start.checked_add(len).ok_or_else(short)?)
+            .ok_or_else(|| {
+                format!(
+                    "event record {} ({}) byte_len {len} exceeds its payload",
+                    r.index, r.kind
+                )
+            })
+    };
+    let mut f = RecordFields {
+        kind: r.kind,
+        ..RecordFields::default()
+    };
+    match r.kind {
+        "key" => {
+            let key = u32_at(KEY_KEY)?;
+            let (mods, action) = (u32_at(KEY_MODS)?, u32_at(KEY_ACTION)?);
+            if key == KEY_FOCUS_IN || key == KEY_FOCUS_OUT {
+                f.kind = "focus";
+                f.focused = Some(key == KEY_FOCUS_IN);
+            } else {
+                (f.key, f.mods, f.action) = (Some(key), Some(mods), Some(action));
+            }
+        }
+        "text" => {
+            let cp = u32_at(0)?;
+            let ch = char::from_u32(cp).ok_or_else(|| {
+                format!(
+                    "event record {} (text) has invalid codepoint 0x{cp:X}",
+                    r.index
+                )
+            })?;
+            f.codepoint = Some(cp);
+            f.text = Some(ch.to_string());
+        }
+        "paste" => {
+            let bytes = trailing(PASTE_BYTE_LEN, PASTE_PAYLOAD)?;
+            f.text = Some(String::from_utf8_lossy(bytes).into_owned());
+        }
+        "mouse" => {
+            f.x = Some(i32_at(MOUSE_X)?);
+            f.y = Some(i32_at(MOUSE_Y)?);
+            f.mouse_kind = Some(u32_at(MOUSE_KIND)?);
+            f.mods = Some(u32_at(MOUSE_MODS)?);
+            f.buttons = Some(u32_at(MOUSE_BUTTONS)?);
+            f.wheel_x = Some(i32_at(MOUSE_WHEEL_X)?);
+            f.wheel_y = Some(i32_at(MOUSE_WHEEL_Y)?);
+        }
+        "resize" => {
+            f.cols = Some(u32_at(RESIZE_COLS)?);
+            f.rows = Some(u32_at(RESIZE_ROWS)?);
+        }
+        "tick" => f.dt_ms = Some(u32_at(0)?),
+        "user" => {
+            f.tag = Some(u32_at(USER_TAG)?);
+            f.data = Some(trailing(USER_BYTE_LEN, USER_PAYLOAD)?.to_vec());
+        }
+        _ => {}
+    }
+    Ok(f)
+}
+
+/// Which runs of consecutive same-type records collapse into one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CoalescePolicy {
+    /// Keep only the latest of consecutive mouse moves.
+    pub(crate) mouse_move: bool,
+    /// Keep only the latest of consecutive mouse drags.
+    pub(crate) mouse_drag: bool,
+    /// Fold consecutive wheel events into one, summing the deltas.
+    pub(crate) wheel: bool,
+    /// Keep only the latest of consecutive resizes.
+    pub(crate) resize: bool,
+}
+
+/// Mouse kind plus the fields that must match for two mouse records to merge.
+fn mouse_key(payload: &[u8]) -> Option<(u32, u32, u32)> {
+    Some((
+        read_u32(payload, MOUSE_KIND)?,
+        read_u32(payload, MOUSE_MODS)?,
+        read_u32(payload, MOUSE_BUTTONS)?,
+    ))
+}
+
+/// Merge `next` into `prev` when the policy allows it. Returns `false` when
+/// the two records must stay separate.
+fn coalesce_into(prev: &mut RawRecord, next: &RawRecord, policy: CoalescePolicy) -> bool {
+    if prev.kind != next.kind {
+        return false;
+    }
+    let mut payload = next.payload.clone();
+    let merge = match next.kind {
+        "resize" => policy.resize,
+        "mouse" => match (mouse_key(&prev.payload), mouse_key(&next.payload)) {
+            (Some(a), Some(b)) if a.0 == b.0 => match b.0 {
+                MOUSE_KIND_MOVE => policy.mouse_move && a == b,
+                MOUSE_KIND_DRAG => policy.mouse_drag && a == b,
+                /* Wheel runs merge regardless of buttons; modifiers change meaning. */
+                MOUSE_KIND_WHEEL if policy.wheel && a.1 == b.1 => {
+                    sum_wheel_deltas(&prev.payload, &mut payload)
+                }
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    };
+    if merge {
+        prev.index = next.index;
+        prev.time_ms = next.time_ms;
+        prev.flags = next.flags;
+        prev.payload = payload;
+    }
+    merge
+}
+
+/// Add `prev`'s wheel deltas into `next` (saturating).
+fn sum_wheel_deltas(prev: &[u8], next: &mut [u8]) -> bool {
+    for off in [MOUSE_WHEEL_X, MOUSE_WHEEL_Y] {
+        let (Some(p), Some(n)) = (read_i32(prev, off), read_i32(next, off)) else {
+            return false;
+        };
+        next[off..off + 4].copy_from_slice(&p.saturating_add(n).to_le_bytes());
+    }
+    true
+}
+
+/// Collapse runs of consecutive same-type records per `policy`. Only adjacent
+/// records merge, so ordering relative to other input (keys, clicks) is kept.
+pub(crate) fn coalesce_records(records: Vec<RawRecord>, policy: CoalescePolicy) -> Vec<RawRecord> {
+    if policy == CoalescePolicy::default() {
+        return records;
+    }
+    let mut out: Vec<RawRecord> = Vec::with_capacity(records.len());
+    for r in records {
+        if let Some(prev) = out.last_mut() {
+            if coalesce_into(prev, &r, policy) {
+                continue;
+            }
+        }
+        out.push(r);
+    }
+    out
+}
+
+fn apply_coalesce(dst: &mut CoalescePolicy, obj: &JsObject) -> ParseResult<()> {
+    if let Some(v) = js_u8_bool(obj, "mouseMove", "mouse_move")? {
+        dst.mouse_move = v != 0;
+    }
+    if let Some(v) = js_u8_bool(obj, "mouseDrag", "mouse_drag")? {
+        dst.mouse_drag = v != 0;
+    }
+    if let Some(v) = js_u8_bool(obj, "wheel", "wheel")? {
+        dst.wheel = v != 0;
+    }
+    if let Some(v) = js_u8_bool(obj, "resize", "resize")? {
+        dst.resize = v != 0;
+    }
+    Ok(())
+}
+
+fn js_f64_array(obj: &JsObject, primary: &str, alias: &str) -> ParseResult<Option<Vec<f64>>> {
+    for name in [primary, alias] {
+        let value = match obj.get_named_property::<JsUnknown>(name) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if value.get_type().map_err(|_| ())? == ValueType::Undefined {
+            continue;
+        }
+        let array = obj
+            .get_named_property::<Float64Array>(name)
+            .map_err(|_| ())?;
+        return Ok(Some(array.to_vec()));
+    }
+    Ok(None)
+}
+
+#[derive(Default)]
+struct DecodeOpts {
+    policy: CoalescePolicy,
+    first_seq: u64,
+    timestamps_us: Option<Vec<f64>>,
+}
+
+fn parse_decode_opts(opts: Option<&JsObject>) -> napi::Result<DecodeOpts, ZrCode> {
+    let mut out = DecodeOpts::default();
+    let Some(obj) = opts else {
+        return Ok(out);
+    };
+    validate_known_keys(obj, DECODE_OPTS_KEYS, "decodeEventBatch opts")?;
+    let invalid = || invalid_argument("decodeEventBatch: invalid opts value");
+    if let Some(c) = js_obj(obj, "coalesce", "coalesce").map_err(|_| invalid())? {
+        validate_known_keys(&c, COALESCE_KEYS, "decodeEventBatch opts.coalesce")?;
+        apply_coalesce(&mut out.policy, &c).map_err(|_| invalid())?;
+    }
+    out.first_seq = js_u64(obj, "firstSeq", "first_seq")
+        .map_err(|_| invalid_argument("decodeEventBatch: opts.firstSeq must be a safe integer"))?
+        .unwrap_or(0);
+    out.timestamps_us = js_f64_array(obj, "timestampsUs", "timestamps_us").map_err(|_| {
+        invalid_argument("decodeEventBatch: opts.timestampsUs must be a Float64Array")
+    })?;
+    Ok(out)
+}
+
+fn record_kind(ty: u32) -> Option<&'static str> {
+    match ty {
+        1 => Some("key"),
+        2 => Some("text"),
+        3 => Some("paste"),
+        4 => Some("mouse"),
+        5 => Some("resize"),
+        6 => Some("tick"),
+        7 => Some("user"),
+        _ => None,
+    }
+}
+
+/// Raw bytes of a `UNKNOWN_SEQUENCE_TAG` user record (see unknown.rs).
+fn unknown_sequence<'a>(kind: &str, payload: &'a [u8]) -> Option<&'a [u8]> {
+    if kind != "user" || read_u32(payload, USER_TAG)? != UNKNOWN_SEQUENCE_TAG {
+        return None;
+    }
+    let len = read_u32(payload, USER_BYTE_LEN)? as usize;
+    payload.get(USER_PAYLOAD..USER_PAYLOAD.checked_add(len)?)
+}
+
+fn unsupported_version(version: u32) -> String {
+    if EVENT_BATCH_DECODER_VERSION_MIN == EVENT_BATCH_DECODER_VERSION_MAX {
+        format!(
+            "event batch version {version} not supported by decoder (supports {EVENT_BATCH_DECODER_VERSION_MAX})"
+        )
+    } else {
+        format!(
+            "event batch version {version} not supported by decoder (supports {EVENT_BATCH_DECODER_VERSION_MIN}-{EVENT_BATCH_DECODER_VERSION_MAX})"
+        )
+    }
+}
+
+fn version_supported(version: u32) -> bool {
+    (EVENT_BATCH_DECODER_VERSION_MIN..=EVENT_BATCH_DECODER_VERSION_MAX).contains(&version)
+}
+
+/// Parse an event batch produced for `version`.
+///
+/// Both the caller's negotiated version and the version embedded in the batch
+/// header must be supported and must agree; a mismatch is an error rather than
+/// a best-effort parse. Record types this decoder does not know are skipped,
+/// as the format allows.
+pub(crate) fn decode_batch(bytes: &[u8], version: u32) -> Result<Vec<RawRecord>, String> {
+    if !version_supported(version) {
+        return Err(unsupported_version(version));
+    }
+    let (Some(magic), Some(embedded)) = (read_u32(bytes, 0), read_u32(bytes, 4)) else {
+        return Err(format!(
+            "event batch truncated: need {EV_HEADER_BYTES} header bytes, got {}",
+            bytes.len()
+        ));
+    };
+    if magic != EV_MAGIC {
+        return Err(format!("event batch has bad magic 0x{magic:08X}"));
+    }
+    if !version_supported(embedded) {
+        return Err(unsupported_version(embedded));
+    }
+    if embedded != version {
+        return Err(format!(
+            "event batch version {embedded} does not match expected version {version}"
+        ));
+    }
+    if bytes.len() < EV_HEADER_BYTES {
+        return Err(format!(
+            "event batch truncated: need {EV_HEADER_BYTES} header bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let total_size = read_u32(bytes, 8).unwrap_or(0) as usize;
+    let event_count = read_u32(bytes, 12).unwrap_or(0) as usize;
+    if total_size < EV_HEADER_BYTES || !total_size.is_multiple_of(4) {
+        return Err(format!("event batch total_size {total_size} is invalid"));
+    }
+    if total_size > bytes.len() {
+        return Err(format!(
+            "event batch truncated: total_size {total_size} exceeds {} available bytes",
+            bytes.len()
+        ));
+    }
+
+    let body = &bytes[..total_size];
+    let mut records = Vec::with_capacity(event_count.min(total_size / EV_RECORD_HEADER_BYTES));
+    let mut off = EV_HEADER_BYTES;
+    for i in 0..event_count {
+        let header = (
+            read_u32(body, off),
+            read_u32(body, off + 4),
+            read_u32(body, off + 8),
+            read_u32(body, off + 12),
+        );
+        let (Some(ty), Some(size), Some(time_ms), Some(flags)) = header else {
+            return Err(format!("event batch truncated at record {i}"));
+        };
+        let size = size as usize;
+        if size < EV_RECORD_HEADER_BYTES || !size.is_multiple_of(4) {
+            return Err(format!("event record {i} has invalid size {size}"));
+        }
+        let Some(record) = off.checked_add(size).and_then(|end| body.get(off..end)) else {
+            return Err(format!("event record {i} exceeds total_size"));
+        };
+        if let Some(kind) = record_kind(ty) {
+            let payload = &record[EV_RECORD_HEADER_BYTES..];
+            let (kind, payload) = match unknown_sequence(kind, payload) {
+                Some(seq) => ("unknown", seq),
+                None => (kind, payload),
+            };
+            records.push(RawRecord {
+                kind,
+                index: i as u32,
+                time_ms,
+                flags,
+                payload: payload.to_vec(),
+            });
+        }
+        off += size;
+    }
+    Ok(records)
+}
+
+/// Decode a batch filled by `enginePollEvents`. `version` is the negotiated
+/// `negotiatedEventBatchVersion`; batches in any other version are rejected
+/// with a precise error instead of being misparsed.
+///
+/// `opts.coalesce` collapses runs of consecutive mouse moves, drags, wheel
+/// events (deltas summed) or resizes into the latest one, per event type;
+/// the merged record keeps the latest one's `seq`. `opts.firstSeq` (from
+/// `engineEventSeqBase`) numbers the records across polls, and
+/// `opts.timestampsUs` (from `engineEventTimestampsUs`) gives each record
+/// its `timestampUs`.
+#[napi(js_name = "decodeEventBatch")]
+pub fn decode_event_batch(
+    bytes: Uint8Array,
+    version: u32,
+    opts: Option<JsObject>,
+) -> napi::Result<Vec<EventRecord>, ZrCode> {
+    let opts = parse_decode_opts(opts.as_ref())?;
+    let stamps = opts.timestamps_us.as_deref().unwrap_or(&[]);
+    let format = |msg| zr_error(ffi::ZR_ERR_FORMAT, msg);
+    let records = decode_batch(&bytes, version).map_err(format)?;
+    coalesce_records(records, opts.policy)
+        .into_iter()
+        .map(|r| {
+            let f = record_fields(&r).map_err(format)?;
+            Ok(EventRecord {
+                kind: f.kind.to_string(),
+                timeMs: r.time_ms,
+                flags: r.flags,
+                payload: Uint8Array::new(r.payload),
+                seq: opts.first_seq.saturating_add(u64::from(r.index)) as f64,
+                timestampUs: stamps.get(r.index as usize).copied(),
+                key: f.key,
+                mods: f.mods,
+                action: f.action,
+                focused: f.focused,
+                codepoint: f.codepoint,
+                text: f.text,
+                x: f.x,
+                y: f.y,
+                mouseKind: f.mouse_kind,
+                buttons: f.buttons,
+                wheelX: f.wheel_x,
+                wheelY: f.wheel_y,
+                cols: f.cols,
+                rows: f.rows,
+                dtMs: f.dt_ms,
+                tag: f.tag,
+                data: f.data.map(Uint8Array::new),
+            })
+        })
+        .collect()
+}
+
+/// Records in the batch an `engine_poll_events` call returning `rc` wrote to
+/// `out` (0 for errors and empty polls).
+pub(crate) fn batch_event_count(rc: i32, out: &[u8]) -> u32 {
+    if rc > 0 {
+        read_u32(out, EV_EVENT_COUNT).unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+/// Fill `times` with the queue stamps (`engine_get_event_times_us`) of the
+/// batch the last poll returned.
+pub(crate) fn read_event_times_us(engine: *mut ffi::zr_engine_t, times: &mut Vec<u64>) {
+    let cap = times.len();
+    let rc = unsafe { ffi::engine_get_event_times_us(engine, times.as_mut_ptr(), cap as i32) };
+    times.truncate((rc.max(0) as usize).min(cap));
+}
+
+/// Per-engine count of records handed out by polls, so every record gets a
+/// sequence number that keeps increasing across batches, plus the queue
+/// stamps of the last batch.
+#[derive(Default)]
+pub(crate) struct EventSeq {
+    next: AtomicU64,
+    last_base: AtomicU64,
+    last_times_us: Mutex<Vec<u64>>,
+}
+
+impl EventSeq {
+    /// Number the records of a batch filled by `engine_poll_events`; `read`
+    /// fills their queue stamps into a buffer sized to the record count.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8], read: impl FnOnce(&mut Vec<u64>)) {
+        let count = batch_event_count(rc, out);
+        let base = self.next.fetch_add(u64::from(count), Ordering::Relaxed);
+        self.last_base.store(base, Ordering::Relaxed);
+        let mut times = self.last_times_us.lock().unwrap_or_else(|e| e.into_inner());
+        times.clear();
+        times.resize(count as usize, 0);
+        if count > 0 {
+            read(&mut times);
+        }
+    }
+
+    pub(crate) fn last_base(&self) -> u64 {
+        self.last_base.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn last_times_us(&self) -> Vec<u64> {
+        self.last_times_us
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// `seq` of the first record in the batch the last poll returned; pass it
+/// as `decodeEventBatch` `opts.firstSeq`. Every record an engine hands out
+/// gets the next number, so `seq` follows queue order across polls.
+#[napi(js_name = "engineEventSeqBase")]
+pub fn engine_event_seq_base(engine_id: u32) -> napi::Result<f64, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineEventSeqBase")?;
+    Ok(guard.slot.event_seq.last_base() as f64)
+}
+
+/// Queue stamps of the records in the batch the last poll returned, in
+/// record order; pass them as `decodeEventBatch` `opts.timestampsUs`. Each
+/// is the engine's monotonic microsecond clock when the record was queued,
+/// and stamps never decrease in queue order, across polls included.
+#[napi(js_name = "engineEventTimestampsUs")]
+pub fn engine_event_timestamps_us(engine_id: u32) -> napi::Result<Float64Array, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineEventTimestampsUs")?;
+    let times = guard.slot.event_seq.last_times_us();
+    Ok(Float64Array::new(
+        times.into_iter().map(|t| t as f64).collect(),
+    ))
+}
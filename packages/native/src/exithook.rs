@@ -0,0 +1,99 @@
+use crate::error::{napi_error, ZrCode};
+use crate::output::TerminalTarget;
+use crate::registry::{
+    owned_engine_ids, snapshot_engine_slots, take_idle_engine_for_owner, EngineSlot,
+};
+use crate::signals::{restore_cooked_termios, LEAVE_ALT_SCREEN, RESTORE_MODES};
+use crate::{destroy_slot, destroy_slot_at_exit};
+use napi::Env;
+use napi_derive::napi;
+use std::cell::Cell;
+use std::ffi::c_int;
+use std::sync::{Arc, Once};
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> c_int;
+}
+
+thread_local! {
+    /// One hook per environment; each environment lives on its own thread.
+    static ENV_HOOK_INSTALLED: Cell<bool> = const { Cell::new(false) };
+}
+
+static ATEXIT_INSTALLED: Once = Once::new();
+
+/// Take every engine the calling thread owns that no other thread is inside
+/// a call on. Busy engines stay registered: waiting for them would hang
+/// shutdown.
+pub(crate) fn take_idle_owned_engines() -> Vec<(u32, Arc<EngineSlot>)> {
+    owned_engine_ids()
+        .into_iter()
+        .filter_map(|engine_id| take_idle_engine_for_owner(engine_id).map(|slot| (engine_id, slot)))
+        .collect()
+}
+
+/// `engineTryDestroy` every engine the calling thread owns.
+fn destroy_owned_engines() {
+    for (engine_id, slot) in take_idle_owned_engines() {
+        destroy_slot(engine_id, &slot);
+    }
+}
+
+/// Whether engines left on the process terminal after the exiting thread
+/// destroyed its own need it restored, and if so whether one of them is on
+/// the alt screen. Engines bound to caller fds are left to their caller.
+pub(crate) fn remaining_terminal_restore() -> Option<bool> {
+    let mut remaining = false;
+    let mut alt_screen = false;
+    for (_, slot) in snapshot_engine_slots() {
+        if !slot.output.target().is_bound() {
+            remaining = true;
+            alt_screen |= !slot.inline_screen;
+        }
+    }
+    remaining.then_some(alt_screen)
+}
+
+/* `process.exit()` skips environment cleanup hooks; the C runtime's exit
+ * handlers still run, on the thread that called it, after the environment
+ * is gone. Engines of other threads (a worker's) or busy in a call cannot
+ * be destroyed from here, but they share the one terminal: it gets the
+ * same restore the signal handlers write, without tearing them down. */
+extern "C" fn destroy_owned_engines_at_exit() {
+    for (engine_id, slot) in take_idle_owned_engines() {
+        destroy_slot_at_exit(engine_id, &slot);
+    }
+    let Some(alt_screen) = remaining_terminal_restore() else {
+        return;
+    };
+    let target = TerminalTarget::Process;
+    if target.is_tty() {
+        let mut restore = RESTORE_MODES.to_vec();
+        if alt_screen {
+            restore.extend_from_slice(LEAVE_ALT_SCREEN);
+        }
+        let _ = target.write(&restore);
+    }
+    restore_cooked_termios();
+}
+
+/// Destroy the engines this thread still owns when its JS environment shuts
+/// down (the event loop draining, `process.exit()`, or a worker
+/// terminating), restoring the terminal instead of leaving it in raw mode.
+/// Engines owned by other threads are left to those threads: call this on
+/// each thread that creates engines. On `process.exit()` the terminal those
+/// engines render to is still restored, though they are not destroyed. Like
+/// `engineTryDestroy` it never waits, so an engine another thread is inside a
+/// call on is skipped. Installing twice on a thread is a no-op.
+#[napi(js_name = "engineInstallExitHook")]
+pub fn engine_install_exit_hook(mut env: Env) -> napi::Result<(), ZrCode> {
+    if !ENV_HOOK_INSTALLED.with(Cell::get) {
+        env.add_env_cleanup_hook((), |()| destroy_owned_engines())
+            .map_err(napi_error)?;
+        ENV_HOOK_INSTALLED.with(|installed| installed.set(true));
+    }
+    ATEXIT_INSTALLED.call_once(|| {
+        let _ = unsafe { atexit(destroy_owned_engines_at_exit) };
+    });
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use crate::error::{zr_error, ZrCode};
+use crate::events::decode_batch;
+use crate::ffi;
+use crate::framebuffer::CellRect;
+use crate::owner_engine_guard;
+use crate::render::{execute_drawlist_with, OwnedFb, OwnedResources};
+use crate::scroll::scroll_fb;
+use crate::state::SavedState;
+
+use napi_derive::napi;
+use std::sync::{Mutex, MutexGuard};
+
+/* zr_event.h: batch version at byte 4; resize payload is cols, rows. */
+const EV_BATCH_VERSION_OFFSET: usize = 4;
+const EV_RESIZE_COLS: usize = 0;
+const EV_RESIZE_ROWS: usize = 4;
+
+/* kMaxClip in zr_dl_execute: deeper limits are rejected outright. */
+const DL_CLIP_DEPTH_CEILING: u32 = 64;
+
+/// One framebuffer cell, colors as `0x00RRGGBB`.
+#[napi(object)]
+pub struct ExportedCell {
+    /// UTF-8 grapheme; empty for the trailing half of a wide glyph.
+    pub glyph: String,
+    pub fg: u32,
+    pub bg: u32,
+    /// `ATTR_*` bitmask.
+    pub attrs: u32,
+    /// Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail.
+    pub width: u32,
+}
+
+/// Binding-side copy of the engine's next frame.
+///
+/// The engine keeps its framebuffers private, so with frame tracking on
+/// (`trackFrame` at create) every accepted drawlist is replayed here against
+/// a persistent resource store. The size follows the `resize` records the
+/// engine hands out through `enginePollEvents` either way.
+pub(crate) struct FrameShadow {
+    /// Replay drawlists; off, only the size is followed.
+    tracking: bool,
+    inner: Mutex<ShadowInner>,
+}
+
+struct ShadowInner {
+    size: Option<(u32, u32)>,
+    fb: Option<OwnedFb>,
+    resources: OwnedResources,
+    /// Cursor state the replayed drawlists requested, like the engine's.
+    cursor: ffi::zr_cursor_state_t,
+    /// Set when a drawlist the engine accepted could not be mirrored, e.g. it
+    /// arrived before the first resize was polled or it used a resource the
+    /// shadow never saw. Cleared by the next resize.
+    diverged: bool,
+}
+
+impl FrameShadow {
+    pub(crate) fn new(tracking: bool) -> Self {
+        Self {
+            tracking,
+            inner: Mutex::new(ShadowInner {
+                size: None,
+                fb: None,
+                resources: OwnedResources::new(),
+                cursor: ffi::zr_cursor_state_t {
+                    x: -1,
+                    y: -1,
+                    shape: ffi::ZR_CURSOR_SHAPE_BLOCK,
+                    visible: 0,
+                    blink: 0,
+                    reserved0: 0,
+                },
+                diverged: false,
+            }),
+        }
+    }
+}
+
+/// Drawlists reaching the shadow were already accepted by the engine under
+/// its configured limits, so the replay must not reject them on size.
+fn replay_limits() -> ffi::zr_limits_t {
+    let mut limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    limits.dl_max_total_bytes = u32::MAX;
+    limits.dl_max_cmds = u32::MAX;
+    limits.dl_max_strings = u32::MAX;
+    limits.dl_max_blobs = u32::MAX;
+    limits.dl_max_clip_depth = DL_CLIP_DEPTH_CEILING;
+    limits.dl_max_text_run_segments = u32::MAX;
+    limits
+}
+
+/// Size carried by the last `resize` record of a polled batch.
+pub(crate) fn last_resize(batch: &[u8]) -> Option<(u32, u32)> {
+    let b = batch.get(EV_BATCH_VERSION_OFFSET..EV_BATCH_VERSION_OFFSET + 4)?;
+    let version = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    let records = decode_batch(batch, version).ok()?;
+    let record = records.iter().rev().find(|r| r.kind == "resize")?;
+    let field = |off: usize| {
+        let b = record.payload.get(off..off + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    Some((field(EV_RESIZE_COLS)?, field(EV_RESIZE_ROWS)?))
+}
+
+impl FrameShadow {
+    fn lock(&self) -> MutexGuard<'_, ShadowInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Follow resizes in a batch filled by `engine_poll_events`. The engine
+    /// reallocates its framebuffers blank on resize; so does the shadow.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8]) {
+        if rc <= 0 {
+            return;
+        }
+        let Some((cols, rows)) = last_resize(&out[..out.len().min(rc as usize)]) else {
+            return;
+        };
+        let mut inner = self.lock();
+        if inner.size == Some((cols, rows)) {
+            return;
+        }
+        inner.size = Some((cols, rows));
+        if self.tracking {
+            inner.fb = OwnedFb::new(cols, rows).ok();
+            inner.diverged = inner.fb.is_none();
+        }
+    }
+
+    /// Replay a drawlist `engine_submit_drawlist` accepted.
+    pub(crate) fn note_submit(&self, rc: i32, bytes: &[u8]) {
+        if !self.tracking || rc != ffi::ZR_OK {
+            return;
+        }
+        let mut inner = self.lock();
+        let ShadowInner {
+            fb,
+            resources,
+            cursor,
+            diverged,
+            ..
+        } = &mut *inner;
+        let Some(fb) = fb.as_mut() else {
+            *diverged = true;
+            return;
+        };
+        if execute_drawlist_with(bytes, fb, &replay_limits(), resources, cursor).is_err() {
+            *diverged = true;
+        }
+    }
+
+    /// The shadow frame, or why it cannot be trusted to match the engine's.
+    fn frame<'a>(&self, inner: &'a mut ShadowInner) -> Result<&'a mut OwnedFb, &'static str> {
+        if !self.tracking {
+            return Err("frame tracking is off: create the engine with trackFrame: true");
+        }
+        if inner.diverged {
+            return Err(
+                "a drawlist was submitted before the first resize was polled or used a resource \
+                 defined then; the next resize resynchronizes the frame",
+            );
+        }
+        inner
+            .fb
+            .as_mut()
+            .ok_or("no frame yet: poll the initial resize event first")
+    }
+
+    /// Current frame size, once the first resize was polled.
+    pub(crate) fn size(&self) -> Option<(u32, u32)> {
+        self.lock().size
+    }
+
+    /// Current frame size, or why the frame cannot be trusted. Without
+    /// tracking there is no frame to distrust, only the size.
+    pub(crate) fn tracked_size(&self) -> Result<(u32, u32), &'static str> {
+        let mut inner = self.lock();
+        if !self.tracking {
+            return inner
+                .size
+                .ok_or("no frame yet: poll the initial resize event first");
+        }
+        let fb = self.frame(&mut inner)?;
+        Ok((fb.0.cols, fb.0.rows))
+    }
+
+    pub(crate) fn save(&self) -> Result<SavedState, &'static str> {
+        let mut inner = self.lock();
+        let cursor = inner.cursor;
+        Ok(SavedState::capture(&self.frame(&mut inner)?.0, cursor))
+    }
+
+    /// Mirror a restore the engine accepted.
+    pub(crate) fn restore(&self, state: &SavedState) {
+        let mut inner = self.lock();
+        inner.cursor = state.cursor();
+        if let Some(fb) = inner.fb.as_mut() {
+            state.write_into(&mut fb.0);
+        }
+    }
+
+    /// Mirror a scroll the engine accepted.
+    pub(crate) fn note_scroll(&self, rect: CellRect, dy: i32, fill: ffi::zr_style_t) {
+        if let Some(fb) = self.lock().fb.as_mut() {
+            scroll_fb(&mut fb.0, rect, dy, fill);
+        }
+    }
+
+    pub(crate) fn export(&self) -> Result<Vec<Vec<ExportedCell>>, &'static str> {
+        let mut inner = self.lock();
+        let fb = self.frame(&mut inner)?;
+        let (cols, rows) = (fb.0.cols, fb.0.rows);
+        let mut out = Vec::with_capacity(rows as usize);
+        for y in 0..rows {
+            let mut row = Vec::with_capacity(cols as usize);
+            for x in 0..cols {
+                let cell = unsafe { &*ffi::zr_fb_cell(&mut fb.0, x, y) };
+                let len = (cell.glyph_len as usize).min(cell.glyph.len());
+                row.push(ExportedCell {
+                    glyph: String::from_utf8_lossy(&cell.glyph[..len]).into_owned(),
+                    fg: cell.style.fg_rgb,
+                    bg: cell.style.bg_rgb,
+                    attrs: cell.style.attrs,
+                    width: u32::from(cell.width),
+                });
+            }
+            out.push(row);
+        }
+        Ok(out)
+    }
+}
+
+/// Export the frame built by the drawlists submitted so far as rows of
+/// cells, for rendering to an image outside the terminal. Colors are the
+/// drawlist's RGB values, before any downgrade to the terminal's color mode,
+/// so an export looks the same whatever terminal produced it.
+#[napi(js_name = "engineExportFrame")]
+pub fn engine_export_frame(engine_id: u32) -> napi::Result<Vec<Vec<ExportedCell>>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineExportFrame")?;
+    guard
+        .slot
+        .shadow
+        .export()
+        .map_err(|msg| zr_error(ffi::ZR_ERR_UNSUPPORTED, format!("engineExportFrame: {msg}")))
+}
@@ -4,9 +4,16 @@ pub(crate) type ZrResultT = i32;
 
 pub(crate) const ZR_OK: ZrResultT = 0;
 pub(crate) const ZR_ERR_INVALID_ARGUMENT: ZrResultT = -1;
+pub(crate) const ZR_ERR_OOM: ZrResultT = -2;
 pub(crate) const ZR_ERR_LIMIT: ZrResultT = -3;
+pub(crate) const ZR_ERR_UNSUPPORTED: ZrResultT = -4;
+pub(crate) const ZR_ERR_FORMAT: ZrResultT = -5;
 pub(crate) const ZR_ERR_PLATFORM: ZrResultT = -6;
 
+pub(crate) const ZR_ENGINE_ABI_MAJOR: u32 = 1;
+pub(crate) const ZR_ENGINE_ABI_MINOR: u32 = 5;
+pub(crate) const ZR_ENGINE_ABI_PATCH: u32 = 0;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_limits_t {
@@ -22,6 +29,11 @@ pub(crate) struct zr_limits_t {
     pub(crate) diff_max_damage_rects: u32,
 }
 
+pub(crate) const PLAT_COLOR_MODE_UNKNOWN: u8 = 0;
+pub(crate) const PLAT_COLOR_MODE_16: u8 = 1;
+pub(crate) const PLAT_COLOR_MODE_256: u8 = 2;
+pub(crate) const PLAT_COLOR_MODE_RGB: u8 = 3;
+
 pub(crate) const ZR_SCREEN_MODE_ALT: u8 = 0;
 pub(crate) const ZR_SCREEN_MODE_INLINE: u8 = 1;
 
@@ -57,6 +69,8 @@ pub(crate) struct zr_engine_config_t {
     pub(crate) cap_force_flags: u32,
     pub(crate) cap_suppress_flags: u32,
     pub(crate) inline_rows: u32,
+    pub(crate) enable_erase_optimizations: u8,
+    pub(crate) _pad_erase0: [u8; 3],
 }
 
 #[repr(C)]
@@ -74,8 +88,14 @@ pub(crate) struct zr_engine_runtime_config_t {
     pub(crate) cap_force_flags: u32,
     pub(crate) cap_suppress_flags: u32,
     pub(crate) inline_rows: u32,
+    pub(crate) enable_erase_optimizations: u8,
+    pub(crate) _pad_erase0: [u8; 3],
 }
 
+/// `zr_metrics_t` bytes an ABI 1.4 engine fills: everything before
+/// `diff_line_erases_total`.
+pub(crate) const ZR_METRICS_SIZE_V1_4: u32 = 120;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_metrics_t {
@@ -105,6 +125,10 @@ pub(crate) struct zr_metrics_t {
     pub(crate) damage_cells_last_frame: u32,
     pub(crate) damage_full_frame: u8,
     pub(crate) _pad2: [u8; 3],
+    pub(crate) diff_line_erases_total: u64,
+    pub(crate) diff_screen_erases_total: u64,
+    pub(crate) input_parse_errors_total: u32,
+    pub(crate) paste_dropped_total: u32,
 }
 
 #[repr(C)]
@@ -130,6 +154,40 @@ pub(crate) struct zr_terminal_caps_t {
     pub(crate) cap_suppress_flags: u32,
 }
 
+pub(crate) const ZR_TERMINAL_VERSION_LEN: usize = 64;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_terminal_profile_t {
+    pub(crate) id: u32,
+    pub(crate) _pad0: [u8; 3],
+    pub(crate) version_string: [u8; ZR_TERMINAL_VERSION_LEN],
+    pub(crate) supports_sixel: u8,
+    pub(crate) supports_kitty_graphics: u8,
+    pub(crate) supports_iterm2_images: u8,
+    pub(crate) supports_underline_styles: u8,
+    pub(crate) supports_colored_underlines: u8,
+    pub(crate) supports_hyperlinks: u8,
+    pub(crate) supports_grapheme_clusters: u8,
+    pub(crate) supports_overline: u8,
+    pub(crate) supports_pixel_mouse: u8,
+    pub(crate) supports_kitty_keyboard: u8,
+    pub(crate) supports_mouse: u8,
+    pub(crate) supports_bracketed_paste: u8,
+    pub(crate) supports_focus_events: u8,
+    pub(crate) supports_osc52: u8,
+    pub(crate) supports_sync_update: u8,
+    pub(crate) _pad1: u8,
+    pub(crate) cell_width_px: u16,
+    pub(crate) cell_height_px: u16,
+    pub(crate) screen_width_px: u16,
+    pub(crate) screen_height_px: u16,
+    pub(crate) xtversion_responded: u8,
+    pub(crate) da1_responded: u8,
+    pub(crate) da2_responded: u8,
+    pub(crate) _pad2: u8,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct plat_caps_t {
@@ -159,10 +217,73 @@ pub(crate) struct zr_style_t {
     pub(crate) link_ref: u32,
 }
 
+pub(crate) const ZR_STYLE_ATTR_BOLD: u32 = 1 << 0;
+pub(crate) const ZR_STYLE_ATTR_ITALIC: u32 = 1 << 1;
+pub(crate) const ZR_STYLE_ATTR_UNDERLINE: u32 = 1 << 2;
+pub(crate) const ZR_STYLE_ATTR_REVERSE: u32 = 1 << 3;
+pub(crate) const ZR_STYLE_ATTR_DIM: u32 = 1 << 4;
+pub(crate) const ZR_STYLE_ATTR_STRIKE: u32 = 1 << 5;
+pub(crate) const ZR_STYLE_ATTR_OVERLINE: u32 = 1 << 6;
+pub(crate) const ZR_STYLE_ATTR_BLINK: u32 = 1 << 7;
+pub(crate) const ZR_STYLE_ATTR_ALL_MASK: u32 = 0xFF;
+
+pub(crate) const ZR_DL_OP_CLEAR: u16 = 1;
+pub(crate) const ZR_DL_OP_FILL_RECT: u16 = 2;
+pub(crate) const ZR_DL_OP_DRAW_TEXT: u16 = 3;
+pub(crate) const ZR_DL_OP_PUSH_CLIP: u16 = 4;
+pub(crate) const ZR_DL_OP_POP_CLIP: u16 = 5;
+pub(crate) const ZR_DL_OP_DRAW_TEXT_RUN: u16 = 6;
+pub(crate) const ZR_DL_OP_SET_CURSOR: u16 = 7;
+pub(crate) const ZR_DL_OP_DRAW_CANVAS: u16 = 8;
+pub(crate) const ZR_DL_OP_DRAW_IMAGE: u16 = 9;
+pub(crate) const ZR_DL_OP_DEF_STRING: u16 = 10;
+pub(crate) const ZR_DL_OP_FREE_STRING: u16 = 11;
+pub(crate) const ZR_DL_OP_DEF_BLOB: u16 = 12;
+pub(crate) const ZR_DL_OP_FREE_BLOB: u16 = 13;
+pub(crate) const ZR_DL_OP_BLIT_RECT: u16 = 14;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_string_view_t {
+    pub(crate) ptr: *const core::ffi::c_char,
+    pub(crate) len: usize,
+}
+
+pub(crate) type zr_log_sink_fn_t =
+    Option<extern "C" fn(user: *mut core::ffi::c_void, msg: zr_string_view_t)>;
+
+pub(crate) const ZR_CELL_GLYPH_MAX: usize = 32;
+
+pub(crate) const ZR_WIDTH_EMOJI_NARROW: u32 = 0;
+/// Engine default width policy (`ZR_WIDTH_POLICY_DEFAULT`).
+pub(crate) const ZR_WIDTH_EMOJI_WIDE: u32 = 1;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_grapheme_t {
+    pub(crate) offset: usize,
+    pub(crate) size: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_grapheme_iter_t {
+    pub(crate) bytes: *const u8,
+    pub(crate) len: usize,
+    pub(crate) off: usize,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_measure_utf8_t {
+    pub(crate) lines: u32,
+    pub(crate) max_cols: u32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_cell_t {
-    pub(crate) glyph: [u8; 32],
+    pub(crate) glyph: [u8; ZR_CELL_GLYPH_MAX],
     pub(crate) glyph_len: u8,
     pub(crate) width: u8,
     pub(crate) _pad0: u16,
@@ -210,6 +331,8 @@ pub(crate) struct zr_fb_painter_t {
     pub(crate) clip_len: u32,
 }
 
+pub(crate) const ZR_CURSOR_SHAPE_BLOCK: u8 = 0;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_cursor_state_t {
@@ -221,6 +344,9 @@ pub(crate) struct zr_cursor_state_t {
     pub(crate) reserved0: u8,
 }
 
+pub(crate) const ZR_TERM_STATE_STYLE_VALID: u8 = 0x01;
+pub(crate) const ZR_TERM_STATE_SCREEN_VALID: u8 = 0x10;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_term_state_t {
@@ -250,6 +376,8 @@ pub(crate) struct zr_diff_stats_t {
     pub(crate) scroll_opt_hit: u8,
     pub(crate) collision_guard_hits: u32,
     pub(crate) _pad0: u32,
+    pub(crate) line_erases: u32,
+    pub(crate) screen_erases: u32,
     pub(crate) bytes_emitted: usize,
 }
 
@@ -260,6 +388,93 @@ pub(crate) struct zr_damage_rect_t {
     pub(crate) y0: u32,
     pub(crate) x1: u32,
     pub(crate) y1: u32,
+    /* Coalescing link the differ keeps next to each rect. */
+    pub(crate) _link: u32,
+}
+
+/* Engine-internal drawlist pipeline types (src/core/zr_drawlist.h, zr_image.h, zr_blit.h). */
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_dl_header_t {
+    pub(crate) magic: u32,
+    pub(crate) version: u32,
+    pub(crate) header_size: u32,
+    pub(crate) total_size: u32,
+    pub(crate) cmd_offset: u32,
+    pub(crate) cmd_bytes: u32,
+    pub(crate) cmd_count: u32,
+    pub(crate) strings_span_offset: u32,
+    pub(crate) strings_count: u32,
+    pub(crate) strings_bytes_offset: u32,
+    pub(crate) strings_bytes_len: u32,
+    pub(crate) blobs_span_offset: u32,
+    pub(crate) blobs_count: u32,
+    pub(crate) blobs_bytes_offset: u32,
+    pub(crate) blobs_bytes_len: u32,
+    pub(crate) reserved0: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_dl_view_t {
+    pub(crate) hdr: zr_dl_header_t,
+    pub(crate) bytes: *const u8,
+    pub(crate) bytes_len: usize,
+    pub(crate) cmd_bytes: *const u8,
+    pub(crate) cmd_bytes_len: usize,
+    pub(crate) strings_span_bytes: *const u8,
+    pub(crate) strings_count: usize,
+    pub(crate) strings_bytes: *const u8,
+    pub(crate) strings_bytes_len: usize,
+    pub(crate) blobs_span_bytes: *const u8,
+    pub(crate) blobs_count: usize,
+    pub(crate) blobs_bytes: *const u8,
+    pub(crate) blobs_bytes_len: usize,
+    pub(crate) text_tab_width: u32,
+    pub(crate) text_width_policy: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_dl_resource_store_t {
+    pub(crate) entries: *mut core::ffi::c_void,
+    pub(crate) len: u32,
+    pub(crate) cap: u32,
+    pub(crate) total_bytes: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_dl_resources_t {
+    pub(crate) strings: zr_dl_resource_store_t,
+    pub(crate) blobs: zr_dl_resource_store_t,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_image_frame_t {
+    pub(crate) cmds: *mut core::ffi::c_void,
+    pub(crate) cmds_len: u32,
+    pub(crate) cmds_cap: u32,
+    pub(crate) blob_bytes: *mut u8,
+    pub(crate) blob_len: u32,
+    pub(crate) blob_cap: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_blit_caps_t {
+    pub(crate) terminal_id: u32,
+    pub(crate) is_dumb_terminal: u8,
+    pub(crate) is_pipe_mode: u8,
+    pub(crate) supports_unicode: u8,
+    pub(crate) supports_quadrant: u8,
+    pub(crate) supports_sextant: u8,
+    pub(crate) supports_halfblock: u8,
+    pub(crate) supports_braille: u8,
+    pub(crate) include_braille_in_auto: u8,
+    pub(crate) _pad0: [u8; 3],
 }
 
 #[repr(C)]
@@ -267,6 +482,21 @@ pub(crate) struct zr_engine_t {
     _private: [u8; 0],
 }
 
+pub(crate) const ZR_DEBUG_CAT_NONE: u32 = 0;
+pub(crate) const ZR_DEBUG_CAT_FRAME: u32 = 1;
+pub(crate) const ZR_DEBUG_CAT_EVENT: u32 = 2;
+pub(crate) const ZR_DEBUG_CAT_DRAWLIST: u32 = 3;
+pub(crate) const ZR_DEBUG_CAT_ERROR: u32 = 4;
+pub(crate) const ZR_DEBUG_CAT_STATE: u32 = 5;
+pub(crate) const ZR_DEBUG_CAT_PERF: u32 = 6;
+
+pub(crate) const ZR_DEBUG_CODE_PERF_DIFF_PATH: u32 = 0x0601;
+
+pub(crate) const ZR_DEBUG_SEV_TRACE: u32 = 0;
+pub(crate) const ZR_DEBUG_SEV_INFO: u32 = 1;
+pub(crate) const ZR_DEBUG_SEV_WARN: u32 = 2;
+pub(crate) const ZR_DEBUG_SEV_ERROR: u32 = 3;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub(crate) struct zr_debug_config_t {
@@ -327,8 +557,130 @@ pub(crate) struct zr_debug_stats_t {
     pub(crate) ring_capacity: u32,
 }
 
+pub(crate) const ZR_EV_KEY: u32 = 1;
+pub(crate) const ZR_EV_TEXT: u32 = 2;
+pub(crate) const ZR_EV_USER: u32 = 7;
+pub(crate) const ZR_KEY_ESCAPE: u32 = 1;
+
+/// Internal queued event (core/zr_event_queue.h); `u` is the payload union.
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+pub(crate) struct zr_event_t {
+    pub(crate) type_: u32,
+    pub(crate) time_ms: u32,
+    pub(crate) flags: u32,
+    pub(crate) u: [u32; 8],
+    pub(crate) time_us: u64,
+}
+
+/// Internal event queue (core/zr_event_queue.h); `lock` is a C11 `atomic_flag`.
+#[repr(C)]
+pub(crate) struct zr_event_queue_t {
+    pub(crate) lock: u8,
+    pub(crate) events: *mut zr_event_t,
+    pub(crate) cap: u32,
+    pub(crate) head: u32,
+    pub(crate) count: u32,
+    pub(crate) user_bytes: *mut u8,
+    pub(crate) user_bytes_cap: u32,
+    pub(crate) user_head: u32,
+    pub(crate) user_tail: u32,
+    pub(crate) user_used: u32,
+    pub(crate) user_pad_end: u32,
+    pub(crate) dropped_total: u32,
+    pub(crate) dropped_due_to_full: u32,
+    pub(crate) dropped_user_due_to_full: u32,
+    pub(crate) dropped_coalesce_candidates: u32,
+    pub(crate) input_parse_errors: u32,
+    pub(crate) _pad0: u32,
+    pub(crate) now_us: Option<extern "C" fn() -> u64>,
+    pub(crate) last_time_us: u64,
+}
+
+/// `zr_engine_io_t` (zr_engine.h); a negative fd keeps the process stdio.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct zr_engine_io_t {
+    pub(crate) input_fd: i32,
+    pub(crate) output_fd: i32,
+}
+
+/// `plat_size_t` (zr_platform_types.h).
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub(crate) struct plat_size_t {
+    pub(crate) cols: u32,
+    pub(crate) rows: u32,
+}
+
+pub(crate) type zr_plat_write_output_hook_t =
+    Option<extern "C" fn(plat: *mut core::ffi::c_void, bytes: *const u8, len: i32) -> ZrResultT>;
+
+pub(crate) type zr_plat_get_size_hook_t =
+    Option<extern "C" fn(plat: *mut core::ffi::c_void, out_size: *mut plat_size_t) -> ZrResultT>;
+
+pub(crate) type zr_input_parse_bytes_hook_t =
+    Option<extern "C" fn(q: *mut zr_event_queue_t, bytes: *const u8, len: usize, time_ms: u32)>;
+
+pub(crate) type zr_input_parse_bytes_prefix_hook_t = Option<
+    extern "C" fn(q: *mut zr_event_queue_t, bytes: *const u8, len: usize, time_ms: u32) -> usize,
+>;
+
+pub(crate) type zr_dl_execute_hook_t = Option<
+    extern "C" fn(
+        v: *const zr_dl_view_t,
+        dst: *mut zr_fb_t,
+        lim: *const zr_limits_t,
+        tab_width: u32,
+        width_policy: u32,
+        blit_caps: *const zr_blit_caps_t,
+        term_profile: *const zr_terminal_profile_t,
+        image_frame_stage: *mut zr_image_frame_t,
+        resources: *mut zr_dl_resources_t,
+        inout_cursor_state: *mut zr_cursor_state_t,
+    ) -> ZrResultT,
+>;
+
+pub(crate) type zr_diff_render_ex_hook_t = Option<
+    extern "C" fn(
+        prev: *const zr_fb_t,
+        next: *const zr_fb_t,
+        caps: *const plat_caps_t,
+        initial_term_state: *const zr_term_state_t,
+        desired_cursor_state: *const zr_cursor_state_t,
+        lim: *const zr_limits_t,
+        scratch_damage_rects: *mut zr_damage_rect_t,
+        scratch_damage_rect_cap: u32,
+        scratch: *mut core::ffi::c_void,
+        enable_scroll_optimizations: u8,
+        enable_erase_optimizations: u8,
+        out_buf: *mut u8,
+        out_cap: usize,
+        out_len: *mut usize,
+        out_final_term_state: *mut zr_term_state_t,
+        out_stats: *mut zr_diff_stats_t,
+    ) -> ZrResultT,
+>;
+
+pub(crate) type zr_debug_overlay_render_hook_t =
+    Option<extern "C" fn(fb: *mut zr_fb_t, metrics: *const core::ffi::c_void) -> ZrResultT>;
+
+/// `zr_engine_hooks_t` (zr_engine_hooks.h); `None` keeps the engine's call.
+#[repr(C)]
+#[derive(Default)]
+pub(crate) struct zr_engine_hooks_t {
+    pub(crate) plat_write_output: zr_plat_write_output_hook_t,
+    pub(crate) plat_get_size: zr_plat_get_size_hook_t,
+    pub(crate) input_parse_bytes: zr_input_parse_bytes_hook_t,
+    pub(crate) input_parse_bytes_prefix: zr_input_parse_bytes_prefix_hook_t,
+    pub(crate) dl_execute: zr_dl_execute_hook_t,
+    pub(crate) diff_render_ex: zr_diff_render_ex_hook_t,
+    pub(crate) debug_overlay_render: zr_debug_overlay_render_hook_t,
+}
+
 unsafe extern "C" {
     pub(crate) fn zr_engine_config_default() -> zr_engine_config_t;
+    pub(crate) fn zr_engine_config_validate(cfg: *const zr_engine_config_t) -> ZrResultT;
     pub(crate) fn zr_fb_init(fb: *mut zr_fb_t, cols: u32, rows: u32) -> ZrResultT;
     pub(crate) fn zr_fb_release(fb: *mut zr_fb_t);
     pub(crate) fn zr_fb_cell(fb: *mut zr_fb_t, x: u32, y: u32) -> *mut zr_cell_t;
@@ -367,6 +719,72 @@ unsafe extern "C" {
         width: u8,
         style: *const zr_style_t,
     ) -> ZrResultT;
+    pub(crate) fn zr_width_grapheme_utf8(bytes: *const u8, len: usize, policy: u32) -> u8;
+    pub(crate) fn zr_grapheme_iter_init(it: *mut zr_grapheme_iter_t, bytes: *const u8, len: usize);
+    pub(crate) fn zr_grapheme_next(it: *mut zr_grapheme_iter_t, out: *mut zr_grapheme_t) -> bool;
+    pub(crate) fn zr_measure_utf8(
+        bytes: *const u8,
+        len: usize,
+        policy: u32,
+        tab_stop: u32,
+        out: *mut zr_measure_utf8_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_wrap_greedy_utf8(
+        bytes: *const u8,
+        len: usize,
+        max_cols: u32,
+        policy: u32,
+        tab_stop: u32,
+        out_offsets: *mut usize,
+        out_offsets_cap: usize,
+        out_count: *mut usize,
+        out_truncated: *mut bool,
+    ) -> ZrResultT;
+    pub(crate) fn zr_dl_validate(
+        bytes: *const u8,
+        bytes_len: usize,
+        lim: *const zr_limits_t,
+        out_view: *mut zr_dl_view_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_dl_preflight_resources(
+        v: *const zr_dl_view_t,
+        fb: *mut zr_fb_t,
+        image_stage: *mut zr_image_frame_t,
+        lim: *const zr_limits_t,
+        term_profile: *const zr_terminal_profile_t,
+        resources: *mut zr_dl_resources_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_dl_execute(
+        v: *const zr_dl_view_t,
+        dst: *mut zr_fb_t,
+        lim: *const zr_limits_t,
+        tab_width: u32,
+        width_policy: u32,
+        blit_caps: *const zr_blit_caps_t,
+        term_profile: *const zr_terminal_profile_t,
+        image_frame_stage: *mut zr_image_frame_t,
+        resources: *mut zr_dl_resources_t,
+        inout_cursor_state: *mut zr_cursor_state_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_log_set_sink(sink: zr_log_sink_fn_t, user: *mut core::ffi::c_void);
+    pub(crate) fn zr_log_write(msg: zr_string_view_t);
+    pub(crate) fn zr_dl_resources_init(resources: *mut zr_dl_resources_t);
+    pub(crate) fn zr_dl_resources_release(resources: *mut zr_dl_resources_t);
+    pub(crate) fn zr_dl_resources_swap(a: *mut zr_dl_resources_t, b: *mut zr_dl_resources_t);
+    pub(crate) fn zr_dl_resources_clone(
+        dst: *mut zr_dl_resources_t,
+        src: *const zr_dl_resources_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_dl_resources_clone_shallow(
+        dst: *mut zr_dl_resources_t,
+        src: *const zr_dl_resources_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_image_frame_init(frame: *mut zr_image_frame_t);
+    pub(crate) fn zr_image_frame_release(frame: *mut zr_image_frame_t);
+    pub(crate) fn zr_blit_caps_from_profile(
+        profile: *const zr_terminal_profile_t,
+        out_caps: *mut zr_blit_caps_t,
+    );
     pub(crate) fn zr_diff_render(
         prev: *const zr_fb_t,
         next: *const zr_fb_t,
@@ -377,16 +795,63 @@ unsafe extern "C" {
         scratch_damage_rects: *mut zr_damage_rect_t,
         scratch_damage_rect_cap: u32,
         enable_scroll_optimizations: u8,
+        enable_erase_optimizations: u8,
+        out_buf: *mut u8,
+        out_cap: usize,
+        out_len: *mut usize,
+        out_final_term_state: *mut zr_term_state_t,
+        out_stats: *mut zr_diff_stats_t,
+    ) -> ZrResultT;
+
+    pub(crate) fn zr_engine_hooks_set(hooks: *const zr_engine_hooks_t);
+    pub(crate) fn plat_write_output(
+        plat: *mut core::ffi::c_void,
+        bytes: *const u8,
+        len: i32,
+    ) -> ZrResultT;
+    pub(crate) fn plat_get_size(
+        plat: *mut core::ffi::c_void,
+        out_size: *mut plat_size_t,
+    ) -> ZrResultT;
+    pub(crate) fn zr_input_parse_bytes(
+        q: *mut zr_event_queue_t,
+        bytes: *const u8,
+        len: usize,
+        time_ms: u32,
+    );
+    pub(crate) fn zr_input_parse_bytes_prefix(
+        q: *mut zr_event_queue_t,
+        bytes: *const u8,
+        len: usize,
+        time_ms: u32,
+    ) -> usize;
+    pub(crate) fn zr_diff_render_ex(
+        prev: *const zr_fb_t,
+        next: *const zr_fb_t,
+        caps: *const plat_caps_t,
+        initial_term_state: *const zr_term_state_t,
+        desired_cursor_state: *const zr_cursor_state_t,
+        lim: *const zr_limits_t,
+        scratch_damage_rects: *mut zr_damage_rect_t,
+        scratch_damage_rect_cap: u32,
+        scratch: *mut core::ffi::c_void,
+        enable_scroll_optimizations: u8,
+        enable_erase_optimizations: u8,
         out_buf: *mut u8,
         out_cap: usize,
         out_len: *mut usize,
         out_final_term_state: *mut zr_term_state_t,
         out_stats: *mut zr_diff_stats_t,
     ) -> ZrResultT;
+    pub(crate) fn zr_debug_overlay_render(
+        fb: *mut zr_fb_t,
+        metrics: *const core::ffi::c_void,
+    ) -> ZrResultT;
 
-    pub(crate) fn engine_create(
+    pub(crate) fn engine_create_io(
         out_engine: *mut *mut zr_engine_t,
         cfg: *const zr_engine_config_t,
+        io: *const zr_engine_io_t,
     ) -> ZrResultT;
     pub(crate) fn engine_destroy(e: *mut zr_engine_t);
 
@@ -396,12 +861,18 @@ unsafe extern "C" {
         out_buf: *mut u8,
         out_cap: i32,
     ) -> i32;
+    pub(crate) fn engine_get_event_times_us(
+        e: *const zr_engine_t,
+        out_times: *mut u64,
+        out_cap: i32,
+    ) -> i32;
     pub(crate) fn engine_post_user_event(
         e: *mut zr_engine_t,
         tag: u32,
         payload: *const u8,
         payload_len: i32,
     ) -> ZrResultT;
+    pub(crate) fn engine_wake(e: *mut zr_engine_t) -> ZrResultT;
 
     pub(crate) fn engine_submit_drawlist(
         e: *mut zr_engine_t,
@@ -424,6 +895,9 @@ unsafe extern "C" {
         e: *mut zr_engine_t,
         out_caps: *mut zr_terminal_caps_t,
     ) -> ZrResultT;
+    pub(crate) fn engine_get_terminal_profile(
+        e: *const zr_engine_t,
+    ) -> *const zr_terminal_profile_t;
     pub(crate) fn engine_set_config(
         e: *mut zr_engine_t,
         cfg: *const zr_engine_runtime_config_t,
@@ -455,4 +929,30 @@ unsafe extern "C" {
     pub(crate) fn engine_debug_export(e: *mut zr_engine_t, out_buf: *mut u8, out_cap: usize)
         -> i32;
     pub(crate) fn engine_debug_reset(e: *mut zr_engine_t);
+    pub(crate) fn engine_debug_trace_warning(
+        e: *mut zr_engine_t,
+        message: *const core::ffi::c_char,
+    ) -> ZrResultT;
+
+    pub(crate) fn zr_event_queue_init(
+        q: *mut zr_event_queue_t,
+        events: *mut zr_event_t,
+        events_cap: u32,
+        user_bytes: *mut u8,
+        user_bytes_cap: u32,
+    ) -> ZrResultT;
+    pub(crate) fn zr_event_queue_set_clock(
+        q: *mut zr_event_queue_t,
+        now_us: Option<extern "C" fn() -> u64>,
+    );
+    pub(crate) fn zr_event_queue_push(q: *mut zr_event_queue_t, ev: *const zr_event_t)
+        -> ZrResultT;
+    pub(crate) fn zr_event_queue_pop(q: *mut zr_event_queue_t, out_ev: *mut zr_event_t) -> bool;
+    pub(crate) fn zr_event_queue_post_user(
+        q: *mut zr_event_queue_t,
+        time_ms: u32,
+        tag: u32,
+        payload: *const u8,
+        payload_len: u32,
+    ) -> ZrResultT;
 }
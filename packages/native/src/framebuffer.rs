@@ -0,0 +1,1009 @@
+use crate::config::{js_obj, js_str, js_u32, js_u8_bool, validate_known_keys};
+use crate::error::{invalid_argument, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::render::OwnedFb;
+use crate::scroll::{repair_wide_pairs, scroll_fb};
+use crate::style::{parse_packed_style, Style};
+use napi::bindgen_prelude::{Either, Uint8Array};
+use napi::JsObject;
+use napi_derive::napi;
+use std::cell::RefCell;
+
+const DIFF_CAPS_KEYS: &[(&str, &str)] = &[
+    ("colorMode", "color_mode"),
+    ("sgrAttrsSupported", "sgr_attrs_supported"),
+    ("supportsScrollRegion", "supports_scroll_region"),
+    ("supportsCursorShape", "supports_cursor_shape"),
+    ("supportsSyncUpdate", "supports_sync_update"),
+    ("supportsUnderlineStyles", "supports_underline_styles"),
+    ("supportsColoredUnderlines", "supports_colored_underlines"),
+    ("supportsHyperlinks", "supports_hyperlinks"),
+];
+
+const DIFF_OPTS_KEYS: &[(&str, &str)] = &[
+    ("eraseBlankTails", "erase_blank_tails"),
+    ("enableScrollOptimizations", "enable_scroll_optimizations"),
+    ("assumeScreen", "assume_screen"),
+    ("stats", "stats"),
+    ("damage", "damage"),
+];
+
+const CELL_WRITE_KEYS: &[(&str, &str)] = &[("glyph", "glyph"), ("style", "style")];
+
+/// Cell rectangle of a framebuffer.
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// One framebuffer cell as `Framebuffer.cell` returns it.
+#[napi(object)]
+pub struct FramebufferCell {
+    /// UTF-8 grapheme; empty for the trailing half of a wide glyph.
+    pub glyph: String,
+    /// Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail.
+    pub width: u32,
+    pub style: Style,
+}
+
+/// One cell of `Framebuffer.snapshot`, keyed short to keep fixtures small.
+#[napi(object)]
+pub struct SnapshotCell {
+    /// UTF-8 grapheme; empty for the trailing half of a wide glyph.
+    pub g: String,
+    /// Columns the glyph occupies: 1, 2 for a wide lead cell, 0 for its tail.
+    pub w: u32,
+    /// `0x00RRGGBB`.
+    pub fg: u32,
+    /// `0x00RRGGBB`.
+    pub bg: u32,
+    /// `ZR_STYLE_ATTR_*` bitmask.
+    pub attrs: u32,
+}
+
+/// Whole-framebuffer contents as `Framebuffer.snapshot` returns them.
+#[napi(object)]
+pub struct FramebufferSnapshot {
+    pub cols: u32,
+    pub rows: u32,
+    /// Row-major, `cols * rows` entries.
+    pub cells: Vec<SnapshotCell>,
+}
+
+fn cell_glyph(cell: &ffi::zr_cell_t) -> String {
+    let len = (cell.glyph_len as usize).min(cell.glyph.len());
+    String::from_utf8_lossy(&cell.glyph[..len]).into_owned()
+}
+
+/// Cells of `fb` in row-major order.
+fn fb_cells(fb: &mut ffi::zr_fb_t) -> Vec<ffi::zr_cell_t> {
+    let mut cells = Vec::with_capacity(fb.cols as usize * fb.rows as usize);
+    for y in 0..fb.rows {
+        for x in 0..fb.cols {
+            let cell = unsafe { ffi::zr_fb_cell(fb as *mut _, x, y) };
+            if !cell.is_null() {
+                cells.push(unsafe { *cell });
+            }
+        }
+    }
+    cells
+}
+
+/// Text of each row of `fb` as a terminal would show it: wide-glyph tails
+/// skipped, empty glyphs read as spaces, trailing spaces trimmed.
+pub(crate) fn fb_lines(fb: &mut ffi::zr_fb_t) -> Vec<String> {
+    let cols = fb.cols as usize;
+    let cells = fb_cells(fb);
+    if cols == 0 {
+        return vec![String::new(); fb.rows as usize];
+    }
+    cells
+        .chunks(cols)
+        .map(|row| {
+            let mut line = String::with_capacity(cols);
+            for cell in row.iter().filter(|cell| cell.width != 0) {
+                match cell_glyph(cell) {
+                    glyph if glyph.is_empty() => line.push(' '),
+                    glyph => line.push_str(&glyph),
+                }
+            }
+            line.truncate(line.trim_end_matches(' ').len());
+            line
+        })
+        .collect()
+}
+
+/// Whether `rect` lies inside a `cols`x`rows` framebuffer.
+pub(crate) fn rect_fits(rect: CellRect, cols: u32, rows: u32) -> bool {
+    let fits = |at: u32, len: u32, max: u32| at.checked_add(len).is_some_and(|end| end <= max);
+    fits(rect.x, rect.w, cols) && fits(rect.y, rect.h, rows)
+}
+
+pub(crate) fn check_rect(
+    rect: CellRect,
+    cols: u32,
+    rows: u32,
+    ctx: &str,
+) -> napi::Result<(), ZrCode> {
+    if !rect_fits(rect, cols, rows) {
+        return Err(invalid_argument(format!(
+            "{ctx}: rect {}x{} at ({}, {}) is outside the {cols}x{rows} framebuffer",
+            rect.w, rect.h, rect.x, rect.y
+        )));
+    }
+    Ok(())
+}
+
+/// Columns the engine gives one grapheme: empty glyphs become a space and
+/// oversized ones U+FFFD, both one column wide.
+pub(crate) fn grapheme_width(glyph: &[u8]) -> u8 {
+    if glyph.is_empty() || glyph.len() > ffi::ZR_CELL_GLYPH_MAX {
+        return 1;
+    }
+    unsafe { ffi::zr_width_grapheme_utf8(glyph.as_ptr(), glyph.len(), ffi::ZR_WIDTH_EMOJI_WIDE) }
+}
+
+/// Starting column of each grapheme laid out left to right from `x`, or the
+/// index of the first one that runs past `cols`.
+pub(crate) fn layout_run(x: u32, widths: &[u8], cols: u32) -> Result<Vec<u32>, usize> {
+    let mut at = x;
+    let mut columns = Vec::with_capacity(widths.len());
+    for (i, &width) in widths.iter().enumerate() {
+        match at.checked_add(u32::from(width)) {
+            Some(end) if end <= cols => {
+                columns.push(at);
+                at = end;
+            }
+            _ => return Err(i),
+        }
+    }
+    Ok(columns)
+}
+
+/// One validated grapheme write.
+struct Put<'a> {
+    x: u32,
+    y: u32,
+    glyph: &'a [u8],
+    width: u8,
+    style: ffi::zr_style_t,
+}
+
+/// Write `puts`, all of which were checked to fit, through a painter clipped
+/// to the whole framebuffer.
+fn paint(fb: &mut ffi::zr_fb_t, puts: &[Put<'_>], ctx: &str) -> napi::Result<(), ZrCode> {
+    let mut clip_stack = [ffi::zr_rect_t {
+        x: 0,
+        y: 0,
+        w: 0,
+        h: 0,
+    }];
+    let mut painter = ffi::zr_fb_painter_t {
+        fb: std::ptr::null_mut(),
+        clip_stack: std::ptr::null_mut(),
+        clip_cap: 0,
+        clip_len: 0,
+    };
+    let rc = unsafe {
+        ffi::zr_fb_painter_begin(
+            &mut painter as *mut _,
+            fb as *mut _,
+            clip_stack.as_mut_ptr(),
+            clip_stack.len() as u32,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+    for put in puts {
+        let rc = unsafe {
+            ffi::zr_fb_put_grapheme(
+                &mut painter as *mut _,
+                put.x as i32,
+                put.y as i32,
+                put.glyph.as_ptr(),
+                put.glyph.len(),
+                put.width,
+                &put.style as *const _,
+            )
+        };
+        if rc != ffi::ZR_OK {
+            return Err(zr_result_error(rc, ctx));
+        }
+    }
+    Ok(())
+}
+
+/// Width of the grapheme at `index`, rejecting zero-width ones, which the
+/// framebuffer cannot hold on their own.
+fn checked_width(glyph: &[u8], index: usize, ctx: &str) -> napi::Result<u8, ZrCode> {
+    match grapheme_width(glyph) {
+        0 => Err(invalid_argument(format!(
+            "{ctx}: grapheme {index} has zero width"
+        ))),
+        width => Ok(width),
+    }
+}
+
+pub(crate) fn optional_style(
+    obj: Option<JsObject>,
+    ctx: &str,
+) -> napi::Result<ffi::zr_style_t, ZrCode> {
+    match obj {
+        Some(obj) => parse_packed_style(&obj, ctx),
+        None => Ok(unsafe { std::mem::zeroed() }),
+    }
+}
+
+/// Offscreen framebuffer owned by JS (no engine or terminal required).
+#[napi]
+pub struct Framebuffer {
+    raw: ffi::zr_fb_t,
+    released: bool,
+}
+
+impl Framebuffer {
+    pub(crate) fn raw(&self, ctx: &str) -> napi::Result<&ffi::zr_fb_t, ZrCode> {
+        if self.released {
+            return Err(invalid_argument(format!(
+                "{ctx}: framebuffer has been released"
+            )));
+        }
+        Ok(&self.raw)
+    }
+
+    pub(crate) fn raw_mut(&mut self, ctx: &str) -> napi::Result<&mut ffi::zr_fb_t, ZrCode> {
+        self.raw(ctx)?;
+        Ok(&mut self.raw)
+    }
+
+    fn check_row(&self, y: u32, ctx: &str) -> napi::Result<(), ZrCode> {
+        if y >= self.raw.rows {
+            return Err(invalid_argument(format!(
+                "{ctx}: row {y} is outside the {}x{} framebuffer",
+                self.raw.cols, self.raw.rows
+            )));
+        }
+        Ok(())
+    }
+
+    fn out_of_bounds(&self, ctx: &str, index: usize, x: u32) -> napi::Error<ZrCode> {
+        invalid_argument(format!(
+            "{ctx}: grapheme {index} at column {x} does not fit the {}x{} framebuffer",
+            self.raw.cols, self.raw.rows
+        ))
+    }
+}
+
+#[napi]
+impl Framebuffer {
+    #[napi(constructor)]
+    pub fn new(cols: u32, rows: u32) -> napi::Result<Self, ZrCode> {
+        let mut raw: ffi::zr_fb_t = unsafe { std::mem::zeroed() };
+        let rc = unsafe { ffi::zr_fb_init(&mut raw as *mut _, cols, rows) };
+        if rc != ffi::ZR_OK {
+            return Err(zr_result_error(rc, "Framebuffer"));
+        }
+        Ok(Self {
+            raw,
+            released: false,
+        })
+    }
+
+    #[napi(getter)]
+    pub fn cols(&self) -> u32 {
+        self.raw.cols
+    }
+
+    #[napi(getter)]
+    pub fn rows(&self) -> u32 {
+        self.raw.rows
+    }
+
+    /// Reset every cell to a space in `style` (default all zeros) and drop
+    /// the hyperlinks.
+    #[napi]
+    pub fn clear(&mut self, style: Option<JsObject>) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.clear";
+        self.raw(CTX)?;
+        let style = optional_style(style, CTX)?;
+        let rc = unsafe { ffi::zr_fb_clear(&mut self.raw as *mut _, &style as *const _) };
+        if rc != ffi::ZR_OK {
+            return Err(zr_result_error(rc, CTX));
+        }
+        Ok(())
+    }
+
+    /// The cell at `(x, y)`.
+    #[napi]
+    pub fn cell(&mut self, x: u32, y: u32) -> napi::Result<FramebufferCell, ZrCode> {
+        const CTX: &str = "Framebuffer.cell";
+        self.raw(CTX)?;
+        if x >= self.raw.cols || y >= self.raw.rows {
+            return Err(invalid_argument(format!(
+                "{CTX}: ({x}, {y}) is outside the {}x{} framebuffer",
+                self.raw.cols, self.raw.rows
+            )));
+        }
+        let cell = unsafe { ffi::zr_fb_cell(&mut self.raw as *mut _, x, y) };
+        if cell.is_null() {
+            return Err(invalid_argument(format!("{CTX}: framebuffer has no cells")));
+        }
+        let cell = unsafe { &*cell };
+        Ok(FramebufferCell {
+            glyph: cell_glyph(cell),
+            width: u32::from(cell.width),
+            style: Style {
+                fgRgb: cell.style.fg_rgb,
+                bgRgb: cell.style.bg_rgb,
+                attrs: cell.style.attrs,
+                reserved: 0,
+            },
+        })
+    }
+
+    /// Write one `{ glyph, style? }` cell per grapheme along row `y` from
+    /// column 0. Everything is checked before any cell changes.
+    #[napi(js_name = "putRow")]
+    pub fn put_row(&mut self, y: u32, cells: Vec<JsObject>) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.putRow";
+        self.raw(CTX)?;
+        self.check_row(y, CTX)?;
+        let mut glyphs = Vec::with_capacity(cells.len());
+        let mut styles = Vec::with_capacity(cells.len());
+        for (i, cell) in cells.iter().enumerate() {
+            let ctx = format!("{CTX} cell {i}");
+            validate_known_keys(cell, CELL_WRITE_KEYS, &ctx)?;
+            let glyph = js_str(cell, "glyph", "glyph")
+                .map_err(|_| invalid_argument(format!("{ctx}: glyph must be a string")))?
+                .ok_or_else(|| invalid_argument(format!("{ctx}: glyph is required")))?;
+            let style = js_obj(cell, "style", "style")
+                .map_err(|_| invalid_argument(format!("{ctx}: style must be an object")))?;
+            glyphs.push(glyph);
+            styles.push(optional_style(style, &ctx)?);
+        }
+        let widths = glyphs
+            .iter()
+            .enumerate()
+            .map(|(i, g)| checked_width(g.as_bytes(), i, CTX))
+            .collect::<napi::Result<Vec<u8>, ZrCode>>()?;
+        let columns = layout_run(0, &widths, self.raw.cols).map_err(|i| {
+            let x = widths[..i].iter().map(|&w| u32::from(w)).sum();
+            self.out_of_bounds(CTX, i, x)
+        })?;
+        let puts: Vec<Put<'_>> = (0..glyphs.len())
+            .map(|i| Put {
+                x: columns[i],
+                y,
+                glyph: glyphs[i].as_bytes(),
+                width: widths[i],
+                style: styles[i],
+            })
+            .collect();
+        paint(&mut self.raw, &puts, CTX)
+    }
+
+    /// Write `graphemes` left to right from `(x, y)` in one style, each
+    /// advancing by its width. Everything is checked before any cell changes.
+    #[napi(js_name = "putSpan")]
+    pub fn put_span(
+        &mut self,
+        x: u32,
+        y: u32,
+        graphemes: Vec<String>,
+        style: Option<JsObject>,
+    ) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.putSpan";
+        self.raw(CTX)?;
+        self.check_row(y, CTX)?;
+        let style = optional_style(style, CTX)?;
+        let widths = graphemes
+            .iter()
+            .enumerate()
+            .map(|(i, g)| checked_width(g.as_bytes(), i, CTX))
+            .collect::<napi::Result<Vec<u8>, ZrCode>>()?;
+        let columns = layout_run(x, &widths, self.raw.cols).map_err(|i| {
+            let at = widths[..i]
+                .iter()
+                .fold(x, |at, &w| at.saturating_add(u32::from(w)));
+            self.out_of_bounds(CTX, i, at)
+        })?;
+        let puts: Vec<Put<'_>> = graphemes
+            .iter()
+            .zip(columns.iter().zip(&widths))
+            .map(|(glyph, (&x, &width))| Put {
+                x,
+                y,
+                glyph: glyph.as_bytes(),
+                width,
+                style,
+            })
+            .collect();
+        paint(&mut self.raw, &puts, CTX)
+    }
+
+    /// Fill `rect` with `glyph` in one style. A wide glyph repeats every two
+    /// columns and an odd last column gets a space. The rect must lie inside
+    /// the framebuffer.
+    #[napi(js_name = "fillRect")]
+    pub fn fill_rect(
+        &mut self,
+        rect: CellRect,
+        glyph: String,
+        style: Option<JsObject>,
+    ) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.fillRect";
+        self.raw(CTX)?;
+        check_rect(rect, self.raw.cols, self.raw.rows, CTX)?;
+        let style = optional_style(style, CTX)?;
+        let width = checked_width(glyph.as_bytes(), 0, CTX)?;
+        let mut puts = Vec::new();
+        for y in rect.y..rect.y + rect.h {
+            let mut x = rect.x;
+            while x < rect.x + rect.w {
+                let (glyph, width) = if x + u32::from(width) <= rect.x + rect.w {
+                    (glyph.as_bytes(), width)
+                } else {
+                    (&b" "[..], 1)
+                };
+                puts.push(Put {
+                    x,
+                    y,
+                    glyph,
+                    width,
+                    style,
+                });
+                x += u32::from(width);
+            }
+        }
+        paint(&mut self.raw, &puts, CTX)
+    }
+
+    /// Shift the rows of `rect` by `dy` (positive moves content down) and
+    /// fill the vacated rows with spaces in `style`. Wide glyphs cut by the
+    /// rect's side edges become spaces.
+    #[napi]
+    pub fn scroll(
+        &mut self,
+        rect: CellRect,
+        dy: i32,
+        style: Option<JsObject>,
+    ) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.scroll";
+        self.raw(CTX)?;
+        check_rect(rect, self.raw.cols, self.raw.rows, CTX)?;
+        let fill = optional_style(style, CTX)?;
+        scroll_fb(&mut self.raw, rect, dy, fill);
+        Ok(())
+    }
+
+    /// Reallocate to `cols` x `rows`, as the engine's buffers are on a
+    /// terminal resize. The overlapping top-left region keeps its cells and
+    /// hyperlinks; newly exposed cells are spaces in `style` (default all
+    /// zeros), never left over from the old storage. A wide glyph cut by
+    /// the new right edge becomes a space. On failure nothing changes.
+    #[napi]
+    pub fn resize(
+        &mut self,
+        cols: u32,
+        rows: u32,
+        style: Option<JsObject>,
+    ) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Framebuffer.resize";
+        self.raw(CTX)?;
+        let fill = optional_style(style, CTX)?;
+        resize_fb(&mut self.raw, cols, rows, fill, CTX)
+    }
+
+    /// Every cell as `{ g, w, fg, bg, attrs }`, row-major, for golden tests.
+    #[napi]
+    pub fn snapshot(&mut self) -> napi::Result<FramebufferSnapshot, ZrCode> {
+        self.raw("Framebuffer.snapshot")?;
+        let cells = fb_cells(&mut self.raw)
+            .iter()
+            .map(|cell| SnapshotCell {
+                g: cell_glyph(cell),
+                w: u32::from(cell.width),
+                fg: cell.style.fg_rgb,
+                bg: cell.style.bg_rgb,
+                attrs: cell.style.attrs,
+            })
+            .collect();
+        Ok(FramebufferSnapshot {
+            cols: self.raw.cols,
+            rows: self.raw.rows,
+            cells,
+        })
+    }
+
+    /// The text of each row, trailing spaces trimmed.
+    #[napi(js_name = "toLines")]
+    pub fn to_lines(&mut self) -> napi::Result<Vec<String>, ZrCode> {
+        self.raw("Framebuffer.toLines")?;
+        Ok(fb_lines(&mut self.raw))
+    }
+
+    /// Free the cell storage now instead of waiting for GC. Idempotent.
+    #[napi]
+    pub fn release(&mut self) {
+        if !self.released {
+            unsafe { ffi::zr_fb_release(&mut self.raw as *mut _) };
+            self.released = true;
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+pub(crate) fn default_diff_caps() -> ffi::plat_caps_t {
+    ffi::plat_caps_t {
+        color_mode: 3,
+        supports_mouse: 0,
+        supports_bracketed_paste: 0,
+        supports_focus_events: 0,
+        supports_osc52: 0,
+        supports_sync_update: 0,
+        supports_scroll_region: 0,
+        supports_cursor_shape: 1,
+        supports_output_wait_writable: 0,
+        supports_underline_styles: 0,
+        supports_colored_underlines: 0,
+        supports_hyperlinks: 0,
+        sgr_attrs_supported: ffi::ZR_STYLE_ATTR_ALL_MASK,
+    }
+}
+
+pub(crate) fn apply_diff_caps(
+    dst: &mut ffi::plat_caps_t,
+    obj: &JsObject,
+    ctx: &str,
+) -> napi::Result<(), ZrCode> {
+    validate_known_keys(obj, DIFF_CAPS_KEYS, &format!("{ctx} caps"))?;
+    let invalid = |_| invalid_argument(format!("{ctx}: invalid caps value"));
+    if let Some(v) = js_u32(obj, "colorMode", "color_mode").map_err(invalid)? {
+        dst.color_mode = u8::try_from(v).map_err(|_| invalid(()))?;
+    }
+    if let Some(v) = js_u32(obj, "sgrAttrsSupported", "sgr_attrs_supported").map_err(invalid)? {
+        dst.sgr_attrs_supported = v;
+    }
+    let flags: [(&str, &str, &mut u8); 6] = [
+        (
+            "supportsScrollRegion",
+            "supports_scroll_region",
+            &mut dst.supports_scroll_region,
+        ),
+        (
+            "supportsCursorShape",
+            "supports_cursor_shape",
+            &mut dst.supports_cursor_shape,
+        ),
+        (
+            "supportsSyncUpdate",
+            "supports_sync_update",
+            &mut dst.supports_sync_update,
+        ),
+        (
+            "supportsUnderlineStyles",
+            "supports_underline_styles",
+            &mut dst.supports_underline_styles,
+        ),
+        (
+            "supportsColoredUnderlines",
+            "supports_colored_underlines",
+            &mut dst.supports_colored_underlines,
+        ),
+        (
+            "supportsHyperlinks",
+            "supports_hyperlinks",
+            &mut dst.supports_hyperlinks,
+        ),
+    ];
+    for (primary, alias, field) in flags {
+        if let Some(v) = js_u8_bool(obj, primary, alias).map_err(invalid)? {
+            *field = v;
+        }
+    }
+    Ok(())
+}
+
+/// Reject framebuffer pairs the differ cannot compare cell-for-cell.
+pub(crate) fn check_same_size(
+    prev: &ffi::zr_fb_t,
+    next: &ffi::zr_fb_t,
+    ctx: &str,
+) -> napi::Result<(), ZrCode> {
+    if prev.cols != next.cols || prev.rows != next.rows {
+        return Err(invalid_argument(format!(
+            "{ctx}: prev is {}x{} but next is {}x{}; framebuffers must have identical dimensions",
+            prev.cols, prev.rows, next.cols, next.rows
+        )));
+    }
+    Ok(())
+}
+
+/// Cursor the standalone diffs leave alone: visible, position unrequested.
+const DIFF_CURSOR: ffi::zr_cursor_state_t = ffi::zr_cursor_state_t {
+    x: -1,
+    y: -1,
+    shape: 0,
+    visible: 1,
+    blink: 0,
+    reserved0: 0,
+};
+
+const EMPTY_DAMAGE_RECT: ffi::zr_damage_rect_t = ffi::zr_damage_rect_t {
+    x0: 0,
+    y0: 0,
+    x1: 0,
+    y1: 0,
+    _link: 0,
+};
+
+thread_local! {
+    /// Internal scratch damage rects for `zr_diff_render`. Each JS thread
+    /// (main or worker) owns one, so it needs no locking; it is reused across
+    /// diffs and reallocated only when `diff_max_damage_rects` changes.
+    static DAMAGE_SCRATCH: RefCell<Vec<ffi::zr_damage_rect_t>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// Lend `f` this thread's scratch buffer sized to `max_rects`. A re-entrant
+/// call gets a temporary buffer instead.
+pub(crate) fn with_damage_scratch<R>(
+    max_rects: u32,
+    f: impl FnOnce(&mut [ffi::zr_damage_rect_t]) -> R,
+) -> R {
+    DAMAGE_SCRATCH.with(|cell| match cell.try_borrow_mut() {
+        Ok(mut scratch) => {
+            if scratch.len() != max_rects as usize {
+                *scratch = vec![EMPTY_DAMAGE_RECT; max_rects as usize];
+            }
+            f(&mut scratch)
+        }
+        Err(_) => f(&mut vec![EMPTY_DAMAGE_RECT; max_rects as usize]),
+    })
+}
+
+/// One `zr_diff_render` call: the emitted bytes, the terminal state after
+/// them and the differ's stats, or the engine's result code. With `damage`,
+/// the damage rects the differ built are copied there (see `damage_rects`).
+#[allow(clippy::too_many_arguments)]
+fn run_diff(
+    prev: &ffi::zr_fb_t,
+    next: &ffi::zr_fb_t,
+    caps: &ffi::plat_caps_t,
+    limits: &ffi::zr_limits_t,
+    desired_cursor_state: &ffi::zr_cursor_state_t,
+    initial_term_state: &ffi::zr_term_state_t,
+    enable_scroll_optimizations: u8,
+    enable_erase_optimizations: u8,
+    damage: Option<&mut Vec<DamageRect>>,
+) -> Result<(Vec<u8>, ffi::zr_term_state_t, ffi::zr_diff_stats_t), i32> {
+    let mut out = vec![0u8; limits.out_max_bytes_per_frame as usize];
+    let mut out_len = 0usize;
+    let mut out_final_term_state: ffi::zr_term_state_t = unsafe { std::mem::zeroed() };
+    let mut out_stats: ffi::zr_diff_stats_t = unsafe { std::mem::zeroed() };
+
+    let rc = with_damage_scratch(limits.diff_max_damage_rects, |scratch| {
+        let rc = unsafe {
+            ffi::zr_diff_render(
+                prev as *const _,
+                next as *const _,
+                caps as *const _,
+                initial_term_state as *const _,
+                desired_cursor_state as *const _,
+                limits as *const _,
+                scratch.as_mut_ptr(),
+                scratch.len() as u32,
+                enable_scroll_optimizations,
+                enable_erase_optimizations,
+                out.as_mut_ptr(),
+                out.len(),
+                &mut out_len as *mut _,
+                &mut out_final_term_state as *mut _,
+                &mut out_stats as *mut _,
+            )
+        };
+        if let Some(damage) = damage {
+            if rc == ffi::ZR_OK {
+                *damage = damage_rects(&out_stats, scratch, next.cols, next.rows);
+            }
+        }
+        rc
+    });
+    if rc != ffi::ZR_OK {
+        return Err(rc);
+    }
+    out.truncate(out_len);
+    Ok((out, out_final_term_state, out_stats))
+}
+
+/// Swap `fb` for a `cols` x `rows` framebuffer of spaces in `fill` holding
+/// its overlapping cells and its hyperlinks. `fb` is untouched on failure.
+pub(crate) fn resize_fb(
+    fb: &mut ffi::zr_fb_t,
+    cols: u32,
+    rows: u32,
+    fill: ffi::zr_style_t,
+    ctx: &str,
+) -> napi::Result<(), ZrCode> {
+    let mut next = OwnedFb::new(cols, rows).map_err(|rc| zr_result_error(rc, ctx))?;
+    let rc = unsafe { ffi::zr_fb_clear(&mut next.0, &fill) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+    copy_overlap(fb, &mut next.0);
+    let rc = unsafe { ffi::zr_fb_links_clone_from(&mut next.0, fb) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+    std::mem::swap(fb, &mut next.0);
+    Ok(())
+}
+
+/// Copy the top-left region `prev` and `next` share into `next`, turning
+/// wide glyphs cut by `next`'s right edge into spaces.
+pub(crate) fn copy_overlap(prev: &ffi::zr_fb_t, next: &mut ffi::zr_fb_t) {
+    let (cols, rows) = (
+        prev.cols.min(next.cols) as usize,
+        prev.rows.min(next.rows) as usize,
+    );
+    if cols == 0 || rows == 0 || prev.cells.is_null() || next.cells.is_null() {
+        return;
+    }
+    let (prev_cols, next_cols) = (prev.cols as usize, next.cols as usize);
+    let src = unsafe { std::slice::from_raw_parts(prev.cells, prev_cols * prev.rows as usize) };
+    let dst = unsafe { std::slice::from_raw_parts_mut(next.cells, next_cols * next.rows as usize) };
+    for y in 0..rows {
+        let row = &mut dst[y * next_cols..(y + 1) * next_cols];
+        row[..cols].copy_from_slice(&src[y * prev_cols..y * prev_cols + cols]);
+        repair_wide_pairs(row);
+    }
+}
+
+/// Run `zr_diff_render` from `prev` to `next` and return the emitted bytes.
+/// The starting terminal state is unknown unless `initial_style` is given, in
+/// which case the screen is taken to be blank and in that SGR state already,
+/// so no clear or leading reset is emitted. With `erase_blank_tails`, the
+/// differ clears blank row tails and bottom rows with EL/ED.
+pub(crate) fn diff_to_bytes(
+    prev: &ffi::zr_fb_t,
+    next: &ffi::zr_fb_t,
+    caps: &ffi::plat_caps_t,
+    limits: &ffi::zr_limits_t,
+    desired_cursor_state: &ffi::zr_cursor_state_t,
+    initial_style: Option<&ffi::zr_style_t>,
+    erase_blank_tails: bool,
+) -> Result<Vec<u8>, i32> {
+    let mut initial_term_state: ffi::zr_term_state_t = unsafe { std::mem::zeroed() };
+    if let Some(style) = initial_style {
+        initial_term_state.style = *style;
+        initial_term_state.flags |=
+            ffi::ZR_TERM_STATE_STYLE_VALID | ffi::ZR_TERM_STATE_SCREEN_VALID;
+    }
+    let (out, _, _) = run_diff(
+        prev,
+        next,
+        caps,
+        limits,
+        desired_cursor_state,
+        &initial_term_state,
+        0,
+        u8::from(erase_blank_tails),
+        None,
+    )?;
+    Ok(out)
+}
+
+/// `diffRender` options.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct DiffOpts {
+    pub(crate) erase_blank_tails: bool,
+    pub(crate) enable_scroll_optimizations: bool,
+    /// `prev` is what the screen shows already (SGR state and cursor
+    /// unknown), so the diff starts without a clear.
+    pub(crate) assume_screen: bool,
+    pub(crate) stats: bool,
+    pub(crate) damage: bool,
+}
+
+fn parse_diff_opts(obj: &JsObject) -> napi::Result<DiffOpts, ZrCode> {
+    validate_known_keys(obj, DIFF_OPTS_KEYS, "diffRender opts")?;
+    let flag = |key: &str, snake: &str| {
+        js_u8_bool(obj, key, snake)
+            .map(|v| v == Some(1))
+            .map_err(|_| invalid_argument(format!("diffRender: invalid opts.{key}")))
+    };
+    Ok(DiffOpts {
+        erase_blank_tails: flag("eraseBlankTails", "erase_blank_tails")?,
+        enable_scroll_optimizations: flag(
+            "enableScrollOptimizations",
+            "enable_scroll_optimizations",
+        )?,
+        assume_screen: flag("assumeScreen", "assume_screen")?,
+        stats: flag("stats", "stats")?,
+        damage: flag("damage", "damage")?,
+    })
+}
+
+/// Diff two caller-owned framebuffers and return the terminal bytes that turn
+/// `prev` into `next`, or, when `opts` asks for `stats` or `damage`, the bytes
+/// with those. Unless `assumeScreen` is set the terminal state is unknown at
+/// the start, so output is deterministic for a given pair and caps.
+#[napi(js_name = "diffRender")]
+pub fn diff_render(
+    prev: &Framebuffer,
+    next: &Framebuffer,
+    caps: Option<JsObject>,
+    opts: Option<JsObject>,
+) -> napi::Result<Either<Uint8Array, FramebufferDiff>, ZrCode> {
+    let prev = prev.raw("diffRender prev")?;
+    let next = next.raw("diffRender next")?;
+    check_same_size(prev, next, "diffRender")?;
+
+    let mut plat_caps = default_diff_caps();
+    if let Some(obj) = caps {
+        apply_diff_caps(&mut plat_caps, &obj, "diffRender")?;
+    }
+    let opts = match opts {
+        Some(obj) => parse_diff_opts(&obj)?,
+        None => DiffOpts::default(),
+    };
+
+    let mut damage = Vec::new();
+    let (bytes, stats) = diff_with_stats(
+        prev,
+        next,
+        &plat_caps,
+        &opts,
+        opts.damage.then_some(&mut damage),
+    )
+    .map_err(|rc| zr_result_error(rc, "diffRender"))?;
+    if !opts.stats && !opts.damage {
+        return Ok(Either::A(Uint8Array::new(bytes)));
+    }
+    Ok(Either::B(FramebufferDiff {
+        bytes: Uint8Array::new(bytes),
+        stats: opts.stats.then(|| DiffStats::from(&stats)),
+        damage: opts.damage.then_some(damage),
+    }))
+}
+
+/// What the differ did for one `diffRender` call; mirrors
+/// `zr_diff_stats_t`.
+#[napi(object)]
+#[allow(non_snake_case)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffStats {
+    pub dirtyLines: u32,
+    pub dirtyCells: u32,
+    pub damageRects: u32,
+    pub damageCells: u32,
+    /// Damage covered the whole frame.
+    pub damageFullFrame: bool,
+    /// Full line sweep was used.
+    pub sweepUsed: bool,
+    /// Damage-rect path was used.
+    pub damageUsed: bool,
+    pub scrollOptAttempted: bool,
+    pub scrollOptHit: bool,
+    /// Hash-collision guard hits.
+    pub collisionGuardHits: u32,
+    /// Blank row tails cleared with EL.
+    pub lineErases: u32,
+    /// Blank bottom blocks cleared with ED.
+    pub screenErases: u32,
+    pub bytesEmitted: u32,
+}
+
+impl From<&ffi::zr_diff_stats_t> for DiffStats {
+    fn from(stats: &ffi::zr_diff_stats_t) -> Self {
+        DiffStats {
+            dirtyLines: stats.dirty_lines,
+            dirtyCells: stats.dirty_cells,
+            damageRects: stats.damage_rects,
+            damageCells: stats.damage_cells,
+            damageFullFrame: stats.damage_full_frame != 0,
+            sweepUsed: stats.path_sweep_used != 0,
+            damageUsed: stats.path_damage_used != 0,
+            scrollOptAttempted: stats.scroll_opt_attempted != 0,
+            scrollOptHit: stats.scroll_opt_hit != 0,
+            collisionGuardHits: stats.collision_guard_hits,
+            lineErases: stats.line_erases,
+            screenErases: stats.screen_erases,
+            bytesEmitted: u32::try_from(stats.bytes_emitted).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// Cells a diff considered dirty, inclusive on both ends.
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DamageRect {
+    pub x0: u32,
+    pub y0: u32,
+    pub x1: u32,
+    pub y1: u32,
+}
+
+/// The damage geometry behind `stats`: the whole frame when the differ
+/// gave up on tracking, the rects it built in `scratch` on the damage-rect
+/// path, and nothing on the row sweep, which tracks no rects.
+pub(crate) fn damage_rects(
+    stats: &ffi::zr_diff_stats_t,
+    scratch: &[ffi::zr_damage_rect_t],
+    cols: u32,
+    rows: u32,
+) -> Vec<DamageRect> {
+    if stats.damage_full_frame != 0 {
+        if cols == 0 || rows == 0 {
+            return Vec::new();
+        }
+        return vec![DamageRect {
+            x0: 0,
+            y0: 0,
+            x1: cols - 1,
+            y1: rows - 1,
+        }];
+    }
+    if stats.path_damage_used == 0 {
+        return Vec::new();
+    }
+    let count = scratch.len().min(stats.damage_rects as usize);
+    scratch[..count]
+        .iter()
+        .map(|r| DamageRect {
+            x0: r.x0,
+            y0: r.y0,
+            x1: r.x1,
+            y1: r.y1,
+        })
+        .collect()
+}
+
+/// `diffRender` result when `stats` or `damage` is asked for.
+#[napi(object)]
+pub struct FramebufferDiff {
+    pub bytes: Uint8Array,
+    /// Set with `opts.stats`.
+    pub stats: Option<DiffStats>,
+    /// Set with `opts.damage`: the damage rects behind `stats.damageRects`
+    /// (see `damage_rects`).
+    pub damage: Option<Vec<DamageRect>>,
+}
+
+/// One `diffRender` diff: the bytes with the differ's stats, copying its
+/// damage rects into `damage` if given.
+pub(crate) fn diff_with_stats(
+    prev: &ffi::zr_fb_t,
+    next: &ffi::zr_fb_t,
+    caps: &ffi::plat_caps_t,
+    opts: &DiffOpts,
+    damage: Option<&mut Vec<DamageRect>>,
+) -> Result<(Vec<u8>, ffi::zr_diff_stats_t), i32> {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let mut initial_term_state: ffi::zr_term_state_t = unsafe { std::mem::zeroed() };
+    if opts.assume_screen {
+        initial_term_state.flags = ffi::ZR_TERM_STATE_SCREEN_VALID;
+    }
+    let (bytes, _, stats) = run_diff(
+        prev,
+        next,
+        caps,
+        &limits,
+        &DIFF_CURSOR,
+        &initial_term_state,
+        u8::from(opts.enable_scroll_optimizations),
+        u8::from(opts.erase_blank_tails),
+        damage,
+    )?;
+    Ok((bytes, stats))
+}
@@ -0,0 +1,197 @@
+use crate::error::ZrCode;
+use crate::export::last_resize;
+use crate::{bigint_from_u64, empty_metrics, ffi, owner_engine_guard};
+use napi::bindgen_prelude::BigInt;
+use napi_derive::napi;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+
+/// Full-frame presents `engineRecentFullFrames` remembers per engine.
+pub(crate) const RECENT_FULL_FRAMES_MAX: usize = 32;
+
+/// Why a present repainted every cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum FullFrameReason {
+    /// First present of the engine.
+    Initial,
+    /// The terminal was resized since the previous present.
+    Resize,
+    /// `engineRestoreState` asked for a repaint.
+    Restore,
+    /// `engineWriteRaw` bytes went out after the previous present.
+    RawWrite,
+    /// The engine no longer trusted the screen contents for another reason.
+    Invalidated,
+    /// The scroll optimization shifted rows; the engine counts the frame as
+    /// fully damaged.
+    Scroll,
+    /// The changes themselves covered the frame or overflowed damage tracking.
+    Damage,
+}
+
+impl FullFrameReason {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Initial => "initial",
+            Self::Resize => "resize",
+            Self::Restore => "restore",
+            Self::RawWrite => "raw",
+            Self::Invalidated => "invalidated",
+            Self::Scroll => "scroll",
+            Self::Damage => "damage",
+        }
+    }
+}
+
+/// What the present diff saw (see protect.rs).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DiffOutcome {
+    /// The terminal state passed in lacked `ZR_TERM_STATE_SCREEN_VALID`.
+    pub(crate) screen_invalid: bool,
+    /// The scroll optimization was used.
+    pub(crate) scroll_hit: bool,
+}
+
+/// What the binding knew about a full-frame present.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FullFrameCauses {
+    pub(crate) first: bool,
+    pub(crate) resized: bool,
+    pub(crate) restore: bool,
+    pub(crate) raw_write: bool,
+    /// `None` where the diff cannot be observed.
+    pub(crate) diff: Option<DiffOutcome>,
+}
+
+/// Pick the reason for a full-frame present, most specific first.
+pub(crate) fn classify_full_frame(causes: FullFrameCauses) -> FullFrameReason {
+    let screen_invalid = causes.diff.is_none_or(|d| d.screen_invalid);
+    if causes.restore {
+        FullFrameReason::Restore
+    } else if causes.raw_write {
+        FullFrameReason::RawWrite
+    } else if causes.first && screen_invalid {
+        FullFrameReason::Initial
+    } else if causes.resized && screen_invalid {
+        FullFrameReason::Resize
+    } else if let Some(diff) = causes.diff {
+        if diff.screen_invalid {
+            FullFrameReason::Invalidated
+        } else if diff.scroll_hit {
+            FullFrameReason::Scroll
+        } else {
+            FullFrameReason::Damage
+        }
+    } else {
+        FullFrameReason::Damage
+    }
+}
+
+thread_local! {
+    static DIFF: Cell<Option<DiffOutcome>> = const { Cell::new(None) };
+}
+
+/// Record the outcome of the present diff for the running present.
+pub(crate) fn note_diff(outcome: DiffOutcome) {
+    DIFF.with(|d| d.set(Some(outcome)));
+}
+
+/// One full-frame present.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct FullFrame {
+    pub frameIndex: BigInt,
+    /// `"initial"`, `"resize"`, `"restore"`, `"raw"`, `"invalidated"`,
+    /// `"scroll"` or `"damage"`.
+    pub reason: String,
+}
+
+#[derive(Default)]
+struct LogInner {
+    recent: VecDeque<(u64, FullFrameReason)>,
+    last_frame_index: Option<u64>,
+    resized: bool,
+}
+
+/// Ring of the engine's recent full-frame presents.
+#[derive(Default)]
+pub(crate) struct FullFrameLog {
+    inner: Mutex<LogInner>,
+}
+
+impl FullFrameLog {
+    fn lock(&self) -> MutexGuard<'_, LogInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Remember a polled resize for the next present's reason.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8]) {
+        if rc > 0 && last_resize(&out[..out.len().min(rc as usize)]).is_some() {
+            self.lock().resized = true;
+        }
+    }
+
+    /// Run `present` and log it when the engine reports full-frame damage.
+    /// `restore` and `raw_write` say whether a repaint after
+    /// `engineRestoreState` or `engineWriteRaw` is due.
+    pub(crate) fn record(
+        &self,
+        engine: *mut ffi::zr_engine_t,
+        restore: bool,
+        raw_write: bool,
+        present: impl FnOnce() -> i32,
+    ) -> i32 {
+        DIFF.with(|d| d.set(None));
+        let rc = present();
+        let diff = DIFF.with(Cell::take);
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+        let mut metrics = empty_metrics();
+        if unsafe { ffi::engine_get_metrics(engine, &mut metrics as *mut _) } != ffi::ZR_OK {
+            return rc;
+        }
+        let mut inner = self.lock();
+        let first = inner.last_frame_index.is_none();
+        if inner.last_frame_index == Some(metrics.frame_index) {
+            /* Nothing was presented (idle). */
+            return rc;
+        }
+        inner.last_frame_index = Some(metrics.frame_index);
+        let resized = std::mem::take(&mut inner.resized);
+        if metrics.damage_full_frame != 0 {
+            let reason = classify_full_frame(FullFrameCauses {
+                first,
+                resized,
+                restore,
+                raw_write,
+                diff,
+            });
+            if inner.recent.len() == RECENT_FULL_FRAMES_MAX {
+                inner.recent.pop_front();
+            }
+            inner.recent.push_back((metrics.frame_index, reason));
+        }
+        rc
+    }
+}
+
+/// Frame indices of the last `RECENT_FULL_FRAMES_MAX` presents whose damage
+/// covered the whole frame, oldest first, each with why it happened.
+#[napi(js_name = "engineRecentFullFrames")]
+pub fn engine_recent_full_frames(engine_id: u32) -> napi::Result<Vec<FullFrame>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineRecentFullFrames")?;
+    let inner = guard.slot.full_frames.lock();
+    Ok(inner
+        .recent
+        .iter()
+        .map(|&(index, reason)| FullFrame {
+            frameIndex: bigint_from_u64(index),
+            reason: reason.name().to_string(),
+        })
+        .collect())
+}
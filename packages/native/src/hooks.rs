@@ -0,0 +1,26 @@
+use crate::ffi;
+use std::sync::Once;
+
+static INSTALL: Once = Once::new();
+
+/// Hand the engine the binding's hook points (zr_engine_hooks.h): terminal
+/// flushes (spans.rs), size queries (size.rs), input parsing (unknown.rs),
+/// drawlist execution (state.rs), the present diff (protect.rs) and the
+/// debug overlay (overlay.rs). Each hook passes through to the engine's own
+/// call unless the engine it runs for has something to change, so one
+/// process-wide table serves every engine. Runs once, before the first
+/// `engine_create`.
+pub(crate) fn install_engine_hooks() {
+    INSTALL.call_once(|| {
+        let hooks = ffi::zr_engine_hooks_t {
+            plat_write_output: Some(crate::spans::hook_plat_write_output),
+            plat_get_size: Some(crate::size::hook_plat_get_size),
+            input_parse_bytes: Some(crate::unknown::hook_input_parse_bytes),
+            input_parse_bytes_prefix: Some(crate::unknown::hook_input_parse_bytes_prefix),
+            dl_execute: Some(crate::state::hook_dl_execute),
+            diff_render_ex: Some(crate::protect::hook_diff_render_ex),
+            debug_overlay_render: Some(crate::overlay::hook_debug_overlay_render),
+        };
+        unsafe { ffi::zr_engine_hooks_set(&hooks) };
+    });
+}
@@ -0,0 +1,139 @@
+use crate::error::{zr_result_error, ZrCode};
+use crate::ffi;
+use crate::registry::find_engine_slot;
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+const IDLE_TRANSITIONS_MAX: usize = 64;
+
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct IdleTransition {
+    /// `true` when presenting was paused, `false` when it resumed.
+    pub idle: bool,
+    /// Quiet period before pausing, or time spent paused before resuming.
+    pub durationMs: u32,
+}
+
+struct IdleInner {
+    last_activity: Instant,
+    idle_since: Option<Instant>,
+    transitions: VecDeque<IdleTransition>,
+}
+
+/// Present throttling for engines created with `idleMs`.
+///
+/// Activity is anything that can change the next frame: a submitted drawlist,
+/// polled input, a config update, or queued terminal output. Once frames stay
+/// undamaged for `idle_ms` without activity, presents are skipped until the
+/// next activity.
+pub(crate) struct IdleState {
+    idle_ms: u32,
+    inner: Mutex<IdleInner>,
+}
+
+fn duration_ms(d: Duration) -> u32 {
+    d.as_millis().min(u32::MAX as u128) as u32
+}
+
+impl IdleState {
+    pub(crate) fn new(idle_ms: u32) -> Self {
+        Self {
+            idle_ms,
+            inner: Mutex::new(IdleInner {
+                last_activity: Instant::now(),
+                idle_since: None,
+                transitions: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IdleInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.idle_ms != 0
+    }
+
+    fn push_transition(inner: &mut IdleInner, transition: IdleTransition) {
+        if inner.transitions.len() == IDLE_TRANSITIONS_MAX {
+            inner.transitions.pop_front();
+        }
+        inner.transitions.push_back(transition);
+    }
+
+    pub(crate) fn note_activity(&self, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        let mut inner = self.lock();
+        inner.last_activity = now;
+        if let Some(since) = inner.idle_since.take() {
+            let duration = now.saturating_duration_since(since);
+            Self::push_transition(
+                &mut inner,
+                IdleTransition {
+                    idle: false,
+                    durationMs: duration_ms(duration),
+                },
+            );
+        }
+    }
+
+    /// `true` while idle: the present can be skipped entirely.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.enabled() && self.lock().idle_since.is_some()
+    }
+
+    /// Record the outcome of a present and enter idle after a long enough
+    /// run of undamaged frames.
+    pub(crate) fn after_present(&self, damaged: bool, now: Instant) {
+        if !self.enabled() {
+            return;
+        }
+        let mut inner = self.lock();
+        if damaged {
+            inner.last_activity = now;
+            return;
+        }
+        let quiet = now.saturating_duration_since(inner.last_activity);
+        if inner.idle_since.is_none() && quiet >= Duration::from_millis(u64::from(self.idle_ms)) {
+            inner.idle_since = Some(now);
+            Self::push_transition(
+                &mut inner,
+                IdleTransition {
+                    idle: true,
+                    durationMs: duration_ms(quiet),
+                },
+            );
+        }
+    }
+
+    pub(crate) fn take_transitions(&self) -> Vec<IdleTransition> {
+        self.lock().transitions.drain(..).collect()
+    }
+}
+
+/// Whether presenting is currently paused by the idle policy. Throws
+/// `ZR_ERR_INVALID_ARGUMENT` for an unknown or destroyed engine id.
+#[napi(js_name = "engineIsIdle")]
+pub fn engine_is_idle(engine_id: u32) -> napi::Result<bool, ZrCode> {
+    let slot = find_engine_slot(engine_id)
+        .ok_or_else(|| zr_result_error(ffi::ZR_ERR_INVALID_ARGUMENT, "engineIsIdle"))?;
+    Ok(slot.idle.is_idle())
+}
+
+/// Drain idle/active transitions recorded since the last call.
+#[napi(js_name = "engineTakeIdleTransitions")]
+pub fn engine_take_idle_transitions(engine_id: u32) -> napi::Result<Vec<IdleTransition>, ZrCode> {
+    let slot = find_engine_slot(engine_id).ok_or_else(|| {
+        zr_result_error(ffi::ZR_ERR_INVALID_ARGUMENT, "engineTakeIdleTransitions")
+    })?;
+    Ok(slot.idle.take_transitions())
+}
@@ -0,0 +1,69 @@
+use crate::ffi;
+use std::cell::Cell;
+use std::sync::{Mutex, MutexGuard};
+
+thread_local! {
+    static ACTIVE: Cell<Option<ffi::zr_style_t>> = const { Cell::new(None) };
+}
+
+/// SGR state the terminal is in before the engine's first present, from
+/// `initialStyle` at create. The engine starts without a known style and
+/// leads its first frame with a reset; seeding the style lets that frame
+/// start from what the host already set. Cleared by the first present that
+/// succeeds.
+pub(crate) struct InitialStyle {
+    pending: Mutex<Option<ffi::zr_style_t>>,
+}
+
+impl InitialStyle {
+    pub(crate) fn new(style: Option<ffi::zr_style_t>) -> Self {
+        Self {
+            pending: Mutex::new(style),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<ffi::zr_style_t>> {
+        match self.pending.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Run `present` with the style seeded into the diff's starting state.
+    pub(crate) fn apply(&self, present: impl FnOnce() -> i32) -> i32 {
+        let Some(style) = *self.lock() else {
+            return present();
+        };
+        ACTIVE.with(|a| a.set(Some(style)));
+        let rc = present();
+        ACTIVE.with(|a| a.set(None));
+        if rc == ffi::ZR_OK {
+            *self.lock() = None;
+        }
+        rc
+    }
+}
+
+/// `ts` with `style` as its known SGR state, unless it already has one.
+pub(crate) fn seed_style(ts: ffi::zr_term_state_t, style: ffi::zr_style_t) -> ffi::zr_term_state_t {
+    if ts.flags & ffi::ZR_TERM_STATE_STYLE_VALID != 0 {
+        return ts;
+    }
+    ffi::zr_term_state_t {
+        style,
+        flags: ts.flags | ffi::ZR_TERM_STATE_STYLE_VALID,
+        ..ts
+    }
+}
+
+/// Copy of the diff's initial terminal state with the pending initial style
+/// seeded, for the present diff hook (see protect.rs); `None` otherwise.
+pub(crate) fn seeded_term_state(
+    initial: *const ffi::zr_term_state_t,
+) -> Option<ffi::zr_term_state_t> {
+    let style = ACTIVE.with(Cell::get)?;
+    if initial.is_null() {
+        return None;
+    }
+    Some(seed_style(unsafe { *initial }, style))
+}
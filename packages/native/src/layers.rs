@@ -0,0 +1,106 @@
+use crate::drawlist::clear_drawlist;
+use crate::error::{zr_result_to_napi, ZrCode};
+use crate::ffi;
+use crate::registry::{get_engine_guard, EngineSlot};
+use crate::{apply_attr_fallback, submit_prepared};
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Number of layer slots `engineSubmitLayer` accepts (indices `0..LAYERS_MAX`).
+pub(crate) const LAYERS_MAX: u32 = 8;
+
+/// Drawlist per layer index, after the attr-fallback rewrite.
+type Layers = Vec<Option<Vec<u8>>>;
+
+/// Cached layer drawlists, replayed back-to-front into the framebuffer
+/// whenever one of them changes.
+pub(crate) struct LayerStack {
+    layers: Mutex<Layers>,
+}
+
+impl Default for LayerStack {
+    fn default() -> Self {
+        Self {
+            layers: Mutex::new(vec![None; LAYERS_MAX as usize]),
+        }
+    }
+}
+
+impl LayerStack {
+    fn lock(&self) -> MutexGuard<'_, Layers> {
+        match self.layers.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+}
+
+/// Clear the framebuffer and replay every cached layer in index order.
+fn composite(slot: &EngineSlot, layers: &Layers) -> i32 {
+    let rc = submit_prepared(slot, &clear_drawlist(slot.drawlist_version));
+    if rc != ffi::ZR_OK {
+        return rc;
+    }
+    for bytes in layers.iter().flatten() {
+        let rc = submit_prepared(slot, bytes);
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+    }
+    ffi::ZR_OK
+}
+
+/// Replace layer `layer_index` and rebuild the next frame from all layers,
+/// lowest index first, so higher layers paint over lower ones cell by cell.
+/// Unchanged layers stay cached here and need not be resubmitted; an empty
+/// array removes the layer. If the new drawlist is rejected the previous one
+/// is restored and its error is thrown. Indices at or above
+/// `LAYERS_MAX` throw `ZR_ERR_LIMIT`.
+///
+/// Each layer is a separate engine submit, so images survive only from the
+/// highest non-empty layer, and an `engineSubmitDrawlist` in between draws
+/// over the composite until the next layer submit.
+#[napi(js_name = "engineSubmitLayer")]
+pub fn engine_submit_layer(
+    engine_id: u32,
+    layer_index: u32,
+    drawlist: Uint8Array,
+) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(
+        submit_layer(engine_id, layer_index, drawlist),
+        "engineSubmitLayer",
+    )
+}
+
+fn submit_layer(engine_id: u32, layer_index: u32, drawlist: Uint8Array) -> i32 {
+    let guard = match get_engine_guard(engine_id) {
+        Ok(guard) => guard,
+        Err(rc) => return rc,
+    };
+    if !guard.slot.is_owner_thread() {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    if layer_index >= LAYERS_MAX || drawlist.len() > (i32::MAX as usize) {
+        return ffi::ZR_ERR_LIMIT;
+    }
+
+    let slot = &guard.slot;
+    let bytes = if drawlist.is_empty() {
+        None
+    } else {
+        Some(apply_attr_fallback(slot, drawlist.as_ref()).unwrap_or_else(|| drawlist.to_vec()))
+    };
+    slot.idle.note_activity(Instant::now());
+    let _watch = slot.watchdog.watch(slot.engine, "engineSubmitLayer", 0);
+
+    let mut layers = slot.layers.lock();
+    let previous = std::mem::replace(&mut layers[layer_index as usize], bytes);
+    let rc = composite(slot, &layers);
+    if rc != ffi::ZR_OK {
+        layers[layer_index as usize] = previous;
+        composite(slot, &layers);
+    }
+    rc
+}
@@ -1,26 +1,141 @@
 #![allow(non_snake_case)]
 
+mod asserts;
+mod budget;
+mod chunks;
 mod config;
+mod cursor;
 mod debug;
+mod debugjson;
+mod debugsub;
+mod drawlist;
+mod drops;
+mod error;
+mod events;
+mod exithook;
+mod export;
 mod ffi;
+mod framebuffer;
+mod fullframes;
+mod hooks;
+mod idle;
+mod initstyle;
+mod layers;
+mod metrics;
+mod modes;
+mod output;
+mod overlay;
+mod painter;
+mod pollbuf;
+mod prewarm;
+mod protect;
 mod registry;
+mod render;
+mod replay;
+mod report;
+mod scroll;
+mod signals;
+mod size;
+mod spans;
+mod state;
+mod style;
+mod text;
+mod unknown;
+mod watchdog;
 
 #[cfg(test)]
 mod tests;
 
+pub use crate::asserts::{set_assert_mode, take_assert_failures, ASSERT_FAILED};
+pub use crate::budget::{
+    engine_drawlist_budget, engine_submit_drawlist_checked, DrawlistBudget, DrawlistUsage,
+};
+pub use crate::config::{
+    ColorMode, EngineCreateConfig, EngineLimits, EnginePlatConfig, EngineRuntimeConfig, WidthPolicy,
+};
+pub use crate::cursor::{engine_set_cursor_blink, CursorBlinkOptions};
 pub use crate::debug::{
+    debug_category_name_js, debug_severity_name_js, engine_debug_category_counts,
     engine_debug_disable, engine_debug_enable, engine_debug_export, engine_debug_get_payload,
-    engine_debug_get_stats, engine_debug_query, engine_debug_reset, DebugQueryResult, DebugStats,
+    engine_debug_get_payload_alloc, engine_debug_get_stats, engine_debug_query,
+    engine_debug_query_records, engine_debug_reset, DebugCategory, DebugCategoryCounts,
+    DebugQueryResult, DebugRecord, DebugSeverity, DebugStats, DEBUG_CATEGORY_MASK_ALL,
+};
+
+pub use crate::debugjson::engine_debug_export_json;
+pub use crate::debugsub::{engine_debug_subscribe, engine_debug_unsubscribe, DebugTraceEvent};
+pub use crate::drops::{engine_dropped_event_reasons, DroppedEventReasons};
+
+pub use crate::events::{
+    decode_event_batch, engine_event_seq_base, engine_event_timestamps_us, EventRecord,
+    EVENT_BATCH_DECODER_VERSION_MAX, EVENT_BATCH_DECODER_VERSION_MIN,
 };
 
+pub use crate::error::{zi_error_is_ok, zi_error_name, ZrCode};
+pub use crate::exithook::engine_install_exit_hook;
+pub use crate::export::{engine_export_frame, ExportedCell};
+pub use crate::framebuffer::{
+    diff_render, CellRect, DamageRect, DiffStats, Framebuffer, FramebufferCell, FramebufferDiff,
+    FramebufferSnapshot, SnapshotCell,
+};
+pub use crate::fullframes::{engine_recent_full_frames, FullFrame};
+
+pub use crate::idle::{engine_is_idle, engine_take_idle_transitions, IdleTransition};
+
+pub use crate::layers::engine_submit_layer;
+pub use crate::metrics::{engine_get_frame_stats, engine_reset_metrics, FrameStats};
+pub use crate::modes::{
+    engine_is_focused, engine_set_bracketed_paste, engine_set_focus_events, engine_set_mouse,
+};
+pub use crate::overlay::{engine_set_debug_overlay, DebugOverlayOptions};
+pub use crate::painter::Painter;
+pub use crate::pollbuf::{engine_poll_buffer, engine_poll_events_into};
+pub use crate::prewarm::{engine_prewarm, PrewarmReport};
+pub use crate::protect::{engine_set_protected_regions, ProtectedRect};
+pub use crate::render::{render_drawlist_to_bytes, validate_drawlist_js, DrawlistValidation};
+pub use crate::replay::engine_debug_replay;
+pub use crate::report::{engine_last_frame_report, FrameDiffPath, FrameReport};
+pub use crate::scroll::{engine_scroll_region, ScrollRegion};
+pub use crate::signals::engine_install_signal_handlers;
+pub use crate::size::{engine_get_size, engine_post_resize, engine_take_resize, TerminalSize};
+pub use crate::spans::{engine_last_frame_spans, engine_set_frame_spans, FrameSpan, FrameSpans};
+
+pub use crate::state::{engine_restore_state, engine_save_state};
+pub use crate::style::{
+    decode_sgr_attrs, encode_sgr_attrs, make_style, SgrAttrs, Style, ATTR_BLINK, ATTR_BOLD,
+    ATTR_DIM, ATTR_ITALIC, ATTR_OVERLINE, ATTR_REVERSE, ATTR_STRIKE, ATTR_UNDERLINE,
+};
+pub use crate::text::{
+    measure_graphemes, measure_text, segment_graphemes, wrap_text, GraphemeMeasure,
+};
+pub use crate::unknown::UNKNOWN_SEQUENCE_TAG;
+
+pub use crate::watchdog::{engine_take_watchdog_reports, WatchdogReport};
+
+use crate::asserts::AssertScope;
+use crate::chunks::WriteChunking;
 use crate::config::{
-    apply_create_cfg_strict, apply_runtime_cfg_strict, create_default_runtime_cfg,
+    apply_create_cfg_strict, apply_runtime_cfg_strict, config_preset, create_cfg_to_js, js_u8_bool,
+    limits_to_js, parse_binding_create_opts, parse_json_config, runtime_cfg_to_js,
+    validate_known_keys, BindingCreateOptions, CONFIG_PRESET_NAMES,
+};
+use crate::drawlist::{fallback_attrs, rewrite_style_attrs, AttrFallback};
+use crate::error::{invalid_argument, napi_error, zr_error, zr_result_error, zr_result_to_napi};
+use crate::events::{batch_event_count, read_event_times_us};
+use crate::output::{drain_terminal, FLUSH_WAIT_MS};
+use crate::registry::{
+    get_engine_guard, is_engine_registered, register_engine, registered_engine_ids,
+    take_engine_for_owner, take_idle_engine_for_owner, transfer_ownership, EngineGuard, EngineSlot,
+    TransferRefused,
 };
-use crate::registry::{get_engine_guard, register_engine, take_engine_for_owner};
-use napi::bindgen_prelude::{BigInt, Error, Status, Uint8Array};
-use napi::{Env, JsObject};
+use crate::spans::divert_output;
+use crate::unknown::with_unknown_sequences;
+use napi::bindgen_prelude::{BigInt, Either, Error, Int32Array, Status, Uint8Array};
+use napi::{Env, JsObject, ValueType};
 use napi_derive::{module_exports, napi};
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::OnceLock;
+use std::time::Instant;
 
 pub(crate) fn bigint_from_u64(value: u64) -> BigInt {
     BigInt {
@@ -29,8 +144,15 @@ pub(crate) fn bigint_from_u64(value: u64) -> BigInt {
     }
 }
 
-pub(crate) fn invalid_arg_error() -> Error {
-    Error::new(Status::InvalidArg, "ZR_ERR_INVALID_ARGUMENT")
+/// The engine behind `engine_id` for a call from its owner thread. An unknown
+/// or destroyed id, or any other thread, fails as `ZR_ERR_INVALID_ARGUMENT`
+/// reported under `ctx`.
+pub(crate) fn owner_engine_guard(engine_id: u32, ctx: &str) -> napi::Result<EngineGuard, ZrCode> {
+    match get_engine_guard(engine_id) {
+        Ok(guard) if guard.slot.is_owner_thread() => Ok(guard),
+        Ok(_) => Err(zr_result_error(ffi::ZR_ERR_INVALID_ARGUMENT, ctx)),
+        Err(rc) => Err(zr_result_error(rc, ctx)),
+    }
 }
 
 // Keep the addon resident for process lifetime so worker-thread TLS cleanup
@@ -168,6 +290,20 @@ pub struct EngineMetrics {
     pub damageRectsLastFrame: u32,
     pub damageCellsLastFrame: u32,
     pub damageFullFrame: bool,
+    /// Writes the last present was split into (0 unless `writeChunkBytes` is set).
+    pub writeChunksLastFrame: u32,
+    /// Chunks of the last present after which the terminal was not writable.
+    pub writeStallsLastFrame: u32,
+    pub writeChunksTotal: BigInt,
+    pub writeStallsTotal: BigInt,
+    /// Blank row tails the differ cleared with EL (`enableEraseOptimizations`).
+    pub diffLineErasesTotal: BigInt,
+    /// Blank bottom blocks the differ cleared with ED (`enableEraseOptimizations`).
+    pub diffScreenErasesTotal: BigInt,
+    /// Input bytes the parser could not decode (malformed CSI/SS3, invalid UTF-8).
+    pub inputParseErrorsTotal: u32,
+    /// Bracketed pastes dropped for exceeding the 64 KiB payload ring.
+    pub pasteDroppedTotal: u32,
 }
 
 #[napi(object)]
@@ -190,7 +326,7 @@ pub struct TerminalCaps {
     pub sgrAttrsSupported: u32,
 }
 
-fn empty_metrics() -> ffi::zr_metrics_t {
+pub(crate) fn empty_metrics() -> ffi::zr_metrics_t {
     ffi::zr_metrics_t {
         struct_size: std::mem::size_of::<ffi::zr_metrics_t>() as u32,
         negotiated_engine_abi_major: 0,
@@ -218,10 +354,14 @@ fn empty_metrics() -> ffi::zr_metrics_t {
         damage_cells_last_frame: 0,
         damage_full_frame: 0,
         _pad2: [0, 0, 0],
+        diff_line_erases_total: 0,
+        diff_screen_erases_total: 0,
+        input_parse_errors_total: 0,
+        paste_dropped_total: 0,
     }
 }
 
-fn empty_terminal_caps() -> ffi::zr_terminal_caps_t {
+pub(crate) fn empty_terminal_caps() -> ffi::zr_terminal_caps_t {
     ffi::zr_terminal_caps_t {
         color_mode: 0,
         supports_mouse: 0,
@@ -244,7 +384,22 @@ fn empty_terminal_caps() -> ffi::zr_terminal_caps_t {
     }
 }
 
-fn metrics_to_js(metrics: ffi::zr_metrics_t) -> EngineMetrics {
+/// Zero the fields past the prefix the engine filled. An engine older than
+/// the binding (a prebuilt one from before ABI 1.5) reports a shorter
+/// `struct_size` and never writes the counters appended since, so a reused
+/// buffer would otherwise keep stale bytes there.
+pub(crate) fn clear_unfilled_metrics(metrics: &mut ffi::zr_metrics_t) {
+    let size = std::mem::size_of::<ffi::zr_metrics_t>();
+    let filled = (metrics.struct_size as usize).min(size);
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(metrics as *mut ffi::zr_metrics_t as *mut u8, size)
+    };
+    bytes[filled..].fill(0);
+}
+
+fn metrics_to_js(metrics: ffi::zr_metrics_t, chunking: &WriteChunking) -> EngineMetrics {
+    let last = chunking.last_frame();
+    let (chunks_total, stalls_total) = chunking.totals();
     EngineMetrics {
         structSize: metrics.struct_size,
         negotiatedEngineAbiMajor: metrics.negotiated_engine_abi_major,
@@ -269,6 +424,14 @@ fn metrics_to_js(metrics: ffi::zr_metrics_t) -> EngineMetrics {
         damageRectsLastFrame: metrics.damage_rects_last_frame,
         damageCellsLastFrame: metrics.damage_cells_last_frame,
         damageFullFrame: metrics.damage_full_frame != 0,
+        writeChunksLastFrame: last.chunks,
+        writeStallsLastFrame: last.stalls,
+        writeChunksTotal: bigint_from_u64(chunks_total),
+        writeStallsTotal: bigint_from_u64(stalls_total),
+        diffLineErasesTotal: bigint_from_u64(metrics.diff_line_erases_total),
+        diffScreenErasesTotal: bigint_from_u64(metrics.diff_screen_erases_total),
+        inputParseErrorsTotal: metrics.input_parse_errors_total,
+        pasteDroppedTotal: metrics.paste_dropped_total,
     }
 }
 
@@ -291,26 +454,43 @@ fn terminal_caps_to_js(caps: ffi::zr_terminal_caps_t) -> TerminalCaps {
 }
 
 #[napi(js_name = "engineCreate")]
-pub fn engine_create(_env: Env, config: Option<JsObject>) -> napi::Result<i64> {
+pub fn engine_create(
+    env: Env,
+    config: Option<Either<String, JsObject>>,
+) -> napi::Result<u32, ZrCode> {
     let mut cfg = unsafe { ffi::zr_engine_config_default() };
+    let mut opts = BindingCreateOptions::default();
+    let config = match config {
+        Some(Either::A(json)) => Some(parse_json_config(&env, &json, "engineCreate")?),
+        Some(Either::B(obj)) => Some(obj),
+        None => None,
+    };
     if let Some(obj) = config {
         apply_create_cfg_strict(&mut cfg, &obj)?;
+        opts = parse_binding_create_opts(&obj)?;
     }
+    check_requested_versions(&cfg)
+        .map_err(|msg| zr_error(ffi::ZR_ERR_UNSUPPORTED, format!("engineCreate: {msg}")))?;
+    opts.inline_screen = cfg.plat.screen_mode == ffi::ZR_SCREEN_MODE_INLINE;
+    signals::capture_cooked_termios();
+    hooks::install_engine_hooks();
 
     let mut out_engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
-    let rc = unsafe { ffi::engine_create(&mut out_engine as *mut _, &cfg as *const _) };
-    if rc != ffi::ZR_OK {
-        return Ok(rc as i64);
-    }
+    let io = opts.engine_io();
+    let rc = unsafe { ffi::engine_create_io(&mut out_engine as *mut _, &cfg as *const _, &io) };
+    zr_result_to_napi(rc, "engineCreate")?;
     if out_engine.is_null() {
-        return Ok(ffi::ZR_ERR_PLATFORM as i64);
+        return Err(zr_error(
+            ffi::ZR_ERR_PLATFORM,
+            "engineCreate: engine_create_io returned no engine",
+        ));
     }
 
-    match register_engine(out_engine) {
-        Ok(engine_id) => Ok(engine_id as i64),
-        Err(err) => {
+    match register_engine(out_engine, &cfg, &opts) {
+        Ok(engine_id) => Ok(engine_id),
+        Err(rc) => {
             unsafe { ffi::engine_destroy(out_engine) };
-            Ok(err as i64)
+            Err(zr_error(rc, "engineCreate: could not register the engine"))
         }
     }
 }
@@ -320,63 +500,289 @@ pub fn engine_destroy(engine_id: u32) {
     let Some(slot) = take_engine_for_owner(engine_id) else {
         return;
     };
+    destroy_slot(engine_id, &slot);
+}
 
+/// Tear down an engine already removed from the registry.
+pub(crate) fn destroy_slot(engine_id: u32, slot: &EngineSlot) {
     slot.mark_destroyed();
     slot.wait_for_idle();
+    slot.debug_sub.clear();
+    release_engine(engine_id, slot);
+}
+
+/// `destroy_slot` for the C runtime's exit handlers: the slot must come from
+/// `take_idle_engine_for_owner`, and no N-API handle is released.
+pub(crate) fn destroy_slot_at_exit(engine_id: u32, slot: &EngineSlot) {
+    slot.mark_destroyed();
+    slot.debug_sub.abandon();
+    release_engine(engine_id, slot);
+}
+
+fn release_engine(engine_id: u32, slot: &EngineSlot) {
+    signals::disarm(engine_id);
+    let target = slot.output.target();
+    let mut leave = slot.output.take_restore().to_vec();
+    if target.is_tty() {
+        leave.extend(slot.modes.leave_sequence());
+    }
+    let _ = target.write(&leave);
     unsafe { ffi::engine_destroy(slot.engine) };
 }
 
-#[napi(js_name = "engineSubmitDrawlist")]
-pub fn engine_submit_drawlist(engine_id: u32, drawlist: Uint8Array) -> i32 {
-    let guard = match get_engine_guard(engine_id) {
-        Ok(guard) => guard,
-        Err(rc) => return rc,
+#[napi(object)]
+pub struct TryDestroyResult {
+    pub destroyed: bool,
+}
+
+/// `engineDestroy` that never waits: if another thread is inside a call on
+/// the engine it leaves the engine registered and reports
+/// `destroyed: false`, so shutdown code can retry instead of hanging. Also
+/// `false` for an unknown id or a caller that is not the owner.
+#[napi(js_name = "engineTryDestroy")]
+pub fn engine_try_destroy(engine_id: u32) -> TryDestroyResult {
+    let Some(slot) = take_idle_engine_for_owner(engine_id) else {
+        return TryDestroyResult { destroyed: false };
     };
-    if !guard.slot.is_owner_thread() {
-        return ffi::ZR_ERR_INVALID_ARGUMENT;
-    }
+    destroy_slot(engine_id, &slot);
+    TryDestroyResult { destroyed: true }
+}
 
+/// Make the calling thread the engine's owner, e.g. a worker taking over an
+/// engine created on the main thread. Refused while another thread is inside
+/// a call on the engine; the caller must stop the old owner from calling in
+/// first, since its next call after the handover fails.
+#[napi(js_name = "engineTransferOwnership")]
+pub fn engine_transfer_ownership(engine_id: u32) -> napi::Result<(), ZrCode> {
+    transfer_ownership(engine_id).map_err(|refused| match refused {
+        TransferRefused::Unknown => invalid_argument(format!(
+            "engineTransferOwnership: no live engine with id {engine_id}"
+        )),
+        TransferRefused::Busy => invalid_argument(format!(
+            "engineTransferOwnership: engine {engine_id} is in a call on another thread"
+        )),
+    })
+}
+
+/// Whether `engineId` names an engine that has not been destroyed. Safe from
+/// any thread; never throws.
+#[napi(js_name = "engineIsAlive")]
+pub fn engine_is_alive(engine_id: u32) -> bool {
+    is_engine_registered(engine_id)
+}
+
+/// Ids of every live engine in the process, ascending. Safe from any thread;
+/// meant for diagnostics and leak checks in test teardown.
+#[napi(js_name = "engineListEngines")]
+pub fn engine_list_engines() -> Vec<u32> {
+    registered_engine_ids()
+}
+
+#[napi(js_name = "engineSubmitDrawlist")]
+pub fn engine_submit_drawlist(engine_id: u32, drawlist: Uint8Array) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSubmitDrawlist")?;
+    zr_result_to_napi(
+        submit_slot(&guard.slot, drawlist.as_ref(), "engineSubmitDrawlist"),
+        "engineSubmitDrawlist",
+    )
+}
+
+/// `engineSubmitDrawlist` on the owner thread.
+pub(crate) fn submit_slot(slot: &EngineSlot, drawlist: &[u8], function_name: &'static str) -> i32 {
     if drawlist.len() > (i32::MAX as usize) {
         return ffi::ZR_ERR_LIMIT;
     }
-    let bytes = drawlist.as_ref();
-    unsafe { ffi::engine_submit_drawlist(guard.slot.engine, bytes.as_ptr(), bytes.len() as i32) }
+    let rewritten = apply_attr_fallback(slot, drawlist);
+    let bytes = rewritten.as_deref().unwrap_or(drawlist);
+    slot.idle.note_activity(Instant::now());
+    let _watch = slot.watchdog.watch(slot.engine, function_name, 0);
+    submit_prepared(slot, bytes)
 }
 
-#[napi(js_name = "engineCommitScrollback")]
-pub fn engine_commit_scrollback(engine_id: u32, drawlist: Uint8Array, rows: u32) -> i32 {
-    let guard = match get_engine_guard(engine_id) {
-        Ok(guard) => guard,
-        Err(rc) => return rc,
-    };
-    if !guard.slot.is_owner_thread() {
-        return ffi::ZR_ERR_INVALID_ARGUMENT;
+/// Submit a drawlist already passed through `apply_attr_fallback` and record
+/// it for frame export.
+pub(crate) fn submit_prepared(slot: &EngineSlot, bytes: &[u8]) -> i32 {
+    let asserts = AssertScope::begin();
+    let rc =
+        unsafe { ffi::engine_submit_drawlist(slot.engine, bytes.as_ptr(), bytes.len() as i32) };
+    let rc = asserts.finish(rc);
+    slot.shadow.note_submit(rc, bytes);
+    slot.debug_sub.deliver(slot.engine);
+    rc
+}
+
+/// Rewrite drawlist styles per the engine's `unsupportedAttrFallback` policy.
+///
+/// Returns `None` when the original bytes should be submitted unchanged
+/// (policy `drop`, every attribute supported, or malformed framing that the
+/// engine will reject with a precise error).
+pub(crate) fn apply_attr_fallback(slot: &EngineSlot, bytes: &[u8]) -> Option<Vec<u8>> {
+    let policy = slot.attr_fallback;
+    if policy == AttrFallback::Drop {
+        return None;
+    }
+    let mut caps = empty_terminal_caps();
+    if unsafe { ffi::engine_get_caps(slot.engine, &mut caps as *mut _) } != ffi::ZR_OK {
+        return None;
     }
+    let supported = caps.sgr_attrs_supported;
+    if supported & ffi::ZR_STYLE_ATTR_ALL_MASK == ffi::ZR_STYLE_ATTR_ALL_MASK {
+        return None;
+    }
+    let mut copy = bytes.to_vec();
+    if !rewrite_style_attrs(&mut copy, |attrs| fallback_attrs(attrs, supported, policy)) {
+        return None;
+    }
+    Some(copy)
+}
 
+#[napi(js_name = "engineCommitScrollback")]
+pub fn engine_commit_scrollback(
+    engine_id: u32,
+    drawlist: Uint8Array,
+    rows: u32,
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineCommitScrollback")?;
     if drawlist.len() > (i32::MAX as usize) {
-        return ffi::ZR_ERR_LIMIT;
+        return Err(zr_error(
+            ffi::ZR_ERR_LIMIT,
+            format!(
+                "engineCommitScrollback: drawlist is {} bytes, over the {} byte limit",
+                drawlist.len(),
+                i32::MAX
+            ),
+        ));
     }
-    let bytes = drawlist.as_ref();
-    unsafe {
+    let rewritten = apply_attr_fallback(&guard.slot, drawlist.as_ref());
+    let bytes = rewritten.as_deref().unwrap_or(drawlist.as_ref());
+    guard.slot.idle.note_activity(Instant::now());
+    let _watch = guard
+        .slot
+        .watchdog
+        .watch(guard.slot.engine, "engineCommitScrollback", 0);
+    let asserts = AssertScope::begin();
+    let rc = unsafe {
         ffi::engine_commit_scrollback(guard.slot.engine, bytes.as_ptr(), bytes.len() as i32, rows)
-    }
+    };
+    zr_result_to_napi(asserts.finish(rc), "engineCommitScrollback")
 }
 
 #[napi(js_name = "enginePresent")]
-pub fn engine_present(engine_id: u32) -> i32 {
-    let guard = match get_engine_guard(engine_id) {
-        Ok(guard) => guard,
-        Err(rc) => return rc,
-    };
-    if !guard.slot.is_owner_thread() {
-        return ffi::ZR_ERR_INVALID_ARGUMENT;
+pub fn engine_present(engine_id: u32) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePresent")?;
+    zr_result_to_napi(present_slot(&guard.slot, "enginePresent"), "enginePresent")
+}
+
+/// `enginePresent` on the owner thread: present to the terminal, then flush
+/// queued terminal output.
+pub(crate) fn present_slot(slot: &EngineSlot, function_name: &'static str) -> i32 {
+    if slot.idle.is_idle() {
+        return ffi::ZR_OK;
+    }
+    let _watch = slot.watchdog.watch(slot.engine, function_name, 0);
+    slot.repaint.before_terminal_present();
+    let asserts = AssertScope::begin();
+    let rc = asserts.finish(present_with_hooks(slot));
+    if rc != ffi::ZR_OK {
+        return rc;
+    }
+    let now = Instant::now();
+    slot.frame_times.after_present(now);
+    if slot.idle.enabled() {
+        slot.idle
+            .after_present(last_frame_damaged(slot.engine), now);
+    }
+    if slot.output.raw_queued() {
+        slot.repaint.note_raw_written();
+    }
+    slot.output
+        .target()
+        .write(&slot.output.take_after_present(now))
+}
+
+/// Block until output already presented has reached the terminal, whatever
+/// `waitForOutputDrain` says.
+#[napi(js_name = "engineFlush")]
+pub fn engine_flush(engine_id: u32) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineFlush")?;
+    let _watch = guard
+        .slot
+        .watchdog
+        .watch(guard.slot.engine, "engineFlush", FLUSH_WAIT_MS as u32);
+    let mut caps = empty_terminal_caps();
+    let wait_writable = unsafe { ffi::engine_get_caps(guard.slot.engine, &mut caps as *mut _) }
+        == ffi::ZR_OK
+        && caps.supports_output_wait_writable != 0;
+    zr_result_to_napi(
+        drain_terminal(guard.slot.output.target(), wait_writable),
+        "engineFlush",
+    )
+}
+
+/// Run the present pipeline with the bytes it would write to the terminal
+/// returned instead, for golden-file tests. Caps, limits and every present
+/// hook apply as for `enginePresent`, including idle engines. Successive
+/// calls diff against each other; the terminal never sees these frames, so
+/// the next `enginePresent` repaints every cell.
+#[napi(js_name = "enginePresentToString")]
+pub fn engine_present_to_string(engine_id: u32) -> napi::Result<Uint8Array, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePresentToString")?;
+    let _watch = guard
+        .slot
+        .watchdog
+        .watch(guard.slot.engine, "enginePresentToString", 0);
+    let asserts = AssertScope::begin();
+    let (rc, bytes) = divert_output(|| present_with_hooks(&guard.slot));
+    let rc = asserts.finish(rc);
+    guard.slot.repaint.note_diverted();
+    guard.slot.idle.note_activity(Instant::now());
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "enginePresentToString"));
     }
+    Ok(Uint8Array::new(bytes))
+}
 
-    unsafe { ffi::engine_present(guard.slot.engine) }
+/// `engine_present` with the binding's per-present hooks: frame span capture,
+/// protected regions, the cursor blink override, the debug overlay corner, a
+/// pending repaint and debug subscription delivery.
+fn present_with_hooks(slot: &EngineSlot) -> i32 {
+    let restore = slot.repaint.pending();
+    let raw_write = slot.repaint.pending_after_raw();
+    let rc = slot
+        .full_frames
+        .record(slot.engine, restore, raw_write, || {
+            slot.spans.capture(|| {
+                slot.protected.apply(|| {
+                    slot.cursor_blink.apply(|| {
+                        slot.overlay_corner.apply(|| {
+                            slot.initial_style.apply(|| {
+                                slot.repaint.apply(|| {
+                                    slot.write_chunks.apply(slot.engine, || unsafe {
+                                        ffi::engine_present(slot.engine)
+                                    })
+                                })
+                            })
+                        })
+                    })
+                })
+            })
+        });
+    slot.debug_sub.deliver(slot.engine);
+    rc
 }
 
 #[napi(js_name = "enginePollEvents")]
-pub fn engine_poll_events(engine_id: u32, timeout_ms: i32, mut out: Uint8Array) -> i32 {
+pub fn engine_poll_events(
+    engine_id: u32,
+    timeout_ms: i32,
+    out: Uint8Array,
+) -> napi::Result<i32, ZrCode> {
+    let rc = poll_events(engine_id, timeout_ms, out);
+    zr_result_to_napi(rc, "enginePollEvents")?;
+    Ok(rc)
+}
+
+fn poll_events(engine_id: u32, timeout_ms: i32, mut out: Uint8Array) -> i32 {
     let guard = match get_engine_guard(engine_id) {
         Ok(guard) => guard,
         Err(rc) => return rc,
@@ -391,19 +797,141 @@ pub fn engine_poll_events(engine_id: u32, timeout_ms: i32, mut out: Uint8Array)
         return ffi::ZR_ERR_LIMIT;
     }
 
-    let out_buf = out.as_mut();
-    unsafe {
-        ffi::engine_poll_events(
-            guard.slot.engine,
-            timeout_ms,
-            out_buf.as_mut_ptr(),
-            out_buf.len() as i32,
-        )
+    poll_slot(&guard.slot, timeout_ms, out.as_mut(), "enginePollEvents")
+}
+
+/// Poll into `out` (at most `i32::MAX` bytes) on the owner thread.
+pub(crate) fn poll_slot(
+    slot: &EngineSlot,
+    timeout_ms: i32,
+    out: &mut [u8],
+    function_name: &'static str,
+) -> i32 {
+    let _watch = slot
+        .watchdog
+        .watch(slot.engine, function_name, timeout_ms as u32);
+    let asserts = AssertScope::begin();
+    let rc = poll_engine(slot, timeout_ms, out);
+    finish_poll(slot, asserts.finish(rc), out)
+}
+
+/// One `engine_poll_events` with the poll hooks applied.
+fn poll_engine(slot: &EngineSlot, timeout_ms: i32, out: &mut [u8]) -> i32 {
+    with_unknown_sequences(slot.unknown_sequences, || {
+        slot.posted_size.apply(|| unsafe {
+            ffi::engine_poll_events(slot.engine, timeout_ms, out.as_mut_ptr(), out.len() as i32)
+        })
+    })
+}
+
+/// Bookkeeping shared by every poll entry point.
+fn finish_poll(slot: &EngineSlot, rc: i32, out: &[u8]) -> i32 {
+    slot.drops.note_poll(rc, out);
+    slot.shadow.note_poll(rc, out);
+    slot.modes.note_poll(rc, out);
+    slot.full_frames.note_poll(rc, out);
+    slot.event_seq
+        .note_poll(rc, out, |times| read_event_times_us(slot.engine, times));
+    slot.polled_size.note_poll(rc, out);
+    slot.debug_sub.deliver(slot.engine);
+    let now = Instant::now();
+    if rc > 0 {
+        slot.idle.note_activity(now);
     }
+    let _ = slot.output.target().write(slot.output.take_expired(now));
+    rc
 }
 
-#[napi(js_name = "enginePostUserEvent")]
-pub fn engine_post_user_event(engine_id: u32, tag: u32, payload: Uint8Array) -> i32 {
+/// `enginePollEventsCounted` outcome.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct PollResult {
+    /// Bytes of the batch written to `out` (0 when nothing arrived).
+    pub bytesWritten: u32,
+    /// Records in that batch.
+    pub eventCount: u32,
+    /// Events the engine dropped since the previous `enginePollEventsCounted`.
+    pub dropped: u32,
+}
+
+/// `enginePollEvents` that also reports the batch's record count and the
+/// engine's drops, without a separate metrics call.
+#[napi(js_name = "enginePollEventsCounted")]
+pub fn engine_poll_events_counted(
+    engine_id: u32,
+    timeout_ms: i32,
+    mut out: Uint8Array,
+) -> napi::Result<PollResult, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePollEventsCounted")?;
+    if timeout_ms < 0 {
+        return Err(zr_result_error(
+            ffi::ZR_ERR_INVALID_ARGUMENT,
+            "enginePollEventsCounted",
+        ));
+    }
+    if out.len() > (i32::MAX as usize) {
+        return Err(zr_error(
+            ffi::ZR_ERR_LIMIT,
+            "enginePollEventsCounted: out exceeds 2 GiB",
+        ));
+    }
+
+    let out = out.as_mut();
+    let rc = poll_slot(&guard.slot, timeout_ms, out, "enginePollEventsCounted");
+    zr_result_to_napi(rc, "enginePollEventsCounted")?;
+    let mut metrics = empty_metrics();
+    let mrc = unsafe { ffi::engine_get_metrics(guard.slot.engine, &mut metrics as *mut _) };
+    if mrc != ffi::ZR_OK {
+        return Err(zr_result_error(mrc, "enginePollEventsCounted"));
+    }
+    Ok(PollResult {
+        bytesWritten: rc as u32,
+        eventCount: batch_event_count(rc, out),
+        dropped: guard
+            .slot
+            .drops
+            .take_engine_drops(metrics.events_dropped_total),
+    })
+}
+
+/// Code `enginePollEventsCancelable` throws when the cancel token was tripped
+/// before any event arrived. Binding-owned; never produced by the engine.
+#[napi]
+pub const POLL_CANCELED: i32 = -100;
+
+fn poll_cancel_cell(token: &Int32Array) -> &AtomicI32 {
+    // SAFETY: `token` is a live, non-empty Int32Array (checked by the caller),
+    // so its first element is a valid, 4-byte aligned i32 for the whole call.
+    // Other threads only write it through `Atomics`, hence the atomic access.
+    unsafe { &*(token.as_ptr() as *const AtomicI32) }
+}
+
+fn poll_cancel_requested(token: &Int32Array) -> bool {
+    poll_cancel_cell(token).load(Ordering::Acquire) != 0
+}
+
+/// Like `enginePollEvents`, but throws `POLL_CANCELED` early once
+/// `cancelToken[0]` becomes non-zero. The token is an `Int32Array` (usually
+/// over a `SharedArrayBuffer`) tripped with `enginePollCancel`, which also
+/// wakes the poll; it is never reset by the binding.
+#[napi(js_name = "enginePollEventsCancelable")]
+pub fn engine_poll_events_cancelable(
+    engine_id: u32,
+    timeout_ms: i32,
+    out: Uint8Array,
+    cancel_token: Int32Array,
+) -> napi::Result<i32, ZrCode> {
+    let rc = poll_events_cancelable(engine_id, timeout_ms, out, cancel_token);
+    zr_result_to_napi(rc, "enginePollEventsCancelable")?;
+    Ok(rc)
+}
+
+fn poll_events_cancelable(
+    engine_id: u32,
+    timeout_ms: i32,
+    mut out: Uint8Array,
+    cancel_token: Int32Array,
+) -> i32 {
     let guard = match get_engine_guard(engine_id) {
         Ok(guard) => guard,
         Err(rc) => return rc,
@@ -411,74 +939,602 @@ pub fn engine_post_user_event(engine_id: u32, tag: u32, payload: Uint8Array) ->
     if !guard.slot.is_owner_thread() {
         return ffi::ZR_ERR_INVALID_ARGUMENT;
     }
+    if timeout_ms < 0 || cancel_token.is_empty() {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    if out.len() > (i32::MAX as usize) {
+        return ffi::ZR_ERR_LIMIT;
+    }
 
-    if payload.len() > (i32::MAX as usize) {
+    let out_buf = out.as_mut();
+    let _watch = guard.slot.watchdog.watch(
+        guard.slot.engine,
+        "enginePollEventsCancelable",
+        timeout_ms as u32,
+    );
+    let deadline = Instant::now() + std::time::Duration::from_millis(timeout_ms as u64);
+    let asserts = AssertScope::begin();
+    /* A wake from `enginePollCancel` ends the wait with no events; any
+     * other early return of 0 just waits out the rest of the timeout. */
+    let rc = loop {
+        if poll_cancel_requested(&cancel_token) {
+            break POLL_CANCELED;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let wait_ms = remaining.as_micros().div_ceil(1000) as i32;
+        let rc = poll_engine(&guard.slot, wait_ms, out_buf);
+        if rc != 0 || remaining.is_zero() {
+            break rc;
+        }
+    };
+    finish_poll(&guard.slot, asserts.finish(rc), out_buf)
+}
+
+/// Trip `cancelToken` and wake the engine's blocked poll so
+/// `enginePollEventsCancelable` returns at once. Callable from any thread.
+#[napi(js_name = "enginePollCancel")]
+pub fn engine_poll_cancel(engine_id: u32, cancel_token: Int32Array) -> napi::Result<(), ZrCode> {
+    let guard =
+        get_engine_guard(engine_id).map_err(|rc| zr_result_error(rc, "enginePollCancel"))?;
+    if cancel_token.is_empty() {
+        return Err(invalid_argument("enginePollCancel: cancelToken is empty"));
+    }
+    poll_cancel_cell(&cancel_token).store(1, Ordering::Release);
+    let rc = unsafe { ffi::engine_wake(guard.slot.engine) };
+    zr_result_to_napi(rc, "enginePollCancel")
+}
+
+/// Whether the frame just presented changed anything on screen. Errors count
+/// as damage so a metrics failure never pauses presenting.
+fn last_frame_damaged(engine: *mut ffi::zr_engine_t) -> bool {
+    let mut metrics = empty_metrics();
+    let rc = unsafe { ffi::engine_get_metrics(engine, &mut metrics as *mut _) };
+    rc != ffi::ZR_OK || metrics.dirty_lines_last_frame != 0 || metrics.damage_rects_last_frame != 0
+}
+
+/// Set the terminal window title (OSC 2) on the next present. Control
+/// characters are stripped.
+#[napi(js_name = "engineSetTitle")]
+pub fn engine_set_title(engine_id: u32, title: String) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSetTitle")?;
+    guard.slot.idle.note_activity(Instant::now());
+    guard.slot.output.set_title(&title);
+    Ok(())
+}
+
+/// Queue raw bytes to be written right after the next present's diff output.
+/// The bytes are wrapped in DECSC/DECRC so the cursor position and SGR state
+/// the differ tracks are restored afterwards, and the present after them
+/// repaints every cell.
+#[napi(js_name = "engineWriteRaw")]
+pub fn engine_write_raw(engine_id: u32, bytes: Uint8Array) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineWriteRaw")?;
+    zr_result_to_napi(guard.slot.output.write_raw(&bytes), "engineWriteRaw")?;
+    if !bytes.is_empty() {
+        guard.slot.idle.note_activity(Instant::now());
+    }
+    Ok(())
+}
+
+/// Save (`"push"`) or restore (`"pop"`) the title via the xterm title stack on
+/// the next present.
+#[napi(js_name = "engineSetTitleStack")]
+pub fn engine_set_title_stack(engine_id: u32, op: String) -> napi::Result<(), ZrCode> {
+    let push = match op.as_str() {
+        "push" => true,
+        "pop" => false,
+        _ => {
+            return Err(invalid_argument(format!(
+                "engineSetTitleStack: op must be \"push\" or \"pop\", got {op:?}"
+            )))
+        }
+    };
+    let guard = owner_engine_guard(engine_id, "engineSetTitleStack")?;
+    guard.slot.output.title_stack(push);
+    guard.slot.idle.note_activity(Instant::now());
+    Ok(())
+}
+
+/// Ring the terminal bell. `{ visual: true }` flashes the screen in reverse
+/// video on the next present instead of emitting BEL.
+#[napi(js_name = "engineBell")]
+pub fn engine_bell(engine_id: u32, opts: Option<JsObject>) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineBell")?;
+    let visual = match opts {
+        Some(obj) => {
+            validate_known_keys(&obj, &[("visual", "visual")], "engineBell")?;
+            js_u8_bool(&obj, "visual", "visual")
+                .map_err(|()| invalid_argument("engineBell: opts.visual must be a boolean"))?
+                == Some(1)
+        }
+        None => false,
+    };
+    guard.slot.idle.note_activity(Instant::now());
+    zr_result_to_napi(guard.slot.output.bell(visual), "engineBell")
+}
+
+#[napi(js_name = "enginePostUserEvent")]
+pub fn engine_post_user_event(
+    engine_id: u32,
+    tag: u32,
+    payload: Uint8Array,
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePostUserEvent")?;
+    zr_result_to_napi(
+        post_to_slot(&guard.slot, tag, payload.as_ref()),
+        "enginePostUserEvent",
+    )
+}
+
+fn post_to_slot(slot: &EngineSlot, tag: u32, bytes: &[u8]) -> i32 {
+    if tag == UNKNOWN_SEQUENCE_TAG {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    if bytes.len() > (i32::MAX as usize) {
         return ffi::ZR_ERR_LIMIT;
     }
-    let bytes = payload.as_ref();
     let (ptr, len) = if bytes.is_empty() {
         (std::ptr::null(), 0)
     } else {
         (bytes.as_ptr(), bytes.len() as i32)
     };
 
-    unsafe { ffi::engine_post_user_event(guard.slot.engine, tag, ptr, len) }
+    let asserts = AssertScope::begin();
+    let rc = asserts.finish(unsafe { ffi::engine_post_user_event(slot.engine, tag, ptr, len) });
+    slot.drops.note_user_post(rc, bytes.len());
+    rc
+}
+
+/// One event for `enginePostUserEvents`.
+#[napi(object)]
+pub struct UserEvent {
+    pub tag: u32,
+    pub payload: Uint8Array,
+}
+
+/// `enginePostUserEvent` for each of `events`, in order, under a single
+/// engine lookup. Stops at the first event the engine refuses and throws
+/// with its index; the events before it stay posted.
+#[napi(js_name = "enginePostUserEvents")]
+pub fn engine_post_user_events(engine_id: u32, events: Vec<UserEvent>) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePostUserEvents")?;
+    post_batch_to_slot(
+        &guard.slot,
+        events
+            .iter()
+            .map(|event| (event.tag, event.payload.as_ref())),
+    )
+}
+
+/// `enginePostUserEvents` for a slot the caller already holds.
+pub(crate) fn post_batch_to_slot<'a>(
+    slot: &EngineSlot,
+    events: impl IntoIterator<Item = (u32, &'a [u8])>,
+) -> napi::Result<(), ZrCode> {
+    for (i, (tag, payload)) in events.into_iter().enumerate() {
+        let rc = post_to_slot(slot, tag, payload);
+        if rc != ffi::ZR_OK {
+            zr_result_to_napi(rc, &format!("enginePostUserEvents: event {i}"))?;
+        }
+    }
+    Ok(())
 }
 
 #[napi(js_name = "engineSetConfig")]
-pub fn engine_set_config(_env: Env, engine_id: u32, cfg: Option<JsObject>) -> napi::Result<i32> {
-    let guard = match get_engine_guard(engine_id) {
-        Ok(guard) => guard,
-        Err(rc) => return Ok(rc),
-    };
-    if !guard.slot.is_owner_thread() {
-        return Ok(ffi::ZR_ERR_INVALID_ARGUMENT);
+pub fn engine_set_config(engine_id: u32, cfg: JsObject) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSetConfig")?;
+
+    // Untyped callers can still pass `null`; report it under the addon's code.
+    let cfg = cfg.into_unknown();
+    if cfg.get_type().map_err(napi_error)? != ValueType::Object {
+        return Err(invalid_argument(
+            "engineSetConfig: config object is required",
+        ));
     }
+    let cfg: JsObject = unsafe { cfg.cast() };
 
-    let mut runtime_cfg = create_default_runtime_cfg();
-    if let Some(obj) = cfg {
-        apply_runtime_cfg_strict(&mut runtime_cfg, &obj)?;
-    } else {
-        return Ok(ffi::ZR_ERR_INVALID_ARGUMENT);
+    // Omitted keys keep their current values rather than the engine defaults.
+    let mut runtime_cfg = guard.slot.runtime_cfg();
+    apply_runtime_cfg_strict(&mut runtime_cfg, &cfg)?;
+
+    guard.slot.idle.note_activity(Instant::now());
+    let _watch = guard
+        .slot
+        .watchdog
+        .watch(guard.slot.engine, "engineSetConfig", 0);
+    let asserts = AssertScope::begin();
+    let rc = unsafe { ffi::engine_set_config(guard.slot.engine, &runtime_cfg as *const _) };
+    zr_result_to_napi(asserts.finish(rc), "engineSetConfig")?;
+    guard.slot.set_runtime_cfg(runtime_cfg);
+    Ok(())
+}
+
+/// The runtime config the engine last accepted, from `engineCreate` or the
+/// latest successful `engineSetConfig`.
+#[napi(js_name = "engineGetConfig")]
+pub fn engine_get_config(engine_id: u32) -> napi::Result<EngineRuntimeConfig, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetConfig")?;
+    Ok(runtime_cfg_to_js(&guard.slot.runtime_cfg()))
+}
+
+/// A ready-to-pass `engineCreate` config: the engine defaults with the
+/// overrides of the named preset.
+#[napi(js_name = "engineConfigPreset")]
+pub fn engine_config_preset(name: String) -> napi::Result<EngineCreateConfig, ZrCode> {
+    let cfg = config_preset(&name).ok_or_else(|| {
+        invalid_argument(format!(
+            "engineConfigPreset: unknown preset {name:?}; expected one of {}",
+            CONFIG_PRESET_NAMES.join(", ")
+        ))
+    })?;
+    Ok(create_cfg_to_js(&cfg))
+}
+
+/// The engine's default `limits`, as `engineCreate` applies them when the
+/// config leaves them out.
+#[napi(js_name = "ziLimitsDefault")]
+pub fn zi_limits_default() -> EngineLimits {
+    limits_to_js(&unsafe { ffi::zr_engine_config_default() }.limits)
+}
+
+/// The engine's default create config (`engineConfigPreset` without
+/// overrides).
+#[napi(js_name = "ziConfigDefault")]
+pub fn zi_config_default() -> EngineCreateConfig {
+    create_cfg_to_js(&unsafe { ffi::zr_engine_config_default() })
+}
+
+/// Which engine the addon was built against: the vendored commit, the local
+/// patches applied on top of it and the ABI version `engineCreate` requests
+/// by default.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EngineVersion {
+    pub commit: String,
+    pub patches: Vec<String>,
+    pub abiMajor: u32,
+    pub abiMinor: u32,
+    pub abiPatch: u32,
+}
+
+#[napi(js_name = "ziEngineVersion")]
+pub fn zi_engine_version() -> EngineVersion {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    EngineVersion {
+        commit: env!("ZIREAEL_VENDOR_COMMIT").to_owned(),
+        patches: env!("ZIREAEL_VENDOR_PATCHES")
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+            .collect(),
+        abiMajor: cfg.requested_engine_abi_major,
+        abiMinor: cfg.requested_engine_abi_minor,
+        abiPatch: cfg.requested_engine_abi_patch,
     }
+}
+
+/// Highest drawlist and event batch version `ziSupported*Versions` probe.
+const VERSION_PROBE_MAX: u32 = 64;
+
+/// Writes a version into the create config field it is requested by.
+type SetVersion = fn(&mut ffi::zr_engine_config_t, u32);
 
-    Ok(unsafe { ffi::engine_set_config(guard.slot.engine, &runtime_cfg as *const _) })
+/// Versions in `1..=VERSION_PROBE_MAX` the engine's config validation
+/// accepts once `set` puts them into an otherwise default create config.
+fn probe_versions(set: SetVersion) -> Vec<u32> {
+    (1..=VERSION_PROBE_MAX)
+        .filter(|&v| {
+            let mut cfg = unsafe { ffi::zr_engine_config_default() };
+            set(&mut cfg, v);
+            let rc = unsafe { ffi::zr_engine_config_validate(&cfg) };
+            rc == ffi::ZR_OK
+        })
+        .collect()
 }
 
-#[napi(js_name = "engineGetMetrics")]
-pub fn engine_get_metrics(engine_id: u32) -> napi::Result<EngineMetrics> {
-    let guard = get_engine_guard(engine_id).map_err(|_| invalid_arg_error())?;
-    if !guard.slot.is_owner_thread() {
-        return Err(invalid_arg_error());
+fn set_drawlist_version(cfg: &mut ffi::zr_engine_config_t, v: u32) {
+    cfg.requested_drawlist_version = v;
+}
+
+fn set_event_batch_version(cfg: &mut ffi::zr_engine_config_t, v: u32) {
+    cfg.requested_event_batch_version = v;
+}
+
+// The linked engine cannot change under a running process, so each set is
+// probed once and shared by every `engineCreate`.
+static SUPPORTED_DRAWLIST_VERSIONS: OnceLock<Vec<u32>> = OnceLock::new();
+static SUPPORTED_EVENT_BATCH_VERSIONS: OnceLock<Vec<u32>> = OnceLock::new();
+
+fn supported_drawlist_versions() -> &'static [u32] {
+    SUPPORTED_DRAWLIST_VERSIONS.get_or_init(|| probe_versions(set_drawlist_version))
+}
+
+fn supported_event_batch_versions() -> &'static [u32] {
+    SUPPORTED_EVENT_BATCH_VERSIONS.get_or_init(|| probe_versions(set_event_batch_version))
+}
+
+/// Reject a create config pinning a drawlist or event batch version the
+/// linked engine does not support, naming the ones it does.
+pub(crate) fn check_requested_versions(cfg: &ffi::zr_engine_config_t) -> Result<(), String> {
+    let pins: [(&str, u32, &[u32]); 2] = [
+        (
+            "requestedDrawlistVersion",
+            cfg.requested_drawlist_version,
+            supported_drawlist_versions(),
+        ),
+        (
+            "requestedEventBatchVersion",
+            cfg.requested_event_batch_version,
+            supported_event_batch_versions(),
+        ),
+    ];
+    for (key, requested, supported) in pins {
+        if !supported.contains(&requested) {
+            let list: Vec<String> = supported.iter().map(u32::to_string).collect();
+            return Err(format!(
+                "{key} {requested} is not supported by the linked engine (supported: {})",
+                list.join(", ")
+            ));
+        }
     }
+    Ok(())
+}
+
+/// Drawlist versions the linked engine accepts in `requestedDrawlistVersion`,
+/// ascending; pick the highest one the client also speaks.
+#[napi(js_name = "ziSupportedDrawlistVersions")]
+pub fn zi_supported_drawlist_versions() -> Vec<u32> {
+    supported_drawlist_versions().to_vec()
+}
+
+/// Event batch versions the linked engine accepts in
+/// `requestedEventBatchVersion`, ascending.
+#[napi(js_name = "ziSupportedEventBatchVersions")]
+pub fn zi_supported_event_batch_versions() -> Vec<u32> {
+    supported_event_batch_versions().to_vec()
+}
+
+#[napi(js_name = "engineGetMetrics")]
+pub fn engine_get_metrics(engine_id: u32) -> napi::Result<EngineMetrics, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetMetrics")?;
 
     let mut metrics = empty_metrics();
     let rc = unsafe { ffi::engine_get_metrics(guard.slot.engine, &mut metrics as *mut _) };
     if rc != ffi::ZR_OK {
-        return Err(Error::new(
-            Status::GenericFailure,
-            format!("engine_get_metrics failed: {rc}"),
-        ));
+        return Err(zr_result_error(rc, "engineGetMetrics"));
     }
+    clear_unfilled_metrics(&mut metrics);
+    guard.slot.metrics_baseline.apply(&mut metrics);
 
-    Ok(metrics_to_js(metrics))
+    Ok(metrics_to_js(metrics, &guard.slot.write_chunks))
 }
 
-#[napi(js_name = "engineGetCaps")]
-pub fn engine_get_caps(engine_id: u32) -> napi::Result<TerminalCaps> {
-    let guard = get_engine_guard(engine_id).map_err(|_| invalid_arg_error())?;
-    if !guard.slot.is_owner_thread() {
-        return Err(invalid_arg_error());
+/// Versions the engine settled on at create, which may be lower than the
+/// `requested*` ones.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct EngineNegotiation {
+    pub abiMajor: u32,
+    pub abiMinor: u32,
+    pub abiPatch: u32,
+    pub drawlistVersion: u32,
+    pub eventBatchVersion: u32,
+}
+
+pub(crate) fn negotiation_from_metrics(metrics: &ffi::zr_metrics_t) -> EngineNegotiation {
+    EngineNegotiation {
+        abiMajor: metrics.negotiated_engine_abi_major,
+        abiMinor: metrics.negotiated_engine_abi_minor,
+        abiPatch: metrics.negotiated_engine_abi_patch,
+        drawlistVersion: metrics.negotiated_drawlist_version,
+        eventBatchVersion: metrics.negotiated_event_batch_version,
+    }
+}
+
+/// The negotiated ABI and format versions, for compatibility checks right
+/// after `engineCreate`.
+#[napi(js_name = "engineGetNegotiation")]
+pub fn engine_get_negotiation(engine_id: u32) -> napi::Result<EngineNegotiation, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetNegotiation")?;
+
+    let mut metrics = empty_metrics();
+    let rc = unsafe { ffi::engine_get_metrics(guard.slot.engine, &mut metrics as *mut _) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineGetNegotiation"));
+    }
+    Ok(negotiation_from_metrics(&metrics))
+}
+
+/// Size in bytes of the raw `zr_metrics_t` that `engineGetMetricsInto` writes.
+#[napi]
+pub const METRICS_STRUCT_SIZE: u32 = std::mem::size_of::<ffi::zr_metrics_t>() as u32;
+
+/// Write the engine's raw `zr_metrics_t` (little-endian, layout in the
+/// native backend docs) to the start of `out` and return the bytes the
+/// engine filled, without allocating any JS object. An engine from before
+/// ABI 1.5 fills 120 bytes; the rest of the struct is zeroed. `out` must
+/// hold `METRICS_STRUCT_SIZE` bytes and be 8-byte aligned. The binding-side
+/// write chunk counters of `engineGetMetrics` are not part of the struct.
+#[napi(js_name = "engineGetMetricsInto")]
+pub fn engine_get_metrics_into(engine_id: u32, mut out: Uint8Array) -> napi::Result<u32, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetMetricsInto")?;
+
+    let out = out.as_mut();
+    if out.len() < METRICS_STRUCT_SIZE as usize {
+        return Err(zr_error(
+            ffi::ZR_ERR_LIMIT,
+            format!(
+                "engineGetMetricsInto: out holds {} bytes, need {METRICS_STRUCT_SIZE}",
+                out.len()
+            ),
+        ));
+    }
+    if !(out.as_ptr() as usize).is_multiple_of(std::mem::align_of::<ffi::zr_metrics_t>()) {
+        return Err(invalid_argument(
+            "engineGetMetricsInto: out must be 8-byte aligned",
+        ));
     }
+    let metrics = out.as_mut_ptr() as *mut ffi::zr_metrics_t;
+    let rc = unsafe {
+        (*metrics).struct_size = METRICS_STRUCT_SIZE;
+        ffi::engine_get_metrics(guard.slot.engine, metrics)
+    };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineGetMetricsInto"));
+    }
+    let metrics = unsafe { &mut *metrics };
+    clear_unfilled_metrics(metrics);
+    guard.slot.metrics_baseline.apply(metrics);
+    Ok(metrics.struct_size.min(METRICS_STRUCT_SIZE))
+}
+
+fn read_engine_caps(engine_id: u32, ctx: &str) -> napi::Result<ffi::zr_terminal_caps_t, ZrCode> {
+    let guard = owner_engine_guard(engine_id, ctx)?;
 
     let mut caps = empty_terminal_caps();
     let rc = unsafe { ffi::engine_get_caps(guard.slot.engine, &mut caps as *mut _) };
     if rc != ffi::ZR_OK {
-        return Err(Error::new(
-            Status::GenericFailure,
-            format!("engine_get_caps failed: {rc}"),
-        ));
+        return Err(zr_result_error(rc, ctx));
     }
+    Ok(caps)
+}
+
+#[napi(js_name = "engineGetCaps")]
+pub fn engine_get_caps(engine_id: u32) -> napi::Result<TerminalCaps, ZrCode> {
+    Ok(terminal_caps_to_js(read_engine_caps(
+        engine_id,
+        "engineGetCaps",
+    )?))
+}
+
+/// Where the engine is rendering, for bug reports and diagnostics.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct PlatformInfo {
+    /// Platform backend compiled in: `"posix"` or `"win32"`.
+    pub backend: String,
+    /// Terminal identified by the engine's detection (`"unknown"` if none).
+    pub terminal: String,
+    /// `$TERM` as seen by the platform layer; absent for an engine bound to
+    /// `inputFd`/`outputFd`, whose terminal the environment does not describe.
+    pub term: Option<String>,
+    /// `$TERM_PROGRAM` as seen by the platform layer; absent when bound.
+    pub termProgram: Option<String>,
+    /// XTVERSION reply when the terminal answered, else `$TERM_PROGRAM_VERSION`
+    /// (never the environment when bound).
+    pub termVersion: Option<String>,
+    /// `false` when rendering to redirected stdio (pipe mode) or to a bound
+    /// `outputFd` that is not a terminal.
+    pub isTty: bool,
+}
+
+/* Indexed by zr_terminal_id_t. */
+const TERMINAL_NAMES: &[&str] = &[
+    "unknown",
+    "kitty",
+    "ghostty",
+    "wezterm",
+    "foot",
+    "iterm2",
+    "vte",
+    "konsole",
+    "contour",
+    "windows-terminal",
+    "alacritty",
+    "xterm",
+    "mintty",
+    "tmux",
+    "screen",
+];
+
+pub(crate) fn terminal_name(id: u32) -> &'static str {
+    TERMINAL_NAMES
+        .get(id as usize)
+        .copied()
+        .unwrap_or("unknown")
+}
+
+fn env_nonempty(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Report the platform backend and the terminal the engine detected.
+#[napi(js_name = "enginePlatformInfo")]
+pub fn engine_platform_info(engine_id: u32) -> napi::Result<PlatformInfo, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePlatformInfo")?;
+
+    let profile = unsafe { ffi::engine_get_terminal_profile(guard.slot.engine) };
+    let (terminal, xtversion) = match unsafe { profile.as_ref() } {
+        Some(profile) => {
+            let raw = &profile.version_string;
+            let len = raw.iter().position(|&b| b == 0).unwrap_or(raw.len());
+            let version = String::from_utf8_lossy(&raw[..len]).trim().to_string();
+            (
+                terminal_name(profile.id),
+                Some(version).filter(|v| !v.is_empty()),
+            )
+        }
+        None => (terminal_name(0), None),
+    };
+
+    let target = guard.slot.output.target();
+    let env = |name: &str| {
+        if target.is_bound() {
+            None
+        } else {
+            env_nonempty(name)
+        }
+    };
+    Ok(PlatformInfo {
+        backend: if cfg!(windows) { "win32" } else { "posix" }.to_string(),
+        terminal: terminal.to_string(),
+        term: env("TERM"),
+        termProgram: env("TERM_PROGRAM"),
+        termVersion: xtversion.or_else(|| env("TERM_PROGRAM_VERSION")),
+        isTty: target.is_tty(),
+    })
+}
+
+pub(crate) const SUPPORTS_FEATURES: &[&str] = &[
+    "osc52",
+    "hyperlinks",
+    "syncUpdate",
+    "scrollRegion",
+    "cursorShape",
+    "mouse",
+    "truecolor",
+    "bracketedPaste",
+    "focusEvents",
+    "underlineStyles",
+    "coloredUnderlines",
+];
+
+/// Map a stable feature name onto detected caps; `None` for unknown names.
+pub(crate) fn caps_supports(caps: &ffi::zr_terminal_caps_t, feature: &str) -> Option<bool> {
+    let flag = match feature {
+        "osc52" => caps.supports_osc52,
+        "hyperlinks" => caps.supports_hyperlinks,
+        "syncUpdate" => caps.supports_sync_update,
+        "scrollRegion" => caps.supports_scroll_region,
+        "cursorShape" => caps.supports_cursor_shape,
+        "mouse" => caps.supports_mouse,
+        "truecolor" => u8::from(caps.color_mode == ffi::PLAT_COLOR_MODE_RGB),
+        "bracketedPaste" => caps.supports_bracketed_paste,
+        "focusEvents" => caps.supports_focus_events,
+        "underlineStyles" => caps.supports_underline_styles,
+        "coloredUnderlines" => caps.supports_colored_underlines,
+        _ => return None,
+    };
+    Some(flag != 0)
+}
 
-    Ok(terminal_caps_to_js(caps))
+#[napi(js_name = "engineSupports")]
+pub fn engine_supports(engine_id: u32, feature: String) -> napi::Result<bool, ZrCode> {
+    let caps = read_engine_caps(engine_id, "engineSupports")?;
+    caps_supports(&caps, &feature).ok_or_else(|| {
+        invalid_argument(format!(
+            "engineSupports: unknown feature \"{feature}\" (expected one of: {})",
+            SUPPORTS_FEATURES.join(", ")
+        ))
+    })
 }
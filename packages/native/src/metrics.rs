@@ -0,0 +1,197 @@
+use crate::error::{zr_result_error, ZrCode};
+use crate::{empty_metrics, ffi, owner_engine_guard};
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Frames `engineGetFrameStats` summarizes when `frameStatsWindow` is unset.
+pub(crate) const FRAME_STATS_WINDOW_DEFAULT: u32 = 120;
+/// Largest accepted `frameStatsWindow`.
+pub(crate) const FRAME_STATS_WINDOW_MAX: u32 = 4096;
+
+/// Engine totals as of the last `engineResetMetrics`. The engine cannot
+/// reset its own counters, so every reader subtracts these.
+#[derive(Default)]
+pub(crate) struct MetricsBaseline {
+    bytes_emitted_total: AtomicU64,
+    events_dropped_total: AtomicU32,
+    diff_line_erases_total: AtomicU64,
+    diff_screen_erases_total: AtomicU64,
+    input_parse_errors_total: AtomicU32,
+    paste_dropped_total: AtomicU32,
+}
+
+impl MetricsBaseline {
+    /// Count the totals in `m` as already seen.
+    pub(crate) fn reset_to(&self, m: &ffi::zr_metrics_t) {
+        self.bytes_emitted_total
+            .store(m.bytes_emitted_total, Ordering::Relaxed);
+        self.events_dropped_total
+            .store(m.events_dropped_total, Ordering::Relaxed);
+        self.diff_line_erases_total
+            .store(m.diff_line_erases_total, Ordering::Relaxed);
+        self.diff_screen_erases_total
+            .store(m.diff_screen_erases_total, Ordering::Relaxed);
+        self.input_parse_errors_total
+            .store(m.input_parse_errors_total, Ordering::Relaxed);
+        self.paste_dropped_total
+            .store(m.paste_dropped_total, Ordering::Relaxed);
+    }
+
+    /// Rebase the totals in `m` on the last reset.
+    pub(crate) fn apply(&self, m: &mut ffi::zr_metrics_t) {
+        m.bytes_emitted_total = m
+            .bytes_emitted_total
+            .saturating_sub(self.bytes_emitted_total.load(Ordering::Relaxed));
+        m.events_dropped_total = m
+            .events_dropped_total
+            .saturating_sub(self.events_dropped_total.load(Ordering::Relaxed));
+        m.diff_line_erases_total = m
+            .diff_line_erases_total
+            .saturating_sub(self.diff_line_erases_total.load(Ordering::Relaxed));
+        m.diff_screen_erases_total = m
+            .diff_screen_erases_total
+            .saturating_sub(self.diff_screen_erases_total.load(Ordering::Relaxed));
+        m.input_parse_errors_total = m
+            .input_parse_errors_total
+            .saturating_sub(self.input_parse_errors_total.load(Ordering::Relaxed));
+        m.paste_dropped_total = m
+            .paste_dropped_total
+            .saturating_sub(self.paste_dropped_total.load(Ordering::Relaxed));
+    }
+}
+
+/// Start a fresh measurement window: `bytesEmittedTotal`,
+/// `eventsDroppedTotal`, the `diff*ErasesTotal` counts,
+/// `inputParseErrorsTotal`, `pasteDroppedTotal`, `writeChunksTotal`,
+/// `writeStallsTotal` and the `engineDroppedEventReasons` counts restart
+/// from zero. Per-frame fields,
+/// `frameIndex` and the arena high-water marks are unaffected.
+#[napi(js_name = "engineResetMetrics")]
+pub fn engine_reset_metrics(engine_id: u32) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineResetMetrics")?;
+
+    let mut m = empty_metrics();
+    let rc = unsafe { ffi::engine_get_metrics(guard.slot.engine, &mut m as *mut _) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineResetMetrics"));
+    }
+    guard.slot.metrics_baseline.reset_to(&m);
+    guard.slot.write_chunks.reset_totals();
+    guard.slot.drops.reset();
+    Ok(())
+}
+
+#[derive(Default)]
+struct FrameTimesInner {
+    last_present: Option<Instant>,
+    /// Microseconds between consecutive presents, oldest first.
+    times_us: VecDeque<u64>,
+}
+
+/// The last `window` frame times of an engine: the intervals between
+/// successive terminal presents. Skipped idle presents are not frames.
+pub(crate) struct FrameTimes {
+    window: usize,
+    inner: Mutex<FrameTimesInner>,
+}
+
+impl FrameTimes {
+    /// `window` 0 selects `FRAME_STATS_WINDOW_DEFAULT`.
+    pub(crate) fn new(window: u32) -> Self {
+        let window = if window == 0 {
+            FRAME_STATS_WINDOW_DEFAULT
+        } else {
+            window
+        };
+        Self {
+            window: window as usize,
+            inner: Mutex::new(FrameTimesInner::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FrameTimesInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    pub(crate) fn after_present(&self, now: Instant) {
+        let mut inner = self.lock();
+        if let Some(last) = inner.last_present.replace(now) {
+            let us = now.saturating_duration_since(last).as_micros();
+            if inner.times_us.len() == self.window {
+                inner.times_us.pop_front();
+            }
+            inner.times_us.push_back(us.min(u64::MAX as u128) as u64);
+        }
+    }
+
+    pub(crate) fn stats(&self) -> FrameStats {
+        let mut samples: Vec<u64> = self.lock().times_us.iter().copied().collect();
+        frame_stats(&mut samples)
+    }
+}
+
+/// Rolling frame timing over the last `frameStatsWindow` presents.
+#[napi(object)]
+pub struct FrameStats {
+    /// Frame times summarized (0 until two frames have been presented).
+    pub frames: u32,
+    /// Frames per second over the window.
+    pub fps: f64,
+    /// Median frame time in milliseconds.
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+/// Summarize frame times in microseconds the way the benchmark harness does:
+/// the median averages the middle pair, p95/p99 take the nearest rank.
+pub(crate) fn frame_stats(samples_us: &mut [u64]) -> FrameStats {
+    let n = samples_us.len();
+    if n == 0 {
+        return FrameStats {
+            frames: 0,
+            fps: 0.0,
+            p50: 0.0,
+            p95: 0.0,
+            p99: 0.0,
+            max: 0.0,
+        };
+    }
+    samples_us.sort_unstable();
+    let ms = |us: u64| us as f64 / 1000.0;
+    let rank = |q: f64| samples_us[((n as f64 * q).ceil() as usize).clamp(1, n) - 1];
+    let p50 = if n.is_multiple_of(2) {
+        (ms(samples_us[n / 2 - 1]) + ms(samples_us[n / 2])) / 2.0
+    } else {
+        ms(samples_us[n / 2])
+    };
+    let total_us: u64 = samples_us.iter().sum();
+    FrameStats {
+        frames: n as u32,
+        fps: if total_us == 0 {
+            0.0
+        } else {
+            n as f64 * 1_000_000.0 / total_us as f64
+        },
+        p50,
+        p95: ms(rank(0.95)),
+        p99: ms(rank(0.99)),
+        max: ms(samples_us[n - 1]),
+    }
+}
+
+/// FPS and frame-time percentiles (milliseconds) over the engine's last
+/// `frameStatsWindow` frames, where a frame time is the interval between two
+/// successive `enginePresent` calls that reached the terminal.
+#[napi(js_name = "engineGetFrameStats")]
+pub fn engine_get_frame_stats(engine_id: u32) -> napi::Result<FrameStats, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetFrameStats")?;
+    Ok(guard.slot.frame_times.stats())
+}
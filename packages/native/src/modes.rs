@@ -0,0 +1,243 @@
+use crate::error::{zr_result_to_napi, ZrCode};
+use crate::events::decode_batch;
+use crate::registry::get_engine_guard;
+use crate::{empty_terminal_caps, ffi};
+use napi::bindgen_prelude::{Either, Undefined};
+use napi_derive::napi;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+
+/* Same sequences and order as zr_posix_emit_enter_sequences /
+ * zr_posix_emit_leave_sequences. */
+pub(crate) const MOUSE_ON: &[u8] = b"\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1006h";
+pub(crate) const MOUSE_OFF: &[u8] = b"\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l";
+pub(crate) const PASTE_ON: &[u8] = b"\x1b[?2004h";
+pub(crate) const PASTE_OFF: &[u8] = b"\x1b[?2004l";
+pub(crate) const FOCUS_ON: &[u8] = b"\x1b[?1004h";
+pub(crate) const FOCUS_OFF: &[u8] = b"\x1b[?1004l";
+
+/* zr_event.h: batch version at byte 4; a key payload starts with the key. */
+const EV_BATCH_VERSION_OFFSET: usize = 4;
+const KEY_FOCUS_IN: u32 = 30;
+const KEY_FOCUS_OUT: u32 = 31;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InputMode {
+    Mouse,
+    BracketedPaste,
+    FocusEvents,
+}
+
+impl InputMode {
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn supported(self, caps: &ffi::zr_terminal_caps_t) -> bool {
+        let flag = match self {
+            Self::Mouse => caps.supports_mouse,
+            Self::BracketedPaste => caps.supports_bracketed_paste,
+            Self::FocusEvents => caps.supports_focus_events,
+        };
+        flag != 0
+    }
+}
+
+/// Bytes that move `mode` from `was_on` to `enabled`; `None` when the
+/// terminal is already there. Mouse off is always sent: another program may
+/// have left tracking on without this engine knowing.
+pub(crate) fn mode_transition(
+    mode: InputMode,
+    was_on: bool,
+    enabled: bool,
+) -> Option<&'static [u8]> {
+    match (mode, enabled) {
+        (_, true) if was_on => None,
+        (InputMode::Mouse, true) => Some(MOUSE_ON),
+        (InputMode::BracketedPaste, true) => Some(PASTE_ON),
+        (InputMode::FocusEvents, true) => Some(FOCUS_ON),
+        (InputMode::Mouse, false) => Some(MOUSE_OFF),
+        (_, false) if !was_on => None,
+        (InputMode::BracketedPaste, false) => Some(PASTE_OFF),
+        (InputMode::FocusEvents, false) => Some(FOCUS_OFF),
+    }
+}
+
+/// Input reporting modes as last requested, starting from the create-time
+/// plat config. The engine rejects plat changes in `engine_set_config`, so
+/// runtime toggles live here rather than in the tracked runtime config.
+pub(crate) struct InputModes {
+    requested: Mutex<[bool; 3]>,
+    /// Modes on in the create-time plat config: the engine's own leave
+    /// sequence turns these off.
+    at_create: [bool; 3],
+    /// Last focus the terminal reported; focused until told otherwise.
+    focused: AtomicBool,
+}
+
+/// Focus carried by the last focus in/out key of a polled batch.
+pub(crate) fn last_focus(batch: &[u8]) -> Option<bool> {
+    let b = batch.get(EV_BATCH_VERSION_OFFSET..EV_BATCH_VERSION_OFFSET + 4)?;
+    let version = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    let records = decode_batch(batch, version).ok()?;
+    records.iter().rev().find_map(|r| {
+        if r.kind != "key" {
+            return None;
+        }
+        let key = u32::from_le_bytes(r.payload.get(..4)?.try_into().ok()?);
+        match key {
+            KEY_FOCUS_IN => Some(true),
+            KEY_FOCUS_OUT => Some(false),
+            _ => None,
+        }
+    })
+}
+
+impl InputModes {
+    pub(crate) fn new(plat: &ffi::plat_config_t) -> Self {
+        let at_create = [
+            plat.enable_mouse != 0,
+            plat.enable_bracketed_paste != 0,
+            plat.enable_focus_events != 0,
+        ];
+        Self {
+            requested: Mutex::new(at_create),
+            at_create,
+            focused: AtomicBool::new(true),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, [bool; 3]> {
+        match self.requested.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    pub(crate) fn requested(&self, mode: InputMode) -> bool {
+        self.lock()[mode.index()]
+    }
+
+    pub(crate) fn set(&self, mode: InputMode, enabled: bool) {
+        self.lock()[mode.index()] = enabled;
+    }
+
+    /// Disable sequences for modes turned on at runtime and still on. The
+    /// engine's leave sequence covers the modes on in the create-time plat
+    /// config, so destroy only adds these.
+    pub(crate) fn leave_sequence(&self) -> Vec<u8> {
+        let requested = *self.lock();
+        [
+            (InputMode::Mouse, MOUSE_OFF),
+            (InputMode::BracketedPaste, PASTE_OFF),
+            (InputMode::FocusEvents, FOCUS_OFF),
+        ]
+        .iter()
+        .filter(|(mode, _)| requested[mode.index()] && !self.at_create[mode.index()])
+        .flat_map(|(_, seq)| seq.iter().copied())
+        .collect()
+    }
+
+    /// Follow focus in/out keys in a batch filled by `engine_poll_events`.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8]) {
+        if rc <= 0 {
+            return;
+        }
+        if let Some(focused) = last_focus(&out[..out.len().min(rc as usize)]) {
+            self.focused.store(focused, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn focused(&self) -> bool {
+        self.focused.load(Ordering::Relaxed)
+    }
+}
+
+fn set_input_mode(engine_id: u32, mode: InputMode, enabled: bool) -> i32 {
+    let guard = match get_engine_guard(engine_id) {
+        Ok(guard) => guard,
+        Err(rc) => return rc,
+    };
+    if !guard.slot.is_owner_thread() {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+
+    let mut caps = empty_terminal_caps();
+    let rc = unsafe { ffi::engine_get_caps(guard.slot.engine, &mut caps as *mut _) };
+    if rc != ffi::ZR_OK {
+        return rc;
+    }
+    let supported = mode.supported(&caps);
+    if enabled && !supported {
+        return ffi::ZR_ERR_UNSUPPORTED;
+    }
+
+    /* The engine only entered a mode the terminal supports. */
+    let was_on = supported && guard.slot.modes.requested(mode);
+    guard.slot.idle.note_activity(Instant::now());
+    /* Pipe mode renders to redirected stdio: no terminal to switch. */
+    let target = guard.slot.output.target();
+    if let Some(seq) = mode_transition(mode, was_on, enabled).filter(|_| target.is_tty()) {
+        let rc = target.write(seq);
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+    }
+    guard.slot.modes.set(mode, enabled);
+    ffi::ZR_OK
+}
+
+/// Turn mouse reporting on or off right away. Disabling always writes the
+/// disable sequence, so it also cleans up after another program.
+#[napi(js_name = "engineSetMouse")]
+pub fn engine_set_mouse(engine_id: u32, enabled: bool) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(
+        set_input_mode(engine_id, InputMode::Mouse, enabled),
+        "engineSetMouse",
+    )
+}
+
+/// Turn bracketed paste on or off right away.
+#[napi(js_name = "engineSetBracketedPaste")]
+pub fn engine_set_bracketed_paste(engine_id: u32, enabled: bool) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(
+        set_input_mode(engine_id, InputMode::BracketedPaste, enabled),
+        "engineSetBracketedPaste",
+    )
+}
+
+/// Turn focus in/out reporting on or off right away.
+#[napi(js_name = "engineSetFocusEvents")]
+pub fn engine_set_focus_events(engine_id: u32, enabled: bool) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(
+        set_input_mode(engine_id, InputMode::FocusEvents, enabled),
+        "engineSetFocusEvents",
+    )
+}
+
+/// Last focus state the terminal reported, `true` before the first focus
+/// event. `undefined` while focus reporting is off or unsupported, and for
+/// unknown engine IDs.
+#[napi(js_name = "engineIsFocused")]
+pub fn engine_is_focused(engine_id: u32) -> Either<bool, Undefined> {
+    match reported_focus(engine_id) {
+        Some(focused) => Either::A(focused),
+        None => Either::B(()),
+    }
+}
+
+fn reported_focus(engine_id: u32) -> Option<bool> {
+    let guard = get_engine_guard(engine_id).ok()?;
+    if !guard.slot.is_owner_thread() {
+        return None;
+    }
+    let mut caps = empty_terminal_caps();
+    if unsafe { ffi::engine_get_caps(guard.slot.engine, &mut caps as *mut _) } != ffi::ZR_OK {
+        return None;
+    }
+    let modes = &guard.slot.modes;
+    let reporting =
+        InputMode::FocusEvents.supported(&caps) && modes.requested(InputMode::FocusEvents);
+    reporting.then(|| modes.focused())
+}
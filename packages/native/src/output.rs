@@ -0,0 +1,393 @@
+use crate::ffi;
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How long a visual bell keeps the screen in reverse video.
+pub(crate) const VISUAL_BELL_DURATION: Duration = Duration::from_millis(100);
+
+const BELL: &[u8] = b"\x07";
+/* DECSCNM flips the whole screen without touching cell SGR state, so the
+ * differ's view of the terminal stays valid while the flash is up. */
+const SCREEN_REVERSE_ON: &[u8] = b"\x1b[?5h";
+const SCREEN_REVERSE_OFF: &[u8] = b"\x1b[?5l";
+const TITLE_STACK_PUSH: &[u8] = b"\x1b[22t";
+const TITLE_STACK_POP: &[u8] = b"\x1b[23t";
+/* DECSC/DECRC save and restore cursor position and SGR attributes, which is
+ * exactly the terminal state the differ tracks between frames. */
+const SAVE_CURSOR: &[u8] = b"\x1b7";
+const RESTORE_CURSOR: &[u8] = b"\x1b8";
+
+/// Upper bound on raw bytes queued between presents.
+pub(crate) const RAW_QUEUE_MAX_BYTES: usize = 1 << 20;
+
+/// Drop C0/C1 controls and DEL so a title cannot terminate the OSC early or
+/// smuggle its own escape sequence.
+pub(crate) fn sanitize_title(title: &str) -> String {
+    title.chars().filter(|&c| !c.is_control()).collect()
+}
+
+/// OSC 2 (window title) terminated by BEL, the most widely accepted form.
+pub(crate) fn title_sequence(title: &str) -> Vec<u8> {
+    let clean = sanitize_title(title);
+    let mut out = Vec::with_capacity(clean.len() + 5);
+    out.extend_from_slice(b"\x1b]2;");
+    out.extend_from_slice(clean.as_bytes());
+    out.push(0x07);
+    out
+}
+
+#[cfg(unix)]
+fn pipe_mode_enabled() -> bool {
+    matches!(
+        std::env::var("ZIREAEL_POSIX_PIPE_MODE").as_deref(),
+        Ok("1" | "true" | "TRUE" | "yes" | "YES" | "on" | "ON")
+    )
+}
+
+#[cfg(unix)]
+fn stdio_is_terminal() -> bool {
+    use std::io::IsTerminal;
+
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Whether the platform layer talks to a terminal: stdio when it is a TTY,
+/// the `/dev/tty` fallback otherwise, unless pipe mode keeps redirected stdio.
+#[cfg(unix)]
+pub(crate) fn renders_to_tty() -> bool {
+    stdio_is_terminal() || !pipe_mode_enabled()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn renders_to_tty() -> bool {
+    use std::io::IsTerminal;
+
+    std::io::stdout().is_terminal()
+}
+
+/// Descriptor a bound engine writes to when only `inputFd` was given.
+const STDOUT_FD: i32 = 1;
+
+/// The stream an engine's binding-side writes (bell, title, raw bytes, mode
+/// toggles, teardown) go to: the one its platform layer renders to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum TerminalTarget {
+    /// Process stdio, or `/dev/tty` when stdio is redirected.
+    #[default]
+    Process,
+    /// Caller-supplied descriptor, used as-is like the platform layer does.
+    Fd(i32),
+}
+
+impl TerminalTarget {
+    /// Target for an engine created with `outputFd`/`inputFd` (`None` = stdio).
+    pub(crate) fn bound(output_fd: Option<i32>) -> Self {
+        Self::Fd(output_fd.unwrap_or(STDOUT_FD))
+    }
+
+    /// Bound to caller-supplied descriptors rather than the process terminal.
+    pub(crate) fn is_bound(self) -> bool {
+        matches!(self, Self::Fd(_))
+    }
+
+    /// Whether the target is a terminal that mode sequences mean anything to.
+    pub(crate) fn is_tty(self) -> bool {
+        match self {
+            Self::Process => renders_to_tty(),
+            Self::Fd(fd) => fd_is_terminal(fd),
+        }
+    }
+
+    /// Write bytes straight to the engine's terminal. Owner-thread only,
+    /// between engine calls, so the bytes never interleave with a frame.
+    pub(crate) fn write(self, bytes: &[u8]) -> i32 {
+        if bytes.is_empty() {
+            return ffi::ZR_OK;
+        }
+        let written = match self {
+            Self::Process => process_writer().and_then(|mut out| {
+                out.write_all(bytes)?;
+                out.flush()
+            }),
+            Self::Fd(fd) => write_fd(fd, bytes),
+        };
+        match written {
+            Ok(()) => ffi::ZR_OK,
+            Err(_) => ffi::ZR_ERR_PLATFORM,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn fd_is_terminal(fd: i32) -> bool {
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+#[cfg(not(unix))]
+fn fd_is_terminal(_fd: i32) -> bool {
+    false
+}
+
+/// Resolve the process stream the platform layer renders to.
+///
+/// Mirrors the POSIX backend's choice: stdio when both stdin and stdout are
+/// terminals (or `ZIREAEL_POSIX_PIPE_MODE` is on), otherwise `/dev/tty`.
+#[cfg(unix)]
+fn process_writer() -> std::io::Result<Box<dyn Write>> {
+    if pipe_mode_enabled() || stdio_is_terminal() {
+        return Ok(Box::new(std::io::stdout()));
+    }
+    let tty = std::fs::OpenOptions::new().write(true).open("/dev/tty")?;
+    Ok(Box::new(tty))
+}
+
+#[cfg(not(unix))]
+fn process_writer() -> std::io::Result<Box<dyn Write>> {
+    Ok(Box::new(std::io::stdout()))
+}
+
+/// Write all of `bytes` to a caller-owned descriptor, waiting up to
+/// `FLUSH_WAIT_MS` whenever a non-blocking one is full.
+#[cfg(unix)]
+fn write_fd(fd: i32, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::ErrorKind;
+    use std::os::fd::FromRawFd;
+
+    /* Borrowed from the caller: dropping the File must not close it. */
+    let mut file = std::mem::ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(fd) });
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        match file.write(rest) {
+            Ok(0) => return Err(ErrorKind::WriteZero.into()),
+            Ok(n) => rest = &rest[n..],
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                if wait_fd_writable(fd, FLUSH_WAIT_MS) != ffi::ZR_OK {
+                    return Err(err);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_fd(_fd: i32, _bytes: &[u8]) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// How long `engineFlush` waits for the terminal to accept output.
+pub(crate) const FLUSH_WAIT_MS: i32 = 1000;
+
+/// The `engineFlush` sequence: when the platform reports writability, wait
+/// up to `FLUSH_WAIT_MS` for it (a timeout is `ZR_ERR_LIMIT` and skips the
+/// drain), then drain.
+pub(crate) fn flush_sequence(
+    wait_writable: bool,
+    wait: impl FnOnce(i32) -> i32,
+    drain: impl FnOnce() -> i32,
+) -> i32 {
+    if wait_writable {
+        let rc = wait(FLUSH_WAIT_MS);
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+    }
+    drain()
+}
+
+#[cfg(unix)]
+fn wait_fd_writable(fd: i32, timeout_ms: i32) -> i32 {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLOUT,
+        revents: 0,
+    };
+    match unsafe { libc::poll(&mut pfd, 1, timeout_ms) } {
+        0 => ffi::ZR_ERR_LIMIT,
+        n if n > 0 => ffi::ZR_OK,
+        _ => ffi::ZR_ERR_PLATFORM,
+    }
+}
+
+/// Block until everything written to the engine's terminal has been handed
+/// to the device: flush stdout, optionally wait for writability, then
+/// `tcdrain` when the stream is a TTY. Nothing pending returns at once.
+#[cfg(unix)]
+pub(crate) fn drain_terminal(target: TerminalTarget, wait_writable: bool) -> i32 {
+    use std::os::fd::AsRawFd;
+
+    let tty;
+    let fd = match target {
+        TerminalTarget::Fd(fd) => fd,
+        TerminalTarget::Process => {
+            if std::io::stdout().flush().is_err() {
+                return ffi::ZR_ERR_PLATFORM;
+            }
+            if pipe_mode_enabled() || stdio_is_terminal() {
+                libc::STDOUT_FILENO
+            } else {
+                match std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+                    Ok(file) => {
+                        tty = file;
+                        tty.as_raw_fd()
+                    }
+                    Err(_) => return ffi::ZR_ERR_PLATFORM,
+                }
+            }
+        }
+    };
+    flush_sequence(
+        wait_writable,
+        |timeout_ms| wait_fd_writable(fd, timeout_ms),
+        || {
+            if unsafe { libc::isatty(fd) } == 0 {
+                return ffi::ZR_OK;
+            }
+            loop {
+                if unsafe { libc::tcdrain(fd) } == 0 {
+                    return ffi::ZR_OK;
+                }
+                if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+                    return ffi::ZR_ERR_PLATFORM;
+                }
+            }
+        },
+    )
+}
+
+#[cfg(not(unix))]
+pub(crate) fn drain_terminal(_target: TerminalTarget, _wait_writable: bool) -> i32 {
+    match std::io::stdout().flush() {
+        Ok(()) => ffi::ZR_OK,
+        Err(_) => ffi::ZR_ERR_PLATFORM,
+    }
+}
+
+#[derive(Default)]
+struct OutputInner {
+    /// Sequences queued for the next successful present, in call order.
+    after_present: Vec<u8>,
+    /// `after_present` holds bytes from `write_raw`.
+    raw_queued: bool,
+    visual_bell_pending: bool,
+    visual_bell_until: Option<Instant>,
+}
+
+/// Binding-side terminal output that rides alongside engine frames.
+#[derive(Default)]
+pub(crate) struct TerminalOutput {
+    target: TerminalTarget,
+    inner: Mutex<OutputInner>,
+}
+
+impl TerminalOutput {
+    pub(crate) fn new(target: TerminalTarget) -> Self {
+        Self {
+            target,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Where this engine's binding-side bytes are written.
+    pub(crate) fn target(&self) -> TerminalTarget {
+        self.target
+    }
+
+    fn lock(&self) -> MutexGuard<'_, OutputInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    pub(crate) fn bell(&self, visual: bool) -> i32 {
+        if visual {
+            self.lock().visual_bell_pending = true;
+            return ffi::ZR_OK;
+        }
+        self.target.write(BELL)
+    }
+
+    pub(crate) fn set_title(&self, title: &str) {
+        let seq = title_sequence(title);
+        self.lock().after_present.extend_from_slice(&seq);
+    }
+
+    pub(crate) fn title_stack(&self, push: bool) {
+        let seq = if push {
+            TITLE_STACK_PUSH
+        } else {
+            TITLE_STACK_POP
+        };
+        self.lock().after_present.extend_from_slice(seq);
+    }
+
+    /// Queue raw bytes for the next present, fenced by DECSC/DECRC. Returns
+    /// `ZR_ERR_LIMIT` once the queue would exceed `RAW_QUEUE_MAX_BYTES`.
+    pub(crate) fn write_raw(&self, bytes: &[u8]) -> i32 {
+        if bytes.is_empty() {
+            return ffi::ZR_OK;
+        }
+        let mut inner = self.lock();
+        let framed = SAVE_CURSOR.len() + bytes.len() + RESTORE_CURSOR.len();
+        if inner.after_present.len().saturating_add(framed) > RAW_QUEUE_MAX_BYTES {
+            return ffi::ZR_ERR_LIMIT;
+        }
+        inner.after_present.extend_from_slice(SAVE_CURSOR);
+        inner.after_present.extend_from_slice(bytes);
+        inner.after_present.extend_from_slice(RESTORE_CURSOR);
+        inner.raw_queued = true;
+        ffi::ZR_OK
+    }
+
+    /// Whether the next `take_after_present` hands out raw bytes, after which
+    /// the engine's picture of the screen can no longer be trusted.
+    pub(crate) fn raw_queued(&self) -> bool {
+        self.lock().raw_queued
+    }
+
+    /// Bytes to write after a successful present at `now`.
+    pub(crate) fn take_after_present(&self, now: Instant) -> Vec<u8> {
+        let mut inner = self.lock();
+        let mut out = std::mem::take(&mut inner.after_present);
+        inner.raw_queued = false;
+        if inner.visual_bell_pending {
+            inner.visual_bell_pending = false;
+            if inner.visual_bell_until.is_none() {
+                out.extend_from_slice(SCREEN_REVERSE_ON);
+            }
+            inner.visual_bell_until = Some(now + VISUAL_BELL_DURATION);
+        } else {
+            out.extend_from_slice(Self::expire_visual_bell(&mut inner, now));
+        }
+        out
+    }
+
+    /// Bytes that end an expired visual bell; checked on idle calls (poll).
+    pub(crate) fn take_expired(&self, now: Instant) -> &'static [u8] {
+        Self::expire_visual_bell(&mut self.lock(), now)
+    }
+
+    /// Bytes that restore the screen before the engine is torn down.
+    pub(crate) fn take_restore(&self) -> &'static [u8] {
+        let mut inner = self.lock();
+        inner.visual_bell_pending = false;
+        match inner.visual_bell_until.take() {
+            Some(_) => SCREEN_REVERSE_OFF,
+            None => &[],
+        }
+    }
+
+    fn expire_visual_bell(inner: &mut OutputInner, now: Instant) -> &'static [u8] {
+        match inner.visual_bell_until {
+            Some(until) if now >= until => {
+                inner.visual_bell_until = None;
+                SCREEN_REVERSE_OFF
+            }
+            _ => &[],
+        }
+    }
+}
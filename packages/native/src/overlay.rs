@@ -0,0 +1,211 @@
+use crate::asserts::AssertScope;
+use crate::error::{invalid_argument, zr_result_error, ZrCode};
+use crate::scroll::space;
+use crate::{ffi, owner_engine_guard};
+use napi_derive::napi;
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Instant;
+
+/// Overlay bounds (`ZR_DEBUG_OVERLAY_MAX_ROWS`/`_COLS`, zr_debug_overlay.h).
+pub(crate) const OVERLAY_ROWS: usize = 4;
+pub(crate) const OVERLAY_COLS: usize = 40;
+
+/// Screen corner the engine's debug overlay is drawn in.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum OverlayCorner {
+    /// Where the engine draws it by itself.
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayCorner {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "topLeft" => Some(Self::TopLeft),
+            "topRight" => Some(Self::TopRight),
+            "bottomLeft" => Some(Self::BottomLeft),
+            "bottomRight" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::TopRight,
+            2 => Self::BottomLeft,
+            3 => Self::BottomRight,
+            _ => Self::TopLeft,
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        match self {
+            Self::TopLeft => 0,
+            Self::TopRight => 1,
+            Self::BottomLeft => 2,
+            Self::BottomRight => 3,
+        }
+    }
+
+    /// Top-left cell of a `w` x `h` overlay on a `cols` x `rows` screen.
+    fn origin(self, cols: usize, rows: usize, w: usize, h: usize) -> (usize, usize) {
+        match self {
+            Self::TopLeft => (0, 0),
+            Self::TopRight => (cols - w, 0),
+            Self::BottomLeft => (0, rows - h),
+            Self::BottomRight => (cols - w, rows - h),
+        }
+    }
+}
+
+/// Run the engine's overlay `render` (which always draws top-left) and move
+/// what it drew to `corner`, putting the top-left cells back. Wide glyphs the
+/// overlay leaves whole at its edge become spaces, as do wide glyphs of the
+/// screen cut by the moved overlay's edges.
+pub(crate) fn place_overlay(
+    cells: &mut [ffi::zr_cell_t],
+    cols: usize,
+    rows: usize,
+    corner: OverlayCorner,
+    render: impl FnOnce(&mut [ffi::zr_cell_t]) -> i32,
+) -> i32 {
+    let (w, h) = (cols.min(OVERLAY_COLS), rows.min(OVERLAY_ROWS));
+    if corner == OverlayCorner::TopLeft || w == 0 || h == 0 || cells.len() < cols * rows {
+        return render(cells);
+    }
+    let region = |cells: &[ffi::zr_cell_t]| -> Vec<ffi::zr_cell_t> {
+        (0..h)
+            .flat_map(|y| cells[y * cols..y * cols + w].iter().copied())
+            .collect()
+    };
+    let saved = region(cells);
+    let rc = render(cells);
+    let drawn = region(cells);
+    for y in 0..h {
+        cells[y * cols..y * cols + w].copy_from_slice(&saved[y * w..(y + 1) * w]);
+    }
+    if rc != ffi::ZR_OK {
+        return rc;
+    }
+
+    let (x0, y0) = corner.origin(cols, rows, w, h);
+    for y in y0..y0 + h {
+        let row = &mut cells[y * cols..(y + 1) * cols];
+        if x0 > 0 && row[x0].width == 0 {
+            row[x0 - 1] = space(unsafe { std::mem::zeroed() });
+        }
+        if x0 + w < cols && row[x0 + w].width == 0 {
+            row[x0 + w] = space(unsafe { std::mem::zeroed() });
+        }
+        for (dst, src) in row[x0..x0 + w].iter_mut().zip(&drawn[(y - y0) * w..]) {
+            *dst = if src.width == 1 {
+                *src
+            } else {
+                space(unsafe { std::mem::zeroed() })
+            };
+        }
+    }
+    ffi::ZR_OK
+}
+
+thread_local! {
+    static ACTIVE: Cell<Option<OverlayCorner>> = const { Cell::new(None) };
+}
+
+/// Corner an engine's presents draw the debug overlay in, as last set.
+#[derive(Default)]
+pub(crate) struct OverlayCornerState {
+    corner: AtomicU8,
+}
+
+impl OverlayCornerState {
+    pub(crate) fn get(&self) -> OverlayCorner {
+        OverlayCorner::from_u8(self.corner.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, corner: OverlayCorner) {
+        self.corner.store(corner.to_u8(), Ordering::Relaxed);
+    }
+
+    /// Run `present` with the overlay moved to the corner.
+    pub(crate) fn apply(&self, present: impl FnOnce() -> i32) -> i32 {
+        let corner = self.get();
+        if corner == OverlayCorner::TopLeft {
+            return present();
+        }
+        ACTIVE.with(|a| a.set(Some(corner)));
+        let rc = present();
+        ACTIVE.with(|a| a.set(None));
+        rc
+    }
+}
+
+/// The engine's `zr_debug_overlay_render` hook (see hooks.rs), so the
+/// overlay can be moved after the engine draws it. Present draws the overlay
+/// into its staging framebuffer, never the app's, so moving cells around here
+/// leaves the next frame untouched.
+pub(crate) extern "C" fn hook_debug_overlay_render(
+    fb: *mut ffi::zr_fb_t,
+    metrics: *const c_void,
+) -> ffi::ZrResultT {
+    let corner = ACTIVE.with(Cell::get);
+    let Some(corner) = corner.filter(|_| !fb.is_null()) else {
+        return unsafe { ffi::zr_debug_overlay_render(fb, metrics) };
+    };
+    let (cols, rows, cells) = unsafe { ((*fb).cols as usize, (*fb).rows as usize, (*fb).cells) };
+    if cells.is_null() || cols == 0 || rows == 0 {
+        return unsafe { ffi::zr_debug_overlay_render(fb, metrics) };
+    }
+    let cells = unsafe { std::slice::from_raw_parts_mut(cells, cols * rows) };
+    place_overlay(cells, cols, rows, corner, |_| unsafe {
+        ffi::zr_debug_overlay_render(fb, metrics)
+    })
+}
+
+#[napi(object)]
+pub struct DebugOverlayOptions {
+    pub enabled: bool,
+    /// `"topLeft"` (the default), `"topRight"`, `"bottomLeft"` or
+    /// `"bottomRight"`; omitted keeps the current corner.
+    pub corner: Option<String>,
+}
+
+/// Show or hide the engine's debug overlay (frame metrics, 4x40 cells) from
+/// the next present on, leaving the rest of the runtime config alone.
+#[napi(js_name = "engineSetDebugOverlay")]
+pub fn engine_set_debug_overlay(
+    engine_id: u32,
+    options: DebugOverlayOptions,
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSetDebugOverlay")?;
+    let corner = match options.corner.as_deref() {
+        None => guard.slot.overlay_corner.get(),
+        Some(name) => OverlayCorner::parse(name).ok_or_else(|| {
+            invalid_argument(format!(
+                "engineSetDebugOverlay: unknown corner {name:?} (expected \"topLeft\", \"topRight\", \"bottomLeft\" or \"bottomRight\")"
+            ))
+        })?,
+    };
+
+    let mut runtime_cfg = guard.slot.runtime_cfg();
+    runtime_cfg.enable_debug_overlay = u8::from(options.enabled);
+    guard.slot.idle.note_activity(Instant::now());
+    let _watch = guard
+        .slot
+        .watchdog
+        .watch(guard.slot.engine, "engineSetDebugOverlay", 0);
+    let asserts = AssertScope::begin();
+    let rc = unsafe { ffi::engine_set_config(guard.slot.engine, &runtime_cfg as *const _) };
+    let rc = asserts.finish(rc);
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineSetDebugOverlay"));
+    }
+    guard.slot.set_runtime_cfg(runtime_cfg);
+    guard.slot.overlay_corner.set(corner);
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use crate::error::{invalid_argument, zr_error, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::framebuffer::{optional_style, CellRect, Framebuffer};
+use napi::bindgen_prelude::Reference;
+use napi::JsObject;
+use napi_derive::napi;
+
+/// Most clips a `Painter` may nest.
+pub(crate) const PAINTER_CLIP_MAX: u32 = 1024;
+
+/// Storage behind a painter's clip stack. Entry 0 is always the whole
+/// framebuffer; `len` counts it.
+pub(crate) struct ClipStack {
+    rects: Vec<ffi::zr_rect_t>,
+    len: u32,
+}
+
+impl ClipStack {
+    /// Room for `capacity` pushed clips on top of the framebuffer bounds.
+    pub(crate) fn new(capacity: u32) -> Self {
+        let empty = ffi::zr_rect_t {
+            x: 0,
+            y: 0,
+            w: 0,
+            h: 0,
+        };
+        Self {
+            rects: vec![empty; capacity as usize + 1],
+            len: 1,
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> u32 {
+        self.rects.len() as u32 - 1
+    }
+
+    pub(crate) fn depth(&self) -> u32 {
+        self.len - 1
+    }
+
+    /// Run `f` with an engine painter over `fb` that starts from the clips
+    /// pushed so far and keeps whatever `f` pushes or pops. The base entry is
+    /// refreshed from `fb` each time.
+    pub(crate) fn with_painter(
+        &mut self,
+        fb: &mut ffi::zr_fb_t,
+        f: impl FnOnce(&mut ffi::zr_fb_painter_t) -> i32,
+    ) -> i32 {
+        let mut painter = ffi::zr_fb_painter_t {
+            fb: std::ptr::null_mut(),
+            clip_stack: std::ptr::null_mut(),
+            clip_cap: 0,
+            clip_len: 0,
+        };
+        let rc = unsafe {
+            ffi::zr_fb_painter_begin(
+                &mut painter as *mut _,
+                fb as *mut _,
+                self.rects.as_mut_ptr(),
+                self.rects.len() as u32,
+            )
+        };
+        if rc != ffi::ZR_OK {
+            return rc;
+        }
+        painter.clip_len = self.len;
+        let rc = f(&mut painter);
+        self.len = painter.clip_len;
+        rc
+    }
+
+    pub(crate) fn push(&mut self, fb: &mut ffi::zr_fb_t, clip: ffi::zr_rect_t) -> i32 {
+        self.with_painter(fb, |p| unsafe { ffi::zr_fb_clip_push(p as *mut _, clip) })
+    }
+
+    pub(crate) fn pop(&mut self, fb: &mut ffi::zr_fb_t) -> i32 {
+        self.with_painter(fb, |p| unsafe { ffi::zr_fb_clip_pop(p as *mut _) })
+    }
+
+    pub(crate) fn put_grapheme(
+        &mut self,
+        fb: &mut ffi::zr_fb_t,
+        x: i32,
+        y: i32,
+        glyph: &[u8],
+        width: u8,
+        style: &ffi::zr_style_t,
+    ) -> i32 {
+        self.with_painter(fb, |p| unsafe {
+            ffi::zr_fb_put_grapheme(
+                p as *mut _,
+                x,
+                y,
+                glyph.as_ptr(),
+                glyph.len(),
+                width,
+                style as *const _,
+            )
+        })
+    }
+}
+
+/// Clipped drawing on a `Framebuffer`, as the engine draws drawlists:
+/// writes outside the current clip are dropped, and wide glyphs cut by a
+/// clip edge are cleaned up the same way. Obtained from `fb.painter()`.
+#[napi]
+pub struct Painter {
+    fb: Reference<Framebuffer>,
+    clips: ClipStack,
+}
+
+#[napi]
+impl Framebuffer {
+    /// A painter over this framebuffer that can nest up to `clipCapacity`
+    /// clips.
+    #[napi]
+    pub fn painter(
+        &self,
+        reference: Reference<Framebuffer>,
+        clip_capacity: u32,
+    ) -> napi::Result<Painter, ZrCode> {
+        const CTX: &str = "Framebuffer.painter";
+        self.raw(CTX)?;
+        if clip_capacity > PAINTER_CLIP_MAX {
+            return Err(zr_error(
+                ffi::ZR_ERR_LIMIT,
+                format!("{CTX}: clipCapacity {clip_capacity} is over {PAINTER_CLIP_MAX}"),
+            ));
+        }
+        Ok(Painter {
+            fb: reference,
+            clips: ClipStack::new(clip_capacity),
+        })
+    }
+}
+
+#[napi]
+impl Painter {
+    /// Clips currently pushed.
+    #[napi(getter, js_name = "clipDepth")]
+    pub fn clip_depth(&self) -> u32 {
+        self.clips.depth()
+    }
+
+    /// Narrow the clip to `rect` intersected with the current one. Throws
+    /// `ZR_ERR_LIMIT` when `clipCapacity` clips are already pushed.
+    #[napi(js_name = "clipPush")]
+    pub fn clip_push(&mut self, rect: CellRect) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Painter.clipPush";
+        let capacity = self.clips.capacity();
+        let fields = [rect.x, rect.y, rect.w, rect.h];
+        if fields.iter().any(|&v| v > i32::MAX as u32) {
+            return Err(invalid_argument(format!("{CTX}: rect is out of range")));
+        }
+        let clip = ffi::zr_rect_t {
+            x: rect.x as i32,
+            y: rect.y as i32,
+            w: rect.w as i32,
+            h: rect.h as i32,
+        };
+        let fb = self.fb.raw_mut(CTX)?;
+        match self.clips.push(fb, clip) {
+            ffi::ZR_OK => Ok(()),
+            ffi::ZR_ERR_LIMIT => Err(zr_error(
+                ffi::ZR_ERR_LIMIT,
+                format!("{CTX}: clip stack is full (clipCapacity {capacity})"),
+            )),
+            rc => Err(zr_result_error(rc, CTX)),
+        }
+    }
+
+    /// Restore the clip in effect before the last `clipPush`. Throws
+    /// `ZR_ERR_LIMIT` when nothing is pushed.
+    #[napi(js_name = "clipPop")]
+    pub fn clip_pop(&mut self) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Painter.clipPop";
+        let fb = self.fb.raw_mut(CTX)?;
+        match self.clips.pop(fb) {
+            ffi::ZR_OK => Ok(()),
+            ffi::ZR_ERR_LIMIT => Err(zr_error(
+                ffi::ZR_ERR_LIMIT,
+                format!("{CTX}: no clip is pushed"),
+            )),
+            rc => Err(zr_result_error(rc, CTX)),
+        }
+    }
+
+    /// Write grapheme `glyph` at `(x, y)` taking `width` columns (1 or 2),
+    /// subject to the current clip. An empty glyph writes a space and one over
+    /// 32 bytes U+FFFD.
+    #[napi(js_name = "putGrapheme")]
+    pub fn put_grapheme(
+        &mut self,
+        x: i32,
+        y: i32,
+        glyph: String,
+        width: u32,
+        style: Option<JsObject>,
+    ) -> napi::Result<(), ZrCode> {
+        const CTX: &str = "Painter.putGrapheme";
+        if width != 1 && width != 2 {
+            return Err(invalid_argument(format!(
+                "{CTX}: width must be 1 or 2, got {width}"
+            )));
+        }
+        let style = optional_style(style, CTX)?;
+        let fb = self.fb.raw_mut(CTX)?;
+        let rc = self
+            .clips
+            .put_grapheme(fb, x, y, glyph.as_bytes(), width as u8, &style);
+        if rc != ffi::ZR_OK {
+            return Err(zr_result_error(rc, CTX));
+        }
+        Ok(())
+    }
+}
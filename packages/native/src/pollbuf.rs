@@ -0,0 +1,120 @@
+use crate::error::{invalid_argument, napi_error, zr_result_error, zr_result_to_napi, ZrCode};
+use crate::{ffi, owner_engine_guard, poll_slot};
+use napi::{Env, JsTypedArray, TypedArrayType};
+use napi_derive::napi;
+use std::sync::{Arc, Mutex};
+
+/// Heap bytes shared by an engine and the JS views over them. Freed once
+/// the engine has moved on to another buffer (or was destroyed) and every
+/// view was collected.
+pub(crate) struct PollStorage {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for PollStorage {}
+unsafe impl Sync for PollStorage {}
+
+impl PollStorage {
+    pub(crate) fn new(len: usize) -> Self {
+        let bytes = Box::into_raw(vec![0u8; len].into_boxed_slice());
+        Self {
+            ptr: bytes.cast(),
+            len,
+        }
+    }
+
+    /// The bytes, for a poll on the owner thread. JS only reads them between
+    /// calls on that same thread, so this never aliases a live reader.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn bytes_mut(&self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for PollStorage {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.ptr, self.len)) });
+    }
+}
+
+/// The buffer `enginePollEventsInto` fills, once `enginePollBuffer` made one.
+#[derive(Default)]
+pub(crate) struct PollBuffer(Mutex<Option<Arc<PollStorage>>>);
+
+impl PollBuffer {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Arc<PollStorage>>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Make `storage` the buffer later polls fill.
+    pub(crate) fn replace(&self, storage: Arc<PollStorage>) {
+        *self.lock() = Some(storage);
+    }
+
+    pub(crate) fn current(&self) -> Option<Arc<PollStorage>> {
+        self.lock().clone()
+    }
+}
+
+/// Allocate a `capacity`-byte poll buffer owned by the engine and return a
+/// view over it. `enginePollEventsInto` fills this buffer from then on; keep
+/// the view and read each batch from it instead of passing a fresh array to
+/// `enginePollEvents`. A batch is only valid until the next poll overwrites
+/// it. Calling again replaces the buffer: earlier views stay readable but are
+/// no longer written. Throws where the runtime forbids external buffers.
+#[napi(js_name = "enginePollBuffer")]
+pub fn engine_poll_buffer(
+    env: Env,
+    engine_id: u32,
+    capacity: u32,
+) -> napi::Result<JsTypedArray, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePollBuffer")?;
+    if capacity == 0 || capacity > i32::MAX as u32 {
+        return Err(invalid_argument(
+            "enginePollBuffer: capacity must be between 1 and 2^31-1 bytes",
+        ));
+    }
+    let storage = Arc::new(PollStorage::new(capacity as usize));
+    let view = unsafe {
+        env.create_arraybuffer_with_borrowed_data(
+            storage.ptr,
+            storage.len,
+            Arc::clone(&storage),
+            |storage, _env| drop(storage),
+        )
+    }
+    .and_then(|buf| {
+        buf.into_raw()
+            .into_typedarray(TypedArrayType::Uint8, capacity as usize, 0)
+    })
+    .map_err(napi_error)?;
+    guard.slot.poll_buffer.replace(storage);
+    Ok(view)
+}
+
+/// `enginePollEvents` into the buffer from `enginePollBuffer`. Returns the
+/// number of bytes written at its start (0 when nothing arrived before the
+/// timeout); the batch is only valid until the next poll.
+#[napi(js_name = "enginePollEventsInto")]
+pub fn engine_poll_events_into(engine_id: u32, timeout_ms: i32) -> napi::Result<i32, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePollEventsInto")?;
+    if timeout_ms < 0 {
+        return Err(zr_result_error(
+            ffi::ZR_ERR_INVALID_ARGUMENT,
+            "enginePollEventsInto",
+        ));
+    }
+    let Some(storage) = guard.slot.poll_buffer.current() else {
+        return Err(invalid_argument(
+            "enginePollEventsInto: no poll buffer; call enginePollBuffer first",
+        ));
+    };
+    let out = unsafe { storage.bytes_mut() };
+    let rc = poll_slot(&guard.slot, timeout_ms, out, "enginePollEventsInto");
+    zr_result_to_napi(rc, "enginePollEventsInto")?;
+    Ok(rc)
+}
@@ -0,0 +1,82 @@
+use crate::config::{js_u32, validate_known_keys};
+use crate::error::{invalid_argument, zr_error, ZrCode};
+use crate::{ffi, owner_engine_guard};
+use napi::JsObject;
+use napi_derive::napi;
+use std::time::Instant;
+
+const PREWARM_OPTS_KEYS: &[(&str, &str)] = &[("cols", "cols"), ("rows", "rows")];
+
+/// What `enginePrewarm` reserved.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct PrewarmReport {
+    /// Bytes a full `cols`x`rows` frame asks each arena for.
+    pub arenaBytesRequested: u32,
+    /// Initial bytes each engine arena now reserves up front.
+    pub arenaBytesReserved: u32,
+    /// `true` when `arenaMaxTotalBytes` capped the reservation.
+    pub clamped: bool,
+}
+
+/// Arena bytes for one full frame: a framebuffer's worth of cells.
+pub(crate) fn prewarm_arena_bytes(cols: u32, rows: u32) -> u32 {
+    let cells = u64::from(cols) * u64::from(rows);
+    let bytes = cells.saturating_mul(std::mem::size_of::<ffi::zr_cell_t>() as u64);
+    bytes.min(u64::from(u32::MAX)) as u32
+}
+
+/// Initial arena size to run with: grown to `requested`, never past the
+/// arena cap, never shrunk. Returns the size and whether the cap applied.
+pub(crate) fn prewarm_initial_bytes(limits: &ffi::zr_limits_t, requested: u32) -> (u32, bool) {
+    let current = limits.arena_initial_bytes;
+    let max = limits.arena_max_total_bytes;
+    let target = requested.min(max).max(current);
+    (target, requested > max && target < requested)
+}
+
+/// Reserve the engine arenas a full-screen frame needs before the first
+/// present, so early frames do not pay for arena growth. The engine
+/// reallocates its arenas only through a config change, so this re-applies the
+/// engine's current runtime config with a larger `arenaInitialBytes`; the
+/// framebuffers are already allocated at the full terminal size.
+#[napi(js_name = "enginePrewarm")]
+pub fn engine_prewarm(engine_id: u32, opts: JsObject) -> napi::Result<PrewarmReport, ZrCode> {
+    const CTX: &str = "enginePrewarm";
+    let guard = owner_engine_guard(engine_id, CTX)?;
+    validate_known_keys(&opts, PREWARM_OPTS_KEYS, &format!("{CTX} opts"))?;
+    let dims = (js_u32(&opts, "cols", "cols"), js_u32(&opts, "rows", "rows"));
+    let (Ok(Some(cols)), Ok(Some(rows))) = dims else {
+        return Err(invalid_argument(format!(
+            "{CTX}: opts.cols and opts.rows must be non-negative integers"
+        )));
+    };
+
+    let mut cfg = guard.slot.runtime_cfg();
+    let requested = prewarm_arena_bytes(cols, rows);
+    let (reserved, clamped) = prewarm_initial_bytes(&cfg.limits, requested);
+    if reserved != cfg.limits.arena_initial_bytes {
+        cfg.limits.arena_initial_bytes = reserved;
+        guard.slot.idle.note_activity(Instant::now());
+        let _watch = guard
+            .slot
+            .watchdog
+            .watch(guard.slot.engine, "enginePrewarm", 0);
+        let rc = unsafe { ffi::engine_set_config(guard.slot.engine, &cfg as *const _) };
+        if rc != ffi::ZR_OK {
+            return Err(zr_error(
+                rc,
+                format!(
+                    "{CTX}: reserving {reserved} arena bytes failed: {}",
+                    ZrCode(rc).name()
+                ),
+            ));
+        }
+        guard.slot.set_runtime_cfg(cfg);
+    }
+    Ok(PrewarmReport {
+        arenaBytesRequested: requested,
+        arenaBytesReserved: reserved,
+        clamped,
+    })
+}
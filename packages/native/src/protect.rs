@@ -0,0 +1,253 @@
+use crate::cursor::override_desired_cursor;
+use crate::error::{zr_result_to_napi, ZrCode};
+use crate::ffi;
+use crate::fullframes::{note_diff, DiffOutcome};
+use crate::initstyle::seeded_term_state;
+use crate::registry::get_engine_guard;
+use crate::state::repaint_term_state;
+use napi_derive::napi;
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::sync::{Mutex, MutexGuard};
+
+/// Most rectangles `engineSetProtectedRegions` accepts at once.
+pub(crate) const PROTECTED_REGIONS_MAX: usize = 64;
+
+/* U+FFFD, the framebuffer's stand-in for a wide glyph cut by a clip edge. */
+const REPLACEMENT: &[u8] = "\u{FFFD}".as_bytes();
+
+/// Screen rectangle the engine's diff leaves alone, in cells.
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtectedRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Per-cell mask of the protected area, widened so a wide glyph the
+/// terminal shows across a region edge is kept whole.
+fn protected_mask(prev: &[ffi::zr_cell_t], cols: usize, rects: &[ProtectedRect]) -> Vec<bool> {
+    let rows = prev.len() / cols;
+    let mut mask = vec![false; prev.len()];
+    for r in rects {
+        let (x0, y0) = (r.x as usize, r.y as usize);
+        let x1 = x0.saturating_add(r.w as usize).min(cols);
+        let y1 = y0.saturating_add(r.h as usize).min(rows);
+        for y in y0.min(y1)..y1 {
+            mask[y * cols + x0.min(x1)..y * cols + x1].fill(true);
+        }
+    }
+    for row in 0..rows {
+        for x in 0..cols {
+            let i = row * cols + x;
+            if !mask[i] {
+                continue;
+            }
+            if prev[i].width == 2 && x + 1 < cols {
+                mask[i + 1] = true;
+            }
+            if prev[i].width == 0 && x > 0 {
+                mask[i - 1] = true;
+            }
+        }
+    }
+    mask
+}
+
+fn set_single(cell: &mut ffi::zr_cell_t, glyph: &[u8]) {
+    cell.glyph.fill(0);
+    cell.glyph[..glyph.len()].copy_from_slice(glyph);
+    cell.glyph_len = glyph.len() as u8;
+    cell.width = 1;
+}
+
+/// Make `next` match `prev` inside `rects` so the differ emits nothing
+/// there, and `next` (committed as the engine's view of the screen) keeps
+/// describing what the terminal still shows. Wide glyphs of `next` cut by
+/// a region edge follow the clip-edge policy: a lead outside becomes U+FFFD,
+/// an orphaned continuation outside becomes a space. Hyperlinks are dropped
+/// from protected cells because link refs are local to each framebuffer.
+/// Does nothing across a resize.
+pub(crate) fn protect_cells(
+    prev: &mut ffi::zr_fb_t,
+    next: &mut ffi::zr_fb_t,
+    rects: &[ProtectedRect],
+) {
+    let cols = next.cols as usize;
+    let len = cols * next.rows as usize;
+    if prev.cols != next.cols
+        || prev.rows != next.rows
+        || len == 0
+        || prev.cells.is_null()
+        || next.cells.is_null()
+    {
+        return;
+    }
+    let prev = unsafe { std::slice::from_raw_parts_mut(prev.cells, len) };
+    let next = unsafe { std::slice::from_raw_parts_mut(next.cells, len) };
+    let mask = protected_mask(prev, cols, rects);
+
+    for (i, _) in mask.iter().enumerate().filter(|(_, &m)| m) {
+        prev[i].style.link_ref = 0;
+        next[i] = prev[i];
+    }
+    for i in 0..len {
+        if mask[i] {
+            continue;
+        }
+        let x = i % cols;
+        let cut_lead = next[i].width == 2 && x + 1 < cols && mask[i + 1];
+        let orphan = next[i].width == 0 && x > 0 && mask[i - 1];
+        if cut_lead {
+            set_single(&mut next[i], REPLACEMENT);
+        } else if orphan {
+            set_single(&mut next[i], b" ");
+        }
+    }
+}
+
+/// Regions the engine's present diff must not touch, as last set.
+#[derive(Default)]
+pub(crate) struct ProtectedRegions {
+    rects: Mutex<Vec<ProtectedRect>>,
+}
+
+impl ProtectedRegions {
+    fn lock(&self) -> MutexGuard<'_, Vec<ProtectedRect>> {
+        match self.rects.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Run `present` with the regions applied to its diff.
+    pub(crate) fn apply(&self, present: impl FnOnce() -> i32) -> i32 {
+        let rects = self.lock().clone();
+        if rects.is_empty() {
+            return present();
+        }
+        ACTIVE.with(|a| *a.borrow_mut() = Some(rects));
+        let rc = present();
+        ACTIVE.with(|a| *a.borrow_mut() = None);
+        rc
+    }
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Vec<ProtectedRect>>> = const { RefCell::new(None) };
+}
+
+/// The engine's `zr_diff_render_ex` hook (see hooks.rs), so the present diff
+/// can be handed a protected framebuffer. Only the engine's present calls
+/// this; both framebuffers are engine-owned and mutable behind the const
+/// pointers.
+/// The cursor blink override (see cursor.rs), the initial style (see
+/// initstyle.rs) and the repaint after a restore (see state.rs) are applied
+/// here as well, and the outcome is
+/// kept for full-frame reasons (see fullframes.rs).
+pub(crate) extern "C" fn hook_diff_render_ex(
+    prev: *const ffi::zr_fb_t,
+    next: *const ffi::zr_fb_t,
+    caps: *const ffi::plat_caps_t,
+    initial_term_state: *const ffi::zr_term_state_t,
+    desired_cursor_state: *const ffi::zr_cursor_state_t,
+    lim: *const ffi::zr_limits_t,
+    scratch_damage_rects: *mut ffi::zr_damage_rect_t,
+    scratch_damage_rect_cap: u32,
+    scratch: *mut c_void,
+    enable_scroll_optimizations: u8,
+    enable_erase_optimizations: u8,
+    out_buf: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+    out_final_term_state: *mut ffi::zr_term_state_t,
+    out_stats: *mut ffi::zr_diff_stats_t,
+) -> ffi::ZrResultT {
+    let cursor = override_desired_cursor(desired_cursor_state);
+    let desired_cursor_state = cursor
+        .as_ref()
+        .map_or(desired_cursor_state, |c| c as *const _);
+    let screen_invalid = !initial_term_state.is_null()
+        && unsafe { (*initial_term_state).flags } & ffi::ZR_TERM_STATE_SCREEN_VALID == 0;
+    let seeded = seeded_term_state(initial_term_state);
+    let initial_term_state = seeded
+        .as_ref()
+        .map_or(initial_term_state, |t| t as *const _);
+    let repaint = repaint_term_state(initial_term_state);
+    let initial_term_state = repaint
+        .as_ref()
+        .map_or(initial_term_state, |t| t as *const _);
+    let mut scroll = enable_scroll_optimizations;
+    let mut erase = enable_erase_optimizations;
+    ACTIVE.with(|a| {
+        if let Some(rects) = a.borrow().as_deref() {
+            if !prev.is_null() && !next.is_null() {
+                unsafe { protect_cells(&mut *prev.cast_mut(), &mut *next.cast_mut(), rects) };
+            }
+            /* A scroll would move the protected content with the rows, and
+             * an erase would wipe it where its cells hold blanks. */
+            scroll = 0;
+            erase = 0;
+        }
+    });
+    let rc = unsafe {
+        ffi::zr_diff_render_ex(
+            prev,
+            next,
+            caps,
+            initial_term_state,
+            desired_cursor_state,
+            lim,
+            scratch_damage_rects,
+            scratch_damage_rect_cap,
+            scratch,
+            scroll,
+            erase,
+            out_buf,
+            out_cap,
+            out_len,
+            out_final_term_state,
+            out_stats,
+        )
+    };
+    if rc == ffi::ZR_OK && !out_stats.is_null() {
+        note_diff(DiffOutcome {
+            screen_invalid,
+            scroll_hit: unsafe { (*out_stats).scroll_opt_hit } != 0,
+        });
+    }
+    rc
+}
+
+/// Keep the engine's present diff out of `rects`: cells inside are never
+/// emitted and scroll optimizations are off while any region is set. Pass
+/// an empty array to clear. More than `PROTECTED_REGIONS_MAX` rects return
+/// `ZR_ERR_LIMIT`.
+#[napi(js_name = "engineSetProtectedRegions")]
+pub fn engine_set_protected_regions(
+    engine_id: u32,
+    rects: Vec<ProtectedRect>,
+) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(
+        set_protected_regions(engine_id, rects),
+        "engineSetProtectedRegions",
+    )
+}
+
+fn set_protected_regions(engine_id: u32, rects: Vec<ProtectedRect>) -> i32 {
+    let guard = match get_engine_guard(engine_id) {
+        Ok(guard) => guard,
+        Err(rc) => return rc,
+    };
+    if !guard.slot.is_owner_thread() {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    if rects.len() > PROTECTED_REGIONS_MAX {
+        return ffi::ZR_ERR_LIMIT;
+    }
+    let rects: Vec<_> = rects.into_iter().filter(|r| r.w > 0 && r.h > 0).collect();
+    *guard.slot.protected.lock() = rects;
+    ffi::ZR_OK
+}
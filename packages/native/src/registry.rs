@@ -1,35 +1,132 @@
+use crate::chunks::WriteChunking;
+use crate::config::{runtime_cfg_from_create, BindingCreateOptions};
+use crate::cursor::CursorBlinkState;
+use crate::debugsub::DebugSubscriber;
+use crate::drawlist::AttrFallback;
+use crate::drops::DropCounters;
+use crate::events::EventSeq;
+use crate::export::FrameShadow;
 use crate::ffi;
+use crate::fullframes::FullFrameLog;
+use crate::idle::IdleState;
+use crate::initstyle::InitialStyle;
+use crate::layers::LayerStack;
+use crate::metrics::{FrameTimes, MetricsBaseline};
+use crate::modes::InputModes;
+use crate::output::TerminalOutput;
+use crate::overlay::OverlayCornerState;
+use crate::pollbuf::PollBuffer;
+use crate::protect::ProtectedRegions;
+use crate::size::{PolledSize, PostedSize};
+use crate::spans::FrameSpanState;
+use crate::state::RepaintRequest;
+use crate::watchdog::WatchdogState;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Condvar, Mutex, OnceLock};
-use std::thread::ThreadId;
 
 pub(crate) struct EngineSlot {
     pub(crate) engine: *mut ffi::zr_engine_t,
-    owner_thread_id: ThreadId,
+    owner_thread_id: AtomicU64,
     active_calls: AtomicUsize,
     active_calls_mu: Mutex<()>,
     active_calls_cv: Condvar,
     destroyed: AtomicBool,
+    pub(crate) watchdog: WatchdogState,
+    pub(crate) attr_fallback: AttrFallback,
+    pub(crate) output: TerminalOutput,
+    pub(crate) idle: IdleState,
+    pub(crate) drops: DropCounters,
+    pub(crate) shadow: FrameShadow,
+    pub(crate) inline_screen: bool,
+    pub(crate) modes: InputModes,
+    pub(crate) spans: FrameSpanState,
+    pub(crate) protected: ProtectedRegions,
+    pub(crate) layers: LayerStack,
+    pub(crate) cursor_blink: CursorBlinkState,
+    pub(crate) overlay_corner: OverlayCornerState,
+    pub(crate) repaint: RepaintRequest,
+    pub(crate) initial_style: InitialStyle,
+    pub(crate) write_chunks: WriteChunking,
+    pub(crate) metrics_baseline: MetricsBaseline,
+    pub(crate) frame_times: FrameTimes,
+    pub(crate) debug_sub: DebugSubscriber,
+    pub(crate) full_frames: FullFrameLog,
+    pub(crate) event_seq: EventSeq,
+    pub(crate) polled_size: PolledSize,
+    pub(crate) posted_size: PostedSize,
+    pub(crate) poll_buffer: PollBuffer,
+    /// Queue unrecognized input sequences as `"unknown"` records.
+    pub(crate) unknown_sequences: bool,
+    /// Drawlist version negotiated at create; the engine accepts no other.
+    pub(crate) drawlist_version: u32,
+    /// Runtime config last accepted by the engine (create or `engineSetConfig`).
+    runtime_cfg: Mutex<ffi::zr_engine_runtime_config_t>,
 }
 
 unsafe impl Send for EngineSlot {}
 unsafe impl Sync for EngineSlot {}
 
 impl EngineSlot {
-    fn new(engine: *mut ffi::zr_engine_t) -> Self {
+    fn new(
+        engine: *mut ffi::zr_engine_t,
+        cfg: &ffi::zr_engine_config_t,
+        opts: &BindingCreateOptions,
+    ) -> Self {
         Self {
             engine,
-            owner_thread_id: current_thread_id(),
+            owner_thread_id: AtomicU64::new(current_thread_id()),
             active_calls: AtomicUsize::new(0),
             active_calls_mu: Mutex::new(()),
             active_calls_cv: Condvar::new(),
             destroyed: AtomicBool::new(false),
+            watchdog: WatchdogState::new(opts.max_call_ms),
+            attr_fallback: opts.attr_fallback,
+            output: TerminalOutput::new(opts.terminal_target()),
+            idle: IdleState::new(opts.idle_ms),
+            drops: DropCounters::default(),
+            shadow: FrameShadow::new(opts.track_frame),
+            inline_screen: opts.inline_screen,
+            modes: InputModes::new(&cfg.plat),
+            spans: FrameSpanState::default(),
+            protected: ProtectedRegions::default(),
+            layers: LayerStack::default(),
+            cursor_blink: CursorBlinkState::default(),
+            overlay_corner: OverlayCornerState::default(),
+            repaint: RepaintRequest::default(),
+            initial_style: InitialStyle::new(opts.initial_style),
+            write_chunks: WriteChunking::new(opts.write_chunk_bytes),
+            metrics_baseline: MetricsBaseline::default(),
+            frame_times: FrameTimes::new(opts.frame_stats_window),
+            debug_sub: DebugSubscriber::default(),
+            full_frames: FullFrameLog::default(),
+            event_seq: EventSeq::default(),
+            polled_size: PolledSize::default(),
+            posted_size: PostedSize::default(),
+            poll_buffer: PollBuffer::default(),
+            unknown_sequences: opts.unknown_sequences,
+            drawlist_version: cfg.requested_drawlist_version,
+            runtime_cfg: Mutex::new(runtime_cfg_from_create(cfg)),
+        }
+    }
+
+    pub(crate) fn runtime_cfg(&self) -> ffi::zr_engine_runtime_config_t {
+        match self.runtime_cfg.lock() {
+            Ok(guard) => *guard,
+            Err(poison) => *poison.into_inner(),
+        }
+    }
+
+    /// Record a runtime config the engine accepted.
+    pub(crate) fn set_runtime_cfg(&self, cfg: ffi::zr_engine_runtime_config_t) {
+        match self.runtime_cfg.lock() {
+            Ok(mut guard) => *guard = cfg,
+            Err(poison) => *poison.into_inner() = cfg,
         }
     }
 
     pub(crate) fn is_owner_thread(&self) -> bool {
-        self.owner_thread_id == current_thread_id()
+        self.owner_thread_id.load(Ordering::Acquire) == current_thread_id()
     }
 
     pub(crate) fn mark_destroyed(&self) {
@@ -75,8 +172,14 @@ fn registry() -> &'static Mutex<HashMap<u32, Arc<EngineSlot>>> {
     REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-fn current_thread_id() -> ThreadId {
-    std::thread::current().id()
+/// Process-unique id of the calling thread. `std::thread::ThreadId` has no
+/// stable integer form, and the owner has to fit in an atomic.
+fn current_thread_id() -> u64 {
+    static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    }
+    THREAD_ID.with(|id| *id)
 }
 
 fn alloc_engine_id() -> Result<u32, i32> {
@@ -113,9 +216,13 @@ fn lock_registry<T>(f: impl FnOnce(&mut HashMap<u32, Arc<EngineSlot>>) -> T) ->
     f(&mut guard)
 }
 
-pub(crate) fn register_engine(engine: *mut ffi::zr_engine_t) -> Result<u32, i32> {
+pub(crate) fn register_engine(
+    engine: *mut ffi::zr_engine_t,
+    cfg: &ffi::zr_engine_config_t,
+    opts: &BindingCreateOptions,
+) -> Result<u32, i32> {
     let engine_id = alloc_engine_id()?;
-    let slot = Arc::new(EngineSlot::new(engine));
+    let slot = Arc::new(EngineSlot::new(engine, cfg, opts));
 
     lock_registry(|map| {
         map.insert(engine_id, slot);
@@ -141,6 +248,105 @@ pub(crate) fn take_engine_for_owner(engine_id: u32) -> Option<Arc<EngineSlot>> {
     })
 }
 
+/// Like `take_engine_for_owner`, but only when no call is in flight, so the
+/// caller can destroy the engine without waiting. Calls register under the
+/// registry lock, so none can start between the check and the removal.
+pub(crate) fn take_idle_engine_for_owner(engine_id: u32) -> Option<Arc<EngineSlot>> {
+    if engine_id == 0 {
+        return None;
+    }
+
+    lock_registry(|map| {
+        let slot = map.get(&engine_id)?;
+        if !slot.is_owner_thread() || slot.active_calls.load(Ordering::Acquire) != 0 {
+            return None;
+        }
+        map.remove(&engine_id)
+    })
+}
+
+/// Why `transfer_ownership` refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TransferRefused {
+    /// No live engine has this id (never created, or destroyed).
+    Unknown,
+    /// Another thread is inside a call on the engine.
+    Busy,
+}
+
+/// Make the calling thread the owner of `engine_id`. New calls take the
+/// registry lock to register themselves, so holding it while checking the
+/// in-flight count makes the handover atomic with respect to them.
+pub(crate) fn transfer_ownership(engine_id: u32) -> Result<(), TransferRefused> {
+    if engine_id == 0 {
+        return Err(TransferRefused::Unknown);
+    }
+    lock_registry(|map| {
+        let slot = map.get(&engine_id).ok_or(TransferRefused::Unknown)?;
+        if slot.destroyed.load(Ordering::Acquire) {
+            return Err(TransferRefused::Unknown);
+        }
+        if slot.active_calls.load(Ordering::Acquire) != 0 {
+            return Err(TransferRefused::Busy);
+        }
+        slot.owner_thread_id
+            .store(current_thread_id(), Ordering::Release);
+        Ok(())
+    })
+}
+
+/// Look up a slot without registering an active call. Only for binding-side
+/// state that is safe to read from any thread (never touch `slot.engine`).
+pub(crate) fn find_engine_slot(engine_id: u32) -> Option<Arc<EngineSlot>> {
+    if engine_id == 0 {
+        return None;
+    }
+    lock_registry(|map| map.get(&engine_id).map(Arc::clone))
+}
+
+/// Whether `engine_id` names a live engine. Does not register a call.
+pub(crate) fn is_engine_registered(engine_id: u32) -> bool {
+    if engine_id == 0 {
+        return false;
+    }
+    lock_registry(|map| {
+        map.get(&engine_id)
+            .is_some_and(|slot| !slot.destroyed.load(Ordering::Acquire))
+    })
+}
+
+/// Ids of all live engines, ascending.
+pub(crate) fn registered_engine_ids() -> Vec<u32> {
+    let mut ids = lock_registry(|map| {
+        map.iter()
+            .filter(|(_, slot)| !slot.destroyed.load(Ordering::Acquire))
+            .map(|(engine_id, _)| *engine_id)
+            .collect::<Vec<_>>()
+    });
+    ids.sort_unstable();
+    ids
+}
+
+/// Ids of the live engines the calling thread owns, ascending.
+pub(crate) fn owned_engine_ids() -> Vec<u32> {
+    let mut ids = lock_registry(|map| {
+        map.iter()
+            .filter(|(_, slot)| slot.is_owner_thread())
+            .map(|(engine_id, _)| *engine_id)
+            .collect::<Vec<_>>()
+    });
+    ids.sort_unstable();
+    ids
+}
+
+pub(crate) fn snapshot_engine_slots() -> Vec<(u32, Arc<EngineSlot>)> {
+    lock_registry(|map| {
+        map.iter()
+            .map(|(engine_id, slot)| (*engine_id, Arc::clone(slot)))
+            .collect()
+    })
+}
+
 pub(crate) fn get_engine_guard(engine_id: u32) -> Result<EngineGuard, i32> {
     if engine_id == 0 {
         return Err(ffi::ZR_ERR_INVALID_ARGUMENT);
@@ -0,0 +1,424 @@
+use crate::config::{
+    apply_limits, check_limits, js_obj, js_u32, js_u8_bool, validate_known_keys, LIMITS_KEYS,
+};
+use crate::drawlist::{dl_opcode_name, drawlist_prefix, find_limit_violation, walk_cmds};
+use crate::error::{invalid_argument, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::framebuffer::{apply_diff_caps, default_diff_caps, diff_to_bytes};
+use crate::style::parse_packed_style;
+use napi::bindgen_prelude::Uint8Array;
+use napi::JsObject;
+use napi_derive::napi;
+
+const RENDER_OPTS_KEYS: &[(&str, &str)] = &[
+    ("cols", "cols"),
+    ("rows", "rows"),
+    ("caps", "caps"),
+    ("limits", "limits"),
+    ("initialStyle", "initial_style"),
+    ("eraseBlankTails", "erase_blank_tails"),
+];
+
+/// Pipeline stage that rejected a drawlist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DrawlistStage {
+    Validate,
+    Preflight,
+    Execute,
+}
+
+impl DrawlistStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Validate => "validation",
+            Self::Preflight => "resource preflight",
+            Self::Execute => "execution",
+        }
+    }
+}
+
+/// Binding-owned `zr_fb_t`, released on drop.
+pub(crate) struct OwnedFb(pub(crate) ffi::zr_fb_t);
+
+impl OwnedFb {
+    pub(crate) fn new(cols: u32, rows: u32) -> Result<Self, i32> {
+        let mut raw: ffi::zr_fb_t = unsafe { std::mem::zeroed() };
+        let rc = unsafe { ffi::zr_fb_init(&mut raw as *mut _, cols, rows) };
+        if rc != ffi::ZR_OK {
+            return Err(rc);
+        }
+        Ok(Self(raw))
+    }
+}
+
+impl Drop for OwnedFb {
+    fn drop(&mut self) {
+        unsafe { ffi::zr_fb_release(&mut self.0 as *mut _) };
+    }
+}
+
+/// Binding-owned drawlist resource store (`DEF_STRING`/`DEF_BLOB` tables),
+/// released on drop.
+pub(crate) struct OwnedResources(pub(crate) ffi::zr_dl_resources_t);
+
+impl OwnedResources {
+    pub(crate) fn new() -> Self {
+        let mut raw: ffi::zr_dl_resources_t = unsafe { std::mem::zeroed() };
+        unsafe { ffi::zr_dl_resources_init(&mut raw) };
+        Self(raw)
+    }
+}
+
+impl Drop for OwnedResources {
+    fn drop(&mut self) {
+        unsafe { ffi::zr_dl_resources_release(&mut self.0) };
+    }
+}
+
+/// Validate and execute `bytes` into `fb` the way `engine_submit_drawlist`
+/// does, but with binding-owned resources and a neutral terminal profile.
+/// Returns the cursor state the drawlist requested.
+pub(crate) fn execute_drawlist(
+    bytes: &[u8],
+    fb: &mut OwnedFb,
+    limits: &ffi::zr_limits_t,
+) -> Result<ffi::zr_cursor_state_t, (DrawlistStage, i32)> {
+    let mut cursor = ffi::zr_cursor_state_t {
+        x: -1,
+        y: -1,
+        shape: 0,
+        visible: 0,
+        blink: 0,
+        reserved0: 0,
+    };
+    execute_drawlist_with(bytes, fb, limits, &mut OwnedResources::new(), &mut cursor)?;
+    Ok(cursor)
+}
+
+/// Run the engine's drawlist validation (framing, versions, `limits`)
+/// without executing anything.
+pub(crate) fn validate_drawlist(bytes: &[u8], limits: &ffi::zr_limits_t) -> i32 {
+    let mut view: ffi::zr_dl_view_t = unsafe { std::mem::zeroed() };
+    unsafe { ffi::zr_dl_validate(bytes.as_ptr(), bytes.len(), limits, &mut view) }
+}
+
+/// Index of the command [`validate_drawlist`] rejects `bytes` at, found by
+/// bisecting over command prefixes like [`describe_drawlist_error`]; `None`
+/// when the header is at fault.
+fn first_invalid_command(bytes: &[u8], limits: &ffi::zr_limits_t) -> Option<usize> {
+    let (count, spans) = walk_cmds(bytes)?;
+    let valid =
+        |k: usize| validate_drawlist(&drawlist_prefix(bytes, &spans, k), limits) == ffi::ZR_OK;
+    if !valid(0) {
+        return None;
+    }
+    if valid(spans.len()) {
+        return (spans.len() < count).then_some(spans.len());
+    }
+    let (mut lo, mut hi) = (0usize, spans.len());
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if valid(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(hi - 1)
+}
+
+/// [`execute_drawlist`] against a resource store that outlives the call, so
+/// strings and blobs defined by earlier drawlists stay visible. `resources`
+/// is only updated when the whole drawlist succeeds; `fb` may be partially
+/// written on failure. `cursor` carries the requested cursor state across
+/// drawlists, like the engine's desired cursor, and is also only updated on
+/// success.
+pub(crate) fn execute_drawlist_with(
+    bytes: &[u8],
+    fb: &mut OwnedFb,
+    limits: &ffi::zr_limits_t,
+    resources: &mut OwnedResources,
+    cursor: &mut ffi::zr_cursor_state_t,
+) -> Result<(), (DrawlistStage, i32)> {
+    let mut next_cursor = *cursor;
+    let mut view: ffi::zr_dl_view_t = unsafe { std::mem::zeroed() };
+    let rc = unsafe { ffi::zr_dl_validate(bytes.as_ptr(), bytes.len(), limits, &mut view) };
+    if rc != ffi::ZR_OK {
+        return Err((DrawlistStage::Validate, rc));
+    }
+
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let profile: ffi::zr_terminal_profile_t = unsafe { std::mem::zeroed() };
+    let mut blit_caps: ffi::zr_blit_caps_t = unsafe { std::mem::zeroed() };
+    let mut image_stage: ffi::zr_image_frame_t = unsafe { std::mem::zeroed() };
+    let mut preflight = OwnedResources::new();
+    let mut stage = OwnedResources::new();
+    unsafe {
+        ffi::zr_blit_caps_from_profile(&profile, &mut blit_caps);
+        ffi::zr_image_frame_init(&mut image_stage);
+    }
+
+    /* Mirror the engine: preflight sees a shallow view of the live store,
+     * execution mutates a deep copy that replaces it only on success. */
+    let mut failed_stage = DrawlistStage::Preflight;
+    let mut rc = unsafe { ffi::zr_dl_resources_clone(&mut stage.0, &resources.0) };
+    if rc == ffi::ZR_OK {
+        rc = unsafe { ffi::zr_dl_resources_clone_shallow(&mut preflight.0, &resources.0) };
+    }
+    if rc == ffi::ZR_OK {
+        rc = unsafe {
+            ffi::zr_dl_preflight_resources(
+                &view,
+                &mut fb.0,
+                &mut image_stage,
+                limits,
+                &profile,
+                &mut preflight.0,
+            )
+        };
+    }
+    if rc == ffi::ZR_OK {
+        failed_stage = DrawlistStage::Execute;
+        rc = unsafe {
+            ffi::zr_dl_execute(
+                &view,
+                &mut fb.0,
+                limits,
+                cfg.tab_width,
+                cfg.width_policy,
+                &blit_caps,
+                &profile,
+                &mut image_stage,
+                &mut stage.0,
+                &mut next_cursor,
+            )
+        };
+    }
+    unsafe { ffi::zr_image_frame_release(&mut image_stage) };
+    if rc != ffi::ZR_OK {
+        return Err((failed_stage, rc));
+    }
+    unsafe { ffi::zr_dl_resources_swap(&mut resources.0, &mut stage.0) };
+    *cursor = next_cursor;
+    Ok(())
+}
+
+/// Describe why `bytes` was rejected, naming the first offending command.
+///
+/// The vendored validator only returns a result code, so the offending index
+/// is found by bisecting over self-consistent command prefixes: the first
+/// prefix that fails ends with the culprit. When every well-framed prefix
+/// passes, the culprit is the first command whose framing is broken, or the
+/// header itself.
+pub(crate) fn describe_drawlist_error(
+    bytes: &[u8],
+    cols: u32,
+    rows: u32,
+    limits: &ffi::zr_limits_t,
+    stage: DrawlistStage,
+    rc: i32,
+    ctx: &str,
+) -> String {
+    let header_error = || {
+        format!(
+            "{ctx}: drawlist rejected during {}: {}",
+            stage.as_str(),
+            ZrCode(rc).name()
+        )
+    };
+    let Some((count, spans)) = walk_cmds(bytes) else {
+        return header_error();
+    };
+    let run = |k: usize| match OwnedFb::new(cols, rows) {
+        Ok(mut fb) => execute_drawlist(&drawlist_prefix(bytes, &spans, k), &mut fb, limits),
+        Err(rc) => Err((stage, rc)),
+    };
+    if run(0).is_err() {
+        return header_error();
+    }
+
+    /* Prefix `lo` always passes; once a failure is seen, prefix `hi` fails. */
+    let (mut lo, mut hi) = (0usize, spans.len());
+    let mut failure = None;
+    if let Err(e) = run(hi) {
+        failure = Some(e);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            match run(mid) {
+                Ok(_) => lo = mid,
+                Err(e) => {
+                    hi = mid;
+                    failure = Some(e);
+                }
+            }
+        }
+    }
+
+    match failure {
+        Some((stage, rc)) => {
+            let opcode = spans[hi - 1].0;
+            format!(
+                "{ctx}: command {} ({}) rejected during {}: {}",
+                hi - 1,
+                opcode_label(opcode),
+                stage.as_str(),
+                ZrCode(rc).name()
+            )
+        }
+        None if spans.len() < count => format!(
+            "{ctx}: command {} has invalid framing (size or bounds): {}",
+            spans.len(),
+            ZrCode(rc).name()
+        ),
+        None => header_error(),
+    }
+}
+
+fn opcode_label(opcode: u16) -> String {
+    match dl_opcode_name(opcode) {
+        Some(name) => name.to_string(),
+        None => format!("opcode {opcode}"),
+    }
+}
+
+/// Outcome of `validateDrawlist`.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct DrawlistValidation {
+    pub ok: bool,
+    /// The `EngineLimits` field exceeded (`"dlMaxClipDepth"`, ...), or the
+    /// result code name for any other rejection (`"ZR_ERR_FORMAT"`, ...).
+    pub violation: Option<String>,
+    /// Index of the offending command, when one is to blame.
+    pub commandIndex: Option<u32>,
+    /// What went wrong, for logs; `"ok"` when valid.
+    pub detail: String,
+}
+
+/// Check a drawlist against `limits` (defaults when omitted) the way
+/// `engineSubmitDrawlist` would, without an engine, and say which limit or
+/// command is at fault instead of only `ZR_ERR_LIMIT`. Strings and blobs
+/// defined by earlier submits are not known here.
+#[napi(js_name = "validateDrawlist")]
+pub fn validate_drawlist_js(
+    drawlist: Uint8Array,
+    limits: Option<JsObject>,
+) -> napi::Result<DrawlistValidation, ZrCode> {
+    const CTX: &str = "validateDrawlist";
+    let mut lim = unsafe { ffi::zr_engine_config_default() }.limits;
+    if let Some(obj) = limits {
+        validate_known_keys(&obj, LIMITS_KEYS, &format!("{CTX} limits"))?;
+        apply_limits(&mut lim, &obj)
+            .map_err(|_| invalid_argument(format!("{CTX}: invalid limits value")))?;
+        check_limits(&lim, CTX)?;
+    }
+
+    let rc = validate_drawlist(&drawlist, &lim);
+    if rc == ffi::ZR_OK || rc == ffi::ZR_ERR_LIMIT {
+        if let Some(v) = find_limit_violation(&drawlist, &lim) {
+            return Ok(DrawlistValidation {
+                ok: false,
+                violation: Some(v.limit.to_string()),
+                commandIndex: v.command_index,
+                detail: v.detail,
+            });
+        }
+    }
+    if rc == ffi::ZR_OK {
+        return Ok(DrawlistValidation {
+            ok: true,
+            violation: None,
+            commandIndex: None,
+            detail: "ok".to_string(),
+        });
+    }
+
+    let index = first_invalid_command(&drawlist, &lim);
+    let name = ZrCode(rc).name();
+    let opcode = index.and_then(|i| Some(walk_cmds(&drawlist)?.1.get(i)?.0));
+    let detail = match (index, opcode) {
+        (Some(i), Some(opcode)) => {
+            format!("command {i} ({}) rejected: {name}", opcode_label(opcode))
+        }
+        (Some(i), None) => format!("command {i} has invalid framing (size or bounds): {name}"),
+        (None, _) => format!("drawlist header rejected: {name}"),
+    };
+    Ok(DrawlistValidation {
+        ok: false,
+        violation: Some(name.to_owned()),
+        commandIndex: index.map(|i| i as u32),
+        detail,
+    })
+}
+
+/// Parse a drawlist into a fresh framebuffer and return the bytes that paint
+/// it over a blank screen, without an engine or a TTY. Malformed drawlists
+/// throw with the index of the offending command. `initialStyle` declares the
+/// screen already blank in that SGR state, so the output carries only deltas
+/// instead of a clear and a full reset.
+#[napi(js_name = "renderDrawlistToBytes")]
+pub fn render_drawlist_to_bytes(
+    drawlist: Uint8Array,
+    opts: JsObject,
+) -> napi::Result<Uint8Array, ZrCode> {
+    const CTX: &str = "renderDrawlistToBytes";
+    validate_known_keys(&opts, RENDER_OPTS_KEYS, &format!("{CTX} opts"))?;
+    let invalid = |what: &str| invalid_argument(format!("{CTX}: invalid {what}"));
+
+    let cols = js_u32(&opts, "cols", "cols").map_err(|_| invalid("cols"))?;
+    let rows = js_u32(&opts, "rows", "rows").map_err(|_| invalid("rows"))?;
+    let (Some(cols), Some(rows)) = (cols, rows) else {
+        return Err(invalid_argument(format!(
+            "{CTX}: opts.cols and opts.rows are required"
+        )));
+    };
+
+    let mut caps = default_diff_caps();
+    if let Some(obj) = js_obj(&opts, "caps", "caps").map_err(|_| invalid("caps"))? {
+        apply_diff_caps(&mut caps, &obj, CTX)?;
+    }
+    let mut limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    if let Some(obj) = js_obj(&opts, "limits", "limits").map_err(|_| invalid("limits"))? {
+        validate_known_keys(&obj, LIMITS_KEYS, &format!("{CTX} opts.limits"))?;
+        apply_limits(&mut limits, &obj).map_err(|_| invalid("limits value"))?;
+        check_limits(&limits, CTX)?;
+    }
+    let initial_style = match js_obj(&opts, "initialStyle", "initial_style")
+        .map_err(|_| invalid("initialStyle"))?
+    {
+        Some(obj) => Some(parse_packed_style(
+            &obj,
+            &format!("{CTX} opts.initialStyle"),
+        )?),
+        None => None,
+    };
+
+    let erase_blank_tails = js_u8_bool(&opts, "eraseBlankTails", "erase_blank_tails")
+        .map_err(|_| invalid("eraseBlankTails"))?
+        == Some(1);
+
+    let size_error = |rc| {
+        invalid_argument(format!(
+            "{CTX}: cannot allocate a {cols}x{rows} framebuffer: {}",
+            ZrCode(rc).name()
+        ))
+    };
+    let blank = OwnedFb::new(cols, rows).map_err(size_error)?;
+    let mut next = OwnedFb::new(cols, rows).map_err(size_error)?;
+    let cursor = execute_drawlist(&drawlist, &mut next, &limits).map_err(|(stage, rc)| {
+        let msg = describe_drawlist_error(&drawlist, cols, rows, &limits, stage, rc, CTX);
+        invalid_argument(msg)
+    })?;
+
+    let out = diff_to_bytes(
+        &blank.0,
+        &next.0,
+        &caps,
+        &limits,
+        &cursor,
+        initial_style.as_ref(),
+        erase_blank_tails,
+    )
+    .map_err(|rc| zr_result_error(rc, CTX))?;
+    Ok(Uint8Array::new(out))
+}
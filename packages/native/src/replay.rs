@@ -0,0 +1,61 @@
+use crate::error::{zr_error, ZrCode};
+use crate::render::validate_drawlist;
+use crate::report::{last_frame_report, FrameReport};
+use crate::{ffi, owner_engine_guard, present_slot, submit_slot};
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+
+/// Submit and present each of `frames` in order, as recorded drawlists
+/// (`drawlist_cmd` trace payloads or saved `engineSubmitDrawlist` input), and
+/// return the `engineLastFrameReport` of every frame. All frames are
+/// validated against the engine's limits before the first is submitted; a
+/// malformed one throws naming its index and nothing is replayed. A frame the
+/// engine rejects at submit or present throws the same way, after the frames
+/// before it were shown.
+#[napi(js_name = "engineDebugReplay")]
+pub fn engine_debug_replay(
+    engine_id: u32,
+    frames: Vec<Uint8Array>,
+) -> napi::Result<Vec<FrameReport>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineDebugReplay")?;
+
+    let limits = guard.slot.runtime_cfg().limits;
+    for (i, frame) in frames.iter().enumerate() {
+        let rc = validate_drawlist(frame, &limits);
+        if rc != ffi::ZR_OK {
+            return Err(zr_error(
+                rc,
+                format!(
+                    "engineDebugReplay: frame {i} is not a valid drawlist: {}",
+                    ZrCode(rc).name()
+                ),
+            ));
+        }
+    }
+
+    let mut reports = Vec::with_capacity(frames.len());
+    for (i, frame) in frames.iter().enumerate() {
+        let rc = submit_slot(&guard.slot, frame, "engineDebugReplay");
+        if rc != ffi::ZR_OK {
+            return Err(zr_error(
+                rc,
+                format!(
+                    "engineDebugReplay: frame {i} rejected at submit: {}",
+                    ZrCode(rc).name()
+                ),
+            ));
+        }
+        let rc = present_slot(&guard.slot, "engineDebugReplay");
+        if rc != ffi::ZR_OK {
+            return Err(zr_error(
+                rc,
+                format!(
+                    "engineDebugReplay: frame {i} failed to present: {}",
+                    ZrCode(rc).name()
+                ),
+            ));
+        }
+        reports.push(last_frame_report(guard.slot.engine, "engineDebugReplay")?);
+    }
+    Ok(reports)
+}
@@ -0,0 +1,165 @@
+use crate::debug::query_debug_headers;
+use crate::error::{zr_result_error, ZrCode};
+use crate::{bigint_from_u64, empty_metrics, ffi, owner_engine_guard};
+use napi::bindgen_prelude::BigInt;
+use napi_derive::napi;
+
+/* zr_diff_telemetry_record_t (zr_engine_present.inc): six u64 totals, four u8
+ * path flags, u32 collision_guard_hits_last, u32 pad. */
+const DIFF_TELEMETRY_BYTES: usize = 64;
+const DIFF_TELEMETRY_COLLISION_TOTAL: usize = 40;
+const DIFF_TELEMETRY_PATH_FLAGS: usize = 48;
+const DIFF_TELEMETRY_COLLISION_LAST: usize = 52;
+
+/// How the differ produced the last frame. Only available while debug tracing
+/// records the `perf` category.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct FrameDiffPath {
+    /// Full line sweep was used.
+    pub sweepUsed: bool,
+    /// Damage-rect path was used.
+    pub damageUsed: bool,
+    pub scrollOptAttempted: bool,
+    pub scrollOptHit: bool,
+    /// Hash-collision guard hits in this frame.
+    pub collisionGuardHits: u32,
+    pub collisionGuardHitsTotal: BigInt,
+}
+
+/// Everything known about the last presented frame, gathered in one call.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct FrameReport {
+    pub frameIndex: BigInt,
+    pub usInput: u32,
+    pub usDrawlist: u32,
+    pub usDiff: u32,
+    pub usWrite: u32,
+    pub bytesEmitted: u32,
+    pub dirtyLines: u32,
+    pub dirtyCols: u32,
+    pub damageRects: u32,
+    pub damageCells: u32,
+    pub damageFullFrame: bool,
+    /// `bytesEmitted / damageCells`, the diff-efficiency headline; `0` when
+    /// no cell changed.
+    pub bytesPerDamagedCell: f64,
+    pub arenaFrameHighWaterBytes: BigInt,
+    pub arenaPersistentHighWaterBytes: BigInt,
+    /// `null` unless debug tracing captured the frame's diff telemetry.
+    pub diffPath: Option<FrameDiffPath>,
+}
+
+fn read_u32(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([bytes[off], bytes[off + 1], bytes[off + 2], bytes[off + 3]])
+}
+
+fn read_u64(bytes: &[u8], off: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&bytes[off..off + 8]);
+    u64::from_le_bytes(b)
+}
+
+/// Output bytes per changed cell; `0` for a frame that changed none.
+pub(crate) fn bytes_per_damaged_cell(bytes: u32, cells: u32) -> f64 {
+    if cells == 0 {
+        return 0.0;
+    }
+    f64::from(bytes) / f64::from(cells)
+}
+
+/// Decode a `ZR_DEBUG_CODE_PERF_DIFF_PATH` payload.
+pub(crate) fn parse_diff_telemetry(payload: &[u8]) -> Option<FrameDiffPath> {
+    if payload.len() < DIFF_TELEMETRY_COLLISION_LAST + 4 {
+        return None;
+    }
+    let flags = &payload[DIFF_TELEMETRY_PATH_FLAGS..DIFF_TELEMETRY_PATH_FLAGS + 4];
+    Some(FrameDiffPath {
+        sweepUsed: flags[0] != 0,
+        damageUsed: flags[1] != 0,
+        scrollOptAttempted: flags[2] != 0,
+        scrollOptHit: flags[3] != 0,
+        collisionGuardHits: read_u32(payload, DIFF_TELEMETRY_COLLISION_LAST),
+        collisionGuardHitsTotal: bigint_from_u64(read_u64(payload, DIFF_TELEMETRY_COLLISION_TOTAL)),
+    })
+}
+
+/// Latest diff telemetry traced for `frame_id`, if the debug ring holds one.
+fn traced_diff_path(engine: *mut ffi::zr_engine_t, frame_id: u64) -> Option<FrameDiffPath> {
+    if frame_id == 0 {
+        return None;
+    }
+    let query = ffi::zr_debug_query_t {
+        min_record_id: 0,
+        max_record_id: 0,
+        min_frame_id: frame_id,
+        max_frame_id: frame_id,
+        category_mask: 1 << ffi::ZR_DEBUG_CAT_PERF,
+        min_severity: 0,
+        max_records: 0,
+        _pad0: 0,
+    };
+    let headers = query_debug_headers(engine, &query, "engineLastFrameReport").ok()?;
+    let header = headers
+        .iter()
+        .rev()
+        .find(|h| h.code == ffi::ZR_DEBUG_CODE_PERF_DIFF_PATH)?;
+
+    let mut payload = [0u8; DIFF_TELEMETRY_BYTES];
+    let mut size = 0u32;
+    let rc = unsafe {
+        ffi::engine_debug_get_payload(
+            engine,
+            header.record_id,
+            payload.as_mut_ptr(),
+            payload.len() as u32,
+            &mut size as *mut _,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return None;
+    }
+    parse_diff_telemetry(&payload[..(size as usize).min(payload.len())])
+}
+
+/// Snapshot of the last presented frame: timing, bytes, damage, arena
+/// high-water marks and, when traced, the diff path taken.
+#[napi(js_name = "engineLastFrameReport")]
+pub fn engine_last_frame_report(engine_id: u32) -> napi::Result<FrameReport, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineLastFrameReport")?;
+    last_frame_report(guard.slot.engine, "engineLastFrameReport")
+}
+
+/// `engineLastFrameReport` for an engine the caller already holds.
+pub(crate) fn last_frame_report(
+    engine: *mut ffi::zr_engine_t,
+    ctx: &str,
+) -> napi::Result<FrameReport, ZrCode> {
+    let mut m = empty_metrics();
+    let rc = unsafe { ffi::engine_get_metrics(engine, &mut m as *mut _) };
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, ctx));
+    }
+
+    Ok(FrameReport {
+        frameIndex: bigint_from_u64(m.frame_index),
+        usInput: m.us_input_last_frame,
+        usDrawlist: m.us_drawlist_last_frame,
+        usDiff: m.us_diff_last_frame,
+        usWrite: m.us_write_last_frame,
+        bytesEmitted: m.bytes_emitted_last_frame,
+        dirtyLines: m.dirty_lines_last_frame,
+        dirtyCols: m.dirty_cols_last_frame,
+        damageRects: m.damage_rects_last_frame,
+        damageCells: m.damage_cells_last_frame,
+        damageFullFrame: m.damage_full_frame != 0,
+        bytesPerDamagedCell: bytes_per_damaged_cell(
+            m.bytes_emitted_last_frame,
+            m.damage_cells_last_frame,
+        ),
+        arenaFrameHighWaterBytes: bigint_from_u64(m.arena_frame_high_water_bytes),
+        arenaPersistentHighWaterBytes: bigint_from_u64(m.arena_persistent_high_water_bytes),
+        diffPath: traced_diff_path(engine, m.frame_index),
+    })
+}
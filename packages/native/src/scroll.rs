@@ -0,0 +1,121 @@
+use crate::error::{zr_error, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::framebuffer::{check_rect, rect_fits, CellRect};
+use crate::owner_engine_guard;
+use crate::state::submit_edit;
+use crate::style::parse_packed_style;
+use napi::JsObject;
+use napi_derive::napi;
+use std::time::Instant;
+
+/// Rows of a rect to shift: positive `dy` moves content down, negative up.
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScrollRegion {
+    pub rect: CellRect,
+    pub dy: i32,
+}
+
+pub(crate) fn space(style: ffi::zr_style_t) -> ffi::zr_cell_t {
+    let mut glyph = [0u8; ffi::ZR_CELL_GLYPH_MAX];
+    glyph[0] = b' ';
+    ffi::zr_cell_t {
+        glyph,
+        glyph_len: 1,
+        width: 1,
+        _pad0: 0,
+        style,
+    }
+}
+
+/// Turn wide-glyph halves separated by a rect edge into spaces, keeping
+/// their style, so the row holds only whole lead/continuation pairs.
+pub(crate) fn repair_wide_pairs(row: &mut [ffi::zr_cell_t]) {
+    let mut x = 0;
+    while x < row.len() {
+        match row[x].width {
+            0 => row[x] = space(row[x].style),
+            2 if row.get(x + 1).is_some_and(|c| c.width == 0) => x += 1,
+            2 => row[x] = space(row[x].style),
+            _ => {}
+        }
+        x += 1;
+    }
+}
+
+/// Shift the rows of `rect` (already checked to lie inside `fb`) by `dy`
+/// and fill the vacated rows with spaces in `fill`. Cells outside the rect
+/// are left alone, except that wide glyphs cut by its side edges become
+/// spaces.
+pub(crate) fn scroll_fb(fb: &mut ffi::zr_fb_t, rect: CellRect, dy: i32, fill: ffi::zr_style_t) {
+    if dy == 0 || rect.w == 0 || rect.h == 0 || fb.cells.is_null() {
+        return;
+    }
+    let cols = fb.cols as usize;
+    let cells = unsafe { std::slice::from_raw_parts_mut(fb.cells, cols * fb.rows as usize) };
+    let (x0, y0) = (rect.x as usize, rect.y as usize);
+    let (w, h) = (rect.w as usize, rect.h as usize);
+    let shift = (dy.unsigned_abs() as usize).min(h);
+    let row = |y: usize| (y0 + y) * cols + x0;
+    if dy < 0 {
+        for y in 0..h - shift {
+            cells.copy_within(row(y + shift)..row(y + shift) + w, row(y));
+        }
+        for y in h - shift..h {
+            cells[row(y)..row(y) + w].fill(space(fill));
+        }
+    } else {
+        for y in (shift..h).rev() {
+            cells.copy_within(row(y - shift)..row(y - shift) + w, row(y));
+        }
+        for y in 0..shift {
+            cells[row(y)..row(y) + w].fill(space(fill));
+        }
+    }
+    if w < cols {
+        for y in y0..y0 + h {
+            repair_wide_pairs(&mut cells[y * cols..(y + 1) * cols]);
+        }
+    }
+}
+
+/// Shift the rows of `region.rect` in the frame built so far by
+/// `region.dy` and fill the vacated rows with spaces in `style` (default all
+/// zeros). When the rect spans the full width and the terminal supports
+/// scroll regions, the next present can move the rows with a hardware
+/// scroll instead of repainting them. Needs the same frame tracking as
+/// `engineSaveState`.
+#[napi(js_name = "engineScrollRegion")]
+pub fn engine_scroll_region(
+    engine_id: u32,
+    region: ScrollRegion,
+    style: Option<JsObject>,
+) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineScrollRegion")?;
+    let fill = match style {
+        Some(obj) => parse_packed_style(&obj, "engineScrollRegion style")?,
+        None => unsafe { std::mem::zeroed() },
+    };
+    let slot = &guard.slot;
+    let (cols, rows) = slot.shadow.tracked_size().map_err(|msg| {
+        zr_error(
+            ffi::ZR_ERR_UNSUPPORTED,
+            format!("engineScrollRegion: {msg}"),
+        )
+    })?;
+    check_rect(region.rect, cols, rows, "engineScrollRegion")?;
+
+    slot.idle.note_activity(Instant::now());
+    let rc = submit_edit(slot, &|fb, _cursor| {
+        if !rect_fits(region.rect, fb.cols, fb.rows) {
+            return false;
+        }
+        scroll_fb(fb, region.rect, region.dy, fill);
+        true
+    });
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineScrollRegion"));
+    }
+    slot.shadow.note_scroll(region.rect, region.dy, fill);
+    Ok(())
+}
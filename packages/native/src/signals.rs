@@ -0,0 +1,389 @@
+use crate::error::{invalid_argument, zr_error, zr_result_to_napi, ZrCode};
+use crate::ffi;
+use crate::owner_engine_guard;
+use napi_derive::napi;
+
+/* What zr_posix_emit_leave_sequences writes, pre-formatted so the handler
+ * never allocates: SGR reset, mouse/focus/paste off, autowrap on, cursor
+ * shown and, in alt-screen mode only, back to the main screen. */
+pub(crate) const RESTORE_MODES: &[u8] =
+    b"\x1b[0m\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l\x1b[?1004l\x1b[?2004l\x1b[?7h\x1b[?25h";
+pub(crate) const LEAVE_ALT_SCREEN: &[u8] = b"\x1b[?1049l";
+
+#[cfg(unix)]
+mod imp {
+    use crate::output::renders_to_tty;
+    use std::cell::UnsafeCell;
+    use std::ffi::{c_int, c_void};
+    use std::mem::MaybeUninit;
+    use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
+    /// Most engines that can have the handlers armed at once.
+    const ARMED_MAX: usize = 16;
+
+    /// Engines whose terminal the handlers restore (0 = free slot), with
+    /// whether each renders inline. `ARMED_INLINE[i]` is written before
+    /// `ARMED[i]` claims the slot.
+    static ARMED: [AtomicU32; ARMED_MAX] = [const { AtomicU32::new(0) }; ARMED_MAX];
+    static ARMED_INLINE: [AtomicBool; ARMED_MAX] = [const { AtomicBool::new(false) }; ARMED_MAX];
+    /// Serializes `install` and `disarm`; never taken by the handler.
+    static ARMING: Mutex<()> = Mutex::new(());
+    /// Terminal the restore bytes go to; -1 until the first install.
+    static OUT_FD: AtomicI32 = AtomicI32::new(-1);
+    /// Input mode before the first engine switched the terminal to raw.
+    static COOKED_TERMIOS: OnceLock<libc::termios> = OnceLock::new();
+
+    const HANDLED: [c_int; 3] = [libc::SIGTERM, libc::SIGINT, libc::SIGHUP];
+
+    /// Actions the handlers replaced, one per `HANDLED` signal. `saved` is
+    /// set after `action` is written and cleared by whoever puts it back.
+    struct Previous {
+        saved: [AtomicBool; 3],
+        action: [UnsafeCell<MaybeUninit<libc::sigaction>>; 3],
+    }
+
+    unsafe impl Sync for Previous {}
+
+    static PREVIOUS: Previous = Previous {
+        saved: [const { AtomicBool::new(false) }; 3],
+        action: [const { UnsafeCell::new(MaybeUninit::uninit()) }; 3],
+    };
+
+    fn handled_index(sig: c_int) -> Option<usize> {
+        HANDLED.iter().position(|&s| s == sig)
+    }
+
+    /// The action `install` replaced for `sig`, while it is saved.
+    fn saved_previous(sig: c_int) -> Option<libc::sigaction> {
+        let i = handled_index(sig)?;
+        if !PREVIOUS.saved[i].load(Ordering::Acquire) {
+            return None;
+        }
+        Some(unsafe { (*PREVIOUS.action[i].get()).assume_init() })
+    }
+
+    /// Run the handler function of `prev` as the kernel would have.
+    unsafe fn chain(
+        prev: &libc::sigaction,
+        sig: c_int,
+        info: *mut libc::siginfo_t,
+        ctx: *mut c_void,
+    ) {
+        if prev.sa_flags & libc::SA_SIGINFO != 0 {
+            let handler =
+                std::mem::transmute::<libc::sighandler_t, SigInfoHandler>(prev.sa_sigaction);
+            handler(sig, info, ctx);
+        } else {
+            let handler =
+                std::mem::transmute::<libc::sighandler_t, extern "C" fn(c_int)>(prev.sa_sigaction);
+            handler(sig);
+        }
+    }
+
+    /// Put back the action `install` replaced for `sig`. Async-signal-safe.
+    fn restore_previous(sig: c_int) -> bool {
+        let Some(i) = handled_index(sig) else {
+            return false;
+        };
+        if !PREVIOUS.saved[i].swap(false, Ordering::AcqRel) {
+            return false;
+        }
+        unsafe {
+            libc::sigaction(
+                sig,
+                (*PREVIOUS.action[i].get()).as_ptr(),
+                std::ptr::null_mut(),
+            )
+        };
+        true
+    }
+
+    pub(crate) fn signal_number(name: &str) -> Option<c_int> {
+        match name {
+            "SIGTERM" => Some(libc::SIGTERM),
+            "SIGINT" => Some(libc::SIGINT),
+            "SIGHUP" => Some(libc::SIGHUP),
+            _ => None,
+        }
+    }
+
+    /// Remember stdin's termios before an engine makes it raw, so a handler
+    /// can put it back. Only the first capture is kept.
+    pub(crate) fn capture_cooked_termios() {
+        if COOKED_TERMIOS.get().is_some() || unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+            return;
+        }
+        let mut t: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut t) } == 0 {
+            let _ = COOKED_TERMIOS.set(t);
+        }
+    }
+
+    /// Same stream choice as `output::process_writer`, resolved up front
+    /// because `open` from the handler would race teardown.
+    fn resolve_out_fd() -> c_int {
+        let fd = OUT_FD.load(Ordering::Acquire);
+        if fd >= 0 {
+            return fd;
+        }
+        let stdio = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1 || !renders_to_tty();
+        let fd = if stdio {
+            libc::STDOUT_FILENO
+        } else {
+            /* Lives as long as the handlers: never closed. */
+            let tty = unsafe { libc::open(c"/dev/tty".as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC) };
+            if tty < 0 {
+                libc::STDOUT_FILENO
+            } else {
+                tty
+            }
+        };
+        match OUT_FD.compare_exchange(-1, fd, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => fd,
+            Err(existing) => {
+                if fd != libc::STDOUT_FILENO {
+                    unsafe { libc::close(fd) };
+                }
+                existing
+            }
+        }
+    }
+
+    fn write_all(fd: c_int, bytes: &[u8]) {
+        let mut off = 0;
+        while fd >= 0 && off < bytes.len() {
+            let rest = &bytes[off..];
+            let n = unsafe { libc::write(fd, rest.as_ptr().cast(), rest.len()) };
+            if n > 0 {
+                off += n as usize;
+            } else if n < 0 && std::io::Error::last_os_error().raw_os_error() == Some(libc::EINTR) {
+                continue;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Put the cooked input mode back, outside any handler.
+    pub(crate) fn restore_cooked_termios() {
+        if let Some(t) = COOKED_TERMIOS.get() {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, t) };
+        }
+    }
+
+    /// Async-signal-safe: atomics, `write`, `tcsetattr`, `sigaction`, `raise`.
+    /// A handler the app (or Node) had installed runs instead, with the
+    /// terminal left alone since the process may carry on; that handler
+    /// restores it by destroying the engines. An ignored signal stays
+    /// ignored. Only the default action, which kills the process, is
+    /// preceded by the restore.
+    extern "C" fn restore_and_reraise(sig: c_int, info: *mut libc::siginfo_t, ctx: *mut c_void) {
+        if let Some(prev) = saved_previous(sig) {
+            match prev.sa_sigaction {
+                libc::SIG_DFL => {}
+                libc::SIG_IGN => return,
+                _ => {
+                    unsafe { chain(&prev, sig, info, ctx) };
+                    return;
+                }
+            }
+        }
+        /* All armed engines share the one terminal: restore it once, and
+         * leave the alt screen if any of them entered it. */
+        let mut armed = false;
+        let mut alt_screen = false;
+        for (slot, inline) in ARMED.iter().zip(&ARMED_INLINE) {
+            if slot.swap(0, Ordering::AcqRel) != 0 {
+                armed = true;
+                alt_screen |= !inline.load(Ordering::Acquire);
+            }
+        }
+        if armed {
+            let fd = OUT_FD.load(Ordering::Acquire);
+            write_all(fd, super::RESTORE_MODES);
+            if alt_screen {
+                write_all(fd, super::LEAVE_ALT_SCREEN);
+            }
+            if let Some(t) = COOKED_TERMIOS.get() {
+                unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, t) };
+            }
+        }
+        unsafe {
+            if !restore_previous(sig) {
+                let mut dfl: libc::sigaction = std::mem::zeroed();
+                dfl.sa_sigaction = libc::SIG_DFL;
+                libc::sigemptyset(&mut dfl.sa_mask);
+                libc::sigaction(sig, &dfl, std::ptr::null_mut());
+            }
+            /* Blocked while the handler runs; fires with the restored action
+             * as soon as it returns. */
+            libc::raise(sig);
+        }
+    }
+
+    type SigInfoHandler = extern "C" fn(c_int, *mut libc::siginfo_t, *mut c_void);
+
+    fn our_handler() -> libc::sighandler_t {
+        restore_and_reraise as SigInfoHandler as libc::sighandler_t
+    }
+
+    fn is_ours(action: &libc::sigaction) -> bool {
+        action.sa_sigaction == our_handler()
+    }
+
+    /// Whether the handlers still restore `engine_id`'s terminal.
+    #[cfg(test)]
+    pub(crate) fn is_armed(engine_id: u32) -> bool {
+        ARMED
+            .iter()
+            .any(|slot| slot.load(Ordering::Acquire) == engine_id)
+    }
+
+    /// The terminal the restore bytes go to, once an install resolved it.
+    #[cfg(test)]
+    pub(crate) fn out_fd() -> c_int {
+        OUT_FD.load(Ordering::Acquire)
+    }
+
+    /// The action currently installed for `sig`.
+    #[cfg(test)]
+    pub(crate) fn current_action(sig: c_int) -> libc::sighandler_t {
+        let mut cur: libc::sigaction = unsafe { std::mem::zeroed() };
+        unsafe { libc::sigaction(sig, std::ptr::null(), &mut cur) };
+        cur.sa_sigaction
+    }
+
+    /// Arm the handlers for `engine_id` alongside any engine already armed.
+    /// Fails with `ZR_ERR_LIMIT` once `ARMED_MAX` engines are armed. The
+    /// slot is claimed only once every handler is in; a failed install
+    /// leaves the dispositions it found.
+    pub(crate) fn install(engine_id: u32, inline_screen: bool, signals: &[c_int]) -> i32 {
+        let _arming = ARMING.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(indices) = signals
+            .iter()
+            .map(|&sig| handled_index(sig))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return super::ffi::ZR_ERR_INVALID_ARGUMENT;
+        };
+        let slot = ARMED
+            .iter()
+            .position(|slot| slot.load(Ordering::Acquire) == engine_id)
+            .or_else(|| {
+                ARMED
+                    .iter()
+                    .position(|slot| slot.load(Ordering::Acquire) == 0)
+            });
+        let Some(slot) = slot else {
+            return super::ffi::ZR_ERR_LIMIT;
+        };
+        let _ = resolve_out_fd();
+        let mut saved_here = Vec::with_capacity(signals.len());
+        for (&sig, i) in signals.iter().zip(indices) {
+            let mut old: libc::sigaction = unsafe { std::mem::zeroed() };
+            let rc = unsafe {
+                let mut sa: libc::sigaction = std::mem::zeroed();
+                sa.sa_sigaction = our_handler();
+                sa.sa_flags = libc::SA_SIGINFO;
+                libc::sigemptyset(&mut sa.sa_mask);
+                libc::sigaction(sig, &sa, &mut old)
+            };
+            if rc != 0 {
+                /* Put back what this call replaced; the slot is not
+                 * claimed yet, so earlier engines stay as they were. */
+                for sig in saved_here {
+                    restore_previous(sig);
+                }
+                return super::ffi::ZR_ERR_PLATFORM;
+            }
+            /* A second install replaces our own handler: keep the first
+             * saved action. */
+            if !is_ours(&old) {
+                unsafe { (*PREVIOUS.action[i].get()).write(old) };
+                PREVIOUS.saved[i].store(true, Ordering::Release);
+                saved_here.push(sig);
+            }
+        }
+        ARMED_INLINE[slot].store(inline_screen, Ordering::Release);
+        ARMED[slot].store(engine_id, Ordering::Release);
+        super::ffi::ZR_OK
+    }
+
+    /// The engine restored its terminal itself; a later signal must not
+    /// write restore bytes over whatever runs next. Once no engine is left
+    /// armed, the handlers the install replaced go back in place.
+    pub(crate) fn disarm(engine_id: u32) {
+        let _arming = ARMING.lock().unwrap_or_else(|e| e.into_inner());
+        let was_armed = ARMED.iter().any(|slot| {
+            slot.compare_exchange(engine_id, 0, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        });
+        if was_armed && ARMED.iter().all(|slot| slot.load(Ordering::Acquire) == 0) {
+            for sig in HANDLED {
+                restore_previous(sig);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    pub(crate) fn signal_number(name: &str) -> Option<i32> {
+        match name {
+            "SIGTERM" => Some(15),
+            "SIGINT" => Some(2),
+            "SIGHUP" => Some(1),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn capture_cooked_termios() {}
+
+    pub(crate) fn restore_cooked_termios() {}
+
+    pub(crate) fn install(_engine_id: u32, _inline_screen: bool, _signals: &[i32]) -> i32 {
+        super::ffi::ZR_ERR_UNSUPPORTED
+    }
+
+    pub(crate) fn disarm(_engine_id: u32) {}
+}
+
+pub(crate) use imp::{capture_cooked_termios, disarm, restore_cooked_termios, signal_number};
+#[cfg(all(unix, test))]
+pub(crate) use imp::{current_action, install, is_armed, out_fd};
+
+/// Restore the terminal when the process is killed by one of `signals`
+/// (`"SIGTERM"`, `"SIGINT"`, `"SIGHUP"`). A handler the app (or Node) had
+/// installed for the signal runs instead and the terminal is left alone,
+/// since the process may carry on; that handler restores it by destroying
+/// the engine. An ignored signal stays ignored. The restore only writes
+/// pre-formatted bytes and resets the input mode, so it is safe from any
+/// point of execution.
+#[napi(js_name = "engineInstallSignalHandlers")]
+pub fn engine_install_signal_handlers(
+    engine_id: u32,
+    signals: Vec<String>,
+) -> napi::Result<(), ZrCode> {
+    const CTX: &str = "engineInstallSignalHandlers";
+    let guard = owner_engine_guard(engine_id, CTX)?;
+    if guard.slot.output.target().is_bound() {
+        /* The handlers restore the process terminal, not the engine's fds. */
+        return Err(zr_error(
+            ffi::ZR_ERR_UNSUPPORTED,
+            format!("{CTX}: engines created with inputFd or outputFd are not supported"),
+        ));
+    }
+
+    let mut numbers = Vec::with_capacity(signals.len());
+    for name in &signals {
+        match signal_number(name) {
+            Some(sig) => numbers.push(sig),
+            None => return Err(invalid_argument(format!("{CTX}: unknown signal {name:?}"))),
+        }
+    }
+    zr_result_to_napi(
+        imp::install(engine_id, guard.slot.inline_screen, &numbers),
+        CTX,
+    )
+}
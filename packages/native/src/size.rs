@@ -0,0 +1,158 @@
+use crate::error::{invalid_argument, ZrCode};
+use crate::export::last_resize;
+use crate::{ffi, owner_engine_guard};
+use napi_derive::napi;
+use std::cell::Cell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Largest `enginePostResize` dimension; the same range `TIOCGWINSZ` reports.
+pub(crate) const POSTED_SIZE_MAX: u32 = u16::MAX as u32;
+
+/// Terminal dimensions in cells.
+#[napi(object)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// Size carried by the last resize an engine handed out, packed as
+/// `cols << 32 | rows`; zero until the first one is polled. `untaken` is set
+/// by every polled resize and cleared by `engineTakeResize`.
+#[derive(Default)]
+pub(crate) struct PolledSize {
+    packed: AtomicU64,
+    untaken: AtomicBool,
+}
+
+impl PolledSize {
+    /// Follow resizes in a batch filled by `engine_poll_events`.
+    pub(crate) fn note_poll(&self, rc: i32, out: &[u8]) {
+        if rc <= 0 {
+            return;
+        }
+        if let Some((cols, rows)) = last_resize(&out[..out.len().min(rc as usize)]) {
+            self.packed
+                .store(u64::from(cols) << 32 | u64::from(rows), Ordering::Relaxed);
+            self.untaken.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn get(&self) -> TerminalSize {
+        let packed = self.packed.load(Ordering::Relaxed);
+        TerminalSize {
+            cols: (packed >> 32) as u32,
+            rows: packed as u32,
+        }
+    }
+
+    /// Latest size polled since the previous call, if any.
+    pub(crate) fn take(&self) -> Option<TerminalSize> {
+        self.untaken
+            .swap(false, Ordering::Relaxed)
+            .then(|| self.get())
+    }
+}
+
+/// Size given to `enginePostResize`, packed as `cols << 32 | rows`; zero
+/// while the engine follows the real terminal size.
+#[derive(Default)]
+pub(crate) struct PostedSize {
+    packed: AtomicU64,
+}
+
+impl PostedSize {
+    pub(crate) fn set(&self, size: TerminalSize) {
+        self.packed.store(
+            u64::from(size.cols) << 32 | u64::from(size.rows),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub(crate) fn get(&self) -> Option<TerminalSize> {
+        let packed = self.packed.load(Ordering::Relaxed);
+        (packed != 0).then_some(TerminalSize {
+            cols: (packed >> 32) as u32,
+            rows: packed as u32,
+        })
+    }
+
+    /// Run `poll` with the engine's size queries answered by the posted size.
+    pub(crate) fn apply(&self, poll: impl FnOnce() -> i32) -> i32 {
+        let Some(size) = self.get() else {
+            return poll();
+        };
+        ACTIVE.with(|a| a.set(Some(size)));
+        let rc = poll();
+        ACTIVE.with(|a| a.set(None));
+        rc
+    }
+}
+
+thread_local! {
+    static ACTIVE: Cell<Option<TerminalSize>> = const { Cell::new(None) };
+}
+
+/// The engine's `plat_get_size` hook (see hooks.rs), so a posted size can
+/// stand in for the terminal's own. The engine asks for the size at create
+/// and on every poll that checks for a resize; polls of an engine with a
+/// posted size get that instead.
+pub(crate) extern "C" fn hook_plat_get_size(
+    plat: *mut c_void,
+    out_size: *mut ffi::plat_size_t,
+) -> ffi::ZrResultT {
+    match ACTIVE.with(Cell::get) {
+        Some(size) if !plat.is_null() && !out_size.is_null() => {
+            unsafe {
+                *out_size = ffi::plat_size_t {
+                    cols: size.cols,
+                    rows: size.rows,
+                }
+            };
+            ffi::ZR_OK
+        }
+        _ => unsafe { ffi::plat_get_size(plat, out_size) },
+    }
+}
+
+/// The `enginePostResize` arguments as a size, or why they are rejected.
+pub(crate) fn check_posted_size(cols: u32, rows: u32) -> Result<TerminalSize, String> {
+    let range = 1..=POSTED_SIZE_MAX;
+    if !range.contains(&cols) || !range.contains(&rows) {
+        return Err(format!(
+            "enginePostResize: cols and rows must be 1..={POSTED_SIZE_MAX} (got {cols}x{rows})"
+        ));
+    }
+    Ok(TerminalSize { cols, rows })
+}
+
+/// Treat the terminal as `cols` x `rows` from now on, for hosts whose size
+/// changes never reach the platform layer as SIGWINCH.
+#[napi(js_name = "enginePostResize")]
+pub fn engine_post_resize(engine_id: u32, cols: u32, rows: u32) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "enginePostResize")?;
+    let size = check_posted_size(cols, rows).map_err(invalid_argument)?;
+    guard.slot.posted_size.set(size);
+    Ok(())
+}
+
+/// Size of the area the engine renders into, as carried by the last
+/// `resize` event polled from it: the full screen, or the viewport rows in
+/// inline mode. The engine queues a resize at create, so the size is known
+/// after the first poll; before that both fields are 0.
+#[napi(js_name = "engineGetSize")]
+pub fn engine_get_size(engine_id: u32) -> napi::Result<TerminalSize, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineGetSize")?;
+    Ok(guard.slot.polled_size.get())
+}
+
+/// Latest size from a `resize` event polled since the previous call, or
+/// `null` when none was. Several resizes in between collapse into the last.
+/// The events stay in the batches they arrived in; this only saves callers
+/// from scanning those batches for resizes.
+#[napi(js_name = "engineTakeResize")]
+pub fn engine_take_resize(engine_id: u32) -> napi::Result<Option<TerminalSize>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineTakeResize")?;
+    Ok(guard.slot.polled_size.take())
+}
@@ -0,0 +1,279 @@
+use crate::error::{zr_result_to_napi, ZrCode};
+use crate::registry::get_engine_guard;
+use crate::{ffi, owner_engine_guard};
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SpanKind {
+    /// Cursor movement: CUP and relative moves, CR, LF, BS.
+    Cup,
+    /// SGR style changes.
+    Sgr,
+    /// Glyph bytes.
+    Text,
+    /// EL/ED erases.
+    El,
+    /// Scroll region (DECSTBM), SU/SD and IND/RI.
+    Scroll,
+    /// Anything else: mode switches, cursor shape, OSC 8, sync delimiters.
+    Other,
+}
+
+impl SpanKind {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Cup => "cup",
+            Self::Sgr => "sgr",
+            Self::Text => "text",
+            Self::El => "el",
+            Self::Scroll => "scroll",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) offset: u32,
+    pub(crate) len: u32,
+    pub(crate) kind: SpanKind,
+}
+
+/// One byte range of a presented frame and what it does.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct FrameSpan {
+    pub byteOffset: u32,
+    pub length: u32,
+    /// `"cup"`, `"sgr"`, `"text"`, `"el"`, `"scroll"` or `"other"`.
+    pub kind: String,
+}
+
+/// Bytes of the last presented frame and the spans that cover them.
+#[napi(object)]
+pub struct FrameSpans {
+    pub bytes: Uint8Array,
+    pub spans: Vec<FrameSpan>,
+}
+
+fn csi_kind(params: &[u8], final_byte: u8) -> SpanKind {
+    /* Private (`?25h`, `>4;2m`) and intermediate (` q`) forms are modes. */
+    let plain = params
+        .iter()
+        .all(|&b| b.is_ascii_digit() || b == b';' || b == b':');
+    match final_byte {
+        _ if !plain => SpanKind::Other,
+        b'H' | b'f' | b'G' | b'd' | b'A' | b'B' | b'C' | b'D' | b'E' | b'F' => SpanKind::Cup,
+        b'm' => SpanKind::Sgr,
+        b'K' | b'J' => SpanKind::El,
+        b'r' | b'S' | b'T' => SpanKind::Scroll,
+        _ => SpanKind::Other,
+    }
+}
+
+/// End of a string sequence (OSC, DCS, APC) starting at `from`: after BEL or
+/// ST, or the end of `bytes` when it is cut off.
+fn string_end(bytes: &[u8], from: usize) -> usize {
+    let mut i = from;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x07 => return i + 1,
+            0x1b if bytes.get(i + 1) == Some(&b'\\') => return i + 2,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Length and kind of the token at the start of `bytes`.
+fn next_token(bytes: &[u8]) -> (usize, SpanKind) {
+    match bytes[0] {
+        0x1b => match bytes.get(1) {
+            Some(b'[') => {
+                let body = &bytes[2..];
+                match body.iter().position(|b| (0x40..=0x7e).contains(b)) {
+                    Some(end) => (end + 3, csi_kind(&body[..end], body[end])),
+                    None => (bytes.len(), SpanKind::Other),
+                }
+            }
+            Some(b']' | b'P' | b'_' | b'^') => (string_end(bytes, 2), SpanKind::Other),
+            Some(b'M' | b'D') => (2, SpanKind::Scroll),
+            Some(_) => (2, SpanKind::Other),
+            None => (1, SpanKind::Other),
+        },
+        b'\r' | b'\n' | 0x08 => (1, SpanKind::Cup),
+        b if b < 0x20 || b == 0x7f => (1, SpanKind::Other),
+        _ => {
+            let len = bytes
+                .iter()
+                .position(|&b| b < 0x20 || b == 0x7f)
+                .unwrap_or(bytes.len());
+            (len, SpanKind::Text)
+        }
+    }
+}
+
+/// Split emitted terminal output into spans, merging neighbours of the same
+/// kind so a run of cursor moves or a styled line reads as one span.
+pub(crate) fn tokenize(bytes: &[u8]) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut at = 0usize;
+    while at < bytes.len() {
+        let (len, kind) = next_token(&bytes[at..]);
+        match spans.last_mut() {
+            Some(last) if last.kind == kind => last.len += len as u32,
+            _ => spans.push(Span {
+                offset: at as u32,
+                len: len as u32,
+                kind,
+            }),
+        }
+        at += len;
+    }
+    spans
+}
+
+struct CapturedFrame {
+    bytes: Vec<u8>,
+    spans: Vec<Span>,
+}
+
+/// Per-engine span recording. Off by default; nothing is captured or
+/// allocated until `engineSetFrameSpans` turns it on.
+#[derive(Default)]
+pub(crate) struct FrameSpanState {
+    enabled: AtomicBool,
+    last: Mutex<Option<CapturedFrame>>,
+}
+
+impl FrameSpanState {
+    fn lock(&self) -> MutexGuard<'_, Option<CapturedFrame>> {
+        match self.last.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+        if !enabled {
+            *self.lock() = None;
+        }
+    }
+
+    /// Run `present` and, when enabled, keep what it wrote to the terminal.
+    pub(crate) fn capture(&self, present: impl FnOnce() -> i32) -> i32 {
+        if !self.enabled() {
+            return present();
+        }
+        begin_capture();
+        let rc = present();
+        let bytes = end_capture();
+        if rc == ffi::ZR_OK {
+            let spans = tokenize(&bytes);
+            *self.lock() = Some(CapturedFrame { bytes, spans });
+        }
+        rc
+    }
+}
+
+thread_local! {
+    static CAPTURE: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+    static DIVERT: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+/// The engine's `plat_write_output` hook (see hooks.rs): every flush goes
+/// through here and can be copied while a present runs, diverted away from
+/// the terminal, and split up by `writeChunkBytes` (see chunks.rs).
+pub(crate) extern "C" fn hook_plat_write_output(
+    plat: *mut c_void,
+    bytes: *const u8,
+    len: i32,
+) -> i32 {
+    if bytes.is_null() || len <= 0 {
+        return unsafe { ffi::plat_write_output(plat, bytes, len) };
+    }
+    let out = unsafe { std::slice::from_raw_parts(bytes, len as usize) };
+    CAPTURE.with(|c| {
+        if let Some(buf) = c.borrow_mut().as_mut() {
+            buf.extend_from_slice(out);
+        }
+    });
+    let diverted = DIVERT.with(|d| match d.borrow_mut().as_mut() {
+        Some(buf) => {
+            buf.extend_from_slice(out);
+            true
+        }
+        None => false,
+    });
+    if diverted {
+        return ffi::ZR_OK;
+    }
+    crate::chunks::write_output(plat, out, |chunk| unsafe {
+        ffi::plat_write_output(plat, chunk.as_ptr(), chunk.len() as i32)
+    })
+}
+
+fn begin_capture() {
+    CAPTURE.with(|c| *c.borrow_mut() = Some(Vec::new()));
+}
+
+fn end_capture() -> Vec<u8> {
+    CAPTURE.with(|c| c.borrow_mut().take().unwrap_or_default())
+}
+
+/// Run `present` with its terminal output collected instead of written.
+pub(crate) fn divert_output(present: impl FnOnce() -> i32) -> (i32, Vec<u8>) {
+    DIVERT.with(|d| *d.borrow_mut() = Some(Vec::new()));
+    let rc = present();
+    let bytes = DIVERT.with(|d| d.borrow_mut().take().unwrap_or_default());
+    (rc, bytes)
+}
+
+/// Record the bytes of each present and split them into spans for
+/// `engineLastFrameSpans`. Disabling drops the last recording.
+#[napi(js_name = "engineSetFrameSpans")]
+pub fn engine_set_frame_spans(engine_id: u32, enabled: bool) -> napi::Result<(), ZrCode> {
+    zr_result_to_napi(set_frame_spans(engine_id, enabled), "engineSetFrameSpans")
+}
+
+fn set_frame_spans(engine_id: u32, enabled: bool) -> i32 {
+    let guard = match get_engine_guard(engine_id) {
+        Ok(guard) => guard,
+        Err(rc) => return rc,
+    };
+    if !guard.slot.is_owner_thread() {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    guard.slot.spans.set_enabled(enabled);
+    ffi::ZR_OK
+}
+
+/// Bytes and spans of the last successful present recorded since
+/// `engineSetFrameSpans(engineId, true)`; `null` before the first one.
+#[napi(js_name = "engineLastFrameSpans")]
+pub fn engine_last_frame_spans(engine_id: u32) -> napi::Result<Option<FrameSpans>, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineLastFrameSpans")?;
+    let last = guard.slot.spans.lock();
+    Ok(last.as_ref().map(|frame| FrameSpans {
+        bytes: Uint8Array::new(frame.bytes.clone()),
+        spans: frame
+            .spans
+            .iter()
+            .map(|s| FrameSpan {
+                byteOffset: s.offset,
+                length: s.len,
+                kind: s.kind.name().to_string(),
+            })
+            .collect(),
+    }))
+}
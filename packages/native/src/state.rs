@@ -0,0 +1,427 @@
+use crate::asserts::AssertScope;
+use crate::drawlist::clear_drawlist;
+use crate::error::{zr_error, zr_result_error, ZrCode};
+use crate::ffi;
+use crate::owner_engine_guard;
+use crate::registry::EngineSlot;
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/* Blob layout (little-endian): header, then `cols * rows` cells row-major. */
+const STATE_MAGIC: u32 = 0x5453_525A; /* 'ZRST' */
+const STATE_FORMAT_VERSION: u32 = 1;
+const STATE_HEADER_BYTES: usize = 40;
+/* glyph[32], glyph_len, width, 2 pad, fg, bg, attrs, underline. */
+const STATE_CELL_BYTES: usize = 52;
+
+/// Back buffer and desired cursor captured by `engineSaveState`.
+#[derive(Clone)]
+pub(crate) struct SavedState {
+    cols: u32,
+    rows: u32,
+    cells: Vec<ffi::zr_cell_t>,
+    cursor: ffi::zr_cursor_state_t,
+}
+
+fn fb_cells(fb: &ffi::zr_fb_t) -> &[ffi::zr_cell_t] {
+    let len = fb.cols as usize * fb.rows as usize;
+    if len == 0 || fb.cells.is_null() {
+        return &[];
+    }
+    unsafe { std::slice::from_raw_parts(fb.cells, len) }
+}
+
+fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn get_u32(bytes: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([bytes[off], bytes[off + 1], bytes[off + 2], bytes[off + 3]])
+}
+
+impl SavedState {
+    /// Copy of `fb` and `cursor`. Hyperlinks are dropped: link refs index
+    /// a table local to each framebuffer.
+    pub(crate) fn capture(fb: &ffi::zr_fb_t, cursor: ffi::zr_cursor_state_t) -> Self {
+        let mut cells = fb_cells(fb).to_vec();
+        for cell in &mut cells {
+            cell.style.link_ref = 0;
+        }
+        Self {
+            cols: fb.cols,
+            rows: fb.rows,
+            cells,
+            cursor,
+        }
+    }
+
+    pub(crate) fn size(&self) -> (u32, u32) {
+        (self.cols, self.rows)
+    }
+
+    pub(crate) fn cursor(&self) -> ffi::zr_cursor_state_t {
+        self.cursor
+    }
+
+    /// Overwrite every cell of `fb`; `false` when the sizes differ.
+    pub(crate) fn write_into(&self, fb: &mut ffi::zr_fb_t) -> bool {
+        if (fb.cols, fb.rows) != self.size() {
+            return false;
+        }
+        if !self.cells.is_empty() {
+            let dst = unsafe { std::slice::from_raw_parts_mut(fb.cells, self.cells.len()) };
+            dst.copy_from_slice(&self.cells);
+        }
+        true
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(STATE_HEADER_BYTES + self.cells.len() * STATE_CELL_BYTES);
+        for v in [
+            STATE_MAGIC,
+            STATE_FORMAT_VERSION,
+            ffi::ZR_ENGINE_ABI_MAJOR,
+            ffi::ZR_ENGINE_ABI_MINOR,
+            ffi::ZR_ENGINE_ABI_PATCH,
+            self.cols,
+            self.rows,
+            self.cursor.x as u32,
+            self.cursor.y as u32,
+        ] {
+            put_u32(&mut out, v);
+        }
+        out.extend_from_slice(&[self.cursor.shape, self.cursor.visible, self.cursor.blink, 0]);
+        for cell in &self.cells {
+            out.extend_from_slice(&cell.glyph);
+            out.extend_from_slice(&[cell.glyph_len, cell.width, 0, 0]);
+            for v in [
+                cell.style.fg_rgb,
+                cell.style.bg_rgb,
+                cell.style.attrs,
+                cell.style.underline_rgb,
+            ] {
+                put_u32(&mut out, v);
+            }
+        }
+        out
+    }
+
+    /// Parse a blob from [`SavedState::encode`]. Errors carry the result
+    /// code to throw: `ZR_ERR_FORMAT` for bytes that are not a state blob,
+    /// `ZR_ERR_UNSUPPORTED` for one written by another engine ABI.
+    pub(crate) fn decode(bytes: &[u8]) -> Result<Self, (i32, String)> {
+        let format = |msg: &str| (ffi::ZR_ERR_FORMAT, msg.to_string());
+        if bytes.len() < STATE_HEADER_BYTES || get_u32(bytes, 0) != STATE_MAGIC {
+            return Err(format("not a saved engine state"));
+        }
+        let version = get_u32(bytes, 4);
+        if version != STATE_FORMAT_VERSION {
+            return Err((
+                ffi::ZR_ERR_UNSUPPORTED,
+                format!(
+                    "state format {version} is not supported (expected {STATE_FORMAT_VERSION})"
+                ),
+            ));
+        }
+        let abi = (get_u32(bytes, 8), get_u32(bytes, 12), get_u32(bytes, 16));
+        if abi.0 != ffi::ZR_ENGINE_ABI_MAJOR || abi.1 != ffi::ZR_ENGINE_ABI_MINOR {
+            return Err((
+                ffi::ZR_ERR_UNSUPPORTED,
+                format!(
+                    "state saved by engine ABI {}.{}.{}, this engine is {}.{}.{}",
+                    abi.0,
+                    abi.1,
+                    abi.2,
+                    ffi::ZR_ENGINE_ABI_MAJOR,
+                    ffi::ZR_ENGINE_ABI_MINOR,
+                    ffi::ZR_ENGINE_ABI_PATCH
+                ),
+            ));
+        }
+        let (cols, rows) = (get_u32(bytes, 20), get_u32(bytes, 24));
+        let count = (cols as usize).checked_mul(rows as usize);
+        let expected = count
+            .and_then(|n| n.checked_mul(STATE_CELL_BYTES))
+            .and_then(|n| n.checked_add(STATE_HEADER_BYTES));
+        if expected != Some(bytes.len()) {
+            return Err(format("state blob is truncated or has trailing bytes"));
+        }
+        let cursor = ffi::zr_cursor_state_t {
+            x: get_u32(bytes, 28) as i32,
+            y: get_u32(bytes, 32) as i32,
+            shape: bytes[36],
+            visible: bytes[37],
+            blink: bytes[38],
+            reserved0: 0,
+        };
+        let mut cells = Vec::with_capacity(count.unwrap_or(0));
+        for rec in bytes[STATE_HEADER_BYTES..].chunks_exact(STATE_CELL_BYTES) {
+            let mut glyph = [0u8; 32];
+            glyph.copy_from_slice(&rec[..32]);
+            let (glyph_len, width) = (rec[32], rec[33]);
+            if glyph_len as usize > glyph.len() || width > 2 {
+                return Err(format("state blob holds an invalid cell"));
+            }
+            cells.push(ffi::zr_cell_t {
+                glyph,
+                glyph_len,
+                width,
+                _pad0: 0,
+                style: ffi::zr_style_t {
+                    fg_rgb: get_u32(rec, 36),
+                    bg_rgb: get_u32(rec, 40),
+                    attrs: get_u32(rec, 44),
+                    reserved: 0,
+                    underline_rgb: get_u32(rec, 48),
+                    link_ref: 0,
+                },
+            });
+        }
+        if cols != 0 {
+            for (y, row) in cells.chunks_exact(cols as usize).enumerate() {
+                check_row(row).map_err(|(x, why)| {
+                    (ffi::ZR_ERR_FORMAT, format!("state blob cell {x},{y} {why}"))
+                })?;
+            }
+        }
+        Ok(Self {
+            cols,
+            rows,
+            cells,
+            cursor,
+        })
+    }
+}
+
+/// The framebuffer invariants the differ relies on, for one decoded row:
+/// UTF-8 glyphs, and every wide lead directly followed by its continuation
+/// and every continuation directly preceded by its lead. Errors carry the
+/// column and what is wrong there.
+fn check_row(row: &[ffi::zr_cell_t]) -> Result<(), (usize, &'static str)> {
+    for (x, cell) in row.iter().enumerate() {
+        if std::str::from_utf8(&cell.glyph[..cell.glyph_len as usize]).is_err() {
+            return Err((x, "holds a glyph that is not UTF-8"));
+        }
+    }
+    let mut x = 0;
+    while x < row.len() {
+        match row[x].width {
+            0 => return Err((x, "is a continuation without a wide lead")),
+            2 if x + 1 == row.len() => return Err((x, "is a wide lead in the last column")),
+            2 if row[x + 1].width != 0 => return Err((x, "is a wide lead without a continuation")),
+            2 => x += 2,
+            _ => x += 1,
+        }
+    }
+    Ok(())
+}
+
+/// Full repaint owed to the terminal after a restore, cleared by the first
+/// present that succeeds. A present diverted by `enginePresentToString`
+/// leaves the terminal behind, so it owes one to the next terminal present,
+/// and so do bytes written by `engineWriteRaw`.
+#[derive(Default)]
+pub(crate) struct RepaintRequest {
+    pending: AtomicBool,
+    diverted: AtomicBool,
+    after_raw: AtomicBool,
+}
+
+impl RepaintRequest {
+    fn request(&self) {
+        self.pending.store(true, Ordering::Relaxed);
+    }
+
+    /// Raw bytes reached the terminal behind the differ's back.
+    pub(crate) fn note_raw_written(&self) {
+        self.after_raw.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn note_diverted(&self) {
+        self.diverted.store(true, Ordering::Relaxed);
+    }
+
+    /// Called before a present that reaches the terminal.
+    pub(crate) fn before_terminal_present(&self) {
+        if self.diverted.swap(false, Ordering::Relaxed) {
+            self.request();
+        }
+    }
+
+    pub(crate) fn pending(&self) -> bool {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn pending_after_raw(&self) -> bool {
+        self.after_raw.load(Ordering::Relaxed)
+    }
+
+    /// Run `present` with the terminal state treated as unknown when a
+    /// repaint is pending, so the diff redraws every cell.
+    pub(crate) fn apply(&self, present: impl FnOnce() -> i32) -> i32 {
+        let restore = self.pending.swap(false, Ordering::Relaxed);
+        let after_raw = self.after_raw.swap(false, Ordering::Relaxed);
+        if !restore && !after_raw {
+            return present();
+        }
+        set_repaint(true);
+        let rc = present();
+        set_repaint(false);
+        if rc != ffi::ZR_OK {
+            self.pending.fetch_or(restore, Ordering::Relaxed);
+            self.after_raw.fetch_or(after_raw, Ordering::Relaxed);
+        }
+        rc
+    }
+}
+
+/// Change to the engine's back buffer and requested cursor, made in place
+/// of executing a drawlist; `false` rejects the submit.
+pub(crate) type BackBufferEdit<'a> =
+    dyn Fn(&mut ffi::zr_fb_t, &mut ffi::zr_cursor_state_t) -> bool + 'a;
+
+thread_local! {
+    static PENDING: Cell<Option<*const BackBufferEdit<'static>>> = const { Cell::new(None) };
+    static REPAINT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `submit` with the next drawlist execution replaced by `edit`.
+fn with_pending(edit: &BackBufferEdit<'_>, submit: impl FnOnce() -> i32) -> i32 {
+    /* Only dereferenced during `submit`, while `edit` is still borrowed. */
+    let edit = unsafe {
+        std::mem::transmute::<*const BackBufferEdit<'_>, *const BackBufferEdit<'static>>(edit)
+    };
+    PENDING.with(|p| p.set(Some(edit)));
+    let rc = submit();
+    PENDING.with(|p| p.set(None));
+    rc
+}
+
+fn set_repaint(on: bool) {
+    REPAINT.with(|r| r.set(on));
+}
+
+/// Copy of the diff's initial terminal state with every validity flag
+/// cleared while a repaint is pending; `None` otherwise.
+pub(crate) fn repaint_term_state(
+    initial: *const ffi::zr_term_state_t,
+) -> Option<ffi::zr_term_state_t> {
+    if !REPAINT.with(Cell::get) || initial.is_null() {
+        return None;
+    }
+    let mut ts = unsafe { *initial };
+    ts.flags = 0;
+    Some(ts)
+}
+
+/// The engine's `zr_dl_execute` hook (see hooks.rs), so a restore or scroll
+/// can edit the back buffer through the engine's own submit path. Every
+/// engine drawlist execution goes through here; only the one an edit
+/// submits is replaced.
+pub(crate) extern "C" fn hook_dl_execute(
+    v: *const ffi::zr_dl_view_t,
+    dst: *mut ffi::zr_fb_t,
+    lim: *const ffi::zr_limits_t,
+    tab_width: u32,
+    width_policy: u32,
+    blit_caps: *const ffi::zr_blit_caps_t,
+    term_profile: *const ffi::zr_terminal_profile_t,
+    image_frame_stage: *mut ffi::zr_image_frame_t,
+    resources: *mut ffi::zr_dl_resources_t,
+    inout_cursor_state: *mut ffi::zr_cursor_state_t,
+) -> ffi::ZrResultT {
+    let Some(pending) = PENDING.with(Cell::take) else {
+        return unsafe {
+            ffi::zr_dl_execute(
+                v,
+                dst,
+                lim,
+                tab_width,
+                width_policy,
+                blit_caps,
+                term_profile,
+                image_frame_stage,
+                resources,
+                inout_cursor_state,
+            )
+        };
+    };
+    let edit = unsafe { &*pending };
+    if dst.is_null()
+        || inout_cursor_state.is_null()
+        || !edit(unsafe { &mut *dst }, unsafe { &mut *inout_cursor_state })
+    {
+        return ffi::ZR_ERR_INVALID_ARGUMENT;
+    }
+    ffi::ZR_OK
+}
+
+/// Apply `edit` to the engine's back buffer through an otherwise empty
+/// submit, so the engine's rollback and present bookkeeping stay intact.
+pub(crate) fn submit_edit(slot: &EngineSlot, edit: &BackBufferEdit<'_>) -> i32 {
+    let clear = clear_drawlist(slot.drawlist_version);
+    let asserts = AssertScope::begin();
+    let rc = with_pending(edit, || unsafe {
+        ffi::engine_submit_drawlist(slot.engine, clear.as_ptr(), clear.len() as i32)
+    });
+    asserts.finish(rc)
+}
+
+/// Capture the frame built by the drawlists submitted so far and the
+/// requested cursor as an opaque blob tagged with the engine ABI. Needs the
+/// same frame tracking as `engineExportFrame`.
+#[napi(js_name = "engineSaveState")]
+pub fn engine_save_state(engine_id: u32) -> napi::Result<Uint8Array, ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineSaveState")?;
+    let state = guard
+        .slot
+        .shadow
+        .save()
+        .map_err(|msg| zr_error(ffi::ZR_ERR_UNSUPPORTED, format!("engineSaveState: {msg}")))?;
+    Ok(Uint8Array::new(state.encode()))
+}
+
+/// Replace the back buffer and requested cursor with a blob from
+/// `engineSaveState`, and make the next present repaint every cell from an
+/// unknown terminal state. The screen must have the size the blob was saved
+/// at.
+#[napi(js_name = "engineRestoreState")]
+pub fn engine_restore_state(engine_id: u32, blob: Uint8Array) -> napi::Result<(), ZrCode> {
+    let guard = owner_engine_guard(engine_id, "engineRestoreState")?;
+    let state = SavedState::decode(blob.as_ref())
+        .map_err(|(rc, msg)| zr_error(rc, format!("engineRestoreState: {msg}")))?;
+    let slot = &guard.slot;
+    let Some(size) = slot.shadow.size() else {
+        return Err(zr_error(
+            ffi::ZR_ERR_UNSUPPORTED,
+            "engineRestoreState: no frame yet: poll the initial resize event first",
+        ));
+    };
+    if size != state.size() {
+        return Err(zr_error(
+            ffi::ZR_ERR_INVALID_ARGUMENT,
+            format!(
+                "engineRestoreState: state is {}x{} but the screen is {}x{}",
+                state.cols, state.rows, size.0, size.1
+            ),
+        ));
+    }
+
+    slot.idle.note_activity(Instant::now());
+    let rc = submit_edit(slot, &|fb, cursor| {
+        if !state.write_into(fb) {
+            return false;
+        }
+        *cursor = state.cursor();
+        true
+    });
+    if rc != ffi::ZR_OK {
+        return Err(zr_result_error(rc, "engineRestoreState"));
+    }
+    slot.shadow.restore(&state);
+    slot.repaint.request();
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use crate::config::{js_u32, js_u8_bool, validate_known_keys, ParseResult};
+use crate::error::{invalid_argument, ZrCode};
+use crate::ffi;
+use napi::bindgen_prelude::ValueType;
+use napi::{JsObject, JsUnknown};
+use napi_derive::napi;
+
+const STYLE_KEYS: &[(&str, &str)] = &[
+    ("fg", "fg"),
+    ("bg", "bg"),
+    ("bold", "bold"),
+    ("dim", "dim"),
+    ("italic", "italic"),
+    ("underline", "underline"),
+    ("reverse", "reverse"),
+    ("strike", "strike"),
+];
+
+const PACKED_STYLE_KEYS: &[(&str, &str)] = &[
+    ("fgRgb", "fg_rgb"),
+    ("bgRgb", "bg_rgb"),
+    ("attrs", "attrs"),
+    ("reserved", "reserved"),
+];
+
+const STYLE_ATTR_KEYS: &[(&str, u32)] = &[
+    ("bold", ffi::ZR_STYLE_ATTR_BOLD),
+    ("dim", ffi::ZR_STYLE_ATTR_DIM),
+    ("italic", ffi::ZR_STYLE_ATTR_ITALIC),
+    ("underline", ffi::ZR_STYLE_ATTR_UNDERLINE),
+    ("reverse", ffi::ZR_STYLE_ATTR_REVERSE),
+    ("strike", ffi::ZR_STYLE_ATTR_STRIKE),
+];
+
+/// `ZR_STYLE_ATTR_*` bits, as used in `Style.attrs` and
+/// `TerminalCaps.sgrAttrsSupported`.
+#[napi]
+pub const ATTR_BOLD: u32 = ffi::ZR_STYLE_ATTR_BOLD;
+#[napi]
+pub const ATTR_ITALIC: u32 = ffi::ZR_STYLE_ATTR_ITALIC;
+#[napi]
+pub const ATTR_UNDERLINE: u32 = ffi::ZR_STYLE_ATTR_UNDERLINE;
+#[napi]
+pub const ATTR_REVERSE: u32 = ffi::ZR_STYLE_ATTR_REVERSE;
+#[napi]
+pub const ATTR_DIM: u32 = ffi::ZR_STYLE_ATTR_DIM;
+#[napi]
+pub const ATTR_STRIKE: u32 = ffi::ZR_STYLE_ATTR_STRIKE;
+#[napi]
+pub const ATTR_OVERLINE: u32 = ffi::ZR_STYLE_ATTR_OVERLINE;
+#[napi]
+pub const ATTR_BLINK: u32 = ffi::ZR_STYLE_ATTR_BLINK;
+
+const SGR_ATTR_KEYS: &[(&str, &str)] = &[
+    ("bold", "bold"),
+    ("italic", "italic"),
+    ("underline", "underline"),
+    ("reverse", "reverse"),
+    ("dim", "dim"),
+    ("strike", "strike"),
+    ("overline", "overline"),
+    ("blink", "blink"),
+];
+
+/// Every attribute bit under its `SgrAttrs` key, in bit order.
+pub(crate) const SGR_ATTR_BITS: &[(&str, u32)] = &[
+    ("bold", ATTR_BOLD),
+    ("italic", ATTR_ITALIC),
+    ("underline", ATTR_UNDERLINE),
+    ("reverse", ATTR_REVERSE),
+    ("dim", ATTR_DIM),
+    ("strike", ATTR_STRIKE),
+    ("overline", ATTR_OVERLINE),
+    ("blink", ATTR_BLINK),
+];
+
+/* Must match ZR_ANSI16_PALETTE / ZR_XTERM256_* in zr_diff.c so palette colors
+ * survive the differ's color downgrade (cube/gray in 256-color mode, 0-15 in
+ * 16-color mode). */
+const ANSI16_PALETTE: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00, 0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5, 0x7F7F7F,
+    0xFF0000, 0x00FF00, 0xFFFF00, 0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+];
+const XTERM256_LEVELS: [u32; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Packed `zr_style_t` colors and attributes (`0x00RRGGBB` colors).
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct Style {
+    pub fgRgb: u32,
+    pub bgRgb: u32,
+    /// `ZR_STYLE_ATTR_*` bitmask.
+    pub attrs: u32,
+    pub reserved: u32,
+}
+
+/// An attribute bitmask as one flag per attribute.
+#[napi(object)]
+pub struct SgrAttrs {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+    pub dim: bool,
+    pub strike: bool,
+    pub overline: bool,
+    pub blink: bool,
+}
+
+pub(crate) fn sgr_attrs_from_mask(mask: u32) -> SgrAttrs {
+    let has = |bit: u32| mask & bit != 0;
+    SgrAttrs {
+        bold: has(ATTR_BOLD),
+        italic: has(ATTR_ITALIC),
+        underline: has(ATTR_UNDERLINE),
+        reverse: has(ATTR_REVERSE),
+        dim: has(ATTR_DIM),
+        strike: has(ATTR_STRIKE),
+        overline: has(ATTR_OVERLINE),
+        blink: has(ATTR_BLINK),
+    }
+}
+
+/// Resolve an xterm 256-color palette index to `0x00RRGGBB`.
+pub(crate) fn palette_rgb(index: u8) -> u32 {
+    let i = index as usize;
+    if i < 16 {
+        return ANSI16_PALETTE[i];
+    }
+    if i < 232 {
+        let c = i - 16;
+        let (r, g, b) = (c / 36, (c / 6) % 6, c % 6);
+        return (XTERM256_LEVELS[r] << 16) | (XTERM256_LEVELS[g] << 8) | XTERM256_LEVELS[b];
+    }
+    let v = 8 + 10 * (i as u32 - 232);
+    (v << 16) | (v << 8) | v
+}
+
+/// Parse `#rgb`, `#rrggbb` (leading `#` optional) into `0x00RRGGBB`.
+pub(crate) fn parse_hex_rgb(s: &str) -> Option<u32> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        6 => u32::from_str_radix(hex, 16).ok(),
+        3 => {
+            let v = u32::from_str_radix(hex, 16).ok()?;
+            let (r, g, b) = ((v >> 8) & 0xF, (v >> 4) & 0xF, v & 0xF);
+            Some((r * 0x11) << 16 | (g * 0x11) << 8 | (b * 0x11))
+        }
+        _ => None,
+    }
+}
+
+fn js_channel(obj: &JsObject, name: &str) -> ParseResult<u32> {
+    let v = obj.get_named_property::<JsUnknown>(name).map_err(|_| ())?;
+    if v.get_type().map_err(|_| ())? != ValueType::Number {
+        return Err(());
+    }
+    let f = v
+        .coerce_to_number()
+        .map_err(|_| ())?
+        .get_double()
+        .map_err(|_| ())?;
+    if !(0.0..=255.0).contains(&f) || f.fract() != 0.0 {
+        return Err(());
+    }
+    Ok(f as u32)
+}
+
+/// Accepts a palette index (0..255), a hex string, or `{ r, g, b }`.
+fn js_color(obj: &JsObject, name: &str) -> ParseResult<Option<u32>> {
+    let v = match obj.get_named_property::<JsUnknown>(name) {
+        Ok(v) => v,
+        Err(_) => return Ok(None),
+    };
+    match v.get_type().map_err(|_| ())? {
+        ValueType::Undefined => Ok(None),
+        ValueType::Number => {
+            let f = v
+                .coerce_to_number()
+                .map_err(|_| ())?
+                .get_double()
+                .map_err(|_| ())?;
+            if !(0.0..=255.0).contains(&f) || f.fract() != 0.0 {
+                return Err(());
+            }
+            Ok(Some(palette_rgb(f as u8)))
+        }
+        ValueType::String => {
+            let s = v
+                .coerce_to_string()
+                .map_err(|_| ())?
+                .into_utf8()
+                .map_err(|_| ())?;
+            parse_hex_rgb(s.as_str().map_err(|_| ())?)
+                .map(Some)
+                .ok_or(())
+        }
+        ValueType::Object => {
+            let o = v.coerce_to_object().map_err(|_| ())?;
+            validate_known_keys(&o, &[("r", "r"), ("g", "g"), ("b", "b")], "makeStyle color")
+                .map_err(|_| ())?;
+            let (r, g, b) = (
+                js_channel(&o, "r")?,
+                js_channel(&o, "g")?,
+                js_channel(&o, "b")?,
+            );
+            Ok(Some((r << 16) | (g << 8) | b))
+        }
+        _ => Err(()),
+    }
+}
+
+/// Read a packed `Style` object (as returned by `makeStyle`) into a
+/// `zr_style_t`. Omitted fields are 0.
+pub(crate) fn parse_packed_style(
+    obj: &JsObject,
+    ctx: &str,
+) -> napi::Result<ffi::zr_style_t, ZrCode> {
+    validate_known_keys(obj, PACKED_STYLE_KEYS, ctx)?;
+    let field = |primary: &str, alias: &str| {
+        js_u32(obj, primary, alias).map_err(|_| {
+            invalid_argument(format!("{ctx}: {primary} must be a non-negative integer"))
+        })
+    };
+    let fg_rgb = field("fgRgb", "fg_rgb")?.unwrap_or(0);
+    let bg_rgb = field("bgRgb", "bg_rgb")?.unwrap_or(0);
+    let attrs = field("attrs", "attrs")?.unwrap_or(0);
+    if fg_rgb > 0xFF_FFFF || bg_rgb > 0xFF_FFFF || attrs & !ffi::ZR_STYLE_ATTR_ALL_MASK != 0 {
+        return Err(invalid_argument(format!(
+            "{ctx}: colors must be 0x00RRGGBB and attrs a ZR_STYLE_ATTR_* mask"
+        )));
+    }
+    Ok(ffi::zr_style_t {
+        fg_rgb,
+        bg_rgb,
+        attrs,
+        reserved: 0,
+        underline_rgb: 0,
+        link_ref: 0,
+    })
+}
+
+/// Build a packed style from colors (palette index, hex string, or
+/// `{ r, g, b }`) and attribute flags. Omitted colors default to 0 (black).
+#[napi(js_name = "makeStyle")]
+pub fn make_style(opts: Option<JsObject>) -> napi::Result<Style, ZrCode> {
+    let mut style = Style {
+        fgRgb: 0,
+        bgRgb: 0,
+        attrs: 0,
+        reserved: 0,
+    };
+    let Some(obj) = opts else {
+        return Ok(style);
+    };
+    validate_known_keys(&obj, STYLE_KEYS, "makeStyle")?;
+
+    for (name, slot) in [("fg", &mut style.fgRgb), ("bg", &mut style.bgRgb)] {
+        let color = js_color(&obj, name).map_err(|_| {
+            invalid_argument(format!(
+                "makeStyle: {name} must be a palette index (0-255), a hex string, or {{ r, g, b }}"
+            ))
+        })?;
+        if let Some(rgb) = color {
+            *slot = rgb;
+        }
+    }
+    for &(name, bit) in STYLE_ATTR_KEYS {
+        let flag = js_u8_bool(&obj, name, name)
+            .map_err(|_| invalid_argument(format!("makeStyle: {name} must be a boolean")))?;
+        if flag == Some(1) {
+            style.attrs |= bit;
+        }
+    }
+    Ok(style)
+}
+
+/// Split an attribute bitmask (`Style.attrs`, `TerminalCaps.sgrAttrsSupported`)
+/// into named flags. Bits outside `ZR_STYLE_ATTR_*` throw.
+#[napi(js_name = "decodeSgrAttrs")]
+pub fn decode_sgr_attrs(mask: u32) -> napi::Result<SgrAttrs, ZrCode> {
+    if mask & !ffi::ZR_STYLE_ATTR_ALL_MASK != 0 {
+        return Err(invalid_argument(format!(
+            "decodeSgrAttrs: {mask:#x} has bits outside the ATTR_* mask"
+        )));
+    }
+    Ok(sgr_attrs_from_mask(mask))
+}
+
+/// Pack `SgrAttrs` flags back into a bitmask; omitted flags are off.
+#[napi(js_name = "encodeSgrAttrs")]
+pub fn encode_sgr_attrs(attrs: JsObject) -> napi::Result<u32, ZrCode> {
+    validate_known_keys(&attrs, SGR_ATTR_KEYS, "encodeSgrAttrs")?;
+    let mut mask = 0;
+    for &(name, bit) in SGR_ATTR_BITS {
+        let flag = js_u8_bool(&attrs, name, name)
+            .map_err(|_| invalid_argument(format!("encodeSgrAttrs: {name} must be a boolean")))?;
+        if flag == Some(1) {
+            mask |= bit;
+        }
+    }
+    Ok(mask)
+}
@@ -1,10 +1,83 @@
-use crate::config::checked_u8;
-use crate::debug::{parse_debug_query_bigint_u64, parse_debug_query_number_u64};
+use crate::asserts::{apply_assert_mode, take_assert_failures, AssertScope, ASSERT_FAILED};
+use crate::budget::drawlist_budget;
+use crate::chunks::{write_chunks, ChunkCounts, ChunkPlan, CHUNK_WAIT_MS};
+use crate::config::{
+    check_color_mode, check_limits, check_width_policy, checked_u8, config_preset,
+    create_cfg_to_js, runtime_cfg_from_create, runtime_cfg_to_js, BindingCreateOptions, ColorMode,
+    WidthPolicy, CONFIG_PRESET_NAMES,
+};
+use crate::cursor::{CursorBlink, CursorBlinkState};
+use crate::debug::{
+    count_debug_categories, debug_category_name, debug_export_size, debug_headers_cap,
+    debug_severity_name, parse_debug_query_bigint_u64, parse_debug_query_number_u64,
+    read_debug_payload,
+};
+use crate::debugjson::debug_export_to_json;
+use crate::debugsub::TraceCursor;
+use crate::drawlist::{
+    clear_drawlist, drawlist_counts, fallback_attrs, find_limit_violation, rewrite_style_attrs,
+    walk_cmds, AttrFallback, DrawlistCounts,
+};
+use crate::drops::{DropCounters, ENGINE_USER_BYTES_CAP};
+use crate::error::{
+    napi_error, zi_error_is_ok, zi_error_name, zi_result_codes, zr_error, zr_result_to_napi, ZrCode,
+};
+use crate::events::{
+    batch_event_count, coalesce_records, decode_batch, record_fields, CoalescePolicy, EventSeq,
+};
+use crate::exithook::{remaining_terminal_restore, take_idle_owned_engines};
+use crate::export::FrameShadow;
 use crate::ffi;
-
-const ATTR_BOLD: u32 = 1 << 0;
-const ATTR_UNDERLINE: u32 = 1 << 2;
-const ATTR_DIM: u32 = 1 << 4;
+use crate::framebuffer::{
+    check_same_size, damage_rects, default_diff_caps, diff_to_bytes, diff_with_stats, fb_lines,
+    grapheme_width, layout_run, resize_fb, with_damage_scratch, CellRect, DamageRect, DiffOpts,
+    DiffStats, Framebuffer,
+};
+use crate::fullframes::{classify_full_frame, DiffOutcome, FullFrameCauses, FullFrameReason};
+use crate::idle::{engine_is_idle, engine_take_idle_transitions, IdleState};
+use crate::initstyle::{seed_style, seeded_term_state, InitialStyle};
+use crate::metrics::{frame_stats, FrameTimes, MetricsBaseline};
+use crate::modes::{
+    last_focus, mode_transition, InputMode, InputModes, FOCUS_OFF, MOUSE_OFF, MOUSE_ON, PASTE_OFF,
+    PASTE_ON,
+};
+use crate::output::{
+    drain_terminal, flush_sequence, sanitize_title, TerminalOutput, TerminalTarget, FLUSH_WAIT_MS,
+    RAW_QUEUE_MAX_BYTES, VISUAL_BELL_DURATION,
+};
+use crate::overlay::{place_overlay, OverlayCorner, OVERLAY_COLS, OVERLAY_ROWS};
+use crate::painter::ClipStack;
+use crate::pollbuf::{PollBuffer, PollStorage};
+use crate::prewarm::{prewarm_arena_bytes, prewarm_initial_bytes};
+use crate::protect::{protect_cells, ProtectedRect};
+use crate::registry::{
+    get_engine_guard, is_engine_registered, owned_engine_ids, register_engine,
+    registered_engine_ids, take_engine_for_owner, take_idle_engine_for_owner, transfer_ownership,
+    TransferRefused,
+};
+use crate::render::{describe_drawlist_error, execute_drawlist, validate_drawlist, OwnedFb};
+use crate::report::{bytes_per_damaged_cell, parse_diff_telemetry};
+use crate::scroll::scroll_fb;
+use crate::signals::{signal_number, LEAVE_ALT_SCREEN, RESTORE_MODES};
+use crate::size::{check_posted_size, PolledSize, PostedSize, TerminalSize, POSTED_SIZE_MAX};
+use crate::spans::{tokenize, SpanKind};
+use crate::state::{repaint_term_state, RepaintRequest, SavedState};
+use crate::style::{
+    decode_sgr_attrs, palette_rgb, parse_hex_rgb, ATTR_BOLD, ATTR_DIM, ATTR_STRIKE, ATTR_UNDERLINE,
+    SGR_ATTR_BITS,
+};
+use crate::text::{grapheme_spans, measure_utf8, wrap_lines};
+use crate::unknown::{
+    hook_input_parse_bytes, sequence_at, split_input, with_unknown_sequences, Piece, Sequence,
+    UNKNOWN_SEQUENCE_TAG,
+};
+use crate::watchdog::{engine_take_watchdog_reports, WatchdogState};
+use crate::{
+    check_requested_versions, engine_flush, engine_get_metrics, negotiation_from_metrics,
+    post_batch_to_slot, zi_config_default, zi_engine_version, zi_limits_default,
+    zi_supported_drawlist_versions, zi_supported_event_batch_versions,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 fn contains_subsequence(haystack: &[u8], needle: &[u8]) -> bool {
     if needle.is_empty() {
@@ -216,6 +289,7 @@ fn render_diff_bytes(
             y0: 0,
             x1: 0,
             y1: 0,
+            _link: 0,
         };
         limits.diff_max_damage_rects as usize
     ];
@@ -235,6 +309,7 @@ fn render_diff_bytes(
             scratch_damage_rects.as_mut_ptr(),
             scratch_damage_rects.len() as u32,
             0,
+            0,
             out.as_mut_ptr(),
             out.len(),
             &mut out_len as *mut _,
@@ -426,15 +501,35 @@ fn fb_link_intern_compacts_stale_refs_and_bounds_growth() {
 
 #[test]
 fn ffi_layout_matches_vendored_headers() {
-    use std::mem::{align_of, size_of};
+    use std::mem::{align_of, offset_of, size_of};
     use std::ptr::addr_of;
 
     assert_eq!(size_of::<ffi::zr_style_t>(), 24);
     assert_eq!(align_of::<ffi::zr_style_t>(), 4);
     assert_eq!(size_of::<ffi::zr_cell_t>(), 60);
     assert_eq!(size_of::<ffi::zr_term_state_t>(), 44);
+    assert_eq!(size_of::<ffi::zr_damage_rect_t>(), 20);
     assert_eq!(size_of::<ffi::plat_caps_t>(), 16);
     assert_eq!(size_of::<ffi::plat_config_t>(), 8);
+    assert_eq!(size_of::<ffi::zr_terminal_profile_t>(), 100);
+    assert_eq!(
+        offset_of!(ffi::zr_event_t, time_us),
+        44usize.next_multiple_of(align_of::<u64>())
+    );
+    assert_eq!(
+        size_of::<ffi::zr_event_t>(),
+        offset_of!(ffi::zr_event_t, time_us) + 8
+    );
+    assert_eq!(size_of::<ffi::zr_dl_header_t>(), 64);
+    assert_eq!(size_of::<ffi::zr_blit_caps_t>(), 16);
+    {
+        let profile = std::mem::MaybeUninit::<ffi::zr_terminal_profile_t>::uninit();
+        let base = profile.as_ptr();
+        unsafe {
+            assert_eq!(addr_of!((*base).version_string) as usize - base as usize, 7);
+            assert_eq!(addr_of!((*base).cell_width_px) as usize - base as usize, 88);
+        }
+    }
     {
         let plat = std::mem::MaybeUninit::<ffi::plat_config_t>::uninit();
         let base = plat.as_ptr();
@@ -448,6 +543,10 @@ fn ffi_layout_matches_vendored_headers() {
         unsafe {
             assert_eq!(
                 addr_of!((*base).inline_rows) as usize - base as usize,
+                size_of::<ffi::zr_engine_config_t>() - 8
+            );
+            assert_eq!(
+                addr_of!((*base).enable_erase_optimizations) as usize - base as usize,
                 size_of::<ffi::zr_engine_config_t>() - 4
             );
         }
@@ -483,9 +582,23 @@ fn ffi_layout_matches_vendored_headers() {
     if cfg!(target_pointer_width = "64") {
         assert_eq!(size_of::<ffi::zr_fb_t>(), 48);
         assert_eq!(align_of::<ffi::zr_fb_t>(), 8);
+        assert_eq!(size_of::<ffi::zr_fb_painter_t>(), 24);
+        assert_eq!(size_of::<ffi::zr_event_queue_t>(), 104);
+        assert_eq!(size_of::<ffi::zr_dl_view_t>(), 168);
+        assert_eq!(size_of::<ffi::zr_dl_resource_store_t>(), 24);
+        assert_eq!(size_of::<ffi::zr_dl_resources_t>(), 48);
+        assert_eq!(size_of::<ffi::zr_image_frame_t>(), 32);
+        assert_eq!(size_of::<ffi::zr_engine_hooks_t>(), 56);
     } else if cfg!(target_pointer_width = "32") {
         assert_eq!(size_of::<ffi::zr_fb_t>(), 36);
         assert_eq!(align_of::<ffi::zr_fb_t>(), 4);
+        assert_eq!(size_of::<ffi::zr_fb_painter_t>(), 16);
+        assert_eq!(size_of::<ffi::zr_event_queue_t>(), 80);
+        assert_eq!(size_of::<ffi::zr_dl_view_t>(), 120);
+        assert_eq!(size_of::<ffi::zr_dl_resource_store_t>(), 16);
+        assert_eq!(size_of::<ffi::zr_dl_resources_t>(), 32);
+        assert_eq!(size_of::<ffi::zr_image_frame_t>(), 24);
+        assert_eq!(size_of::<ffi::zr_engine_hooks_t>(), 28);
     }
 }
 
@@ -755,52 +868,3796 @@ fn debug_query_number_u64_rejects_fractional_or_unsafe_numbers() {
 }
 
 #[test]
-fn checked_u8_rejects_out_of_range_values() {
-    assert_eq!(checked_u8(0), Ok(0));
-    assert_eq!(checked_u8(255), Ok(255));
-    assert!(checked_u8(256).is_err());
+fn debug_names_cover_vendored_categories_and_severities() {
+    assert_eq!(debug_category_name(ffi::ZR_DEBUG_CAT_NONE), Some("none"));
+    assert_eq!(debug_category_name(ffi::ZR_DEBUG_CAT_FRAME), Some("frame"));
+    assert_eq!(debug_category_name(ffi::ZR_DEBUG_CAT_PERF), Some("perf"));
+    assert_eq!(debug_category_name(7), None);
+
+    assert_eq!(debug_severity_name(ffi::ZR_DEBUG_SEV_TRACE), Some("trace"));
+    assert_eq!(debug_severity_name(ffi::ZR_DEBUG_SEV_WARN), Some("warn"));
+    assert_eq!(debug_severity_name(ffi::ZR_DEBUG_SEV_ERROR), Some("error"));
+    assert_eq!(debug_severity_name(4), None);
 }
 
 #[test]
-fn diff_emits_dim_and_normal_intensity_sequences() {
-    let to_dim = render_style_transition(0, ATTR_DIM);
+fn watchdog_reports_overrun_once_per_call() {
+    let state = WatchdogState::new(5);
+    let start = std::time::Instant::now();
+    {
+        let _watch = state.watch(std::ptr::null_mut(), "enginePresent", 0);
+        state.check(7, start);
+        state.check(7, start + std::time::Duration::from_millis(50));
+        state.check(7, start + std::time::Duration::from_millis(100));
+    }
+    let reports = state.take_reports();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].engineId, 7);
+    assert_eq!(reports[0].functionName, "enginePresent");
+    assert_eq!(reports[0].budgetMs, 5);
+
+    state.check(7, start + std::time::Duration::from_millis(500));
     assert!(
-        contains_subsequence(&to_dim, b"\x1b[0;2;"),
-        "expected dim SGR sequence in output: {:?}",
-        String::from_utf8_lossy(&to_dim),
+        state.take_reports().is_empty(),
+        "no report once the call ended"
     );
+}
 
-    let to_normal = render_style_transition(ATTR_DIM, 0);
+#[test]
+fn watchdog_allowance_extends_budget_for_blocking_polls() {
+    let state = WatchdogState::new(5);
+    let _watch = state.watch(std::ptr::null_mut(), "enginePollEvents", 100);
+    state.check(
+        1,
+        std::time::Instant::now() + std::time::Duration::from_millis(50),
+    );
+    assert!(state.take_reports().is_empty());
+}
+
+// Replacing the process-wide log sink would steal lines from the
+// `setAssertMode` listener that the assert-log tests install.
+#[cfg(not(feature = "assert-log"))]
+#[test]
+fn watchdog_logs_each_overrun_to_the_log_sink() {
+    static LINES: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+    extern "C" fn sink(_user: *mut std::ffi::c_void, msg: ffi::zr_string_view_t) {
+        let bytes = unsafe { std::slice::from_raw_parts(msg.ptr.cast::<u8>(), msg.len) };
+        let line = String::from_utf8_lossy(bytes).into_owned();
+        LINES.lock().unwrap().push(line);
+    }
+    unsafe { ffi::zr_log_set_sink(Some(sink), std::ptr::null_mut()) };
+
+    let state = WatchdogState::new(5);
+    {
+        let _watch = state.watch(std::ptr::null_mut(), "enginePresent", 0);
+        let start = std::time::Instant::now();
+        state.check(4242, start + std::time::Duration::from_millis(50));
+        state.check(4242, start + std::time::Duration::from_millis(100));
+    }
+    unsafe { ffi::zr_log_set_sink(None, std::ptr::null_mut()) };
+
+    let lines = LINES.lock().unwrap();
+    let ours: Vec<&String> = lines
+        .iter()
+        .filter(|line| line.starts_with("watchdog: engine 4242:"))
+        .collect();
+    assert_eq!(
+        ours,
+        ["watchdog: engine 4242: enginePresent exceeded maxCallMs 5 (50 ms)"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn watchdog_traces_an_overrun_once_the_call_returns() {
+    let mut input = [-1; 2];
+    let mut output = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(input.as_mut_ptr()) }, 0);
+    assert_eq!(unsafe { libc::pipe(output.as_mut_ptr()) }, 0);
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let io = ffi::zr_engine_io_t {
+        input_fd: input[0],
+        output_fd: output[1],
+    };
+    let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+    assert_eq!(
+        unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) },
+        ffi::ZR_OK
+    );
+    let debug = ffi::zr_debug_config_t {
+        enabled: 1,
+        ring_capacity: 64,
+        min_severity: ffi::ZR_DEBUG_SEV_TRACE,
+        category_mask: 1 << ffi::ZR_DEBUG_CAT_ERROR,
+        capture_raw_events: 0,
+        capture_drawlist_bytes: 0,
+        _pad0: 0,
+        _pad1: 0,
+    };
+    assert_eq!(
+        unsafe { ffi::engine_debug_enable(engine, &debug) },
+        ffi::ZR_OK
+    );
+
+    let state = WatchdogState::new(5);
+    let query = ffi::zr_debug_query_t {
+        min_record_id: 0,
+        max_record_id: 0,
+        min_frame_id: 0,
+        max_frame_id: 0,
+        category_mask: 1 << ffi::ZR_DEBUG_CAT_ERROR,
+        min_severity: ffi::ZR_DEBUG_SEV_TRACE,
+        max_records: 0,
+        _pad0: 0,
+    };
+    let mut headers = [ffi::zr_debug_record_header_t {
+        record_id: 0,
+        timestamp_us: 0,
+        frame_id: 0,
+        category: 0,
+        severity: 0,
+        code: 0,
+        payload_size: 0,
+    }; 4];
+    let mut result = ffi::zr_debug_query_result_t {
+        records_returned: 0,
+        records_available: 0,
+        oldest_record_id: 0,
+        newest_record_id: 0,
+        records_dropped: 0,
+        _pad0: 0,
+    };
+    let mut query_records = |result: &mut ffi::zr_debug_query_result_t| unsafe {
+        ffi::engine_debug_query(engine, &query, headers.as_mut_ptr(), 4, result)
+    };
+    let in_call = {
+        let _watch = state.watch(engine, "enginePresent", 0);
+        let start = std::time::Instant::now();
+        state.check(1, start + std::time::Duration::from_millis(50));
+        assert_eq!(query_records(&mut result), ffi::ZR_OK);
+        result.records_returned
+    };
+    assert_eq!(query_records(&mut result), ffi::ZR_OK);
+    let header = headers[0];
+    let mut payload = [0u8; 120];
+    let mut size = 0u32;
+    let payload_rc = unsafe {
+        ffi::engine_debug_get_payload(
+            engine,
+            header.record_id,
+            payload.as_mut_ptr(),
+            120,
+            &mut size,
+        )
+    };
+    unsafe { ffi::engine_destroy(engine) };
+    for fd in input.into_iter().chain(output) {
+        unsafe { libc::close(fd) };
+    }
+
+    assert_eq!(in_call, 0, "no record while the call is still running");
+    assert_eq!(result.records_returned, 1);
+    assert_eq!(header.severity, ffi::ZR_DEBUG_SEV_WARN);
+    assert_eq!(header.code, 0x0400);
+    assert_eq!((payload_rc, size), (ffi::ZR_OK, 120));
+    let message = &payload[56..];
+    let len = message.iter().position(|&b| b == 0).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&message[..len]).unwrap(),
+        "enginePresent exceeded maxCallMs 5 (50 ms)"
+    );
+}
+
+fn push_u32s(out: &mut Vec<u8>, values: &[u32]) {
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+fn push_cmd(out: &mut Vec<u8>, opcode: u16, payload: &[u32]) {
+    out.extend_from_slice(&opcode.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&(8 + payload.len() as u32 * 4).to_le_bytes());
+    push_u32s(out, payload);
+}
+
+/// FILL_RECT + DEF_BLOB(text run, 1 segment) + DRAW_TEXT_RUN, all with `attrs`.
+fn drawlist_with_attrs(attrs: u32) -> Vec<u8> {
+    let mut cmds = Vec::new();
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_FILL_RECT,
+        &[0, 0, 4, 1, 0, 0, attrs, 0, 0, 0, 0],
+    );
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DEF_BLOB,
+        &[9, 44, 1, 0, 0, attrs, 0, 0, 0, 0, 7, 0, 2],
+    );
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_DRAW_TEXT_RUN, &[0, 0, 9, 0]);
+
+    let mut out = Vec::new();
+    push_u32s(
+        &mut out,
+        &[
+            0x4C44_525A,
+            1,
+            64,
+            64 + cmds.len() as u32,
+            64,
+            cmds.len() as u32,
+            3,
+        ],
+    );
+    out.resize(64, 0);
+    out.extend_from_slice(&cmds);
+    out
+}
+
+#[test]
+fn attr_fallback_substitutes_only_for_missing_attrs() {
+    let supported = ATTR_BOLD | ATTR_UNDERLINE | ATTR_DIM;
+    assert_eq!(
+        fallback_attrs(ATTR_STRIKE, supported, AttrFallback::Underline),
+        ATTR_STRIKE | ATTR_UNDERLINE
+    );
+    assert_eq!(
+        fallback_attrs(ATTR_STRIKE | ATTR_BOLD, supported, AttrFallback::Dim),
+        ATTR_STRIKE | ATTR_BOLD | ATTR_DIM
+    );
+    assert_eq!(
+        fallback_attrs(ATTR_STRIKE, supported, AttrFallback::Drop),
+        ATTR_STRIKE
+    );
+    assert_eq!(
+        fallback_attrs(ATTR_BOLD, supported, AttrFallback::Underline),
+        ATTR_BOLD,
+        "supported attrs are untouched"
+    );
+    assert_eq!(
+        fallback_attrs(ATTR_STRIKE, ATTR_BOLD, AttrFallback::Dim),
+        ATTR_STRIKE,
+        "an unsupported substitute is not added"
+    );
+}
+
+#[test]
+fn attr_fallback_rewrites_rects_and_same_drawlist_text_runs() {
+    let mut bytes = drawlist_with_attrs(ATTR_STRIKE);
+    let expected = drawlist_with_attrs(ATTR_STRIKE | ATTR_UNDERLINE);
+    assert!(rewrite_style_attrs(&mut bytes, |attrs| {
+        fallback_attrs(attrs, ATTR_UNDERLINE, AttrFallback::Underline)
+    }));
+    assert_eq!(bytes, expected);
+
+    let mut truncated = drawlist_with_attrs(ATTR_STRIKE);
+    truncated.truncate(truncated.len() - 4);
+    assert!(!rewrite_style_attrs(&mut truncated, |attrs| attrs));
+}
+
+/// CLEAR + DEF_STRING(1, "hi") + DRAW_TEXT(string `text_id`) as a v1 drawlist.
+fn drawlist_with_text(text_id: u32) -> Vec<u8> {
+    let mut cmds = Vec::new();
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_CLEAR, &[]);
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DEF_STRING,
+        &[1, 2, u32::from_le_bytes(*b"hi\0\0")],
+    );
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DRAW_TEXT,
+        &[1, 0, text_id, 0, 2, 0xFF_0000, 0, ATTR_BOLD, 0, 0, 0, 0, 0],
+    );
+    drawlist_from_cmds(&cmds, 3)
+}
+
+fn drawlist_from_cmds(cmds: &[u8], cmd_count: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32s(
+        &mut out,
+        &[
+            0x4C44_525A,
+            1,
+            64,
+            64 + cmds.len() as u32,
+            64,
+            cmds.len() as u32,
+            cmd_count,
+        ],
+    );
+    out.resize(64, 0);
+    out.extend_from_slice(cmds);
+    out
+}
+
+#[test]
+fn drawlist_errors_name_the_offending_command() {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let good = drawlist_with_text(1);
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    assert!(execute_drawlist(&good, &mut fb, &limits).is_ok());
+    assert_eq!(cell_snapshot(&mut fb.0, 1, 0).0, b'h');
+
+    let bad = drawlist_with_text(5);
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    let Err((stage, rc)) = execute_drawlist(&bad, &mut fb, &limits) else {
+        panic!("expected failure: undefined string");
+    };
+    let msg = describe_drawlist_error(&bad, 4, 1, &limits, stage, rc, "ctx");
     assert!(
-        contains_subsequence(&to_normal, b"\x1b[0;38;"),
-        "expected normal-intensity SGR sequence in output: {:?}",
-        String::from_utf8_lossy(&to_normal),
+        msg.starts_with("ctx: command 2 (DRAW_TEXT) rejected"),
+        "{msg}"
+    );
+
+    let mut bad_version = good.clone();
+    bad_version[4..8].copy_from_slice(&99u32.to_le_bytes());
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    let Err((stage, rc)) = execute_drawlist(&bad_version, &mut fb, &limits) else {
+        panic!("expected failure: version");
+    };
+    let msg = describe_drawlist_error(&bad_version, 4, 1, &limits, stage, rc, "ctx");
+    assert_eq!(
+        msg,
+        "ctx: drawlist rejected during validation: ZR_ERR_UNSUPPORTED"
+    );
+
+    /* DRAW_TEXT starts after CLEAR (8 bytes) and DEF_STRING (20 bytes). */
+    let mut truncated = good.clone();
+    truncated[64 + 28 + 4..64 + 28 + 8].copy_from_slice(&4u32.to_le_bytes());
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    let Err((stage, rc)) = execute_drawlist(&truncated, &mut fb, &limits) else {
+        panic!("expected failure: framing");
+    };
+    let msg = describe_drawlist_error(&truncated, 4, 1, &limits, stage, rc, "ctx");
+    assert!(
+        msg.starts_with("ctx: command 2 has invalid framing"),
+        "{msg}"
     );
 }
 
 #[test]
-fn diff_reapplies_intensity_when_switching_bold_and_dim() {
-    let dim_to_bold = render_style_transition(ATTR_DIM, ATTR_BOLD);
+fn validate_drawlist_checks_framing_without_resources() {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let good = drawlist_with_text(1);
+    assert_eq!(validate_drawlist(&good, &limits), ffi::ZR_OK);
+    assert_eq!(
+        validate_drawlist(&drawlist_with_text(5), &limits),
+        ffi::ZR_OK,
+        "string ids are resolved at submit, not validation"
+    );
+    assert_ne!(
+        validate_drawlist(&good[..good.len() - 1], &limits),
+        ffi::ZR_OK
+    );
+    assert_ne!(validate_drawlist(&[], &limits), ffi::ZR_OK);
+}
+
+#[test]
+fn limit_violations_name_the_limit_and_command() {
+    let defaults = unsafe { ffi::zr_engine_config_default() }.limits;
+    let check = |bytes: &[u8], tweak: fn(&mut ffi::zr_limits_t)| {
+        let mut limits = defaults;
+        tweak(&mut limits);
+        find_limit_violation(bytes, &limits).map(|v| (v.limit, v.command_index))
+    };
+    let text = drawlist_with_text(1);
+    assert_eq!(check(&text, |_| {}), None);
+    assert_eq!(
+        check(&text, |l| l.dl_max_total_bytes = 64),
+        Some(("dlMaxTotalBytes", None))
+    );
+    assert_eq!(
+        check(&text, |l| l.dl_max_cmds = 2),
+        Some(("dlMaxCmds", Some(2)))
+    );
+    assert_eq!(
+        check(&text, |l| l.dl_max_strings = 0),
+        Some(("dlMaxStrings", Some(1)))
+    );
+    assert_eq!(
+        check(&drawlist_with_attrs(0), |l| l.dl_max_text_run_segments = 0),
+        Some(("dlMaxTextRunSegments", Some(2)))
+    );
+
+    let mut cmds = Vec::new();
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_CLEAR, &[]);
+    for _ in 0..2 {
+        push_cmd(&mut cmds, ffi::ZR_DL_OP_PUSH_CLIP, &[0, 0, 4, 1]);
+    }
+    for _ in 0..2 {
+        push_cmd(&mut cmds, ffi::ZR_DL_OP_POP_CLIP, &[]);
+    }
+    let clips = drawlist_from_cmds(&cmds, 5);
+    assert_eq!(check(&clips, |_| {}), None);
+    assert_eq!(
+        check(&clips, |l| l.dl_max_clip_depth = 1),
+        Some(("dlMaxClipDepth", Some(2)))
+    );
+    let mut limits = defaults;
+    limits.dl_max_clip_depth = 1;
+    assert_eq!(validate_drawlist(&clips, &limits), ffi::ZR_ERR_LIMIT);
+}
+
+#[test]
+fn drawlist_counts_track_live_resources_and_peaks() {
+    let text = drawlist_with_text(1);
+    assert_eq!(
+        drawlist_counts(&text),
+        DrawlistCounts {
+            total_bytes: text.len() as u32,
+            cmds: 3,
+            strings: 1,
+            ..DrawlistCounts::default()
+        }
+    );
+    let runs = drawlist_counts(&drawlist_with_attrs(0));
+    assert_eq!((runs.blobs, runs.max_text_run_segments), (1, 1));
+
+    let mut cmds = Vec::new();
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_PUSH_CLIP, &[0, 0, 4, 1]);
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_PUSH_CLIP, &[0, 0, 2, 1]);
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_POP_CLIP, &[]);
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_PUSH_CLIP, &[0, 0, 2, 1]);
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DEF_STRING,
+        &[1, 1, u32::from(b'a')],
+    );
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DEF_STRING,
+        &[2, 1, u32::from(b'b')],
+    );
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_FREE_STRING, &[1]);
+    let counts = drawlist_counts(&drawlist_from_cmds(&cmds, 7));
+    assert_eq!(counts.max_clip_depth, 2);
+    assert_eq!(counts.strings, 1, "freed strings are not live");
+}
+
+#[test]
+fn initial_style_skips_baseline_clear_and_reset() {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let caps = default_diff_caps();
+    let blank = OwnedFb::new(4, 1).expect("fb");
+    let mut next = OwnedFb::new(4, 1).expect("fb");
+    let cursor = execute_drawlist(&drawlist_with_text(1), &mut next, &limits)
+        .unwrap_or_else(|e| panic!("{e:?}"));
+
+    let cold =
+        diff_to_bytes(&blank.0, &next.0, &caps, &limits, &cursor, None, false).expect("diff");
+    assert!(contains_subsequence(&cold, b"\x1b[2J"));
+
+    let mut text_style = style_plain();
+    text_style.fg_rgb = 0xFF_0000;
+    text_style.attrs = ATTR_BOLD;
+    let warm = diff_to_bytes(
+        &blank.0,
+        &next.0,
+        &caps,
+        &limits,
+        &cursor,
+        Some(&text_style),
+        false,
+    )
+    .expect("diff");
+    assert!(!contains_subsequence(&warm, b"\x1b[2J"));
     assert!(
-        contains_subsequence(&dim_to_bold, b"\x1b[0;1;"),
-        "expected dim->bold transition to emit bold SGR: {:?}",
-        String::from_utf8_lossy(&dim_to_bold),
+        !contains_subsequence(&warm, b"m"),
+        "no SGR when the style already matches"
     );
+    assert!(contains_subsequence(&warm, b"hi"));
+}
 
-    let bold_to_dim = render_style_transition(ATTR_BOLD, ATTR_DIM);
+#[test]
+fn engine_initial_style_seeds_the_first_successful_present_only() {
+    let mut style = style_plain();
+    style.fg_rgb = 0x00_FF00;
+    let unknown: ffi::zr_term_state_t = unsafe { std::mem::zeroed() };
+    let seeded = seed_style(unknown, style);
+    assert_eq!(seeded.style.fg_rgb, 0x00_FF00);
+    assert_ne!(seeded.flags & ffi::ZR_TERM_STATE_STYLE_VALID, 0);
+    let known = seed_style(seeded, style_plain());
+    assert_eq!(known.style.fg_rgb, 0x00_FF00, "a known style is kept");
+
+    let initial = InitialStyle::new(Some(style));
     assert!(
-        contains_subsequence(&bold_to_dim, b"\x1b[0;2;"),
-        "expected bold->dim transition to emit dim SGR: {:?}",
-        String::from_utf8_lossy(&bold_to_dim),
+        seeded_term_state(&unknown).is_none(),
+        "only inside a present"
     );
+    let rc = initial.apply(|| {
+        assert!(seeded_term_state(&unknown).is_some());
+        ffi::ZR_ERR_PLATFORM
+    });
+    assert_eq!(rc, ffi::ZR_ERR_PLATFORM);
+    initial.apply(|| {
+        assert!(
+            seeded_term_state(&unknown).is_some(),
+            "kept after a failure"
+        );
+        ffi::ZR_OK
+    });
+    initial.apply(|| {
+        assert!(seeded_term_state(&unknown).is_none());
+        ffi::ZR_OK
+    });
 }
 
 #[test]
-fn diff_preserves_non_intensity_attr_delta_path() {
-    let dim_to_dim_underline = render_style_transition(ATTR_DIM, ATTR_DIM | ATTR_UNDERLINE);
+fn diff_render_rejects_mismatched_framebuffer_sizes() {
+    let a = TestFramebuffer::new(4, 2);
+    let b = TestFramebuffer::new(4, 2);
+    let c = TestFramebuffer::new(3, 2);
+    assert!(check_same_size(&a.raw, &b.raw, "diffRender").is_ok());
+    let err = check_same_size(&a.raw, &c.raw, "diffRender").unwrap_err();
     assert!(
-        contains_subsequence(&dim_to_dim_underline, b"\x1b[0;2;4;"),
-        "expected underline+dim sequence in output: {:?}",
-        String::from_utf8_lossy(&dim_to_dim_underline),
+        err.reason.contains("prev is 4x2 but next is 3x2"),
+        "{}",
+        err.reason
+    );
+}
+
+#[test]
+fn make_style_palette_matches_differ_tables() {
+    assert_eq!(palette_rgb(1), 0xCD0000);
+    assert_eq!(palette_rgb(12), 0x5C5CFF);
+    assert_eq!(palette_rgb(16), 0x000000);
+    assert_eq!(palette_rgb(196), 0xFF0000);
+    assert_eq!(palette_rgb(231), 0xFFFFFF);
+    assert_eq!(palette_rgb(232), 0x080808);
+    assert_eq!(palette_rgb(255), 0xEEEEEE);
+}
+
+#[test]
+fn make_style_parses_hex_colors() {
+    assert_eq!(parse_hex_rgb("#1a2B3c"), Some(0x1A2B3C));
+    assert_eq!(parse_hex_rgb("ff8800"), Some(0xFF8800));
+    assert_eq!(parse_hex_rgb("#f80"), Some(0xFF8800));
+    assert_eq!(parse_hex_rgb("#ff88"), None);
+    assert_eq!(parse_hex_rgb("#gg0000"), None);
+    assert_eq!(parse_hex_rgb("+12345"), None);
+}
+
+#[test]
+fn engine_supports_maps_every_feature_name() {
+    let mut caps = crate::empty_terminal_caps();
+    for feature in crate::SUPPORTS_FEATURES {
+        assert_eq!(
+            crate::caps_supports(&caps, feature),
+            Some(false),
+            "{feature}"
+        );
+    }
+    assert_eq!(crate::caps_supports(&caps, "sixel"), None);
+
+    caps.color_mode = ffi::PLAT_COLOR_MODE_256;
+    caps.supports_hyperlinks = 1;
+    assert_eq!(crate::caps_supports(&caps, "truecolor"), Some(false));
+    assert_eq!(crate::caps_supports(&caps, "hyperlinks"), Some(true));
+    caps.color_mode = ffi::PLAT_COLOR_MODE_RGB;
+    assert_eq!(crate::caps_supports(&caps, "truecolor"), Some(true));
+}
+
+#[test]
+fn visual_bell_flashes_on_present_and_restores_after_duration() {
+    let output = TerminalOutput::default();
+    let t0 = std::time::Instant::now();
+    assert_eq!(output.take_after_present(t0), b"");
+    assert_eq!(output.bell(true), ffi::ZR_OK);
+    assert_eq!(output.take_after_present(t0), b"\x1b[?5h");
+    assert_eq!(output.take_expired(t0), b"", "flash still up");
+    assert_eq!(
+        output.take_after_present(t0 + VISUAL_BELL_DURATION),
+        b"\x1b[?5l"
+    );
+    assert_eq!(output.take_expired(t0 + VISUAL_BELL_DURATION * 2), b"");
+
+    assert_eq!(output.bell(true), ffi::ZR_OK);
+    assert_eq!(output.take_after_present(t0), b"\x1b[?5h");
+    assert_eq!(
+        output.take_restore(),
+        b"\x1b[?5l",
+        "destroy restores an active flash"
+    );
+    assert_eq!(output.take_restore(), b"");
+}
+
+#[test]
+fn title_is_sanitized_and_queued_in_call_order() {
+    assert_eq!(
+        sanitize_title("a\x1b]0;evil\x07b\u{9b}c\td\u{7f}"),
+        "a]0;evilbcd"
+    );
+    assert_eq!(
+        sanitize_title("Editing file.txt — Modified"),
+        "Editing file.txt — Modified"
+    );
+
+    let output = TerminalOutput::default();
+    output.title_stack(true);
+    output.set_title("x\ny");
+    let now = std::time::Instant::now();
+    assert_eq!(output.take_after_present(now), b"\x1b[22t\x1b]2;xy\x07");
+    assert!(output.take_after_present(now).is_empty());
+}
+
+#[test]
+fn raw_writes_are_fenced_and_bounded() {
+    let output = TerminalOutput::default();
+    output.set_title("t");
+    assert_eq!(output.write_raw(b""), ffi::ZR_OK);
+    assert!(!output.raw_queued(), "titles leave the screen alone");
+    assert_eq!(output.write_raw(b"\x1bPq#0~\x1b\\"), ffi::ZR_OK);
+    assert!(output.raw_queued());
+    let now = std::time::Instant::now();
+    assert_eq!(
+        output.take_after_present(now),
+        b"\x1b]2;t\x07\x1b7\x1bPq#0~\x1b\\\x1b8"
+    );
+    assert!(!output.raw_queued());
+
+    let big = vec![b'x'; RAW_QUEUE_MAX_BYTES];
+    assert_eq!(output.write_raw(&big), ffi::ZR_ERR_LIMIT);
+    assert!(output.take_after_present(now).is_empty());
+}
+
+#[test]
+fn idle_state_pauses_after_quiet_period_and_resumes_on_activity() {
+    let idle = IdleState::new(50);
+    let t0 = std::time::Instant::now();
+    let ms = std::time::Duration::from_millis;
+    idle.note_activity(t0);
+    idle.after_present(false, t0 + ms(20));
+    assert!(!idle.is_idle(), "quiet period not reached");
+    idle.after_present(true, t0 + ms(40));
+    idle.after_present(false, t0 + ms(80));
+    assert!(!idle.is_idle(), "damage restarts the quiet period");
+    idle.after_present(false, t0 + ms(95));
+    assert!(idle.is_idle());
+
+    idle.note_activity(t0 + ms(120));
+    assert!(!idle.is_idle());
+    let transitions = idle.take_transitions();
+    assert_eq!(transitions.len(), 2);
+    assert!(transitions[0].idle && transitions[0].durationMs == 55);
+    assert!(!transitions[1].idle && transitions[1].durationMs == 25);
+
+    let disabled = IdleState::new(0);
+    disabled.after_present(false, t0 + ms(10_000));
+    assert!(!disabled.is_idle());
+}
+
+fn event_batch(version: u32, records: &[(u32, u32, &[u32])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for &(ty, time_ms, payload) in records {
+        push_u32s(&mut body, &[ty, 16 + 4 * payload.len() as u32, time_ms, 0]);
+        push_u32s(&mut body, payload);
+    }
+    let mut out = Vec::new();
+    push_u32s(
+        &mut out,
+        &[
+            0x5645_525A,
+            version,
+            24 + body.len() as u32,
+            records.len() as u32,
+            0,
+            0,
+        ],
+    );
+    out.extend_from_slice(&body);
+    out
+}
+
+#[test]
+fn decode_event_batch_rejects_version_mismatch() {
+    let v1 = event_batch(1, &[(2, 7, &[0x41, 0])]);
+    let err = decode_batch(&v1, 2)
+        .err()
+        .expect("unsupported requested version");
+    assert_eq!(
+        err,
+        "event batch version 2 not supported by decoder (supports 1)"
+    );
+
+    let v3 = event_batch(3, &[(2, 7, &[0x41, 0])]);
+    let err = decode_batch(&v3, 1)
+        .err()
+        .expect("unsupported embedded version");
+    assert_eq!(
+        err,
+        "event batch version 3 not supported by decoder (supports 1)"
+    );
+
+    let records = decode_batch(&v1, 1).expect("v1 batch decodes");
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].kind, "text");
+    assert_eq!(records[0].time_ms, 7);
+    assert_eq!(records[0].payload, [0x41, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn decode_event_batch_rejects_truncated_input() {
+    let batch = event_batch(1, &[(1, 0, &[10, 0, 1, 0]), (99, 0, &[]), (6, 0, &[16, 0])]);
+    let records = decode_batch(&batch, 1).expect("unknown record types are skipped");
+    let kinds: Vec<_> = records.iter().map(|r| r.kind).collect();
+    assert_eq!(kinds, ["key", "tick"]);
+
+    assert!(decode_batch(&batch[..4], 1).is_err());
+    assert!(decode_batch(&batch[..batch.len() - 4], 1).is_err());
+    let mut oversized = batch.clone();
+    oversized[28..32].copy_from_slice(&1024u32.to_le_bytes());
+    assert!(decode_batch(&oversized, 1).is_err());
+}
+
+#[test]
+fn event_records_decode_their_payload_fields_by_kind() {
+    let paste = u32::from_le_bytes(*b"hi\xc3\xa9");
+    let batch = event_batch(
+        1,
+        &[
+            (1, 0, &[22, 2, 1, 0]),
+            (1, 0, &[31, 0, 1, 0]),
+            (2, 0, &[0x1F600, 0]),
+            (3, 0, &[4, 0, paste]),
+            (4, 0, &[3, 7, 5, 1, 0, 0, (-2i32) as u32, 0]),
+            (5, 0, &[80, 24, 0, 0]),
+            (6, 0, &[16, 0, 0, 0]),
+            (7, 0, &[9, 1, 0, 0, 0x7a]),
+        ],
+    );
+    let fields: Vec<_> = decode_batch(&batch, 1)
+        .expect("valid batch")
+        .iter()
+        .map(|r| record_fields(r).expect("well-formed payload"))
+        .collect();
+    let kinds: Vec<_> = fields.iter().map(|f| f.kind).collect();
+    assert_eq!(
+        kinds,
+        ["key", "focus", "text", "paste", "mouse", "resize", "tick", "user"]
+    );
+    assert_eq!(
+        (fields[0].key, fields[0].mods, fields[0].action),
+        (Some(22), Some(2), Some(1))
+    );
+    assert_eq!((fields[1].focused, fields[1].key), (Some(false), None));
+    assert_eq!(fields[2].text.as_deref(), Some("\u{1F600}"));
+    assert_eq!(fields[3].text.as_deref(), Some("hi\u{e9}"));
+    let m = &fields[4];
+    assert_eq!(
+        (m.x, m.y, m.mouse_kind, m.mods),
+        (Some(3), Some(7), Some(5), Some(1))
+    );
+    assert_eq!((m.wheel_x, m.wheel_y), (Some(0), Some(-2)));
+    assert_eq!((fields[5].cols, fields[5].rows), (Some(80), Some(24)));
+    assert_eq!(fields[6].dt_ms, Some(16));
+    assert_eq!(
+        (fields[7].tag, fields[7].data.as_deref()),
+        (Some(9), Some(&b"z"[..]))
+    );
+
+    let short = event_batch(1, &[(4, 0, &[3, 7])]);
+    let past_end = event_batch(1, &[(3, 0, &[9, 0, paste])]);
+    let surrogate = event_batch(1, &[(2, 0, &[0xD800, 0])]);
+    for bad in [short, past_end, surrogate] {
+        let records = decode_batch(&bad, 1).expect("framing is valid");
+        assert!(record_fields(&records[0]).is_err());
+    }
+}
+
+#[test]
+fn coalescing_merges_adjacent_runs_and_sums_wheel_deltas() {
+    let neg = |v: i32| v as u32;
+    let batch = event_batch(
+        1,
+        &[
+            (4, 1, &[1, 1, 1, 0, 0, 0, 0, 0]),
+            (4, 2, &[2, 2, 1, 0, 0, 0, 0, 0]),
+            (4, 3, &[3, 3, 1, 0, 0, 0, 0, 0]),
+            (4, 4, &[3, 3, 5, 0, 0, 0, 1, 0]),
+            (4, 5, &[3, 3, 5, 0, 1, 0, neg(-3), 0]),
+            (1, 6, &[10, 0, 1, 0]),
+            (4, 7, &[3, 3, 5, 0, 0, 0, 1, 0]),
+            (4, 8, &[3, 3, 5, 4, 0, 0, 1, 0]),
+            (5, 9, &[80, 24, 0, 0]),
+            (5, 10, &[100, 30, 0, 0]),
+        ],
+    );
+    let records = decode_batch(&batch, 1).expect("valid batch");
+    assert_eq!(
+        coalesce_records(
+            decode_batch(&batch, 1).expect("valid batch"),
+            CoalescePolicy::default()
+        )
+        .len(),
+        10
+    );
+
+    let policy = CoalescePolicy {
+        mouse_move: true,
+        mouse_drag: false,
+        wheel: true,
+        resize: true,
+    };
+    let out = coalesce_records(records, policy);
+    let times: Vec<_> = out.iter().map(|r| r.time_ms).collect();
+    assert_eq!(
+        times,
+        [3, 5, 6, 7, 8, 10],
+        "keys and modifier changes split runs"
+    );
+    assert_eq!(&out[0].payload[..4], &3i32.to_le_bytes());
+    assert_eq!(&out[1].payload[24..28], &(-2i32).to_le_bytes());
+    assert_eq!(&out[1].payload[16..20], &1u32.to_le_bytes());
+    assert_eq!(&out[5].payload[..4], &100u32.to_le_bytes());
+}
+
+#[test]
+fn drop_counters_classify_poll_and_post_outcomes() {
+    let drops = DropCounters::default();
+    let mut truncated = event_batch(1, &[(6, 0, &[16, 0])]);
+    truncated[16] = 1;
+    drops.note_poll(truncated.len() as i32, &truncated);
+    drops.note_poll(40, &event_batch(1, &[(6, 0, &[16, 0])]));
+    drops.note_poll(ffi::ZR_ERR_LIMIT, &[]);
+    drops.note_poll(0, &[]);
+    drops.note_user_post(ffi::ZR_ERR_LIMIT, ENGINE_USER_BYTES_CAP + 1);
+    drops.note_user_post(ffi::ZR_ERR_LIMIT, 16);
+    drops.note_user_post(ffi::ZR_OK, 16);
+
+    let mut m = crate::empty_metrics();
+    m.events_dropped_total = 7;
+    m.input_parse_errors_total = 2;
+    m.paste_dropped_total = 3;
+    let r = drops.snapshot(&m);
+    assert_eq!(
+        (
+            r.queueFull,
+            r.pollTruncated,
+            r.pollBufferTooSmall,
+            r.userPayloadTooLarge,
+            r.userQueueFull
+        ),
+        (7, 1, 1, 1, 1)
+    );
+    assert_eq!((r.parseErrors, r.pasteTooLarge), (2, 3));
+}
+
+#[test]
+fn frame_shadow_follows_resizes_and_persistent_resources() {
+    let shadow = FrameShadow::new(true);
+    assert!(shadow.export().is_err(), "no size before the first resize");
+
+    let resize = event_batch(1, &[(5, 0, &[4, 2, 0, 0])]);
+    shadow.note_poll(resize.len() as i32, &resize);
+    shadow.note_submit(ffi::ZR_OK, &drawlist_with_text(1));
+    shadow.note_submit(ffi::ZR_ERR_FORMAT, &[]);
+
+    let Ok(rows) = shadow.export() else {
+        panic!("export after resize and submit must succeed");
+    };
+    assert_eq!((rows.len(), rows[0].len()), (2, 4));
+    let h = &rows[0][1];
+    assert_eq!(
+        (h.glyph.as_str(), h.fg, h.attrs, h.width),
+        ("h", 0xFF_0000, ATTR_BOLD, 1)
+    );
+    assert_eq!(rows[0][2].glyph, "i");
+    assert_eq!(rows[1][0].glyph, " ");
+
+    /* String 1 was defined by the previous frame; the replay must see it. */
+    let mut cmds = Vec::new();
+    push_cmd(
+        &mut cmds,
+        ffi::ZR_DL_OP_DRAW_TEXT,
+        &[0, 1, 1, 1, 1, 0x00_00FF, 0, 0, 0, 0, 0, 0, 0],
+    );
+    shadow.note_submit(ffi::ZR_OK, &drawlist_from_cmds(&cmds, 1));
+    let Ok(rows) = shadow.export() else {
+        panic!("resources must persist across submits");
+    };
+    assert_eq!((rows[1][0].glyph.as_str(), rows[1][0].fg), ("i", 0x00_00FF));
+    assert_eq!(rows[0][1].glyph, "h");
+
+    let resize = event_batch(1, &[(5, 0, &[3, 1, 0, 0])]);
+    shadow.note_poll(resize.len() as i32, &resize);
+    let Ok(rows) = shadow.export() else {
+        panic!("export after resize must succeed");
+    };
+    assert_eq!(
+        (rows.len(), rows[0].len(), rows[0][1].glyph.as_str()),
+        (1, 3, " ")
+    );
+}
+
+#[test]
+fn frame_shadow_without_tracking_follows_only_the_size() {
+    let shadow = FrameShadow::new(false);
+    let resize = event_batch(1, &[(5, 0, &[4, 2, 0, 0])]);
+    shadow.note_poll(resize.len() as i32, &resize);
+    shadow.note_submit(ffi::ZR_OK, &drawlist_with_text(1));
+
+    assert_eq!(shadow.size(), Some((4, 2)));
+    assert_eq!(shadow.tracked_size(), Ok((4, 2)));
+    let Err(msg) = shadow.export() else {
+        panic!("export needs frame tracking");
+    };
+    assert!(msg.contains("trackFrame"), "{msg}");
+    assert!(shadow.save().is_err());
+}
+
+#[test]
+fn signal_restore_leaves_every_mode_the_engine_enters() {
+    for name in ["SIGTERM", "SIGINT", "SIGHUP"] {
+        assert!(signal_number(name).is_some(), "{name} must be accepted");
+    }
+    assert!(signal_number("SIGKILL").is_none());
+    assert!(signal_number("sigterm").is_none());
+
+    for mode in [
+        &b"\x1b[?1000l"[..],
+        b"\x1b[?1002l",
+        b"\x1b[?1003l",
+        b"\x1b[?1006l",
+        b"\x1b[?1004l",
+        b"\x1b[?2004l",
+        b"\x1b[?25h",
+        b"\x1b[0m",
+    ] {
+        assert!(contains_subsequence(RESTORE_MODES, mode));
+    }
+    assert!(!contains_subsequence(RESTORE_MODES, LEAVE_ALT_SCREEN));
+}
+
+/// Signal dispositions are process-wide; tests that change them take turns.
+#[cfg(unix)]
+static SIGNAL_TESTS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(unix)]
+#[test]
+fn signal_handlers_put_back_the_action_they_replaced_on_disarm() {
+    use crate::signals::{current_action, disarm, install};
+
+    let _serial = SIGNAL_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let sig = libc::SIGHUP;
+    let before = unsafe { libc::signal(sig, libc::SIG_IGN) };
+    let engine_id = u32::MAX - 7;
+    assert_eq!(install(engine_id, false, &[sig]), ffi::ZR_OK);
+    assert_ne!(current_action(sig), libc::SIG_IGN);
+    /* Installing again must not save our own handler as the previous one. */
+    assert_eq!(install(engine_id, false, &[sig]), ffi::ZR_OK);
+
+    disarm(engine_id + 1);
+    assert_ne!(current_action(sig), libc::SIG_IGN);
+    disarm(engine_id);
+    assert_eq!(current_action(sig), libc::SIG_IGN);
+
+    unsafe { libc::signal(sig, before) };
+}
+
+/// Everything written to the restore fd while `f` runs.
+#[cfg(unix)]
+fn capture_restore_bytes(f: impl FnOnce()) -> Vec<u8> {
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+
+    let out = crate::signals::out_fd();
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    /* Processes other tests spawn meanwhile must not hold the pipe open. */
+    for fd in fds {
+        unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+    let saved = unsafe { libc::dup(out) };
+    unsafe { libc::dup2(fds[1], out) };
+    f();
+    unsafe {
+        libc::dup2(saved, out);
+        libc::close(saved);
+        libc::close(fds[1]);
+    }
+    let mut bytes = Vec::new();
+    let mut reader = unsafe { std::fs::File::from_raw_fd(fds[0]) };
+    reader.read_to_end(&mut bytes).unwrap();
+    bytes
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_handlers_chain_to_an_app_handler_without_restoring() {
+    use crate::signals::{current_action, disarm, install, is_armed};
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    extern "C" fn app_handler(_sig: std::ffi::c_int) {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+    let app = app_handler as extern "C" fn(std::ffi::c_int) as libc::sighandler_t;
+
+    let _serial = SIGNAL_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let sig = libc::SIGHUP;
+    let before = unsafe { libc::signal(sig, app) };
+    let engine_id = u32::MAX - 9;
+    assert_eq!(install(engine_id, false, &[sig]), ffi::ZR_OK);
+
+    let written = capture_restore_bytes(|| unsafe {
+        libc::raise(sig);
+    });
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    assert!(written.is_empty(), "the app handler owns the terminal");
+    assert!(is_armed(engine_id));
+    assert_ne!(current_action(sig), app, "still installed for the next one");
+
+    disarm(engine_id);
+    assert_eq!(current_action(sig), app);
+    unsafe { libc::signal(sig, before) };
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_handlers_restore_before_the_default_action_terminates() {
+    use crate::signals::{disarm, install};
+
+    let _serial = SIGNAL_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let sig = libc::SIGHUP;
+    let before = unsafe { libc::signal(sig, libc::SIG_DFL) };
+    let engine_id = u32::MAX - 10;
+    assert_eq!(install(engine_id, false, &[sig]), ffi::ZR_OK);
+
+    let mut status = 0;
+    let written = capture_restore_bytes(|| {
+        /* The child only raises: nothing that could deadlock on a lock
+         * another test thread held at fork time. */
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            unsafe {
+                libc::raise(sig);
+                libc::_exit(0);
+            }
+        }
+        assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    });
+    assert!(
+        libc::WIFSIGNALED(status),
+        "the default action must still run"
+    );
+    assert_eq!(libc::WTERMSIG(status), sig);
+    assert!(contains_subsequence(&written, RESTORE_MODES));
+    assert!(contains_subsequence(&written, LEAVE_ALT_SCREEN));
+
+    disarm(engine_id);
+    unsafe { libc::signal(sig, before) };
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_handlers_stay_armed_for_every_engine_that_installed_them() {
+    use crate::signals::{current_action, disarm, install, is_armed};
+
+    let _serial = SIGNAL_TESTS.lock().unwrap_or_else(|e| e.into_inner());
+    let sig = libc::SIGHUP;
+    let before = unsafe { libc::signal(sig, libc::SIG_IGN) };
+    let (first, second) = (u32::MAX - 11, u32::MAX - 12);
+    assert_eq!(install(first, false, &[sig]), ffi::ZR_OK);
+    assert_eq!(install(second, true, &[sig]), ffi::ZR_OK);
+    assert!(
+        is_armed(first),
+        "a second install must not disarm the first"
+    );
+    assert!(is_armed(second));
+
+    disarm(first);
+    assert!(!is_armed(first));
+    assert!(is_armed(second));
+    assert_ne!(
+        current_action(sig),
+        libc::SIG_IGN,
+        "second engine still armed"
+    );
+    disarm(second);
+    assert_eq!(current_action(sig), libc::SIG_IGN);
+
+    unsafe { libc::signal(sig, before) };
+}
+
+#[cfg(unix)]
+#[test]
+fn engines_bind_caller_fds_and_reject_unusable_ones() {
+    use crate::config::{fd_allows, FdAccess};
+
+    let pipe = || {
+        let mut fds = [-1; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        (fds[0], fds[1])
+    };
+    let (in_r, in_w) = pipe();
+    let (out_r, out_w) = pipe();
+    assert_eq!(fd_allows(in_r, FdAccess::Read), Some(true));
+    assert_eq!(fd_allows(in_r, FdAccess::Write), Some(false));
+    assert_eq!(fd_allows(out_w, FdAccess::Write), Some(true));
+    assert_eq!(fd_allows(-1, FdAccess::Read), None);
+
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let create = |input_fd: i32, output_fd: i32| {
+        let io = ffi::zr_engine_io_t {
+            input_fd,
+            output_fd,
+        };
+        let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+        let rc = unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) };
+        (rc, engine)
+    };
+    assert_eq!(create(in_w, out_w).0, ffi::ZR_ERR_INVALID_ARGUMENT);
+    assert_eq!(create(in_r, out_r).0, ffi::ZR_ERR_INVALID_ARGUMENT);
+
+    let (rc, engine) = create(in_r, out_w);
+    assert_eq!(rc, ffi::ZR_OK);
+    assert_eq!(unsafe { libc::write(in_w, b"q".as_ptr().cast(), 1) }, 1);
+    let mut out = [0u8; 256];
+    let mut kinds = Vec::new();
+    for _ in 0..2 {
+        let n = unsafe { ffi::engine_poll_events(engine, 10, out.as_mut_ptr(), 256) };
+        assert!(n >= 0);
+        let records = decode_batch(&out[..n as usize], 1).unwrap_or_default();
+        kinds.extend(records.iter().map(|r| (r.kind, r.payload.first().copied())));
+    }
+    unsafe { ffi::engine_destroy(engine) };
+    assert!(
+        kinds.contains(&("text", Some(b'q'))),
+        "input came from the pipe: {kinds:?}"
+    );
+    assert_eq!(
+        unsafe { libc::fcntl(in_r, libc::F_GETFL) } & libc::O_NONBLOCK,
+        0,
+        "destroy restores the input fd's flags and leaves it open"
+    );
+    for fd in [in_r, in_w, out_r, out_w] {
+        unsafe { libc::close(fd) };
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn bound_engines_write_binding_output_to_their_own_fd() {
+    let stdio_only = BindingCreateOptions::default();
+    assert_eq!(stdio_only.terminal_target(), TerminalTarget::Process);
+    let input_only = BindingCreateOptions {
+        input_fd: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(input_only.terminal_target(), TerminalTarget::Fd(1));
+
+    let mut fds = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    let bound = BindingCreateOptions {
+        output_fd: Some(fds[1]),
+        ..Default::default()
+    };
+    let output = TerminalOutput::new(bound.terminal_target());
+    assert!(!output.target().is_tty(), "a pipe is not a terminal");
+    assert_eq!(output.bell(false), ffi::ZR_OK);
+    output.set_title("t");
+    let t0 = std::time::Instant::now();
+    assert_eq!(
+        output.target().write(&output.take_after_present(t0)),
+        ffi::ZR_OK
+    );
+    assert_eq!(drain_terminal(output.target(), true), ffi::ZR_OK);
+
+    let mut buf = [0u8; 16];
+    let n = unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(&buf[..n as usize], b"\x07\x1b]2;t\x07");
+    assert!(
+        unsafe { libc::fcntl(fds[1], libc::F_GETFD) } >= 0,
+        "writes borrow the fd without closing it"
+    );
+    for fd in fds {
+        unsafe { libc::close(fd) };
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn user_event_batches_stop_at_the_first_refused_event() {
+    let mut input = [-1; 2];
+    let mut output = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(input.as_mut_ptr()) }, 0);
+    assert_eq!(unsafe { libc::pipe(output.as_mut_ptr()) }, 0);
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let opts = BindingCreateOptions {
+        input_fd: Some(input[0]),
+        output_fd: Some(output[1]),
+        ..Default::default()
+    };
+    let io = opts.engine_io();
+    let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+    assert_eq!(
+        unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) },
+        ffi::ZR_OK
+    );
+    let id = register_engine(engine, &cfg, &opts).expect("register");
+    let guard = get_engine_guard(id).expect("guard");
+    let payload = vec![0u8; ENGINE_USER_BYTES_CAP + 1];
+    let event = |tag, len| (tag, &payload[..len]);
+    let posted_tags = || {
+        let mut out = vec![0u8; 4096];
+        let n = unsafe { ffi::engine_poll_events(engine, 0, out.as_mut_ptr(), 4096) };
+        assert!(n >= 0);
+        decode_batch(&out[..n as usize], 1)
+            .unwrap_or_default()
+            .iter()
+            .filter(|r| r.kind == "user")
+            .map(|r| u32::from_le_bytes(r.payload[..4].try_into().unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    let err = post_batch_to_slot(
+        &guard.slot,
+        vec![event(1, 4), event(UNKNOWN_SEQUENCE_TAG, 4), event(3, 4)],
+    )
+    .expect_err("reserved tag");
+    assert_eq!(err.status, ZrCode(ffi::ZR_ERR_INVALID_ARGUMENT));
+    assert!(
+        err.reason.starts_with("enginePostUserEvents: event 1:"),
+        "{}",
+        err.reason
+    );
+    assert_eq!(
+        posted_tags(),
+        [1],
+        "events before the refused one stay posted"
+    );
+
+    let err = post_batch_to_slot(
+        &guard.slot,
+        vec![event(4, 0), event(5, ENGINE_USER_BYTES_CAP + 1)],
+    )
+    .expect_err("payload over the limit");
+    assert_eq!(err.status, ZrCode(ffi::ZR_ERR_LIMIT));
+    assert!(
+        err.reason.starts_with("enginePostUserEvents: event 1:"),
+        "{}",
+        err.reason
+    );
+    assert_eq!(posted_tags(), [4]);
+
+    drop(guard);
+    let slot = take_engine_for_owner(id).expect("owner");
+    unsafe { ffi::engine_destroy(slot.engine) };
+    for fd in input.into_iter().chain(output) {
+        unsafe { libc::close(fd) };
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn engine_wake_ends_a_blocked_poll_without_an_event() {
+    let mut input = [-1; 2];
+    let mut output = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(input.as_mut_ptr()) }, 0);
+    assert_eq!(unsafe { libc::pipe(output.as_mut_ptr()) }, 0);
+    let mut cfg = unsafe { ffi::zr_engine_config_default() };
+    /* One tick a second, so a tick cannot end the wait either. */
+    cfg.target_fps = 1;
+    let io = ffi::zr_engine_io_t {
+        input_fd: input[0],
+        output_fd: output[1],
+    };
+    let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+    assert_eq!(
+        unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) },
+        ffi::ZR_OK
+    );
+    let mut out = [0u8; 256];
+    /* Drain the initial resize so the next poll has to wait. */
+    while unsafe { ffi::engine_poll_events(engine, 0, out.as_mut_ptr(), 256) } > 0 {}
+
+    let handle = engine as usize;
+    let waker = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        unsafe { ffi::engine_wake(handle as *mut ffi::zr_engine_t) }
+    });
+    let started = std::time::Instant::now();
+    let rc = unsafe { ffi::engine_poll_events(engine, 10_000, out.as_mut_ptr(), 256) };
+    let waited = started.elapsed();
+    assert_eq!(waker.join().unwrap(), ffi::ZR_OK);
+    unsafe { ffi::engine_destroy(engine) };
+    for fd in input.into_iter().chain(output) {
+        unsafe { libc::close(fd) };
+    }
+    assert_eq!(rc, 0, "a wake queues nothing");
+    assert!(
+        waited < std::time::Duration::from_millis(500),
+        "woken early: {waited:?}"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn engine_presents_erase_blank_tails_and_count_them() {
+    let present_erases = |enable: u8| {
+        let mut input = [-1; 2];
+        let mut output = [-1; 2];
+        assert_eq!(unsafe { libc::pipe(input.as_mut_ptr()) }, 0);
+        assert_eq!(unsafe { libc::pipe(output.as_mut_ptr()) }, 0);
+        let mut cfg = unsafe { ffi::zr_engine_config_default() };
+        cfg.enable_erase_optimizations = enable;
+        let io = ffi::zr_engine_io_t {
+            input_fd: input[0],
+            output_fd: output[1],
+        };
+        let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+        assert_eq!(
+            unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) },
+            ffi::ZR_OK
+        );
+        /* A blue screen with three red cells in the top-left corner. */
+        let mut cmds = Vec::new();
+        push_cmd(
+            &mut cmds,
+            ffi::ZR_DL_OP_FILL_RECT,
+            &[0, 0, 1000, 1000, 0, 0x00_00AA, 0, 0, 0, 0, 0],
+        );
+        push_cmd(
+            &mut cmds,
+            ffi::ZR_DL_OP_FILL_RECT,
+            &[0, 0, 3, 1, 0, 0xAA_0000, 0, 0, 0, 0, 0],
+        );
+        let dl = drawlist_from_cmds(&cmds, 2);
+        let mut m = crate::empty_metrics();
+        let rc = unsafe {
+            let rc = ffi::engine_submit_drawlist(engine, dl.as_ptr(), dl.len() as i32);
+            if rc == ffi::ZR_OK {
+                ffi::engine_present(engine)
+            } else {
+                rc
+            }
+        };
+        assert_eq!(
+            unsafe { ffi::engine_get_metrics(engine, &mut m) },
+            ffi::ZR_OK
+        );
+        unsafe { ffi::engine_destroy(engine) };
+        for fd in input.into_iter().chain(output) {
+            unsafe { libc::close(fd) };
+        }
+        assert_eq!(rc, ffi::ZR_OK);
+        (m.diff_line_erases_total, m.diff_screen_erases_total)
+    };
+
+    assert_eq!(present_erases(0), (0, 0));
+    assert_eq!(present_erases(1), (1, 1));
+}
+
+#[cfg(unix)]
+#[test]
+fn engine_traces_each_dropped_event_with_its_reason() {
+    let mut input = [-1; 2];
+    let mut output = [-1; 2];
+    assert_eq!(unsafe { libc::pipe(input.as_mut_ptr()) }, 0);
+    assert_eq!(unsafe { libc::pipe(output.as_mut_ptr()) }, 0);
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let io = ffi::zr_engine_io_t {
+        input_fd: input[0],
+        output_fd: output[1],
+    };
+    let mut engine: *mut ffi::zr_engine_t = std::ptr::null_mut();
+    assert_eq!(
+        unsafe { ffi::engine_create_io(&mut engine, &cfg, &io) },
+        ffi::ZR_OK
+    );
+    let debug = ffi::zr_debug_config_t {
+        enabled: 1,
+        ring_capacity: 64,
+        min_severity: ffi::ZR_DEBUG_SEV_TRACE,
+        category_mask: u32::MAX,
+        capture_raw_events: 0,
+        capture_drawlist_bytes: 0,
+        _pad0: 0,
+        _pad1: 0,
+    };
+    assert_eq!(
+        unsafe { ffi::engine_debug_enable(engine, &debug) },
+        ffi::ZR_OK
+    );
+
+    /* A CSI with an unknown final byte, then a payload no ring can hold. */
+    let csi = b"\x1b[12;34y";
+    assert_eq!(
+        unsafe { libc::write(input[1], csi.as_ptr().cast(), csi.len()) },
+        csi.len() as isize
+    );
+    let big = vec![0u8; ENGINE_USER_BYTES_CAP + 1];
+    assert_eq!(
+        unsafe { ffi::engine_post_user_event(engine, 1, big.as_ptr(), big.len() as i32) },
+        ffi::ZR_ERR_LIMIT
+    );
+    let mut out = [0u8; 1024];
+    for _ in 0..2 {
+        assert!(unsafe { ffi::engine_poll_events(engine, 10, out.as_mut_ptr(), 1024) } >= 0);
+    }
+
+    let mut m = crate::empty_metrics();
+    assert_eq!(
+        unsafe { ffi::engine_get_metrics(engine, &mut m) },
+        ffi::ZR_OK
+    );
+    let query = ffi::zr_debug_query_t {
+        min_record_id: 0,
+        max_record_id: 0,
+        min_frame_id: 0,
+        max_frame_id: 0,
+        category_mask: 1 << ffi::ZR_DEBUG_CAT_EVENT,
+        min_severity: ffi::ZR_DEBUG_SEV_TRACE,
+        max_records: 0,
+        _pad0: 0,
+    };
+    let mut headers = [ffi::zr_debug_record_header_t {
+        record_id: 0,
+        timestamp_us: 0,
+        frame_id: 0,
+        category: 0,
+        severity: 0,
+        code: 0,
+        payload_size: 0,
+    }; 64];
+    let mut result = ffi::zr_debug_query_result_t {
+        records_returned: 0,
+        records_available: 0,
+        oldest_record_id: 0,
+        newest_record_id: 0,
+        records_dropped: 0,
+        _pad0: 0,
+    };
+    let rc =
+        unsafe { ffi::engine_debug_query(engine, &query, headers.as_mut_ptr(), 64, &mut result) };
+    let mut reasons = Vec::new();
+    for h in &headers[..result.records_returned as usize] {
+        if h.code != 0x0203 {
+            continue;
+        }
+        let mut payload = [0u8; 32];
+        let mut size = 0u32;
+        let rc = unsafe {
+            ffi::engine_debug_get_payload(engine, h.record_id, payload.as_mut_ptr(), 32, &mut size)
+        };
+        assert_eq!((rc, size), (ffi::ZR_OK, 32));
+        assert_eq!(h.severity, ffi::ZR_DEBUG_SEV_WARN);
+        reasons.push(u32::from_le_bytes([
+            payload[28],
+            payload[29],
+            payload[30],
+            payload[31],
+        ]));
+    }
+    unsafe { ffi::engine_destroy(engine) };
+    for fd in input.into_iter().chain(output) {
+        unsafe { libc::close(fd) };
+    }
+    assert_eq!(rc, ffi::ZR_OK);
+    assert_eq!(m.input_parse_errors_total, 1);
+    reasons.sort_unstable();
+    assert_eq!(reasons, [3, 6], "payload too large and parse error");
+}
+
+#[test]
+fn prewarm_grows_arena_within_cap_and_never_shrinks() {
+    let cell = std::mem::size_of::<ffi::zr_cell_t>() as u32;
+    assert_eq!(prewarm_arena_bytes(80, 24), 80 * 24 * cell);
+    assert_eq!(prewarm_arena_bytes(u32::MAX, u32::MAX), u32::MAX);
+
+    let mut limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    limits.arena_initial_bytes = 4096;
+    limits.arena_max_total_bytes = 1 << 20;
+    assert_eq!(
+        prewarm_initial_bytes(&limits, 64 * 1024),
+        (64 * 1024, false)
+    );
+    assert_eq!(prewarm_initial_bytes(&limits, 1024), (4096, false));
+    assert_eq!(prewarm_initial_bytes(&limits, 4 << 20), (1 << 20, true));
+}
+
+#[cfg(not(feature = "assert-log"))]
+#[test]
+fn assert_mode_keeps_vendored_abort_without_feature() {
+    assert!(apply_assert_mode("abort", None).is_ok());
+    for mode in ["log", "error"] {
+        let err = apply_assert_mode(mode, None).unwrap_err();
+        assert_eq!(err.status.as_ref(), "ZR_ERR_UNSUPPORTED");
+    }
+    let err = apply_assert_mode("panic", None).unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(take_assert_failures().is_empty());
+    assert_eq!(AssertScope::begin().finish(ffi::ZR_OK), ffi::ZR_OK);
+}
+
+#[cfg(feature = "assert-log")]
+#[test]
+fn assert_failures_are_reported_and_fail_the_call_in_error_mode() {
+    use std::ffi::{c_char, c_int};
+    extern "C" {
+        fn zr_assert_fail(file: *const c_char, line: c_int, expr: *const c_char);
+    }
+
+    let heard = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = heard.clone();
+    let listener: crate::asserts::AssertListener =
+        Box::new(move |msg: &str| sink.lock().unwrap().push(msg.to_owned()));
+    assert!(apply_assert_mode("error", Some(listener)).is_ok());
+    let scope = AssertScope::begin();
+    unsafe {
+        zr_assert_fail(
+            c"src/core/zr_framebuffer.c".as_ptr(),
+            812,
+            c"width <= 2".as_ptr(),
+        )
+    };
+    assert_eq!(scope.finish(ffi::ZR_OK), crate::asserts::ASSERT_FAILED);
+    assert_eq!(AssertScope::begin().finish(ffi::ZR_OK), ffi::ZR_OK);
+    assert_eq!(
+        take_assert_failures(),
+        vec!["assertion failed: width <= 2 at zr_framebuffer.c:812".to_string()]
+    );
+    assert_eq!(
+        *heard.lock().unwrap(),
+        ["assertion failed: width <= 2 at zr_framebuffer.c:812"],
+        "the listener hears the failure"
+    );
+    assert!(apply_assert_mode("abort", None).is_ok());
+}
+
+#[test]
+fn terminal_names_follow_terminal_id_order() {
+    assert_eq!(crate::terminal_name(0), "unknown");
+    assert_eq!(crate::terminal_name(1), "kitty");
+    assert_eq!(crate::terminal_name(9), "windows-terminal");
+    assert_eq!(crate::terminal_name(14), "screen");
+    assert_eq!(
+        crate::terminal_name(15),
+        "unknown",
+        "ZR_TERM_COUNT is not a terminal"
+    );
+}
+
+#[test]
+fn debug_category_counts_bucket_unknown_categories() {
+    let header = |category| ffi::zr_debug_record_header_t {
+        record_id: 0,
+        timestamp_us: 0,
+        frame_id: 0,
+        category,
+        severity: ffi::ZR_DEBUG_SEV_INFO,
+        code: 0,
+        payload_size: 0,
+    };
+    let headers = [
+        header(ffi::ZR_DEBUG_CAT_EVENT),
+        header(ffi::ZR_DEBUG_CAT_EVENT),
+        header(ffi::ZR_DEBUG_CAT_FRAME),
+        header(ffi::ZR_DEBUG_CAT_PERF),
+        header(99),
+    ];
+    let counts = count_debug_categories(&headers);
+    assert_eq!(counts.event, 2);
+    assert_eq!(counts.frame, 1);
+    assert_eq!(counts.perf, 1);
+    assert_eq!(counts.unknown, 1);
+    assert_eq!(
+        counts.none + counts.drawlist + counts.error + counts.state,
+        0
+    );
+}
+
+#[test]
+fn debug_name_tables_match_the_vendored_header() {
+    let header = include_str!(concat!(env!("OUT_DIR"), "/zireael/include/zr/zr_debug.h"));
+    let mut seen = 0;
+    for line in header.lines() {
+        let Some((name, rest)) = line.trim().split_once(" = ") else {
+            continue;
+        };
+        let value = rest.split(',').next().unwrap_or(rest);
+        let lookup = if let Some(cat) = name.strip_prefix("ZR_DEBUG_CAT_") {
+            (
+                cat,
+                debug_category_name(value.parse().expect("category value")),
+            )
+        } else if let Some(sev) = name.strip_prefix("ZR_DEBUG_SEV_") {
+            (
+                sev,
+                debug_severity_name(value.parse().expect("severity value")),
+            )
+        } else {
+            continue;
+        };
+        assert_eq!(
+            Some(lookup.0.to_ascii_lowercase().as_str()),
+            lookup.1,
+            "{name}"
+        );
+        seen += 1;
+    }
+    assert_eq!(seen, 11, "7 categories and 4 severities");
+}
+
+#[test]
+fn debug_export_size_counts_headers_and_payloads() {
+    let header = |payload_size| ffi::zr_debug_record_header_t {
+        record_id: 0,
+        timestamp_us: 0,
+        frame_id: 0,
+        category: ffi::ZR_DEBUG_CAT_FRAME,
+        severity: ffi::ZR_DEBUG_SEV_INFO,
+        code: 0,
+        payload_size,
+    };
+    assert_eq!(debug_export_size(&[]), 0);
+    assert_eq!(debug_export_size(&[header(0)]), 40);
+    assert_eq!(
+        debug_export_size(&[header(12), header(0), header(4)]),
+        3 * 40 + 16
+    );
+}
+
+#[test]
+fn debug_export_json_decodes_known_payloads() {
+    let record = |id: u64, category: u32, code: u32, payload: &[u8]| {
+        let mut bytes = Vec::new();
+        for v in [id, 1000 + id, 7] {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        push_u32s(
+            &mut bytes,
+            &[category, ffi::ZR_DEBUG_SEV_INFO, code, payload.len() as u32],
+        );
+        bytes.extend_from_slice(payload);
+        bytes
+    };
+    let mut frame = 7u64.to_le_bytes().to_vec();
+    push_u32s(&mut frame, &[80, 24, 0, 0, 512, 3, 90, 2, 0, 0, 0, 0]);
+    let mut blob = record(1, ffi::ZR_DEBUG_CAT_FRAME, 0x0102, &frame);
+    blob.extend(record(2, ffi::ZR_DEBUG_CAT_DRAWLIST, 0x0302, b"Ma\"\x01"));
+
+    let json = debug_export_to_json(&blob).expect("whole records");
+    assert!(json.starts_with(r#"{"version":1,"records":[{"recordId":1,"timestampUs":1001,"frameId":7,"category":1,"categoryName":"frame""#));
+    assert!(json.contains(
+        r#""codeName":"frame_present","payloadSize":56,"payload":{"frameId":7,"cols":80,"rows":24,"#
+    ));
+    assert!(
+        json.contains(r#""diffBytesEmitted":512,"dirtyLines":3,"dirtyCells":90,"damageRects":2,"#)
+    );
+    assert!(json.ends_with(
+        r#""codeName":"drawlist_cmd","payloadSize":4,"payload":null,"payloadBase64":"TWEiAQ=="}]}"#
+    ));
+
+    assert_eq!(
+        debug_export_to_json(&[]).as_deref(),
+        Ok(r#"{"version":1,"records":[]}"#)
+    );
+    assert!(debug_export_to_json(&blob[..blob.len() - 1]).is_err());
+    assert!(debug_export_to_json(&blob[..20]).is_err());
+}
+
+#[test]
+fn diff_telemetry_payload_decodes_path_flags() {
+    let mut payload = Vec::new();
+    for total in [1u64, 2, 3, 4, 5, 6] {
+        payload.extend_from_slice(&total.to_le_bytes());
+    }
+    payload.extend_from_slice(&[0, 1, 1, 0]);
+    push_u32s(&mut payload, &[7, 0, 0]);
+    let path = parse_diff_telemetry(&payload).expect("64-byte payload");
+    assert!(!path.sweepUsed && path.damageUsed);
+    assert!(path.scrollOptAttempted && !path.scrollOptHit);
+    assert_eq!(path.collisionGuardHits, 7);
+    assert_eq!(path.collisionGuardHitsTotal.words, vec![6]);
+    assert!(parse_diff_telemetry(&payload[..52]).is_none());
+}
+
+#[test]
+fn checked_u8_rejects_out_of_range_values() {
+    assert_eq!(checked_u8(0), Ok(0));
+    assert_eq!(checked_u8(255), Ok(255));
+    assert!(checked_u8(256).is_err());
+}
+
+#[test]
+fn diff_emits_dim_and_normal_intensity_sequences() {
+    let to_dim = render_style_transition(0, ATTR_DIM);
+    assert!(
+        contains_subsequence(&to_dim, b"\x1b[0;2;"),
+        "expected dim SGR sequence in output: {:?}",
+        String::from_utf8_lossy(&to_dim),
+    );
+
+    let to_normal = render_style_transition(ATTR_DIM, 0);
+    assert!(
+        contains_subsequence(&to_normal, b"\x1b[0;38;"),
+        "expected normal-intensity SGR sequence in output: {:?}",
+        String::from_utf8_lossy(&to_normal),
+    );
+}
+
+#[test]
+fn diff_reapplies_intensity_when_switching_bold_and_dim() {
+    let dim_to_bold = render_style_transition(ATTR_DIM, ATTR_BOLD);
+    assert!(
+        contains_subsequence(&dim_to_bold, b"\x1b[0;1;"),
+        "expected dim->bold transition to emit bold SGR: {:?}",
+        String::from_utf8_lossy(&dim_to_bold),
+    );
+
+    let bold_to_dim = render_style_transition(ATTR_BOLD, ATTR_DIM);
+    assert!(
+        contains_subsequence(&bold_to_dim, b"\x1b[0;2;"),
+        "expected bold->dim transition to emit dim SGR: {:?}",
+        String::from_utf8_lossy(&bold_to_dim),
+    );
+}
+
+#[test]
+fn diff_preserves_non_intensity_attr_delta_path() {
+    let dim_to_dim_underline = render_style_transition(ATTR_DIM, ATTR_DIM | ATTR_UNDERLINE);
+    assert!(
+        contains_subsequence(&dim_to_dim_underline, b"\x1b[0;2;4;"),
+        "expected underline+dim sequence in output: {:?}",
+        String::from_utf8_lossy(&dim_to_dim_underline),
+    );
+}
+
+#[test]
+fn damage_scratch_is_reused_until_limits_change() {
+    let first = with_damage_scratch(16, |s| (s.as_ptr() as usize, s.len()));
+    let again = with_damage_scratch(16, |s| (s.as_ptr() as usize, s.len()));
+    assert_eq!(first, again);
+    assert_eq!(first.1, 16);
+
+    let grown = with_damage_scratch(32, |s| s.len());
+    assert_eq!(grown, 32);
+
+    let nested = with_damage_scratch(8, |outer| {
+        let outer_ptr = outer.as_ptr() as usize;
+        with_damage_scratch(8, |inner| {
+            (outer_ptr != inner.as_ptr() as usize, inner.len())
+        })
+    });
+    assert_eq!(nested, (true, 8));
+}
+
+#[test]
+fn drawlist_budget_reports_tracked_runtime_limits() {
+    let mut create = unsafe { ffi::zr_engine_config_default() };
+    create.limits.dl_max_cmds = 77;
+    create.limits.dl_max_clip_depth = 9;
+    let budget = drawlist_budget(&runtime_cfg_from_create(&create).limits);
+    assert_eq!(budget.maxCmds, 77);
+    assert_eq!(budget.maxClipDepth, 9);
+    assert_eq!(budget.maxTotalBytes, create.limits.dl_max_total_bytes);
+    assert_eq!(budget.maxStrings, create.limits.dl_max_strings);
+    assert_eq!(budget.maxBlobs, create.limits.dl_max_blobs);
+    assert_eq!(
+        budget.maxTextRunSegments,
+        create.limits.dl_max_text_run_segments
+    );
+}
+
+#[test]
+fn input_mode_toggles_emit_only_transitions_but_always_clear_mouse() {
+    assert_eq!(
+        mode_transition(InputMode::Mouse, false, true),
+        Some(MOUSE_ON)
+    );
+    assert_eq!(mode_transition(InputMode::Mouse, true, true), None);
+    assert_eq!(
+        mode_transition(InputMode::Mouse, true, false),
+        Some(MOUSE_OFF)
+    );
+    assert_eq!(
+        mode_transition(InputMode::Mouse, false, false),
+        Some(MOUSE_OFF)
+    );
+    assert_eq!(
+        mode_transition(InputMode::BracketedPaste, false, true),
+        Some(PASTE_ON)
+    );
+    assert_eq!(
+        mode_transition(InputMode::BracketedPaste, false, false),
+        None
+    );
+    assert_eq!(
+        mode_transition(InputMode::FocusEvents, true, false),
+        Some(FOCUS_OFF)
+    );
+
+    let mut plat = unsafe { ffi::zr_engine_config_default() }.plat;
+    plat.enable_mouse = 1;
+    plat.enable_bracketed_paste = 0;
+    let modes = InputModes::new(&plat);
+    assert!(modes.requested(InputMode::Mouse));
+    assert!(!modes.requested(InputMode::BracketedPaste));
+    modes.set(InputMode::Mouse, false);
+    assert!(!modes.requested(InputMode::Mouse));
+}
+
+#[test]
+fn destroy_leave_sequence_covers_modes_enabled_at_runtime() {
+    let mut plat = unsafe { ffi::zr_engine_config_default() }.plat;
+    plat.enable_mouse = 0;
+    plat.enable_bracketed_paste = 0;
+    plat.enable_focus_events = 0;
+    let modes = InputModes::new(&plat);
+    assert!(modes.leave_sequence().is_empty());
+
+    modes.set(InputMode::Mouse, true);
+    modes.set(InputMode::FocusEvents, true);
+    assert_eq!(modes.leave_sequence(), [MOUSE_OFF, FOCUS_OFF].concat());
+
+    modes.set(InputMode::Mouse, false);
+    modes.set(InputMode::BracketedPaste, true);
+    assert_eq!(modes.leave_sequence(), [PASTE_OFF, FOCUS_OFF].concat());
+}
+
+#[test]
+fn destroy_leave_sequence_skips_modes_the_engine_leaves_itself() {
+    let mut plat = unsafe { ffi::zr_engine_config_default() }.plat;
+    plat.enable_mouse = 1;
+    plat.enable_bracketed_paste = 1;
+    plat.enable_focus_events = 0;
+    let modes = InputModes::new(&plat);
+    assert!(
+        modes.leave_sequence().is_empty(),
+        "create-time modes are left by the engine"
+    );
+
+    modes.set(InputMode::Mouse, false);
+    modes.set(InputMode::Mouse, true);
+    modes.set(InputMode::FocusEvents, true);
+    assert_eq!(modes.leave_sequence(), FOCUS_OFF);
+}
+
+#[test]
+fn bytes_per_damaged_cell_guards_frames_without_damage() {
+    assert_eq!(bytes_per_damaged_cell(0, 0), 0.0);
+    assert_eq!(bytes_per_damaged_cell(120, 0), 0.0);
+    assert_eq!(bytes_per_damaged_cell(30, 12), 2.5);
+    assert_eq!(bytes_per_damaged_cell(u32::MAX, 1), f64::from(u32::MAX));
+}
+
+#[test]
+fn erase_blank_tails_clears_colored_blanks_with_el_and_ed() {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let cursor = ffi::zr_cursor_state_t {
+        x: -1,
+        y: -1,
+        shape: 0,
+        visible: 1,
+        blink: 0,
+        reserved0: 0,
+    };
+    let blank = OwnedFb::new(40, 6).expect("fb");
+    let mut next = OwnedFb::new(40, 6).expect("fb");
+    let mut blue = style_plain();
+    blue.bg_rgb = 0x00_00AA;
+    assert_eq!(unsafe { ffi::zr_fb_clear(&mut next.0, &blue) }, ffi::ZR_OK);
+    for x in 0..3 {
+        let cell = unsafe { &mut *ffi::zr_fb_cell(&mut next.0, x, 0) };
+        cell.glyph[0] = b'x';
+    }
+
+    /* The differ sets the blanks' style itself, so any color mode works. */
+    for color_mode in [3, 2] {
+        let mut caps = default_diff_caps();
+        caps.color_mode = color_mode;
+        for initial in [None, Some(style_plain())] {
+            let diff = |erase| {
+                diff_to_bytes(
+                    &blank.0,
+                    &next.0,
+                    &caps,
+                    &limits,
+                    &cursor,
+                    initial.as_ref(),
+                    erase,
+                )
+                .expect("diff")
+            };
+            let (plain, erased) = (diff(false), diff(true));
+            assert!(
+                erased.len() < plain.len(),
+                "{} >= {}",
+                erased.len(),
+                plain.len()
+            );
+            assert!(!contains_subsequence(&plain, b"\x1b[K"));
+            assert!(contains_subsequence(&erased, b"xxx\x1b[K"));
+            assert!(contains_subsequence(&erased, b"\x1b[2;1H\x1b[J"));
+        }
+    }
+}
+
+#[test]
+fn frame_spans_classify_and_cover_emitted_bytes() {
+    let bytes: &[u8] = b"\x1b[?2026h\x1b[1;1H\x1b[0;38;2;1;2;3mhi \xc3\xa9\r\n\x1b[2C\x1b[K\x1b[1;5r\x1b[2S\x1bM\x1b]8;;x\x1b\\ok\x1b[2 q\x1b[";
+    let spans = tokenize(bytes);
+    let kinds: Vec<_> = spans.iter().map(|s| s.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            SpanKind::Other,
+            SpanKind::Cup,
+            SpanKind::Sgr,
+            SpanKind::Text,
+            SpanKind::Cup,
+            SpanKind::El,
+            SpanKind::Scroll,
+            SpanKind::Other,
+            SpanKind::Text,
+            SpanKind::Other,
+        ]
+    );
+    /* Spans tile the output without gaps, including a cut-off sequence. */
+    let mut at = 0;
+    for span in &spans {
+        assert_eq!(span.offset, at);
+        at += span.len;
+    }
+    assert_eq!(at as usize, bytes.len());
+    let cup = spans[4];
+    assert_eq!(
+        &bytes[cup.offset as usize..(cup.offset + cup.len) as usize],
+        b"\r\n\x1b[2C"
+    );
+    assert_eq!(spans[3].len, 5);
+    assert!(tokenize(b"").is_empty());
+}
+
+fn put_glyph(fb: &mut ffi::zr_fb_t, x: u32, y: u32, glyph: &[u8], width: u8) {
+    let cell = unsafe { &mut *ffi::zr_fb_cell(fb, x, y) };
+    cell.glyph.fill(0);
+    cell.glyph[..glyph.len()].copy_from_slice(glyph);
+    cell.glyph_len = glyph.len() as u8;
+    cell.width = width;
+}
+
+fn put_wide(fb: &mut ffi::zr_fb_t, x: u32, y: u32) {
+    put_glyph(fb, x, y, "\u{754C}".as_bytes(), 2);
+    put_glyph(fb, x + 1, y, b"", 0);
+}
+
+#[test]
+fn protected_regions_keep_prev_cells_and_split_straddling_glyphs() {
+    let mut prev = OwnedFb::new(10, 2).expect("fb");
+    let mut next = OwnedFb::new(10, 2).expect("fb");
+    for x in 0..10 {
+        put_glyph(&mut next.0, x, 0, b"x", 1);
+    }
+    put_wide(&mut next.0, 1, 0);
+    put_wide(&mut next.0, 4, 0);
+    put_wide(&mut prev.0, 4, 1);
+    let rects = [ProtectedRect {
+        x: 2,
+        y: 0,
+        w: 3,
+        h: 2,
+    }];
+    protect_cells(&mut prev.0, &mut next.0, &rects);
+
+    let snap = |fb: &mut ffi::zr_fb_t, x, y| {
+        let cell = unsafe { &*ffi::zr_fb_cell(fb, x, y) };
+        (cell.glyph[..cell.glyph_len as usize].to_vec(), cell.width)
+    };
+    /* A lead outside whose continuation is protected: clip-edge U+FFFD. */
+    assert_eq!(snap(&mut next.0, 1, 0), ("\u{FFFD}".as_bytes().to_vec(), 1));
+    for x in 2..5 {
+        assert_eq!(snap(&mut next.0, x, 0), (b" ".to_vec(), 1));
+    }
+    /* A continuation outside whose lead is protected becomes a space. */
+    assert_eq!(snap(&mut next.0, 5, 0), (b" ".to_vec(), 1));
+    assert_eq!(snap(&mut next.0, 6, 0), (b"x".to_vec(), 1));
+    /* A wide glyph the terminal shows across the edge stays whole. */
+    assert_eq!(snap(&mut next.0, 4, 1), ("\u{754C}".as_bytes().to_vec(), 2));
+    assert_eq!(snap(&mut next.0, 5, 1), (Vec::new(), 0));
+
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let cursor = ffi::zr_cursor_state_t {
+        x: -1,
+        y: -1,
+        shape: 0,
+        visible: 1,
+        blink: 0,
+        reserved0: 0,
+    };
+    let caps = default_diff_caps();
+    let out = diff_to_bytes(
+        &prev.0,
+        &next.0,
+        &caps,
+        &limits,
+        &cursor,
+        Some(&style_plain()),
+        false,
+    )
+    .expect("diff");
+    assert!(!contains_subsequence(&out, "\u{754C}".as_bytes()));
+    assert!(contains_subsequence(&out, "\u{FFFD}".as_bytes()));
+
+    /* Across a resize there is no screen to protect. */
+    let mut small = OwnedFb::new(4, 2).expect("fb");
+    put_glyph(&mut small.0, 2, 0, b"y", 1);
+    protect_cells(&mut prev.0, &mut small.0, &rects);
+    assert_eq!(snap(&mut small.0, 2, 0), (b"y".to_vec(), 1));
+}
+
+#[test]
+fn unknown_engine_ids_fail_under_the_calling_function() {
+    let err = engine_flush(0x7fff_fffe).unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(err.reason.starts_with("engineFlush: "), "{}", err.reason);
+    assert!(err.reason.ends_with("(ZR_ERR_INVALID_ARGUMENT, -1)"));
+    let err = engine_get_metrics(0).err().expect("engine 0 never exists");
+    assert!(
+        err.reason.starts_with("engineGetMetrics: "),
+        "{}",
+        err.reason
+    );
+}
+
+#[test]
+fn any_thread_readers_reject_unknown_engine_ids() {
+    const UNKNOWN: u32 = 0x7fff_fffe;
+    let err = engine_is_idle(UNKNOWN).unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(err.reason.starts_with("engineIsIdle: "), "{}", err.reason);
+    let err = engine_take_idle_transitions(UNKNOWN)
+        .err()
+        .expect("unknown id");
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    let err = engine_take_watchdog_reports(0).err().expect("unknown id");
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+}
+
+#[test]
+fn result_code_table_matches_thrown_error_names() {
+    let codes = zi_result_codes();
+    assert_eq!(codes.len(), 8);
+    assert_eq!(
+        codes["ZR_ERR_INVALID_ARGUMENT"],
+        ffi::ZR_ERR_INVALID_ARGUMENT
+    );
+    assert_eq!(codes["POLL_CANCELED"], crate::POLL_CANCELED);
+    for (name, rc) in codes {
+        assert_eq!(zi_error_name(rc), name);
+    }
+}
+
+#[test]
+fn thrown_errors_use_result_code_names() {
+    assert_eq!(ZrCode(ffi::ZR_ERR_LIMIT).as_ref(), "ZR_ERR_LIMIT");
+    assert_eq!(ZrCode(-77).as_ref(), "ZR_ERR_UNKNOWN");
+    let err = zr_error(ffi::ZR_ERR_FORMAT, "bad batch");
+    assert_eq!(err.status.as_ref(), "ZR_ERR_FORMAT");
+    assert_eq!(err.reason, "bad batch");
+
+    let wrong_type = napi::Error::new(napi::Status::NumberExpected, "x");
+    assert_eq!(
+        napi_error(wrong_type).status.as_ref(),
+        "ZR_ERR_INVALID_ARGUMENT"
+    );
+    /* A JS exception must stay pending so napi rethrows it unchanged. */
+    let pending = napi::Error::new(napi::Status::PendingException, "");
+    assert_eq!(
+        napi_error(pending).status.as_ref(),
+        napi::Status::PendingException.as_ref()
+    );
+}
+
+#[test]
+fn engine_results_throw_only_when_negative() {
+    assert!(zr_result_to_napi(ffi::ZR_OK, "enginePresent").is_ok());
+    assert!(zr_result_to_napi(56, "enginePollEvents").is_ok());
+
+    let err = zr_result_to_napi(ffi::ZR_ERR_LIMIT, "enginePollEvents").unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_LIMIT");
+    assert!(
+        err.reason.starts_with("enginePollEvents: "),
+        "{}",
+        err.reason
+    );
+    assert!(err.reason.ends_with("(ZR_ERR_LIMIT, -3)"), "{}", err.reason);
+
+    let err = zr_result_to_napi(-77, "enginePresent").unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_UNKNOWN");
+    assert!(
+        err.reason.ends_with("(ZR_ERR_UNKNOWN, -77)"),
+        "{}",
+        err.reason
+    );
+}
+
+#[test]
+fn layer_composite_clear_resets_previous_layers() {
+    let limits = unsafe { ffi::zr_engine_config_default() }.limits;
+    let base = drawlist_with_text(1);
+
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    assert!(execute_drawlist(&base, &mut fb, &limits).is_ok());
+    assert_eq!(cell_snapshot(&mut fb.0, 1, 0).0, b'h');
+
+    let clear = clear_drawlist(1);
+    assert_eq!(walk_cmds(&clear).map(|(n, s)| (n, s.len())), Some((1, 1)));
+    assert!(execute_drawlist(&clear, &mut fb, &limits).is_ok());
+    assert_eq!(cell_snapshot(&mut fb.0, 1, 0).0, b' ');
+
+    let mut wrong_version = clear_drawlist(99);
+    let mut fb = OwnedFb::new(4, 1).expect("fb");
+    assert!(execute_drawlist(&wrong_version, &mut fb, &limits).is_err());
+    wrong_version[4..8].copy_from_slice(&1u32.to_le_bytes());
+    assert_eq!(wrong_version, clear);
+}
+
+#[test]
+fn cursor_blink_modes_map_to_decscusr_variants() {
+    for name in ["on", "off", "steady"] {
+        assert_eq!(CursorBlink::parse(name).map(CursorBlink::name), Some(name));
+    }
+    assert_eq!(CursorBlink::parse("fast"), None);
+    assert_eq!(CursorBlink::default(), CursorBlink::Off);
+
+    assert_eq!(CursorBlink::Off.blink_flag(), None);
+    assert_eq!(CursorBlink::On.blink_flag(), Some(1));
+    assert_eq!(CursorBlink::Steady.blink_flag(), Some(0));
+
+    assert_eq!(CursorBlink::Steady.effective(true), CursorBlink::Steady);
+    assert_eq!(CursorBlink::On.effective(false), CursorBlink::Off);
+
+    let state = CursorBlinkState::default();
+    assert_eq!(state.get(), CursorBlink::Off);
+    assert_eq!(state.apply(|| 7), 7);
+}
+
+#[test]
+fn saved_state_round_trips_frame_and_cursor() {
+    let shadow = FrameShadow::new(true);
+    assert!(shadow.save().is_err(), "no frame before the first resize");
+    let resize = event_batch(1, &[(5, 0, &[4, 2, 0, 0])]);
+    shadow.note_poll(resize.len() as i32, &resize);
+
+    let mut cmds = Vec::new();
+    push_cmd(&mut cmds, ffi::ZR_DL_OP_SET_CURSOR, &[2, 1, 0x0000_0102]);
+    shadow.note_submit(ffi::ZR_OK, &drawlist_with_text(1));
+    shadow.note_submit(ffi::ZR_OK, &drawlist_from_cmds(&cmds, 1));
+    let Ok(saved) = shadow.save() else {
+        panic!("save after resize and submit must succeed");
+    };
+    let blob = saved.encode();
+    assert_eq!(blob.len(), 40 + 8 * 52);
+
+    shadow.note_submit(ffi::ZR_OK, &clear_drawlist(1));
+    assert_eq!(shadow.export().expect("export")[0][1].glyph, " ");
+    let restored = SavedState::decode(&blob).expect("decode");
+    assert_eq!(restored.size(), (4, 2));
+    let cursor = restored.cursor();
+    assert_eq!(
+        (
+            cursor.x,
+            cursor.y,
+            cursor.shape,
+            cursor.visible,
+            cursor.blink
+        ),
+        (2, 1, 2, 1, 0)
+    );
+    shadow.restore(&restored);
+    let rows = shadow.export().expect("export");
+    assert_eq!((rows[0][1].glyph.as_str(), rows[0][1].fg), ("h", 0xFF_0000));
+    assert_eq!(rows[0][1].attrs, ATTR_BOLD);
+    assert_eq!(shadow.save().expect("save").encode(), blob);
+
+    let mut small = OwnedFb::new(3, 2).expect("fb");
+    assert!(!restored.write_into(&mut small.0));
+
+    let err = |bytes: &[u8]| SavedState::decode(bytes).err().map(|(rc, _)| rc);
+    assert_eq!(err(&blob[..blob.len() - 1]), Some(ffi::ZR_ERR_FORMAT));
+    assert_eq!(err(&[0u8; 8]), Some(ffi::ZR_ERR_FORMAT));
+    let mut other_abi = blob.clone();
+    other_abi[8..12].copy_from_slice(&2u32.to_le_bytes());
+    assert_eq!(err(&other_abi), Some(ffi::ZR_ERR_UNSUPPORTED));
+    let mut bad_cell = blob.clone();
+    bad_cell[40 + 33] = 3;
+    assert_eq!(err(&bad_cell), Some(ffi::ZR_ERR_FORMAT));
+
+    /* Cells that would break the back buffer's wide-pair and UTF-8 rules. */
+    let cell = |x: usize, y: usize| 40 + (y * 4 + x) * 52;
+    let edited = |edit: &dyn Fn(&mut Vec<u8>)| {
+        let mut bytes = blob.clone();
+        edit(&mut bytes);
+        SavedState::decode(&bytes).err()
+    };
+    let lead_in_last_column = edited(&|b| b[cell(3, 0) + 33] = 2);
+    let orphan_continuation = edited(&|b| {
+        b[cell(2, 1) + 32] = 0;
+        b[cell(2, 1) + 33] = 0;
+    });
+    let lead_without_continuation = edited(&|b| b[cell(0, 1) + 33] = 2);
+    let invalid_utf8 = edited(&|b| b[cell(1, 0)] = 0xFF);
+    for (got, at) in [
+        (lead_in_last_column, "3,0"),
+        (orphan_continuation, "2,1"),
+        (lead_without_continuation, "0,1"),
+        (invalid_utf8, "1,0"),
+    ] {
+        let (rc, msg) = got.expect("rejected");
+        assert_eq!(rc, ffi::ZR_ERR_FORMAT, "{msg}");
+        assert!(msg.contains(&format!("cell {at} ")), "{msg}");
+    }
+    let wide_pair = edited(&|b| {
+        b[cell(1, 1)..cell(1, 1) + 3].copy_from_slice("\u{754C}".as_bytes());
+        b[cell(1, 1) + 32] = 3;
+        b[cell(1, 1) + 33] = 2;
+        b[cell(2, 1) + 32] = 0;
+        b[cell(2, 1) + 33] = 0;
+    });
+    assert!(wide_pair.is_none(), "a whole wide pair decodes");
+}
+
+#[test]
+fn focus_follows_the_last_focus_key_of_each_poll() {
+    let plat = unsafe { ffi::zr_engine_config_default() }.plat;
+    let modes = InputModes::new(&plat);
+    assert!(
+        modes.focused(),
+        "focused until the terminal reports otherwise"
+    );
+
+    let out = event_batch(1, &[(1, 0, &[31, 0, 1, 0]), (2, 0, &[0x61, 0])]);
+    assert_eq!(last_focus(&out), Some(false));
+    modes.note_poll(out.len() as i32, &out);
+    assert!(!modes.focused());
+
+    let keys = event_batch(1, &[(1, 0, &[0x41, 0, 1, 0]), (5, 0, &[80, 24, 0, 0])]);
+    assert_eq!(last_focus(&keys), None);
+    modes.note_poll(keys.len() as i32, &keys);
+    assert!(!modes.focused());
+
+    let both = event_batch(1, &[(1, 0, &[31, 0, 1, 0]), (1, 0, &[30, 0, 1, 0])]);
+    modes.note_poll(ffi::ZR_ERR_LIMIT, &both);
+    assert!(!modes.focused(), "failed polls are ignored");
+    modes.note_poll(both.len() as i32, &both);
+    assert!(modes.focused());
+}
+
+#[test]
+fn write_chunks_split_output_and_wait_for_writability_between_chunks() {
+    let bytes: Vec<u8> = (0..10u8).collect();
+    let plan = ChunkPlan {
+        chunk_bytes: 4,
+        wait_writable: true,
+    };
+    let mut counts = ChunkCounts::default();
+    let mut written = Vec::new();
+    let mut waits = Vec::new();
+    let mut writable = [ffi::ZR_ERR_LIMIT, ffi::ZR_OK].into_iter();
+    let rc = write_chunks(
+        &bytes,
+        plan,
+        &mut counts,
+        |chunk| {
+            written.push(chunk.to_vec());
+            ffi::ZR_OK
+        },
+        |timeout_ms| {
+            waits.push(timeout_ms);
+            if timeout_ms == 0 {
+                writable.next().unwrap_or(ffi::ZR_OK)
+            } else {
+                ffi::ZR_OK
+            }
+        },
+    );
+    assert_eq!(rc, ffi::ZR_OK);
+    assert_eq!(
+        written,
+        vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![8, 9]]
+    );
+    assert_eq!(waits, vec![0, CHUNK_WAIT_MS, 0], "only a stall waits");
+    assert_eq!(
+        counts,
+        ChunkCounts {
+            chunks: 3,
+            stalls: 1
+        }
+    );
+
+    let mut counts = ChunkCounts::default();
+    let mut calls = 0;
+    let rc = write_chunks(
+        &bytes,
+        ChunkPlan {
+            chunk_bytes: 3,
+            wait_writable: false,
+        },
+        &mut counts,
+        |_| {
+            calls += 1;
+            if calls == 2 {
+                ffi::ZR_ERR_PLATFORM
+            } else {
+                ffi::ZR_OK
+            }
+        },
+        |_| panic!("no writability support"),
+    );
+    assert_eq!(rc, ffi::ZR_ERR_PLATFORM, "a failed chunk stops the write");
+    assert_eq!(counts.chunks, 2);
+}
+
+#[test]
+fn full_frame_reasons_prefer_the_most_specific_cause() {
+    let invalid = DiffOutcome {
+        screen_invalid: true,
+        scroll_hit: false,
+    };
+    let valid = DiffOutcome::default();
+    let reason = |first, resized, restore, diff| {
+        classify_full_frame(FullFrameCauses {
+            first,
+            resized,
+            restore,
+            raw_write: false,
+            diff,
+        })
+    };
+    assert_eq!(
+        reason(true, true, false, Some(invalid)),
+        FullFrameReason::Initial
+    );
+    assert_eq!(
+        reason(false, true, false, Some(invalid)),
+        FullFrameReason::Resize
+    );
+    assert_eq!(
+        reason(false, true, true, Some(invalid)),
+        FullFrameReason::Restore
+    );
+    assert_eq!(
+        reason(false, false, false, Some(invalid)),
+        FullFrameReason::Invalidated
+    );
+    assert_eq!(
+        reason(false, true, false, Some(valid)),
+        FullFrameReason::Damage,
+        "a resize the screen state survived did not cause the repaint"
+    );
+    let scrolled = DiffOutcome {
+        screen_invalid: false,
+        scroll_hit: true,
+    };
+    assert_eq!(
+        reason(false, false, false, Some(scrolled)),
+        FullFrameReason::Scroll
+    );
+    assert_eq!(reason(false, true, false, None), FullFrameReason::Resize);
+    assert_eq!(reason(false, false, false, None), FullFrameReason::Damage);
+    let after_raw = FullFrameCauses {
+        raw_write: true,
+        diff: Some(invalid),
+        ..FullFrameCauses::default()
+    };
+    assert_eq!(classify_full_frame(after_raw), FullFrameReason::RawWrite);
+    assert_eq!(
+        classify_full_frame(FullFrameCauses {
+            restore: true,
+            ..after_raw
+        }),
+        FullFrameReason::Restore
+    );
+    assert_eq!(FullFrameReason::Invalidated.name(), "invalidated");
+}
+
+#[test]
+fn unknown_sequences_are_split_out_and_decoded_with_their_bytes() {
+    assert_eq!(sequence_at(b"\x1b[1;5A", 0), Sequence::Control(6));
+    assert_eq!(sequence_at(b"\x1bOP", 0), Sequence::Control(3));
+    assert_eq!(sequence_at(b"\x1b]11;x\x07!", 0), Sequence::String(7));
+    assert_eq!(sequence_at(b"\x1bP>|a\x1b\\", 0), Sequence::String(7));
+    assert_eq!(sequence_at(b"\x1b[?1;2", 0), Sequence::Incomplete);
+    assert_eq!(sequence_at(b"\x1b]11;", 0), Sequence::Incomplete);
+    assert_eq!(sequence_at(b"\x1bx", 0), Sequence::None, "Alt+x");
+    assert_eq!(sequence_at(b"\x1b", 0), Sequence::None);
+    assert_eq!(sequence_at(b"\x1b[1\x01", 0), Sequence::None);
+
+    let input = b"a\x1b[A\x1b[>5Qb\x1b]0;t\x07";
+    let knows = |seq: &[u8]| seq == b"\x1b[A";
+    let split = split_input(input, true, knows);
+    assert_eq!(
+        split.pieces,
+        vec![
+            Piece::Engine(0..4),
+            Piece::Unknown(4..9),
+            Piece::Engine(9..10),
+            Piece::Unknown(10..16),
+        ]
+    );
+    assert_eq!(split.held, None);
+
+    let split = split_input(b"ab\x1b[?1;", true, knows);
+    assert_eq!(split.pieces, vec![Piece::Engine(0..2)]);
+    assert_eq!(split.held, Some(2), "incomplete sequences stay buffered");
+    let split = split_input(b"ab\x1b[?1;", false, knows);
+    assert_eq!(split.pieces, vec![Piece::Engine(0..7)]);
+    assert_eq!(split.held, None, "a flush leaves them to the engine");
+
+    let seq = u32::from_le_bytes(*b"\x1b[>5");
+    let batch = event_batch(
+        1,
+        &[
+            (7, 3, &[UNKNOWN_SEQUENCE_TAG, 5, 0, 0, seq, u32::from(b'Q')]),
+            (7, 4, &[9, 1, 0, 0, 0x7a]),
+        ],
+    );
+    let records = decode_batch(&batch, 1).unwrap();
+    assert_eq!(records[0].kind, "unknown");
+    assert_eq!(records[0].time_ms, 3);
+    assert_eq!(records[0].payload, b"\x1b[>5Q");
+    assert_eq!(records[1].kind, "user", "other tags stay user events");
+    assert_eq!(records[1].payload.len(), 20);
+}
+
+#[test]
+fn unknown_sequences_reach_the_queue_only_when_enabled() {
+    let parse = |enabled: bool| -> Vec<ffi::zr_event_t> {
+        let mut events = [ffi::zr_event_t::default(); 8];
+        let mut payload = [0u8; 64];
+        let mut q = std::mem::MaybeUninit::<ffi::zr_event_queue_t>::zeroed();
+        let rc = unsafe {
+            ffi::zr_event_queue_init(
+                q.as_mut_ptr(),
+                events.as_mut_ptr(),
+                events.len() as u32,
+                payload.as_mut_ptr(),
+                payload.len() as u32,
+            )
+        };
+        assert_eq!(rc, ffi::ZR_OK);
+        let input = b"\x1b[>5Q";
+        with_unknown_sequences(enabled, || {
+            hook_input_parse_bytes(q.as_mut_ptr(), input.as_ptr(), input.len(), 0);
+            ffi::ZR_OK
+        });
+        let mut out = Vec::new();
+        let mut ev = ffi::zr_event_t::default();
+        while unsafe { ffi::zr_event_queue_pop(q.as_mut_ptr(), &mut ev) } {
+            out.push(ev);
+        }
+        out
+    };
+
+    let off = parse(false);
+    assert_eq!(off[0].type_, ffi::ZR_EV_KEY, "the engine's own fallback");
+    assert_eq!(off[0].u[0], ffi::ZR_KEY_ESCAPE);
+
+    let on = parse(true);
+    assert_eq!(on.len(), 1);
+    assert_eq!(on[0].type_, ffi::ZR_EV_USER);
+    assert_eq!(on[0].u[0], UNKNOWN_SEQUENCE_TAG);
+    assert_eq!(on[0].u[1], 5, "payload length");
+}
+
+#[test]
+fn batched_cell_writes_lay_out_by_width_and_name_the_first_misfit() {
+    let widths: Vec<u8> = ["a", "", "\u{4E16}", "e\u{301}"]
+        .iter()
+        .map(|g| grapheme_width(g.as_bytes()))
+        .collect();
+    assert_eq!(widths, vec![1, 1, 2, 1]);
+    assert_eq!(grapheme_width("\u{301}".as_bytes()), 0);
+    assert_eq!(grapheme_width(&[b'x'; ffi::ZR_CELL_GLYPH_MAX + 1]), 1);
+
+    assert_eq!(layout_run(3, &widths, 8), Ok(vec![3, 4, 5, 7]));
+    assert_eq!(layout_run(0, &[], 0), Ok(vec![]));
+    /* The wide grapheme would cover columns 6 and 7 of a 7-column row. */
+    assert_eq!(layout_run(4, &widths, 7), Err(2));
+    assert_eq!(layout_run(7, &widths, 7), Err(0));
+    assert_eq!(layout_run(u32::MAX, &[1], u32::MAX), Err(0));
+}
+
+#[test]
+fn scroll_shifts_rect_rows_fills_vacated_ones_and_splits_cut_wide_glyphs() {
+    let set = |fb: &mut OwnedFb, x: u32, y: u32, glyph: &str, width: u8| {
+        let cell = unsafe { &mut *ffi::zr_fb_cell(&mut fb.0, x, y) };
+        cell.glyph = [0; ffi::ZR_CELL_GLYPH_MAX];
+        cell.glyph[..glyph.len()].copy_from_slice(glyph.as_bytes());
+        cell.glyph_len = glyph.len() as u8;
+        cell.width = width;
+    };
+    let rows_of = |fb: &mut OwnedFb| -> Vec<String> {
+        (0..fb.0.rows)
+            .map(|y| {
+                (0..fb.0.cols)
+                    .map(|x| {
+                        let cell = unsafe { &*ffi::zr_fb_cell(&mut fb.0, x, y) };
+                        String::from_utf8_lossy(&cell.glyph[..cell.glyph_len as usize]).into_owned()
+                    })
+                    .collect()
+            })
+            .collect()
+    };
+    let fill = style_with_attrs(1);
+
+    let mut fb = OwnedFb::new(3, 3).expect("fb");
+    for (y, row) in ["abc", "def", "ghi"].iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            set(&mut fb, x as u32, y as u32, &ch.to_string(), 1);
+        }
+    }
+    let all = CellRect {
+        x: 0,
+        y: 0,
+        w: 3,
+        h: 3,
+    };
+    scroll_fb(&mut fb.0, all, -1, fill);
+    assert_eq!(rows_of(&mut fb), vec!["def", "ghi", "   "]);
+    assert_eq!(
+        unsafe { (*ffi::zr_fb_cell(&mut fb.0, 1, 2)).style.attrs },
+        fill.attrs
+    );
+    scroll_fb(&mut fb.0, all, 2, fill);
+    assert_eq!(rows_of(&mut fb), vec!["   ", "   ", "def"]);
+    scroll_fb(&mut fb.0, all, i32::MIN, fill);
+    assert_eq!(rows_of(&mut fb), vec!["   ", "   ", "   "]);
+
+    /* Row 0 is `a`, a wide lead at 1 with its continuation at 2, then `d`. */
+    let mut fb = OwnedFb::new(4, 2).expect("fb");
+    set(&mut fb, 0, 0, "a", 1);
+    set(&mut fb, 1, 0, "\u{4E16}", 2);
+    set(&mut fb, 2, 0, "", 0);
+    set(&mut fb, 3, 0, "d", 1);
+    for (x, ch) in "wxyz".chars().enumerate() {
+        set(&mut fb, x as u32, 1, &ch.to_string(), 1);
+    }
+    let right = CellRect {
+        x: 2,
+        y: 0,
+        w: 2,
+        h: 2,
+    };
+    scroll_fb(&mut fb.0, right, 1, fill);
+    assert_eq!(rows_of(&mut fb), vec!["a   ", "wx d"]);
+}
+
+#[test]
+fn event_records_keep_their_queue_position_across_polls() {
+    let batch = event_batch(
+        1,
+        &[
+            (1, 1, &[10, 0, 1, 0]),
+            (99, 2, &[]),
+            (7, 3, &[5, 0, 0, 0]),
+            (4, 4, &[1, 1, 1, 0, 0, 0, 0, 0]),
+            (4, 5, &[2, 2, 1, 0, 0, 0, 0, 0]),
+        ],
+    );
+    let records = decode_batch(&batch, 1).expect("valid batch");
+    let indices: Vec<_> = records.iter().map(|r| r.index).collect();
+    assert_eq!(
+        indices,
+        [0, 2, 3, 4],
+        "skipped records still take a position"
+    );
+    let policy = CoalescePolicy {
+        mouse_move: true,
+        ..CoalescePolicy::default()
+    };
+    let merged = coalesce_records(records, policy);
+    assert_eq!(merged.last().map(|r| r.index), Some(4));
+
+    let seq = EventSeq::default();
+    seq.note_poll(batch.len() as i32, &batch, |t| {
+        t.copy_from_slice(&[10, 20, 30, 40, 50])
+    });
+    assert_eq!(seq.last_base(), 0);
+    assert_eq!(seq.last_times_us(), [10, 20, 30, 40, 50]);
+    let two = event_batch(1, &[(6, 0, &[16, 0]), (6, 0, &[16, 0])]);
+    seq.note_poll(two.len() as i32, &two, |t| t.truncate(1));
+    assert_eq!(seq.last_base(), 5);
+    assert_eq!(seq.last_times_us(), [0], "a short read keeps what it got");
+    seq.note_poll(0, &[], |_| panic!("no records to stamp"));
+    assert_eq!(seq.last_base(), 7);
+    assert!(seq.last_times_us().is_empty());
+    seq.note_poll(ffi::ZR_ERR_LIMIT, &two, |_| panic!("no records to stamp"));
+    assert_eq!(seq.last_base(), 7);
+}
+
+static QUEUE_CLOCK_US: AtomicU64 = AtomicU64::new(0);
+
+extern "C" fn queue_clock_us() -> u64 {
+    QUEUE_CLOCK_US.load(Ordering::Relaxed)
+}
+
+#[test]
+fn queue_stamps_follow_fifo_order_across_sources_and_coalescing() {
+    let mut events = [ffi::zr_event_t::default(); 8];
+    let mut payload = [0u8; 64];
+    let mut q = std::mem::MaybeUninit::<ffi::zr_event_queue_t>::zeroed();
+    let rc = unsafe {
+        ffi::zr_event_queue_init(
+            q.as_mut_ptr(),
+            events.as_mut_ptr(),
+            events.len() as u32,
+            payload.as_mut_ptr(),
+            payload.len() as u32,
+        )
+    };
+    assert_eq!(rc, ffi::ZR_OK);
+    unsafe { ffi::zr_event_queue_set_clock(q.as_mut_ptr(), Some(queue_clock_us)) };
+    let mouse_move = |x: u32| ffi::zr_event_t {
+        type_: 4,
+        u: [x, 0, 1, 0, 0, 0, 0, 0],
+        ..ffi::zr_event_t::default()
+    };
+    let key = ffi::zr_event_t {
+        type_: ffi::ZR_EV_KEY,
+        u: [ffi::ZR_KEY_ESCAPE, 0, 1, 0, 0, 0, 0, 0],
+        ..ffi::zr_event_t::default()
+    };
+    let mut at = |us: u64, push: &mut dyn FnMut(*mut ffi::zr_event_queue_t) -> i32| {
+        QUEUE_CLOCK_US.store(us, Ordering::Relaxed);
+        assert_eq!(push(q.as_mut_ptr()), ffi::ZR_OK);
+    };
+    at(500, &mut |q| unsafe {
+        ffi::zr_event_queue_push(q, &mouse_move(1))
+    });
+    at(700, &mut |q| unsafe {
+        ffi::zr_event_queue_post_user(q, 0, 9, [1u8].as_ptr(), 1)
+    });
+    at(300, &mut |q| unsafe { ffi::zr_event_queue_push(q, &key) });
+    at(900, &mut |q| unsafe {
+        ffi::zr_event_queue_push(q, &mouse_move(2))
+    });
+
+    let mut out = Vec::new();
+    let mut ev = ffi::zr_event_t::default();
+    while unsafe { ffi::zr_event_queue_pop(q.as_mut_ptr(), &mut ev) } {
+        out.push((ev.type_, ev.u[0], ev.time_us));
+    }
+    assert_eq!(
+        out,
+        [
+            (4, 2, 500),
+            (ffi::ZR_EV_USER, 9, 700),
+            (ffi::ZR_EV_KEY, ffi::ZR_KEY_ESCAPE, 700),
+        ],
+        "a backwards clock is clamped; a coalesced move keeps its slot's stamp"
+    );
+}
+
+#[test]
+fn framebuffer_init_failures_keep_the_engine_code() {
+    let err = Framebuffer::new(u32::MAX, 1).err().expect("too wide");
+    assert_eq!(err.status, ZrCode(ffi::ZR_ERR_LIMIT));
+    assert!(err.reason.starts_with("Framebuffer: "), "{}", err.reason);
+}
+
+#[test]
+fn framebuffer_cells_read_back_and_reject_bad_coordinates() {
+    let mut fb = Framebuffer::new(3, 2).expect("framebuffer");
+    let cell = fb.cell(2, 1).expect("in range");
+    assert_eq!((cell.glyph.as_str(), cell.width), (" ", 1));
+    assert_eq!(
+        (cell.style.fgRgb, cell.style.bgRgb, cell.style.attrs),
+        (0, 0, 0)
+    );
+
+    for (x, y) in [(3, 0), (0, 2), (u32::MAX, u32::MAX)] {
+        let err = fb.cell(x, y).err().expect("out of range");
+        assert_eq!(err.status, ZrCode(ffi::ZR_ERR_INVALID_ARGUMENT));
+        assert!(
+            err.reason.contains("outside the 3x2 framebuffer"),
+            "{}",
+            err.reason
+        );
+    }
+    fb.release();
+    assert!(fb.cell(0, 0).is_err());
+}
+
+#[test]
+fn diverted_present_owes_the_next_terminal_present_a_repaint() {
+    let repaint = RepaintRequest::default();
+    repaint.note_diverted();
+    assert!(!repaint.pending());
+
+    repaint.before_terminal_present();
+    assert!(repaint.pending());
+    assert_eq!(repaint.apply(|| ffi::ZR_OK), ffi::ZR_OK);
+    assert!(!repaint.pending());
+
+    repaint.before_terminal_present();
+    assert!(!repaint.pending());
+}
+
+#[test]
+fn raw_write_makes_the_next_present_forget_the_screen() {
+    let repaint = RepaintRequest::default();
+    let ts = unsafe { std::mem::zeroed::<ffi::zr_term_state_t>() };
+    let forgets = || repaint_term_state(&ts).is_some();
+    assert_eq!(
+        repaint.apply(|| if forgets() { 1 } else { ffi::ZR_OK }),
+        ffi::ZR_OK
+    );
+
+    repaint.note_raw_written();
+    assert!(repaint.pending_after_raw());
+    assert!(!repaint.pending(), "not reported as a restore");
+    assert_eq!(
+        repaint.apply(|| if forgets() {
+            ffi::ZR_ERR_PLATFORM
+        } else {
+            ffi::ZR_OK
+        }),
+        ffi::ZR_ERR_PLATFORM
+    );
+    assert!(
+        repaint.pending_after_raw(),
+        "a failed present still owes it"
+    );
+    assert_eq!(
+        repaint.apply(|| if forgets() { ffi::ZR_OK } else { 1 }),
+        ffi::ZR_OK
+    );
+    assert!(!repaint.pending_after_raw());
+    assert!(!forgets());
+}
+
+#[test]
+fn framebuffer_diff_starts_from_prev_on_screen_and_reports_stats() {
+    let prev = OwnedFb::new(4, 2).expect("prev");
+    let mut next = OwnedFb::new(4, 2).expect("next");
+    let cell = unsafe { &mut *ffi::zr_fb_cell(&mut next.0, 1, 1) };
+    cell.glyph[0] = b'x';
+    cell.glyph_len = 1;
+    cell.width = 1;
+    let caps = default_diff_caps();
+    let on_screen = DiffOpts {
+        assume_screen: true,
+        ..DiffOpts::default()
+    };
+
+    let (bytes, stats) =
+        diff_with_stats(&prev.0, &prev.0, &caps, &on_screen, None).expect("same diff");
+    let stats = DiffStats::from(&stats);
+    assert_eq!((stats.dirtyLines, stats.dirtyCells), (0, 0));
+    assert!(
+        !bytes.windows(2).any(|w| w == b"2J"),
+        "prev is already on screen"
+    );
+    assert_eq!(stats.bytesEmitted as usize, bytes.len());
+
+    let (bytes, stats) = diff_with_stats(&prev.0, &next.0, &caps, &on_screen, None).expect("diff");
+    let stats = DiffStats::from(&stats);
+    assert_eq!((stats.dirtyLines, stats.dirtyCells), (1, 1));
+    assert!(!stats.damageFullFrame && !stats.scrollOptAttempted);
+    assert!(bytes.windows(6).any(|w| w == b"\x1b[2;2H"), "{bytes:?}");
+    assert_eq!(bytes.iter().filter(|&&b| b == b'x').count(), 1);
+    assert_eq!(stats.bytesEmitted as usize, bytes.len());
+
+    let (bytes, _) =
+        diff_with_stats(&prev.0, &next.0, &caps, &DiffOpts::default(), None).expect("diff");
+    assert!(
+        bytes.windows(2).any(|w| w == b"2J"),
+        "without assumeScreen the screen is cleared first"
+    );
+}
+
+#[test]
+fn ownership_moves_between_threads_only_between_calls() {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let id = register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+        .expect("register");
+    let on_other_thread = || {
+        std::thread::spawn(move || transfer_ownership(id))
+            .join()
+            .unwrap()
+    };
+
+    let guard = get_engine_guard(id).expect("guard");
+    assert_eq!(on_other_thread(), Err(TransferRefused::Busy));
+    assert!(guard.slot.is_owner_thread());
+    drop(guard);
+
+    assert_eq!(on_other_thread(), Ok(()));
+    let slot = std::sync::Arc::clone(&get_engine_guard(id).expect("guard").slot);
+    assert!(!slot.is_owner_thread());
+    assert!(
+        take_engine_for_owner(id).is_none(),
+        "only the owner may destroy"
+    );
+
+    assert_eq!(transfer_ownership(id), Ok(()));
+    assert!(slot.is_owner_thread());
+    assert!(take_engine_for_owner(id).is_some());
+    assert_eq!(transfer_ownership(id), Err(TransferRefused::Unknown));
+    assert_eq!(transfer_ownership(0), Err(TransferRefused::Unknown));
+}
+
+#[test]
+fn liveness_follows_the_registry_without_counting_a_call() {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let id = register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+        .expect("register");
+    assert!(is_engine_registered(id));
+    assert!(registered_engine_ids().contains(&id));
+    let listed = std::thread::spawn(move || {
+        (
+            is_engine_registered(id),
+            registered_engine_ids().contains(&id),
+        )
+    })
+    .join()
+    .unwrap();
+    assert_eq!(listed, (true, true), "readable from any thread");
+
+    let slot = take_engine_for_owner(id).expect("owner takes");
+    slot.wait_for_idle();
+    assert!(!is_engine_registered(id));
+    assert!(!registered_engine_ids().contains(&id));
+    assert!(!is_engine_registered(0));
+}
+
+#[test]
+fn idle_take_refuses_while_a_call_is_in_flight() {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let id = register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+        .expect("register");
+
+    let guard = get_engine_guard(id).expect("guard");
+    assert!(take_idle_engine_for_owner(id).is_none());
+    assert!(is_engine_registered(id), "left registered for a retry");
+    drop(guard);
+
+    let other = std::thread::spawn(move || take_idle_engine_for_owner(id).is_some())
+        .join()
+        .unwrap();
+    assert!(!other, "only the owner may destroy");
+    assert!(take_idle_engine_for_owner(id).is_some());
+    assert!(!is_engine_registered(id));
+    assert!(take_idle_engine_for_owner(id).is_none());
+    assert!(take_idle_engine_for_owner(0).is_none());
+}
+
+#[test]
+fn owned_engines_are_listed_only_on_the_owner_thread() {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let id = register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+        .expect("register");
+    assert!(owned_engine_ids().contains(&id));
+    let elsewhere = std::thread::spawn(move || owned_engine_ids().contains(&id))
+        .join()
+        .unwrap();
+    assert!(!elsewhere);
+    assert!(take_engine_for_owner(id).is_some());
+    assert!(!owned_engine_ids().contains(&id));
+}
+
+#[test]
+fn exit_hook_takes_idle_owned_engines_and_skips_busy_ones() {
+    fn register() -> u32 {
+        let cfg = unsafe { ffi::zr_engine_config_default() };
+        register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+            .expect("register")
+    }
+    let (idle, busy) = (register(), register());
+    let foreign = std::thread::spawn(register).join().unwrap();
+
+    let guard = get_engine_guard(busy).expect("guard");
+    let taken: Vec<u32> = take_idle_owned_engines()
+        .into_iter()
+        .map(|(engine_id, _)| engine_id)
+        .collect();
+    assert_eq!(
+        taken,
+        vec![idle],
+        "returns instead of waiting for the busy one"
+    );
+    assert!(is_engine_registered(busy));
+    assert!(is_engine_registered(foreign), "other threads' engines stay");
+    drop(guard);
+
+    assert_eq!(
+        take_idle_owned_engines()
+            .into_iter()
+            .map(|(engine_id, _)| engine_id)
+            .collect::<Vec<_>>(),
+        vec![busy]
+    );
+    assert_eq!(transfer_ownership(foreign), Ok(()));
+    assert!(take_engine_for_owner(foreign).is_some());
+}
+
+#[test]
+fn exit_hook_restores_the_terminal_of_engines_it_cannot_destroy() {
+    let foreign = std::thread::spawn(|| {
+        let cfg = unsafe { ffi::zr_engine_config_default() };
+        register_engine(std::ptr::null_mut(), &cfg, &BindingCreateOptions::default())
+            .expect("register")
+    })
+    .join()
+    .unwrap();
+
+    assert!(take_idle_owned_engines()
+        .iter()
+        .all(|(engine_id, _)| *engine_id != foreign));
+    assert_eq!(
+        remaining_terminal_restore(),
+        Some(true),
+        "an alt-screen engine of another thread still gets the main screen back"
+    );
+    assert!(is_engine_registered(foreign), "restored, not destroyed");
+
+    assert_eq!(transfer_ownership(foreign), Ok(()));
+    assert!(take_engine_for_owner(foreign).is_some());
+}
+
+#[test]
+fn runtime_config_reads_back_under_set_config_names() {
+    let mut cfg = runtime_cfg_from_create(&unsafe { ffi::zr_engine_config_default() });
+    cfg.target_fps = 45;
+    cfg.enable_scroll_optimizations = 0;
+    cfg.plat.enable_mouse = 1;
+    cfg.plat.screen_mode = ffi::ZR_SCREEN_MODE_INLINE;
+    cfg.limits.dl_max_cmds = 77;
+    cfg.inline_rows = 6;
+    cfg.enable_erase_optimizations = 1;
+
+    let js = runtime_cfg_to_js(&cfg);
+    assert_eq!(js.targetFps, 45);
+    assert!(!js.enableScrollOptimizations);
+    assert!(js.plat.enableMouse);
+    assert_eq!(js.plat.screenMode, 1);
+    assert_eq!(js.limits.dlMaxCmds, 77);
+    assert_eq!(js.inlineRows, 6);
+    assert!(js.enableEraseOptimizations);
+    assert_eq!(js.tabWidth, cfg.tab_width);
+}
+
+#[test]
+fn frame_stats_summarize_the_last_window_of_present_intervals() {
+    let empty = frame_stats(&mut []);
+    assert_eq!((empty.frames, empty.fps), (0, 0.0));
+
+    let mut samples = [40_000, 10_000, 20_000, 30_000];
+    let stats = frame_stats(&mut samples);
+    assert_eq!(stats.frames, 4);
+    assert_eq!(stats.fps, 40.0);
+    assert_eq!(stats.p50, 25.0);
+    assert_eq!((stats.p95, stats.p99, stats.max), (40.0, 40.0, 40.0));
+
+    let times = FrameTimes::new(3);
+    let t0 = std::time::Instant::now();
+    times.after_present(t0);
+    assert_eq!(times.stats().frames, 0);
+    for (i, at_ms) in [100u64, 110, 120, 130].into_iter().enumerate() {
+        times.after_present(t0 + std::time::Duration::from_millis(at_ms));
+        assert_eq!(times.stats().frames as usize, (i + 1).min(3));
+    }
+    let stats = times.stats();
+    assert_eq!(stats.max, 10.0, "the 100ms frame left the window");
+    assert_eq!(stats.fps, 100.0);
+}
+
+#[test]
+fn enum_config_values_are_checked_against_the_named_sets() {
+    for mode in [
+        ColorMode::UNKNOWN,
+        ColorMode::ANSI16,
+        ColorMode::ANSI256,
+        ColorMode::RGB,
+    ] {
+        assert!(check_color_mode(mode as u32, "engineCreate").is_ok());
+    }
+    let err = check_color_mode(4, "engineCreate").unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(
+        err.reason.contains("plat.requestedColorMode"),
+        "{}",
+        err.reason
+    );
+    assert!(err.reason.ends_with("got 4"), "{}", err.reason);
+    assert!(check_color_mode(259, "engineCreate").is_err());
+
+    assert!(check_width_policy(WidthPolicy::EMOJI_NARROW as u32, "engineSetConfig").is_ok());
+    assert!(check_width_policy(WidthPolicy::EMOJI_WIDE as u32, "engineSetConfig").is_ok());
+    let err = check_width_policy(2, "engineSetConfig").unwrap_err();
+    assert!(
+        err.reason.starts_with("engineSetConfig: widthPolicy"),
+        "{}",
+        err.reason
+    );
+}
+
+#[test]
+fn limit_checks_name_the_offending_key() {
+    let defaults = unsafe { ffi::zr_engine_config_default() }.limits;
+    assert!(check_limits(&defaults, "engineCreate").is_ok());
+
+    let mut lim = defaults;
+    lim.dl_max_clip_depth = 0;
+    let err = check_limits(&lim, "engineCreate").unwrap_err();
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert_eq!(
+        err.reason,
+        "engineCreate: limits.dlMaxClipDepth must be greater than 0"
+    );
+
+    let mut lim = defaults;
+    lim.arena_initial_bytes = 8192;
+    lim.arena_max_total_bytes = 4096;
+    let err = check_limits(&lim, "engineSetConfig").unwrap_err();
+    assert_eq!(
+        err.reason,
+        "engineSetConfig: limits.arenaInitialBytes (8192) must not exceed limits.arenaMaxTotalBytes (4096)"
+    );
+}
+
+#[test]
+fn config_presets_override_the_engine_defaults() {
+    let base = create_cfg_to_js(&unsafe { ffi::zr_engine_config_default() });
+    for name in CONFIG_PRESET_NAMES {
+        let cfg = config_preset(name).expect("listed preset");
+        let lim = &cfg.limits;
+        assert!(
+            lim.arena_initial_bytes <= lim.arena_max_total_bytes,
+            "{name}"
+        );
+        assert!(lim.out_max_bytes_per_frame > 0 && lim.dl_max_total_bytes > 0);
+        let js = create_cfg_to_js(&cfg);
+        assert_eq!(js.requestedEngineAbiMajor, base.requestedEngineAbiMajor);
+        assert_eq!(js.tabWidth, base.tabWidth);
+    }
+
+    let low = create_cfg_to_js(&config_preset("low-memory").unwrap());
+    assert!(low.limits.arenaMaxTotalBytes < base.limits.arenaMaxTotalBytes);
+    assert!(low.limits.outMaxBytesPerFrame < base.limits.outMaxBytesPerFrame);
+    let fast = create_cfg_to_js(&config_preset("high-throughput").unwrap());
+    assert!(fast.limits.dlMaxCmds > base.limits.dlMaxCmds);
+    assert!(fast.targetFps > base.targetFps);
+    let debug = create_cfg_to_js(&config_preset("debug").unwrap());
+    assert!(debug.enableDebugOverlay && debug.enableReplayRecording);
+    assert_eq!(
+        debug.limits.arenaMaxTotalBytes,
+        base.limits.arenaMaxTotalBytes
+    );
+
+    assert!(config_preset("fast").is_none());
+}
+
+#[test]
+fn polled_size_tracks_the_last_resize_handed_out() {
+    let size = PolledSize::default();
+    assert_eq!(size.get(), TerminalSize { cols: 0, rows: 0 });
+    assert_eq!(size.take(), None);
+
+    let resizes = event_batch(1, &[(5, 0, &[80, 24, 0, 0]), (5, 3, &[120, 40, 0, 0])]);
+    size.note_poll(resizes.len() as i32, &resizes);
+    assert_eq!(
+        size.get(),
+        TerminalSize {
+            cols: 120,
+            rows: 40
+        }
+    );
+
+    let keys = event_batch(1, &[(2, 7, &[0x41, 0])]);
+    size.note_poll(keys.len() as i32, &keys);
+    let shrink = event_batch(1, &[(5, 9, &[10, 5, 0, 0])]);
+    size.note_poll(ffi::ZR_ERR_LIMIT, &shrink);
+    size.note_poll(0, &shrink);
+    assert_eq!(
+        size.get(),
+        TerminalSize {
+            cols: 120,
+            rows: 40
+        }
+    );
+}
+
+#[test]
+fn poll_buffer_replacement_leaves_earlier_views_untouched() {
+    let buffer = PollBuffer::default();
+    assert!(
+        buffer.current().is_none(),
+        "no buffer until one is requested"
+    );
+
+    let first = std::sync::Arc::new(PollStorage::new(8));
+    buffer.replace(std::sync::Arc::clone(&first));
+    let polled = buffer.current().expect("buffer after replace");
+    unsafe { polled.bytes_mut() }.copy_from_slice(b"batch #1");
+
+    buffer.replace(std::sync::Arc::new(PollStorage::new(16)));
+    let next = buffer.current().expect("replacement");
+    assert_eq!(unsafe { next.bytes_mut() }, [0u8; 16]);
+    unsafe { next.bytes_mut() }.fill(0xFF);
+    assert_eq!(
+        unsafe { first.bytes_mut() },
+        b"batch #1",
+        "a view kept across a replacement still holds its last batch"
+    );
+}
+
+#[test]
+fn metrics_struct_matches_the_documented_offset_table() {
+    use std::mem::offset_of;
+    type M = ffi::zr_metrics_t;
+
+    assert_eq!(crate::METRICS_STRUCT_SIZE, 144);
+    let documented = [
+        (offset_of!(M, struct_size), 0),
+        (offset_of!(M, negotiated_engine_abi_major), 4),
+        (offset_of!(M, negotiated_event_batch_version), 20),
+        (offset_of!(M, frame_index), 24),
+        (offset_of!(M, fps), 32),
+        (offset_of!(M, bytes_emitted_total), 40),
+        (offset_of!(M, bytes_emitted_last_frame), 48),
+        (offset_of!(M, dirty_lines_last_frame), 56),
+        (offset_of!(M, dirty_cols_last_frame), 60),
+        (offset_of!(M, us_input_last_frame), 64),
+        (offset_of!(M, us_write_last_frame), 76),
+        (offset_of!(M, events_out_last_poll), 80),
+        (offset_of!(M, events_dropped_total), 84),
+        (offset_of!(M, arena_frame_high_water_bytes), 88),
+        (offset_of!(M, arena_persistent_high_water_bytes), 96),
+        (offset_of!(M, damage_rects_last_frame), 104),
+        (offset_of!(M, damage_cells_last_frame), 108),
+        (offset_of!(M, damage_full_frame), 112),
+        (offset_of!(M, diff_line_erases_total), 120),
+        (offset_of!(M, diff_screen_erases_total), 128),
+        (offset_of!(M, input_parse_errors_total), 136),
+        (offset_of!(M, paste_dropped_total), 140),
+    ];
+    assert_eq!(
+        offset_of!(M, diff_line_erases_total),
+        ffi::ZR_METRICS_SIZE_V1_4 as usize
+    );
+    for (actual, doc) in documented {
+        assert_eq!(actual, doc);
+    }
+}
+
+#[test]
+fn metrics_from_an_older_engine_leave_appended_counters_zero() {
+    let mut m = crate::empty_metrics();
+    m.events_dropped_total = 3;
+    m.diff_line_erases_total = 7;
+    m.input_parse_errors_total = 2;
+    m.paste_dropped_total = 1;
+
+    m.struct_size = ffi::ZR_METRICS_SIZE_V1_4;
+    crate::clear_unfilled_metrics(&mut m);
+    assert_eq!(m.events_dropped_total, 3, "ABI 1.4 fields are kept");
+    assert_eq!(m.diff_line_erases_total, 0);
+    assert_eq!(m.input_parse_errors_total, 0);
+    assert_eq!(m.paste_dropped_total, 0);
+
+    m.struct_size = crate::METRICS_STRUCT_SIZE;
+    m.paste_dropped_total = 1;
+    crate::clear_unfilled_metrics(&mut m);
+    assert_eq!(m.paste_dropped_total, 1);
+}
+
+#[test]
+fn metrics_reset_rebases_totals_without_touching_frame_fields() {
+    let baseline = MetricsBaseline::default();
+    let mut m = crate::empty_metrics();
+    m.bytes_emitted_total = 5_000;
+    m.events_dropped_total = 3;
+    m.bytes_emitted_last_frame = 200;
+    m.arena_frame_high_water_bytes = 4096;
+    baseline.apply(&mut m);
+    assert_eq!((m.bytes_emitted_total, m.events_dropped_total), (5_000, 3));
+
+    baseline.reset_to(&m);
+    m.bytes_emitted_total = 5_750;
+    m.events_dropped_total = 4;
+    baseline.apply(&mut m);
+    assert_eq!((m.bytes_emitted_total, m.events_dropped_total), (750, 1));
+    assert_eq!(
+        (m.bytes_emitted_last_frame, m.arena_frame_high_water_bytes),
+        (200, 4096)
+    );
+
+    let drops = DropCounters::default();
+    drops.note_poll(ffi::ZR_ERR_LIMIT, &[]);
+    drops.note_user_post(ffi::ZR_ERR_LIMIT, 16);
+    drops.reset();
+    let r = drops.snapshot(&crate::empty_metrics());
+    assert_eq!((r.pollBufferTooSmall, r.userQueueFull), (0, 0));
+}
+
+#[test]
+fn trace_cursor_counts_overwritten_records_and_rewinds_on_reset() {
+    let mut cursor = TraceCursor::new(5);
+    cursor.advance(5);
+    cursor.advance(6);
+    assert_eq!(
+        cursor,
+        TraceCursor {
+            next_id: 7,
+            dropped: 0
+        }
+    );
+
+    cursor.advance(10);
+    assert_eq!(
+        cursor.dropped, 3,
+        "ids 7..=9 were overwritten before delivery"
+    );
+    cursor.note_dropped(1);
+    assert_eq!(
+        cursor,
+        TraceCursor {
+            next_id: 11,
+            dropped: 4
+        }
+    );
+
+    cursor.rewind();
+    cursor.advance(1);
+    assert_eq!(
+        cursor,
+        TraceCursor {
+            next_id: 2,
+            dropped: 4
+        }
+    );
+}
+
+#[test]
+fn text_measurement_follows_engine_graphemes_and_policy() {
+    let text = "e\u{301}\u{754c}\u{1F44D}\u{1F3FD}";
+    assert_eq!(
+        grapheme_spans(text.as_bytes(), ffi::ZR_WIDTH_EMOJI_WIDE),
+        vec![(0, 3, 1), (3, 3, 2), (6, 8, 2)]
+    );
+    let narrow = grapheme_spans(text.as_bytes(), ffi::ZR_WIDTH_EMOJI_NARROW);
+    assert_eq!(narrow[2].2, 1);
+    assert!(grapheme_spans(b"", ffi::ZR_WIDTH_EMOJI_WIDE).is_empty());
+
+    let m = measure_utf8(b"ab\tc\nxyzxyzxyz", ffi::ZR_WIDTH_EMOJI_WIDE, 8).expect("measure");
+    assert_eq!((m.lines, m.max_cols), (2, 9));
+    let m = measure_utf8(b"ab\tc", ffi::ZR_WIDTH_EMOJI_WIDE, 4).expect("measure");
+    assert_eq!(m.max_cols, 5);
+}
+
+#[test]
+fn wrapped_lines_drop_breaks_and_keep_long_words_on_request() {
+    let wrap = |text: &str, width: u32, break_long_words: bool| {
+        wrap_lines(text, width, ffi::ZR_WIDTH_EMOJI_WIDE, 4, break_long_words).expect("wrap")
+    };
+    assert_eq!(
+        wrap("the quick brown fox", 10, true),
+        ["the quick", "brown fox"]
+    );
+    assert_eq!(wrap("a\r\nb\rc\n\nd", 10, true), ["a", "b", "c", "", "d"]);
+    assert_eq!(
+        wrap("ab\n", 10, true),
+        ["ab", ""],
+        "trailing newline opens a line"
+    );
+    assert_eq!(wrap("", 10, true), [""]);
+    assert_eq!(wrap("abc", 1, true), ["a", "b", "c"]);
+    assert_eq!(
+        wrap_lines("abc", 0, ffi::ZR_WIDTH_EMOJI_WIDE, 4, true),
+        Err(ffi::ZR_ERR_INVALID_ARGUMENT),
+        "zero width is rejected, not wrapped as one"
+    );
+    assert_eq!(
+        wrap("\u{754c}x", 1, true),
+        ["\u{754c}", "x"],
+        "a grapheme wider than the line gets one to itself"
+    );
+
+    assert_eq!(wrap("abcdefghij xy", 4, true), ["abcd", "efgh", "ij", "xy"]);
+    assert_eq!(wrap("abcdefghij xy", 4, false), ["abcdefghij", "xy"]);
+    assert_eq!(
+        wrap("hi abcdefghij\nxy", 4, false),
+        ["hi", "abcdefghij", "xy"]
+    );
+    assert_eq!(wrap("abcdefghij\n", 4, false), ["abcdefghij", ""]);
+    assert_eq!(wrap("abcdefghij  ", 4, false), ["abcdefghij"]);
+}
+
+#[test]
+fn invalid_utf8_segments_byte_by_byte_within_the_input() {
+    let bytes = [0xFF, b'a', 0xC3, 0xE4, 0xB8];
+    assert_eq!(
+        grapheme_spans(&bytes, ffi::ZR_WIDTH_EMOJI_WIDE),
+        vec![(0, 1, 1), (1, 1, 1), (2, 1, 1), (3, 1, 1), (4, 1, 1)]
+    );
+    /* A ZWJ sequence stays one cluster. */
+    let family = "\u{1F469}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    assert_eq!(
+        grapheme_spans(family.as_bytes(), ffi::ZR_WIDTH_EMOJI_WIDE),
+        vec![(0, family.len(), 2)]
+    );
+}
+
+#[test]
+fn clip_stack_keeps_clips_between_calls_and_enforces_capacity() {
+    let mut fb: ffi::zr_fb_t = unsafe { std::mem::zeroed() };
+    assert_eq!(
+        unsafe { ffi::zr_fb_init(&mut fb as *mut _, 4, 1) },
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        unsafe { ffi::zr_fb_clear(&mut fb as *mut _, &style_plain() as *const _) },
+        ffi::ZR_OK
+    );
+    let mut clips = ClipStack::new(1);
+    let clip = ffi::zr_rect_t {
+        x: 2,
+        y: 0,
+        w: 1,
+        h: 1,
+    };
+
+    let rc = clips.put_grapheme(&mut fb, 1, 0, "\u{754c}".as_bytes(), 2, &style_plain());
+    assert_eq!(rc, ffi::ZR_OK);
+    assert_eq!(clips.push(&mut fb, clip), ffi::ZR_OK);
+    assert_eq!(clips.depth(), 1);
+    assert_eq!(clips.push(&mut fb, clip), ffi::ZR_ERR_LIMIT);
+    assert_eq!(clips.depth(), 1);
+
+    /* The clip pushed in the earlier call still applies. */
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 0, 0, b"Z", 1, &style_plain()),
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 2, 0, b"A", 1, &style_plain()),
+        ffi::ZR_OK
+    );
+    assert_eq!(cell_snapshot(&mut fb, 0, 0), (b' ', 1));
+    assert_eq!(
+        cell_snapshot(&mut fb, 1, 0),
+        (b' ', 1),
+        "wide lead cleared when its continuation is overwritten"
+    );
+    assert_eq!(cell_snapshot(&mut fb, 2, 0), (b'A', 1));
+
+    assert_eq!(clips.pop(&mut fb), ffi::ZR_OK);
+    assert_eq!(clips.pop(&mut fb), ffi::ZR_ERR_LIMIT);
+    assert_eq!(clips.depth(), 0);
+    unsafe { ffi::zr_fb_release(&mut fb as *mut _) };
+}
+
+#[test]
+fn framebuffer_lines_skip_wide_tails_and_trim() {
+    let mut fb: ffi::zr_fb_t = unsafe { std::mem::zeroed() };
+    assert_eq!(
+        unsafe { ffi::zr_fb_init(&mut fb as *mut _, 5, 2) },
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        unsafe { ffi::zr_fb_clear(&mut fb as *mut _, &style_plain() as *const _) },
+        ffi::ZR_OK
+    );
+    let mut clips = ClipStack::new(0);
+    let wide = "\u{754c}".as_bytes();
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 0, 0, b"a", 1, &style_plain()),
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 1, 0, wide, 2, &style_plain()),
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 3, 0, b"b", 1, &style_plain()),
+        ffi::ZR_OK
+    );
+    assert_eq!(
+        clips.put_grapheme(&mut fb, 2, 1, b"c", 1, &style_plain()),
+        ffi::ZR_OK
+    );
+
+    assert_eq!(fb_lines(&mut fb), ["a\u{754c}b", "  c"]);
+    unsafe { ffi::zr_fb_release(&mut fb as *mut _) };
+}
+
+#[test]
+fn error_names_match_thrown_codes() {
+    assert_eq!(zi_error_name(ffi::ZR_OK), "ZR_OK");
+    assert_eq!(
+        zi_error_name(ffi::ZR_ERR_INVALID_ARGUMENT),
+        "ZR_ERR_INVALID_ARGUMENT"
+    );
+    assert_eq!(zi_error_name(ffi::ZR_ERR_LIMIT), "ZR_ERR_LIMIT");
+    assert_eq!(zi_error_name(ffi::ZR_ERR_PLATFORM), "ZR_ERR_PLATFORM");
+    assert_eq!(zi_error_name(-99), "ZR_ERR_UNKNOWN");
+    assert_eq!(zi_error_name(i32::MIN), "ZR_ERR_UNKNOWN");
+    for rc in [-1, -3, -6, ASSERT_FAILED] {
+        assert_eq!(zi_error_name(rc), ZrCode(rc).name());
+    }
+    assert!(zi_error_is_ok(ffi::ZR_OK) && zi_error_is_ok(7));
+    assert!(!zi_error_is_ok(ffi::ZR_ERR_LIMIT));
+}
+
+#[test]
+fn engine_version_reports_the_vendored_commit() {
+    let version = zi_engine_version();
+    let vendored = include_str!("../vendor/VENDOR_COMMIT.txt").trim();
+    assert_eq!(version.commit, vendored);
+    let series: Vec<&str> = include_str!("../vendor/patches/series")
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    assert_eq!(version.patches, series);
+    assert_eq!(
+        (version.abiMajor, version.abiMinor, version.abiPatch),
+        (1, 5, 0)
+    );
+}
+
+#[test]
+fn flush_waits_for_writability_only_when_supported() {
+    let mut waits = Vec::new();
+    let mut drains = 0;
+    let rc = flush_sequence(
+        false,
+        |ms| {
+            waits.push(ms);
+            ffi::ZR_OK
+        },
+        || {
+            drains += 1;
+            ffi::ZR_OK
+        },
+    );
+    assert_eq!((rc, waits.len(), drains), (ffi::ZR_OK, 0, 1));
+
+    let rc = flush_sequence(
+        true,
+        |ms| {
+            waits.push(ms);
+            ffi::ZR_OK
+        },
+        || {
+            drains += 1;
+            ffi::ZR_OK
+        },
+    );
+    assert_eq!(
+        (rc, waits.as_slice(), drains),
+        (ffi::ZR_OK, &[FLUSH_WAIT_MS][..], 2)
+    );
+
+    let rc = flush_sequence(
+        true,
+        |_| ffi::ZR_ERR_LIMIT,
+        || {
+            drains += 1;
+            ffi::ZR_OK
+        },
+    );
+    assert_eq!((rc, drains), (ffi::ZR_ERR_LIMIT, 2));
+}
+
+#[test]
+fn default_exports_mirror_the_engine_defaults() {
+    let cfg = unsafe { ffi::zr_engine_config_default() };
+    let limits = zi_limits_default();
+    assert_eq!(limits.arenaMaxTotalBytes, cfg.limits.arena_max_total_bytes);
+    assert_eq!(limits.dlMaxCmds, cfg.limits.dl_max_cmds);
+    assert_eq!(limits.diffMaxDamageRects, cfg.limits.diff_max_damage_rects);
+    assert!(check_limits(&cfg.limits, "ziLimitsDefault").is_ok());
+
+    let defaults = zi_config_default();
+    assert_eq!(
+        defaults.limits.outMaxBytesPerFrame,
+        limits.outMaxBytesPerFrame
+    );
+    assert_eq!(
+        defaults.requestedDrawlistVersion,
+        cfg.requested_drawlist_version
+    );
+    assert_eq!(defaults.targetFps, cfg.target_fps);
+    assert_eq!(defaults.enableDebugOverlay, cfg.enable_debug_overlay != 0);
+}
+
+#[test]
+fn negotiation_reports_the_negotiated_metrics_fields() {
+    let mut m = crate::empty_metrics();
+    m.negotiated_engine_abi_major = 1;
+    m.negotiated_engine_abi_minor = 4;
+    m.negotiated_engine_abi_patch = 2;
+    m.negotiated_drawlist_version = 1;
+    m.negotiated_event_batch_version = 1;
+    m.frame_index = 9;
+    let n = negotiation_from_metrics(&m);
+    assert_eq!(
+        (
+            n.abiMajor,
+            n.abiMinor,
+            n.abiPatch,
+            n.drawlistVersion,
+            n.eventBatchVersion
+        ),
+        (1, 4, 2, 1, 1)
+    );
+}
+
+#[test]
+fn sgr_attr_names_cover_every_attr_bit_in_order() {
+    for (i, &(_, bit)) in SGR_ATTR_BITS.iter().enumerate() {
+        assert_eq!(bit, 1 << i);
+    }
+    let all = SGR_ATTR_BITS.iter().fold(0, |mask, &(_, bit)| mask | bit);
+    assert_eq!(all, ffi::ZR_STYLE_ATTR_ALL_MASK);
+
+    let attrs = decode_sgr_attrs(ATTR_BOLD | ATTR_STRIKE).unwrap();
+    assert!(attrs.bold && attrs.strike);
+    assert!(!(attrs.italic || attrs.underline || attrs.reverse || attrs.dim));
+    assert!(!(attrs.overline || attrs.blink));
+    let all = decode_sgr_attrs(ffi::ZR_STYLE_ATTR_ALL_MASK).unwrap();
+    assert!(all.overline && all.blink && all.dim);
+
+    let err = decode_sgr_attrs(0x100)
+        .err()
+        .expect("bit 8 is not an attribute");
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(err.reason.contains("0x100"));
+}
+
+#[test]
+fn counted_polls_report_batch_records_and_new_engine_drops() {
+    let batch = event_batch(1, &[(1, 0, &[]), (2, 0, &[]), (3, 0, &[])]);
+    assert_eq!(batch_event_count(batch.len() as i32, &batch), 3);
+    assert_eq!(batch_event_count(0, &batch), 0);
+    assert_eq!(batch_event_count(ffi::ZR_ERR_LIMIT, &batch), 0);
+
+    let drops = DropCounters::default();
+    assert_eq!(drops.take_engine_drops(0), 0);
+    assert_eq!(drops.take_engine_drops(5), 5);
+    assert_eq!(drops.take_engine_drops(5), 0);
+    drops.reset();
+    assert_eq!(drops.take_engine_drops(7), 2);
+}
+
+#[test]
+fn posted_sizes_are_bounded_and_kept_until_replaced() {
+    assert_eq!(
+        check_posted_size(100, 30),
+        Ok(TerminalSize {
+            cols: 100,
+            rows: 30
+        })
+    );
+    assert!(check_posted_size(POSTED_SIZE_MAX, 1).is_ok());
+    for (cols, rows) in [(0, 24), (80, 0), (POSTED_SIZE_MAX + 1, 24)] {
+        let err = check_posted_size(cols, rows).unwrap_err();
+        assert!(err.contains(&format!("got {cols}x{rows}")), "{err}");
+    }
+
+    let posted = PostedSize::default();
+    assert_eq!(posted.get(), None);
+    posted.set(TerminalSize { cols: 81, rows: 25 });
+    posted.set(TerminalSize { cols: 90, rows: 20 });
+    assert_eq!(posted.get(), Some(TerminalSize { cols: 90, rows: 20 }));
+    assert_eq!(posted.apply(|| 7), 7);
+}
+
+#[test]
+fn debug_header_capacity_checks_alignment_and_clamps() {
+    let size = std::mem::size_of::<ffi::zr_debug_record_header_t>();
+    let align = std::mem::align_of::<ffi::zr_debug_record_header_t>();
+    assert_eq!(debug_headers_cap(0, 1).ok(), Some(0));
+    assert_eq!(debug_headers_cap(size * 3, align).ok(), Some(3));
+    assert_eq!(debug_headers_cap(size - 1, align).ok(), Some(0));
+
+    let err = debug_headers_cap(size - 1, align + 1).expect_err("misaligned probe buffer");
+    assert_eq!(err.status.as_ref(), "ZR_ERR_INVALID_ARGUMENT");
+    assert!(err.reason.contains("aligned"));
+
+    if usize::BITS > 32 {
+        assert_eq!(debug_headers_cap(usize::MAX, 0).ok(), Some(u32::MAX));
+    }
+}
+
+#[test]
+fn debug_payload_alloc_sizes_the_buffer_from_a_probe() {
+    let record = [7u8, 8, 9];
+    let mut caps = Vec::new();
+    let payload = read_debug_payload(|out| {
+        caps.push(out.len());
+        if out.len() < record.len() {
+            return (ffi::ZR_ERR_LIMIT, record.len() as u32);
+        }
+        out[..record.len()].copy_from_slice(&record);
+        (ffi::ZR_OK, record.len() as u32)
+    });
+    assert_eq!(payload, Ok(record.to_vec()));
+    assert_eq!(caps, [0, 3]);
+
+    assert_eq!(read_debug_payload(|_| (ffi::ZR_OK, 0)), Ok(Vec::new()));
+    assert_eq!(
+        read_debug_payload(|_| (ffi::ZR_ERR_LIMIT, 0)),
+        Err(ffi::ZR_ERR_LIMIT)
+    );
+    assert_eq!(
+        read_debug_payload(|_| (ffi::ZR_ERR_INVALID_ARGUMENT, 0)),
+        Err(ffi::ZR_ERR_INVALID_ARGUMENT)
+    );
+}
+
+#[test]
+fn debug_overlay_moves_to_its_corner_and_restores_the_top_left() {
+    let (cols, rows) = (50u32, 6u32);
+    let mut fb = OwnedFb::new(cols, rows).expect("fb");
+    for y in 0..rows {
+        for x in 0..cols {
+            put_glyph(&mut fb.0, x, y, b"x", 1);
+        }
+    }
+    put_wide(&mut fb.0, 9, 5);
+    assert_eq!(
+        OverlayCorner::parse("bottomRight"),
+        Some(OverlayCorner::BottomRight)
+    );
+    assert_eq!(OverlayCorner::parse("bottom-right"), None);
+
+    let len = (cols * rows) as usize;
+    let cells = unsafe { std::slice::from_raw_parts_mut(fb.0.cells, len) };
+    let rc = place_overlay(
+        cells,
+        cols as usize,
+        rows as usize,
+        OverlayCorner::BottomRight,
+        |cells| {
+            for y in 0..OVERLAY_ROWS {
+                for cell in &mut cells[y * cols as usize..][..OVERLAY_COLS] {
+                    cell.glyph[0] = b'o';
+                    cell.glyph_len = 1;
+                    cell.width = 1;
+                }
+            }
+            /* A wide glyph the engine leaves whole at the overlay's edge. */
+            cells[OVERLAY_COLS - 1].width = 2;
+            ffi::ZR_OK
+        },
+    );
+    assert_eq!(rc, ffi::ZR_OK);
+
+    let snap = |fb: &mut ffi::zr_fb_t, x, y| {
+        let cell = unsafe { &*ffi::zr_fb_cell(fb, x, y) };
+        (cell.glyph[..cell.glyph_len as usize].to_vec(), cell.width)
+    };
+    for y in 0..rows {
+        for x in 0..cols {
+            let want: &[u8] = match (x, y) {
+                (49, 2) => b" ",
+                (9, 5) => b" ",
+                (10.., 2..) => b"o",
+                _ => b"x",
+            };
+            assert_eq!(snap(&mut fb.0, x, y), (want.to_vec(), 1), "cell {x},{y}");
+        }
+    }
+}
+
+#[test]
+fn framebuffer_resize_keeps_the_overlap_and_blanks_the_rest() {
+    let mut fb = OwnedFb::new(4, 2).expect("fb");
+    put_glyph(&mut fb.0, 0, 0, b"a", 1);
+    put_glyph(&mut fb.0, 1, 0, b"b", 1);
+    put_wide(&mut fb.0, 2, 0);
+    put_glyph(&mut fb.0, 0, 1, b"c", 1);
+    let fill: ffi::zr_style_t = unsafe { std::mem::zeroed() };
+
+    let snap = |fb: &mut ffi::zr_fb_t, x, y| {
+        let cell = unsafe { &*ffi::zr_fb_cell(fb, x, y) };
+        (cell.glyph[..cell.glyph_len as usize].to_vec(), cell.width)
+    };
+    /* Wider: the wide glyph stays whole, the new column is blank. */
+    resize_fb(&mut fb.0, 5, 2, fill, "test").expect("resize");
+    assert_eq!((fb.0.cols, fb.0.rows), (5, 2));
+    assert_eq!(snap(&mut fb.0, 2, 0), ("\u{754C}".as_bytes().to_vec(), 2));
+    assert_eq!(snap(&mut fb.0, 4, 0), (b" ".to_vec(), 1));
+
+    /* Narrower and taller: the cut lead becomes a space, new rows blank. */
+    resize_fb(&mut fb.0, 3, 3, fill, "test").expect("resize");
+    assert_eq!(fb_lines(&mut fb.0), ["ab", "c", ""]);
+    assert_eq!(snap(&mut fb.0, 2, 0), (b" ".to_vec(), 1));
+    for x in 0..3 {
+        assert_eq!(snap(&mut fb.0, x, 2), (b" ".to_vec(), 1));
+    }
+
+    /* A failed resize keeps the engine code and leaves `fb` as it was. */
+    let err = resize_fb(&mut fb.0, u32::MAX, 1, fill, "test").expect_err("too wide");
+    assert_eq!(err.status, ZrCode(ffi::ZR_ERR_LIMIT));
+    assert_eq!((fb.0.cols, fb.0.rows), (3, 3));
+}
+
+#[test]
+fn framebuffer_diff_reports_the_damage_rects_it_built() {
+    let prev = OwnedFb::new(20, 8).expect("prev");
+    let mut next = OwnedFb::new(20, 8).expect("next");
+    for (x, y) in [(2, 1), (3, 1), (2, 2), (3, 2), (15, 6)] {
+        put_glyph(&mut next.0, x, y, b"x", 1);
+    }
+    let caps = default_diff_caps();
+    let on_screen = DiffOpts {
+        assume_screen: true,
+        ..DiffOpts::default()
+    };
+    let mut damage = Vec::new();
+    let (_, stats) =
+        diff_with_stats(&prev.0, &next.0, &caps, &on_screen, Some(&mut damage)).expect("diff");
+    assert_eq!(stats.path_damage_used, 1);
+    assert_eq!(damage.len(), stats.damage_rects as usize);
+    assert_eq!(
+        damage,
+        [
+            DamageRect {
+                x0: 2,
+                y0: 1,
+                x1: 3,
+                y1: 2
+            },
+            DamageRect {
+                x0: 15,
+                y0: 6,
+                x1: 15,
+                y1: 6
+            },
+        ]
+    );
+
+    let mut stats: ffi::zr_diff_stats_t = unsafe { std::mem::zeroed() };
+    stats.damage_full_frame = 1;
+    stats.damage_rects = 1;
+    assert_eq!(
+        damage_rects(&stats, &[], 20, 8),
+        [DamageRect {
+            x0: 0,
+            y0: 0,
+            x1: 19,
+            y1: 7
+        }]
+    );
+    /* The row sweep counts dirty rows as rects but builds none. */
+    stats.damage_full_frame = 0;
+    stats.path_sweep_used = 1;
+    stats.damage_rects = 3;
+    assert!(damage_rects(&stats, &[], 20, 8).is_empty());
+}
+
+#[test]
+fn supported_versions_come_from_engine_validation() {
+    let drawlist = zi_supported_drawlist_versions();
+    let event_batch = zi_supported_event_batch_versions();
+    assert_eq!(drawlist, [1, 2]);
+    assert_eq!(event_batch, [1]);
+
+    let mut cfg = unsafe { ffi::zr_engine_config_default() };
+    assert_eq!(check_requested_versions(&cfg), Ok(()));
+    cfg.requested_drawlist_version = 2;
+    assert_eq!(check_requested_versions(&cfg), Ok(()));
+    cfg.requested_drawlist_version = 3;
+    assert_eq!(
+        check_requested_versions(&cfg),
+        Err(
+            "requestedDrawlistVersion 3 is not supported by the linked engine (supported: 1, 2)"
+                .to_owned()
+        )
+    );
+    cfg.requested_drawlist_version = 1;
+    cfg.requested_event_batch_version = 0;
+    assert_eq!(
+        check_requested_versions(&cfg),
+        Err(
+            "requestedEventBatchVersion 0 is not supported by the linked engine (supported: 1)"
+                .to_owned()
+        )
     );
 }
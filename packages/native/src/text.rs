@@ -0,0 +1,302 @@
+use crate::config::{check_width_policy, js_u32, js_u8_bool, validate_known_keys};
+use crate::error::{invalid_argument, zr_error, zr_result_error, ZrCode};
+use crate::ffi;
+use napi::bindgen_prelude::{Either, Uint8Array};
+use napi::JsObject;
+use napi_derive::napi;
+
+const WRAP_OPTS_KEYS: &[(&str, &str)] = &[
+    ("tabWidth", "tabWidth"),
+    ("widthPolicy", "widthPolicy"),
+    ("breakLongWords", "breakLongWords"),
+];
+
+/// Engine default tab stop (`ZR_CFG_DEFAULT_TAB_WIDTH`).
+const DEFAULT_TAB_WIDTH: u32 = 4;
+
+/// `zr_measure_utf8`: line count and widest line of `bytes`, in columns.
+pub(crate) fn measure_utf8(
+    bytes: &[u8],
+    policy: u32,
+    tab_width: u32,
+) -> Result<ffi::zr_measure_utf8_t, i32> {
+    let mut out = ffi::zr_measure_utf8_t {
+        lines: 0,
+        max_cols: 0,
+    };
+    let rc = unsafe {
+        ffi::zr_measure_utf8(
+            bytes.as_ptr(),
+            bytes.len(),
+            policy,
+            tab_width,
+            &mut out as *mut _,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return Err(rc);
+    }
+    Ok(out)
+}
+
+/// Grapheme clusters of `bytes` as the engine splits them: byte offset,
+/// byte length and column width of each.
+pub(crate) fn grapheme_spans(bytes: &[u8], policy: u32) -> Vec<(usize, usize, u8)> {
+    let mut it = ffi::zr_grapheme_iter_t {
+        bytes: std::ptr::null(),
+        len: 0,
+        off: 0,
+    };
+    let mut g = ffi::zr_grapheme_t { offset: 0, size: 0 };
+    let mut spans = Vec::new();
+    unsafe { ffi::zr_grapheme_iter_init(&mut it as *mut _, bytes.as_ptr(), bytes.len()) };
+    while unsafe { ffi::zr_grapheme_next(&mut it as *mut _, &mut g as *mut _) } {
+        let glyph = &bytes[g.offset..g.offset + g.size];
+        let width = unsafe { ffi::zr_width_grapheme_utf8(glyph.as_ptr(), glyph.len(), policy) };
+        spans.push((g.offset, g.size, width));
+    }
+    spans
+}
+
+/// Columns `input` occupies on screen under the engine's width rules: the
+/// widest line when it contains line breaks, with tabs expanded to stops
+/// every `tabWidth` columns. `widthPolicy` is 0 (emoji narrow) or 1 (emoji
+/// wide, the engine default).
+#[napi(js_name = "measureText")]
+pub fn measure_text(input: String, width_policy: u32, tab_width: u32) -> napi::Result<u32, ZrCode> {
+    check_width_policy(width_policy, "measureText")?;
+    if tab_width == 0 {
+        return Err(invalid_argument("measureText: tabWidth must be at least 1"));
+    }
+    measure_utf8(input.as_bytes(), width_policy, tab_width)
+        .map(|m| m.max_cols)
+        .map_err(|rc| zr_result_error(rc, "measureText"))
+}
+
+/// One grapheme cluster of a `measureGraphemes` input. Offsets are in
+/// UTF-8 bytes.
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct GraphemeMeasure {
+    pub byteStart: u32,
+    pub byteLen: u32,
+    pub width: u32,
+}
+
+/// Split `input` into grapheme clusters the way the engine does and give
+/// each one's column width. `widthPolicy` defaults to 1 (emoji wide).
+/// Control characters, tabs and line breaks report the engine's width for a
+/// lone cluster, not their layout effect.
+#[napi(js_name = "measureGraphemes")]
+pub fn measure_graphemes(
+    input: String,
+    width_policy: Option<u32>,
+) -> napi::Result<Vec<GraphemeMeasure>, ZrCode> {
+    let policy = width_policy.unwrap_or(ffi::ZR_WIDTH_EMOJI_WIDE);
+    check_width_policy(policy, "measureGraphemes")?;
+    grapheme_measures(input.as_bytes(), policy, "measureGraphemes")
+}
+
+fn grapheme_measures(
+    bytes: &[u8],
+    policy: u32,
+    ctx: &str,
+) -> napi::Result<Vec<GraphemeMeasure>, ZrCode> {
+    if bytes.len() > u32::MAX as usize {
+        return Err(zr_error(
+            ffi::ZR_ERR_LIMIT,
+            format!("{ctx}: input over 4 GiB"),
+        ));
+    }
+    Ok(grapheme_spans(bytes, policy)
+        .into_iter()
+        .map(|(start, len, width)| GraphemeMeasure {
+            byteStart: start as u32,
+            byteLen: len as u32,
+            width: u32::from(width),
+        })
+        .collect())
+}
+
+/// Grapheme clusters of `input` (a string, or UTF-8 bytes taken as they
+/// are) with their widths under the engine's default policy. Invalid UTF-8
+/// never reads past the input: each byte of a bad or truncated sequence is
+/// a cluster of its own, one column wide like the U+FFFD it renders as.
+#[napi(js_name = "segmentGraphemes")]
+pub fn segment_graphemes(
+    input: Either<String, Uint8Array>,
+) -> napi::Result<Vec<GraphemeMeasure>, ZrCode> {
+    let bytes = match &input {
+        Either::A(text) => text.as_bytes(),
+        Either::B(bytes) => bytes.as_ref(),
+    };
+    grapheme_measures(bytes, ffi::ZR_WIDTH_EMOJI_WIDE, "segmentGraphemes")
+}
+
+/// `zr_wrap_greedy_utf8`: byte offset where each wrapped line starts.
+fn wrap_line_starts(
+    bytes: &[u8],
+    max_width: u32,
+    policy: u32,
+    tab_width: u32,
+) -> Result<Vec<usize>, i32> {
+    /* Every line starts on a grapheme boundary, so this never truncates. */
+    let mut starts = vec![0usize; bytes.len() + 1];
+    let mut count = 0usize;
+    let mut truncated = false;
+    let rc = unsafe {
+        ffi::zr_wrap_greedy_utf8(
+            bytes.as_ptr(),
+            bytes.len(),
+            max_width,
+            policy,
+            tab_width,
+            starts.as_mut_ptr(),
+            starts.len(),
+            &mut count as *mut _,
+            &mut truncated as *mut _,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return Err(rc);
+    }
+    starts.truncate(count);
+    Ok(starts)
+}
+
+fn is_blank(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+fn is_line_break(b: u8) -> bool {
+    b == b'\n' || b == b'\r'
+}
+
+/// Length of the LF, CR or CRLF at the start of `bytes`, if any.
+fn hard_break_len(bytes: &[u8]) -> Option<usize> {
+    match bytes {
+        [b'\r', b'\n', ..] => Some(2),
+        [b, ..] if is_line_break(*b) => Some(1),
+        _ => None,
+    }
+}
+
+/// Line `start..end` as shown: without the hard break that ends it, or
+/// without the whitespace the engine broke at.
+fn visible_line(bytes: &[u8], start: usize, end: usize, last: bool) -> &[u8] {
+    let line = &bytes[start..end];
+    if last {
+        return line;
+    }
+    if let Some(body) = line
+        .strip_suffix(b"\r\n")
+        .or_else(|| line.strip_suffix(b"\n"))
+        .or_else(|| line.strip_suffix(b"\r"))
+    {
+        return body;
+    }
+    let keep = line
+        .iter()
+        .rposition(|&b| !is_blank(b))
+        .map_or(0, |i| i + 1);
+    &line[..keep]
+}
+
+/// Lines of `text` wrapped to `max_width` columns by the engine. With
+/// `break_long_words` off, a run without whitespace wider than the line is
+/// kept whole on a line of its own instead of being split. A zero
+/// `max_width` fails with the engine's `ZR_ERR_INVALID_ARGUMENT`.
+pub(crate) fn wrap_lines(
+    text: &str,
+    max_width: u32,
+    policy: u32,
+    tab_width: u32,
+    break_long_words: bool,
+) -> Result<Vec<String>, i32> {
+    let bytes = text.as_bytes();
+    let mut lines = Vec::new();
+    let mut pos = 0usize;
+    'rewrap: loop {
+        let starts = wrap_line_starts(&bytes[pos..], max_width, policy, tab_width)?;
+        for (i, &start) in starts.iter().enumerate() {
+            let start = pos + start;
+            let last = i + 1 == starts.len();
+            let end = starts.get(i + 1).map_or(bytes.len(), |&next| pos + next);
+            /* A break with no whitespace or line break before it split a word. */
+            let split_word =
+                !last && end > start && !is_blank(bytes[end - 1]) && !is_line_break(bytes[end - 1]);
+            if break_long_words || !split_word {
+                lines.push(
+                    String::from_utf8_lossy(visible_line(bytes, start, end, last)).into_owned(),
+                );
+                continue;
+            }
+
+            /* Keep the word whole and wrap the rest again from after it. */
+            let word_end = bytes[end..]
+                .iter()
+                .position(|&b| is_blank(b) || is_line_break(b))
+                .map_or(bytes.len(), |i| end + i);
+            lines.push(String::from_utf8_lossy(&bytes[start..word_end]).into_owned());
+            pos = word_end;
+            while pos < bytes.len() && is_blank(bytes[pos]) {
+                pos += 1;
+            }
+            match hard_break_len(&bytes[pos..]) {
+                Some(len) => pos += len,
+                None if pos == bytes.len() => break 'rewrap,
+                None => {}
+            }
+            continue 'rewrap;
+        }
+        break;
+    }
+    Ok(lines)
+}
+
+/// Wrap `input` into lines of at most `maxWidth` columns with the engine's
+/// greedy wrapper. LF, CR and CRLF are hard breaks and are not part of the
+/// returned lines; whitespace at a soft break is dropped. `maxWidth` 0 is
+/// rejected rather than treated as 1.
+#[napi(js_name = "wrapText")]
+pub fn wrap_text(
+    input: String,
+    max_width: u32,
+    opts: Option<JsObject>,
+) -> napi::Result<Vec<String>, ZrCode> {
+    const CTX: &str = "wrapText";
+    let mut tab_width = DEFAULT_TAB_WIDTH;
+    let mut policy = ffi::ZR_WIDTH_EMOJI_WIDE;
+    let mut break_long_words = true;
+    if let Some(obj) = opts {
+        validate_known_keys(&obj, WRAP_OPTS_KEYS, &format!("{CTX} opts"))?;
+        if let Some(v) = js_u32(&obj, "tabWidth", "tabWidth")
+            .map_err(|_| invalid_argument(format!("{CTX}: tabWidth must be a whole number")))?
+        {
+            tab_width = v;
+        }
+        if let Some(v) = js_u32(&obj, "widthPolicy", "widthPolicy")
+            .map_err(|_| invalid_argument(format!("{CTX}: widthPolicy must be 0 or 1")))?
+        {
+            policy = v;
+        }
+        if let Some(v) = js_u8_bool(&obj, "breakLongWords", "breakLongWords")
+            .map_err(|_| invalid_argument(format!("{CTX}: breakLongWords must be a boolean")))?
+        {
+            break_long_words = v != 0;
+        }
+    }
+    check_width_policy(policy, CTX)?;
+    if max_width == 0 {
+        return Err(invalid_argument(format!(
+            "{CTX}: maxWidth must be at least 1"
+        )));
+    }
+    if tab_width == 0 {
+        return Err(invalid_argument(format!(
+            "{CTX}: tabWidth must be at least 1"
+        )));
+    }
+    wrap_lines(&input, max_width, policy, tab_width, break_long_words)
+        .map_err(|rc| zr_result_error(rc, CTX))
+}
@@ -0,0 +1,265 @@
+use crate::ffi;
+use napi_derive::napi;
+use std::cell::Cell;
+use std::ops::Range;
+
+/// User event tag the binding reserves for unrecognized input sequences;
+/// `decodeEventBatch` reports such records as `"unknown"` and
+/// `enginePostUserEvent` refuses the tag.
+#[napi]
+pub const UNKNOWN_SEQUENCE_TAG: u32 = 0x5A52_554E;
+
+/// Longest CSI or SS3 sequence checked against the engine's parser; none of
+/// the key and mouse forms it knows come close.
+pub(crate) const KNOWN_SEQUENCE_MAX: usize = 64;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Sequence {
+    /// Not an escape sequence the binding frames (bare ESC, Alt+key, garbage).
+    None,
+    /// A sequence whose end has not arrived yet.
+    Incomplete,
+    /// CSI or SS3 ending before this offset; the engine may know it.
+    Control(usize),
+    /// OSC, DCS, APC, PM or SOS string ending before this offset; the engine
+    /// parses none of these.
+    String(usize),
+}
+
+/// Frame the escape sequence starting at `bytes[at]` (an ESC).
+pub(crate) fn sequence_at(bytes: &[u8], at: usize) -> Sequence {
+    let Some(&intro) = bytes.get(at + 1) else {
+        return Sequence::None;
+    };
+    match intro {
+        b'[' => {
+            let mut j = at + 2;
+            while j < bytes.len() && (0x30..=0x3f).contains(&bytes[j]) {
+                j += 1;
+            }
+            while j < bytes.len() && (0x20..=0x2f).contains(&bytes[j]) {
+                j += 1;
+            }
+            match bytes.get(j) {
+                None => Sequence::Incomplete,
+                Some(b) if (0x40..=0x7e).contains(b) => Sequence::Control(j + 1),
+                Some(_) => Sequence::None,
+            }
+        }
+        b'O' => match bytes.get(at + 2) {
+            None => Sequence::Incomplete,
+            Some(b) if (0x40..=0x7e).contains(b) => Sequence::Control(at + 3),
+            Some(_) => Sequence::None,
+        },
+        b']' | b'P' | b'_' | b'^' | b'X' => {
+            let mut j = at + 2;
+            while j < bytes.len() {
+                match bytes[j] {
+                    BEL => return Sequence::String(j + 1),
+                    ESC => {
+                        return match bytes.get(j + 1) {
+                            Some(b'\\') => Sequence::String(j + 2),
+                            Some(_) => Sequence::None,
+                            None => Sequence::Incomplete,
+                        };
+                    }
+                    _ => j += 1,
+                }
+            }
+            Sequence::Incomplete
+        }
+        _ => Sequence::None,
+    }
+}
+
+/// A run of input and who handles it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Piece {
+    /// Handed to the engine's parser.
+    Engine(Range<usize>),
+    /// A complete sequence the engine would mangle into Escape plus text.
+    Unknown(Range<usize>),
+}
+
+/// How one parser call's input is split.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct InputSplit {
+    pub(crate) pieces: Vec<Piece>,
+    /// Offset of an incomplete sequence to keep buffered; only set when the
+    /// caller can buffer.
+    pub(crate) held: Option<usize>,
+}
+
+/// Split `bytes` around complete sequences `engine_knows` rejects. With
+/// `buffer`, parsing stops before an incomplete sequence so the rest can
+/// arrive, as the engine already does for the sequences it knows.
+pub(crate) fn split_input(
+    bytes: &[u8],
+    buffer: bool,
+    mut engine_knows: impl FnMut(&[u8]) -> bool,
+) -> InputSplit {
+    let mut split = InputSplit::default();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != ESC {
+            i += 1;
+            continue;
+        }
+        let end = match sequence_at(bytes, i) {
+            Sequence::Control(end)
+                if end - i <= KNOWN_SEQUENCE_MAX && engine_knows(&bytes[i..end]) =>
+            {
+                i = end;
+                continue;
+            }
+            Sequence::Control(end) | Sequence::String(end) => end,
+            Sequence::Incomplete if buffer => {
+                if start < i {
+                    split.pieces.push(Piece::Engine(start..i));
+                }
+                split.held = Some(i);
+                return split;
+            }
+            Sequence::Incomplete | Sequence::None => {
+                i += 1;
+                continue;
+            }
+        };
+        if start < i {
+            split.pieces.push(Piece::Engine(start..i));
+        }
+        split.pieces.push(Piece::Unknown(i..end));
+        start = end;
+        i = end;
+    }
+    if start < bytes.len() {
+        split.pieces.push(Piece::Engine(start..bytes.len()));
+    }
+    split
+}
+
+thread_local! {
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `poll` with unrecognized sequences split out when `enabled` (the
+/// `unknownSequences` create option); otherwise input goes straight to the
+/// engine's parser, which never sees it re-parsed.
+pub(crate) fn with_unknown_sequences(enabled: bool, poll: impl FnOnce() -> i32) -> i32 {
+    if !enabled {
+        return poll();
+    }
+    ACTIVE.with(|a| a.set(true));
+    let rc = poll();
+    ACTIVE.with(|a| a.set(false));
+    rc
+}
+
+/// Whether the engine parses `seq` into something other than its
+/// fallback: an Escape key followed by the rest as text.
+fn engine_knows(seq: &[u8]) -> bool {
+    let mut events = [ffi::zr_event_t::default(); KNOWN_SEQUENCE_MAX + 1];
+    let mut q = std::mem::MaybeUninit::<ffi::zr_event_queue_t>::zeroed();
+    let rc = unsafe {
+        ffi::zr_event_queue_init(
+            q.as_mut_ptr(),
+            events.as_mut_ptr(),
+            events.len() as u32,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if rc != ffi::ZR_OK {
+        return true;
+    }
+    unsafe { ffi::zr_input_parse_bytes(q.as_mut_ptr(), seq.as_ptr(), seq.len(), 0) };
+    let mut pop = || {
+        let mut ev = ffi::zr_event_t::default();
+        unsafe { ffi::zr_event_queue_pop(q.as_mut_ptr(), &mut ev) }.then_some(ev)
+    };
+    let (Some(first), Some(second)) = (pop(), pop()) else {
+        return true;
+    };
+    let fallback = first.type_ == ffi::ZR_EV_KEY
+        && first.u[0] == ffi::ZR_KEY_ESCAPE
+        && second.type_ == ffi::ZR_EV_TEXT
+        && second.u[0] == u32::from(seq[1]);
+    !fallback
+}
+
+fn parse(
+    q: *mut ffi::zr_event_queue_t,
+    bytes: *const u8,
+    len: usize,
+    time_ms: u32,
+    buffer: bool,
+) -> usize {
+    if !ACTIVE.with(Cell::get) || q.is_null() || bytes.is_null() || len == 0 {
+        return if buffer {
+            unsafe { ffi::zr_input_parse_bytes_prefix(q, bytes, len, time_ms) }
+        } else {
+            unsafe { ffi::zr_input_parse_bytes(q, bytes, len, time_ms) };
+            len
+        };
+    }
+    let input = unsafe { std::slice::from_raw_parts(bytes, len) };
+    let split = split_input(input, buffer, engine_knows);
+    let last = split.pieces.len();
+    for (n, piece) in split.pieces.iter().enumerate() {
+        match piece {
+            Piece::Engine(r) if buffer && split.held.is_none() && n + 1 == last => {
+                let run = &input[r.clone()];
+                return r.start
+                    + unsafe {
+                        ffi::zr_input_parse_bytes_prefix(q, run.as_ptr(), run.len(), time_ms)
+                    };
+            }
+            Piece::Engine(r) => {
+                let run = &input[r.clone()];
+                unsafe { ffi::zr_input_parse_bytes(q, run.as_ptr(), run.len(), time_ms) };
+            }
+            Piece::Unknown(r) => {
+                let seq = &input[r.clone()];
+                let rc = unsafe {
+                    ffi::zr_event_queue_post_user(
+                        q,
+                        time_ms,
+                        UNKNOWN_SEQUENCE_TAG,
+                        seq.as_ptr(),
+                        seq.len() as u32,
+                    )
+                };
+                if rc != ffi::ZR_OK {
+                    /* No room: degrade the way the engine always has. */
+                    unsafe { ffi::zr_input_parse_bytes(q, seq.as_ptr(), seq.len(), time_ms) };
+                }
+            }
+        }
+    }
+    split.held.unwrap_or(len)
+}
+
+/// The engine's `zr_input_parse_bytes` and `zr_input_parse_bytes_prefix`
+/// hooks (see hooks.rs), so unrecognized sequences can be queued as
+/// `UNKNOWN_SEQUENCE_TAG` user events instead.
+pub(crate) extern "C" fn hook_input_parse_bytes(
+    q: *mut ffi::zr_event_queue_t,
+    bytes: *const u8,
+    len: usize,
+    time_ms: u32,
+) {
+    parse(q, bytes, len, time_ms, false);
+}
+
+pub(crate) extern "C" fn hook_input_parse_bytes_prefix(
+    q: *mut ffi::zr_event_queue_t,
+    bytes: *const u8,
+    len: usize,
+    time_ms: u32,
+) -> usize {
+    parse(q, bytes, len, time_ms, true)
+}
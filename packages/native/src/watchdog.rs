@@ -0,0 +1,234 @@
+use crate::error::{zr_result_error, ZrCode};
+use crate::ffi;
+use crate::registry::{find_engine_slot, snapshot_engine_slots};
+use napi_derive::napi;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+const WATCHDOG_TICK: Duration = Duration::from_millis(10);
+const WATCHDOG_REPORTS_MAX: usize = 64;
+
+// The monitor only observes; aborting a call that is inside the C engine is
+// not safe, so an overrun becomes a report, a log line and a trace record
+// instead of a cancellation.
+static WATCHDOG_THREAD: OnceLock<()> = OnceLock::new();
+
+// Watched calls in flight across all engines. The monitor thread parks on
+// `WATCHDOG_WAKE` while this is zero instead of ticking for nothing.
+static WATCHED_CALLS: Mutex<usize> = Mutex::new(0);
+static WATCHDOG_WAKE: Condvar = Condvar::new();
+
+#[napi(object)]
+#[allow(non_snake_case)]
+pub struct WatchdogReport {
+    pub engineId: u32,
+    pub functionName: String,
+    pub budgetMs: u32,
+    /// Elapsed time when the overrun was detected (the call may still be running).
+    pub elapsedMs: u32,
+}
+
+struct ActiveCall {
+    name: &'static str,
+    started: Instant,
+    budget: Duration,
+    /// What the monitor logged on overrun, traced once the call returns.
+    overrun: Option<CString>,
+}
+
+struct WatchdogInner {
+    active: Option<ActiveCall>,
+    reports: VecDeque<WatchdogReport>,
+}
+
+pub(crate) struct WatchdogState {
+    budget_ms: AtomicU32,
+    inner: Mutex<WatchdogInner>,
+}
+
+impl WatchdogState {
+    pub(crate) fn new(budget_ms: u32) -> Self {
+        if budget_ms != 0 {
+            ensure_watchdog_thread();
+        }
+        Self {
+            budget_ms: AtomicU32::new(budget_ms),
+            inner: Mutex::new(WatchdogInner {
+                active: None,
+                reports: VecDeque::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WatchdogInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        }
+    }
+
+    /// Mark the start of a potentially blocking call on `engine`.
+    /// `allowance_ms` extends the budget for calls that block by contract
+    /// (poll timeouts).
+    pub(crate) fn watch(
+        &self,
+        engine: *mut ffi::zr_engine_t,
+        name: &'static str,
+        allowance_ms: u32,
+    ) -> WatchedCall<'_> {
+        let budget_ms = self.budget_ms.load(Ordering::Relaxed);
+        if budget_ms != 0 {
+            let budget = Duration::from_millis(u64::from(budget_ms) + u64::from(allowance_ms));
+            self.lock().active = Some(ActiveCall {
+                name,
+                started: Instant::now(),
+                budget,
+                overrun: None,
+            });
+            begin_watched_call();
+        }
+        WatchedCall {
+            state: self,
+            engine,
+            counted: budget_ms != 0,
+        }
+    }
+
+    pub(crate) fn check(&self, engine_id: u32, now: Instant) {
+        let budget_ms = self.budget_ms.load(Ordering::Relaxed);
+        if budget_ms == 0 {
+            return;
+        }
+        let mut inner = self.lock();
+        let Some(call) = inner.active.as_mut() else {
+            return;
+        };
+        let elapsed = now.saturating_duration_since(call.started);
+        if call.overrun.is_some() || elapsed <= call.budget {
+            return;
+        }
+        let elapsed_ms = elapsed.as_millis().min(u32::MAX as u128) as u32;
+        let detail = format!(
+            "{} exceeded maxCallMs {budget_ms} ({elapsed_ms} ms)",
+            call.name
+        );
+        log_overrun(&format!("watchdog: engine {engine_id}: {detail}"));
+        call.overrun = CString::new(detail).ok();
+        let report = WatchdogReport {
+            engineId: engine_id,
+            functionName: call.name.to_owned(),
+            budgetMs: budget_ms,
+            elapsedMs: elapsed_ms,
+        };
+        if inner.reports.len() == WATCHDOG_REPORTS_MAX {
+            inner.reports.pop_front();
+        }
+        inner.reports.push_back(report);
+    }
+
+    pub(crate) fn take_reports(&self) -> Vec<WatchdogReport> {
+        self.lock().reports.drain(..).collect()
+    }
+}
+
+pub(crate) struct WatchedCall<'a> {
+    state: &'a WatchdogState,
+    engine: *mut ffi::zr_engine_t,
+    counted: bool,
+}
+
+impl Drop for WatchedCall<'_> {
+    fn drop(&mut self) {
+        if !self.counted {
+            return;
+        }
+        let overrun = self
+            .state
+            .lock()
+            .active
+            .take()
+            .and_then(|call| call.overrun);
+        end_watched_call();
+        // The trace ring is engine-thread only, so the record waits for the
+        // call to return to its owner thread.
+        if let (Some(message), false) = (overrun, self.engine.is_null()) {
+            unsafe { ffi::engine_debug_trace_warning(self.engine, message.as_ptr()) };
+        }
+    }
+}
+
+/// Hand an overrun to the engine log sink (the `setAssertMode` callback when
+/// one is installed). Safe from the monitor thread.
+fn log_overrun(line: &str) {
+    unsafe {
+        ffi::zr_log_write(ffi::zr_string_view_t {
+            ptr: line.as_ptr().cast(),
+            len: line.len(),
+        });
+    }
+}
+
+fn lock_watched_calls() -> MutexGuard<'static, usize> {
+    match WATCHED_CALLS.lock() {
+        Ok(guard) => guard,
+        Err(poison) => poison.into_inner(),
+    }
+}
+
+fn begin_watched_call() {
+    let mut watched = lock_watched_calls();
+    *watched += 1;
+    if *watched == 1 {
+        WATCHDOG_WAKE.notify_one();
+    }
+}
+
+fn end_watched_call() {
+    let mut watched = lock_watched_calls();
+    *watched = watched.saturating_sub(1);
+}
+
+/// Block the monitor thread until some engine is inside a watched call.
+fn wait_for_watched_call() {
+    let mut watched = lock_watched_calls();
+    while *watched == 0 {
+        watched = match WATCHDOG_WAKE.wait(watched) {
+            Ok(guard) => guard,
+            Err(poison) => poison.into_inner(),
+        };
+    }
+}
+
+fn scan_slots(now: Instant) {
+    for (engine_id, slot) in snapshot_engine_slots() {
+        slot.watchdog.check(engine_id, now);
+    }
+}
+
+fn ensure_watchdog_thread() {
+    WATCHDOG_THREAD.get_or_init(|| {
+        let spawned = std::thread::Builder::new()
+            .name("rezi-native-watchdog".to_owned())
+            .spawn(|| loop {
+                wait_for_watched_call();
+                std::thread::sleep(WATCHDOG_TICK);
+                scan_slots(Instant::now());
+            });
+        // Without a monitor thread the watchdog degrades to a no-op.
+        drop(spawned);
+    });
+}
+
+/// Drain overrun reports for an engine. Callable from any thread so a
+/// supervisor can observe an owner thread that is stuck inside the engine.
+/// Throws `ZR_ERR_INVALID_ARGUMENT` for an unknown or destroyed engine id.
+#[napi(js_name = "engineTakeWatchdogReports")]
+pub fn engine_take_watchdog_reports(engine_id: u32) -> napi::Result<Vec<WatchdogReport>, ZrCode> {
+    let slot = find_engine_slot(engine_id).ok_or_else(|| {
+        zr_result_error(ffi::ZR_ERR_INVALID_ARGUMENT, "engineTakeWatchdogReports")
+    })?;
+    Ok(slot.watchdog.take_reports())
+}
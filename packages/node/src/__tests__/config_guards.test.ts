@@ -409,6 +409,16 @@ test("config guard: createNodeBackend rejects maxEventBytes above safe bound", (
   );
 });
 
+test("config guard: createNodeBackend rejects maxEventBytes below one batch header", () => {
+  assert.throws(
+    () => createNodeBackend({ maxEventBytes: 23 }),
+    (err) =>
+      err instanceof ZrUiError &&
+      err.code === "ZRUI_INVALID_PROPS" &&
+      err.message.includes("maxEventBytes must be >= 24"),
+  );
+});
+
 test("config guard: createNodeApp rejects fpsCap above safe bound", () => {
   assert.throws(
     () =>
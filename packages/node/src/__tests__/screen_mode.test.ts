@@ -5,6 +5,7 @@ import {
   INLINE_ROWS_MAX,
   NATIVE_SCREEN_MODE_ALT,
   NATIVE_SCREEN_MODE_INLINE,
+  deriveRuntimeConfigBase,
   mergeScreenIntoNativeConfig,
   normalizeBackendNativeConfig,
 } from "../backend/backendSharedConfig.js";
@@ -89,6 +90,32 @@ test("screen: inline rows at bounds are accepted", () => {
   }
 });
 
+test("screen: runtime config base drops binding-only create keys", () => {
+  const merged = mergeScreenIntoNativeConfig(
+    normalizeBackendNativeConfig({
+      targetFps: 30,
+      writeChunkBytes: 4096,
+      write_chunk_bytes: 4096,
+      frameStatsWindow: 120,
+      frame_stats_window: 120,
+      unknownSequences: true,
+      unknown_sequences: true,
+      initialStyle: { fgRgb: 0, bgRgb: 0, attrs: 0, reserved: 0 },
+    }),
+    { mode: "inline", inlineRows: 4 },
+  );
+  const runtime = deriveRuntimeConfigBase(merged);
+  assert.equal(cfgKey(runtime, "targetFps"), 30);
+  assert.equal(cfgKey(runtime, "inlineRows"), 4);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "writeChunkBytes"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "write_chunk_bytes"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "frameStatsWindow"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "frame_stats_window"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "unknownSequences"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "unknown_sequences"), false);
+  assert.equal(Object.prototype.hasOwnProperty.call(runtime, "initialStyle"), false);
+});
+
 test("backend: worker path forwards screen option to engineCreate", async () => {
   const shim = new URL("./worker/testShims/screenExpectNative.js", import.meta.url).href;
   const backend = createNodeBackendInternal({
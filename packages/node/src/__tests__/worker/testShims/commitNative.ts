@@ -82,7 +82,7 @@ export const native = {
     return {
       structSize: 0,
       negotiatedEngineAbiMajor: 1,
-      negotiatedEngineAbiMinor: 4,
+      negotiatedEngineAbiMinor: 5,
       negotiatedEngineAbiPatch: 0,
       negotiatedDrawlistVersion: 1,
       negotiatedEventBatchVersion: 1,
@@ -0,0 +1,20 @@
+import { RESULT_CODES, nativeError } from "./nativeErrors.js";
+
+/**
+ * Native shim whose engine_create fails the way the addon does without a
+ * usable terminal: it throws a ZR_ERR_PLATFORM-coded error.
+ */
+export const native = {
+  ziResultCodes(): Readonly<Record<string, number>> {
+    return RESULT_CODES;
+  },
+
+  engineCreate(_config?: object | null): number {
+    throw nativeError(
+      "ZR_ERR_PLATFORM",
+      "engineCreate: platform init failed (ZR_ERR_PLATFORM, -6)",
+    );
+  },
+
+  engineDestroy(_engineId: number): void {},
+} as const;
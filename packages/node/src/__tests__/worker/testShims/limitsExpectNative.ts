@@ -1,3 +1,5 @@
+import { nativeError } from "./nativeErrors.js";
+
 type EngineState = Readonly<{
   destroyed: boolean;
 }>;
@@ -34,14 +36,18 @@ function readLimitU32(lim: Limits, camel: keyof Limits, snake: keyof Limits): nu
   return parsePositiveInt(lim[camel]) ?? parsePositiveInt(lim[snake]);
 }
 
+function rejectConfig(): never {
+  throw nativeError("ZR_ERR_INVALID_ARGUMENT", "engineCreate: unexpected limits");
+}
+
 export const native = {
   engineCreate(config?: object | null): number {
     const lim = readLimits(config);
-    if (lim === null) return -1;
+    if (lim === null) rejectConfig();
     const outMax = readLimitU32(lim, "outMaxBytesPerFrame", "out_max_bytes_per_frame");
     const dlMax = readLimitU32(lim, "dlMaxTotalBytes", "dl_max_total_bytes");
-    if (outMax !== 3333333) return -1;
-    if (dlMax !== 2222222) return -1;
+    if (outMax !== 3333333) rejectConfig();
+    if (dlMax !== 2222222) rejectConfig();
     const id = nextEngineId++;
     engines.set(id, Object.freeze({ destroyed: false }));
     return id;
@@ -1,3 +1,5 @@
+import { nativeError } from "./nativeErrors.js";
+
 type EngineState = Readonly<{
   destroyed: boolean;
 }>;
@@ -36,17 +38,21 @@ function readLimitU32(lim: Limits, camel: keyof Limits, snake: keyof Limits): nu
   return parsePositiveInt(lim[camel]) ?? parsePositiveInt(lim[snake]);
 }
 
+function rejectConfig(): never {
+  throw nativeError("ZR_ERR_INVALID_ARGUMENT", "engineCreate: missing default limits");
+}
+
 export const native = {
   engineCreate(config?: object | null): number {
     const lim = readLimits(config);
-    if (lim === null) return -1;
+    if (lim === null) rejectConfig();
     const outMax = readLimitU32(lim, "outMaxBytesPerFrame", "out_max_bytes_per_frame");
     const dlMax = readLimitU32(lim, "dlMaxTotalBytes", "dl_max_total_bytes");
     const cmdMax = readLimitU32(lim, "dlMaxCmds", "dl_max_cmds");
-    if (outMax === null || dlMax === null || cmdMax === null) return -1;
-    if (outMax < 2 * 1024 * 1024) return -1;
-    if (dlMax < 2 * 1024 * 1024) return -1;
-    if (cmdMax < 100_000) return -1;
+    if (outMax === null || dlMax === null || cmdMax === null) rejectConfig();
+    if (outMax < 2 * 1024 * 1024) rejectConfig();
+    if (dlMax < 2 * 1024 * 1024) rejectConfig();
+    if (cmdMax < 100_000) rejectConfig();
 
     const id = nextEngineId++;
     engines.set(id, Object.freeze({ destroyed: false }));
@@ -0,0 +1,17 @@
+/**
+ * Result codes and coded errors shaped like the native addon's, for shims
+ * whose fallible calls throw instead of returning a code.
+ */
+export const RESULT_CODES: Readonly<Record<string, number>> = Object.freeze({
+  ZR_OK: 0,
+  ZR_ERR_INVALID_ARGUMENT: -1,
+  ZR_ERR_OOM: -2,
+  ZR_ERR_LIMIT: -3,
+  ZR_ERR_UNSUPPORTED: -4,
+  ZR_ERR_FORMAT: -5,
+  ZR_ERR_PLATFORM: -6,
+});
+
+export function nativeError(code: string, message: string): Error {
+  return Object.assign(new Error(message), { code });
+}
@@ -1,3 +1,5 @@
+import { nativeError } from "./nativeErrors.js";
+
 type EngineState = Readonly<{
   destroyed: boolean;
 }>;
@@ -37,12 +39,16 @@ function readInlineRows(cfg: unknown): number | null {
   return readU32(rec.inlineRows) ?? readU32(rec.inline_rows);
 }
 
+function rejectConfig(): never {
+  throw nativeError("ZR_ERR_INVALID_ARGUMENT", "engineCreate: unexpected screen config");
+}
+
 export const native = {
   engineCreate(config?: object | null): number {
     // Expect the high-level screen option to arrive as native wire keys:
     // plat.screenMode=1 (inline) and inlineRows=6.
-    if (readScreenMode(config) !== 1) return -1;
-    if (readInlineRows(config) !== 6) return -1;
+    if (readScreenMode(config) !== 1) rejectConfig();
+    if (readInlineRows(config) !== 6) rejectConfig();
     const id = nextEngineId++;
     engines.set(id, Object.freeze({ destroyed: false }));
     return id;
@@ -1,3 +1,5 @@
+import { nativeError } from "./nativeErrors.js";
+
 type EngineState = Readonly<{
   destroyed: boolean;
 }>;
@@ -23,10 +25,14 @@ function readTargetFps(cfg: unknown): number | null {
   return parsePositiveInt(rec.targetFps) ?? parsePositiveInt(rec.target_fps);
 }
 
+function rejectConfig(): never {
+  throw nativeError("ZR_ERR_INVALID_ARGUMENT", "engineCreate: targetFps is required");
+}
+
 export const native = {
   engineCreate(config?: object | null): number {
     const targetFps = readTargetFps(config);
-    if (targetFps === null) return -1;
+    if (targetFps === null) rejectConfig();
     const id = nextEngineId++;
     engines.set(id, Object.freeze({ destroyed: false }));
     return id;
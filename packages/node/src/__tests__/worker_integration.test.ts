@@ -719,6 +719,42 @@ test("backend: inline path fails deterministically on invalid engine_poll_events
   backend.dispose();
 });
 
+test("backend: worker path reports the native code when engine_create fails", async () => {
+  const shim = new URL("./worker/testShims/createFailsNative.js", import.meta.url).href;
+  const backend = createNodeBackendInternal({
+    config: { fpsCap: 1000 },
+    nativeShimModule: shim,
+  });
+
+  await assert.rejects(
+    backend.start(),
+    (err) =>
+      err instanceof ZrUiError &&
+      err.code === "ZRUI_BACKEND_ERROR" &&
+      err.message.includes("engineCreate (-6)") &&
+      err.message.includes("ZR_ERR_PLATFORM"),
+  );
+  backend.dispose();
+});
+
+test("backend: inline path reports the native code when engine_create fails", async () => {
+  const shim = new URL("./worker/testShims/createFailsNative.js", import.meta.url).href;
+  const backend = createNodeBackendInternal({
+    config: { executionMode: "inline", fpsCap: 1000 },
+    nativeShimModule: shim,
+  });
+
+  await assert.rejects(
+    backend.start(),
+    (err) =>
+      err instanceof ZrUiError &&
+      err.code === "ZRUI_BACKEND_ERROR" &&
+      err.message.includes("code=-6") &&
+      err.message.includes("ZR_ERR_PLATFORM"),
+  );
+  backend.dispose();
+});
+
 test(
   "backend: worker mode without shim rejects deterministically when no TTY is present",
   { concurrency: false },
@@ -4,6 +4,12 @@ export const DEFAULT_FPS_CAP = 60 as const;
 export const MAX_SAFE_FPS_CAP = 1000 as const;
 export const DEFAULT_MAX_EVENT_BYTES = 1 << 20;
 export const MAX_SAFE_EVENT_BYTES = 4 << 20;
+/**
+ * One ZREV batch header. The engine truncates a batch that does not fit a
+ * larger buffer, so only a smaller one makes every poll with input pending
+ * fail with `ZR_ERR_LIMIT`.
+ */
+export const MIN_EVENT_BYTES = 24;
 
 const EMPTY_NATIVE_CONFIG = Object.freeze({}) as Readonly<Record<string, unknown>>;
 
@@ -127,6 +133,27 @@ const RUNTIME_STRIPPED_KEYS: readonly string[] = [
   "requested_drawlist_version",
   "requestedEventBatchVersion",
   "requested_event_batch_version",
+  /* Binding-only create keys (consumed by @rezi-ui/native, never forwarded). */
+  "maxCallMs",
+  "max_call_ms",
+  "unsupportedAttrFallback",
+  "unsupported_attr_fallback",
+  "idleMs",
+  "idle_ms",
+  "writeChunkBytes",
+  "write_chunk_bytes",
+  "frameStatsWindow",
+  "frame_stats_window",
+  "trackFrame",
+  "track_frame",
+  "unknownSequences",
+  "unknown_sequences",
+  "inputFd",
+  "input_fd",
+  "outputFd",
+  "output_fd",
+  "initialStyle",
+  "initial_style",
   /* Node-transport keys (worker init only; unknown to engine_set_config). */
   "fpsCap",
   "maxEventBytes",
@@ -207,6 +234,22 @@ export function parseBoundedPositiveIntOrThrow(
   return parsed;
 }
 
+export function parseMaxEventBytesOrThrow(value: unknown): number {
+  const parsed = parseBoundedPositiveIntOrThrow(
+    "maxEventBytes",
+    value,
+    DEFAULT_MAX_EVENT_BYTES,
+    MAX_SAFE_EVENT_BYTES,
+  );
+  if (parsed < MIN_EVENT_BYTES) {
+    throw new ZrUiError(
+      "ZRUI_INVALID_PROPS",
+      `maxEventBytes must be >= ${String(MIN_EVENT_BYTES)}`,
+    );
+  }
+  return parsed;
+}
+
 function readNativeTargetFpsValues(
   cfg: Readonly<Record<string, unknown>>,
 ): Readonly<{ camel: number | null; snake: number | null }> {
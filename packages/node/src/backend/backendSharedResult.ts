@@ -0,0 +1,57 @@
+/**
+ * Fallible native calls throw an error whose `code` is the result code name
+ * instead of returning a negative code. Backends turn such throws back into
+ * the numeric code so every native failure keeps one reporting path.
+ *
+ * The per-frame calls (submit, present, poll, post) are made directly, with
+ * `nativeResultRc` on return and `nativeFailureRc` in the caller's own
+ * `catch`, so a successful frame allocates no closure. `callNativeRc` wraps
+ * the control-plane calls (config, debug, scrollback).
+ */
+type NativeResultCodesApi = Readonly<{
+  ziResultCodes?: () => Readonly<Record<string, number>>;
+}>;
+
+let resultCodes: ReadonlyMap<string, number> = new Map();
+
+/**
+ * Adopt the loaded addon's `code` name to result code table. Until this runs,
+ * and for test shims without `ziResultCodes`, every throw propagates.
+ */
+export function adoptNativeResultCodes(native: NativeResultCodesApi): void {
+  if (typeof native.ziResultCodes !== "function") return;
+  resultCodes = new Map(Object.entries(native.ziResultCodes()));
+}
+
+/** Numeric result code carried by a native error, or null for any other throw. */
+export function nativeErrorCode(err: unknown): number | null {
+  if (typeof err !== "object" || err === null) return null;
+  const code = (err as { code?: unknown }).code;
+  if (typeof code !== "string") return null;
+  return resultCodes.get(code) ?? null;
+}
+
+/** Result of a native call that returned: its count, or `0` for `undefined`. */
+export function nativeResultRc(result: number | void): number {
+  return typeof result === "number" ? result : 0;
+}
+
+/** Result code of a native call that threw a native error; rethrows anything else. */
+export function nativeFailureRc(err: unknown): number {
+  const rc = nativeErrorCode(err);
+  if (rc === null) throw err;
+  return rc;
+}
+
+/**
+ * Run a throwing native call and return its result (`0` for calls that
+ * return nothing) or, when it throws a native error, that error's negative
+ * result code. Other throws propagate.
+ */
+export function callNativeRc(call: () => number | void): number {
+  try {
+    return nativeResultRc(call());
+  } catch (err) {
+    return nativeFailureRc(err);
+  }
+}
@@ -46,14 +46,13 @@ import {
 } from "../worker/protocol.js";
 import {
   DEFAULT_FPS_CAP,
-  DEFAULT_MAX_EVENT_BYTES,
-  MAX_SAFE_EVENT_BYTES,
   MAX_SAFE_FPS_CAP,
   deriveRuntimeConfigBase,
   isInlineScreenNativeConfig,
   mergeScreenIntoNativeConfig,
   normalizeBackendNativeConfig,
   parseBoundedPositiveIntOrThrow,
+  parseMaxEventBytesOrThrow,
   parsePositiveInt,
   parsePositiveIntOr,
   resolveTargetFps,
@@ -152,12 +151,7 @@ export function createNodeBackendInternal(opts: NodeBackendInternalOpts = {}): N
     return createNodeBackendInlineInternal(opts);
   }
   const requestedDrawlistVersion = ZR_DRAWLIST_VERSION_V1;
-  const maxEventBytes = parseBoundedPositiveIntOrThrow(
-    "maxEventBytes",
-    cfg.maxEventBytes,
-    DEFAULT_MAX_EVENT_BYTES,
-    MAX_SAFE_EVENT_BYTES,
-  );
+  const maxEventBytes = parseMaxEventBytesOrThrow(cfg.maxEventBytes);
   const frameTransportMode =
     cfg.frameTransport === "transfer" || cfg.frameTransport === "sab" ? cfg.frameTransport : "auto";
   const frameSabSlotCount = parsePositiveIntOr(cfg.frameSabSlotCount, FRAME_SAB_SLOT_COUNT_DEFAULT);
@@ -36,14 +36,13 @@ import {
 } from "../frameAudit.js";
 import {
   DEFAULT_FPS_CAP,
-  DEFAULT_MAX_EVENT_BYTES,
-  MAX_SAFE_EVENT_BYTES,
   MAX_SAFE_FPS_CAP,
   deriveRuntimeConfigBase,
   isInlineScreenNativeConfig,
   mergeScreenIntoNativeConfig,
   normalizeBackendNativeConfig,
   parseBoundedPositiveIntOrThrow,
+  parseMaxEventBytesOrThrow,
   parsePositiveIntOr,
   resolveTargetFps,
   validateInlineRowsOrThrow,
@@ -54,6 +53,13 @@ import {
   readDebugBytesWithRetry,
 } from "./backendSharedDebug.js";
 import { attachBackendMarkers } from "./backendSharedMarkers.js";
+import {
+  adoptNativeResultCodes,
+  callNativeRc,
+  nativeErrorCode,
+  nativeFailureRc,
+  nativeResultRc,
+} from "./backendSharedResult.js";
 import { applyEmojiWidthPolicy, resolveBackendEmojiWidthPolicy } from "./emojiWidthPolicy.js";
 import type {
   Deferred,
@@ -100,15 +106,15 @@ type NativeDebugQueryResult = Readonly<{
 type NativeApi = Readonly<{
   engineCreate: (config?: object | null) => number;
   engineDestroy: (engineId: number) => void;
-  engineSubmitDrawlist: (engineId: number, drawlist: Uint8Array) => number;
-  enginePresent: (engineId: number) => number;
+  engineSubmitDrawlist: (engineId: number, drawlist: Uint8Array) => void;
+  enginePresent: (engineId: number) => void;
   enginePollEvents: (engineId: number, timeoutMs: number, out: Uint8Array) => number;
-  enginePostUserEvent: (engineId: number, tag: number, payload: Uint8Array) => number;
-  engineSetConfig: (engineId: number, cfg?: object | null) => number;
-  engineCommitScrollback?: (engineId: number, drawlist: Uint8Array, rows: number) => number;
+  enginePostUserEvent: (engineId: number, tag: number, payload: Uint8Array) => void;
+  engineSetConfig: (engineId: number, cfg: object) => void;
+  engineCommitScrollback?: (engineId: number, drawlist: Uint8Array, rows: number) => void;
   engineGetCaps: (engineId: number) => NativeCaps;
-  engineDebugEnable?: (engineId: number, config?: object | null) => number;
-  engineDebugDisable?: (engineId: number) => number;
+  engineDebugEnable?: (engineId: number, config?: object | null) => void;
+  engineDebugDisable?: (engineId: number) => void;
   engineDebugQuery?: (
     engineId: number,
     query: object | null,
@@ -117,13 +123,14 @@ type NativeApi = Readonly<{
   engineDebugGetPayload?: (engineId: number, recordId: bigint, outPayload: Uint8Array) => number;
   engineDebugGetStats?: (engineId: number) => NativeDebugStats;
   engineDebugExport?: (engineId: number, outBuf: Uint8Array) => number;
-  engineDebugReset?: (engineId: number) => number;
+  engineDebugReset?: (engineId: number) => void;
+  ziResultCodes?: () => Readonly<Record<string, number>>;
 }>;
 
 type NativeApiWithDebug = NativeApi &
   Readonly<{
-    engineDebugEnable: (engineId: number, config?: object | null) => number;
-    engineDebugDisable: (engineId: number) => number;
+    engineDebugEnable: (engineId: number, config?: object | null) => void;
+    engineDebugDisable: (engineId: number) => void;
     engineDebugQuery: (
       engineId: number,
       query: object | null,
@@ -132,7 +139,7 @@ type NativeApiWithDebug = NativeApi &
     engineDebugGetPayload: (engineId: number, recordId: bigint, outPayload: Uint8Array) => number;
     engineDebugGetStats: (engineId: number) => NativeDebugStats;
     engineDebugExport: (engineId: number, outBuf: Uint8Array) => number;
-    engineDebugReset: (engineId: number) => number;
+    engineDebugReset: (engineId: number) => void;
   }>;
 
 const EVENT_POOL_SIZE = 16 as const;
@@ -227,12 +234,7 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
     DEFAULT_FPS_CAP,
     MAX_SAFE_FPS_CAP,
   );
-  const maxEventBytes = parseBoundedPositiveIntOrThrow(
-    "maxEventBytes",
-    cfg.maxEventBytes,
-    DEFAULT_MAX_EVENT_BYTES,
-    MAX_SAFE_EVENT_BYTES,
-  );
+  const maxEventBytes = parseMaxEventBytesOrThrow(cfg.maxEventBytes);
   const nativeConfig = mergeScreenIntoNativeConfig(
     normalizeBackendNativeConfig(cfg.nativeConfig),
     cfg.screen,
@@ -438,10 +440,14 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
       let written = -1;
       const startMs = PERF_ENABLED ? performance.now() : 0;
       try {
-        written = native.enginePollEvents(engineId, 0, new Uint8Array(outBuf));
+        written = nativeResultRc(native.enginePollEvents(engineId, 0, new Uint8Array(outBuf)));
       } catch (err) {
-        failWith("enginePollEvents", -1, `engine_poll_events threw: ${safeDetail(err)}`);
-        return;
+        const rc = nativeErrorCode(err);
+        if (rc === null) {
+          failWith("enginePollEvents", -1, `engine_poll_events threw: ${safeDetail(err)}`);
+          return;
+        }
+        written = rc;
       }
       if (PERF_ENABLED) {
         perfRecord("event_poll", performance.now() - startMs);
@@ -493,6 +499,7 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
     if (native !== null) return native;
     if (nativePromise !== null) return nativePromise;
     nativePromise = loadNative(opts.nativeShimModule).then((mod) => {
+      adoptNativeResultCodes(mod);
       native = mod;
       return mod;
     });
@@ -557,14 +564,16 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
         }
 
         const api = await ensureNativeLoaded();
-        let id = 0;
+        let id: number;
         try {
           id = api.engineCreate(initConfigResolved);
         } catch (err) {
-          throw new Error(`engine_create threw: ${safeDetail(err)}`);
-        }
-        if (!Number.isInteger(id) || id <= 0) {
-          throw new Error(`engine_create failed: code=${String(id)}`);
+          const rc = nativeErrorCode(err);
+          if (rc === null) throw new Error(`engine_create threw: ${safeDetail(err)}`);
+          throw new ZrUiError(
+            "ZRUI_BACKEND_ERROR",
+            `engine_create failed: code=${String(rc)}: ${safeDetail(err)}`,
+          );
         }
         engineId = id;
         started = true;
@@ -692,7 +701,12 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
             ...fp,
           });
         }
-        const submitRc = native.engineSubmitDrawlist(engineId, drawlist);
+        let submitRc: number;
+        try {
+          submitRc = nativeResultRc(native.engineSubmitDrawlist(engineId, drawlist));
+        } catch (err) {
+          submitRc = nativeFailureRc(err);
+        }
         if (frameAudit.enabled) {
           frameAudit.emit("frame.submit.result", {
             frameSeq,
@@ -716,7 +730,12 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
         if (frameAudit.enabled) {
           frameAudit.emit("frame.accepted", { frameSeq });
         }
-        const presentRc = native.enginePresent(engineId);
+        let presentRc: number;
+        try {
+          presentRc = nativeResultRc(native.enginePresent(engineId));
+        } catch (err) {
+          presentRc = nativeFailureRc(err);
+        }
         if (frameAudit.enabled) {
           frameAudit.emit("frame.present.result", {
             frameSeq,
@@ -771,7 +790,12 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
       if (!started || engineId === null || native === null)
         throw new Error("NodeBackend(inline): not started");
       if (stopRequested) throw new Error("NodeBackend(inline): stopped");
-      const rc = native.enginePostUserEvent(engineId, tag, payload);
+      let rc: number;
+      try {
+        rc = nativeResultRc(native.enginePostUserEvent(engineId, tag, payload));
+      } catch (err) {
+        rc = nativeFailureRc(err);
+      }
       if (rc < 0) {
         throw new ZrUiError(
           "ZRUI_BACKEND_ERROR",
@@ -797,7 +821,8 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
           "commitScrollback: native addon lacks engineCommitScrollback",
         );
       }
-      const rc = commit(engineId, drawlist, rows);
+      const id = engineId;
+      const rc = callNativeRc(() => commit(id, drawlist, rows));
       if (rc < 0) {
         throw new ZrUiError(
           "ZRUI_BACKEND_ERROR",
@@ -821,7 +846,11 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
       }
       /* Resend the resolved create surface (width policy settles at start). */
       const runtimeBase = deriveRuntimeConfigBase(initConfigResolved);
-      const rc = native.engineSetConfig(engineId, { ...runtimeBase, inlineRows: rows });
+      const nativeApi = native;
+      const id = engineId;
+      const rc = callNativeRc(() =>
+        nativeApi.engineSetConfig(id, { ...runtimeBase, inlineRows: rows }),
+      );
       if (rc < 0) {
         throw new ZrUiError("ZRUI_BACKEND_ERROR", `engine_set_config failed: code=${String(rc)}`);
       }
@@ -879,7 +908,8 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
           ? { captureDrawlistBytes: config.captureDrawlistBytes }
           : {}),
       };
-      const rc = dbg.engineDebugEnable(engineId, configWire);
+      const id = engineId;
+      const rc = callNativeRc(() => dbg.engineDebugEnable(id, configWire));
       if (rc < 0) {
         throw new ZrUiError("ZRUI_BACKEND_ERROR", `engineDebugEnable failed: code=${String(rc)}`);
       }
@@ -890,7 +920,8 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
         throw new Error("NodeBackend(inline): engine not started");
       }
       const dbg = ensureDebugApiLoaded(native);
-      const rc = dbg.engineDebugDisable(engineId);
+      const id = engineId;
+      const rc = callNativeRc(() => dbg.engineDebugDisable(id));
       if (rc < 0) {
         throw new ZrUiError("ZRUI_BACKEND_ERROR", `engineDebugDisable failed: code=${String(rc)}`);
       }
@@ -935,7 +966,7 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
       }
       const dbg = ensureDebugApiLoaded(native);
       return readDebugBytesWithRetry(
-        (out) => dbg.engineDebugGetPayload(activeEngineId, recordId, out),
+        (out) => callNativeRc(() => dbg.engineDebugGetPayload(activeEngineId, recordId, out)),
         maxEventBytes,
         null,
         "engineDebugGetPayload",
@@ -966,7 +997,7 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
       }
       const dbg = ensureDebugApiLoaded(native);
       return readDebugBytesWithRetry(
-        (out) => dbg.engineDebugExport(activeEngineId, out),
+        (out) => callNativeRc(() => dbg.engineDebugExport(activeEngineId, out)),
         maxEventBytes,
         new Uint8Array(0),
         "engineDebugExport",
@@ -978,7 +1009,8 @@ export function createNodeBackendInlineInternal(opts: NodeBackendInternalOpts =
         throw new Error("NodeBackend(inline): engine not started");
       }
       const dbg = ensureDebugApiLoaded(native);
-      const rc = dbg.engineDebugReset(engineId);
+      const id = engineId;
+      const rc = callNativeRc(() => dbg.engineDebugReset(id));
       if (rc < 0) {
         throw new ZrUiError("ZRUI_BACKEND_ERROR", `engineDebugReset failed: code=${String(rc)}`);
       }
@@ -8,6 +8,12 @@
 
 import { performance } from "node:perf_hooks";
 import { parentPort, workerData } from "node:worker_threads";
+import {
+  adoptNativeResultCodes,
+  nativeErrorCode,
+  nativeFailureRc,
+  nativeResultRc,
+} from "../backend/backendSharedResult.js";
 import { createFrameAuditLogger, drawlistFingerprint } from "../frameAudit.js";
 import {
   deleteFrameAudit,
@@ -119,6 +125,7 @@ async function loadNative(): Promise<NativeApi> {
 }
 
 const native = await loadNative();
+adoptNativeResultCodes(native);
 
 const runtimeState: EngineWorkerRuntimeState = {
   engineId: null,
@@ -288,9 +295,19 @@ function shutdownNow(): void {
   }
 }
 
+/** `engineSubmitDrawlist` as a result code, without a closure per frame. */
+function submitDrawlistRc(engineId: number, view: Uint8Array): number {
+  try {
+    return nativeResultRc(native.engineSubmitDrawlist(engineId, view));
+  } catch (err) {
+    return nativeFailureRc(err);
+  }
+}
+
 function tick(): void {
   if (!runtimeState.running) return;
   if (runtimeState.engineId === null) return;
+  const engineId = runtimeState.engineId;
 
   let didSubmitDrawlistThisTick = false;
   let didFrameWork = false;
@@ -325,7 +342,7 @@ function tick(): void {
           });
           emitFrameAuditNow("frame.submit.payload", frame.frameSeq, fp);
         }
-        res = native.engineSubmitDrawlist(runtimeState.engineId, view);
+        res = submitDrawlistRc(engineId, view);
       } else {
         if (runtimeState.frameTransport.kind !== FRAME_TRANSPORT_SAB_V1) {
           throw new Error("SAB frame transport unavailable");
@@ -370,7 +387,7 @@ function tick(): void {
               });
               emitFrameAuditNow("frame.submit.payload", frame.frameSeq, fp);
             }
-            res = native.engineSubmitDrawlist(runtimeState.engineId, view);
+            res = submitDrawlistRc(engineId, view);
           }
         }
       }
@@ -415,16 +432,20 @@ function tick(): void {
   if (runtimeState.haveSubmittedDrawlist && didSubmitDrawlistThisTick) {
     let pres = -1;
     try {
-      pres = native.enginePresent(runtimeState.engineId);
+      pres = nativeResultRc(native.enginePresent(engineId));
     } catch (err) {
-      if (submittedFrameSeq !== null) {
-        emitFrameAuditNow("frame.present.throw", submittedFrameSeq, { detail: safeDetail(err) });
+      const rc = nativeErrorCode(err);
+      if (rc === null) {
+        if (submittedFrameSeq !== null) {
+          emitFrameAuditNow("frame.present.throw", submittedFrameSeq, { detail: safeDetail(err) });
+        }
+        if (submittedFrameSeq !== null) postFrameStatusNow(submittedFrameSeq, -1);
+        drainNativeFrameAuditNow("present-throw");
+        fatal("enginePresent", -1, `engine_present threw: ${safeDetail(err)}`);
+        runtimeState.running = false;
+        return;
       }
-      if (submittedFrameSeq !== null) postFrameStatusNow(submittedFrameSeq, -1);
-      drainNativeFrameAuditNow("present-throw");
-      fatal("enginePresent", -1, `engine_present threw: ${safeDetail(err)}`);
-      runtimeState.running = false;
-      return;
+      pres = rc;
     }
     if (pres < 0) {
       if (submittedFrameSeq !== null) {
@@ -457,11 +478,15 @@ function tick(): void {
     let written = -1;
     const pollStart = PERF_ENABLED ? performance.now() : 0;
     try {
-      written = native.enginePollEvents(runtimeState.engineId, 0, new Uint8Array(outBuf));
+      written = nativeResultRc(native.enginePollEvents(engineId, 0, new Uint8Array(outBuf)));
     } catch (err) {
-      fatal("enginePollEvents", -1, `engine_poll_events threw: ${safeDetail(err)}`);
-      runtimeState.running = false;
-      return;
+      const rc = nativeErrorCode(err);
+      if (rc === null) {
+        fatal("enginePollEvents", -1, `engine_poll_events threw: ${safeDetail(err)}`);
+        runtimeState.running = false;
+        return;
+      }
+      written = rc;
     }
     if (PERF_ENABLED) {
       perfRecord(PERF_ENABLED, perfSamples, "event_poll", performance.now() - pollStart);
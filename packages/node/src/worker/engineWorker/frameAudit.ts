@@ -1,3 +1,4 @@
+import { callNativeRc } from "../../backend/backendSharedResult.js";
 import type { FrameAuditLogger } from "../../frameAudit.js";
 import {
   FRAME_AUDIT_NATIVE_ENABLED,
@@ -114,14 +115,17 @@ export function maybeEnableNativeFrameAudit(
 
   let rc = -1;
   try {
-    rc = args.native.engineDebugEnable(args.engineId, {
-      enabled: true,
-      ringCapacity: FRAME_AUDIT_NATIVE_RING_BYTES,
-      minSeverity: 0,
-      categoryMask: NATIVE_FRAME_AUDIT_CATEGORY_MASK,
-      captureRawEvents: false,
-      captureDrawlistBytes: true,
-    });
+    const engineId = args.engineId;
+    rc = callNativeRc(() =>
+      args.native.engineDebugEnable(engineId, {
+        enabled: true,
+        ringCapacity: FRAME_AUDIT_NATIVE_RING_BYTES,
+        minSeverity: 0,
+        categoryMask: NATIVE_FRAME_AUDIT_CATEGORY_MASK,
+        captureRawEvents: false,
+        captureDrawlistBytes: true,
+      }),
+    );
   } catch (err) {
     args.frameAudit.emit("native.debug.enable_error", { detail: args.safeDetail(err) });
     args.state.nativeFrameAuditEnabled = false;
@@ -1,3 +1,8 @@
+import {
+  callNativeRc,
+  nativeErrorCode,
+  nativeResultRc,
+} from "../../backend/backendSharedResult.js";
 import type { FrameAuditLogger } from "../../frameAudit.js";
 import { drawlistFingerprint } from "../../frameAudit.js";
 import {
@@ -157,7 +162,7 @@ export function handleInitMessage(
 
   ctx.runtimeState.frameTransport = parseFrameTransportConfig(msg.config.frameTransport);
 
-  let id = 0;
+  let id: number;
   try {
     const {
       maxEventBytes: _maxEventBytes,
@@ -167,12 +172,12 @@ export function handleInitMessage(
     } = msg.config;
     id = ctx.native.engineCreate(nativeCfg);
   } catch (err) {
-    ctx.fatal("engineCreate", -1, `engine_create threw: ${ctx.safeDetail(err)}`);
-    ctx.shutdownNow();
-    return;
-  }
-  if (!Number.isInteger(id) || id <= 0) {
-    ctx.fatal("engineCreate", id, "engine_create failed");
+    const code = nativeErrorCode(err);
+    if (code === null) {
+      ctx.fatal("engineCreate", -1, `engine_create threw: ${ctx.safeDetail(err)}`);
+    } else {
+      ctx.fatal("engineCreate", code, `engine_create failed: ${ctx.safeDetail(err)}`);
+    }
     ctx.shutdownNow();
     return;
   }
@@ -378,7 +383,8 @@ export function handleSetConfigMessage(
   if (ctx.runtimeState.engineId === null) return;
   let rc = -1;
   try {
-    rc = ctx.native.engineSetConfig(ctx.runtimeState.engineId, msg.config);
+    const engineId = ctx.runtimeState.engineId;
+    rc = callNativeRc(() => ctx.native.engineSetConfig(engineId, msg.config));
   } catch (err) {
     ctx.fatal("engineSetConfig", -1, `engine_set_config threw: ${ctx.safeDetail(err)}`);
     ctx.runtimeState.running = false;
@@ -397,15 +403,21 @@ export function handlePostUserEventMessage(
   if (ctx.runtimeState.engineId === null) return;
   let rc = -1;
   try {
-    rc = ctx.native.enginePostUserEvent(
-      ctx.runtimeState.engineId,
-      msg.tag,
-      new Uint8Array(msg.payload, 0, msg.byteLen),
+    rc = nativeResultRc(
+      ctx.native.enginePostUserEvent(
+        ctx.runtimeState.engineId,
+        msg.tag,
+        new Uint8Array(msg.payload, 0, msg.byteLen),
+      ),
     );
   } catch (err) {
-    ctx.fatal("enginePostUserEvent", -1, `engine_post_user_event threw: ${ctx.safeDetail(err)}`);
-    ctx.runtimeState.running = false;
-    return;
+    const code = nativeErrorCode(err);
+    if (code === null) {
+      ctx.fatal("enginePostUserEvent", -1, `engine_post_user_event threw: ${ctx.safeDetail(err)}`);
+      ctx.runtimeState.running = false;
+      return;
+    }
+    rc = code;
   }
   if (rc < 0) {
     ctx.fatal("enginePostUserEvent", rc, "engine_post_user_event failed");
@@ -433,8 +445,9 @@ export function handleCommitScrollbackMessage(
       rc = -4;
     } else {
       try {
+        const engineId = ctx.runtimeState.engineId;
         const view = new Uint8Array(msg.bytes, 0, msg.byteLen);
-        rc = commit(ctx.runtimeState.engineId, view, msg.rows);
+        rc = callNativeRc(() => commit(engineId, view, msg.rows));
       } catch (err) {
         ctx.fatal(
           "engineCommitScrollback",
@@ -499,7 +512,8 @@ export function handleDebugEnableMessage(
       captureRawEvents: msg.config.captureRawEvents ?? false,
       captureDrawlistBytes: msg.config.captureDrawlistBytes ?? false,
     };
-    rc = ctx.native.engineDebugEnable(ctx.runtimeState.engineId, nativeConfig);
+    const engineId = ctx.runtimeState.engineId;
+    rc = callNativeRc(() => ctx.native.engineDebugEnable(engineId, nativeConfig));
   } catch (err) {
     ctx.fatal("engineDebugEnable", -1, `engine_debug_enable threw: ${ctx.safeDetail(err)}`);
     return;
@@ -524,7 +538,8 @@ export function handleDebugDisableMessage(
   if (ctx.runtimeState.engineId === null) return;
   let rc = -1;
   try {
-    rc = ctx.native.engineDebugDisable(ctx.runtimeState.engineId);
+    const engineId = ctx.runtimeState.engineId;
+    rc = callNativeRc(() => ctx.native.engineDebugDisable(engineId));
   } catch (err) {
     ctx.fatal("engineDebugDisable", -1, `engine_debug_disable threw: ${ctx.safeDetail(err)}`);
     return;
@@ -596,10 +611,9 @@ export function handleDebugGetPayloadMessage(
     const payloadBuf = new ArrayBuffer(msg.payloadCap);
     const payloadArr = new Uint8Array(payloadBuf);
     const recordId = BigInt(msg.recordId);
-    const bytesWritten = ctx.native.engineDebugGetPayload(
-      ctx.runtimeState.engineId,
-      recordId,
-      payloadArr,
+    const engineId = ctx.runtimeState.engineId;
+    const bytesWritten = callNativeRc(() =>
+      ctx.native.engineDebugGetPayload(engineId, recordId, payloadArr),
     );
     const payloadByteLen = bytesWritten > 0 ? Math.min(bytesWritten, msg.payloadCap) : 0;
     ctx.postToMain(
@@ -651,7 +665,8 @@ export function handleDebugExportMessage(
   try {
     const exportBuf = new ArrayBuffer(msg.bufferCap);
     const exportArr = new Uint8Array(exportBuf);
-    const bytesWritten = ctx.native.engineDebugExport(ctx.runtimeState.engineId, exportArr);
+    const engineId = ctx.runtimeState.engineId;
+    const bytesWritten = callNativeRc(() => ctx.native.engineDebugExport(engineId, exportArr));
     const bufferByteLen = bytesWritten > 0 ? Math.min(bytesWritten, msg.bufferCap) : 0;
     ctx.postToMain(
       {
@@ -673,7 +688,8 @@ export function handleDebugResetMessage(
   if (ctx.runtimeState.engineId === null) return;
   let rc = -1;
   try {
-    rc = ctx.native.engineDebugReset(ctx.runtimeState.engineId);
+    const engineId = ctx.runtimeState.engineId;
+    rc = callNativeRc(() => ctx.native.engineDebugReset(engineId));
   } catch (err) {
     ctx.fatal("engineDebugReset", -1, `engine_debug_reset threw: ${ctx.safeDetail(err)}`);
     return;
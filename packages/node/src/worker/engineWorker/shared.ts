@@ -39,15 +39,15 @@ export type DebugQueryResultNative = Readonly<{
 export type NativeApi = Readonly<{
   engineCreate: (config?: object | null) => number;
   engineDestroy: (engineId: number) => void;
-  engineSubmitDrawlist: (engineId: number, drawlist: Uint8Array) => number;
-  engineCommitScrollback?: (engineId: number, drawlist: Uint8Array, rows: number) => number;
-  enginePresent: (engineId: number) => number;
+  engineSubmitDrawlist: (engineId: number, drawlist: Uint8Array) => void;
+  engineCommitScrollback?: (engineId: number, drawlist: Uint8Array, rows: number) => void;
+  enginePresent: (engineId: number) => void;
   enginePollEvents: (engineId: number, timeoutMs: number, out: Uint8Array) => number;
-  enginePostUserEvent: (engineId: number, tag: number, payload: Uint8Array) => number;
-  engineSetConfig: (engineId: number, cfg?: object | null) => number;
+  enginePostUserEvent: (engineId: number, tag: number, payload: Uint8Array) => void;
+  engineSetConfig: (engineId: number, cfg: object) => void;
   engineGetCaps: (engineId: number) => TerminalCapsNative;
-  engineDebugEnable: (engineId: number, config?: object | null) => number;
-  engineDebugDisable: (engineId: number) => number;
+  engineDebugEnable: (engineId: number, config?: object | null) => void;
+  engineDebugDisable: (engineId: number) => void;
   engineDebugQuery: (
     engineId: number,
     query: object | null,
@@ -56,7 +56,8 @@ export type NativeApi = Readonly<{
   engineDebugGetPayload: (engineId: number, recordId: bigint, outPayload: Uint8Array) => number;
   engineDebugGetStats: (engineId: number) => DebugStatsNative;
   engineDebugExport: (engineId: number, outBuf: Uint8Array) => number;
-  engineDebugReset: (engineId: number) => number;
+  engineDebugReset: (engineId: number) => void;
+  ziResultCodes?: () => Readonly<Record<string, number>>;
 }>;
 
 export type PendingFrameTransfer = Readonly<{
@@ -4,7 +4,7 @@
 
 import { strict as assert } from "node:assert";
 import { execFileSync } from "node:child_process";
-import { mkdirSync, mkdtempSync, rmSync, writeFileSync } from "node:fs";
+import { mkdirSync, mkdtempSync, readFileSync, rmSync, writeFileSync } from "node:fs";
 import { tmpdir } from "node:os";
 import { dirname, join } from "node:path";
 import { describe, test } from "node:test";
@@ -43,6 +43,32 @@ function writeBaseFixture(root, commit = COMMIT_A) {
   mkdirSync(join(root, "packages/native/vendor/zireael/src"), { recursive: true });
 }
 
+/** A mirror with `src/a.c` and a one-patch series editing its third line. */
+function writeSeriesFixture(root, hunkHeader) {
+  writeBaseFixture(root, COMMIT_A);
+  const buildRsPath = join(root, "packages/native/build.rs");
+  const rerunPatches = 'println!("cargo:rerun-if-changed=vendor/patches");';
+  writeUtf8(buildRsPath, `${readFileSync(buildRsPath, "utf8")}// ${rerunPatches}\n`);
+  writeUtf8(join(root, "packages/native/vendor/zireael/src/a.c"), "one\ntwo\nthree\nfour\n");
+  writeUtf8(
+    join(root, "packages/native/vendor/patches/0001-edit.patch"),
+    [
+      "Edit the third line.",
+      "",
+      "diff --git a/src/a.c b/src/a.c",
+      "--- a/src/a.c",
+      "+++ b/src/a.c",
+      hunkHeader,
+      " two",
+      "-three",
+      "+THREE",
+      " four",
+      "",
+    ].join("\n"),
+  );
+  writeUtf8(join(root, "packages/native/vendor/patches/series"), "0001-edit.patch\n");
+}
+
 describe("check-native-vendor-integrity", () => {
   test("passes when pin matches gitlink and build.rs uses native vendor path", () => {
     const root = makeFixtureRoot();
@@ -139,6 +165,80 @@ describe("check-native-vendor-integrity", () => {
     }
   });
 
+  test("fails when the patch series lists a missing patch", () => {
+    const root = makeFixtureRoot();
+    try {
+      writeBaseFixture(root, COMMIT_A);
+      const buildRsPath = join(root, "packages/native/build.rs");
+      const rerunPatches = 'println!("cargo:rerun-if-changed=vendor/patches");';
+      writeUtf8(buildRsPath, `${readFileSync(buildRsPath, "utf8")}// ${rerunPatches}\n`);
+      writeUtf8(join(root, "packages/native/vendor/patches/0001-present.patch"), "");
+      writeUtf8(
+        join(root, "packages/native/vendor/patches/series"),
+        "0001-present.patch\n0002-missing.patch\n",
+      );
+      assert.throws(
+        () =>
+          checkNativeVendorIntegrity(root, {
+            resolveGitlinkCommit: () => COMMIT_A,
+            resolveSubmoduleHead: () => null,
+          }),
+        /series lists a missing patch: 0002-missing\.patch/,
+      );
+    } finally {
+      rmSync(root, { recursive: true, force: true });
+    }
+  });
+
+  test("checks that the patch series applies to the mirror where its hunks say", () => {
+    const root = makeFixtureRoot();
+    try {
+      writeSeriesFixture(root, "@@ -2,3 +2,3 @@");
+      const result = checkNativeVendorIntegrity(root, {
+        resolveGitlinkCommit: () => COMMIT_A,
+        resolveSubmoduleHead: () => null,
+      });
+      assert.deepEqual(result.patches, ["0001-edit.patch"]);
+    } finally {
+      rmSync(root, { recursive: true, force: true });
+    }
+  });
+
+  test("fails when a patch hunk only applies at an offset", () => {
+    const root = makeFixtureRoot();
+    try {
+      writeSeriesFixture(root, "@@ -1,3 +1,3 @@");
+      assert.throws(
+        () =>
+          checkNativeVendorIntegrity(root, {
+            resolveGitlinkCommit: () => COMMIT_A,
+            resolveSubmoduleHead: () => null,
+          }),
+        /0001-edit\.patch does not apply where its hunks say:\nsrc\/a\.c: hunk at line 1/,
+      );
+    } finally {
+      rmSync(root, { recursive: true, force: true });
+    }
+  });
+
+  test("fails when a patch does not apply to the mirror", () => {
+    const root = makeFixtureRoot();
+    try {
+      writeSeriesFixture(root, "@@ -2,3 +2,3 @@");
+      writeUtf8(join(root, "packages/native/vendor/zireael/src/a.c"), "one\ntwo\nTHREE\nfour\n");
+      assert.throws(
+        () =>
+          checkNativeVendorIntegrity(root, {
+            resolveGitlinkCommit: () => COMMIT_A,
+            resolveSubmoduleHead: () => null,
+          }),
+        /0001-edit\.patch does not apply/,
+      );
+    } finally {
+      rmSync(root, { recursive: true, force: true });
+    }
+  });
+
   test("ignores uninitialized gitlink directory in superproject checkout", () => {
     const root = makeFixtureRoot();
     try {
@@ -9,10 +9,22 @@
  * - packages/native/vendor/VENDOR_COMMIT.txt is exactly one 40-hex commit
  * - the commit pin matches the repo gitlink pointer at vendor/zireael
  * - if vendor/zireael is checked out locally, its HEAD matches that pointer
+ * - local engine changes live in packages/native/vendor/patches (applied by
+ *   build.rs to a copy), every patch its series lists exists, and the series
+ *   applies to the mirror in order with every hunk where its header says
  */
 
-import { execFileSync } from "node:child_process";
-import { existsSync, readFileSync, realpathSync, statSync } from "node:fs";
+import { execFileSync, spawnSync } from "node:child_process";
+import {
+  cpSync,
+  existsSync,
+  mkdtempSync,
+  readFileSync,
+  realpathSync,
+  rmSync,
+  statSync,
+} from "node:fs";
+import { tmpdir } from "node:os";
 import { dirname, join } from "node:path";
 import { fileURLToPath } from "node:url";
 
@@ -107,6 +119,105 @@ function validateBuildRs(buildRsText) {
   }
 }
 
+function validatePatchSeries(patchesDir, buildRsText) {
+  const seriesPath = join(patchesDir, "series");
+  if (!existsSync(seriesPath)) return [];
+
+  const rerunPatchesNeedle = 'println!("cargo:rerun-if-changed=vendor/patches");';
+  if (!buildRsText.includes(rerunPatchesNeedle)) {
+    throw new Error("packages/native/build.rs must include rerun-if-changed for vendor/patches");
+  }
+
+  const names = readFileSync(seriesPath, "utf8")
+    .split(/\r?\n/)
+    .map((line) => line.trim())
+    .filter((line) => line.length > 0 && !line.startsWith("#"));
+  for (const name of names) {
+    if (!existsSync(join(patchesDir, name))) {
+      throw new Error(`packages/native/vendor/patches/series lists a missing patch: ${name}`);
+    }
+  }
+  return names;
+}
+
+/**
+ * Hunks of `patchText` whose old lines are not at the line their header
+ * names in the files under `treeDir`. `git apply` accepts such moved hunks
+ * silently; build.rs rejects them.
+ */
+function findMovedHunks(treeDir, patchText) {
+  const moved = [];
+  let path = null;
+  let fileLines = [];
+  const lines = patchText.split("\n");
+  for (let i = 0; i < lines.length; i++) {
+    const line = lines[i];
+    if (line.startsWith("diff --git ")) {
+      path = null;
+      continue;
+    }
+    if (line.startsWith("+++ ")) {
+      path = line.startsWith("+++ b/") ? line.slice(6) : null;
+      const full = path === null ? null : join(treeDir, path);
+      fileLines = full !== null && existsSync(full) ? readFileSync(full, "utf8").split("\n") : [];
+      continue;
+    }
+    const header = /^@@ -(\d+)(?:,(\d+))? \+\d+(?:,(\d+))? @@/.exec(line);
+    if (header === null || path === null) continue;
+    const oldLen = header[2] === undefined ? 1 : Number(header[2]);
+    const newLen = header[3] === undefined ? 1 : Number(header[3]);
+    const old = [];
+    let oldLeft = oldLen;
+    let newLeft = newLen;
+    while ((oldLeft > 0 || newLeft > 0) && i + 1 < lines.length) {
+      const body = lines[++i];
+      if (body.startsWith("\\")) continue;
+      const kind = body.length === 0 ? " " : body[0];
+      if (kind !== "+") {
+        old.push(body.slice(1));
+        oldLeft--;
+      }
+      if (kind !== "-") newLeft--;
+    }
+    const start = Number(header[1]) - (oldLen === 0 ? 0 : 1);
+    if (old.some((text, k) => fileLines[start + k] !== text)) {
+      moved.push(`${path}: hunk at line ${header[1]}`);
+    }
+  }
+  return moved;
+}
+
+/**
+ * Apply the series, in order, to a scratch copy of the mirror, the way
+ * build.rs does: every hunk must apply exactly where its header says.
+ */
+function applyPatchSeries(vendorRoot, patchesDir, names) {
+  if (names.length === 0) return;
+  const scratch = mkdtempSync(join(tmpdir(), "rezi-zireael-patches-"));
+  try {
+    cpSync(join(vendorRoot, "include"), join(scratch, "include"), { recursive: true });
+    cpSync(join(vendorRoot, "src"), join(scratch, "src"), { recursive: true });
+    for (const name of names) {
+      const patch = `packages/native/vendor/patches/${name}`;
+      const moved = findMovedHunks(scratch, readFileSync(join(patchesDir, name), "utf8"));
+      if (moved.length > 0) {
+        throw new Error([`${patch} does not apply where its hunks say:`, ...moved].join("\n"));
+      }
+      const result = spawnSync("git", ["apply", join(patchesDir, name)], {
+        cwd: scratch,
+        encoding: "utf8",
+      });
+      if (result.error !== undefined || result.status !== 0) {
+        const stderr = String(result.stderr ?? "").trim();
+        const detail = stderr.length > 0 ? stderr : String(result.error ?? "");
+        throw new Error(`${patch} does not apply to vendor/zireael:\n${detail}`);
+      }
+    }
+  } finally {
+    rmSync(scratch, { recursive: true, force: true });
+  }
+}
+
 export function checkNativeVendorIntegrity(rootDir, options = {}) {
   const root = rootDir ?? join(dirname(fileURLToPath(import.meta.url)), "..");
 
@@ -121,12 +232,17 @@ export function checkNativeVendorIntegrity(rootDir, options = {}) {
     throw new Error(`missing vendor commit pin file: ${vendorCommitPath}`);
   }
 
-  validateBuildRs(readFileSync(buildRsPath, "utf8"));
+  const buildRsText = readFileSync(buildRsPath, "utf8");
+  validateBuildRs(buildRsText);
+  const patches = validatePatchSeries(join(root, "packages/native/vendor/patches"), buildRsText);
 
   assertDir(nativeVendorRoot, "native vendor root");
   assertDir(join(nativeVendorRoot, "include"), "native vendor include directory");
   assertDir(join(nativeVendorRoot, "src"), "native vendor source directory");
 
+  const checkPatchSeries = options.applyPatchSeries ?? applyPatchSeries;
+  checkPatchSeries(nativeVendorRoot, join(root, "packages/native/vendor/patches"), patches);
+
   const pinnedCommit = normalizeCommitPin(readFileSync(vendorCommitPath, "utf8"));
 
   const resolveGitlinkCommit = options.resolveGitlinkCommit ?? readGitlinkCommit;
@@ -173,6 +289,7 @@ export function checkNativeVendorIntegrity(rootDir, options = {}) {
     success: true,
     pinnedCommit,
     gitlinkCommit,
+    patches,
   };
 }
 